@@ -5,6 +5,8 @@
 // - unprojections explained -- https://www.derschmale.com/2014/09/28/unprojections-explained/
 // - infinite grid vulkan -- https://asliceofrendering.com/scene%20helper/2020/01/05/InfiniteGrid/
 
+#import hex_grid::hash::{hash_cell, hash_cell_f32}
+
 struct View {
     viewport: vec4<u32>,
     projection: mat4x4<f32>,
@@ -12,48 +14,374 @@ struct View {
     view: mat4x4<f32>,
     inverse_view: mat4x4<f32>,
     cursor_pos: vec2<f32>,
+    cursor_grid: u32,   // Grid.id of the grid under the cursor
+    select_grid: u32,   // Grid.id of the grid being box selected
+    // box selection corners on the grid plane (local x, local z); xy & zw
+    select_quad: array<vec4<f32>, 2>,
+    select_color: vec4<f32>,
+    pick_pixel: vec2<f32>,  // cursor in render target pixels, for pick()
+    color_scale: f32,       // undoes the color grading exposure, for UNTONEMAP
+    // physical pixels per LineWidthMode::Pixels pixel; the target's scale
+    // factor times the UiScale
+    scale_factor: f32,
+};
+
+// HexGridConfig & grid transform
+struct Grid {
+    transform: mat4x4<f32>,         // grid plane local to world
+    inverse_transform: mat4x4<f32>,
+    line_color: vec4<f32>,
+    cursor_color: vec4<f32>,
+    cursor_edge_color: vec4<f32>,
+    parity_colors: array<vec4<f32>, 3>, // transparent when disabled
+    size: f32,
+    orientation: u32,   // 0 = pointy, 1 = flat
+    line_width: f32,
+    cursor_line_width: f32,
+    cursor_edge_distance: f32,
+    id: u32,
+    baked_origin: vec2<i32>,    // cell in texel (0, 0) of baked_highlights
+    owners_origin: vec2<i32>,   // cell in texel (0, 0) of owners
+    stencil_reference: u32,     // HexGridStencil, used with STENCIL_TEST
+    stencil_compare: u32,       // StencilCompare variant index
+    edge_width: f32,            // HexGridPartHighlights
+    vertex_radius: f32,
+    line_width_pixels: f32,     // LineWidthMode::Pixels, 0 otherwise
+    wrap: vec2<u32>,            // HexWrap width & height, 0 when not wrapping
+    superhex_color: vec4<f32>,  // SuperHexBorders; radius 0 when not drawn
+    superhex_radius: u32,
+    superhex_width: f32,
+    // HexGridNetworks strokes; only used with PART_HIGHLIGHTS
+    river_color: vec4<f32>,
+    river_border_color: vec4<f32>,
+    road_color: vec4<f32>,
+    road_border_color: vec4<f32>,
+    river_width: f32,
+    river_border_width: f32,
+    road_width: f32,
+    road_border_width: f32,
+    terrain_blend_width: f32,   // HexGridTerrain, used with TERRAIN
+    // HexGridElevation; only used with ELEVATION
+    cliff_color: vec4<f32>,
+    cliff_shadow_color: vec4<f32>,
+    cliff_width: f32,
+    cliff_shadow_width: f32,
+    cliff_threshold: f32,
+    ramp_color: vec4<f32>,
+    // HexGridDistanceRings; count 0 when not drawn
+    rings_center: vec2<i32>,
+    rings_count: u32,
+    rings_near_color: vec4<f32>,
+    rings_far_color: vec4<f32>,
+    // HexGridHeatmap; only used with HEATMAP
+    heat_colors: array<vec4<f32>, 8>,  // HexGridHeatmap::MAX_COLORS
+    heat_count: u32,
+    heat_alpha: f32,
+    heat_min: f32,              // values at the ends of the gradient
+    heat_max: f32,
+    heat_origin: vec2<i32>,     // cell in texel (0, 0) of heat_values
+    time: f32,                  // GridTime seconds; stops while paused
+    // HexGridBorders of each BorderLayer, width 0 when not drawn; only used
+    // with BORDERS
+    border_colors: array<vec4<f32>, 3>,
+    border_widths: vec4<f32>,
+    // EdgeStroke of each HexGridPartHighlights group, as (pattern, width,
+    // period, speed); only used with PART_HIGHLIGHTS
+    edge_strokes: array<vec4<f32>, 4>,  // HexGridPartHighlights::MAX_STROKES
+    edge_reach: f32,            // half the widest edge stroke
+    // HexGridFills kinds, as their color & (pattern, spacing, width, angle);
+    // only used with FILLS
+    fill_colors: array<vec4<f32>, 8>,  // HexGridFills::MAX_KINDS
+    fill_params: array<vec4<f32>, 8>,
+    // ColorblindPreset::team_fill of the first teams, packed as the fills
+    // above; only used with TEAM_FILLS
+    team_fill_colors: array<vec4<f32>, 8>,  // OKABE_ITO.len()
+    team_fill_params: array<vec4<f32>, 8>,
 };
 
+// cell shape is selected with shader defs:
+// - GRID_SQUARE: square cells
+// - GRID_SQUARE & GRID_ISOMETRIC: square cells rotated 45 degrees
+// - GRID_TRIANGLE: equilateral triangles
+// hexes otherwise
+
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
-    @location(0) near_point: vec3<f32>,
-    @location(1) far_point: vec3<f32>,
-    @location(2) cursor_hex: vec2<f32>,     // cursor hex coordinates
-    @location(3) cursor_hex_edge_dist: f32, // distance of cursor from hex edge
-    @location(4) cursor_hex_angle: f32,     // polar coord of cursor in hex
-                                            // round
+    @location(0) cursor_cell: vec2<f32>,        // cursor cell center
+    @location(1) cursor_cell_edge_dist: f32,    // distance of cursor from
+                                                // cell edge
+    @location(2) cursor_cell_side: f32,         // cell edge nearest cursor
+    @location(3) uv: vec2<f32>,                 // viewport uv, (0, 0) top
+                                                // left
 };
 
+#ifdef VIEW_PUSH_CONSTANTS
+var<push_constant> view: View;
+#else
 @group(0) @binding(0)
 var<uniform> view: View;
+#endif
+
+@group(0) @binding(1)
+var<uniform> grid: Grid;
+
+struct Decal {
+    cell: vec2<i32>,
+    uv_min: vec2<f32>,  // rect within the atlas
+    uv_max: vec2<f32>,
+    color: vec4<f32>,
+    rotation: f32,
+    scale: f32,
+};
+
+// decals sorted by (cell.y, cell.x), then `terrains` HexGridTerrain kinds;
+// `decals` always has at least one entry, use the counts
+struct Decals {
+    count: u32,
+    terrains: u32,
+    decals: array<Decal>,
+};
+
+@group(0) @binding(2)
+var<storage, read> decals: Decals;
+
+@group(0) @binding(3)
+var decal_atlas: texture_2d<f32>;
+
+@group(0) @binding(4)
+var decal_sampler: sampler;
+
+struct Highlight {
+    cell: vec2<i32>,
+    occupied: u32,      // 0 for empty slots
+    color: vec4<f32>,
+};
+
+// highlights near the view frustum, in a hash table keyed by cell; see
+// highlight_color()
+struct Highlights {
+    mask: u32,          // slot count - 1
+    slots: array<Highlight>,
+};
+
+@group(0) @binding(5)
+var<storage, read> highlights: Highlights;
+
+// highlights written by hex_grid_bake.wgsl, used instead of `highlights` with
+// BAKED_HIGHLIGHTS
+@group(0) @binding(6)
+var baked_highlights: texture_2d<f32>;
+
+// HexGridMask values written by hex_grid_mask.wgsl, only read with
+// STENCIL_TEST
+@group(0) @binding(7)
+var stencil: texture_2d<u32>;
+
+// owning team + 1 of each cell, 0 when unowned; only read with TERRITORY
+@group(0) @binding(8)
+var owners: texture_2d<u32>;
+
+// HexGridPalette
+struct Palette {
+    border_width: f32,
+    count: u32,
+    colors: array<vec4<f32>, 64>,  // HexGridPalette::MAX_TEAMS
+};
+
+@group(0) @binding(9)
+var<uniform> palette: Palette;
+
+// HexGridPartHighlights, in a table like `highlights`.  Entries are keyed by
+// the canonical cell & the low 8 bits of `occupied`; 1 + direction for edges,
+// 4 + corner for corners.  The bits above hold the stroke group + 1 of an
+// edge, or 0 without one.  HexGridNetworks are stored under key 6, with the
+// masks of the canonical edges of the cell with rivers in `color.x`, and
+// roads in `color.y`.  HexGridTerrain is stored under key 7, with the terrain
+// kind + 1 in `color.x`, its priority in `color.y`, & the kind + 1 & rotation
+// of its variant, if any, in `color.z` & `color.w`.  HexGridElevation is
+// stored under key 8, with the height in `color.x`, 1 in `color.y` when the
+// cell has a height, & the direction of its ramp + 1 in `color.z`.
+// HexGridWeather is stored under key 9, with the weather kind + 1 in
+// `color.x`.  HexGridGlow is stored under key 10, with the color in
+// `color.rgb` & the width in `color.a`.  HexGridFills is stored under key 11,
+// with the fill kind + 1 in `color.x`.  Only read with PART_HIGHLIGHTS,
+// TERRAIN, ELEVATION, WEATHER, or FILLS, & by glow_seed()
+@group(0) @binding(10)
+var<storage, read> part_highlights: Highlights;
+
+// HexGridHoles, one bit per cell over `min` to `min + size`, row by row;
+// only read with HOLES
+struct Holes {
+    min: vec2<i32>,
+    size: vec2<i32>,
+    bits: array<u32>,
+};
+
+@group(0) @binding(11)
+var<storage, read> holes: Holes;
+
+// a HexGridWeather kind
+struct WeatherKind {
+    color: vec4<f32>,
+    pattern: u32,       // WeatherPattern variant index
+    intensity: f32,
+    scale: f32,
+    speed: f32,
+};
+
+// HexGridWeather kinds; only read with WEATHER
+struct Weathers {
+    kinds: array<WeatherKind, 8>,  // HexGridWeather::MAX_KINDS
+};
+
+@group(0) @binding(12)
+var<uniform> weathers: Weathers;
+
+// HexGridHeatmap values around grid.heat_origin, as (value, 1); (0, 0) for
+// cells without one.  Only read with HEATMAP
+@group(0) @binding(13)
+var heat_values: texture_2d<f32>;
+
+// convert a point on the grid plane (local x, local z) into the unit
+// layout used by cell_coords(); mirrors HexGridConfig::world_to_grid()
+fn grid_uv(pos: vec2<f32>) -> vec2<f32> {
+    var uv = pos / grid.size;
+    if grid.orientation == 1u {
+        uv = uv.yx;
+    }
+#ifdef GRID_ISOMETRIC
+    uv = vec2(uv.x + uv.y, uv.y - uv.x) / sqrt(2.0);
+#endif
+    return uv;
+}
+
+// inverse of grid_uv()
+fn grid_pos(uv: vec2<f32>) -> vec2<f32> {
+    var pos = uv;
+#ifdef GRID_ISOMETRIC
+    pos = vec2(pos.x - pos.y, pos.x + pos.y) / sqrt(2.0);
+#endif
+    if grid.orientation == 1u {
+        pos = pos.yx;
+    }
+    return pos * grid.size;
+}
 
 fn unproject_point(pos: vec3<f32>) -> vec3<f32> {
     let point = view.view * view.inverse_projection * vec4(pos, 1.0);
     return point.xyz / point.w;
 }
 
+// convert a fragment position (in render target pixels) into normalized
+// device coordinates relative to the camera viewport.  Cameras with a
+// sub-viewport (split-screen, editor panes) only cover part of the render
+// target, so the fullscreen quad coordinates can't be used directly.
+fn frag_coord_to_ndc(frag_coord: vec2<f32>) -> vec2<f32> {
+    let uv = (frag_coord - vec2<f32>(view.viewport.xy)) / vec2<f32>(view.viewport.zw);
+    return vec2(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0);
+}
+
+struct Ray {
+    origin: vec3<f32>,
+    dir: vec3<f32>,
+};
+
+// build the world-space ray through a point in normalized device
+// coordinates.  This must be done per-pixel; for perspective projections the
+// ray direction changes across the screen.
+//
+// bevy uses reverse-z, so z = 1.0 is the near plane.  For perspective
+// projections the far plane is at infinity (z = 0.0), so we pick a point
+// part way into the scene to get the direction.
+fn view_ray(ndc: vec2<f32>) -> Ray {
+    let near = unproject_point(vec3(ndc, 1.0));
+    let far = unproject_point(vec3(ndc, 0.5));
+    var ray: Ray;
+    ray.origin = near;
+    ray.dir = normalize(far - near);
+    return ray;
+}
+
 @vertex
 fn vertex(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
-    var grid_plane = array<vec3<f32>, 4>(
-        vec3<f32>(-1., -1., 1.),
-        vec3<f32>(-1., 1., 1.),
-        vec3<f32>(1., -1., 1.),
-        vec3<f32>(1., 1., 1.)
-    );
+    // single triangle covering the viewport, as in bevy's fullscreen vertex
+    // shader; uv runs 0..2 along each edge, so the viewport is 0..1
+    let uv = vec2<f32>(f32(in_vertex_index >> 1u), f32(in_vertex_index & 1u)) * 2.0;
 
     var out: VertexOutput;
-    let pos = grid_plane[in_vertex_index];
-    out.clip_position = vec4(pos, 1.0);
-    out.near_point = unproject_point(pos);
-    out.far_point = unproject_point(vec3<f32>(pos.xy, 0.00001));
-
-    let cursor = hex_coords(view.cursor_pos);
-    out.cursor_hex = cursor.coords;
-    out.cursor_hex_edge_dist = cursor.edge_dist;
-    out.cursor_hex_angle = cursor.angle - cursor.angle % (radians(360.0 / 6.0));
+    out.clip_position = vec4(uv * vec2(2.0, -2.0) + vec2(-1.0, 1.0), 1.0, 1.0);
+    out.uv = uv;
+
+    let cursor = cell_coords(grid_uv(view.cursor_pos));
+    out.cursor_cell = cursor.coords;
+    out.cursor_cell_edge_dist = cursor.edge_dist;
+    out.cursor_cell_side = cursor.side;
+    return out;
+}
+
+struct CellCoords {
+    coords: vec2<f32>,  // cell center
+    cell: vec2<i32>,    // cell coordinates, as Axial
+    edge_dist: f32,     // distance to the nearest edge
+    side: f32,          // index of the nearest edge
+    parity: u32,        // parity class; neighbors never match
+};
+
+#ifdef GRID_TRIANGLE
+fn cell_coords(uv: vec2<f32>) -> CellCoords {
+    // skew into the triangle lattice; each parallelogram holds a lower &
+    // upper triangle
+    let lattice = vec2(uv.x - uv.y / sqrt(3.0), uv.y * 2.0 / sqrt(3.0));
+    let base = floor(lattice);
+    let f = lattice - base;
+
+    // barycentric coordinates within the triangle
+    let upper = f.x + f.y > 1.0;
+    var bary: vec3<f32>;
+    var center: vec2<f32>;
+    if upper {
+        bary = vec3(1.0 - f.x, 1.0 - f.y, f.x + f.y - 1.0);
+        center = base + 2.0 / 3.0;
+    } else {
+        bary = vec3(f.x, f.y, 1.0 - f.x - f.y);
+        center = base + 1.0 / 3.0;
+    }
+
+    var out: CellCoords;
+    out.coords = vec2(center.x + center.y * 0.5, center.y * sqrt(3.0) * 0.5);
+    // barycentric coordinates scaled by the triangle height
+    out.edge_dist = min(bary.x, min(bary.y, bary.z)) * sqrt(3.0) * 0.5;
+    if bary.x <= bary.y && bary.x <= bary.z {
+        out.side = 0.0;
+    } else if bary.y <= bary.z {
+        out.side = 1.0;
+    } else {
+        out.side = 2.0;
+    }
+    out.cell = vec2(i32(base.x) * 2 + select(0, 1, upper), i32(base.y));
+    out.parity = select(0u, 1u, upper);
     return out;
 }
+#else
+#ifdef GRID_SQUARE
+fn cell_coords(uv: vec2<f32>) -> CellCoords {
+    // cells are centered on integer coordinates
+    let center = round(uv);
+    let p = uv - center;
 
+    var out: CellCoords;
+    out.coords = center;
+    out.edge_dist = 0.5 - max(abs(p.x), abs(p.y));
+    if abs(p.x) > abs(p.y) {
+        out.side = select(0.0, 2.0, p.x < 0.0);
+    } else {
+        out.side = select(1.0, 3.0, p.y < 0.0);
+    }
+    out.cell = vec2<i32>(center);
+    out.parity = u32(((out.cell.x + out.cell.y) % 2 + 2) % 2);
+    return out;
+}
+#else
 fn mod_euclid(p: vec2<f32>, m: vec2<f32>) -> vec2<f32> {
     var r = p % m;
     if r.x < 0.0 {
@@ -65,19 +393,13 @@ fn mod_euclid(p: vec2<f32>, m: vec2<f32>) -> vec2<f32> {
     return r;
 }
 
-struct HexCoords {
-    coords: vec2<f32>,
-    edge_dist: f32,
-    angle: f32,
-};
-
 fn hex_dist(pos: vec2<f32>) -> f32 {
     let p = abs(pos);
     let c = dot(p, normalize(vec2(1.0, sqrt(3.0))));
     return max(c, p.x);
 }
 
-fn hex_coords(uv: vec2<f32>) -> HexCoords {
+fn cell_coords(uv: vec2<f32>) -> CellCoords {
     let dist = vec2<f32>(1.0, sqrt(3.0));
     let half_dist = dist * 0.5;
 
@@ -85,7 +407,7 @@ fn hex_coords(uv: vec2<f32>) -> HexCoords {
     let a = mod_euclid(uv, dist) - half_dist;
     let b = mod_euclid(uv - half_dist, dist) - half_dist;
 
-    var out: HexCoords;
+    var out: CellCoords;
     var center: vec2<f32>;
     if length(a) < length(b) {
         center = a;
@@ -95,52 +417,1078 @@ fn hex_coords(uv: vec2<f32>) -> HexCoords {
 
     out.coords = uv - center;
     out.edge_dist = 0.5 - hex_dist(center);
-    out.angle = atan2(center.x, center.y) + radians(180.0);
+    // polar coord of the point in the hex, rounded to one of six sides
+    let angle = atan2(center.x, center.y) + radians(180.0);
+    out.side = floor(angle / radians(360.0 / 6.0));
+
+    // axial coordinates of the cell; (q - r) mod 3 three-colors the grid
+    let r = i32(round(out.coords.y * 2.0 / sqrt(3.0)));
+    let q = i32(round(out.coords.x - f32(r) * 0.5));
+    out.cell = vec2(q, r);
+    out.parity = u32(((q - r) % 3 + 3) % 3);
     return out;
 }
+#endif
+#endif
 
 struct FragmentOutput {
     @location(0) color: vec4<f32>,
     @builtin(frag_depth) depth: f32,
 };
 
+struct PlaneHit {
+    pos: vec3<f32>,     // point on the grid plane, grid local space
+    valid: bool,
+};
+
+// intersect the view ray through a fragment with the grid plane (y = 0 in
+// the grid frame).  Rays that are parallel to, or point away from the plane
+// are not valid.
+fn plane_hit(frag_coord: vec2<f32>) -> PlaneHit {
+    let world_ray = view_ray(frag_coord_to_ndc(frag_coord));
+    let origin = (grid.inverse_transform * vec4(world_ray.origin, 1.0)).xyz;
+    let dir = (grid.inverse_transform * vec4(world_ray.dir, 0.0)).xyz;
+    let parallel = abs(dir.y) < 1e-6;
+    let t = -origin.y / select(dir.y, 1.0, parallel);
+
+    var hit: PlaneHit;
+    hit.pos = origin + t * dir;
+    hit.valid = !parallel && t > 0.0;
+    return hit;
+}
+
+// depth of a point on the grid plane, from its position in world space
+fn plane_depth(pos: vec3<f32>) -> f32 {
+    let world_intersect = grid.transform * vec4(pos, 1.0);
+    let clipped = view.projection * view.inverse_view * world_intersect;
+    return clipped.z / clipped.w;
+}
+
+// how much of the grid line covers a point `dist` from the cell edge, for a
+// line of `width`.  The mode is selected with shader defs:
+// - AA_OFF: hard edges
+// - AA_FWIDTH: solid line with a one pixel antialiased edge
+// - AA_FWIDTH & AA_SUPERSAMPLE: as above, averaged over two samples per
+//   pixel; see fragment()
+// otherwise the line fades out towards `width`
+fn line_coverage(dist: f32, width: f32) -> f32 {
+#ifdef AA_OFF
+    return select(0.0, 1.0, dist < width * 0.5);
+#else
+#ifdef AA_FWIDTH
+    let aa = fwidth(dist) * 0.5;
+    return 1.0 - smoothstep(width * 0.5 - aa, width * 0.5 + aa, dist);
+#else
+    // lazy anti-alias using smoothstep
+    return 1.0 - smoothstep(0.0, width, dist);
+#endif
+#endif
+}
+
+// floor division for a positive divisor
+fn div_floor(a: i32, b: i32) -> i32 {
+    let q = a / b;
+    return select(q, q - 1, a % b < 0);
+}
+
+// canonical coordinate of a cell on a wrapped map, so every copy of a cell
+// looks up the same highlights, decals, & owner; mirrors HexWrap::wrap()
+fn wrap_cell(cell: vec2<i32>) -> vec2<i32> {
+    var out = cell;
+    let h = i32(grid.wrap.y);
+    if h > 0 {
+        let copies = div_floor(out.y, h);
+        out = vec2(out.x + copies * h / 2, out.y - copies * h);
+    }
+    let w = i32(grid.wrap.x);
+    if w > 0 {
+        let col = out.x + div_floor(out.y, 2);
+        out.x -= div_floor(col, w) * w;
+    }
+    return out;
+}
+
+// true if the cell at a point on the grid plane is cut out of the grid
+fn is_hole(pos: vec2<f32>) -> bool {
+#ifdef HOLES
+    let p = wrap_cell(cell_coords(grid_uv(pos)).cell) - holes.min;
+    if any(p < vec2(0)) || any(p >= holes.size) {
+        return false;
+    }
+    let i = u32(p.y * holes.size.x + p.x);
+    return (holes.bits[i / 32u] & (1u << (i % 32u))) != 0u;
+#else
+    return false;
+#endif
+}
+
+// must match cell_hash() in render.rs
+fn cell_hash(cell: vec2<i32>) -> u32 {
+    return hash_cell(cell, 0u);
+}
+
+// highlight color of a cell, transparent if it isn't highlighted.  The table
+// uses linear probing and is never more than half full, so the probe ends at
+// the cell or an empty slot after a few steps.
+fn highlight_color(raw: vec2<i32>) -> vec4<f32> {
+    let cell = wrap_cell(raw);
+#ifdef BAKED_HIGHLIGHTS
+    let texel = cell - grid.baked_origin;
+    let size = vec2<i32>(textureDimensions(baked_highlights));
+    if any(texel < vec2(0)) || any(texel >= size) {
+        return vec4(0.0);
+    }
+    return textureLoad(baked_highlights, texel, 0);
+#else
+    var slot = cell_hash(cell) & highlights.mask;
+    for (var i = 0u; i <= highlights.mask; i++) {
+        let highlight = highlights.slots[slot];
+        if highlight.occupied == 0u {
+            break;
+        }
+        if highlight.cell.x == cell.x && highlight.cell.y == cell.y {
+            return highlight.color;
+        }
+        slot = (slot + 1u) & highlights.mask;
+    }
+    return vec4(0.0);
+#endif
+}
+
+// entry of an edge or corner in `part_highlights`, with `occupied` 0 if it
+// isn't highlighted
+fn find_part(raw: vec2<i32>, key: u32) -> Highlight {
+    let cell = wrap_cell(raw);
+    var slot = cell_hash(cell) & part_highlights.mask;
+    for (var i = 0u; i <= part_highlights.mask; i++) {
+        let entry = part_highlights.slots[slot];
+        if entry.occupied == 0u {
+            break;
+        }
+        if (entry.occupied & 0xffu) == key && entry.cell.x == cell.x && entry.cell.y == cell.y {
+            return entry;
+        }
+        slot = (slot + 1u) & part_highlights.mask;
+    }
+    return Highlight(vec2(0), 0u, vec4(0.0));
+}
+
+// color of an edge or corner in `part_highlights`, transparent if it isn't
+// highlighted
+fn part_color(raw: vec2<i32>, key: u32) -> vec4<f32> {
+    return find_part(raw, key).color;
+}
+
+// smooth noise from 0 to 1, with features about 1 apart
+fn value_noise(p: vec2<f32>) -> f32 {
+    let i = vec2<i32>(floor(p));
+    let f = fract(p);
+    let t = f * f * (3.0 - 2.0 * f);
+    let a = hash_cell_f32(i, 0u);
+    let b = hash_cell_f32(i + vec2(1, 0), 0u);
+    let c = hash_cell_f32(i + vec2(0, 1), 0u);
+    let d = hash_cell_f32(i + vec2(1, 1), 0u);
+    return mix(mix(a, b, t.x), mix(c, d, t.x), t.y);
+}
+
+// Weather over the cell at a point on the grid plane, transparent if it has
+// none.  Patterns are in grid units, & move with grid.time.
+fn weather_color(pos: vec2<f32>, cell: CellCoords) -> vec4<f32> {
+    let id = u32(part_color(cell.cell, 9u).x);
+    if id == 0u {
+        return vec4(0.0);
+    }
+    let kind = weathers.kinds[id - 1u];
+    let p = pos / (grid.size * max(kind.scale, 0.001));
+    let t = grid.time * kind.speed;
+    var amount = 0.0;
+    switch kind.pattern {
+        // clouds: a few octaves of noise drifting across the grid
+        case 0u: {
+            var q = p + t * vec2(0.15, 0.08);
+            var n = 0.0;
+            var weight = 0.5;
+            for (var i = 0; i < 4; i++) {
+                n += value_noise(q) * weight;
+                q = q * 2.03 + vec2(17.1, 5.3);
+                weight *= 0.5;
+            }
+            amount = smoothstep(0.35, 0.75, n / 0.9375);
+        }
+        // scanlines: thin bands, & a brighter bar rolling over them
+        case 1u: {
+            let lines = pow(0.5 + 0.5 * sin(6.2831853 * (p.y * 4.0 - t)), 4.0);
+            let bar = smoothstep(0.85, 1.0, fract(p.y * 0.25 - t * 0.2));
+            amount = lines * 0.6 + bar * 0.4;
+        }
+        // static: specks redrawn 30 times a second
+        default: {
+            let speck = hash_cell_f32(vec2<i32>(floor(p * 20.0)), u32(floor(t * 30.0)));
+            amount = select(0.0, speck, speck > 0.5);
+        }
+    }
+    return vec4(kind.color.rgb, kind.color.a * clamp(amount * kind.intensity, 0.0, 1.0));
+}
+
+// Coverage of an EdgeStroke, as (pattern, width, period, speed), at a point
+// `across` from its edge & `along` it from the middle of the edge, smoothed
+// over `pixel` grid units.  Mirrors EdgeStroke::covers()
+fn stroke_coverage(stroke: vec4<f32>, across: f32, along: f32, pixel: f32) -> f32 {
+    let half = stroke.y * 0.5;
+    let period = max(stroke.z, 0.001);
+    let phase = along / period - grid.time * stroke.w;
+    let aa = pixel * 0.5;
+    let band = 1.0 - smoothstep(half - aa, half + aa, across);
+    switch u32(stroke.x) {
+        // dash over the first half of each period
+        case 1u: {
+            let offset = (fract(phase + 0.25) - 0.5) * period;
+            return band * (1.0 - smoothstep(-aa, aa, abs(offset) - period * 0.25));
+        }
+        // dot at the start of each period
+        case 2u: {
+            let offset = (fract(phase + 0.5) - 0.5) * period;
+            return 1.0 - smoothstep(half - aa, half + aa, length(vec2(offset, across)));
+        }
+        // outer thirds of the band
+        case 3u: {
+            return band * smoothstep(half / 3.0 - aa, half / 3.0 + aa, across);
+        }
+        default: {
+            return band;
+        }
+    }
+}
+
+// Coverage of a CellFill, as (pattern, spacing, width, angle), at a point on
+// the grid plane in cell sizes, smoothed over `pixel` cell sizes.  Mirrors
+// CellFill::covers()
+fn fill_coverage(fill: vec4<f32>, p: vec2<f32>, pixel: f32) -> f32 {
+    let spacing = max(fill.y, 0.001);
+    let half = fill.z * spacing * 0.5;
+    let aa = pixel * 0.5;
+    let dir = vec2(cos(fill.w), sin(fill.w));
+    // distance to the nearest line across & along `dir`
+    let across = (fract(dot(p, dir) / spacing + 0.5) - 0.5) * spacing;
+    let along = (fract(dot(p, vec2(-dir.y, dir.x)) / spacing + 0.5) - 0.5) * spacing;
+    let lines = 1.0 - smoothstep(half - aa, half + aa, abs(across));
+    switch u32(fill.x) {
+        // crosshatch
+        case 2u: {
+            let crossing = 1.0 - smoothstep(half - aa, half + aa, abs(along));
+            return max(lines, crossing);
+        }
+        // dots
+        case 3u: {
+            return 1.0 - smoothstep(half - aa, half + aa, length(vec2(across, along)));
+        }
+        // hatch & stripes
+        default: {
+            return lines;
+        }
+    }
+}
+
+// HexGridFills pattern over the cell at a point on the grid plane,
+// transparent if it has none
+fn fill_color(pos: vec2<f32>, cell: CellCoords, pixel: f32) -> vec4<f32> {
+    let id = u32(part_color(cell.cell, 11u).x);
+    if id == 0u {
+        return vec4(0.0);
+    }
+    let color = grid.fill_colors[id - 1u];
+    let coverage = fill_coverage(grid.fill_params[id - 1u], pos / grid.size, pixel);
+    return vec4(color.rgb, color.a * coverage);
+}
+
+// color of the highlighted corner or edge under a point on a hex grid,
+// transparent if there's none; edges are stroked with their group's
+// EdgeStroke, anti-aliased over `pixel` grid units.  Corners & edges are
+// canonicalized the same way as HexVertex::new() & HexEdge::new()
+fn part_highlight_color(uv: vec2<f32>, cell: CellCoords, pixel: f32) -> vec4<f32> {
+    var neighbors = array<vec2<i32>, 6>(
+        vec2(1, 0),
+        vec2(0, 1),
+        vec2(-1, 1),
+        vec2(-1, 0),
+        vec2(0, -1),
+        vec2(1, -1)
+    );
+    let p = uv - cell.coords;
+    let sector = radians(60.0);
+    let angle = atan2(p.y, p.x);
+
+    // corners are drawn over the edges meeting at them
+    let k = (i32(floor(angle / sector)) + 6) % 6;
+    let corner_angle = f32(k) * sector + sector * 0.5;
+    let corner_pos = vec2(cos(corner_angle), sin(corner_angle)) / sqrt(3.0);
+    if distance(p, corner_pos) < grid.vertex_radius {
+        var vertex = cell.cell;
+        var corner = 0u;
+        switch k {
+            case 0: { corner = 0u; }
+            case 1: { corner = 1u; }
+            case 2: { vertex += neighbors[3]; corner = 0u; }
+            case 3: { vertex += neighbors[4]; corner = 1u; }
+            case 4: { vertex += neighbors[4]; corner = 0u; }
+            default: { vertex += neighbors[5]; corner = 1u; }
+        }
+        let color = part_color(vertex, 4u + corner);
+        if color.a > 0.0 {
+            return color;
+        }
+    }
+
+    if cell.edge_dist < grid.edge_reach + pixel {
+        // the canonical edge is on the owner's side 0 to 2, so strokes
+        // line up on both sides of it
+        let dir = (i32(round(angle / sector)) + 6) % 6;
+        var owner = cell.cell;
+        var side = dir;
+        var q = p;
+        if dir >= 3 {
+            owner += neighbors[dir];
+            side = dir - 3;
+            q = p - vec2(cos(f32(dir) * sector), sin(f32(dir) * sector));
+        }
+        let part = find_part(owner, 1u + u32(side));
+        let group = part.occupied >> 8u;
+        var stroke = vec4(0.0, grid.edge_width, 1.0, 0.0);
+        if group > 0u {
+            stroke = grid.edge_strokes[group - 1u];
+        }
+        let side_angle = f32(side) * sector;
+        let along = dot(q, vec2(-sin(side_angle), cos(side_angle)));
+        let coverage = stroke_coverage(stroke, cell.edge_dist, along, pixel);
+        return vec4(part.color.rgb, part.color.a * coverage);
+    }
+    return vec4(0.0);
+}
+
+// superhex of radius `n` containing a cell; mirrors Axial::to_superhex()
+fn superhex(cell: vec2<i32>, n: i32) -> vec2<i32> {
+    let nf = f32(n);
+    let det = 3.0 * nf * nf + 3.0 * nf + 1.0;
+    let c = vec2<f32>(cell);
+    let a = ((2.0 * nf + 1.0) * c.x + nf * c.y) / det;
+    let b = ((nf + 1.0) * c.y - nf * c.x) / det;
+
+    // cube rounding
+    var rq = round(a);
+    var rr = round(b);
+    let rs = round(-a - b);
+    let dq = abs(rq - a);
+    let dr = abs(rr - b);
+    let ds = abs(rs + a + b);
+    if dq > dr && dq > ds {
+        rq = -rr - rs;
+    } else if dr > ds {
+        rr = -rq - rs;
+    }
+    let guess = vec2(i32(rq), i32(rr));
+
+    // the guess may be off by one near superhex corners
+    var candidates = array<vec2<i32>, 7>(
+        vec2(0, 0), vec2(1, 0), vec2(0, 1), vec2(-1, 1), vec2(-1, 0), vec2(0, -1), vec2(1, -1)
+    );
+    for (var i = 0; i < 7; i++) {
+        let s = guess + candidates[i];
+        let center = vec2(n + 1, n) * s.x + vec2(-n, 2 * n + 1) * s.y;
+        let d = cell - center;
+        if max(abs(d.x), max(abs(d.y), abs(d.x + d.y))) <= n {
+            return s;
+        }
+    }
+    return guess;
+}
+
+// coverage of the superhex borders at a point within a hex cell; the two
+// edges nearest the point are checked, so borders meet cleanly at corners
+fn superhex_coverage(uv: vec2<f32>, cell: CellCoords) -> f32 {
+    var neighbors = array<vec2<i32>, 6>(
+        vec2(1, 0),
+        vec2(0, 1),
+        vec2(-1, 1),
+        vec2(-1, 0),
+        vec2(0, -1),
+        vec2(1, -1)
+    );
+    let n = i32(grid.superhex_radius);
+    if n == 0 {
+        return 0.0;
+    }
+    let own = superhex(cell.cell, n);
+    let p = uv - cell.coords;
+    let sector = radians(60.0);
+    let first = i32(floor(atan2(p.y, p.x) / sector));
+    var coverage = 0.0;
+    for (var i = 0; i < 2; i++) {
+        let dir = (first + i + 6) % 6;
+        let angle = f32(dir) * sector;
+        let dist = 0.5 - dot(p, vec2(cos(angle), sin(angle)));
+        // line_coverage() may take derivatives, so it's called for both
+        // edges
+        let line = line_coverage(dist, grid.superhex_width);
+        let border = any(superhex(cell.cell + neighbors[dir], n) != own);
+        coverage = max(coverage, select(0.0, line, border));
+    }
+    return coverage;
+}
+
+fn neighbor_offset(dir: i32) -> vec2<i32> {
+    switch dir {
+        case 0: { return vec2(1, 0); }
+        case 1: { return vec2(0, 1); }
+        case 2: { return vec2(-1, 1); }
+        case 3: { return vec2(-1, 0); }
+        case 4: { return vec2(0, -1); }
+        default: { return vec2(1, -1); }
+    }
+}
+
+// true if the edge of a cell in direction `dir` is in a network; `which` is
+// 0 for rivers, 1 for roads
+fn network_edge(cell: vec2<i32>, dir: i32, which: u32) -> bool {
+    var owner = cell;
+    var bit = dir;
+    if dir >= 3 {
+        owner = cell + neighbor_offset(dir);
+        bit = dir - 3;
+    }
+    let masks = vec2<u32>(part_color(owner, 6u).xy);
+    return ((masks[which] >> u32(bit)) & 1u) != 0u;
+}
+
+fn segment_distance(p: vec2<f32>, a: vec2<f32>, b: vec2<f32>) -> f32 {
+    let ab = b - a;
+    let t = clamp(dot(p - a, ab) / dot(ab, ab), 0.0, 1.0);
+    return distance(p, a + ab * t);
+}
+
+// draw a stroke with a border over `color`, `dist` from its center line
+fn stroke(
+    color: vec4<f32>,
+    dist: f32,
+    width: f32,
+    stroke_color: vec4<f32>,
+    border_width: f32,
+    border_color: vec4<f32>,
+) -> vec4<f32> {
+    let border = line_coverage(dist, width + border_width * 2.0);
+    let inner = line_coverage(dist, width);
+    var out = mix(color, vec4(border_color.rgb, 1.0), border * border_color.a);
+    return mix(out, vec4(stroke_color.rgb, 1.0), inner * stroke_color.a);
+}
+
+// rivers along the edges & roads between the centers of a hex cell, over
+// `color`
+fn network_color(uv: vec2<f32>, cell: CellCoords, color: vec4<f32>) -> vec4<f32> {
+    let p = uv - cell.coords;
+    let sector = radians(60.0);
+
+    // rivers: only the three edges meeting at the nearest corner can reach
+    // the point; two of the cell's own, & the one between its neighbors
+    let k = (i32(floor(atan2(p.y, p.x) / sector)) + 6) % 6;
+    let corner_angle = f32(k) * sector + sector * 0.5;
+    let corner = vec2(cos(corner_angle), sin(corner_angle)) / sqrt(3.0);
+    var river = 1e9;
+    for (var i = 0; i < 2; i++) {
+        let dir = (k + i) % 6;
+        if network_edge(cell.cell, dir, 0u) {
+            let angle = f32(dir) * sector;
+            let a = vec2(cos(angle - sector * 0.5), sin(angle - sector * 0.5)) / sqrt(3.0);
+            let b = vec2(cos(angle + sector * 0.5), sin(angle + sector * 0.5)) / sqrt(3.0);
+            river = min(river, segment_distance(p, a, b));
+        }
+    }
+    if network_edge(cell.cell + neighbor_offset(k), (k + 2) % 6, 0u) {
+        river = min(river, segment_distance(p, corner, corner * 2.0));
+    }
+
+    // roads: from the center towards each connected neighbor, meeting in
+    // the middle of the edge
+    var road = 1e9;
+    for (var dir = 0; dir < 6; dir++) {
+        if network_edge(cell.cell, dir, 1u) {
+            let angle = f32(dir) * sector;
+            road = min(road, segment_distance(p, vec2(0.0), vec2(cos(angle), sin(angle))));
+        }
+    }
+
+    // strokes are drawn outside of the loops; line_coverage() may take
+    // derivatives
+    var out = stroke(
+        color, river,
+        grid.river_width, grid.river_color,
+        grid.river_border_width, grid.river_border_color,
+    );
+    return stroke(
+        out, road,
+        grid.road_width, grid.road_color,
+        grid.road_border_width, grid.road_border_color,
+    );
+}
+
+// index of the decal on a cell, or -1
+fn find_decal(raw: vec2<i32>) -> i32 {
+    let cell = wrap_cell(raw);
+    var lo = 0u;
+    var hi = decals.count;
+    while lo < hi {
+        let mid = (lo + hi) / 2u;
+        let c = decals.decals[mid].cell;
+        if c.y == cell.y && c.x == cell.x {
+            return i32(mid);
+        }
+        if c.y < cell.y || (c.y == cell.y && c.x < cell.x) {
+            lo = mid + 1u;
+        } else {
+            hi = mid;
+        }
+    }
+    return -1;
+}
+
+// decal color at a point on the grid plane, transparent if the cell has no
+// decal
+fn decal_color(pos: vec2<f32>, cell: CellCoords) -> vec4<f32> {
+    let index = find_decal(cell.cell);
+    if index < 0 {
+        return vec4(0.0);
+    }
+    let decal = decals.decals[index];
+
+    // position within the decal, with the cell center at 0.5
+    let offset = (pos - grid_pos(cell.coords)) / grid.size;
+    let c = cos(-decal.rotation);
+    let s = sin(-decal.rotation);
+    let local = vec2(c * offset.x - s * offset.y, s * offset.x + c * offset.y)
+        / decal.scale + 0.5;
+    if any(local < vec2(0.0)) || any(local > vec2(1.0)) {
+        return vec4(0.0);
+    }
+
+    // explicit level; this is non-uniform control flow
+    let uv = mix(decal.uv_min, decal.uv_max, local);
+    return textureSampleLevel(decal_atlas, decal_sampler, uv, 0.0) * decal.color;
+}
+
+// terrain texture of kind `id` - 1 at a point on the grid plane, tiled
+// across the plane; transparent for 0
+fn terrain_texture(pos: vec2<f32>, id: u32) -> vec4<f32> {
+    if id == 0u || id > decals.terrains {
+        return vec4(0.0);
+    }
+    let terrain = decals.decals[decals.count + id - 1u];
+    let local = fract(pos / (grid.size * terrain.scale));
+    // explicit level; this is non-uniform control flow
+    let uv = mix(terrain.uv_min, terrain.uv_max, local);
+    return textureSampleLevel(decal_atlas, decal_sampler, uv, 0.0) * terrain.color;
+}
+
+// texture of terrain kind `id` - 1 stretched over a single cell from corner
+// to corner, turned `rotation` sixths of a turn; transparent for 0.  Mirrors
+// TileVariant
+fn terrain_variant(pos: vec2<f32>, cell: CellCoords, id: u32, rotation: f32) -> vec4<f32> {
+    if id == 0u || id > decals.terrains {
+        return vec4(0.0);
+    }
+    let terrain = decals.decals[decals.count + id - 1u];
+
+    // position within the texture, with the cell center at 0.5 & its
+    // corners on the edges
+    let offset = (pos - grid_pos(cell.coords)) / grid.size;
+    let angle = -rotation * radians(60.0);
+    let c = cos(angle);
+    let s = sin(angle);
+    let local = vec2(c * offset.x - s * offset.y, s * offset.x + c * offset.y)
+        * (sqrt(3.0) / 2.0) + 0.5;
+
+    // explicit level; this is non-uniform control flow
+    let uv = mix(terrain.uv_min, terrain.uv_max, clamp(local, vec2(0.0), vec2(1.0)));
+    return textureSampleLevel(decal_atlas, decal_sampler, uv, 0.0) * terrain.color;
+}
+
+// terrain fill of a cell; near an edge, the terrain across it spills over if
+// its priority is higher, or meets this one halfway if it's the same.
+// Mirrors HexGridTerrain::blend_weight()
+fn terrain_color(pos: vec2<f32>, uv: vec2<f32>, cell: CellCoords) -> vec4<f32> {
+    let terrain = part_color(cell.cell, 7u);
+    let own = terrain.xy;
+    var color = terrain_texture(pos, u32(own.x));
+    if terrain.z > 0.0 {
+        color = terrain_variant(pos, cell, u32(terrain.z), terrain.w);
+    }
+    if own.x == 0.0 || cell.edge_dist >= grid.terrain_blend_width {
+        return color;
+    }
+    // step across the nearest edge, as in territory_color()
+    let offset = normalize(uv - cell.coords) * (cell.edge_dist * 2.0 + 0.01);
+    let other = part_color(cell_coords(uv + offset).cell, 7u).xy;
+    if other.x == 0.0 || other.y < own.y {
+        return color;
+    }
+    var t = 1.0 - smoothstep(0.0, grid.terrain_blend_width, cell.edge_dist);
+    if other.y == own.y {
+        t *= 0.5;
+    }
+    return mix(color, terrain_texture(pos, u32(other.x)), t);
+}
+
+// true if the ramp stored as `ramp` (direction + 1, or 0) on a cell leads
+// to its neighbor `delta` away
+fn ramp_toward(ramp: f32, delta: vec2<i32>) -> bool {
+    return ramp > 0.0 && all(neighbor_offset(i32(ramp) - 1) == delta);
+}
+
+// Height of the cliff from a cell down across its nearest edge, negative when
+// the neighbor is higher; 0 without a cliff, or across a ramp.  Mirrors
+// HexGridElevation::cliff()
+fn cliff_drop(uv: vec2<f32>, cell: CellCoords) -> f32 {
+    if cell.edge_dist >= max(grid.cliff_width, grid.cliff_shadow_width) {
+        return 0.0;
+    }
+    let own = part_color(cell.cell, 8u).xyz;
+    // step across the nearest edge, as in territory_color()
+    let offset = normalize(uv - cell.coords) * (cell.edge_dist * 2.0 + 0.01);
+    let across = cell_coords(uv + offset).cell;
+    let other = part_color(across, 8u).xyz;
+    let drop = own.x - other.x;
+    if own.y == 0.0 || other.y == 0.0 || abs(drop) < grid.cliff_threshold {
+        return 0.0;
+    }
+    let delta = across - cell.cell;
+    if ramp_toward(own.z, delta) || ramp_toward(other.z, -delta) {
+        return 0.0;
+    }
+    return drop;
+}
+
+// distance to the arrow drawn up the ramp on a hex cell, if it has one
+fn ramp_distance(uv: vec2<f32>, cell: CellCoords) -> f32 {
+    let ramp = part_color(cell.cell, 8u).z;
+    if ramp == 0.0 {
+        return 1e9;
+    }
+    let angle = (ramp - 1.0) * radians(60.0);
+    let dir = vec2(cos(angle), sin(angle));
+    let p = uv - cell.coords;
+    // in the frame of the ramp: x up the ramp, y across it
+    let q = vec2(dot(p, dir), abs(dot(p, vec2(-dir.y, dir.x))));
+    // two chevrons pointing up the ramp
+    var dist = 1e9;
+    for (var i = 0; i < 2; i++) {
+        let tip = vec2(0.3 - f32(i) * 0.2, 0.0);
+        dist = min(dist, segment_distance(q, tip, tip + vec2(-0.15, 0.2)));
+    }
+    return dist;
+}
+
+// owning team + 1 of a cell, 0 if it's unowned
+fn owner_id(raw: vec2<i32>) -> u32 {
+    let cell = wrap_cell(raw);
+    let texel = cell - grid.owners_origin;
+    let size = vec2<i32>(textureDimensions(owners));
+    if any(texel < vec2(0)) || any(texel >= size) {
+        return 0u;
+    }
+    return textureLoad(owners, texel, 0).r;
+}
+
+// color of the team owning a cell, transparent if it's unowned
+fn owner_color(raw: vec2<i32>) -> vec4<f32> {
+    let owner = owner_id(raw);
+    if owner == 0u || owner > palette.count {
+        return vec4(0.0);
+    }
+    return palette.colors[owner - 1u];
+}
+
+// territory color of a cell; near an edge it's blended with the territory
+// across the edge, meeting halfway at the edge
+fn territory_color(uv: vec2<f32>, cell: CellCoords) -> vec4<f32> {
+    let color = owner_color(cell.cell);
+    if cell.edge_dist >= palette.border_width {
+        return color;
+    }
+    // step away from the cell center far enough to cross the nearest edge
+    // for every cell shape
+    let offset = normalize(uv - cell.coords) * (cell.edge_dist * 2.0 + 0.01);
+    let other = owner_color(cell_coords(uv + offset).cell);
+    let t = 0.5 * (1.0 - smoothstep(0.0, palette.border_width, cell.edge_dist));
+    return mix(color, other, t);
+}
+
+// ColorblindPreset pattern over the team owning a cell, transparent if it's
+// unowned; teams past the last pattern reuse them in order
+fn team_fill_color(pos: vec2<f32>, cell: CellCoords, pixel: f32) -> vec4<f32> {
+    let owner = owner_id(cell.cell);
+    if owner == 0u || owner > palette.count {
+        return vec4(0.0);
+    }
+    let team = (owner - 1u) % 8u;
+    let color = grid.team_fill_colors[team];
+    let coverage = fill_coverage(grid.team_fill_params[team], pos / grid.size, pixel);
+    return vec4(color.rgb, color.a * coverage);
+}
+
+// value of a cell in a BorderLayer, by index; 0 without one, or when the
+// grid doesn't have the layer
+fn border_value(cell: vec2<i32>, layer: u32) -> u32 {
+    var value = 0u;
+#ifdef TERRAIN
+    if layer == 0u {
+        value = u32(part_color(cell, 7u).x);
+    }
+#endif
+#ifdef WEATHER
+    if layer == 1u {
+        value = u32(part_color(cell, 9u).x);
+    }
+#endif
+#ifdef TERRITORY
+    if layer == 2u {
+        value = owner_id(cell);
+    }
+#endif
+    return value;
+}
+
+// HexGridBorders lines over `color`, along the nearest edge where the cell
+// across it has another value in a layer; mirrors HexGridBorders::edges()
+fn border_color(uv: vec2<f32>, cell: CellCoords, color: vec4<f32>) -> vec4<f32> {
+    // step across the nearest edge, as in territory_color()
+    let offset = normalize(uv - cell.coords) * (cell.edge_dist * 2.0 + 0.01);
+    let across = cell_coords(uv + offset).cell;
+    var result = color;
+    for (var i = 0u; i < 3u; i++) {
+        let width = grid.border_widths[i];
+        let style = grid.border_colors[i];
+        // line_coverage() may take derivatives, so it's called either way
+        let coverage = line_coverage(cell.edge_dist, width);
+        let differs = width > 0.0 && border_value(cell.cell, i) != border_value(across, i);
+        result = mix(result, vec4(style.rgb, 1.0), select(0.0, coverage, differs) * style.a);
+    }
+    return result;
+}
+
+// HexGridStencil test for a pixel; mirrors HexGridStencil::test()
+fn stencil_test(pixel: vec2<f32>) -> bool {
+#ifdef STENCIL_TEST
+    let value = textureLoad(stencil, vec2<i32>(pixel), 0).r;
+    let reference = grid.stencil_reference;
+    switch grid.stencil_compare {
+        case 0u: { return reference == value; }
+        case 1u: { return reference != value; }
+        case 2u: { return reference < value; }
+        case 3u: { return reference <= value; }
+        case 4u: { return reference > value; }
+        default: { return reference >= value; }
+    }
+#else
+    return true;
+#endif
+}
+
+// true if a point on the grid plane is inside the box selection; the quad is
+// convex, but may wind either way
+fn in_select_quad(pos: vec2<f32>) -> bool {
+    let corners = array<vec2<f32>, 4>(
+        view.select_quad[0].xy,
+        view.select_quad[0].zw,
+        view.select_quad[1].xy,
+        view.select_quad[1].zw
+    );
+    var inside = 0;
+    for (var i = 0; i < 4; i++) {
+        let a = corners[i];
+        let b = corners[(i + 1) % 4];
+        let edge = b - a;
+        let to = pos - a;
+        let cross = edge.x * to.y - edge.y * to.x;
+        inside += i32(sign(cross));
+    }
+    return abs(inside) == 4;
+}
+
+// steps between two hex cells
+fn hex_distance(a: vec2<i32>, b: vec2<i32>) -> u32 {
+    let d = a - b;
+    return u32((abs(d.x) + abs(d.y) + abs(d.x + d.y)) / 2);
+}
+
+// steps between two hex cells, the short way around a wrapped map; mirrors
+// HexWrap::distance()
+fn wrapped_distance(a: vec2<i32>, b: vec2<i32>) -> u32 {
+    if all(grid.wrap == vec2(0u)) {
+        return hex_distance(a, b);
+    }
+    let a_ = wrap_cell(a);
+    let b_ = wrap_cell(b);
+    let w = i32(grid.wrap.x);
+    let h = i32(grid.wrap.y);
+    let x = vec2(w, 0);
+    let y = vec2(-h / 2, h);
+    var best = hex_distance(a_, b_);
+    for (var i = -1; i <= 1; i++) {
+        for (var j = -1; j <= 1; j++) {
+            best = min(best, hex_distance(a_, b_ + x * i + y * j));
+        }
+    }
+    return best;
+}
+
+// color at `t`, from 0 to 1, along the heatmap gradient; mirrors
+// HexGridHeatmap::gradient_color()
+fn gradient_color(t: f32) -> vec4<f32> {
+    if grid.heat_count == 0u {
+        return vec4(0.0);
+    }
+    if grid.heat_count == 1u {
+        return grid.heat_colors[0];
+    }
+    let x = clamp(t, 0.0, 1.0) * f32(grid.heat_count - 1u);
+    let i = min(u32(x), grid.heat_count - 2u);
+    return mix(grid.heat_colors[i], grid.heat_colors[i + 1u], x - f32(i));
+}
+
+// heatmap color of a cell, transparent without a value.  Values are mapped
+// onto the gradient as in heatmap::normalize()
+fn heat_color(raw: vec2<i32>) -> vec4<f32> {
+    let p = wrap_cell(raw) - grid.heat_origin;
+    let size = vec2<i32>(textureDimensions(heat_values));
+    if any(p < vec2(0)) || any(p >= size) {
+        return vec4(0.0);
+    }
+    let texel = textureLoad(heat_values, p, 0).xy;
+    if texel.y == 0.0 {
+        return vec4(0.0);
+    }
+    var t = select(0.0, 1.0, texel.x >= grid.heat_max);
+    if grid.heat_max > grid.heat_min {
+        t = clamp((texel.x - grid.heat_min) / (grid.heat_max - grid.heat_min), 0.0, 1.0);
+    }
+    let color = gradient_color(t);
+    return vec4(color.rgb, color.a * grid.heat_alpha);
+}
+
+// fill of a cell in the distance rings around grid.rings_center,
+// transparent outside them; mirrors HexGridDistanceRings::ring_color()
+fn ring_color(cell: vec2<i32>) -> vec4<f32> {
+    if grid.rings_count == 0u {
+        return vec4(0.0);
+    }
+    let ring = wrapped_distance(grid.rings_center, cell);
+    if ring == 0u || ring > grid.rings_count {
+        return vec4(0.0);
+    }
+    let t = f32(ring - 1u) / f32(max(grid.rings_count, 2u) - 1u);
+    return mix(grid.rings_near_color, grid.rings_far_color, t);
+}
+
+// grid color at a point on the grid plane (local x, local z)
+fn grid_color(pos: vec2<f32>, in: VertexOutput) -> vec4<f32> {
+    let uv = grid_uv(pos);
+    let cell = cell_coords(uv);
+
+    // grid units per pixel; uv is continuous across cells, unlike the
+    // distances in CellCoords
+    let pixel = length(fwidth(uv)) / sqrt(2.0);
+    var width = grid.line_width;
+    if grid.line_width_pixels > 0.0 {
+        width = grid.line_width_pixels * view.scale_factor * pixel;
+    }
+    var color = grid.line_color;
+
+    if view.cursor_grid == grid.id && distance(cell.coords, in.cursor_cell) < 0.1 {
+        color = grid.cursor_color;
+        width = grid.cursor_line_width;
+        if in.cursor_cell_edge_dist < grid.cursor_edge_distance
+            && cell.side == in.cursor_cell_side {
+            color = grid.cursor_edge_color;
+        }
+    }
+
+    // terrain, territory & its patterns, and heatmap over the cell tint,
+    // then highlight, fill pattern, distance rings, decal, and the line on top
+    var base = grid.parity_colors[cell.parity];
+#ifdef TERRAIN
+    let terrain = terrain_color(pos, uv, cell);
+    base = mix(base, vec4(terrain.rgb, 1.0), terrain.a);
+#endif
+#ifdef TERRITORY
+    let territory = territory_color(uv, cell);
+    base = mix(base, vec4(territory.rgb, 1.0), territory.a);
+#ifdef TEAM_FILLS
+    let team_fill = team_fill_color(pos, cell, pixel);
+    base = mix(base, vec4(team_fill.rgb, 1.0), team_fill.a);
+#endif
+#endif
+#ifdef HEATMAP
+    let heat = heat_color(cell.cell);
+    base = mix(base, vec4(heat.rgb, 1.0), heat.a);
+#endif
+    let highlight = highlight_color(cell.cell);
+    base = mix(base, vec4(highlight.rgb, 1.0), highlight.a);
+#ifdef FILLS
+    let fill = fill_color(pos, cell, pixel);
+    base = mix(base, vec4(fill.rgb, 1.0), fill.a);
+#endif
+#ifndef GRID_SQUARE
+#ifndef GRID_TRIANGLE
+    let ring = ring_color(cell.cell);
+    base = mix(base, vec4(ring.rgb, 1.0), ring.a);
+#endif
+#endif
+    let decal = decal_color(pos, cell);
+    base = mix(base, vec4(decal.rgb, 1.0), decal.a);
+#ifdef PART_HIGHLIGHTS
+#ifndef GRID_SQUARE
+#ifndef GRID_TRIANGLE
+    base = network_color(uv, cell, base);
+#endif
+#endif
+#endif
+#ifdef ELEVATION
+    // the lower cell is shaded by the cliff above it
+    let drop = cliff_drop(uv, cell);
+    let shade = 1.0 - smoothstep(0.0, grid.cliff_shadow_width, cell.edge_dist);
+    let shadow = select(0.0, shade, drop < 0.0) * grid.cliff_shadow_color.a;
+    base = mix(base, vec4(grid.cliff_shadow_color.rgb, 1.0), shadow);
+#endif
+    var result = mix(base, color, line_coverage(cell.edge_dist, width));
+#ifdef BORDERS
+    result = border_color(uv, cell, result);
+#endif
+#ifdef ELEVATION
+    // line_coverage() may take derivatives, so it's called either way
+    let cliff = line_coverage(cell.edge_dist, grid.cliff_width);
+    let cliff_alpha = select(0.0, cliff, drop != 0.0) * grid.cliff_color.a;
+    result = mix(result, vec4(grid.cliff_color.rgb, 1.0), cliff_alpha);
+#ifndef GRID_SQUARE
+#ifndef GRID_TRIANGLE
+    let ramp = line_coverage(ramp_distance(uv, cell), grid.cliff_width * 0.5);
+    result = mix(result, vec4(grid.ramp_color.rgb, 1.0), ramp * grid.ramp_color.a);
+#endif
+#endif
+#endif
+#ifndef GRID_SQUARE
+#ifndef GRID_TRIANGLE
+    let superhex = superhex_coverage(uv, cell) * grid.superhex_color.a;
+    result = mix(result, vec4(grid.superhex_color.rgb, 1.0), superhex);
+#endif
+#endif
+#ifdef WEATHER
+    // weather over the lines, but under highlighted parts
+    let weather = weather_color(pos, cell);
+    result = mix(result, vec4(weather.rgb, 1.0), weather.a);
+#endif
+#ifdef PART_HIGHLIGHTS
+    let part = part_highlight_color(uv, cell, pixel);
+    result = mix(result, vec4(part.rgb, 1.0), part.a);
+#endif
+
+    // box selection over everything
+    if view.select_grid == grid.id && in_select_quad(pos) {
+        result = mix(result, vec4(view.select_color.rgb, 1.0), view.select_color.a);
+    }
+    return result;
+}
+
+#ifdef UNTONEMAP
+// Drawn before tonemapping on an hdr view; map the linear color to the scene
+// color the view's tonemapping turns back into it.  Display colors near 1
+// need unbounded scene colors, so they're capped.
+fn untonemap(color: vec3<f32>) -> vec3<f32> {
+    let c = min(color, vec3(0.99));
+#ifdef UNTONEMAP_LUMINANCE
+    // inverse of ReinhardLuminance: l / (1 + l)
+    let l = dot(c, vec3(0.2126, 0.7152, 0.0722));
+    let scene = c * (1.0 / max(1.0 - l, 0.01));
+#else ifdef UNTONEMAP_REINHARD
+    // inverse of Reinhard: c / (1 + c)
+    let scene = c / (1.0 - c);
+#else
+    let scene = color;
+#endif
+    return scene * view.color_scale;
+}
+#endif
+
 @fragment
 fn fragment(in: VertexOutput) -> FragmentOutput {
     var out: FragmentOutput;
 
-    // calculate intersect with y = 0;
-    let v = in.far_point - in.near_point;
-    let t = -in.near_point.y / v.y;
-    if t <= 0.0 {
+    // no early returns before the colors are calculated; fwidth() needs
+    // uniform control flow
+    let hit = plane_hit(in.clip_position.xy);
+
+#ifdef AA_SUPERSAMPLE
+    // two samples on opposite corners of the pixel
+    let a = plane_hit(in.clip_position.xy - 0.25);
+    let b = plane_hit(in.clip_position.xy + 0.25);
+    let color = (grid_color(a.pos.xz, in) + grid_color(b.pos.xz, in)) * 0.5;
+#else
+    let color = grid_color(hit.pos.xz, in);
+#endif
+
+    if !hit.valid || !stencil_test(in.clip_position.xy) || is_hole(hit.pos.xz) {
         return out;
     }
 
-    let intersect = in.near_point + t * v;
+    out.depth = plane_depth(hit.pos);
+#ifdef UNTONEMAP
+    out.color = vec4(untonemap(color.rgb), color.a);
+#else
+    out.color = color;
+#endif
+    return out;
+}
 
-    // calculate the depth from the intersect point
-    let clipped = view.projection * view.inverse_view * vec4(intersect, 1.0);
-    out.depth = clipped.z / clipped.w;
+struct PickOutput {
+    @location(0) cell: vec4<u32>,   // Grid.id, q, r, & 1 when hit
+    @builtin(frag_depth) depth: f32,
+};
 
+// HexGridGpuPicking: write the cell at the cursor pixel into a 1x1 target;
+// the depth test keeps the nearest grid
+@fragment
+fn pick(in: VertexOutput) -> PickOutput {
+    var out: PickOutput;
+    let hit = plane_hit(view.pick_pixel);
+    if !hit.valid || !stencil_test(view.pick_pixel) || is_hole(hit.pos.xz) {
+        return out;
+    }
 
-    let hex = hex_coords(intersect.xz);
+    let cell = cell_coords(grid_uv(hit.pos.xz)).cell;
+    out.cell = vec4(grid.id, bitcast<u32>(cell.x), bitcast<u32>(cell.y), 1u);
+    out.depth = plane_depth(hit.pos);
+    return out;
+}
 
-    var step = 0.04;
-    out.color = vec4(0.6);
+// view pixels per texel of the glow textures; flood.rs GLOW_SCALE
+const GLOW_SCALE: f32 = 2.0;
 
-    if distance(hex.coords, in.cursor_hex) < 0.1 {
-        out.color = vec4(1.0, 0.8, 0.2, 1.0);
-        step = 0.23;
-        if in.cursor_hex_edge_dist < 0.15 {
-            let h = hex.angle - hex.angle % radians(360.0 / 6.0);
-            if h == in.cursor_hex_angle {
-                out.color = vec4(0.2, 0.8, 1.0, 1.0);
-                step = 0.23;
-            }
-        }
-    }
+struct GlowSeedOutput {
+    @location(0) seed: vec4<f32>,   // texel center, for the jump flood
+    @location(1) glow: vec4<f32>,   // color * intensity, & width in pixels
+};
 
-    // lazy anti-alias using smoothstep
-    out.color *= (1.0 - smoothstep(0.0, step, hex.edge_dist));
+// HexGridGlow: mark the texels of the half resolution glow textures over a
+// glowing cell as seeds of the jump flood in hex_grid_glow.wgsl.  The glow
+// is stored in the part highlights table under key 10 as (color *
+// intensity, width)
+@fragment
+fn glow_seed(in: VertexOutput) -> GlowSeedOutput {
+    let pixel = in.clip_position.xy * GLOW_SCALE;
+    let hit = plane_hit(pixel);
+    let glow = part_color(cell_coords(grid_uv(hit.pos.xz)).cell, 10u);
+    if !hit.valid || glow.a == 0.0 || !stencil_test(pixel) || is_hole(hit.pos.xz) {
+        discard;
+    }
 
+    var out: GlowSeedOutput;
+    out.seed = vec4(in.clip_position.xy, 0.0, 0.0);
+    out.glow = glow;
     return out;
 }
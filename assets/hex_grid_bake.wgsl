@@ -0,0 +1,30 @@
+// writes highlight colors into a texture covering the highlighted cells; see
+// bake.rs
+
+struct Cell {
+    cell: vec2<i32>,
+    color: vec4<f32>,
+};
+
+struct Bake {
+    origin: vec2<i32>,  // cell stored in texel (0, 0)
+    count: u32,
+    cells: array<Cell>,
+};
+
+@group(0) @binding(0)
+var<storage, read> input: Bake;
+
+@group(0) @binding(1)
+var output: texture_storage_2d<rgba8unorm, write>;
+
+// one invocation per highlighted cell; texels without a highlight are left
+// zeroed, which the grid shader treats as transparent
+@compute @workgroup_size(64)
+fn bake(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= input.count {
+        return;
+    }
+    let cell = input.cells[id.x];
+    textureStore(output, cell.cell - input.origin, cell.color);
+}
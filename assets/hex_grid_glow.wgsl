@@ -0,0 +1,92 @@
+// jump flood & composite of HexGridGlow outlines; see flood.rs.  The seeds
+// are drawn by glow_seed() in hex_grid.wgsl
+
+// view pixels per texel of the glow textures; flood.rs GLOW_SCALE
+const GLOW_SCALE: f32 = 2.0;
+
+// step of one flood pass, in texels
+struct Flood {
+    step: i32,
+};
+
+// nearest seed found so far for each texel, as the texel center of the
+// seed, or (-1, -1) without one
+@group(0) @binding(0)
+var seeds_in: texture_2d<f32>;
+
+@group(0) @binding(1)
+var seeds_out: texture_storage_2d<rg32float, write>;
+
+@group(0) @binding(2)
+var<uniform> flood: Flood;
+
+// the finished flood, & the glow of each seed as (color * intensity,
+// width in view pixels); only used by composite()
+@group(0) @binding(3)
+var seeds: texture_2d<f32>;
+
+@group(0) @binding(4)
+var colors: texture_2d<f32>;
+
+// distance from a texel to a seed, or a large number without one
+fn seed_distance(texel: vec2<i32>, seed: vec2<f32>) -> f32 {
+    if seed.x < 0.0 {
+        return 1e9;
+    }
+    return distance(vec2<f32>(texel) + 0.5, seed);
+}
+
+// keep the nearest seed of the texel & its eight neighbors `flood.step`
+// texels away
+@compute @workgroup_size(8, 8)
+fn flood_step(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = vec2<i32>(textureDimensions(seeds_in));
+    let texel = vec2<i32>(id.xy);
+    if any(texel >= size) {
+        return;
+    }
+    var best = textureLoad(seeds_in, texel, 0).xy;
+    var best_distance = seed_distance(texel, best);
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            let other = texel + vec2(x, y) * flood.step;
+            if (x == 0 && y == 0) || any(other < vec2(0)) || any(other >= size) {
+                continue;
+            }
+            let seed = textureLoad(seeds_in, other, 0).xy;
+            let d = seed_distance(texel, seed);
+            if d < best_distance {
+                best = seed;
+                best_distance = d;
+            }
+        }
+    }
+    textureStore(seeds_out, texel, vec4(best, 0.0, 0.0));
+}
+
+@vertex
+fn vertex(@builtin(vertex_index) in_vertex_index: u32) -> @builtin(position) vec4<f32> {
+    // single triangle covering the viewport, as in hex_grid.wgsl
+    let uv = vec2<f32>(f32(in_vertex_index >> 1u), f32(in_vertex_index & 1u)) * 2.0;
+    return vec4(uv * vec2(2.0, -2.0) + vec2(-1.0, 1.0), 1.0, 1.0);
+}
+
+// glow of the nearest seed, fading out over its width; added to the view.
+// Texels over a glowing cell are their own seed, and don't glow.  Mirrors
+// HexGridGlow::falloff()
+@fragment
+fn composite(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let size = vec2<i32>(textureDimensions(seeds));
+    let p = position.xy / GLOW_SCALE;
+    let texel = min(vec2<i32>(p), size - 1);
+    let seed = textureLoad(seeds, texel, 0).xy;
+    if seed.x < 0.0 || all(vec2<i32>(seed) == texel) {
+        return vec4(0.0);
+    }
+    let glow = textureLoad(colors, vec2<i32>(seed), 0);
+    if glow.a <= 0.0 {
+        return vec4(0.0);
+    }
+    let t = 1.0 - clamp(distance(p, seed) * GLOW_SCALE / glow.a, 0.0, 1.0);
+    return vec4(glow.rgb * t * t, 0.0);
+}
@@ -0,0 +1,42 @@
+// per-cell hashing shared with the cpu; see the hash module in the crate.
+// Every function here must match its counterpart in src/hash.rs exactly.
+#define_import_path hex_grid::hash
+
+// lowbias32 finalizer
+fn hash_mix(key: u32) -> u32 {
+    var h = key;
+    h ^= h >> 16u;
+    h *= 0x7feb352du;
+    h ^= h >> 15u;
+    h *= 0x846ca68bu;
+    h ^= h >> 16u;
+    return h;
+}
+
+// hash_cell() before the last mix
+fn hash_key(cell: vec2<i32>, seed: u32) -> u32 {
+    let h = hash_mix(seed + 0x9e3779b9u);
+    return u32(cell.x) + hash_mix(u32(cell.y) + h);
+}
+
+// hash of a cell & seed; mirrors hash::hash_cell()
+fn hash_cell(cell: vec2<i32>, seed: u32) -> u32 {
+    return hash_mix(hash_key(cell, seed));
+}
+
+// hash_cell() from 0 up to, but not including, 1; mirrors
+// hash::hash_cell_f32()
+fn hash_cell_f32(cell: vec2<i32>, seed: u32) -> f32 {
+    return f32(hash_cell(cell, seed) >> 8u) / 16777216.0;
+}
+
+// value number n, from 0, drawn by a CellRng for a cell & seed
+fn cell_rng(cell: vec2<i32>, seed: u32, n: u32) -> u32 {
+    return hash_mix(hash_key(cell, seed) + n * 0x6a09e667u);
+}
+
+// cell_rng() from 0 up to, but not including, 1; mirrors
+// CellRng::next_f32()
+fn cell_rng_f32(cell: vec2<i32>, seed: u32, n: u32) -> f32 {
+    return f32(cell_rng(cell, seed, n) >> 8u) / 16777216.0;
+}
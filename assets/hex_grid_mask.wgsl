@@ -0,0 +1,57 @@
+// writes HexGridMask meshes into the view stencil texture; see stencil.rs
+
+// must match View in hex_grid.wgsl
+struct View {
+    viewport: vec4<u32>,
+    projection: mat4x4<f32>,
+    inverse_projection: mat4x4<f32>,
+    view: mat4x4<f32>,
+    inverse_view: mat4x4<f32>,
+    cursor_pos: vec2<f32>,
+    cursor_grid: u32,
+    select_grid: u32,
+    select_quad: array<vec4<f32>, 2>,
+    select_color: vec4<f32>,
+    pick_pixel: vec2<f32>,
+    color_scale: f32,
+    scale_factor: f32,
+};
+
+struct Mask {
+    transform: mat4x4<f32>,     // mesh local to world
+    value: u32,
+};
+
+#ifdef VIEW_PUSH_CONSTANTS
+var<push_constant> view: View;
+#else
+@group(0) @binding(0)
+var<uniform> view: View;
+#endif
+
+@group(0) @binding(1)
+var<storage, read> masks: array<Mask>;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) @interpolate(flat) value: u32,
+};
+
+// one instance per mask, indexing `masks`
+@vertex
+fn vertex(
+    @builtin(instance_index) instance: u32,
+    @location(0) position: vec3<f32>,
+) -> VertexOutput {
+    let mask = masks[instance];
+    var out: VertexOutput;
+    out.clip_position = view.projection * view.inverse_view * mask.transform
+        * vec4(position, 1.0);
+    out.value = mask.value;
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<u32> {
+    return vec4(in.value, 0u, 0u, 0u);
+}
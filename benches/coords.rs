@@ -0,0 +1,75 @@
+//! coordinate conversion benchmarks
+//!
+//! World to cell conversion runs for every cursor move & pick, and for every
+//! cell placed on the grid, so it should stay cheap for every grid kind.
+use bevy::prelude::*;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use hex_grid::{Axial, GridKind, HexGridConfig, Orientation};
+
+const SIZES: [u32; 3] = [1_000, 10_000, 100_000];
+
+// deterministic spread of points over a square region of the grid plane
+fn points(count: u32) -> Vec<Vec2> {
+    let side = (count as f32).sqrt().ceil() as u32;
+    (0..count)
+        .map(|i| Vec2::new((i % side) as f32, (i / side) as f32) * 0.37 - side as f32 * 0.18)
+        .collect()
+}
+
+fn world_to_cell(c: &mut Criterion) {
+    let mut group = c.benchmark_group("world_to_cell");
+    for count in SIZES {
+        let points = points(count);
+        group.throughput(Throughput::Elements(count as u64));
+
+        group.bench_with_input(BenchmarkId::new("axial", count), &points, |b, points| {
+            b.iter(|| {
+                for p in points {
+                    black_box(Axial::from_world(*p));
+                }
+            })
+        });
+
+        for kind in GridKind::ALL {
+            let config = HexGridConfig {
+                kind,
+                orientation: Orientation::Flat,
+                size: 1.5,
+                ..default()
+            };
+            let id = BenchmarkId::new(format!("{:?}", kind), count);
+            group.bench_with_input(id, &points, |b, points| {
+                b.iter(|| {
+                    for p in points {
+                        black_box(config.world_to_cell(*p));
+                    }
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+fn cell_to_world(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cell_to_world");
+    for count in SIZES {
+        let cells: Vec<Axial> = Axial::ZERO.spiral(u32::MAX).take(count as usize).collect();
+        group.throughput(Throughput::Elements(count as u64));
+
+        for kind in GridKind::ALL {
+            let config = HexGridConfig { kind, ..default() };
+            let id = BenchmarkId::new(format!("{:?}", kind), count);
+            group.bench_with_input(id, &cells, |b, cells| {
+                b.iter(|| {
+                    for cell in cells {
+                        black_box(config.cell_to_world(*cell));
+                    }
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, world_to_cell, cell_to_world);
+criterion_main!(benches);
@@ -0,0 +1,118 @@
+//! [`HexMap`] & highlight benchmarks, at several map sizes
+//!
+//! Map radii are chosen to cover a skirmish board, a campaign map, and a
+//! large procedurally generated world.
+use bevy::{
+    camera::{primitives::Frustum, CameraProjection},
+    prelude::*,
+};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use hex_grid::{Axial, HexGridConfig, HexGridHighlights, HexGridPlane, HexMap};
+
+const RADII: [u32; 3] = [10, 50, 200];
+
+fn map(radius: u32) -> HexMap<u32> {
+    Axial::ZERO
+        .range(radius)
+        .map(|cell| (cell, cell.length()))
+        .collect()
+}
+
+fn cell_count(radius: u32) -> u64 {
+    let n = radius as u64;
+    1 + 3 * n * (n + 1)
+}
+
+fn insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hex_map_insert");
+    for radius in RADII {
+        let cells: Vec<Axial> = Axial::ZERO.range(radius).collect();
+        group.throughput(Throughput::Elements(cell_count(radius)));
+        group.bench_with_input(BenchmarkId::from_parameter(radius), &cells, |b, cells| {
+            b.iter(|| {
+                let mut map = HexMap::new();
+                for cell in cells {
+                    map.insert(*cell, cell.q);
+                }
+                map
+            })
+        });
+    }
+    group.finish();
+}
+
+fn get(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hex_map_get");
+    for radius in RADII {
+        let map = map(radius);
+        // half the lookups miss
+        let cells: Vec<Axial> = Axial::ZERO.range(radius * 2).step_by(2).collect();
+        group.throughput(Throughput::Elements(cells.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(radius), &cells, |b, cells| {
+            b.iter(|| {
+                for cell in cells {
+                    black_box(map.get(*cell));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn iter(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hex_map_iter");
+    for radius in RADII {
+        let map = map(radius);
+        group.throughput(Throughput::Elements(cell_count(radius)));
+        group.bench_with_input(BenchmarkId::from_parameter(radius), &map, |b, map| {
+            b.iter(|| map.iter().map(|(_, v)| *v as u64).sum::<u64>())
+        });
+    }
+    group.finish();
+}
+
+fn bounds(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hex_map_bounds");
+    for radius in RADII {
+        let map = map(radius);
+        group.throughput(Throughput::Elements(cell_count(radius)));
+        group.bench_with_input(BenchmarkId::from_parameter(radius), &map, |b, map| {
+            b.iter(|| map.bounds())
+        });
+    }
+    group.finish();
+}
+
+/// per-view highlight culling, as done in the render world each frame before
+/// the highlight buffer is written
+fn highlight_culling(c: &mut Criterion) {
+    let config = HexGridConfig::default();
+    let plane = HexGridPlane::default();
+
+    // looking down at the grid origin from an angle; roughly 60 cells across
+    let projection = PerspectiveProjection::default();
+    let view = Transform::from_xyz(0.0, 25.0, 25.0).looking_at(Vec3::ZERO, Vec3::Y);
+    let frustum = Frustum::from_clip_from_world(
+        &(projection.get_clip_from_view() * view.to_matrix().inverse()),
+    );
+
+    let mut group = c.benchmark_group("highlight_culling");
+    for radius in RADII {
+        let highlights = HexGridHighlights(
+            Axial::ZERO
+                .range(radius)
+                .map(|cell| (cell, Color::srgb(1.0, 0.0, 0.0)))
+                .collect(),
+        );
+        group.throughput(Throughput::Elements(cell_count(radius)));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(radius),
+            &highlights,
+            |b, highlights| b.iter(|| highlights.visible(&config, &plane, &frustum).count()),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, insert, get, iter, bounds, highlight_culling);
+criterion_main!(benches);
@@ -0,0 +1,86 @@
+//! field of view, influence, and flow field benchmarks on a 512x512 map
+//!
+//! Run once without & once with the `parallel` feature to see the speedup:
+//!
+//! ```text
+//! cargo bench --bench parallel -- --save-baseline sequential
+//! cargo bench --bench parallel --features parallel -- --baseline sequential
+//! ```
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use hex_grid::{
+    fov::fields_of_view,
+    hash::hash_cell_f32,
+    influence::{propagate, Decay},
+    path::FlowField,
+    Axial, HexBounds,
+};
+
+const SIZE: i32 = 512;
+
+fn board() -> HexBounds {
+    HexBounds::new(Axial::ZERO, Axial::new(SIZE - 1, SIZE - 1))
+}
+
+// one cell in five is a wall
+fn wall(cell: Axial) -> bool {
+    hash_cell_f32(cell, 7) < 0.2
+}
+
+// `count` open cells spread over the board
+fn spread(count: usize, seed: u32) -> Vec<Axial> {
+    board()
+        .cells()
+        .filter(|&cell| {
+            !wall(cell) && hash_cell_f32(cell, seed) < 1.5 * count as f32 / (SIZE * SIZE) as f32
+        })
+        .take(count)
+        .collect()
+}
+
+fn fov(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fields_of_view");
+    let bounds = board();
+    let blocks = |cell| !bounds.contains(cell) || wall(cell);
+    for units in [256, 2048] {
+        let viewers: Vec<(Axial, u32)> = spread(units, 1).into_iter().map(|c| (c, 8)).collect();
+        group.throughput(Throughput::Elements(viewers.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(units),
+            &viewers,
+            |b, viewers| b.iter(|| fields_of_view(viewers, blocks)),
+        );
+    }
+    group.finish();
+}
+
+fn influence(c: &mut Criterion) {
+    let mut group = c.benchmark_group("propagate");
+    for sources in [256, 4096] {
+        let sources: Vec<(Axial, f32)> = spread(sources, 2).into_iter().map(|c| (c, 1.0)).collect();
+        group.throughput(Throughput::Elements(sources.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(sources.len()),
+            &sources,
+            |b, sources| b.iter(|| propagate(sources.iter().copied(), Decay::Linear, 6)),
+        );
+    }
+    group.finish();
+}
+
+fn flow_fields(c: &mut Criterion) {
+    let mut group = c.benchmark_group("flow_fields");
+    group.sample_size(10);
+    let bounds = board();
+    let cost = |cell| (bounds.contains(cell) && !wall(cell)).then_some(1);
+    for sets in [1, 8] {
+        let goals: Vec<Vec<Axial>> = spread(sets * 4, 3).chunks(4).map(<[_]>::to_vec).collect();
+        group.throughput(Throughput::Elements(goals.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(sets), &goals, |b, goals| {
+            b.iter(|| FlowField::many(goals.iter().cloned(), cost))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, fov, influence, flow_fields);
+criterion_main!(benches);
@@ -0,0 +1,60 @@
+/// stress test for highlight culling
+///
+/// Highlights every cell within a large radius of the origin, and logs the
+/// frame time once a second.  Pan & zoom around to compare frame times with
+/// most of the highlights on and off screen.
+///
+/// The radius defaults to 200 (~120k cells), and can be set with the first
+/// argument:
+///     cargo run --release --example highlight_stress -- 400
+use bevy::{
+    diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin},
+    prelude::*,
+};
+use hex_grid::{
+    camera::{HexGridCamera, HexGridCameraPlugin},
+    Axial, HexGridConfig, HexGridCursor, HexGridHighlights, HexGridPlugin, HexMap,
+};
+
+fn main() {
+    App::new()
+        .add_plugins((
+            DefaultPlugins,
+            FrameTimeDiagnosticsPlugin::default(),
+            LogDiagnosticsPlugin::default(),
+            HexGridCameraPlugin,
+            HexGridPlugin::default(),
+        ))
+        .add_systems(Startup, setup)
+        .run();
+}
+
+fn setup(mut commands: Commands) {
+    let radius = std::env::args()
+        .nth(1)
+        .map(|arg| arg.parse().expect("radius must be a number"))
+        .unwrap_or(200);
+
+    let highlights: HexMap<Color> = Axial::ZERO
+        .spiral(radius)
+        .map(|cell| {
+            // rainbow by distance, so culling mistakes are easy to spot
+            let hue = (cell.length() % 36) as f32 * 10.0;
+            (cell, Color::hsla(hue, 0.8, 0.5, 0.5))
+        })
+        .collect();
+    info!("highlighting {} cells", highlights.len());
+
+    commands.spawn((
+        Name::new("Grid"),
+        HexGridConfig::default(),
+        HexGridHighlights(highlights),
+    ));
+
+    commands.spawn((
+        Name::new("Camera"),
+        HexGridCursor::default(),
+        Camera3d::default(),
+        HexGridCamera::default(),
+    ));
+}
@@ -0,0 +1,189 @@
+//! ui & billboards anchored to cells
+//!
+//! Add a [`HexAnchor`] to keep an entity over the center of a cell as the
+//! camera moves; damage numbers, cell labels, health bars:
+//!
+//! * UI nodes are positioned absolutely, centered over the cell, and hidden
+//!   while the cell is behind the camera.
+//! * Anything else with a [`Transform`], such as a `Text2d` or a mesh, is
+//!   moved to the cell and turned to face the camera.  Billboards must not
+//!   have a parent; their transform is in world space.
+//!
+//! ```ignore
+//! commands.spawn((
+//!     Text::new("-12"),
+//!     HexAnchor::new(grid, cell).with_offset(0.5),
+//! ));
+//! ```
+use bevy::{prelude::*, transform::helper::TransformHelper};
+
+use crate::{Axial, HexGridConfig, HexGridPlane};
+
+/// Keep an entity over the center of a cell; see the [module docs](self).
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct HexAnchor {
+    /// grid entity the cell is on
+    pub grid: Entity,
+    pub cell: Axial,
+    /// height above the grid plane, in the grid's local units
+    pub offset: f32,
+    /// added to the position on screen, logical pixels; +Y is down
+    pub screen_offset: Vec2,
+    /// camera to track; defaults to the first active 3d camera
+    pub camera: Option<Entity>,
+}
+
+impl HexAnchor {
+    pub fn new(grid: Entity, cell: Axial) -> Self {
+        Self {
+            grid,
+            cell,
+            offset: 0.0,
+            screen_offset: Vec2::ZERO,
+            camera: None,
+        }
+    }
+
+    pub fn with_offset(mut self, offset: f32) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn with_screen_offset(mut self, screen_offset: Vec2) -> Self {
+        self.screen_offset = screen_offset;
+        self
+    }
+
+    pub fn with_camera(mut self, camera: Entity) -> Self {
+        self.camera = Some(camera);
+        self
+    }
+
+    /// world position of the anchor point on a grid
+    pub fn world_position(&self, config: &HexGridConfig, plane: &HexGridPlane) -> Vec3 {
+        let pos = config.cell_to_world(self.cell);
+        plane.local_to_world(Vec3::new(pos.x, self.offset, pos.y))
+    }
+
+    /// position of the anchor in logical window pixels, where anchored ui
+    /// nodes are centered; `None` if it's behind the camera
+    pub fn screen_position(
+        &self,
+        config: &HexGridConfig,
+        plane: &HexGridPlane,
+        camera: &Camera,
+        camera_transform: &GlobalTransform,
+    ) -> Option<Vec2> {
+        // world_to_viewport() includes the viewport's own offset
+        let pos = self.world_position(config, plane);
+        let screen = camera.world_to_viewport(camera_transform, pos).ok()?;
+        Some(screen + self.screen_offset)
+    }
+}
+
+// the camera an anchor tracks, from cameras & whether each is active
+fn anchor_camera(
+    anchor: &HexAnchor,
+    mut cameras: impl Iterator<Item = (Entity, bool)>,
+) -> Option<Entity> {
+    match anchor.camera {
+        Some(entity) => Some(entity),
+        None => cameras.find(|(_, active)| *active).map(|(e, _)| e),
+    }
+}
+
+/// position anchored ui nodes; runs before layout, which is before transform
+/// propagation, so the transforms are computed here
+#[allow(clippy::type_complexity)]
+pub(crate) fn anchor_ui_nodes(
+    mut nodes: Query<(
+        &HexAnchor,
+        &mut Node,
+        Option<&ComputedNode>,
+        &mut Visibility,
+    )>,
+    cameras: Query<(Entity, &Camera), With<Camera3d>>,
+    grids: Query<&HexGridConfig>,
+    transforms: TransformHelper,
+) {
+    if nodes.is_empty() {
+        return;
+    }
+    for (anchor, mut node, computed, mut visibility) in &mut nodes {
+        let active = cameras.iter().map(|(e, c)| (e, c.is_active));
+        let screen = anchor_camera(anchor, active).and_then(|camera_entity| {
+            let (_, camera) = cameras.get(camera_entity).ok()?;
+            let config = grids.get(anchor.grid).ok()?;
+            let plane = HexGridPlane::from(transforms.compute_global_transform(anchor.grid).ok()?);
+            let camera_transform = transforms.compute_global_transform(camera_entity).ok()?;
+            anchor.screen_position(config, &plane, camera, &camera_transform)
+        });
+
+        let Some(screen) = screen else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+        visibility.set_if_neq(Visibility::Inherited);
+        // centered on the cell, using the size from the last layout
+        let size = computed.map_or(Vec2::ZERO, |c| c.size() * c.inverse_scale_factor());
+        let corner = screen - size * 0.5;
+        node.position_type = PositionType::Absolute;
+        node.left = Val::Px(corner.x);
+        node.top = Val::Px(corner.y);
+    }
+}
+
+/// move anchored billboards; runs after transform propagation
+#[allow(clippy::type_complexity)]
+pub(crate) fn anchor_billboards(
+    mut billboards: Query<(&HexAnchor, &mut Transform, &mut GlobalTransform), Without<Node>>,
+    cameras: Query<(Entity, &Camera, &GlobalTransform), (With<Camera3d>, Without<HexAnchor>)>,
+    grids: Query<(&HexGridConfig, &GlobalTransform), Without<HexAnchor>>,
+) {
+    if billboards.is_empty() {
+        return;
+    }
+    for (anchor, mut transform, mut global) in &mut billboards {
+        let active = cameras.iter().map(|(e, c, _)| (e, c.is_active));
+        let Some(camera) = anchor_camera(anchor, active) else {
+            continue;
+        };
+        let Ok((_, camera, camera_transform)) = cameras.get(camera) else {
+            continue;
+        };
+        let Ok((config, grid_transform)) = grids.get(anchor.grid) else {
+            continue;
+        };
+
+        let mut pos = anchor.world_position(config, &HexGridPlane::from(*grid_transform));
+        if anchor.screen_offset != Vec2::ZERO {
+            // move across the view at the anchor's depth
+            let eye = camera_transform.translation();
+            let forward = camera_transform.forward();
+            let depth = (pos - eye).dot(*forward);
+            let offset = camera
+                .world_to_viewport(camera_transform, pos)
+                .ok()
+                .and_then(|p| {
+                    camera
+                        .viewport_to_world(camera_transform, p + anchor.screen_offset)
+                        .ok()
+                });
+            if let Some(ray) = offset {
+                let t = (depth - (ray.origin - eye).dot(*forward)) / ray.direction.dot(*forward);
+                pos = ray.get_point(t);
+            }
+        }
+
+        // facing the camera, upright on screen; written to both transforms
+        // as propagation has already run
+        let target = Transform::from_translation(pos)
+            .with_rotation(camera_transform.rotation())
+            .with_scale(transform.scale);
+        if *transform != target {
+            *transform = target;
+            *global = GlobalTransform::from(target);
+        }
+    }
+}
@@ -0,0 +1,249 @@
+//! timed highlight animations
+//!
+//! Play a [`CellAnimation`] on a grid entity to flash, fade, or sweep a wave
+//! of color across cells, without writing any timing code:
+//!
+//! ```ignore
+//! commands
+//!     .entity(grid)
+//!     .animate_cells(CellAnimation::wave(path, Color::WHITE, 0.05, 0.3));
+//! ```
+//!
+//! Animations are drawn through the grid's [`HexGridHighlights`]; each frame
+//! the animated colors are blended over the highlight colors the cells
+//! already have, and put back the next frame.  Cells whose highlight is
+//! changed while animating keep the new color.
+use bevy::{platform::collections::HashMap, prelude::*};
+
+use crate::{Axial, GridTime, HexGridHighlights};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Curve {
+    /// transparent to the color & back
+    Flash(Color),
+    /// transparent to the color
+    FadeIn(Color),
+    /// from one color to another
+    Lerp(Color, Color),
+}
+
+/// Color change over time for a set of cells.  Each cell can start after
+/// its own delay, for effects that travel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellAnimation {
+    /// cells & how long after the animation starts each one begins
+    cells: Vec<(Axial, f32)>,
+    curve: Curve,
+    /// seconds each cell animates for
+    duration: f32,
+    keep: bool,
+}
+
+impl CellAnimation {
+    /// flash `color` on the cells, fading in & back out over `duration`
+    /// seconds
+    pub fn flash(cells: impl IntoIterator<Item = Axial>, color: Color, duration: f32) -> Self {
+        Self::new(cells, Curve::Flash(color), duration)
+    }
+
+    /// fade the cells in to `color` over `duration` seconds
+    pub fn fade_in(cells: impl IntoIterator<Item = Axial>, color: Color, duration: f32) -> Self {
+        Self::new(cells, Curve::FadeIn(color), duration)
+    }
+
+    /// change the cells from `from` to `to` over `duration` seconds
+    pub fn lerp(
+        cells: impl IntoIterator<Item = Axial>,
+        from: Color,
+        to: Color,
+        duration: f32,
+    ) -> Self {
+        Self::new(cells, Curve::Lerp(from, to), duration)
+    }
+
+    /// flash each cell of `path` in order, starting `stagger` seconds apart,
+    /// each for `duration` seconds
+    pub fn wave(
+        path: impl IntoIterator<Item = Axial>,
+        color: Color,
+        stagger: f32,
+        duration: f32,
+    ) -> Self {
+        Self {
+            cells: path
+                .into_iter()
+                .enumerate()
+                .map(|(i, cell)| (cell, i as f32 * stagger))
+                .collect(),
+            ..Self::flash([], color, duration)
+        }
+    }
+
+    fn new(cells: impl IntoIterator<Item = Axial>, curve: Curve, duration: f32) -> Self {
+        Self {
+            cells: cells.into_iter().map(|cell| (cell, 0.0)).collect(),
+            curve,
+            duration: duration.max(0.0),
+            keep: false,
+        }
+    }
+
+    /// start `seconds` later
+    pub fn delayed(mut self, seconds: f32) -> Self {
+        for (_, delay) in &mut self.cells {
+            *delay += seconds;
+        }
+        self
+    }
+
+    /// leave the final colors in the grid's [`HexGridHighlights`] when the
+    /// animation ends, instead of putting back the colors from before
+    pub fn keep(mut self) -> Self {
+        self.keep = true;
+        self
+    }
+
+    /// seconds from the start until the last cell finishes
+    pub fn length(&self) -> f32 {
+        self.cells
+            .iter()
+            .map(|(_, delay)| delay + self.duration)
+            .fold(0.0, f32::max)
+    }
+
+    /// Color of each cell `elapsed` seconds after the start; cells that
+    /// haven't started are skipped, and finished cells hold their final
+    /// color.
+    pub fn sample(&self, elapsed: f32) -> impl Iterator<Item = (Axial, Color)> + '_ {
+        self.cells.iter().filter_map(move |&(cell, delay)| {
+            let t = elapsed - delay;
+            if t < 0.0 {
+                return None;
+            }
+            let t = if self.duration > 0.0 {
+                (t / self.duration).min(1.0)
+            } else {
+                1.0
+            };
+            let color = match self.curve {
+                Curve::Flash(color) => {
+                    color.with_alpha(color.alpha() * (1.0 - (2.0 * t - 1.0).abs()))
+                }
+                Curve::FadeIn(color) => color.with_alpha(color.alpha() * t),
+                Curve::Lerp(from, to) => Color::from(from.to_linear().mix(&to.to_linear(), t)),
+            };
+            Some((cell, color))
+        })
+    }
+}
+
+/// Animations playing on a grid entity.  Use [`HexGridCellAnimations::play`],
+/// or [`CellAnimationCommands::animate_cells`].
+#[derive(Component, Debug, Default, Clone)]
+#[require(HexGridHighlights)]
+pub struct HexGridCellAnimations {
+    /// animations & seconds since each started
+    playing: Vec<(CellAnimation, f32)>,
+    /// colors written to the highlights last frame, with the color each cell
+    /// had before
+    written: HashMap<Axial, (Option<Color>, Color)>,
+}
+
+impl HexGridCellAnimations {
+    pub fn play(&mut self, animation: CellAnimation) {
+        self.playing.push((animation, 0.0));
+    }
+
+    pub fn is_playing(&self) -> bool {
+        !self.playing.is_empty()
+    }
+
+    /// stop every animation; the highlights are put back on the next update
+    pub fn stop_all(&mut self) {
+        self.playing.clear();
+    }
+}
+
+/// play [`CellAnimation`]s on a grid entity from [`Commands`]
+pub trait CellAnimationCommands {
+    fn animate_cells(&mut self, animation: CellAnimation) -> &mut Self;
+}
+
+impl CellAnimationCommands for EntityCommands<'_> {
+    fn animate_cells(&mut self, animation: CellAnimation) -> &mut Self {
+        self.entry::<HexGridCellAnimations>()
+            .or_default()
+            .and_modify(move |mut animations| animations.play(animation));
+        self
+    }
+}
+
+pub(crate) fn update_cell_animations(
+    time: Res<GridTime>,
+    mut grids: Query<(&mut HexGridCellAnimations, &mut HexGridHighlights)>,
+) {
+    for (mut animations, mut highlights) in &mut grids {
+        if animations.playing.is_empty() && animations.written.is_empty() {
+            continue;
+        }
+        let animations = &mut *animations;
+        let highlights = &mut highlights.0;
+
+        // put back last frame's colors, unless something else changed them
+        for (cell, (before, written)) in animations.written.drain() {
+            if highlights.get(cell) != Some(&written) {
+                continue;
+            }
+            match before {
+                Some(color) => highlights.insert(cell, color),
+                None => highlights.remove(cell),
+            };
+        }
+
+        let delta = time.delta_secs();
+        for (_, elapsed) in &mut animations.playing {
+            *elapsed += delta;
+        }
+
+        // finished animations that keep their colors become the highlights
+        animations.playing.retain(|(animation, elapsed)| {
+            if *elapsed < animation.length() {
+                return true;
+            }
+            if animation.keep {
+                for (cell, color) in animation.sample(*elapsed) {
+                    let color = blend(highlights.get(cell).copied(), color);
+                    highlights.insert(cell, color);
+                }
+            }
+            false
+        });
+
+        // later animations are drawn over earlier ones
+        for (animation, elapsed) in &animations.playing {
+            for (cell, color) in animation.sample(*elapsed) {
+                let current = highlights.get(cell).copied();
+                let before = match animations.written.get(&cell) {
+                    Some(&(before, _)) => before,
+                    None => current,
+                };
+                let color = blend(current, color);
+                highlights.insert(cell, color);
+                animations.written.insert(cell, (before, color));
+            }
+        }
+    }
+}
+
+// `color` alpha blended over `base`
+fn blend(base: Option<Color>, color: Color) -> Color {
+    let Some(base) = base else { return color };
+    let (base, over) = (base.to_linear(), color.to_linear());
+    let alpha = over.alpha + base.alpha * (1.0 - over.alpha);
+    if alpha <= 0.0 {
+        return Color::NONE;
+    }
+    let rgb =
+        (over.to_vec3() * over.alpha + base.to_vec3() * base.alpha * (1.0 - over.alpha)) / alpha;
+    Color::from(LinearRgba::from_vec3(rgb).with_alpha(alpha))
+}
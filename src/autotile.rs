@@ -0,0 +1,191 @@
+//! auto-tiling of terrain edges
+//!
+//! Add a [`HexGridAutoTile`] next to a grid's [`HexGridTerrain`] to pick a
+//! [`TileVariant`] for each cell from its neighbors, like Wang tiles on hex
+//! adjacency, so coasts & forest edges look placed by hand.  Each
+//! [`AutoTileRule`] covers the cells of one terrain: the neighbors of a cell
+//! with a terrain in [`AutoTileRule::border`] form a pattern of up to six
+//! sides, and the variant given for that pattern, turned any of six ways to
+//! fit, is drawn on the cell.  Variant textures are ordinary [`Terrain`]
+//! kinds; see [`TileVariant`] for how they're laid over the cell.
+//!
+//! ```ignore
+//! // water next to land gets a shore along those sides; patterns have bit
+//! // `i` set for a border toward Axial::NEIGHBORS[i]
+//! let shore = AutoTileRule::new(WATER)
+//!     .with_border([GRASS, SAND])
+//!     .with_variant(0b000001, SHORE_ONE_SIDE)
+//!     .with_variant(0b000011, SHORE_TWO_SIDES)
+//!     .with_variant(0b000101, SHORE_SPLIT);
+//! commands.entity(grid).insert(HexGridAutoTile::new([shore]));
+//! ```
+//!
+//! [`HexGridTerrain::variants`] is kept up to date as cells change; only
+//! cells near the changes are looked at again.  Cells no rule gives a
+//! variant to have theirs removed.
+//!
+//! [`Terrain`]: crate::Terrain
+use bevy::prelude::*;
+
+use crate::{
+    terrain::TileVariant, Axial, HexBounds, HexGridConfig, HexGridTerrain, HexMap, HexMapVersion,
+    TerrainId,
+};
+
+/// Picks variants for the cells of one terrain from which of their
+/// neighbors are borders.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AutoTileRule {
+    /// terrain of the cells the rule picks variants for
+    pub terrain: TerrainId,
+    /// terrains of neighbors that count as a border
+    pub border: Vec<TerrainId>,
+    /// Variant kind for each pattern of borders, with bit `i` set for a
+    /// border toward [`Axial::NEIGHBORS`]`[i]`.  The first pattern that
+    /// matches at any rotation is used.
+    pub variants: Vec<(u8, TerrainId)>,
+}
+
+impl AutoTileRule {
+    pub fn new(terrain: TerrainId) -> Self {
+        Self {
+            terrain,
+            border: Vec::new(),
+            variants: Vec::new(),
+        }
+    }
+
+    pub fn with_border(mut self, terrains: impl IntoIterator<Item = TerrainId>) -> Self {
+        self.border.extend(terrains);
+        self
+    }
+
+    pub fn with_variant(mut self, pattern: u8, kind: TerrainId) -> Self {
+        self.variants.push((pattern & 0x3f, kind));
+        self
+    }
+
+    /// pattern of the neighbors of `cell` that are borders
+    pub fn borders(&self, terrain_at: impl Fn(Axial) -> Option<TerrainId>, cell: Axial) -> u8 {
+        cell.neighbors()
+            .enumerate()
+            .filter(|&(_, n)| terrain_at(n).is_some_and(|t| self.border.contains(&t)))
+            .fold(0, |mask, (i, _)| mask | 1 << i)
+    }
+
+    /// variant for a pattern of borders, turned to fit it
+    pub fn variant(&self, borders: u8) -> Option<TileVariant> {
+        self.variants.iter().find_map(|&(pattern, kind)| {
+            (0..6)
+                .find(|&rotation| rotate_pattern(pattern, rotation) == borders)
+                .map(|rotation| TileVariant { kind, rotation })
+        })
+    }
+}
+
+/// Rules for picking the [`HexGridTerrain::variants`] of a grid entity; see
+/// the [module docs](self).
+#[derive(Component, Debug, Default, Clone)]
+pub struct HexGridAutoTile {
+    /// checked in order; the first rule for a cell's terrain is used
+    pub rules: Vec<AutoTileRule>,
+    /// terrain version the variants were last picked for
+    synced: Option<HexMapVersion>,
+}
+
+impl HexGridAutoTile {
+    pub fn new(rules: impl IntoIterator<Item = AutoTileRule>) -> Self {
+        Self {
+            rules: rules.into_iter().collect(),
+            synced: None,
+        }
+    }
+
+    pub fn with_rule(mut self, rule: AutoTileRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// variant for `cell`, from the terrain of it & its neighbors
+    pub fn variant(
+        &self,
+        terrain_at: impl Fn(Axial) -> Option<TerrainId>,
+        cell: Axial,
+    ) -> Option<TileVariant> {
+        let terrain = terrain_at(cell)?;
+        let rule = self.rules.iter().find(|rule| rule.terrain == terrain)?;
+        rule.variant(rule.borders(terrain_at, cell))
+    }
+
+    /// variant of every cell of `cells` that gets one
+    pub fn variants(&self, cells: &HexMap<TerrainId>) -> HexMap<TileVariant> {
+        cells
+            .cells()
+            .filter_map(|cell| Some((cell, self.variant(|c| cells.get(c).copied(), cell)?)))
+            .collect()
+    }
+
+    /// Pick variants again for the cells near changes to `terrain` since
+    /// the last update, or for every cell after a [`HexGridAutoTile::reset`].
+    /// Done every frame for grid entities with both components.
+    pub fn update(&mut self, terrain: &mut HexGridTerrain, config: &HexGridConfig) {
+        // changes on wrapped maps redo every cell, as neighbors may be
+        // across the seam
+        let changed = self
+            .synced
+            .filter(|_| config.wrap.is_none())
+            .and_then(|synced| terrain.cells.changed_chunks(synced))
+            .map(|chunks| chunks.collect::<Vec<_>>());
+        self.synced = Some(terrain.cells.version());
+
+        let terrain_at = |cell| terrain.cells.get(config.wrap_cell(cell)).copied();
+        let Some(chunks) = changed else {
+            terrain.variants = terrain
+                .cells
+                .cells()
+                .filter_map(|cell| Some((cell, self.variant(terrain_at, cell)?)))
+                .collect();
+            return;
+        };
+        for chunk in chunks {
+            // the borders of the cells around the chunk may have changed too
+            let bounds = HexBounds::new(chunk.min - Axial::new(1, 1), chunk.max + Axial::new(1, 1));
+            for cell in bounds.cells() {
+                let variant = self.variant(terrain_at, cell);
+                if terrain.variants.get(cell).copied() == variant {
+                    continue;
+                }
+                match variant {
+                    Some(variant) => terrain.variants.insert(cell, variant),
+                    None => terrain.variants.remove(cell),
+                };
+            }
+        }
+    }
+
+    /// pick variants for every cell on the next update, as after editing
+    /// the rules
+    pub fn reset(&mut self) {
+        self.synced = None;
+    }
+}
+
+// turn a pattern of sides `steps` sixths toward increasing direction index
+fn rotate_pattern(pattern: u8, steps: u8) -> u8 {
+    ((pattern << steps) | (pattern >> ((6 - steps) % 6))) & 0x3f
+}
+
+pub(crate) fn update_auto_tiles(
+    mut grids: Query<(&mut HexGridAutoTile, &mut HexGridTerrain, &HexGridConfig)>,
+) {
+    for (mut auto, mut terrain, config) in &mut grids {
+        // new or edited rules redo every cell
+        if auto.is_changed() {
+            auto.bypass_change_detection().reset();
+        }
+        if auto.synced != Some(terrain.cells.version()) {
+            auto.bypass_change_detection().update(&mut terrain, config);
+        }
+    }
+}
@@ -0,0 +1,298 @@
+//! render world side of [`crate::highlight::HexGridBakeHighlights`]
+//!
+//! When a baked grid's highlights change, they're uploaded once and a
+//! compute pass writes each highlight into a texture covering the bounds of
+//! the highlighted cells.  The grid shader (with `BAKED_HIGHLIGHTS`) then
+//! reads a single texel per pixel instead of looking up a hash table.
+//!
+//! After the first bake, only the chunks of the [`crate::HexMap`] that
+//! changed are extracted & written over the existing texture, as long as
+//! every highlight still fits in it.
+//!
+//! Baking runs in its own node in the main render graph, before any camera
+//! is drawn.
+use bevy::{
+    platform::collections::HashMap,
+    prelude::*,
+    render::{
+        render_graph::{Node, NodeRunError, RenderGraphContext, RenderLabel},
+        render_resource::{
+            binding_types::{storage_buffer_read_only, texture_storage_2d},
+            BindGroup, BindGroupEntries, BindGroupLayoutDescriptor, BindGroupLayoutEntries,
+            CachedComputePipelineId, ComputePassDescriptor, ComputePipelineDescriptor, Extent3d,
+            PipelineCache, ShaderStages, ShaderType, StorageBuffer, StorageTextureAccess,
+            TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, TextureView,
+            TextureViewDescriptor,
+        },
+        renderer::{RenderContext, RenderDevice, RenderQueue},
+    },
+};
+
+use crate::{
+    highlight::HexGridHighlights, render::ExtractedHexGrid, Axial, HexBounds, HexMap, HexMapVersion,
+};
+
+const WORKGROUP_SIZE: u32 = 64;
+const TEXTURE_FORMAT: TextureFormat = TextureFormat::Rgba8Unorm;
+
+/// baked highlight state of an extracted grid
+#[derive(Debug, Default)]
+pub(crate) enum ExtractedBake {
+    /// grid doesn't bake its highlights
+    #[default]
+    None,
+    /// highlights haven't changed since they were last baked
+    Unchanged,
+    /// highlights need to be baked again
+    Changed(HexGridHighlights),
+    /// only these cells changed; cells that lost their highlight are
+    /// transparent
+    Partial(Vec<(Axial, Color)>),
+}
+
+/// Cells in the chunks of `highlights` changed since `since`, for a texture
+/// covering `bounds`.  `None` if the highlights must be baked from scratch;
+/// they're from another map, or have grown outside the texture.
+pub(crate) fn changed_cells(
+    highlights: &HexMap<Color>,
+    since: HexMapVersion,
+    bounds: HexBounds,
+) -> Option<Vec<(Axial, Color)>> {
+    let mut cells = Vec::new();
+    for chunk in highlights.changed_chunks(since)? {
+        for cell in chunk.cells() {
+            match highlights.get(cell) {
+                Some(_) if !bounds.contains(cell) => return None,
+                Some(&color) => cells.push((cell, color)),
+                None if bounds.contains(cell) => cells.push((cell, Color::NONE)),
+                None => (),
+            }
+        }
+    }
+    Some(cells)
+}
+
+#[derive(Debug, ShaderType, Clone, Copy)]
+struct GpuBakeCell {
+    cell: IVec2,
+    color: Vec4,
+}
+
+#[derive(Debug, ShaderType, Default)]
+struct GpuBake {
+    // cell stored in texel (0, 0)
+    origin: IVec2,
+    count: u32,
+    // never empty; only grids with highlights, or changes, are baked
+    #[shader(size(runtime))]
+    cells: Vec<GpuBakeCell>,
+}
+
+impl GpuBake {
+    fn new<'a>(origin: IVec2, cells: impl Iterator<Item = (Axial, &'a Color)>) -> Self {
+        let cells: Vec<_> = cells
+            .map(|(cell, color)| GpuBakeCell {
+                cell: cell.into(),
+                color: color.to_linear().to_vec4(),
+            })
+            .collect();
+        Self {
+            origin,
+            count: cells.len() as u32,
+            cells,
+        }
+    }
+}
+
+pub(crate) struct BakedHighlights {
+    /// cell stored in texel (0, 0)
+    pub origin: IVec2,
+    pub view: TextureView,
+    cells: StorageBuffer<GpuBake>,
+    /// set when the texture needs to be written
+    pending: bool,
+    /// bind group for the bake dispatch, only present on frames where the
+    /// bake runs
+    bind_group: Option<BindGroup>,
+}
+
+/// baked highlight textures, keyed by render world grid entity.  Dropped the first frame
+/// the grid isn't extracted, or stops baking.
+#[derive(Resource, Default)]
+pub(crate) struct HexGridBakes(pub HashMap<Entity, BakedHighlights>);
+
+pub(crate) fn prepare_hex_grid_bakes(
+    mut bakes: ResMut<HexGridBakes>,
+    grids: Query<(Entity, &ExtractedHexGrid)>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    bakes.0.retain(|entity, _| {
+        grids
+            .get(*entity)
+            .is_ok_and(|(_, grid)| !matches!(grid.bake, ExtractedBake::None))
+    });
+
+    for (entity, grid) in &grids {
+        let highlights = match &grid.bake {
+            ExtractedBake::Changed(highlights) => highlights,
+            ExtractedBake::Partial(cells) => {
+                // written over the existing texture
+                let Some(bake) = bakes.0.get_mut(&entity) else {
+                    continue;
+                };
+                let cells = cells.iter().map(|(cell, color)| (*cell, color));
+                bake.cells = StorageBuffer::from(GpuBake::new(bake.origin, cells));
+                bake.cells.write_buffer(&render_device, &render_queue);
+                bake.pending = true;
+                continue;
+            }
+            _ => continue,
+        };
+
+        // extraction only bakes highlights that fit in a texture
+        let Some(bounds) = highlights.0.bounds() else {
+            bakes.0.remove(&entity);
+            continue;
+        };
+        let size = bounds.size().as_uvec2();
+        let origin = IVec2::from(bounds.min);
+
+        let texture = render_device.create_texture(&TextureDescriptor {
+            label: Some("hex_grid_baked_highlights"),
+            size: Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TEXTURE_FORMAT,
+            usage: TextureUsages::STORAGE_BINDING | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&TextureViewDescriptor::default());
+
+        let mut cells = StorageBuffer::from(GpuBake::new(origin, highlights.0.iter()));
+        cells.write_buffer(&render_device, &render_queue);
+
+        bakes.0.insert(
+            entity,
+            BakedHighlights {
+                origin,
+                view,
+                cells,
+                pending: true,
+                bind_group: None,
+            },
+        );
+    }
+}
+
+pub(crate) fn queue_hex_grid_bakes(
+    mut bakes: ResMut<HexGridBakes>,
+    pipeline: Res<HexGridBakePipeline>,
+    pipeline_cache: Res<PipelineCache>,
+    render_device: Res<RenderDevice>,
+) {
+    let ready = pipeline_cache
+        .get_compute_pipeline(pipeline.pipeline)
+        .is_some();
+    let layout = pipeline_cache.get_bind_group_layout(&pipeline.layout);
+    for bake in bakes.0.values_mut() {
+        bake.bind_group = None;
+        if !bake.pending || !ready {
+            continue;
+        }
+        let Some(cells) = bake.cells.binding() else {
+            continue;
+        };
+        bake.bind_group = Some(render_device.create_bind_group(
+            "hex_grid_bake_bind_group",
+            &layout,
+            &BindGroupEntries::sequential((cells, &bake.view)),
+        ));
+        bake.pending = false;
+    }
+}
+
+#[derive(Debug, Resource)]
+pub(crate) struct HexGridBakePipeline {
+    layout: BindGroupLayoutDescriptor,
+    pipeline: CachedComputePipelineId,
+}
+
+impl FromWorld for HexGridBakePipeline {
+    fn from_world(world: &mut World) -> Self {
+        let shader = world.resource::<AssetServer>().load("hex_grid_bake.wgsl");
+
+        let layout = BindGroupLayoutDescriptor::new(
+            "hex_grid_bake_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::COMPUTE,
+                (
+                    storage_buffer_read_only::<GpuBake>(false),
+                    texture_storage_2d(TEXTURE_FORMAT, StorageTextureAccess::WriteOnly),
+                ),
+            ),
+        );
+
+        let pipeline =
+            world
+                .resource::<PipelineCache>()
+                .queue_compute_pipeline(ComputePipelineDescriptor {
+                    label: Some("hex_grid_bake_pipeline".into()),
+                    layout: vec![layout.clone()],
+                    push_constant_ranges: Vec::new(),
+                    shader,
+                    shader_defs: vec![],
+                    entry_point: Some("bake".into()),
+                    zero_initialize_workgroup_memory: false,
+                });
+
+        Self { layout, pipeline }
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+pub(crate) struct HexGridBakeLabel;
+
+/// dispatches the bakes queued this frame
+#[derive(Debug, Default)]
+pub(crate) struct HexGridBakeNode;
+
+impl Node for HexGridBakeNode {
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let bakes = world.resource::<HexGridBakes>();
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let Some(pipeline) =
+            pipeline_cache.get_compute_pipeline(world.resource::<HexGridBakePipeline>().pipeline)
+        else {
+            return Ok(());
+        };
+
+        let mut pass =
+            render_context
+                .command_encoder()
+                .begin_compute_pass(&ComputePassDescriptor {
+                    label: Some("hex_grid_bake_pass"),
+                    timestamp_writes: None,
+                });
+        pass.set_pipeline(pipeline);
+        for bake in bakes.0.values() {
+            let Some(bind_group) = &bake.bind_group else {
+                continue;
+            };
+            let count = bake.cells.get().count;
+            pass.set_bind_group(0, bind_group, &[]);
+            pass.dispatch_workgroups(count.div_ceil(WORKGROUP_SIZE), 1, 1);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+//! lines between cells of different values
+//!
+//! Add [`HexGridBorders`] to a grid entity to draw a line along each edge
+//! between two cells whose values differ in one of its layers; terrain
+//! borders, team borders, & weather fronts.  Each layer is styled on its
+//! own:
+//!
+//! ```ignore
+//! let borders = HexGridBorders::new()
+//!     .with_border(BorderLayer::Owners, BorderStyle::new(Color::WHITE, 0.08))
+//!     .with_border(BorderLayer::Terrain, BorderStyle::new(Color::BLACK, 0.03));
+//! commands.entity(grid).insert(borders);
+//! ```
+//!
+//! Borders are found in the shader from the cells of each layer already on
+//! the gpu, so they follow changes to the layer for free.  A cell without a
+//! value differs from one with a value, so the edge of a territory is drawn
+//! against unowned cells too.  Borders are drawn over the grid lines, and
+//! under cliffs; owner borders over weather, over terrain.  Use
+//! [`HexGridBorders::edges`] to find the same edges on the cpu.
+use bevy::{platform::collections::HashSet, prelude::*};
+
+use crate::{HexDirection, HexEdge, HexMap};
+
+/// layer of cell values compared across each edge
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BorderLayer {
+    /// [`crate::HexGridTerrain`] kinds
+    Terrain,
+    /// [`crate::HexGridWeather`] kinds
+    Weather,
+    /// [`crate::HexGridOwners`] teams
+    Owners,
+}
+
+impl BorderLayer {
+    /// every layer, in the order they're drawn
+    pub const ALL: [BorderLayer; 3] = [Self::Terrain, Self::Weather, Self::Owners];
+}
+
+/// how the borders of a layer are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BorderStyle {
+    pub color: Color,
+    /// width of the line, as a fraction of the cell size; half of it is
+    /// drawn on each side of the edge
+    pub width: f32,
+}
+
+impl BorderStyle {
+    pub fn new(color: Color, width: f32) -> Self {
+        Self { color, width }
+    }
+}
+
+impl Default for BorderStyle {
+    fn default() -> Self {
+        Self::new(Color::srgb(0.1, 0.1, 0.1), 0.06)
+    }
+}
+
+/// Borders drawn on a grid entity; see the [module docs](self).  Layers
+/// without a style have no borders.
+#[derive(Component, Debug, Default, Clone, PartialEq, Reflect)]
+#[reflect(Component)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HexGridBorders {
+    pub terrain: Option<BorderStyle>,
+    pub weather: Option<BorderStyle>,
+    pub owners: Option<BorderStyle>,
+}
+
+impl HexGridBorders {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_border(mut self, layer: BorderLayer, style: BorderStyle) -> Self {
+        *self.style_mut(layer) = Some(style);
+        self
+    }
+
+    pub fn style(&self, layer: BorderLayer) -> Option<BorderStyle> {
+        match layer {
+            BorderLayer::Terrain => self.terrain,
+            BorderLayer::Weather => self.weather,
+            BorderLayer::Owners => self.owners,
+        }
+    }
+
+    pub fn style_mut(&mut self, layer: BorderLayer) -> &mut Option<BorderStyle> {
+        match layer {
+            BorderLayer::Terrain => &mut self.terrain,
+            BorderLayer::Weather => &mut self.weather,
+            BorderLayer::Owners => &mut self.owners,
+        }
+    }
+
+    /// true if any layer has borders
+    pub fn is_drawn(&self) -> bool {
+        BorderLayer::ALL
+            .into_iter()
+            .any(|layer| self.style(layer).is_some_and(|s| s.width > 0.0))
+    }
+
+    /// Every edge of a cell in `values` whose neighbor across it has a
+    /// different value, or none; the edges drawn for a layer with these
+    /// values.  Mirrors `border_color()` in the grid shader.
+    pub fn edges<T: PartialEq>(values: &HexMap<T>) -> HashSet<HexEdge> {
+        let mut edges = HashSet::new();
+        for (cell, value) in values.iter() {
+            for dir in HexDirection::ALL {
+                if values.get(cell + dir) != Some(value) {
+                    edges.insert(HexEdge::new(cell, dir));
+                }
+            }
+        }
+        edges
+    }
+}
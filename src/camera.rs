@@ -0,0 +1,238 @@
+//! orbit/pan camera controller tuned for board-game views
+//!
+//! Add [`HexGridCameraPlugin`] to the app and a [`HexGridCamera`] component
+//! to a 3d camera.  The camera orbits a focus point on the grid plane, and
+//! supports:
+//! - panning by dragging the grid, or moving the cursor to the window edge
+//! - rotating while the rotate button is held, with clamped pitch & optional
+//!   yaw limits
+//! - clamped zoom with the mouse wheel; this scales orthographic projections
+//!   and moves perspective cameras along the arm
+use bevy::{
+    camera::{NormalizedRenderTarget, RenderTarget},
+    input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel},
+    prelude::*,
+    window::PrimaryWindow,
+};
+
+use crate::{cursor_ray, HexGridPlane};
+
+pub struct HexGridCameraPlugin;
+
+impl Plugin for HexGridCameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<HexGridCamera>().add_systems(
+            Update,
+            (update_camera_controls, update_camera_transform).chain(),
+        );
+    }
+}
+
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HexGridCamera {
+    /// point on the grid plane the camera orbits around
+    pub focus: Vec3,
+    /// rotation around the Y axis, degrees
+    pub yaw: f32,
+    /// angle above the grid plane, degrees; negative looks down
+    pub pitch: f32,
+    /// distance from the focus point at zoom 1.0
+    pub distance: f32,
+    pub zoom: f32,
+    pub zoom_limits: (f32, f32),
+    /// pitch is always clamped; defaults keep the camera looking down
+    pub pitch_limits: (f32, f32),
+    /// optional (min, max) yaw, degrees
+    pub yaw_limits: Option<(f32, f32)>,
+    /// button that pans the camera by dragging the grid
+    pub drag_button: Option<MouseButton>,
+    pub rotate_button: Option<MouseButton>,
+    /// degrees of rotation per pixel of mouse motion
+    pub rotate_sensitivity: f32,
+    /// zoom change per line of mouse wheel scroll; see
+    /// [`HexGridCamera::zoom_by`]
+    pub zoom_sensitivity: f32,
+    /// distance in pixels from the window edge that starts edge scrolling;
+    /// set to zero to disable edge scrolling
+    pub edge_scroll_margin: f32,
+    /// edge scroll speed in world units per second at zoom 1.0
+    pub edge_scroll_speed: f32,
+    /// time in seconds to ease towards the target position; zero disables
+    /// smoothing
+    pub smoothing: f32,
+}
+
+impl Default for HexGridCamera {
+    fn default() -> Self {
+        Self {
+            focus: Vec3::ZERO,
+            yaw: 45.0,
+            pitch: -30.0,
+            distance: 100.0,
+            zoom: 1.0,
+            zoom_limits: (0.1, 10.0),
+            pitch_limits: (-89.0, -15.0),
+            yaw_limits: None,
+            drag_button: Some(MouseButton::Middle),
+            rotate_button: Some(MouseButton::Right),
+            rotate_sensitivity: 0.25,
+            zoom_sensitivity: 0.1,
+            edge_scroll_margin: 8.0,
+            edge_scroll_speed: 10.0,
+            smoothing: 0.1,
+        }
+    }
+}
+
+impl HexGridCamera {
+    pub fn rotation(&self) -> Quat {
+        Quat::from_euler(
+            EulerRot::YXZ,
+            self.yaw.to_radians(),
+            self.pitch.to_radians(),
+            0.0,
+        )
+    }
+
+    /// camera transform for the current focus, rotation, and zoom
+    pub fn target_transform(&self, projection: &Projection) -> Transform {
+        let distance = match projection {
+            // zoom is applied by the projection scale
+            Projection::Orthographic(_) => self.distance,
+            Projection::Perspective(_) | Projection::Custom(_) => self.distance * self.zoom,
+        };
+        let rotation = self.rotation();
+        Transform {
+            translation: self.focus + rotation * Vec3::Z * distance,
+            rotation,
+            ..default()
+        }
+    }
+
+    /// Zoom in by `lines` of mouse wheel scroll, or out for negative lines.
+    /// Each line scales the zoom by the same factor, so it stays positive
+    /// however far a single frame scrolls.
+    pub fn zoom_by(&mut self, lines: f32) {
+        self.zoom *= (-lines * self.zoom_sensitivity).exp();
+        self.zoom = self.zoom.clamp(self.zoom_limits.0, self.zoom_limits.1);
+    }
+
+    fn clamp(&mut self) {
+        self.zoom = self.zoom.clamp(self.zoom_limits.0, self.zoom_limits.1);
+        self.pitch = self.pitch.clamp(self.pitch_limits.0, self.pitch_limits.1);
+        self.yaw = match self.yaw_limits {
+            Some((min, max)) => self.yaw.clamp(min, max),
+            None => self.yaw.rem_euclid(360.0),
+        };
+    }
+}
+
+fn update_camera_controls(
+    windows: Query<&Window>,
+    primary_window: Query<Entity, With<PrimaryWindow>>,
+    mut cameras: Query<(&Camera, &RenderTarget, &GlobalTransform, &mut HexGridCamera)>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    mut motion: MessageReader<MouseMotion>,
+    mut wheel: MessageReader<MouseWheel>,
+    time: Res<Time>,
+) {
+    let primary_window = primary_window.single().ok();
+    let motion: Vec2 = motion.read().map(|m| m.delta).sum();
+    let scroll: f32 = wheel
+        .read()
+        .map(|w| match w.unit {
+            MouseScrollUnit::Line => w.y,
+            MouseScrollUnit::Pixel => w.y / 16.0,
+        })
+        .sum();
+
+    for (camera, target, global_transform, mut controller) in &mut cameras {
+        // the cursor of the window this camera draws into
+        let Some(NormalizedRenderTarget::Window(window)) = target.normalize(primary_window) else {
+            continue;
+        };
+        let Ok(window) = windows.get(window.entity()) else {
+            continue;
+        };
+        let cursor = window.cursor_position();
+
+        // drag the grid under the cursor
+        if let (Some(button), Some(cursor)) = (controller.drag_button, cursor) {
+            if buttons.pressed(button) && motion != Vec2::ZERO {
+                // the camera focus is always on the world Y = 0 plane
+                let plane = HexGridPlane::default();
+                let now = cursor_ray(camera, global_transform, cursor)
+                    .and_then(|ray| plane.intersect(ray));
+                let prev = cursor_ray(camera, global_transform, cursor - motion)
+                    .and_then(|ray| plane.intersect(ray));
+                if let (Some(now), Some(prev)) = (now, prev) {
+                    let delta = prev - now;
+                    controller.focus += Vec3::new(delta.x, 0.0, delta.y);
+                }
+            }
+        }
+
+        if let Some(button) = controller.rotate_button {
+            if buttons.pressed(button) && motion != Vec2::ZERO {
+                let sensitivity = controller.rotate_sensitivity;
+                controller.yaw -= motion.x * sensitivity;
+                controller.pitch -= motion.y * sensitivity;
+            }
+        }
+
+        if scroll != 0.0 {
+            controller.zoom_by(scroll);
+        }
+
+        // edge scrolling, along the camera yaw so "up" is always away from
+        // the viewer
+        if let Some(cursor) = cursor {
+            let margin = controller.edge_scroll_margin;
+            if margin > 0.0 {
+                let mut dir = Vec2::ZERO;
+                if cursor.x < margin {
+                    dir.x -= 1.0;
+                } else if cursor.x > window.width() - margin {
+                    dir.x += 1.0;
+                }
+                if cursor.y < margin {
+                    dir.y -= 1.0;
+                } else if cursor.y > window.height() - margin {
+                    dir.y += 1.0;
+                }
+                if dir != Vec2::ZERO {
+                    let yaw = Quat::from_rotation_y(controller.yaw.to_radians());
+                    let step = controller.edge_scroll_speed * controller.zoom * time.delta_secs();
+                    let offset = yaw * Vec3::new(dir.x, 0.0, dir.y);
+                    controller.focus += offset.normalize() * step;
+                }
+            }
+        }
+
+        controller.clamp();
+    }
+}
+
+fn update_camera_transform(
+    mut cameras: Query<(&HexGridCamera, &mut Transform, &mut Projection)>,
+    time: Res<Time>,
+) {
+    for (controller, mut transform, mut projection) in &mut cameras {
+        let target = controller.target_transform(&projection);
+
+        let t = if controller.smoothing > 0.0 {
+            1.0 - (-time.delta_secs() / controller.smoothing).exp()
+        } else {
+            1.0
+        };
+
+        transform.translation = transform.translation.lerp(target.translation, t);
+        transform.rotation = transform.rotation.slerp(target.rotation, t);
+
+        if let Projection::Orthographic(ortho) = projection.as_mut() {
+            ortho.scale += (controller.zoom - ortho.scale) * t;
+        }
+    }
+}
@@ -0,0 +1,155 @@
+//! entities for cells, spawned on demand
+//!
+//! Cell state can be kept in ordinary components, on an entity per cell,
+//! without spawning an entity for every cell of a large map: an entity is
+//! only spawned for a cell when something is attached to it.  Use
+//! [`HexCells::entity`] to get or spawn the entity for a cell:
+//!
+//! ```ignore
+//! fn poison(mut cells: HexCells, target: Res<Target>) {
+//!     cells.entity(target.grid, target.cell).insert(Poisoned(3));
+//! }
+//! ```
+//!
+//! Every entity with a [`HexCell`] is kept in the [`HexCellIndex`] resource,
+//! however it was spawned, so cells can be looked up by coordinate, and
+//! coordinates by entity.  Cell entities spawned by [`HexCells`] are children
+//! of their grid, and are despawned with it.
+use bevy::{
+    ecs::{lifecycle::HookContext, system::SystemParam, world::DeferredWorld},
+    platform::collections::HashMap,
+    prelude::*,
+};
+
+use crate::{Axial, HexMap};
+
+/// Cell an entity stands for.  Immutable; insert a new one to move the
+/// entity to another cell.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Component)]
+#[component(immutable, on_insert = index_cell, on_replace = unindex_cell)]
+pub struct HexCell {
+    pub grid: Entity,
+    pub cell: Axial,
+}
+
+/// Entity of each cell that has one, and the cell of each such entity.
+/// Maintained by [`HexCell`]'s hooks.
+#[derive(Resource, Debug, Default)]
+pub struct HexCellIndex {
+    grids: HashMap<Entity, HexMap<Entity>>,
+    cells: HashMap<Entity, HexCell>,
+}
+
+impl HexCellIndex {
+    /// entity for a cell, if one has been spawned
+    pub fn get(&self, grid: Entity, cell: Axial) -> Option<Entity> {
+        self.grids.get(&grid)?.get(cell).copied()
+    }
+
+    /// cell an entity stands for
+    pub fn cell(&self, entity: Entity) -> Option<HexCell> {
+        self.cells.get(&entity).copied()
+    }
+
+    /// cells of a grid that have an entity
+    pub fn grid(&self, grid: Entity) -> Option<&HexMap<Entity>> {
+        self.grids.get(&grid)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    fn insert(&mut self, entity: Entity, cell: HexCell) {
+        let prev = self
+            .grids
+            .entry(cell.grid)
+            .or_default()
+            .insert(cell.cell, entity);
+        if let Some(prev) = prev.filter(|&e| e != entity) {
+            warn!(
+                "{:?} replaces {:?} as the entity for cell {:?} on grid {:?}",
+                entity, prev, cell.cell, cell.grid
+            );
+            self.cells.remove(&prev);
+        }
+        self.cells.insert(entity, cell);
+    }
+
+    fn remove(&mut self, entity: Entity) {
+        let Some(cell) = self.cells.remove(&entity) else {
+            return;
+        };
+        let Some(map) = self.grids.get_mut(&cell.grid) else {
+            return;
+        };
+        if map.get(cell.cell) == Some(&entity) {
+            map.remove(cell.cell);
+        }
+        if map.is_empty() {
+            self.grids.remove(&cell.grid);
+        }
+    }
+}
+
+fn index_cell(mut world: DeferredWorld, context: HookContext) {
+    let Some(&cell) = world.get::<HexCell>(context.entity) else {
+        return;
+    };
+    if let Some(mut index) = world.get_resource_mut::<HexCellIndex>() {
+        index.insert(context.entity, cell);
+    }
+}
+
+fn unindex_cell(mut world: DeferredWorld, context: HookContext) {
+    if let Some(mut index) = world.get_resource_mut::<HexCellIndex>() {
+        index.remove(context.entity);
+    }
+}
+
+/// Look up cell entities, spawning them as needed.
+#[derive(SystemParam)]
+pub struct HexCells<'w, 's> {
+    commands: Commands<'w, 's>,
+    index: ResMut<'w, HexCellIndex>,
+}
+
+impl<'w, 's> HexCells<'w, 's> {
+    /// entity for a cell, if one has been spawned
+    pub fn get(&self, grid: Entity, cell: Axial) -> Option<Entity> {
+        self.index.get(grid, cell)
+    }
+
+    /// cell an entity stands for
+    pub fn cell(&self, entity: Entity) -> Option<HexCell> {
+        self.index.cell(entity)
+    }
+
+    /// Commands for the entity of a cell, spawning it if there isn't one.
+    /// Calling this again for the same cell in the same system returns the
+    /// same entity.
+    pub fn entity(&mut self, grid: Entity, cell: Axial) -> EntityCommands<'_> {
+        if let Some(entity) = self.index.get(grid, cell) {
+            return self.commands.entity(entity);
+        }
+        let hex_cell = HexCell { grid, cell };
+        let entity = self.commands.spawn((hex_cell, ChildOf(grid))).id();
+        // indexed now, not when the commands are applied, so the next lookup
+        // finds it
+        self.index.insert(entity, hex_cell);
+        self.commands.entity(entity)
+    }
+
+    /// despawn the entity of a cell, if it has one
+    pub fn despawn(&mut self, grid: Entity, cell: Axial) {
+        if let Some(entity) = self.index.get(grid, cell) {
+            self.index.remove(entity);
+            self.commands.entity(entity).despawn();
+        }
+    }
+}
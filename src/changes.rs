@@ -0,0 +1,187 @@
+//! messages for changed cells
+//!
+//! Add [`HexMapChangesPlugin`] to the app to send a [`CellChanged`] for each
+//! cell changed in a [`HexMap`] on a grid entity, once per frame, so
+//! rendering, minimaps, AI caches, & networking can update just those cells
+//! instead of comparing whole maps each frame:
+//!
+//! ```ignore
+//! fn resync_terrain(mut changed: MessageReader<CellChanged>) {
+//!     for changed in changed.read().filter(|c| c.is::<HexGridTerrain>()) {
+//!         send_cell(changed.grid, changed.cell);
+//!     }
+//! }
+//! ```
+//!
+//! Each layer of cells, such as [`HexGridTerrain`], is a component holding
+//! a map, found through [`HexMapLayer`].  The maps of the built-in layers
+//! are watched; others can be added with [`HexMapChangesPlugin::with_layer`].
+//! A map starts logging its changes with [`HexMap::track_changes`] when the
+//! plugin first sees it, and each of its cells is sent then.  Replacing a
+//! whole map sends each cell of the new map, but not those only in the old.
+use bevy::{ecs::component::Mutable, prelude::*};
+
+use crate::{
+    drag::HexGridMoveCosts, Axial, Decal, FillId, HexGridDecals, HexGridElevation, HexGridFills,
+    HexGridHeatmap, HexGridHighlights, HexGridTerrain, HexGridWeather, HexMap, TerrainId,
+    WeatherId,
+};
+
+/// Sends a [`CellChanged`] for each changed cell of the built-in layers, &
+/// any added with [`HexMapChangesPlugin::with_layer`].
+pub struct HexMapChangesPlugin {
+    layers: Vec<fn(&mut App)>,
+}
+
+impl Default for HexMapChangesPlugin {
+    fn default() -> Self {
+        Self {
+            layers: vec![
+                add_layer::<HexGridTerrain>,
+                add_layer::<HexGridElevation>,
+                add_layer::<HexGridHighlights>,
+                add_layer::<HexGridDecals>,
+                add_layer::<HexGridMoveCosts>,
+                add_layer::<HexGridHeatmap>,
+                add_layer::<HexGridWeather>,
+                add_layer::<HexGridFills>,
+            ],
+        }
+    }
+}
+
+impl HexMapChangesPlugin {
+    /// also send changes to the cells of the `L` layer
+    pub fn with_layer<L: HexMapLayer>(mut self) -> Self {
+        self.layers.push(add_layer::<L>);
+        self
+    }
+}
+
+impl Plugin for HexMapChangesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<CellChanged>();
+        for add in &self.layers {
+            add(app);
+        }
+    }
+}
+
+fn add_layer<L: HexMapLayer>(app: &mut App) {
+    app.add_systems(PostUpdate, send_cell_changes::<L>);
+}
+
+/// Component holding a map of values for the cells of a grid entity.
+pub trait HexMapLayer: Component<Mutability = Mutable> {
+    type Value;
+
+    /// name of the layer in [`CellChanged::layer`]
+    const NAME: &'static str;
+
+    fn map_mut(&mut self) -> &mut HexMap<Self::Value>;
+}
+
+/// sent in `PostUpdate` for each cell of a layer that changed since the last
+/// frame
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellChanged {
+    pub grid: Entity,
+    pub cell: Axial,
+    /// [`HexMapLayer::NAME`] of the changed layer
+    pub layer: &'static str,
+}
+
+impl CellChanged {
+    /// true if the change is to the cells of the `L` layer
+    pub fn is<L: HexMapLayer>(&self) -> bool {
+        self.layer == L::NAME
+    }
+}
+
+fn send_cell_changes<L: HexMapLayer>(
+    mut layers: Query<(Entity, &mut L), Changed<L>>,
+    mut changed: MessageWriter<CellChanged>,
+) {
+    for (grid, mut layer) in &mut layers {
+        // draining the log is no change to the layer
+        let map = layer.bypass_change_detection().map_mut();
+        map.track_changes();
+        changed.write_batch(map.drain_changes().map(|cell| CellChanged {
+            grid,
+            cell,
+            layer: L::NAME,
+        }));
+    }
+}
+
+impl HexMapLayer for HexGridTerrain {
+    type Value = TerrainId;
+    const NAME: &'static str = "terrain";
+
+    fn map_mut(&mut self) -> &mut HexMap<Self::Value> {
+        &mut self.cells
+    }
+}
+
+impl HexMapLayer for HexGridElevation {
+    type Value = f32;
+    const NAME: &'static str = "elevation";
+
+    fn map_mut(&mut self) -> &mut HexMap<Self::Value> {
+        &mut self.cells
+    }
+}
+
+impl HexMapLayer for HexGridHighlights {
+    type Value = Color;
+    const NAME: &'static str = "highlights";
+
+    fn map_mut(&mut self) -> &mut HexMap<Self::Value> {
+        &mut self.0
+    }
+}
+
+impl HexMapLayer for HexGridDecals {
+    type Value = Decal;
+    const NAME: &'static str = "decals";
+
+    fn map_mut(&mut self) -> &mut HexMap<Self::Value> {
+        &mut self.0
+    }
+}
+
+impl HexMapLayer for HexGridMoveCosts {
+    type Value = u32;
+    const NAME: &'static str = "move_costs";
+
+    fn map_mut(&mut self) -> &mut HexMap<Self::Value> {
+        &mut self.0
+    }
+}
+
+impl HexMapLayer for HexGridHeatmap {
+    type Value = f32;
+    const NAME: &'static str = "heatmap";
+
+    fn map_mut(&mut self) -> &mut HexMap<Self::Value> {
+        &mut self.values
+    }
+}
+
+impl HexMapLayer for HexGridWeather {
+    type Value = WeatherId;
+    const NAME: &'static str = "weather";
+
+    fn map_mut(&mut self) -> &mut HexMap<Self::Value> {
+        &mut self.cells
+    }
+}
+
+impl HexMapLayer for HexGridFills {
+    type Value = FillId;
+    const NAME: &'static str = "fills";
+
+    fn map_mut(&mut self) -> &mut HexMap<Self::Value> {
+        &mut self.cells
+    }
+}
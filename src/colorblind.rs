@@ -0,0 +1,95 @@
+//! colorblind-safe team colors
+//!
+//! Set [`HexGridConfig::colorblind`] to a [`ColorblindPreset`] to draw the
+//! [`HexGridPalette`] teams of a grid in the Okabe-Ito colors, which stay
+//! apart for the common kinds of color blindness.  With
+//! [`ColorblindPreset::OkabeItoPatterns`], each team's cells are also filled
+//! with a pattern of their own, as with [`crate::HexGridFills`], so teams can
+//! be told apart without color at all:
+//!
+//! ```ignore
+//! fn toggle_colorblind(mut grids: Query<&mut HexGridConfig>) {
+//!     for mut config in &mut grids {
+//!         config.colorblind = match config.colorblind {
+//!             None => Some(ColorblindPreset::OkabeItoPatterns),
+//!             Some(_) => None,
+//!         };
+//!     }
+//! }
+//! ```
+//!
+//! The preset is applied as the grid is drawn, so it can be switched at any
+//! time, and the palette on the grid keeps its own colors.  There are eight
+//! colors & patterns; teams past the eighth reuse them in order.
+//!
+//! [`HexGridConfig::colorblind`]: crate::HexGridConfig::colorblind
+use bevy::prelude::*;
+
+use crate::{CellFill, FillPattern, HexGridPalette};
+
+/// The Okabe-Ito palette, in its usual order: orange, sky blue, bluish
+/// green, yellow, blue, vermillion, reddish purple, & black.
+pub const OKABE_ITO: [Color; 8] = [
+    Color::srgb(0.902, 0.624, 0.0),
+    Color::srgb(0.337, 0.706, 0.914),
+    Color::srgb(0.0, 0.620, 0.451),
+    Color::srgb(0.941, 0.894, 0.259),
+    Color::srgb(0.0, 0.447, 0.698),
+    Color::srgb(0.835, 0.369, 0.0),
+    Color::srgb(0.8, 0.475, 0.655),
+    Color::srgb(0.0, 0.0, 0.0),
+];
+
+/// accessible look for the teams of a grid; see the [module docs](self)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColorblindPreset {
+    /// team colors from [`OKABE_ITO`]
+    OkabeIto,
+    /// team colors from [`OKABE_ITO`], & a pattern over each team's cells
+    OkabeItoPatterns,
+}
+
+impl ColorblindPreset {
+    /// color drawn for `team`, keeping the alpha of its palette `color`
+    pub fn team_color(self, team: usize, color: Color) -> Color {
+        OKABE_ITO[team % OKABE_ITO.len()].with_alpha(color.alpha())
+    }
+
+    /// `palette` with each team's color replaced
+    pub fn palette(self, palette: &HexGridPalette) -> HexGridPalette {
+        HexGridPalette {
+            colors: (palette.colors.iter().enumerate())
+                .map(|(team, &color)| self.team_color(team, color))
+                .collect(),
+            ..palette.clone()
+        }
+    }
+
+    /// pattern drawn over the cells of `team`, if the preset has patterns
+    pub fn team_fill(self, team: usize) -> Option<CellFill> {
+        if self == Self::OkabeIto {
+            return None;
+        }
+        let (pattern, angle) = match team % OKABE_ITO.len() {
+            0 => (FillPattern::Hatch, 45.0),
+            1 => (FillPattern::Hatch, -45.0),
+            2 => (FillPattern::Stripes, 0.0),
+            3 => (FillPattern::Crosshatch, 45.0),
+            4 => (FillPattern::Dots, 45.0),
+            5 => (FillPattern::Stripes, 90.0),
+            6 => (FillPattern::Crosshatch, 0.0),
+            _ => (FillPattern::Dots, 0.0),
+        };
+        // light patterns over the black team, dark over the rest
+        let color = match team % OKABE_ITO.len() {
+            7 => Color::srgba(1.0, 1.0, 1.0, 0.4),
+            _ => Color::srgba(0.0, 0.0, 0.0, 0.35),
+        };
+        Some(
+            CellFill::new(pattern)
+                .with_angle(f32::to_radians(angle))
+                .with_color(color),
+        )
+    }
+}
@@ -0,0 +1,280 @@
+//! replicated board commands
+//!
+//! Networked games stay in sync by sending every change to the board as a
+//! [`HexCommand`], and applying the same commands in the same order on each
+//! peer.  A [`HexBoard`] holds the state the commands change; applying a
+//! command only depends on the board & the command, so every peer ends up
+//! with the same board.  With the `serde` feature, commands & boards can be
+//! encoded with [`HexCommand::to_bytes`] & [`HexBoard::to_bytes`], for
+//! sending commands and catching up late joiners.
+//!
+//! Send a [`HexGridCommand`] to apply a command to the [`HexBoard`] on a
+//! grid entity:
+//!
+//! ```ignore
+//! for command in connection.received::<HexCommand>() {
+//!     commands.write(HexGridCommand { grid, command });
+//! }
+//! ```
+//!
+//! Each command that applies is mirrored onto the grid's
+//! [`HexGridOwners`], [`HexGridHighlights`], and [`HexGridMinimap`] fog when
+//! it has them, and onto the [`HexPosition`] of the entity with the matching
+//! [`HexUnit`], then sent on as a [`HexCommandApplied`].  Commands that
+//! don't apply, such as moving a unit that doesn't exist, change nothing and
+//! are dropped.
+//!
+//! [`crate::HexGridPlugin`] adds [`HexGridCommandPlugin`]; add it on its own
+//! to apply commands & play replays without rendering, such as on a
+//! dedicated server.
+use std::collections::BTreeMap;
+
+use bevy::{platform::collections::HashMap, prelude::*};
+
+use crate::{
+    replay, time, Axial, CellOwner, GridTime, HexGridHighlights, HexGridMinimap, HexGridOwners,
+    HexGridSelected, HexMap, HexPosition,
+};
+
+/// applies [`HexGridCommand`]s, and records & plays back replays
+pub struct HexGridCommandPlugin;
+
+impl Plugin for HexGridCommandPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<HexUnit>()
+            .register_type::<GridTime>()
+            .init_resource::<GridTime>()
+            .add_message::<HexGridCommand>()
+            .add_message::<HexCommandApplied>()
+            .add_message::<replay::ReplayEventPlayed>()
+            .add_message::<HexGridSelected>()
+            .add_systems(PreUpdate, time::update_grid_time)
+            .add_systems(
+                Update,
+                (
+                    replay::play_replays,
+                    apply_hex_commands,
+                    replay::record_grid_events,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// id of a unit on a [`HexBoard`], the same on every peer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnitId(pub u32);
+
+/// Entity showing a unit of a [`HexBoard`].  Its [`HexPosition`] is set as
+/// [`HexCommand`]s move the unit.  Ids are matched across all grids, so
+/// they must be unique between boards.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Component)]
+pub struct HexUnit(pub UnitId);
+
+/// change to a [`HexBoard`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HexCommand {
+    /// place a new unit; doesn't apply if the id is taken
+    SpawnUnit {
+        unit: UnitId,
+        cell: Axial,
+    },
+    /// move a unit to another cell
+    MoveUnit {
+        unit: UnitId,
+        to: Axial,
+    },
+    RemoveUnit {
+        unit: UnitId,
+    },
+    /// set the owner of a cell, or make it unowned
+    ClaimCell {
+        cell: Axial,
+        owner: Option<CellOwner>,
+    },
+    /// set the highlight of a cell, or clear it
+    Highlight {
+        cell: Axial,
+        color: Option<Color>,
+    },
+    /// reveal the cells within `radius` steps of `center`
+    RevealFog {
+        center: Axial,
+        radius: u32,
+    },
+}
+
+/// Board state changed by [`HexCommand`]s.
+#[derive(Component, Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HexBoard {
+    /// cell of each unit, in id order
+    pub units: BTreeMap<UnitId, Axial>,
+    pub owners: HexMap<CellOwner>,
+    pub highlights: HexMap<Color>,
+    /// cells revealed from under the fog
+    pub revealed: HexMap<()>,
+}
+
+impl HexBoard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply a command, returning false, and changing nothing, if it
+    /// doesn't apply.
+    pub fn apply(&mut self, command: &HexCommand) -> bool {
+        match *command {
+            HexCommand::SpawnUnit { unit, cell } => {
+                if self.units.contains_key(&unit) {
+                    return false;
+                }
+                self.units.insert(unit, cell);
+            }
+            HexCommand::MoveUnit { unit, to } => match self.units.get_mut(&unit) {
+                Some(cell) => *cell = to,
+                None => return false,
+            },
+            HexCommand::RemoveUnit { unit } => return self.units.remove(&unit).is_some(),
+            HexCommand::ClaimCell { cell, owner } => {
+                match owner {
+                    Some(owner) => self.owners.insert(cell, owner),
+                    None => self.owners.remove(cell),
+                };
+            }
+            HexCommand::Highlight { cell, color } => {
+                match color {
+                    Some(color) => self.highlights.insert(cell, color),
+                    None => self.highlights.remove(cell),
+                };
+            }
+            HexCommand::RevealFog { center, radius } => {
+                self.revealed
+                    .extend(center.range(radius).map(|cell| (cell, ())));
+            }
+        }
+        true
+    }
+
+    /// cell a unit stands on
+    pub fn unit(&self, unit: UnitId) -> Option<Axial> {
+        self.units.get(&unit).copied()
+    }
+
+    /// units standing on a cell, in id order
+    pub fn units_on(&self, cell: Axial) -> impl Iterator<Item = UnitId> + '_ {
+        self.units
+            .iter()
+            .filter(move |(_, &c)| c == cell)
+            .map(|(&unit, _)| unit)
+    }
+
+    pub fn is_revealed(&self, cell: Axial) -> bool {
+        self.revealed.contains(cell)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl HexCommand {
+    /// encode the command into a compact binary format
+    pub fn to_bytes(&self) -> bincode::Result<Vec<u8>> {
+        bincode::serialize(self)
+    }
+
+    /// decode a command written by [`HexCommand::to_bytes`]
+    pub fn from_bytes(bytes: &[u8]) -> bincode::Result<Self> {
+        bincode::deserialize(bytes)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl HexBoard {
+    /// encode the board into a compact binary format
+    pub fn to_bytes(&self) -> bincode::Result<Vec<u8>> {
+        bincode::serialize(self)
+    }
+
+    /// decode a board written by [`HexBoard::to_bytes`]
+    pub fn from_bytes(bytes: &[u8]) -> bincode::Result<Self> {
+        bincode::deserialize(bytes)
+    }
+}
+
+/// send to apply a command to the [`HexBoard`] on a grid entity
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct HexGridCommand {
+    pub grid: Entity,
+    pub command: HexCommand,
+}
+
+/// sent for each [`HexGridCommand`] that applied
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct HexCommandApplied {
+    pub grid: Entity,
+    pub command: HexCommand,
+}
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn apply_hex_commands(
+    mut commands: Commands,
+    mut received: MessageReader<HexGridCommand>,
+    mut applied: MessageWriter<HexCommandApplied>,
+    mut grids: Query<(
+        &mut HexBoard,
+        Option<&mut HexGridOwners>,
+        Option<&mut HexGridHighlights>,
+        Option<&mut HexGridMinimap>,
+    )>,
+    units: Query<(Entity, &HexUnit)>,
+) {
+    // built on the first unit command
+    let mut unit_entities: Option<HashMap<UnitId, Entity>> = None;
+
+    for &HexGridCommand { grid, command } in received.read() {
+        let Ok((mut board, owners, highlights, minimap)) = grids.get_mut(grid) else {
+            warn!("HexGridCommand for {:?}, which has no HexBoard", grid);
+            continue;
+        };
+        if !board.apply(&command) {
+            continue;
+        }
+
+        match command {
+            HexCommand::SpawnUnit { unit, cell } | HexCommand::MoveUnit { unit, to: cell } => {
+                let entities = unit_entities
+                    .get_or_insert_with(|| units.iter().map(|(e, u)| (u.0, e)).collect());
+                if let Some(&entity) = entities.get(&unit) {
+                    commands.entity(entity).insert(HexPosition { grid, cell });
+                }
+            }
+            HexCommand::RemoveUnit { .. } => (),
+            HexCommand::ClaimCell { cell, owner } => {
+                if let Some(mut owners) = owners {
+                    match owner {
+                        Some(owner) => owners.set(cell, owner),
+                        None => owners.remove(cell),
+                    };
+                }
+            }
+            HexCommand::Highlight { cell, color } => {
+                if let Some(mut highlights) = highlights {
+                    match color {
+                        Some(color) => highlights.0.insert(cell, color),
+                        None => highlights.0.remove(cell),
+                    };
+                }
+            }
+            HexCommand::RevealFog { center, radius } => {
+                if let Some(mut minimap) = minimap {
+                    for cell in center.range(radius) {
+                        minimap.fog.remove(cell);
+                    }
+                }
+            }
+        }
+        applied.write(HexCommandApplied { grid, command });
+    }
+}
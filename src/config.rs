@@ -0,0 +1,351 @@
+//! grid appearance & layout configuration
+use std::f32::consts::{FRAC_PI_3, FRAC_PI_6, SQRT_2};
+
+use bevy::{math::Vec2Swizzles, prelude::*, render::sync_world::SyncToRenderWorld};
+
+use crate::{
+    colorblind::ColorblindPreset, coords::SQRT_3, cursor_ray, mask::HexGridStencil,
+    theme::HexGridTheme, wrap::HexWrap, Axial, HexGridPlane,
+};
+
+/// which way the hexes point along the world Z axis
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Orientation {
+    /// corners point along Z; rows of hexes run along X
+    #[default]
+    Pointy,
+    /// flat edges face Z; columns of hexes run along Z
+    Flat,
+}
+
+/// Shape of the grid cells.  Every kind uses the same render pipeline,
+/// specialized with shader defs.
+///
+/// Non-hex kinds still use [`Axial`] for cell coordinates:
+/// - `Square` & `Isometric`: `q` & `r` are the column & row
+/// - `Triangle`: `r` is the row, and `q` counts triangles along the row,
+///   alternating between pointing up & down
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GridKind {
+    #[default]
+    Hex,
+    Square,
+    /// equilateral triangles
+    Triangle,
+    /// square cells rotated 45 degrees
+    Isometric,
+}
+
+impl GridKind {
+    pub const ALL: [GridKind; 4] = [
+        GridKind::Hex,
+        GridKind::Square,
+        GridKind::Triangle,
+        GridKind::Isometric,
+    ];
+}
+
+/// How grid lines are antialiased.  Like [`GridKind`], this selects a
+/// pipeline variant.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LineAntialiasing {
+    /// hard edged lines
+    Off,
+    /// lines fade out from the cell edge; cheap, but shimmers at grazing
+    /// angles
+    #[default]
+    Smoothstep,
+    /// solid lines with edges smoothed over one pixel using screen-space
+    /// derivatives
+    Fwidth,
+    /// `Fwidth`, averaged over two samples per pixel
+    Supersample,
+}
+
+/// Units of the grid line width.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LineWidthMode {
+    /// [`HexGridConfig::line_width`] is a fraction of the cell size, so lines
+    /// scale with the cells
+    #[default]
+    CellFraction,
+    /// lines are this many world units wide, whatever the cell size or the
+    /// scale of the grid entity
+    World(f32),
+    /// lines are this many logical pixels wide at any zoom level; scaled by
+    /// the scale factor of the camera's window & the [`UiScale`], so they
+    /// look the same on hi-dpi displays
+    Pixels(f32),
+}
+
+/// Outlines of the superhexes of a hex grid; see [`Axial::to_superhex`].
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SuperHexBorders {
+    /// superhex radius in cells; zero draws nothing
+    pub radius: u32,
+    pub color: Color,
+    /// line width, as a fraction of the cell size
+    pub width: f32,
+}
+
+impl Default for SuperHexBorders {
+    fn default() -> Self {
+        Self {
+            radius: 2,
+            color: Color::linear_rgba(0.9, 0.9, 0.9, 0.9),
+            width: 0.1,
+        }
+    }
+}
+
+/// Layout & appearance of a grid entity.  Spawning this component makes the
+/// entity a grid; the grid is drawn on the local XZ plane of the entity, and
+/// hidden along with it.  Add `RenderLayers` to the entity to only draw the
+/// grid for cameras on those layers.
+///
+/// Changes are picked up by the render world each frame, so this can be
+/// tuned live in the inspector.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+#[require(Transform, Visibility, SyncToRenderWorld)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HexGridConfig {
+    pub kind: GridKind,
+    pub orientation: Orientation,
+    /// distance between the centers of neighboring cells, world units
+    pub size: f32,
+    pub line_color: Color,
+    /// width of the grid lines, as a fraction of the cell size; only used
+    /// with [`LineWidthMode::CellFraction`]
+    pub line_width: f32,
+    /// units of the grid line width; the cursor line width is always a
+    /// fraction of the cell size
+    pub line_width_mode: LineWidthMode,
+    pub antialiasing: LineAntialiasing,
+    /// color of the hex under the cursor
+    pub cursor_color: Color,
+    pub cursor_line_width: f32,
+    /// color of the hex edge nearest the cursor
+    pub cursor_edge_color: Color,
+    /// how close the cursor must be to an edge to highlight it, as a
+    /// fraction of the cell size
+    pub cursor_edge_distance: f32,
+    /// tint cells by parity class so neighboring cells never share a color;
+    /// see [`HexGridConfig::parity`].  Hex grids use all three colors,
+    /// other kinds only the first two
+    pub parity_tint: Option<[Color; 3]>,
+    /// write the grid plane depth, so later passes are occluded by the grid;
+    /// disable to draw the grid as an overlay that's still hidden behind
+    /// geometry, but doesn't affect anything drawn after it
+    pub write_depth: bool,
+    /// only draw the grid where the stencil test passes against the
+    /// [`crate::mask::HexGridMask`]s in view
+    pub stencil: Option<HexGridStencil>,
+    /// wrap the map around east-west, or in both directions; see
+    /// [`crate::wrap`]
+    pub wrap: Option<HexWrap>,
+    /// outline the superhexes of a hex grid over the grid lines
+    pub superhex_borders: Option<SuperHexBorders>,
+    /// draw the grid with a theme from [`crate::theme::HexGridThemes`],
+    /// overriding the colors & widths above
+    pub theme: Option<HexGridTheme>,
+    /// draw [`crate::HexGridPalette`] teams in colorblind-safe colors, and
+    /// patterns; see [`crate::colorblind`]
+    pub colorblind: Option<ColorblindPreset>,
+}
+
+impl Default for HexGridConfig {
+    fn default() -> Self {
+        Self {
+            kind: GridKind::Hex,
+            orientation: Orientation::Pointy,
+            size: 1.0,
+            line_color: Color::linear_rgba(0.6, 0.6, 0.6, 0.6),
+            line_width: 0.04,
+            line_width_mode: LineWidthMode::CellFraction,
+            antialiasing: LineAntialiasing::Smoothstep,
+            cursor_color: Color::linear_rgba(1.0, 0.8, 0.2, 1.0),
+            cursor_line_width: 0.23,
+            cursor_edge_color: Color::linear_rgba(0.2, 0.8, 1.0, 1.0),
+            cursor_edge_distance: 0.15,
+            parity_tint: None,
+            write_depth: true,
+            stencil: None,
+            wrap: None,
+            superhex_borders: None,
+            theme: None,
+            colorblind: None,
+        }
+    }
+}
+
+impl HexGridConfig {
+    /// canonical coordinate of a cell on a wrapped map; see [`HexWrap::wrap`]
+    pub fn wrap_cell(&self, cell: Axial) -> Axial {
+        match self.wrap {
+            Some(wrap) if self.kind == GridKind::Hex => wrap.wrap(cell),
+            _ => cell,
+        }
+    }
+
+    /// convert a point on the grid plane (local X, local Z) into the unit
+    /// layout for the grid kind; pointy-topped for hexes, as used by
+    /// [`Axial`].  Mirrors `grid_uv()` in the shader
+    pub fn world_to_grid(&self, pos: Vec2) -> Vec2 {
+        let pos = pos / self.size;
+        let pos = match self.orientation {
+            Orientation::Pointy => pos,
+            // flat-topped is pointy-topped mirrored across X = Z
+            Orientation::Flat => pos.yx(),
+        };
+        match self.kind {
+            GridKind::Isometric => Vec2::new(pos.x + pos.y, pos.y - pos.x) / SQRT_2,
+            _ => pos,
+        }
+    }
+
+    /// inverse of [`HexGridConfig::world_to_grid`]
+    pub fn grid_to_world(&self, pos: Vec2) -> Vec2 {
+        let pos = match self.kind {
+            GridKind::Isometric => Vec2::new(pos.x - pos.y, pos.x + pos.y) / SQRT_2,
+            _ => pos,
+        };
+        let pos = match self.orientation {
+            Orientation::Pointy => pos,
+            Orientation::Flat => pos.yx(),
+        };
+        pos * self.size
+    }
+
+    /// cell containing a point on the grid plane (local X, local Z)
+    pub fn world_to_cell(&self, pos: Vec2) -> Axial {
+        let pos = self.world_to_grid(pos);
+        match self.kind {
+            GridKind::Hex => Axial::from_world(pos),
+            GridKind::Square | GridKind::Isometric => {
+                let cell = pos.round();
+                Axial::new(cell.x as i32, cell.y as i32)
+            }
+            GridKind::Triangle => {
+                // skew into the triangle lattice; each parallelogram holds
+                // a lower & upper triangle
+                let lattice = Vec2::new(pos.x - pos.y / SQRT_3, pos.y * 2.0 / SQRT_3);
+                let base = lattice.floor();
+                let frac = lattice - base;
+                let upper = (frac.x + frac.y > 1.0) as i32;
+                Axial::new(base.x as i32 * 2 + upper, base.y as i32)
+            }
+        }
+    }
+
+    /// center of a cell on the grid plane (local X, local Z)
+    pub fn cell_to_world(&self, cell: Axial) -> Vec2 {
+        let pos = match self.kind {
+            GridKind::Hex => cell.to_world(),
+            GridKind::Square | GridKind::Isometric => Vec2::new(cell.q as f32, cell.r as f32),
+            GridKind::Triangle => {
+                // centroid in the lattice, then back out of it
+                let offset = if cell.q.rem_euclid(2) == 1 { 2.0 } else { 1.0 } / 3.0;
+                let lattice = Vec2::new(cell.q.div_euclid(2) as f32, cell.r as f32) + offset;
+                Vec2::new(lattice.x + lattice.y * 0.5, lattice.y * SQRT_3 * 0.5)
+            }
+        };
+        self.grid_to_world(pos)
+    }
+
+    /// parity class of a cell; neighboring cells always differ.  Hexes have
+    /// three classes, other kinds two (a checkerboard for squares, up & down
+    /// pointing triangles).  Mirrors `cell_coords()` in the shader
+    pub fn parity(&self, cell: Axial) -> usize {
+        let parity = match self.kind {
+            GridKind::Hex => (cell.q - cell.r).rem_euclid(3),
+            GridKind::Square | GridKind::Isometric => (cell.q + cell.r).rem_euclid(2),
+            GridKind::Triangle => cell.q.rem_euclid(2),
+        };
+        parity as usize
+    }
+
+    /// Cell under a cursor position in logical window pixels, as from
+    /// `Window::cursor_position()`, for this grid on `plane` as seen by
+    /// `camera`.  Uses the same math as the shader, so it's the cell the
+    /// grid draws under the cursor.  `None` if the cursor ray misses the
+    /// plane.
+    pub fn screen_to_cell(
+        &self,
+        cursor: Vec2,
+        camera: &Camera,
+        camera_transform: &GlobalTransform,
+        plane: &HexGridPlane,
+    ) -> Option<Axial> {
+        let ray = cursor_ray(camera, camera_transform, cursor)?;
+        Some(self.world_to_cell(plane.intersect(ray)?))
+    }
+
+    /// Center of a cell in logical window pixels, the inverse of
+    /// [`HexGridConfig::screen_to_cell`]; for placing UI over a cell.  `None`
+    /// if the cell is behind the camera.
+    pub fn cell_to_screen(
+        &self,
+        cell: Axial,
+        camera: &Camera,
+        camera_transform: &GlobalTransform,
+        plane: &HexGridPlane,
+    ) -> Option<Vec2> {
+        // world_to_viewport() includes the viewport's own offset
+        let pos = plane.cell_to_world(self, cell);
+        camera.world_to_viewport(camera_transform, pos).ok()
+    }
+
+    /// distance from the center of a hex cell to a corner, world units
+    pub fn outer_radius(&self) -> f32 {
+        self.size / SQRT_3
+    }
+
+    /// corners of a cell on the grid plane (local X, local Z), counterclockwise
+    /// from X towards Z
+    pub fn cell_corners(&self, cell: Axial) -> Vec<Vec2> {
+        let corners: Vec<Vec2> = match self.kind {
+            GridKind::Hex => {
+                let center = cell.to_world();
+                (0..6)
+                    .map(|i| {
+                        let angle = i as f32 * FRAC_PI_3 + FRAC_PI_6;
+                        center + Vec2::from_angle(angle) / SQRT_3
+                    })
+                    .collect()
+            }
+            GridKind::Square | GridKind::Isometric => {
+                let center = Vec2::new(cell.q as f32, cell.r as f32);
+                [(-0.5, -0.5), (0.5, -0.5), (0.5, 0.5), (-0.5, 0.5)]
+                    .map(|(x, y)| center + Vec2::new(x, y))
+                    .into()
+            }
+            GridKind::Triangle => {
+                // lower & upper triangles of a parallelogram in the lattice
+                let base = Vec2::new(cell.q.div_euclid(2) as f32, cell.r as f32);
+                let lattice = if cell.q.rem_euclid(2) == 1 {
+                    [(1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]
+                } else {
+                    [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)]
+                };
+                lattice
+                    .map(|(x, y)| {
+                        let p = base + Vec2::new(x, y);
+                        Vec2::new(p.x + p.y * 0.5, p.y * SQRT_3 * 0.5)
+                    })
+                    .into()
+            }
+        };
+        let mut corners: Vec<Vec2> = corners.into_iter().map(|c| self.grid_to_world(c)).collect();
+        // flat-topped grids are mirrored, which reverses the winding
+        if self.orientation == Orientation::Flat {
+            corners.reverse();
+        }
+        corners
+    }
+}
@@ -0,0 +1,676 @@
+//! hex coordinate types
+//!
+//! The grid shader lays out pointy-topped hexes on the XZ plane with a
+//! distance of 1.0 between neighboring cell centers (the hex inner radius is
+//! 0.5).  The conversions in here mirror `hex_coords()` in `hex_grid.wgsl`, so
+//! if you change one, change the other.
+//!
+//! See https://www.redblobgames.com/grids/hexagons/ for the math.
+use bevy::prelude::*;
+use std::{
+    f32::consts::{FRAC_PI_2, FRAC_PI_3, FRAC_PI_6},
+    ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign},
+};
+
+use crate::Orientation;
+
+/// sqrt(3), used all over the place in hex math
+pub(crate) const SQRT_3: f32 = 1.732_050_8;
+
+/// axial hex coordinates; `s` is implied as `-q - r`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Axial {
+    pub q: i32,
+    pub r: i32,
+}
+
+impl Axial {
+    pub const ZERO: Axial = Axial::new(0, 0);
+
+    /// unit offsets for the six neighbors, counter-clockwise starting at +X
+    pub const NEIGHBORS: [Axial; 6] = [
+        Axial::new(1, 0),
+        Axial::new(0, 1),
+        Axial::new(-1, 1),
+        Axial::new(-1, 0),
+        Axial::new(0, -1),
+        Axial::new(1, -1),
+    ];
+
+    pub const fn new(q: i32, r: i32) -> Self {
+        Self { q, r }
+    }
+
+    /// implied third cube coordinate
+    pub const fn s(&self) -> i32 {
+        -self.q - self.r
+    }
+
+    pub const fn to_cube(self) -> Cube {
+        Cube::new(self.q, self.r, self.s())
+    }
+
+    /// neighbor in direction `dir`
+    pub fn neighbor(self, dir: HexDirection) -> Self {
+        self + dir
+    }
+
+    pub fn neighbors(self) -> impl Iterator<Item = Axial> {
+        Self::NEIGHBORS.into_iter().map(move |n| self + n)
+    }
+
+    /// number of steps between two cells
+    pub fn distance(self, other: Axial) -> u32 {
+        (self - other).length()
+    }
+
+    /// number of steps from the origin
+    pub fn length(self) -> u32 {
+        ((self.q.abs() + self.r.abs() + self.s().abs()) / 2) as u32
+    }
+
+    /// rotate `steps` * 60 degrees around `center`; see [`Cube::rotate_left`]
+    pub fn rotate_around(self, center: Axial, steps: i32) -> Self {
+        self.to_cube()
+            .rotate_around(center.to_cube(), steps)
+            .to_axial()
+    }
+
+    /// cells exactly `radius` steps away, counter-clockwise; a ring of radius
+    /// zero is just this cell
+    pub fn ring(self, radius: u32) -> impl Iterator<Item = Axial> {
+        let len = if radius == 0 { 1 } else { 6 * radius as usize };
+        let side = radius.max(1) as usize;
+        let start = self + Self::NEIGHBORS[4] * radius as i32;
+        (0..len).scan(start, move |cell, i| {
+            let current = *cell;
+            *cell += Self::NEIGHBORS[i / side];
+            Some(current)
+        })
+    }
+
+    /// cells within `radius` steps, ordered ring by ring outward from this
+    /// cell
+    pub fn spiral(self, radius: u32) -> impl Iterator<Item = Axial> {
+        (0..=radius).flat_map(move |r| self.ring(r))
+    }
+
+    /// cells within `radius` steps; same cells as [`Axial::spiral`], but in
+    /// row order, which is cheaper to compute
+    pub fn range(self, radius: u32) -> impl Iterator<Item = Axial> {
+        let n = radius as i32;
+        (-n..=n).flat_map(move |q| {
+            let r_min = (-n).max(-q - n);
+            let r_max = n.min(-q + n);
+            (r_min..=r_max).map(move |r| self + Axial::new(q, r))
+        })
+    }
+
+    /// Superhex containing this cell, for hexagons of `radius` steps tiling
+    /// the grid.  Superhexes are laid out as a hex grid of their own, with
+    /// the same neighbor directions, so this can be applied again for
+    /// coarser levels.  Radius zero is the cell itself.  Mirrors
+    /// `superhex()` in the shader.
+    pub fn to_superhex(self, radius: u32) -> Axial {
+        let n = radius as f32;
+        let det = 3.0 * n * n + 3.0 * n + 1.0;
+        let (q, r) = (self.q as f32, self.r as f32);
+        // invert the superhex basis of superhex_center()
+        let a = ((2.0 * n + 1.0) * q + n * r) / det;
+        let b = ((n + 1.0) * r - n * q) / det;
+        let guess = Cube::round(Vec3::new(a, b, -a - b)).to_axial();
+        // the rounded guess may be off by one near superhex corners
+        std::iter::once(guess)
+            .chain(guess.neighbors())
+            .find(|s| s.superhex_center(radius).distance(self) <= radius)
+            .unwrap_or(guess)
+    }
+
+    /// center cell of a superhex from [`Axial::to_superhex`]
+    pub fn superhex_center(self, radius: u32) -> Axial {
+        let n = radius as i32;
+        // neighboring superhexes in directions 0 & 1
+        Axial::new(n + 1, n) * self.q + Axial::new(-n, 2 * n + 1) * self.r
+    }
+
+    /// cells of a superhex from [`Axial::to_superhex`]
+    pub fn superhex_children(self, radius: u32) -> impl Iterator<Item = Axial> {
+        self.superhex_center(radius).range(radius)
+    }
+
+    /// cell containing a point on the grid plane (world X, world Z)
+    pub fn from_world(pos: Vec2) -> Self {
+        let r = pos.y * 2.0 / SQRT_3;
+        let q = pos.x - r * 0.5;
+        Cube::round(Vec3::new(q, r, -q - r)).into()
+    }
+
+    /// center of the cell on the grid plane (world X, world Z)
+    pub fn to_world(self) -> Vec2 {
+        Vec2::new(
+            self.q as f32 + self.r as f32 * 0.5,
+            self.r as f32 * SQRT_3 * 0.5,
+        )
+    }
+}
+
+impl From<Cube> for Axial {
+    fn from(cube: Cube) -> Self {
+        Self::new(cube.q, cube.r)
+    }
+}
+
+impl From<IVec2> for Axial {
+    fn from(v: IVec2) -> Self {
+        Self::new(v.x, v.y)
+    }
+}
+
+impl From<Axial> for IVec2 {
+    fn from(a: Axial) -> Self {
+        IVec2::new(a.q, a.r)
+    }
+}
+
+impl Add for Axial {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.q + rhs.q, self.r + rhs.r)
+    }
+}
+
+impl AddAssign for Axial {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Axial {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.q - rhs.q, self.r - rhs.r)
+    }
+}
+
+impl SubAssign for Axial {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul<i32> for Axial {
+    type Output = Self;
+    fn mul(self, rhs: i32) -> Self {
+        Self::new(self.q * rhs, self.r * rhs)
+    }
+}
+
+impl Neg for Axial {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self::new(-self.q, -self.r)
+    }
+}
+
+/// cube hex coordinates; invariant: `q + r + s == 0`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cube {
+    pub q: i32,
+    pub r: i32,
+    pub s: i32,
+}
+
+impl Cube {
+    pub const fn new(q: i32, r: i32, s: i32) -> Self {
+        Self { q, r, s }
+    }
+
+    /// round fractional cube coordinates to the containing cell
+    pub fn round(frac: Vec3) -> Self {
+        let mut q = frac.x.round();
+        let mut r = frac.y.round();
+        let mut s = frac.z.round();
+
+        // reset the component with the largest rounding error so the
+        // invariant holds
+        let dq = (q - frac.x).abs();
+        let dr = (r - frac.y).abs();
+        let ds = (s - frac.z).abs();
+        if dq > dr && dq > ds {
+            q = -r - s;
+        } else if dr > ds {
+            r = -q - s;
+        } else {
+            s = -q - r;
+        }
+
+        Self::new(q as i32, r as i32, s as i32)
+    }
+
+    pub const fn to_axial(self) -> Axial {
+        Axial::new(self.q, self.r)
+    }
+
+    /// rotate 60 degrees around the origin, in the direction of increasing
+    /// [`Axial::NEIGHBORS`] index
+    pub const fn rotate_left(self) -> Self {
+        Self::new(-self.r, -self.s, -self.q)
+    }
+
+    /// rotate 60 degrees around the origin, in the direction of decreasing
+    /// [`Axial::NEIGHBORS`] index
+    pub const fn rotate_right(self) -> Self {
+        Self::new(-self.s, -self.q, -self.r)
+    }
+
+    /// rotate `steps` * 60 degrees around `center`; positive steps rotate
+    /// left
+    pub fn rotate_around(self, center: Cube, steps: i32) -> Self {
+        let mut v = self - center;
+        for _ in 0..steps.rem_euclid(6) {
+            v = v.rotate_left();
+        }
+        v + center
+    }
+
+    /// mirror across the axis where `q` is constant
+    pub const fn reflect_q(self) -> Self {
+        Self::new(self.q, self.s, self.r)
+    }
+
+    /// mirror across the axis where `r` is constant
+    pub const fn reflect_r(self) -> Self {
+        Self::new(self.s, self.r, self.q)
+    }
+
+    /// mirror across the axis where `s` is constant
+    pub const fn reflect_s(self) -> Self {
+        Self::new(self.r, self.q, self.s)
+    }
+}
+
+impl Add for Cube {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.q + rhs.q, self.r + rhs.r, self.s + rhs.s)
+    }
+}
+
+impl Sub for Cube {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.q - rhs.q, self.r - rhs.r, self.s - rhs.s)
+    }
+}
+
+impl From<Axial> for Cube {
+    fn from(axial: Axial) -> Self {
+        axial.to_cube()
+    }
+}
+
+/// One of the six directions to a neighboring cell, in [`Axial::NEIGHBORS`]
+/// order.  Seen on the grid plane with +X right & +Z up, as by
+/// [`HexDirection::to_vec`], the variants are named for pointy-topped grids.
+/// Flat-topped grids are mirrored across X = Z, so the same directions point
+/// north, north east, south east, south, south west, & north west, and
+/// turn the other way; methods that depend on it take the [`Orientation`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HexDirection {
+    #[default]
+    East,
+    NorthEast,
+    NorthWest,
+    West,
+    SouthWest,
+    SouthEast,
+}
+
+impl HexDirection {
+    /// all six, in [`Axial::NEIGHBORS`] order
+    pub const ALL: [HexDirection; 6] = [
+        Self::East,
+        Self::NorthEast,
+        Self::NorthWest,
+        Self::West,
+        Self::SouthWest,
+        Self::SouthEast,
+    ];
+
+    /// direction `index` into [`Axial::NEIGHBORS`]; wraps modulo 6
+    pub const fn new(index: usize) -> Self {
+        Self::ALL[index % 6]
+    }
+
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    /// offset to the neighbor in this direction
+    pub const fn offset(self) -> Axial {
+        Axial::NEIGHBORS[self.index()]
+    }
+
+    pub const fn opposite(self) -> Self {
+        Self::new(self.index() + 3)
+    }
+
+    /// step `steps` places along [`Axial::NEIGHBORS`]; positive steps turn
+    /// the same way as [`Cube::rotate_left`]
+    pub const fn rotate(self, steps: i32) -> Self {
+        Self::new(self.index() + steps.rem_euclid(6) as usize)
+    }
+
+    /// turn 60 degrees counter-clockwise on a grid with `orientation`
+    pub const fn rotate_ccw(self, orientation: Orientation) -> Self {
+        match orientation {
+            Orientation::Pointy => self.rotate(1),
+            Orientation::Flat => self.rotate(-1),
+        }
+    }
+
+    /// turn 60 degrees clockwise on a grid with `orientation`
+    pub const fn rotate_cw(self, orientation: Orientation) -> Self {
+        match orientation {
+            Orientation::Pointy => self.rotate(-1),
+            Orientation::Flat => self.rotate(1),
+        }
+    }
+
+    /// unit vector on the grid plane (local X, local Z) towards the neighbor
+    /// in this direction, on a grid with `orientation`
+    pub fn to_vec(self, orientation: Orientation) -> Vec2 {
+        Vec2::from_angle(self.to_angle(orientation))
+    }
+
+    /// angle of [`HexDirection::to_vec`] from +X towards +Z, in radians
+    pub fn to_angle(self, orientation: Orientation) -> f32 {
+        let angle = self.index() as f32 * FRAC_PI_3;
+        match orientation {
+            Orientation::Pointy => angle,
+            Orientation::Flat => FRAC_PI_2 - angle,
+        }
+    }
+
+    /// direction nearest `angle` radians from +X towards +Z, on a grid with
+    /// `orientation`
+    pub fn from_angle(orientation: Orientation, angle: f32) -> Self {
+        let angle = match orientation {
+            Orientation::Pointy => angle,
+            Orientation::Flat => FRAC_PI_2 - angle,
+        };
+        Self::new(direction(angle, 0.0))
+    }
+
+    /// direction nearest the vector `v` on the grid plane, on a grid with
+    /// `orientation`
+    pub fn from_vec(orientation: Orientation, v: Vec2) -> Self {
+        Self::from_angle(orientation, v.to_angle())
+    }
+
+    /// direction from `from` to a neighboring cell `to`; `None` if they
+    /// aren't neighbors
+    pub fn between(from: Axial, to: Axial) -> Option<Self> {
+        let offset = to - from;
+        Axial::NEIGHBORS
+            .iter()
+            .position(|&n| n == offset)
+            .map(Self::new)
+    }
+}
+
+impl Neg for HexDirection {
+    type Output = Self;
+    fn neg(self) -> Self {
+        self.opposite()
+    }
+}
+
+impl From<HexDirection> for Axial {
+    fn from(dir: HexDirection) -> Self {
+        dir.offset()
+    }
+}
+
+impl Add<HexDirection> for Axial {
+    type Output = Self;
+    fn add(self, rhs: HexDirection) -> Self {
+        self + rhs.offset()
+    }
+}
+
+impl AddAssign<HexDirection> for Axial {
+    fn add_assign(&mut self, rhs: HexDirection) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub<HexDirection> for Axial {
+    type Output = Self;
+    fn sub(self, rhs: HexDirection) -> Self {
+        self - rhs.offset()
+    }
+}
+
+impl SubAssign<HexDirection> for Axial {
+    fn sub_assign(&mut self, rhs: HexDirection) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul<i32> for HexDirection {
+    type Output = Axial;
+    fn mul(self, rhs: i32) -> Axial {
+        self.offset() * rhs
+    }
+}
+
+/// Edge between two neighboring cells.  Each edge has one canonical form:
+/// the cell it's stored on, and a direction of 0, 1, or 2 into
+/// [`Axial::NEIGHBORS`].  Every constructor canonicalizes, so edges can be
+/// compared & hashed directly.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HexEdge {
+    cell: Axial,
+    dir: u8,
+}
+
+impl HexEdge {
+    /// edge of `cell` facing its neighbor in direction `dir`
+    pub fn new(cell: Axial, dir: HexDirection) -> Self {
+        let dir = dir.index();
+        if dir < 3 {
+            Self {
+                cell,
+                dir: dir as u8,
+            }
+        } else {
+            Self {
+                cell: cell + Axial::NEIGHBORS[dir],
+                dir: (dir - 3) as u8,
+            }
+        }
+    }
+
+    /// edge nearest a point on the grid plane (world X, world Z)
+    pub fn from_world(pos: Vec2) -> Self {
+        let cell = Axial::from_world(pos);
+        let angle = (pos - cell.to_world()).to_angle();
+        Self::new(cell, HexDirection::new(direction(angle, 0.0)))
+    }
+
+    /// cell the edge is stored on
+    pub fn cell(self) -> Axial {
+        self.cell
+    }
+
+    /// canonical direction from [`HexEdge::cell`]; east, north east, or
+    /// north west
+    pub fn dir(self) -> HexDirection {
+        HexDirection::new(self.dir as usize)
+    }
+
+    /// the two cells sharing this edge
+    pub fn cells(self) -> [Axial; 2] {
+        [self.cell, self.cell.neighbor(self.dir())]
+    }
+
+    /// the two corners at the ends of this edge
+    pub fn vertices(self) -> [HexVertex; 2] {
+        [
+            HexVertex::new(self.cell, self.dir as usize + 5),
+            HexVertex::new(self.cell, self.dir as usize),
+        ]
+    }
+
+    /// the four edges sharing a corner with this edge
+    pub fn neighbors(self) -> impl Iterator<Item = HexEdge> {
+        self.vertices()
+            .into_iter()
+            .flat_map(|v| v.edges())
+            .filter(move |e| *e != self)
+    }
+
+    /// midpoint of the edge on the grid plane (world X, world Z)
+    pub fn to_world(self) -> Vec2 {
+        self.cell.to_world() + self.dir().to_vec(Orientation::Pointy) * 0.5
+    }
+}
+
+/// Corner shared by three cells.  Corner `k` of a cell lies between its
+/// neighbors in directions `k` and `k + 1`.  Each corner has one canonical
+/// form: the cell it's stored on, and a corner of 0 or 1.  Every constructor
+/// canonicalizes, so vertices can be compared & hashed directly.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HexVertex {
+    cell: Axial,
+    corner: u8,
+}
+
+impl HexVertex {
+    /// corner `corner` of `cell`; corners wrap modulo 6
+    pub fn new(cell: Axial, corner: usize) -> Self {
+        // every corner is corner 0 or 1 of one of the cells touching it
+        let (cell, corner) = match corner % 6 {
+            0 => (cell, 0),
+            1 => (cell, 1),
+            2 => (cell + HexDirection::West, 0),
+            3 => (cell + HexDirection::SouthWest, 1),
+            4 => (cell + HexDirection::SouthWest, 0),
+            _ => (cell + HexDirection::SouthEast, 1),
+        };
+        Self { cell, corner }
+    }
+
+    /// corner nearest a point on the grid plane (world X, world Z)
+    pub fn from_world(pos: Vec2) -> Self {
+        let cell = Axial::from_world(pos);
+        let angle = (pos - cell.to_world()).to_angle();
+        Self::new(cell, direction(angle, FRAC_PI_6))
+    }
+
+    /// cell the corner is stored on
+    pub fn cell(self) -> Axial {
+        self.cell
+    }
+
+    /// canonical corner of [`HexVertex::cell`]; 0 or 1
+    pub fn corner(self) -> usize {
+        self.corner as usize
+    }
+
+    /// the three cells sharing this corner
+    pub fn cells(self) -> [Axial; 3] {
+        let k = HexDirection::new(self.corner());
+        [self.cell, self.cell + k, self.cell + k.rotate(1)]
+    }
+
+    /// the three edges meeting at this corner
+    pub fn edges(self) -> [HexEdge; 3] {
+        let k = HexDirection::new(self.corner());
+        [
+            HexEdge::new(self.cell, k),
+            HexEdge::new(self.cell, k.rotate(1)),
+            HexEdge::new(self.cell + k, k.rotate(2)),
+        ]
+    }
+
+    /// the three corners one edge away
+    pub fn neighbors(self) -> impl Iterator<Item = HexVertex> {
+        self.edges().into_iter().map(move |e| {
+            let [a, b] = e.vertices();
+            if a == self {
+                b
+            } else {
+                a
+            }
+        })
+    }
+
+    /// position of the corner on the grid plane (world X, world Z)
+    pub fn to_world(self) -> Vec2 {
+        let angle = self.corner() as f32 * FRAC_PI_3 + FRAC_PI_6;
+        self.cell.to_world() + Vec2::from_angle(angle) / SQRT_3
+    }
+}
+
+// index of the nearest of six directions spaced 60 degrees apart, the first
+// at `offset` radians
+fn direction(angle: f32, offset: f32) -> usize {
+    ((angle - offset) / FRAC_PI_3).round().rem_euclid(6.0) as usize
+}
+
+/// offset coordinate layouts; which rows (pointy) or columns (flat) are
+/// shoved over by half a cell
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OffsetKind {
+    /// pointy-topped, odd rows shifted right
+    #[default]
+    OddR,
+    /// pointy-topped, even rows shifted right
+    EvenR,
+    /// flat-topped, odd columns shifted down
+    OddQ,
+    /// flat-topped, even columns shifted down
+    EvenQ,
+}
+
+/// offset (column, row) coordinates, as used by most tile map editors
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Offset {
+    pub col: i32,
+    pub row: i32,
+}
+
+impl Offset {
+    pub const fn new(col: i32, row: i32) -> Self {
+        Self { col, row }
+    }
+
+    pub fn to_axial(self, kind: OffsetKind) -> Axial {
+        let Offset { col, row } = self;
+        match kind {
+            OffsetKind::OddR => Axial::new(col - (row - (row & 1)) / 2, row),
+            OffsetKind::EvenR => Axial::new(col - (row + (row & 1)) / 2, row),
+            OffsetKind::OddQ => Axial::new(col, row - (col - (col & 1)) / 2),
+            OffsetKind::EvenQ => Axial::new(col, row - (col + (col & 1)) / 2),
+        }
+    }
+
+    pub fn from_axial(axial: Axial, kind: OffsetKind) -> Self {
+        let Axial { q, r } = axial;
+        match kind {
+            OffsetKind::OddR => Offset::new(q + (r - (r & 1)) / 2, r),
+            OffsetKind::EvenR => Offset::new(q + (r + (r & 1)) / 2, r),
+            OffsetKind::OddQ => Offset::new(q, r + (q - (q & 1)) / 2),
+            OffsetKind::EvenQ => Offset::new(q, r + (q + (q & 1)) / 2),
+        }
+    }
+}
@@ -0,0 +1,196 @@
+//! small textures drawn on grid cells
+//!
+//! Register decal images (movement arrows, danger icons, spawn markers) with
+//! the [`HexGridDecalAtlas`] resource, then add a [`HexGridDecals`] component
+//! to a grid entity to place them on cells.  All registered images are packed
+//! into one atlas texture, and every decal on a grid is uploaded in a single
+//! storage buffer that the grid shader reads from; no entity is needed per
+//! decal.
+//!
+//! Decal images are converted to `Rgba8UnormSrgb` when packed, so they must
+//! be kept in the main world; see `RenderAssetUsages::MAIN_WORLD`.
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+
+use crate::HexMap;
+
+/// pixels between images in the atlas, so linear sampling doesn't bleed
+const ATLAS_PADDING: u32 = 1;
+const ATLAS_MIN_WIDTH: u32 = 256;
+
+/// handle to an image registered with [`HexGridDecalAtlas`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DecalId(u32);
+
+/// Decal images, packed into a single texture.  The atlas is rebuilt once
+/// every registered image has loaded.
+#[derive(Resource, Debug, Default)]
+pub struct HexGridDecalAtlas {
+    sources: Vec<Handle<Image>>,
+    image: Option<Handle<Image>>,
+    /// uv rect of each source within the atlas
+    rects: Vec<Rect>,
+    dirty: bool,
+}
+
+impl HexGridDecalAtlas {
+    /// add an image to the atlas
+    pub fn register(&mut self, image: Handle<Image>) -> DecalId {
+        self.sources.push(image);
+        self.dirty = true;
+        DecalId(self.sources.len() as u32 - 1)
+    }
+
+    /// packed atlas texture; `None` until all registered images have loaded
+    pub fn image(&self) -> Option<&Handle<Image>> {
+        self.image.as_ref()
+    }
+
+    /// uv rect of a decal within the atlas image
+    pub fn rect(&self, id: DecalId) -> Option<Rect> {
+        self.rects.get(id.0 as usize).copied()
+    }
+}
+
+/// a decal placed on a cell
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Decal {
+    pub id: DecalId,
+    /// multiplied with the decal image
+    pub color: Color,
+    /// counter-clockwise rotation when looking down at the grid, radians
+    pub rotation: f32,
+    /// width of the decal, as a fraction of the cell size
+    pub scale: f32,
+}
+
+impl Decal {
+    pub fn new(id: DecalId) -> Self {
+        Self {
+            id,
+            color: Color::WHITE,
+            rotation: 0.0,
+            scale: 0.8,
+        }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+}
+
+/// Decals for a grid entity, keyed by cell.  At most one decal is drawn per
+/// cell.
+#[derive(Component, Debug, Default, Clone)]
+pub struct HexGridDecals(pub HexMap<Decal>);
+
+/// pack the registered images into the atlas once they've all loaded
+pub(crate) fn build_decal_atlas(
+    mut atlas: ResMut<HexGridDecalAtlas>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    if !atlas.dirty {
+        return;
+    }
+
+    let mut sources = Vec::with_capacity(atlas.sources.len());
+    for handle in &atlas.sources {
+        let Some(image) = images.get(handle) else {
+            return;
+        };
+        if image.data.is_none() {
+            warn!(
+                "decal image {:?} is not in the main world; skipping",
+                handle.id()
+            );
+            sources.push(None);
+            continue;
+        }
+        let image = match image.texture_descriptor.format {
+            TextureFormat::Rgba8UnormSrgb => Some(image.clone()),
+            format => {
+                let converted = image.convert(TextureFormat::Rgba8UnormSrgb);
+                if converted.is_none() {
+                    warn!("unsupported decal image format {:?}; skipping", format);
+                }
+                converted
+            }
+        };
+        sources.push(image);
+    }
+
+    // simple shelf packing; images are placed left to right, starting a new
+    // row when the current one is full
+    let sizes: Vec<UVec2> = sources
+        .iter()
+        .map(|image| image.as_ref().map_or(UVec2::ZERO, |i| i.size()))
+        .collect();
+    let width = sizes
+        .iter()
+        .map(|s| s.x + ATLAS_PADDING)
+        .max()
+        .unwrap_or(0)
+        .max(ATLAS_MIN_WIDTH);
+    let mut offsets = Vec::with_capacity(sizes.len());
+    let (mut x, mut y, mut row_height) = (0, 0, 0);
+    for size in &sizes {
+        if x + size.x > width {
+            x = 0;
+            y += row_height + ATLAS_PADDING;
+            row_height = 0;
+        }
+        offsets.push(UVec2::new(x, y));
+        x += size.x + ATLAS_PADDING;
+        row_height = row_height.max(size.y);
+    }
+    let height = (y + row_height).max(1);
+
+    let mut data = vec![0; (width * height * 4) as usize];
+    for (image, offset) in sources.iter().zip(&offsets) {
+        let Some(image) = image else { continue };
+        let Some(pixels) = &image.data else { continue };
+        let row_bytes = image.width() as usize * 4;
+        for (row, src) in pixels.chunks_exact(row_bytes).enumerate() {
+            let start = ((offset.y as usize + row) * width as usize + offset.x as usize) * 4;
+            data[start..start + row_bytes].copy_from_slice(src);
+        }
+    }
+
+    let atlas_size = Vec2::new(width as f32, height as f32);
+    atlas.rects = offsets
+        .iter()
+        .zip(&sizes)
+        .map(|(offset, size)| {
+            let min = offset.as_vec2();
+            Rect::from_corners(min / atlas_size, (min + size.as_vec2()) / atlas_size)
+        })
+        .collect();
+    atlas.image = Some(images.add(Image::new(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    )));
+    atlas.dirty = false;
+}
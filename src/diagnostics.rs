@@ -0,0 +1,76 @@
+//! gpu timing of the grid pass
+//!
+//! Add [`HexGridDiagnosticsPlugin`] to measure what drawing the grids costs.
+//! The grid pass of every camera is wrapped in timestamp queries by bevy's
+//! [`RenderDiagnosticsPlugin`], which is added if it isn't already, and the
+//! times read back a few frames later.  The totals for the latest frame are
+//! kept in the [`HexGridDiagnostics`] resource; the history of each pass is in
+//! the [`DiagnosticsStore`] under [`HexGridDiagnostics::GPU_TIME`] &
+//! [`HexGridDiagnostics::CPU_TIME`], so `LogDiagnosticsPlugin` prints them.
+//!
+//! Timestamp queries are only supported on some backends, such as Vulkan &
+//! DX12; elsewhere only the cpu time is measured.
+use bevy::{
+    diagnostic::{DiagnosticPath, DiagnosticsStore},
+    prelude::*,
+    render::diagnostic::RenderDiagnosticsPlugin,
+};
+
+pub struct HexGridDiagnosticsPlugin;
+
+impl Plugin for HexGridDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<RenderDiagnosticsPlugin>() {
+            app.add_plugins(RenderDiagnosticsPlugin);
+        }
+        app.init_resource::<DiagnosticsStore>()
+            .init_resource::<HexGridDiagnostics>()
+            .add_systems(Update, update_hex_grid_diagnostics);
+    }
+}
+
+/// Time spent drawing grids in the latest frame measured, summed over every
+/// camera.  `None` until the first measurement arrives, or when it isn't
+/// supported.
+#[derive(Resource, Debug, Default, Clone, PartialEq)]
+pub struct HexGridDiagnostics {
+    /// milliseconds the gpu spent in grid passes
+    pub gpu_time: Option<f32>,
+    /// milliseconds spent recording grid passes on the cpu
+    pub cpu_time: Option<f32>,
+    /// grid passes drawn in the frame
+    pub passes: usize,
+}
+
+impl HexGridDiagnostics {
+    /// gpu time of each grid pass, in milliseconds
+    pub const GPU_TIME: DiagnosticPath = DiagnosticPath::const_new("render/hex_grid/elapsed_gpu");
+    /// cpu time of each grid pass, in milliseconds
+    pub const CPU_TIME: DiagnosticPath = DiagnosticPath::const_new("render/hex_grid/elapsed_cpu");
+}
+
+// sum of the measurements from the latest frame; every pass in a frame is
+// measured at the same instant, & count of them
+fn latest_frame(store: &DiagnosticsStore, path: &DiagnosticPath) -> Option<(f32, usize)> {
+    let diagnostic = store.get(path)?;
+    let time = diagnostic.measurement()?.time;
+    let (sum, count) = diagnostic
+        .measurements()
+        .filter(|m| m.time == time)
+        .fold((0.0, 0), |(sum, count), m| (sum + m.value, count + 1));
+    Some((sum as f32, count))
+}
+
+fn update_hex_grid_diagnostics(
+    store: Res<DiagnosticsStore>,
+    mut diagnostics: ResMut<HexGridDiagnostics>,
+) {
+    let gpu = latest_frame(&store, &HexGridDiagnostics::GPU_TIME);
+    let cpu = latest_frame(&store, &HexGridDiagnostics::CPU_TIME);
+    let latest = HexGridDiagnostics {
+        gpu_time: gpu.map(|(time, _)| time),
+        cpu_time: cpu.map(|(time, _)| time),
+        passes: cpu.or(gpu).map_or(0, |(_, count)| count),
+    };
+    diagnostics.set_if_neq(latest);
+}
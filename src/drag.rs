@@ -0,0 +1,330 @@
+//! drag-and-drop unit movement
+//!
+//! Add [`HexGridDragPlugin`] to the app, and a [`Draggable`] to any cell
+//! entity (one with a [`HexCell`]) that should be moved by dragging.
+//! Pressing the drag button over a draggable unit highlights every cell it
+//! can reach with its [`Draggable::range`], using the grid's
+//! [`HexGridHighlights`].  While dragging, a translucent copy of the unit and
+//! a [`HexGridPathPreview`] follow the cursor over reachable cells, and
+//! releasing over one sends a [`UnitMoveRequested`].  The unit isn't moved;
+//! insert a new [`HexCell`] when handling the message to move it.
+//!
+//! Cells cost 1 to enter, or the cost in the grid's [`HexGridMoveCosts`].
+//! Other draggable units, and cells that aren't free in the
+//! [`crate::HexOccupancy`], block movement.  The cancel button ends a drag
+//! without a message.
+use bevy::prelude::*;
+
+use crate::{
+    path::{find_path, movement_range},
+    Axial, HexCell, HexCellIndex, HexGridConfig, HexGridCursor, HexGridHighlights,
+    HexGridPathPreview, HexMap, HexOccupancy,
+};
+
+pub struct HexGridDragPlugin;
+
+impl Plugin for HexGridDragPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Draggable>()
+            .init_resource::<HexGridDragSettings>()
+            .init_resource::<UnitDrag>()
+            .add_message::<UnitMoveRequested>()
+            .add_systems(
+                Update,
+                (start_unit_drag, update_unit_drag)
+                    .chain()
+                    .after(crate::update_hex_grid_cursor),
+            );
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct HexGridDragSettings {
+    pub button: MouseButton,
+    /// button that ends a drag without moving the unit
+    pub cancel_button: MouseButton,
+    /// highlight color of the cells the unit can reach
+    pub range_color: Color,
+    /// alpha of the copy of the unit shown over the destination; zero to
+    /// not show one
+    pub ghost_alpha: f32,
+    /// ribbon drawn from the unit to the destination; `None` to not draw one
+    pub path_preview: Option<HexGridPathPreview>,
+}
+
+impl Default for HexGridDragSettings {
+    fn default() -> Self {
+        Self {
+            button: MouseButton::Left,
+            cancel_button: MouseButton::Right,
+            range_color: Color::srgba(0.3, 0.6, 1.0, 0.3),
+            ghost_alpha: 0.5,
+            path_preview: Some(HexGridPathPreview::default()),
+        }
+    }
+}
+
+/// Unit that can be dragged to another cell.  Must be on an entity with a
+/// [`HexCell`].
+#[derive(Component, Debug, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct Draggable {
+    /// total cost of the cells the unit may enter in one move
+    pub range: u32,
+}
+
+impl Draggable {
+    pub fn new(range: u32) -> Self {
+        Self { range }
+    }
+}
+
+/// Cost of entering each cell of the grid entity, for dragged units & path
+/// requests.  Cells without a cost are blocked.
+#[derive(Component, Debug, Default, Clone)]
+pub struct HexGridMoveCosts(pub HexMap<u32>);
+
+/// sent when a dragged unit is released over a cell it can reach
+#[derive(Message, Debug, Clone, Copy)]
+pub struct UnitMoveRequested {
+    pub entity: Entity,
+    pub grid: Entity,
+    pub from: Axial,
+    pub to: Axial,
+}
+
+/// the drag in progress, if any
+#[derive(Resource, Debug, Default)]
+struct UnitDrag(Option<Drag>);
+
+#[derive(Debug)]
+struct Drag {
+    unit: Entity,
+    grid: Entity,
+    from: Axial,
+    /// cost to reach each cell in range
+    range: HexMap<u32>,
+    /// highlight of each cell in range before the drag, or `None` if the
+    /// grid had no highlights
+    restore: Option<Vec<(Axial, Option<Color>)>>,
+    /// reachable cell under the cursor
+    target: Option<Axial>,
+    ghost: Option<Entity>,
+    preview: Option<Entity>,
+}
+
+/// copy of a dragged unit shown over its destination
+#[derive(Component, Debug)]
+struct DragGhost;
+
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+fn start_unit_drag(
+    mut commands: Commands,
+    settings: Res<HexGridDragSettings>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    index: Res<HexCellIndex>,
+    occupancy: Res<HexOccupancy>,
+    cursors: Query<&HexGridCursor>,
+    units: Query<(
+        &Draggable,
+        &HexCell,
+        Option<&Transform>,
+        Option<&Mesh3d>,
+        Option<&MeshMaterial3d<StandardMaterial>>,
+    )>,
+    mut grids: Query<
+        (Option<&HexGridMoveCosts>, Option<&mut HexGridHighlights>),
+        With<HexGridConfig>,
+    >,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut drag: ResMut<UnitDrag>,
+) {
+    if drag.0.is_some() || !buttons.just_pressed(settings.button) {
+        return;
+    }
+    let Some((grid, cell)) = cursors
+        .iter()
+        .find_map(|cursor| Some((cursor.grid?, cursor.cell)))
+    else {
+        return;
+    };
+    let Some(unit) = index.get(grid, cell) else {
+        return;
+    };
+    let Ok((draggable, hex_cell, transform, mesh, material)) = units.get(unit) else {
+        return;
+    };
+    let Ok((costs, highlights)) = grids.get_mut(grid) else {
+        return;
+    };
+
+    // units block each other
+    let occupancy = occupancy.grid(grid);
+    let occupied = |cell| {
+        index
+            .get(grid, cell)
+            .is_some_and(|e| e != unit && units.contains(e))
+            || occupancy.is_some_and(|o| !o.is_free_for(cell, unit))
+    };
+    let range = movement_range(hex_cell.cell, draggable.range, |cell| {
+        if occupied(cell) {
+            return None;
+        }
+        match costs {
+            Some(costs) => costs.0.get(cell).copied(),
+            None => Some(1),
+        }
+    });
+
+    let reachable: Vec<Axial> = range
+        .iter()
+        .map(|(c, _)| c)
+        .filter(|&c| c != hex_cell.cell)
+        .collect();
+    let restore = match highlights {
+        Some(mut highlights) => {
+            let restore = reachable
+                .iter()
+                .map(|&c| (c, highlights.0.get(c).copied()))
+                .collect();
+            highlights.fill(reachable, settings.range_color);
+            Some(restore)
+        }
+        None => {
+            let mut highlights = HexGridHighlights::default();
+            highlights.fill(reachable, settings.range_color);
+            commands.entity(grid).insert(highlights);
+            None
+        }
+    };
+
+    let ghost = match (mesh, material.and_then(|m| materials.get(&m.0))) {
+        (Some(mesh), Some(material)) if settings.ghost_alpha > 0.0 => {
+            let mut material = material.clone();
+            material.base_color.set_alpha(settings.ghost_alpha);
+            material.alpha_mode = AlphaMode::Blend;
+            let ghost = commands
+                .spawn((
+                    Name::new("Draggable ghost"),
+                    DragGhost,
+                    mesh.clone(),
+                    MeshMaterial3d(materials.add(material)),
+                    transform.copied().unwrap_or_default(),
+                    Visibility::Hidden,
+                    ChildOf(grid),
+                ))
+                .id();
+            Some(ghost)
+        }
+        _ => None,
+    };
+    let preview = settings
+        .path_preview
+        .clone()
+        .map(|preview| commands.spawn((preview, ChildOf(grid))).id());
+
+    drag.0 = Some(Drag {
+        unit,
+        grid,
+        from: hex_cell.cell,
+        range,
+        restore,
+        target: None,
+        ghost,
+        preview,
+    });
+}
+
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+fn update_unit_drag(
+    mut commands: Commands,
+    settings: Res<HexGridDragSettings>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    cursors: Query<&HexGridCursor>,
+    units: Query<(), With<Draggable>>,
+    costs: Query<&HexGridMoveCosts>,
+    mut grids: Query<(&HexGridConfig, Option<&mut HexGridHighlights>)>,
+    mut ghosts: Query<(&mut Transform, &mut Visibility), With<DragGhost>>,
+    mut previews: Query<&mut HexGridPathPreview>,
+    mut moves: MessageWriter<UnitMoveRequested>,
+    mut drag: ResMut<UnitDrag>,
+) {
+    let Some(current) = &mut drag.0 else { return };
+    let released = buttons.just_released(settings.button);
+    let cancelled = buttons.just_pressed(settings.cancel_button);
+    let gone = !units.contains(current.unit) || !grids.contains(current.grid);
+
+    if !released && !cancelled && !gone {
+        let target = cursors
+            .iter()
+            .filter(|cursor| cursor.grid == Some(current.grid))
+            .map(|cursor| cursor.cell)
+            .find(|&cell| cell != current.from && current.range.contains(cell));
+        if target == current.target {
+            return;
+        }
+        current.target = target;
+
+        if let Some((mut transform, mut visibility)) =
+            current.ghost.and_then(|g| ghosts.get_mut(g).ok())
+        {
+            match (target, grids.get(current.grid)) {
+                (Some(cell), Ok((config, _))) => {
+                    let pos = config.cell_to_world(cell);
+                    transform.translation.x = pos.x;
+                    transform.translation.z = pos.y;
+                    *visibility = Visibility::Inherited;
+                }
+                _ => *visibility = Visibility::Hidden,
+            }
+        }
+        if let Some(mut preview) = current.preview.and_then(|p| previews.get_mut(p).ok()) {
+            let path = target.and_then(|to| {
+                let costs = costs.get(current.grid).ok();
+                find_path(current.from, to, |_, cell| {
+                    current.range.get(cell)?;
+                    match costs {
+                        Some(costs) => costs.0.get(cell).copied(),
+                        None => Some(1),
+                    }
+                })
+            });
+            preview.path = path.map(|(path, _)| path).unwrap_or_default();
+        }
+        return;
+    }
+
+    let Some(drag) = drag.0.take() else { return };
+    if released && !gone {
+        if let Some(to) = drag.target {
+            moves.write(UnitMoveRequested {
+                entity: drag.unit,
+                grid: drag.grid,
+                from: drag.from,
+                to,
+            });
+        }
+    }
+
+    if let Ok((_, highlights)) = grids.get_mut(drag.grid) {
+        match (drag.restore, highlights) {
+            (Some(restore), Some(mut highlights)) => {
+                for (cell, color) in restore {
+                    match color {
+                        Some(color) => highlights.0.insert(cell, color),
+                        None => highlights.0.remove(cell),
+                    };
+                }
+            }
+            (None, _) => {
+                commands.entity(drag.grid).remove::<HexGridHighlights>();
+            }
+            _ => (),
+        }
+    }
+    for entity in drag.ghost.into_iter().chain(drag.preview) {
+        if let Ok(mut entity) = commands.get_entity(entity) {
+            entity.despawn();
+        }
+    }
+}
@@ -0,0 +1,690 @@
+//! in-game map editor
+//!
+//! Add [`HexEditorPlugin`] to paint the terrain, elevation, & owners of a
+//! grid with the mouse.  The [`HexEditor`] resource holds the tool, the
+//! layer it paints, & the value painted.  The grid edited is the first one
+//! clicked, or the one set in [`HexEditor::grid`].  Its [`HexGridTerrain`],
+//! [`HexGridElevation`], & [`HexGridOwners`] are read into the editor's
+//! document when editing starts, & every change to the document is written
+//! back to them.  Layers the grid has no component for are kept in the
+//! document, but not drawn.
+//!
+//! The brush tool stamps [`HexEditor::brush`] on every cell the cursor
+//! passes while the button is held, the line tool stamps it along a line
+//! from where the button was pressed to where it's released, and the
+//! rectangle tool paints the cells between them.  Fill paints the connected
+//! cells with the same value as the one clicked.  Each is one
+//! change to undo; see [`HexMapHistory`].  Keyboard shortcuts:
+//!
+//! - `ctrl-z` undo, `ctrl-shift-z` or `ctrl-y` redo
+//! - `b`, `f`, `r`, & `l` pick the brush, fill, rectangle, & line tools
+//! - `e` toggles erasing
+//!
+//! Prefabs can be copied out of [`HexEditor::document`] with
+//! [`HexMap::extract_region`], and pasted, turned any way, with
+//! [`HexEditor::stamp`].
+//!
+//! With the `editor` feature, the plugin also shows an egui panel for the
+//! tools, layers, & values, and for saving & loading the document with
+//! [`HexMap::to_bytes`].
+use bevy::prelude::*;
+
+use crate::{
+    shape::{self, Brush},
+    Axial, CellOwner, HexGridConfig, HexGridCursor, HexGridElevation, HexGridOwners,
+    HexGridTerrain, HexMap, HexMapHistory, HexMapVersion, HexStamp, TerrainId,
+};
+
+pub struct HexEditorPlugin;
+
+impl Plugin for HexEditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HexEditor>().add_systems(
+            Update,
+            (edit_with_keys, edit_with_pointer)
+                .chain()
+                .after(crate::update_hex_grid_cursor),
+        );
+
+        #[cfg(feature = "editor")]
+        {
+            use bevy_inspector_egui::bevy_egui::{EguiPlugin, EguiPrimaryContextPass};
+
+            if !app.is_plugin_added::<EguiPlugin>() {
+                app.add_plugins(EguiPlugin::default());
+            }
+            app.add_systems(EguiPrimaryContextPass, panel::editor_panel);
+        }
+    }
+}
+
+/// every layer of one cell in an editor document
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EditorCell {
+    pub terrain: Option<TerrainId>,
+    pub elevation: Option<f32>,
+    pub owner: Option<CellOwner>,
+}
+
+/// component of the grid painted by the [`HexEditor`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum EditorLayer {
+    /// [`HexGridTerrain::cells`]
+    #[default]
+    Terrain,
+    /// [`HexGridElevation::cells`]
+    Elevation,
+    /// [`HexGridOwners`]
+    Ownership,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum EditorTool {
+    /// the brush, stamped on each cell under the cursor while the button is
+    /// held
+    #[default]
+    Brush,
+    /// connected cells with the same value as the one clicked
+    Fill,
+    /// rectangle from where the button was pressed to where it's released
+    Rectangle,
+    /// the brush, stamped along a line from where the button was pressed to
+    /// where it's released
+    Line,
+}
+
+/// Map editor state & document; see the [module docs](self).
+#[derive(Resource, Debug)]
+pub struct HexEditor {
+    /// false to ignore the mouse & keyboard
+    pub enabled: bool,
+    /// grid entity being edited; `None` until a grid is clicked
+    pub grid: Option<Entity>,
+    pub tool: EditorTool,
+    pub layer: EditorLayer,
+    /// shape stamped by the brush & line tools
+    pub brush: Brush,
+    pub terrain: TerrainId,
+    pub elevation: f32,
+    pub owner: CellOwner,
+    /// clear the layer instead of painting it
+    pub erase: bool,
+    pub button: MouseButton,
+    /// most cells one fill may paint
+    pub fill_limit: usize,
+    /// Set by UI drawn over the grid while it has the pointer or keyboard,
+    /// so input meant for it doesn't edit the map.
+    pub over_ui: bool,
+    document: HexMapHistory<EditorCell>,
+    /// grid the document was read from
+    loaded: Option<Entity>,
+    /// document version last written to the grid
+    synced: Option<HexMapVersion>,
+    /// cell the button was pressed on, & the cell under the cursor since,
+    /// while a tool is dragged
+    stroke: Option<(Axial, Axial)>,
+}
+
+impl Default for HexEditor {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            grid: None,
+            tool: EditorTool::default(),
+            layer: EditorLayer::default(),
+            brush: Brush::Single,
+            terrain: TerrainId(0),
+            elevation: 1.0,
+            owner: CellOwner(0),
+            erase: false,
+            button: MouseButton::Left,
+            fill_limit: 1 << 16,
+            over_ui: false,
+            document: HexMapHistory::default(),
+            loaded: None,
+            synced: None,
+            stroke: None,
+        }
+    }
+}
+
+impl HexEditor {
+    /// every layer of the map being edited
+    pub fn document(&self) -> &HexMap<EditorCell> {
+        self.document.as_map()
+    }
+
+    /// replace the document, forgetting its history; the grid is redrawn
+    /// from it
+    pub fn set_document(&mut self, document: HexMap<EditorCell>) {
+        self.document = HexMapHistory::new(document);
+        self.stroke = None;
+    }
+
+    /// Paint `cells` on the current layer, as one change.  During a brush
+    /// stroke they're part of the stroke instead.
+    pub fn paint(&mut self, cells: impl IntoIterator<Item = Axial>) {
+        let stroke = self.document.in_txn();
+        self.document.begin_txn();
+        for cell in cells {
+            let before = self.document.get(cell).copied().unwrap_or_default();
+            let after = self.painted(before);
+            if after == before {
+                continue;
+            }
+            if after == EditorCell::default() {
+                self.document.remove(cell);
+            } else {
+                self.document.insert(cell, after);
+            }
+        }
+        if !stroke {
+            self.document.commit();
+        }
+    }
+
+    /// Paint the cells connected to `start` that have the same value on
+    /// the current layer.  Empty regions are bounded by the cells in the
+    /// document, & every fill by [`HexEditor::fill_limit`].
+    pub fn fill(&mut self, start: Axial) {
+        let value = self.layer_value(start);
+        let bounds = self.document().bounds();
+        let region = shape::flood(start, self.fill_limit, |cell| {
+            bounds.is_some_and(|b| b.contains(cell)) && self.layer_value(cell) == value
+        });
+        self.paint(region);
+    }
+
+    /// Paste a stamp copied from the document with
+    /// [`HexMap::extract_region`], as one change; see
+    /// [`HexMap::apply_stamp`].
+    pub fn stamp(&mut self, stamp: &HexStamp<EditorCell>, anchor: Axial, rotation: i32) {
+        let stroke = self.document.in_txn();
+        self.document.begin_txn();
+        for (cell, value) in stamp.placed(anchor, rotation) {
+            match value {
+                Some(&value) if value != EditorCell::default() => {
+                    self.document.insert(cell, value);
+                }
+                _ => {
+                    self.document.remove(cell);
+                }
+            }
+        }
+        if !stroke {
+            self.document.commit();
+        }
+    }
+
+    /// cells the tool paints when dragged from `from` to `to`
+    pub fn tool_cells(&self, from: Axial, to: Axial) -> Vec<Axial> {
+        match self.tool {
+            EditorTool::Brush | EditorTool::Line => {
+                self.brush.stroke(from, to).into_iter().collect()
+            }
+            EditorTool::Fill => vec![to],
+            EditorTool::Rectangle => shape::rectangle(from, to).into_iter().collect(),
+        }
+    }
+
+    /// undo the last change; false if there is none
+    pub fn undo(&mut self) -> bool {
+        self.stroke = None;
+        self.document.undo()
+    }
+
+    /// redo the last undone change; false if there is none
+    pub fn redo(&mut self) -> bool {
+        self.document.redo()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.document.can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.document.can_redo()
+    }
+
+    /// start dragging the tool at `cell`
+    fn press(&mut self, cell: Axial) {
+        match self.tool {
+            EditorTool::Brush => {
+                self.document.begin_txn();
+                self.paint(self.brush.cells(cell, cell));
+            }
+            EditorTool::Fill => {
+                self.fill(cell);
+                return;
+            }
+            EditorTool::Rectangle | EditorTool::Line => (),
+        }
+        self.stroke = Some((cell, cell));
+    }
+
+    /// the tool was dragged over `cell`
+    fn drag(&mut self, cell: Axial) {
+        let Some((from, last)) = self.stroke else {
+            return;
+        };
+        if cell == last {
+            return;
+        }
+        if self.tool == EditorTool::Brush {
+            self.paint(self.tool_cells(last, cell));
+        }
+        self.stroke = Some((from, cell));
+    }
+
+    /// the button was released, ending the drag
+    fn release(&mut self) {
+        let Some((from, to)) = self.stroke.take() else {
+            return;
+        };
+        match self.tool {
+            EditorTool::Rectangle | EditorTool::Line => self.paint(self.tool_cells(from, to)),
+            _ => {
+                self.document.commit();
+            }
+        }
+    }
+
+    // value of the current layer on a cell
+    fn layer_value(&self, cell: Axial) -> EditorCell {
+        let value = self.document.get(cell).copied().unwrap_or_default();
+        match self.layer {
+            EditorLayer::Terrain => EditorCell {
+                terrain: value.terrain,
+                ..default()
+            },
+            EditorLayer::Elevation => EditorCell {
+                elevation: value.elevation,
+                ..default()
+            },
+            EditorLayer::Ownership => EditorCell {
+                owner: value.owner,
+                ..default()
+            },
+        }
+    }
+
+    // cell with the current layer painted, or erased
+    fn painted(&self, mut cell: EditorCell) -> EditorCell {
+        let erase = self.erase;
+        match self.layer {
+            EditorLayer::Terrain => cell.terrain = (!erase).then_some(self.terrain),
+            EditorLayer::Elevation => cell.elevation = (!erase).then_some(self.elevation),
+            EditorLayer::Ownership => cell.owner = (!erase).then_some(self.owner),
+        }
+        cell
+    }
+
+    // read the document from the layers of a grid
+    fn load_grid(&mut self, grid: Entity, layers: GridLayers) {
+        let (terrain, elevation, owners) = layers;
+        let mut document = HexMap::<EditorCell>::new();
+        if let Some(terrain) = terrain {
+            for (c, &id) in terrain.cells.iter() {
+                entry(&mut document, c).terrain = Some(id);
+            }
+        }
+        if let Some(elevation) = elevation {
+            for (c, &height) in elevation.cells.iter() {
+                entry(&mut document, c).elevation = Some(height);
+            }
+        }
+        if let Some(owners) = owners {
+            for (c, &owner) in owners.as_map().iter() {
+                entry(&mut document, c).owner = Some(owner);
+            }
+        }
+        self.set_document(document);
+        self.loaded = Some(grid);
+        self.synced = Some(self.document().version());
+    }
+}
+
+#[cfg(feature = "serde")]
+impl HexEditor {
+    /// encode the document into the same format as [`HexMap::to_bytes`]
+    pub fn to_bytes(&self) -> bincode::Result<Vec<u8>> {
+        self.document().to_bytes()
+    }
+
+    /// replace the document with one written by [`HexEditor::to_bytes`]
+    pub fn load_bytes(&mut self, bytes: &[u8]) -> bincode::Result<()> {
+        self.set_document(HexMap::from_bytes(bytes)?);
+        Ok(())
+    }
+
+    /// write the document to a file
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> anyhow::Result<()> {
+        std::fs::write(path, self.to_bytes()?)?;
+        Ok(())
+    }
+
+    /// replace the document with one written by [`HexEditor::save`]
+    pub fn load(&mut self, path: impl AsRef<std::path::Path>) -> anyhow::Result<()> {
+        self.load_bytes(&std::fs::read(path)?)?;
+        Ok(())
+    }
+}
+
+type GridLayers<'a> = (
+    Option<&'a HexGridTerrain>,
+    Option<&'a HexGridElevation>,
+    Option<&'a HexGridOwners>,
+);
+
+fn edit_with_keys(mut editor: ResMut<HexEditor>, keys: Res<ButtonInput<KeyCode>>) {
+    if !editor.enabled || editor.over_ui {
+        return;
+    }
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if ctrl {
+        if keys.just_pressed(KeyCode::KeyY) || (shift && keys.just_pressed(KeyCode::KeyZ)) {
+            editor.redo();
+        } else if keys.just_pressed(KeyCode::KeyZ) {
+            editor.undo();
+        }
+        return;
+    }
+    for (key, tool) in [
+        (KeyCode::KeyB, EditorTool::Brush),
+        (KeyCode::KeyF, EditorTool::Fill),
+        (KeyCode::KeyR, EditorTool::Rectangle),
+        (KeyCode::KeyL, EditorTool::Line),
+    ] {
+        if keys.just_pressed(key) && editor.stroke.is_none() {
+            editor.tool = tool;
+        }
+    }
+    if keys.just_pressed(KeyCode::KeyE) {
+        editor.erase = !editor.erase;
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn edit_with_pointer(
+    mut editor: ResMut<HexEditor>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    cursors: Query<&HexGridCursor>,
+    mut grids: Query<
+        (
+            Option<&mut HexGridTerrain>,
+            Option<&mut HexGridElevation>,
+            Option<&mut HexGridOwners>,
+        ),
+        With<HexGridConfig>,
+    >,
+) {
+    let editor = &mut *editor;
+    if editor.grid != editor.loaded {
+        match editor
+            .grid
+            .and_then(|grid| Some((grid, grids.get(grid).ok()?)))
+        {
+            Some((grid, layers)) => editor.load_grid(grid, layers),
+            None => {
+                editor.grid = None;
+                editor.loaded = None;
+                return;
+            }
+        }
+    }
+
+    if editor.enabled {
+        let hovered = cursors
+            .iter()
+            .find_map(|cursor| Some((cursor.grid?, cursor.cell)));
+        if buttons.just_pressed(editor.button) && !editor.over_ui && editor.stroke.is_none() {
+            if let Some((grid, cell)) = hovered {
+                if editor.grid.is_none() {
+                    if let Ok(layers) = grids.get(grid) {
+                        editor.grid = Some(grid);
+                        editor.load_grid(grid, layers);
+                    }
+                }
+                if editor.grid == Some(grid) {
+                    editor.press(cell);
+                }
+            }
+        } else if let Some((_, cell)) = hovered.filter(|&(grid, _)| Some(grid) == editor.grid) {
+            editor.drag(cell);
+        }
+        if !buttons.pressed(editor.button) {
+            editor.release();
+        }
+    }
+
+    let Some(grid) = editor.loaded else { return };
+    let Ok((terrain, elevation, owners)) = grids.get_mut(grid) else {
+        return;
+    };
+    let document = editor.document.as_map();
+    let changed = editor
+        .synced
+        .and_then(|synced| document.changed_chunks(synced))
+        .map(|chunks| chunks.collect::<Vec<_>>());
+    editor.synced = Some(document.version());
+    match changed {
+        Some(chunks) => {
+            let cells = chunks.iter().flat_map(|bounds| bounds.cells());
+            write_cells(document, cells, terrain, elevation, owners);
+        }
+        None => {
+            if let Some(mut terrain) = terrain {
+                terrain.cells = document
+                    .iter()
+                    .filter_map(|(cell, v)| Some((cell, v.terrain?)))
+                    .collect();
+            }
+            if let Some(mut elevation) = elevation {
+                elevation.cells = document
+                    .iter()
+                    .filter_map(|(cell, v)| Some((cell, v.elevation?)))
+                    .collect();
+            }
+            if let Some(mut owners) = owners {
+                *owners = HexGridOwners::new(
+                    document
+                        .iter()
+                        .filter_map(|(cell, v)| Some((cell, v.owner?)))
+                        .collect(),
+                );
+            }
+        }
+    }
+}
+
+// write the layers of `cells` to the grid, touching only those that differ
+// so change detection stays quiet
+fn write_cells(
+    document: &HexMap<EditorCell>,
+    cells: impl Iterator<Item = Axial>,
+    mut terrain: Option<Mut<HexGridTerrain>>,
+    mut elevation: Option<Mut<HexGridElevation>>,
+    mut owners: Option<Mut<HexGridOwners>>,
+) {
+    for cell in cells {
+        let value = document.get(cell).copied().unwrap_or_default();
+        if let Some(terrain) = terrain.as_mut() {
+            if terrain.cells.get(cell).copied() != value.terrain {
+                set(&mut terrain.cells, cell, value.terrain);
+            }
+        }
+        if let Some(elevation) = elevation.as_mut() {
+            if elevation.cells.get(cell).copied() != value.elevation {
+                set(&mut elevation.cells, cell, value.elevation);
+            }
+        }
+        if let Some(owners) = owners.as_mut() {
+            if owners.get(cell) != value.owner {
+                match value.owner {
+                    Some(owner) => owners.set(cell, owner),
+                    None => owners.remove(cell),
+                };
+            }
+        }
+    }
+}
+
+fn entry(document: &mut HexMap<EditorCell>, cell: Axial) -> &mut EditorCell {
+    if !document.contains(cell) {
+        document.insert(cell, EditorCell::default());
+    }
+    document.get_mut(cell).unwrap()
+}
+
+fn set<T>(map: &mut HexMap<T>, cell: Axial, value: Option<T>) {
+    match value {
+        Some(value) => map.insert(cell, value),
+        None => map.remove(cell),
+    };
+}
+
+#[cfg(feature = "editor")]
+mod panel {
+    use bevy::prelude::*;
+    use bevy_inspector_egui::bevy_egui::{egui, EguiContexts};
+
+    use super::{Brush, EditorLayer, EditorTool, HexEditor};
+
+    /// path & result of the last save or load
+    #[derive(Default)]
+    pub(super) struct FileState {
+        path: String,
+        status: String,
+    }
+
+    pub(super) fn editor_panel(
+        mut contexts: EguiContexts,
+        mut editor: ResMut<HexEditor>,
+        mut file: Local<FileState>,
+    ) -> Result {
+        let ctx = contexts.ctx_mut()?;
+        let editor = &mut *editor;
+        let file = &mut *file;
+
+        egui::Window::new("Map editor").show(ctx, |ui| {
+            ui.checkbox(&mut editor.enabled, "enabled");
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                for (tool, label) in [
+                    (EditorTool::Brush, "brush"),
+                    (EditorTool::Fill, "fill"),
+                    (EditorTool::Rectangle, "rectangle"),
+                    (EditorTool::Line, "line"),
+                ] {
+                    ui.selectable_value(&mut editor.tool, tool, label);
+                }
+            });
+            if matches!(editor.tool, EditorTool::Brush | EditorTool::Line) {
+                brush_editor(ui, &mut editor.brush);
+            }
+
+            ui.horizontal(|ui| {
+                for (layer, label) in [
+                    (EditorLayer::Terrain, "terrain"),
+                    (EditorLayer::Elevation, "elevation"),
+                    (EditorLayer::Ownership, "owner"),
+                ] {
+                    ui.selectable_value(&mut editor.layer, layer, label);
+                }
+            });
+            ui.horizontal(|ui| {
+                match editor.layer {
+                    EditorLayer::Terrain => ui.add(egui::DragValue::new(&mut editor.terrain.0)),
+                    EditorLayer::Elevation => {
+                        ui.add(egui::DragValue::new(&mut editor.elevation).speed(0.05))
+                    }
+                    EditorLayer::Ownership => ui.add(egui::DragValue::new(&mut editor.owner.0)),
+                };
+                ui.checkbox(&mut editor.erase, "erase");
+            });
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(editor.can_undo(), egui::Button::new("undo"))
+                    .clicked()
+                {
+                    editor.undo();
+                }
+                if ui
+                    .add_enabled(editor.can_redo(), egui::Button::new("redo"))
+                    .clicked()
+                {
+                    editor.redo();
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut file.path);
+                if ui.button("save").clicked() {
+                    file.status = match editor.save(&file.path) {
+                        Ok(()) => format!("saved {}", file.path),
+                        Err(e) => format!("save failed: {e}"),
+                    };
+                }
+                if ui.button("load").clicked() {
+                    file.status = match editor.load(&file.path) {
+                        Ok(()) => format!("loaded {}", file.path),
+                        Err(e) => format!("load failed: {e}"),
+                    };
+                }
+            });
+            if !file.status.is_empty() {
+                ui.label(file.status.as_str());
+            }
+        });
+
+        editor.over_ui =
+            ctx.wants_pointer_input() || ctx.is_pointer_over_area() || ctx.wants_keyboard_input();
+        Ok(())
+    }
+
+    fn brush_editor(ui: &mut egui::Ui, brush: &mut Brush) {
+        ui.horizontal(|ui| {
+            for (shape, label) in [
+                (Brush::Single, "single"),
+                (Brush::Blob { radius: 1 }, "blob"),
+                (Brush::Line { width: 0 }, "line"),
+                (Brush::Ring { radius: 2 }, "ring"),
+                (
+                    Brush::Scatter {
+                        radius: 3,
+                        density: 0.3,
+                        seed: 0,
+                    },
+                    "scatter",
+                ),
+            ] {
+                let selected = std::mem::discriminant(brush) == std::mem::discriminant(&shape);
+                if ui.selectable_label(selected, label).clicked() && !selected {
+                    *brush = shape;
+                }
+            }
+        });
+        match brush {
+            Brush::Single => (),
+            Brush::Blob { radius } | Brush::Ring { radius } => {
+                ui.add(egui::Slider::new(radius, 0..=8).text("radius"));
+            }
+            Brush::Line { width } => {
+                ui.add(egui::Slider::new(width, 0..=4).text("width"));
+            }
+            Brush::Scatter {
+                radius,
+                density,
+                seed,
+            } => {
+                ui.add(egui::Slider::new(radius, 0..=8).text("radius"));
+                ui.add(egui::Slider::new(density, 0.0..=1.0).text("density"));
+                ui.add(egui::DragValue::new(seed).prefix("seed "));
+            }
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! cliffs between cells of different heights
+//!
+//! The grid is drawn flat, so height differences on the board don't show.
+//! Add a [`HexGridElevation`] to a grid entity with the height of each cell,
+//! and edges between neighbors whose heights differ by at least
+//! [`HexGridElevation::threshold`] are drawn as a thick cliff line, with a
+//! shadow cast on the lower cell:
+//!
+//! ```ignore
+//! let elevation = HexGridElevation::new(heights).with_threshold(1.0);
+//! commands.entity(grid).insert(elevation);
+//! ```
+//!
+//! Cells without a height never have cliffs.  Shadows are drawn over
+//! terrain, highlights, & decals; the cliff line is drawn over the grid
+//! lines.
+//!
+//! A ramp on a cell joins it to the neighbor it rises toward, whatever the
+//! difference in height; the edge between them isn't a cliff, and an arrow
+//! up the ramp is drawn on the cell.  Ramps are only drawn on
+//! [`crate::GridKind::Hex`] grids.  Wrap a pathfinding cost closure with
+//! [`HexGridElevation::step_cost`] so paths only climb by small steps or up
+//! ramps:
+//!
+//! ```ignore
+//! let path = find_path(start, goal, elevation.step_cost(|_, to| terrain_cost(to)));
+//! ```
+use bevy::prelude::*;
+
+use crate::{Axial, HexDirection, HexMap};
+
+/// how a cliff edge is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CliffStyle {
+    pub color: Color,
+    /// width of the cliff line, as a fraction of the cell size
+    pub width: f32,
+    /// shadow on the lower cell, darkest at the edge
+    pub shadow_color: Color,
+    /// how far the shadow reaches into the lower cell, as a fraction of the
+    /// cell size; zero for no shadow
+    pub shadow_width: f32,
+    /// color of the arrows drawn up ramps
+    pub ramp_color: Color,
+}
+
+impl Default for CliffStyle {
+    fn default() -> Self {
+        Self {
+            color: Color::srgb(0.25, 0.2, 0.15),
+            width: 0.12,
+            shadow_color: Color::srgba(0.0, 0.0, 0.0, 0.45),
+            shadow_width: 0.25,
+            ramp_color: Color::srgba(0.9, 0.85, 0.7, 0.8),
+        }
+    }
+}
+
+/// Height of each cell of a grid entity; see the [module docs](self).
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct HexGridElevation {
+    pub cells: HexMap<f32>,
+    /// direction each ramp rises toward, on the cell at its foot
+    pub ramps: HexMap<HexDirection>,
+    /// smallest difference in height between neighbors drawn as a cliff;
+    /// smaller steps can be walked
+    pub threshold: f32,
+    pub cliff: CliffStyle,
+}
+
+impl Default for HexGridElevation {
+    fn default() -> Self {
+        Self {
+            cells: HexMap::new(),
+            ramps: HexMap::new(),
+            threshold: 1.0,
+            cliff: CliffStyle::default(),
+        }
+    }
+}
+
+impl HexGridElevation {
+    pub fn new(cells: HexMap<f32>) -> Self {
+        Self { cells, ..default() }
+    }
+
+    pub fn with_threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn with_cliff(mut self, cliff: CliffStyle) -> Self {
+        self.cliff = cliff;
+        self
+    }
+
+    /// add a ramp on `cell` rising toward its neighbor in `dir`
+    pub fn with_ramp(mut self, cell: Axial, dir: HexDirection) -> Self {
+        self.ramps.insert(cell, dir);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty() && self.ramps.is_empty()
+    }
+
+    pub fn get(&self, cell: Axial) -> Option<f32> {
+        self.cells.get(cell).copied()
+    }
+
+    /// true if a ramp joins the neighboring cells `a` & `b`, from either end
+    pub fn ramp_between(&self, a: Axial, b: Axial) -> bool {
+        let Some(dir) = HexDirection::between(a, b) else {
+            return false;
+        };
+        self.ramps.get(a) == Some(&dir) || self.ramps.get(b) == Some(&dir.opposite())
+    }
+
+    /// Height of the cliff from `cell` down to its neighbor `other`, negative
+    /// if `other` is higher; `None` when there's no cliff between them, or a
+    /// ramp joins them.
+    pub fn cliff(&self, cell: Axial, other: Axial) -> Option<f32> {
+        let drop = self.get(cell)? - self.get(other)?;
+        let cliff = drop != 0.0 && drop.abs() >= self.threshold;
+        (cliff && !self.ramp_between(cell, other)).then_some(drop)
+    }
+
+    /// true if a mover can step between the neighboring cells `from` & `to`;
+    /// any step but down or up a cliff
+    pub fn can_step(&self, from: Axial, to: Axial) -> bool {
+        self.cliff(from, to).is_none()
+    }
+
+    /// Step cost for [`crate::path::find_path`] & the like that blocks steps
+    /// over cliffs, and otherwise costs what `cost` does.
+    pub fn step_cost<'a>(
+        &'a self,
+        mut cost: impl FnMut(Axial, Axial) -> Option<u32> + 'a,
+    ) -> impl FnMut(Axial, Axial) -> Option<u32> + 'a {
+        move |from, to| {
+            if !self.can_step(from, to) {
+                return None;
+            }
+            cost(from, to)
+        }
+    }
+}
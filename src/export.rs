@@ -0,0 +1,286 @@
+//! glTF export of maps
+//!
+//! [`hex_map_glb`] writes the cells of a [`HexMap`] as a binary glTF
+//! (`.glb`), to inspect a generated board in an external tool, or bake it
+//! offline.  Each cell is a prism: its top at a height given per cell, with
+//! walls down to the grid plane, colored by terrain with vertex colors:
+//!
+//! ```ignore
+//! let glb = hex_map_glb(&terrain, &config, GltfMeshes::PerChunk, |_, t| GltfCell {
+//!     color: t.color(),
+//!     height: t.elevation() * 0.25,
+//! });
+//! std::fs::write("board.glb", glb)?;
+//! ```
+//!
+//! Positions are in the local frame of the grid, with Y up as in bevy &
+//! glTF.
+use std::fmt::Write;
+
+use bevy::prelude::*;
+
+use crate::{map::chunk_index, Axial, HexGridConfig, HexMap};
+
+const GLB_MAGIC: u32 = 0x4654_6c67;
+const CHUNK_JSON: u32 = 0x4e4f_534a;
+const CHUNK_BIN: u32 = 0x004e_4942;
+
+// glTF constants
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+/// how cells are grouped into meshes
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GltfMeshes {
+    /// a single mesh holding every cell
+    #[default]
+    Merged,
+    /// one mesh, and node, per [`HexMap`] chunk
+    PerChunk,
+}
+
+/// appearance of an exported cell
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GltfCell {
+    pub color: Color,
+    /// height of the top of the cell above the grid plane, world units; no
+    /// walls are built for cells at or below zero
+    pub height: f32,
+}
+
+/// Binary glTF of every cell in `map`, each drawn as `cell` returns.  See
+/// the [module docs](self).
+pub fn hex_map_glb<T>(
+    map: &HexMap<T>,
+    config: &HexGridConfig,
+    meshes: GltfMeshes,
+    cell: impl Fn(Axial, &T) -> GltfCell,
+) -> Vec<u8> {
+    // sorted so the same map always exports the same file
+    let mut cells: Vec<(Axial, Axial, &T)> = map
+        .iter()
+        .map(|(c, value)| {
+            let group = match meshes {
+                GltfMeshes::Merged => Axial::ZERO,
+                GltfMeshes::PerChunk => chunk_index(c).0,
+            };
+            (group, c, value)
+        })
+        .collect();
+    cells.sort_by_key(|&(group, c, _)| (group.r, group.q, c.r, c.q));
+
+    let mut builder = GltfBuilder::default();
+    for group in cells.chunk_by(|a, b| a.0 == b.0) {
+        let mut prisms = Prisms::default();
+        for &(_, c, value) in group {
+            prisms.add(config, c, cell(c, value));
+        }
+        let name = match meshes {
+            GltfMeshes::Merged => "hex_map".to_string(),
+            GltfMeshes::PerChunk => format!("chunk {} {}", group[0].0.q, group[0].0.r),
+        };
+        builder.mesh(&name, &prisms);
+    }
+    builder.glb()
+}
+
+/// vertices & triangles of cell prisms
+#[derive(Default)]
+struct Prisms {
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    colors: Vec<Vec4>,
+    indices: Vec<u32>,
+}
+
+impl Prisms {
+    fn add(&mut self, config: &HexGridConfig, c: Axial, cell: GltfCell) {
+        let color = cell.color.to_linear().to_vec4();
+        let corners = config.cell_corners(c);
+        let top = |p: Vec2| Vec3::new(p.x, cell.height, p.y);
+
+        // top; corners are counterclockwise from X towards Z, so the fan is
+        // wound backwards to face +Y
+        let first = self.positions.len() as u32;
+        for &corner in &corners {
+            self.vertex(top(corner), Vec3::Y, color);
+        }
+        for i in 1..corners.len() as u32 - 1 {
+            self.indices.extend([first, first + i + 1, first + i]);
+        }
+
+        if cell.height <= 0.0 {
+            return;
+        }
+        for (i, &a) in corners.iter().enumerate() {
+            let b = corners[(i + 1) % corners.len()];
+            let edge = b - a;
+            let normal = Vec3::new(edge.y, 0.0, -edge.x).normalize();
+            let first = self.positions.len() as u32;
+            for p in [top(a), top(b), b.extend(0.0).xzy(), a.extend(0.0).xzy()] {
+                self.vertex(p, normal, color);
+            }
+            self.indices
+                .extend([first, first + 1, first + 2, first, first + 2, first + 3]);
+        }
+    }
+
+    fn vertex(&mut self, position: Vec3, normal: Vec3, color: Vec4) {
+        self.positions.push(position);
+        self.normals.push(normal);
+        self.colors.push(color);
+    }
+}
+
+/// glTF json & binary buffer, built up one mesh at a time
+#[derive(Default)]
+struct GltfBuilder {
+    bin: Vec<u8>,
+    buffer_views: Vec<String>,
+    accessors: Vec<String>,
+    meshes: Vec<String>,
+    nodes: Vec<String>,
+}
+
+impl GltfBuilder {
+    fn mesh(&mut self, name: &str, prisms: &Prisms) {
+        let (min, max) = prisms
+            .positions
+            .iter()
+            .fold((Vec3::MAX, Vec3::MIN), |(min, max), &p| {
+                (min.min(p), max.max(p))
+            });
+        let position = self.accessor(
+            flatten(&prisms.positions),
+            prisms.positions.len(),
+            "VEC3",
+            FLOAT,
+            ARRAY_BUFFER,
+            Some((min, max)),
+        );
+        let normal = self.accessor(
+            flatten(&prisms.normals),
+            prisms.normals.len(),
+            "VEC3",
+            FLOAT,
+            ARRAY_BUFFER,
+            None,
+        );
+        let color = self.accessor(
+            flatten(&prisms.colors),
+            prisms.colors.len(),
+            "VEC4",
+            FLOAT,
+            ARRAY_BUFFER,
+            None,
+        );
+        let indices = self.accessor(
+            prisms
+                .indices
+                .iter()
+                .flat_map(|i| i.to_le_bytes())
+                .collect(),
+            prisms.indices.len(),
+            "SCALAR",
+            UNSIGNED_INT,
+            ELEMENT_ARRAY_BUFFER,
+            None,
+        );
+
+        let mesh = self.meshes.len();
+        self.meshes.push(format!(
+            r#"{{"name":{name:?},"primitives":[{{"attributes":{{"POSITION":{position},"NORMAL":{normal},"COLOR_0":{color}}},"indices":{indices},"mode":4}}]}}"#
+        ));
+        self.nodes
+            .push(format!(r#"{{"name":{name:?},"mesh":{mesh}}}"#));
+    }
+
+    // append data to the binary buffer with its own buffer view & accessor,
+    // and return the accessor index
+    fn accessor(
+        &mut self,
+        data: Vec<u8>,
+        count: usize,
+        kind: &str,
+        component: u32,
+        target: u32,
+        bounds: Option<(Vec3, Vec3)>,
+    ) -> usize {
+        // every component is 4 bytes, so views stay aligned
+        let view = self.buffer_views.len();
+        self.buffer_views.push(format!(
+            r#"{{"buffer":0,"byteOffset":{},"byteLength":{},"target":{target}}}"#,
+            self.bin.len(),
+            data.len(),
+        ));
+        self.bin.extend(data);
+
+        let mut accessor = format!(
+            r#"{{"bufferView":{view},"componentType":{component},"count":{count},"type":"{kind}""#
+        );
+        if let Some((min, max)) = bounds {
+            let _ = write!(
+                accessor,
+                r#","min":[{},{},{}],"max":[{},{},{}]"#,
+                min.x, min.y, min.z, max.x, max.y, max.z
+            );
+        }
+        accessor.push('}');
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn glb(self) -> Vec<u8> {
+        let nodes: Vec<String> = (0..self.nodes.len()).map(|i| i.to_string()).collect();
+        let mut json = format!(
+            r#"{{"asset":{{"version":"2.0","generator":"hex_grid"}},"scene":0,"scenes":[{{"nodes":[{}]}}]"#,
+            nodes.join(",")
+        );
+        // glTF doesn't allow empty arrays, or an empty buffer
+        if !self.meshes.is_empty() {
+            let _ = write!(
+                json,
+                r#","nodes":[{}],"meshes":[{}],"accessors":[{}],"bufferViews":[{}],"buffers":[{{"byteLength":{}}}]"#,
+                self.nodes.join(","),
+                self.meshes.join(","),
+                self.accessors.join(","),
+                self.buffer_views.join(","),
+                self.bin.len(),
+            );
+        }
+        json.push('}');
+
+        let mut json = json.into_bytes();
+        json.resize(json.len().next_multiple_of(4), b' ');
+        let mut bin = self.bin;
+        bin.resize(bin.len().next_multiple_of(4), 0);
+
+        let mut length = 12 + 8 + json.len();
+        if !bin.is_empty() {
+            length += 8 + bin.len();
+        }
+        let mut glb = Vec::with_capacity(length);
+        for word in [GLB_MAGIC, 2, length as u32] {
+            glb.extend(word.to_le_bytes());
+        }
+        for (kind, data) in [(CHUNK_JSON, json), (CHUNK_BIN, bin)] {
+            if data.is_empty() {
+                continue;
+            }
+            glb.extend((data.len() as u32).to_le_bytes());
+            glb.extend(kind.to_le_bytes());
+            glb.extend(data);
+        }
+        glb
+    }
+}
+
+fn flatten<const N: usize, T: Copy + Into<[f32; N]>>(values: &[T]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|&v| v.into())
+        .flat_map(f32::to_le_bytes)
+        .collect()
+}
@@ -0,0 +1,154 @@
+//! pattern fills for cells
+//!
+//! Add a [`HexGridFills`] to a grid entity to fill some of its cells with a
+//! hatch, stripes, crosshatch, or dots; diagonal hatching over blocked cells,
+//! stripes over contested ones.  Patterns tell cells apart where color
+//! alone is hard to read, such as for colorblind players.  Each kind of fill
+//! is a [`CellFill`], and each cell is given the index of its kind:
+//!
+//! ```ignore
+//! let mut fills = HexGridFills::new([
+//!     CellFill::new(FillPattern::Hatch).with_color(Color::srgba(0.9, 0.2, 0.2, 0.8)),
+//!     CellFill::new(FillPattern::Stripes),
+//! ]);
+//! fills.set_region(blocked, FillId(0));
+//! fills.set_region(contested, FillId(1));
+//! commands.entity(grid).insert(fills);
+//! ```
+//!
+//! Patterns are laid out on the grid plane rather than each cell, so they
+//! run on unbroken across neighboring cells of the same fill.  Fills are
+//! drawn over cell highlights, and under distance rings, decals, & the grid
+//! lines.
+use bevy::prelude::*;
+
+use crate::{Axial, HexMap};
+
+/// kind of fill on a cell; index into [`HexGridFills::kinds`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FillId(pub u8);
+
+/// pattern drawn by a [`CellFill`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FillPattern {
+    /// thin diagonal lines
+    #[default]
+    Hatch,
+    /// wide bands
+    Stripes,
+    /// thin lines crossing at right angles
+    Crosshatch,
+    /// round dots in rows
+    Dots,
+}
+
+/// a kind of pattern drawn over its cells
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CellFill {
+    pub pattern: FillPattern,
+    pub color: Color,
+    /// distance between lines or dots, as a fraction of the cell size
+    pub spacing: f32,
+    /// width of a line, or diameter of a dot, as a fraction of `spacing`
+    pub width: f32,
+    /// angle of the lines from the grid's local X axis, in radians
+    pub angle: f32,
+}
+
+impl CellFill {
+    /// a fill with the usual spacing, width, & angle of `pattern`
+    pub fn new(pattern: FillPattern) -> Self {
+        let (spacing, width, angle) = match pattern {
+            FillPattern::Hatch => (0.15, 0.25, 45.0),
+            FillPattern::Stripes => (0.3, 0.5, 0.0),
+            FillPattern::Crosshatch => (0.2, 0.2, 45.0),
+            FillPattern::Dots => (0.2, 0.4, 45.0),
+        };
+        Self {
+            pattern,
+            color: Color::srgba(0.1, 0.1, 0.1, 0.6),
+            spacing,
+            width,
+            angle: f32::to_radians(angle),
+        }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_spacing(mut self, spacing: f32) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    pub fn with_width(mut self, width: f32) -> Self {
+        self.width = width;
+        self
+    }
+
+    pub fn with_angle(mut self, angle: f32) -> Self {
+        self.angle = angle;
+        self
+    }
+
+    /// True if the pattern covers a point on the grid plane, in cell sizes
+    /// from the grid origin.  Mirrors `fill_coverage()` in the grid shader,
+    /// without anti-aliasing.
+    pub fn covers(&self, point: Vec2) -> bool {
+        let spacing = self.spacing.max(0.001);
+        let half = self.width * spacing * 0.5;
+        let dir = Vec2::from_angle(self.angle);
+        // distance to the nearest line across & along `dir`
+        let offset = |d: f32| ((d / spacing + 0.5).rem_euclid(1.0) - 0.5) * spacing;
+        let across = offset(point.dot(dir));
+        let along = offset(point.dot(dir.perp()));
+        match self.pattern {
+            FillPattern::Hatch | FillPattern::Stripes => across.abs() < half,
+            FillPattern::Crosshatch => across.abs() < half || along.abs() < half,
+            FillPattern::Dots => across.hypot(along) < half,
+        }
+    }
+}
+
+/// Pattern fills over the cells of a grid entity; see the
+/// [module docs](self).  Cells of a kind past [`HexGridFills::MAX_KINDS`],
+/// or one that isn't in `kinds`, are drawn without a fill.
+#[derive(Component, Debug, Default, Clone)]
+pub struct HexGridFills {
+    pub kinds: Vec<CellFill>,
+    pub cells: HexMap<FillId>,
+}
+
+impl HexGridFills {
+    pub const MAX_KINDS: usize = 8;
+
+    pub fn new(kinds: impl IntoIterator<Item = CellFill>) -> Self {
+        Self {
+            kinds: kinds.into_iter().collect(),
+            cells: HexMap::new(),
+        }
+    }
+
+    /// set the fill of every cell in `region`
+    pub fn set_region(&mut self, region: impl IntoIterator<Item = Axial>, id: FillId) {
+        self.cells.extend(region.into_iter().map(|cell| (cell, id)));
+    }
+
+    /// clear the fill from every cell in `region`
+    pub fn clear_region(&mut self, region: impl IntoIterator<Item = Axial>) {
+        for cell in region {
+            self.cells.remove(cell);
+        }
+    }
+
+    /// fill drawn on `cell`, if any
+    pub fn get(&self, cell: Axial) -> Option<&CellFill> {
+        let id = self.cells.get(cell)?.0 as usize;
+        self.kinds.get(id).filter(|_| id < Self::MAX_KINDS)
+    }
+}
@@ -0,0 +1,447 @@
+//! render world side of [`crate::glow`]
+//!
+//! Each view that draws a grid with a [`HexGridGlow`] gets three textures
+//! from the texture cache at half the view's resolution.  The grid node
+//! draws the texels over glowing cells into them as seeds, with the color &
+//! width of their glow, using the `glow_seed` entry point of the grid
+//! shader.  A jump flood then finds the nearest seed of every texel, in
+//! compute passes ping-ponging between the two seed textures, and the glow
+//! of the nearest seed is added over the view in a fullscreen pass.
+//!
+//! The flood takes a pass for each power of two up to the widest glow on
+//! the view, and one more with a step of one texel to fix the few texels a
+//! plain jump flood gets wrong.
+use bevy::{
+    camera::visibility::RenderLayers,
+    prelude::*,
+    render::{
+        camera::ExtractedCamera,
+        render_resource::{
+            binding_types::{texture_2d, texture_storage_2d, uniform_buffer},
+            BindGroup, BindGroupEntries, BindGroupLayoutDescriptor, BindGroupLayoutEntries,
+            BlendComponent, BlendFactor, BlendOperation, BlendState, CachedComputePipelineId,
+            CachedRenderPipelineId, ColorTargetState, ColorWrites, ComputePassDescriptor,
+            ComputePipelineDescriptor, DynamicUniformBuffer, Extent3d, FragmentState, LoadOp,
+            MultisampleState, Operations, PipelineCache, RenderPassColorAttachment,
+            RenderPassDescriptor, RenderPipelineDescriptor, ShaderStages, ShaderType,
+            StorageTextureAccess, StoreOp, TextureDescriptor, TextureDimension, TextureFormat,
+            TextureSampleType, TextureUsages, VertexState,
+        },
+        renderer::{RenderContext, RenderDevice, RenderQueue},
+        texture::{CachedTexture, TextureCache},
+        view::{ExtractedView, ViewTarget},
+    },
+};
+
+use crate::{
+    glow::HexGridGlow,
+    render::{ExtractedHexGrid, HexGridBindGroupCache},
+};
+
+/// view pixels per texel of the glow textures; `GLOW_SCALE` in the shaders
+pub(crate) const GLOW_SCALE: u32 = 2;
+
+/// nearest seed of each texel, as its texel center
+pub(crate) const SEED_FORMAT: TextureFormat = TextureFormat::Rg32Float;
+
+/// glow of each seed, as (color * intensity, width)
+pub(crate) const COLOR_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+const WORKGROUP_SIZE: u32 = 8;
+
+// step of one flood pass, in texels
+#[derive(Debug, ShaderType, Clone, Copy)]
+struct GpuFlood {
+    step: i32,
+}
+
+/// glow textures of a view; only present on views that draw a grid with a
+/// glow
+#[derive(Component)]
+pub(crate) struct ViewHexGridGlow {
+    seeds: [CachedTexture; 2],
+    colors: CachedTexture,
+    size: UVec2,
+    /// dynamic offset of the step of each flood pass, in order
+    steps: Vec<u32>,
+    hdr: bool,
+    /// pass `i` reads `seeds[i % 2]` with `flood[i % 2]`, and writes the
+    /// other
+    flood: Option<[BindGroup; 2]>,
+    /// reads the seeds written by the last flood pass
+    composite: Option<BindGroup>,
+}
+
+impl ViewHexGridGlow {
+    /// the seed texture the flood ends in
+    fn flooded(&self) -> &CachedTexture {
+        &self.seeds[self.steps.len() % 2]
+    }
+}
+
+/// Steps of the flood passes for a glow reaching `width` texels; halving
+/// from the power of two at or over `width`, then one more pass of one.
+fn flood_steps(width: f32) -> impl Iterator<Item = u32> {
+    let widest = (width.ceil().max(1.0) as u32).next_power_of_two();
+    std::iter::successors(Some(widest), |&step| (step > 1).then_some(step / 2))
+        .chain(std::iter::once(1))
+}
+
+pub(crate) fn prepare_hex_grid_glows(
+    mut commands: Commands,
+    mut texture_cache: ResMut<TextureCache>,
+    pipeline: Res<HexGridGlowPipeline>,
+    grids: Query<&ExtractedHexGrid>,
+    views: Query<(
+        Entity,
+        &ExtractedCamera,
+        &ExtractedView,
+        Option<&RenderLayers>,
+    )>,
+    render_device: Res<RenderDevice>,
+) {
+    for (view, camera, extracted_view, view_layers) in &views {
+        let view_layers = view_layers.cloned().unwrap_or_default();
+        let width = grids
+            .iter()
+            .filter(|grid| view_layers.intersects(&grid.layers))
+            .filter_map(|grid| grid.glow_width)
+            .reduce(f32::max);
+        let (Some(width), Some(size)) = (width, camera.physical_target_size) else {
+            commands.entity(view).remove::<ViewHexGridGlow>();
+            continue;
+        };
+
+        let size = (size + GLOW_SCALE - 1) / GLOW_SCALE;
+        let mut texture = |label, format, usage| {
+            texture_cache.get(
+                &render_device,
+                TextureDescriptor {
+                    label: Some(label),
+                    size: Extent3d {
+                        width: size.x,
+                        height: size.y,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format,
+                    usage: usage | TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                },
+            )
+        };
+        let seed_usage = TextureUsages::RENDER_ATTACHMENT | TextureUsages::STORAGE_BINDING;
+        let seeds = [
+            texture("hex_grid_glow_seeds_a", SEED_FORMAT, seed_usage),
+            texture("hex_grid_glow_seeds_b", SEED_FORMAT, seed_usage),
+        ];
+        let colors = texture(
+            "hex_grid_glow_colors",
+            COLOR_FORMAT,
+            TextureUsages::RENDER_ATTACHMENT,
+        );
+
+        let steps = flood_steps(width / GLOW_SCALE as f32)
+            .map(|step| pipeline.step_offset(step))
+            .collect();
+
+        commands.entity(view).insert(ViewHexGridGlow {
+            seeds,
+            colors,
+            size,
+            steps,
+            hdr: extracted_view.hdr,
+            flood: None,
+            composite: None,
+        });
+    }
+}
+
+pub(crate) fn queue_hex_grid_glow_bind_groups(
+    mut views: Query<&mut ViewHexGridGlow>,
+    mut cache: ResMut<HexGridBindGroupCache>,
+    pipeline: Res<HexGridGlowPipeline>,
+    pipeline_cache: Res<PipelineCache>,
+    render_device: Res<RenderDevice>,
+) {
+    let Some(steps) = pipeline.steps.binding() else {
+        return;
+    };
+    let Some(steps_buffer) = pipeline.steps.buffer() else {
+        return;
+    };
+    let flood_layout = pipeline_cache.get_bind_group_layout(&pipeline.flood_layout);
+    let composite_layout = pipeline_cache.get_bind_group_layout(&pipeline.composite_layout);
+    for mut glow in &mut views {
+        let flood = [0, 1].map(|i| {
+            let (read, write) = (&glow.seeds[i].default_view, &glow.seeds[1 - i].default_view);
+            let ids = [read.into(), write.into(), steps_buffer.into()];
+            cache.get_or_create(&flood_layout, &ids, || {
+                render_device.create_bind_group(
+                    "hex_grid_glow_flood_bind_group",
+                    &flood_layout,
+                    &BindGroupEntries::sequential((read, write, steps.clone())),
+                )
+            })
+        });
+
+        let seeds = &glow.flooded().default_view;
+        let colors = &glow.colors.default_view;
+        let ids = [seeds.into(), colors.into()];
+        let composite = cache.get_or_create(&composite_layout, &ids, || {
+            render_device.create_bind_group(
+                "hex_grid_glow_composite_bind_group",
+                &composite_layout,
+                &BindGroupEntries::with_indices(((3, seeds), (4, colors))),
+            )
+        });
+
+        glow.flood = Some(flood);
+        glow.composite = Some(composite);
+    }
+}
+
+/// Draw the seeds of the grids in `seed_draws` into the view's glow
+/// textures, flood them, & add the glow over the view.  Each draw is the
+/// `glow_seed` pipeline & bind group of a grid.
+pub(crate) fn draw_hex_grid_glow<'a>(
+    render_context: &mut RenderContext,
+    camera: &ExtractedCamera,
+    view_target: &ViewTarget,
+    glow: &ViewHexGridGlow,
+    seed_draws: impl Iterator<Item = (CachedRenderPipelineId, &'a BindGroup)>,
+    view_constants: Option<&[u8]>,
+    world: &World,
+) {
+    let pipeline_cache = world.resource::<PipelineCache>();
+    let pipeline = world.resource::<HexGridGlowPipeline>();
+    let (Some(flood), Some(composite)) = (&glow.flood, &glow.composite) else {
+        return;
+    };
+    let Some(flood_pipeline) = pipeline_cache.get_compute_pipeline(pipeline.flood) else {
+        return;
+    };
+    let Some(composite_pipeline) =
+        pipeline_cache.get_render_pipeline(pipeline.composite[usize::from(glow.hdr)])
+    else {
+        return;
+    };
+
+    // seeds are cleared to "no seed"
+    let no_seed = LinearRgba::new(-1.0, -1.0, 0.0, 0.0);
+    let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+        label: Some("hex_grid_glow_seed_pass"),
+        color_attachments: &[
+            Some(RenderPassColorAttachment {
+                view: &glow.seeds[0].default_view,
+                depth_slice: None,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(no_seed.into()),
+                    store: StoreOp::Store,
+                },
+            }),
+            Some(RenderPassColorAttachment {
+                view: &glow.colors.default_view,
+                depth_slice: None,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(default()),
+                    store: StoreOp::Store,
+                },
+            }),
+        ],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+    // the camera viewport, in glow texels
+    if let Some(viewport) = camera.viewport.as_ref() {
+        let mut viewport = viewport.clone();
+        viewport.physical_position /= GLOW_SCALE;
+        viewport.physical_size = viewport
+            .physical_size
+            .map(|x| x.div_ceil(GLOW_SCALE))
+            .min(glow.size.saturating_sub(viewport.physical_position));
+        render_pass.set_camera_viewport(&viewport);
+    }
+    for (pipeline_id, bind_group) in seed_draws {
+        let Some(pipeline) = pipeline_cache.get_render_pipeline(pipeline_id) else {
+            continue;
+        };
+        render_pass.set_render_pipeline(pipeline);
+        if let Some(constants) = view_constants {
+            render_pass.set_push_constants(ShaderStages::VERTEX_FRAGMENT, 0, constants);
+        }
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+    drop(render_pass);
+
+    let mut pass = render_context
+        .command_encoder()
+        .begin_compute_pass(&ComputePassDescriptor {
+            label: Some("hex_grid_glow_flood_pass"),
+            timestamp_writes: None,
+        });
+    pass.set_pipeline(flood_pipeline);
+    let groups = (glow.size + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE;
+    for (i, &offset) in glow.steps.iter().enumerate() {
+        pass.set_bind_group(0, &flood[i % 2], &[offset]);
+        pass.dispatch_workgroups(groups.x, groups.y, 1);
+    }
+    drop(pass);
+
+    let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+        label: Some("hex_grid_glow_pass"),
+        color_attachments: &[Some(view_target.get_color_attachment())],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+    if let Some(viewport) = camera.viewport.as_ref() {
+        render_pass.set_camera_viewport(viewport);
+    }
+    render_pass.set_render_pipeline(composite_pipeline);
+    render_pass.set_bind_group(0, composite, &[]);
+    render_pass.draw(0..3, 0..1);
+}
+
+#[derive(Resource)]
+pub(crate) struct HexGridGlowPipeline {
+    flood_layout: BindGroupLayoutDescriptor,
+    composite_layout: BindGroupLayoutDescriptor,
+    flood: CachedComputePipelineId,
+    /// for ldr & hdr view targets
+    composite: [CachedRenderPipelineId; 2],
+    /// every flood step, from 1 to the widest glow; written once
+    steps: DynamicUniformBuffer<GpuFlood>,
+    step_offsets: Vec<u32>,
+}
+
+impl HexGridGlowPipeline {
+    /// dynamic offset of a power of two `step` in `steps`
+    fn step_offset(&self, step: u32) -> u32 {
+        self.step_offsets[step.trailing_zeros() as usize]
+    }
+}
+
+impl FromWorld for HexGridGlowPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let shader = world.resource::<AssetServer>().load("hex_grid_glow.wgsl");
+
+        let flood_layout = BindGroupLayoutDescriptor::new(
+            "hex_grid_glow_flood_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::COMPUTE,
+                (
+                    texture_2d(TextureSampleType::Float { filterable: false }),
+                    texture_storage_2d(SEED_FORMAT, StorageTextureAccess::WriteOnly),
+                    uniform_buffer::<GpuFlood>(true),
+                ),
+            ),
+        );
+        // bound after the flood's bindings in the shader
+        let composite_layout = BindGroupLayoutDescriptor::new(
+            "hex_grid_glow_composite_bind_group_layout",
+            &BindGroupLayoutEntries::with_indices(
+                ShaderStages::FRAGMENT,
+                (
+                    (
+                        3,
+                        texture_2d(TextureSampleType::Float { filterable: false }),
+                    ),
+                    (
+                        4,
+                        texture_2d(TextureSampleType::Float { filterable: false }),
+                    ),
+                ),
+            ),
+        );
+
+        let mut steps = DynamicUniformBuffer::default();
+        let max_step = flood_steps(HexGridGlow::MAX_WIDTH / GLOW_SCALE as f32)
+            .next()
+            .unwrap_or(1);
+        let step_offsets = std::iter::successors(Some(1), |&step| Some(step * 2))
+            .take_while(|&step| step <= max_step)
+            .map(|step| steps.push(&GpuFlood { step: step as i32 }))
+            .collect();
+        steps.write_buffer(
+            world.resource::<RenderDevice>(),
+            world.resource::<RenderQueue>(),
+        );
+
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let flood = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+            label: Some("hex_grid_glow_flood_pipeline".into()),
+            layout: vec![flood_layout.clone()],
+            push_constant_ranges: Vec::new(),
+            shader: shader.clone(),
+            shader_defs: vec![],
+            entry_point: Some("flood_step".into()),
+            zero_initialize_workgroup_memory: false,
+        });
+
+        // the glow is added to the view, leaving its alpha alone
+        let blend = BlendState {
+            color: BlendComponent {
+                src_factor: BlendFactor::One,
+                dst_factor: BlendFactor::One,
+                operation: BlendOperation::Add,
+            },
+            alpha: BlendComponent {
+                src_factor: BlendFactor::Zero,
+                dst_factor: BlendFactor::One,
+                operation: BlendOperation::Add,
+            },
+        };
+        let composite = [
+            TextureFormat::bevy_default(),
+            ViewTarget::TEXTURE_FORMAT_HDR,
+        ]
+        .map(|format| {
+            pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
+                label: Some("hex_grid_glow_composite_pipeline".into()),
+                layout: vec![composite_layout.clone()],
+                push_constant_ranges: Vec::new(),
+                vertex: VertexState {
+                    shader: shader.clone(),
+                    shader_defs: vec![],
+                    entry_point: Some("vertex".into()),
+                    buffers: vec![],
+                },
+                primitive: default(),
+                depth_stencil: None,
+                // drawn into the same target as the grid pass
+                multisample: MultisampleState {
+                    count: 4,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                fragment: Some(FragmentState {
+                    shader: shader.clone(),
+                    shader_defs: vec![],
+                    entry_point: Some("composite".into()),
+                    targets: vec![Some(ColorTargetState {
+                        format,
+                        blend: Some(blend),
+                        write_mask: ColorWrites::ALL,
+                    })],
+                }),
+                zero_initialize_workgroup_memory: false,
+            })
+        });
+
+        Self {
+            flood_layout,
+            composite_layout,
+            flood,
+            composite,
+            steps,
+            step_offsets,
+        }
+    }
+}
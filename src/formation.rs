@@ -0,0 +1,346 @@
+//! group movement in formation
+//!
+//! [`group_paths`] plans paths for a group of units ordered to the same
+//! goal, such as a box selection.  Rather than each unit taking its own
+//! cheapest path, which funnels the group into a single-file line, the
+//! group follows one [`FlowField`] toward the goal and each unit keeps to
+//! its place in a [`Formation`] around the cell leading the way:
+//!
+//! ```ignore
+//! let cells: Vec<Axial> = selected.iter().map(|(_, pos)| pos.cell).collect();
+//! let paths = formation::group_paths(&cells, goal, Formation::Wedge, |cell| costs.get(cell).copied());
+//! for ((unit, _), path) in selected.iter().zip(paths) {
+//!     commands.entity(unit).insert(FollowPath::new(path, 3.0));
+//! }
+//! ```
+//!
+//! The paths advance together one cell per step, and no two units are on
+//! the same cell, or swap cells, at the same step.  A cell repeated in a
+//! path is a step spent waiting for another unit to move out of the way,
+//! so give every unit the same speed to keep them in step.  Where the
+//! formation doesn't fit, such as through a narrow pass, units squeeze in
+//! as close to their place as they can, and spread out again after.
+use bevy::{
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
+
+use crate::{
+    path::{find_path, FlowField},
+    shape::{cone, line},
+    Axial, HexDirection,
+};
+
+/// arrangement of a group of units around the cell leading the way
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Formation {
+    /// packed around the leader, ring by ring
+    #[default]
+    Cluster,
+    /// abreast of the leader, across the direction of travel
+    Line,
+    /// two abreast, behind the leader
+    Column,
+    /// widening behind the leader, one more unit in each row
+    Wedge,
+}
+
+impl Formation {
+    /// Cell of each of `count` units relative to the leader, when facing
+    /// `facing`.  The leader's own cell comes first, and places nearer the
+    /// leader before those further out.
+    pub fn offsets(self, count: usize, facing: HexDirection) -> Vec<Axial> {
+        let [_, right, back_right, back, ..] = Axial::NEIGHBORS;
+        let offsets: Vec<Axial> = match self {
+            Formation::Cluster => Axial::ZERO.spiral(count as u32).take(count).collect(),
+            Formation::Line => (0..count as i32)
+                .map(|i| {
+                    // alternate sides, stepping right & back-right in turn
+                    // to stay square to the direction of travel
+                    let k = (i + 1) / 2;
+                    let side = right * ((k + 1) / 2) + back_right * (k / 2);
+                    if i % 2 == 0 {
+                        -side
+                    } else {
+                        side
+                    }
+                })
+                .collect(),
+            Formation::Column => (0..count as i32)
+                .map(|i| back * (i / 2) + right * (i % 2))
+                .collect(),
+            Formation::Wedge => {
+                let mut cells: Vec<Axial> = cone(Axial::ZERO, HexDirection::new(3), count as u32)
+                    .into_iter()
+                    .collect();
+                cells.sort_by_key(|&c| (c.length(), c.r.abs(), c.r));
+                [Axial::ZERO].into_iter().chain(cells).take(count).collect()
+            }
+        };
+        offsets
+            .into_iter()
+            .map(|offset| offset.rotate_around(Axial::ZERO, facing.index() as i32))
+            .collect()
+    }
+}
+
+/// Paths for the units at `units` to move to `goal` together, ending in
+/// `formation` around it, in the same order as `units`.  Each path starts
+/// on the unit's cell & takes one cell per step; see the
+/// [module docs](self).  `cost(cell)` is the cost of stepping into a cell,
+/// or `None` if it's blocked; it must be `None` outside the playable area,
+/// as for [`FlowField`].  Units that can't reach the goal stay where they
+/// are.
+pub fn group_paths(
+    units: &[Axial],
+    goal: Axial,
+    formation: Formation,
+    cost: impl Fn(Axial) -> Option<u32>,
+) -> Vec<Vec<Axial>> {
+    let field = FlowField::new([goal], &cost);
+    let mut paths: Vec<Vec<Axial>> = units.iter().map(|&cell| vec![cell]).collect();
+
+    // the unit nearest the middle of the group leads, along the field
+    let Some(leader) = units
+        .iter()
+        .filter(|&&cell| field.cost(cell).is_some())
+        .min_by_key(|&&cell| units.iter().map(|&c| c.distance(cell)).sum::<u32>())
+    else {
+        return paths;
+    };
+    let mut route = vec![*leader];
+    while let Some(dir) = field.direction(*route.last().unwrap()) {
+        route.push(*route.last().unwrap() + dir);
+    }
+    // direction faced at each cell of the route
+    let start_facing = heading(*leader, goal).unwrap_or_default();
+    let facings: Vec<HexDirection> = (0..route.len())
+        .map(|i| match i {
+            0 => start_facing,
+            _ => HexDirection::between(route[i - 1], route[i]).unwrap_or(start_facing),
+        })
+        .collect();
+    let offsets = |step: usize| formation.offsets(units.len(), facings[step]);
+
+    // places are handed out nearest first, so units don't cross the group
+    // to reach theirs
+    let start: Vec<Axial> = offsets(0).into_iter().map(|o| *leader + o).collect();
+    let mut pairs: Vec<(u32, usize, usize)> = paths
+        .iter()
+        .enumerate()
+        .filter(|(_, path)| field.cost(path[0]).is_some())
+        .flat_map(|(unit, path)| {
+            let cell = path[0];
+            start
+                .iter()
+                .enumerate()
+                .map(move |(place, &p)| (cell.distance(p), unit, place))
+        })
+        .collect();
+    pairs.sort();
+    let mut places: HashMap<usize, usize> = HashMap::new();
+    let mut taken = HashSet::new();
+    for (_, unit, place) in pairs {
+        if !places.contains_key(&unit) && taken.insert(place) {
+            places.insert(unit, place);
+        }
+    }
+
+    // final cell of each unit; places that are blocked move to the nearest
+    // open cell
+    let last = route.len() - 1;
+    let end = offsets(last);
+    let mut claimed = HashSet::new();
+    let mut slots: HashMap<usize, Axial> = HashMap::new();
+    let mut by_place: Vec<(usize, usize)> = places.iter().map(|(&u, &p)| (p, u)).collect();
+    by_place.sort();
+    for (place, unit) in by_place {
+        let wanted = goal + end[place];
+        // the open cell nearest both the place & the goal, so a blocked
+        // place doesn't move across a wall
+        let slot = wanted
+            .spiral(units.len() as u32 + 2)
+            .filter(|cell| !claimed.contains(cell))
+            .filter_map(|cell| {
+                let total = field.cost(cell)? + cell.distance(wanted);
+                Some((total, cell.distance(wanted), cell))
+            })
+            .min()
+            .map_or(goal, |(_, _, cell)| cell);
+        claimed.insert(slot);
+        slots.insert(unit, slot);
+    }
+
+    // step the group together; units further along the route move first so
+    // those behind don't block them
+    let mut order: Vec<usize> = slots.keys().copied().collect();
+    order.sort_by_key(|&unit| (field.cost(paths[unit][0]), unit));
+    let max_steps = route.len() * 2 + units.len() * 8 + 16;
+    let goal_cells: Vec<Axial> = slots.values().copied().collect();
+    let mut arrived = false;
+    for step in 0..max_steps {
+        let at = |paths: &Vec<Vec<Axial>>, unit: usize| *paths[unit].last().unwrap();
+        if order.iter().all(|&unit| at(&paths, unit) == slots[&unit]) {
+            break;
+        }
+        let mut occupied: HashSet<Axial> = paths.iter().map(|path| *path.last().unwrap()).collect();
+        let mut reserved = HashSet::new();
+        let ahead = (step + 1).min(last);
+        let ahead_offsets = offsets(ahead);
+        let mut pushes: HashMap<usize, Axial> = HashMap::new();
+        if ahead == last {
+            // at the goal, units on any of the cells around it keep that
+            // one, and those whose cell was taken go for the nearest left,
+            // so none end up waiting on each other's
+            let mut left: HashSet<Axial> = goal_cells.iter().copied().collect();
+            let mut waiting = Vec::new();
+            for &unit in &order {
+                let cell = at(&paths, unit);
+                if left.remove(&cell) {
+                    slots.insert(unit, cell);
+                } else {
+                    waiting.push(unit);
+                }
+            }
+            waiting.retain(|unit| !(arrived && left.remove(&slots[unit])));
+            let mut pairs: Vec<(u32, usize, Axial)> = waiting
+                .iter()
+                .flat_map(|&unit| {
+                    let cell = at(&paths, unit);
+                    left.iter()
+                        .map(move |&slot| (cell.distance(slot), unit, slot))
+                })
+                .collect();
+            pairs.sort();
+            let mut assigned = HashSet::new();
+            for (_, unit, slot) in pairs {
+                if !assigned.contains(&unit) && left.remove(&slot) {
+                    assigned.insert(unit);
+                    slots.insert(unit, slot);
+                }
+            }
+            arrived = true;
+
+            // a unit whose way to its cell is walled off by units already in
+            // theirs pushes them along its way, one cell each, into the
+            // next free one, so it's in one of their cells after
+            let holder: HashMap<Axial, usize> =
+                order.iter().map(|&unit| (at(&paths, unit), unit)).collect();
+            for &unit in &waiting {
+                let (cell, slot) = (at(&paths, unit), slots[&unit]);
+                let around = |_, to| (!occupied.contains(&to)).then(|| cost(to)).flatten();
+                if find_path(cell, slot, around).is_some() {
+                    continue;
+                }
+                let Some((way, _)) = find_path(cell, slot, |_, to| cost(to)) else {
+                    continue;
+                };
+                let Some(end) = way.iter().position(|c| !occupied.contains(c)) else {
+                    continue;
+                };
+                let pushable = |c: &Axial| {
+                    goal_cells.contains(c) && holder.get(c).is_some_and(|u| !pushes.contains_key(u))
+                };
+                let into = way[end];
+                if end < 2
+                    || !goal_cells.contains(&into)
+                    || reserved.contains(&into)
+                    || !way[1..end].iter().all(pushable)
+                {
+                    continue;
+                }
+                for i in 1..end {
+                    pushes.insert(holder[&way[i]], way[i + 1]);
+                }
+                pushes.insert(unit, way[1]);
+                reserved.extend(way[1..=end].iter().copied());
+            }
+        }
+        for &unit in &order {
+            let cell = at(&paths, unit);
+            // the unit's place around the leader's next cell, or the
+            // leader's cell where that's blocked
+            let place = route[ahead] + ahead_offsets[places[&unit]];
+            let target = if ahead == last {
+                slots[&unit]
+            } else if cost(place).is_some() {
+                place
+            } else {
+                route[ahead]
+            };
+            let free = |c| !occupied.contains(&c) && !reserved.contains(&c);
+            let next = match pushes.get(&unit) {
+                Some(&next) => next,
+                None => next_cell(cell, target, ahead == last, &field, &cost, free),
+            };
+            // units behind may step into the cell it left, as it can't be
+            // swapped with one that has already moved
+            occupied.remove(&cell);
+            reserved.insert(next);
+            paths[unit].push(next);
+        }
+        // units that haven't set off wait in place
+        for (unit, path) in paths.iter_mut().enumerate() {
+            if !slots.contains_key(&unit) {
+                path.push(path[0]);
+            }
+        }
+    }
+
+    // trailing waits
+    for path in &mut paths {
+        while path.len() > 1 && path[path.len() - 1] == path[path.len() - 2] {
+            path.pop();
+        }
+    }
+    paths
+}
+
+// direction of the first step on the straight line from `from` to `to`
+fn heading(from: Axial, to: Axial) -> Option<HexDirection> {
+    line(from, to)
+        .get(1)
+        .and_then(|&next| HexDirection::between(from, next))
+}
+
+// cell to move to from `cell` this step; the unit waits when no free
+// neighbor gets it closer
+fn next_cell(
+    cell: Axial,
+    target: Axial,
+    home: bool,
+    field: &FlowField,
+    cost: impl Fn(Axial) -> Option<u32>,
+    free: impl Fn(Axial) -> bool,
+) -> Axial {
+    // headed for its final cell, the unit goes around the others
+    if home {
+        if cell == target {
+            return cell;
+        }
+        // or where there's no way around, heads down its way until it's
+        // next to those in the way
+        let around = find_path(cell, target, |_, to| if free(to) { cost(to) } else { None })
+            .or_else(|| {
+                find_path(cell, target, |_, to| cost(to)).filter(|(path, _)| free(path[1]))
+            });
+        return around.map_or(cell, |(path, _)| path[1]);
+    }
+    // on the way there, it keeps to its place as it heads down the field,
+    // so walls between it & its place don't hold it up; where none of its
+    // neighbors are closer to both, it follows the field
+    let potential = |c: Axial| Some(field.cost(c)?.saturating_add(c.distance(target)));
+    let downhill = field
+        .direction(cell)
+        .map(|dir| cell + dir)
+        .filter(|&n| free(n));
+    cell.neighbors()
+        .filter(|&n| cost(n).is_some() && free(n))
+        .filter_map(|n| Some((potential(n)?, n)))
+        .min()
+        .filter(|&(p, _)| potential(cell).is_none_or(|here| p < here))
+        .map(|(_, n)| n)
+        .or(downhill)
+        .unwrap_or(cell)
+}
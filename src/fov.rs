@@ -0,0 +1,51 @@
+//! field of view
+//!
+//! [`field_of_view`] finds the cells a unit can see within a radius.  A
+//! cell is seen when no cell on the [`shape::line`] to it, other than the
+//! ends, blocks sight; cells that block sight are seen themselves, so walls
+//! facing the unit are revealed.
+//!
+//! ```ignore
+//! let seen = fov::field_of_view(unit.cell, 8, |cell| walls.contains(cell));
+//! highlights.fill(seen, Color::srgba(1.0, 1.0, 0.6, 0.2));
+//! ```
+//!
+//! [`fields_of_view`] does the same for every unit of a side at once.  With
+//! the `parallel` feature the units are spread over rayon's threads.
+//!
+//! [`shape::line`]: crate::shape::line
+use bevy::platform::collections::HashSet;
+
+use crate::{shape::line, Axial};
+
+/// Cells within `radius` steps of `origin` that can be seen from it,
+/// including `origin`.  `blocks(cell)` is true for cells sight can't pass.
+pub fn field_of_view(origin: Axial, radius: u32, blocks: impl Fn(Axial) -> bool) -> HashSet<Axial> {
+    origin
+        .range(radius)
+        .filter(|&cell| {
+            let path = line(origin, cell);
+            path.len() < 3 || !path[1..path.len() - 1].iter().any(|&c| blocks(c))
+        })
+        .collect()
+}
+
+/// [`field_of_view`] from each `(origin, radius)` of `viewers`, in order.
+pub fn fields_of_view(
+    viewers: &[(Axial, u32)],
+    blocks: impl Fn(Axial) -> bool + Sync,
+) -> Vec<HashSet<Axial>> {
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        viewers
+            .par_iter()
+            .map(|&(origin, radius)| field_of_view(origin, radius, &blocks))
+            .collect()
+    }
+    #[cfg(not(feature = "parallel"))]
+    viewers
+        .iter()
+        .map(|&(origin, radius)| field_of_view(origin, radius, &blocks))
+        .collect()
+}
@@ -0,0 +1,66 @@
+//! local coordinate frames for the grid
+//!
+//! Each grid lies on the local Y = 0 plane of its entity, so a grid follows
+//! its entity (and that entity's parents) around; for example the deck of a
+//! moving ship.  Cursor picking, and the conversions on [`HexGridPlane`], are
+//! all done in the local frame.
+use bevy::prelude::*;
+
+use crate::{Axial, HexGridConfig};
+
+/// World transform of a grid plane; build one from the [`GlobalTransform`]
+/// of a grid entity.  The default is the world Y = 0 plane.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct HexGridPlane {
+    pub transform: GlobalTransform,
+}
+
+impl From<GlobalTransform> for HexGridPlane {
+    fn from(transform: GlobalTransform) -> Self {
+        Self { transform }
+    }
+}
+
+impl HexGridPlane {
+    /// convert a world position into the grid frame
+    pub fn world_to_local(&self, pos: Vec3) -> Vec3 {
+        self.transform.affine().inverse().transform_point3(pos)
+    }
+
+    pub fn local_to_world(&self, pos: Vec3) -> Vec3 {
+        self.transform.transform_point(pos)
+    }
+
+    /// distance along a world-space ray to the grid plane
+    pub fn ray_distance(&self, ray: Ray3d) -> Option<f32> {
+        ray.intersect_plane(
+            self.transform.translation(),
+            InfinitePlane3d::new(self.transform.up()),
+        )
+    }
+
+    /// intersect a world-space ray with the grid plane, returning the point
+    /// on the plane in local coordinates (X, Z)
+    pub fn intersect(&self, ray: Ray3d) -> Option<Vec2> {
+        let dist = self.ray_distance(ray)?;
+        let local = self.world_to_local(ray.get_point(dist));
+        Some(Vec2::new(local.x, local.z))
+    }
+
+    /// world position of the center of a cell
+    pub fn cell_to_world(&self, config: &HexGridConfig, cell: Axial) -> Vec3 {
+        let pos = config.cell_to_world(cell);
+        self.local_to_world(Vec3::new(pos.x, 0.0, pos.y))
+    }
+
+    /// cell under a world position, projected onto the grid plane
+    pub fn world_to_cell(&self, config: &HexGridConfig, pos: Vec3) -> Axial {
+        let local = self.world_to_local(pos);
+        config.world_to_cell(Vec2::new(local.x, local.z))
+    }
+
+    /// snap a world position to the center of the cell below it
+    pub fn snap(&self, config: &HexGridConfig, pos: Vec3) -> Vec3 {
+        self.cell_to_world(config, self.world_to_cell(config, pos))
+    }
+}
@@ -0,0 +1,246 @@
+//! procedural map generation
+//!
+//! Enough to bootstrap a prototype map without another dependency:
+//!
+//! * [`noise_terrain`] assigns terrain to cells by weight, using a noise
+//!   function so that like terrain clumps together.  Bring your own noise, or
+//!   use [`value_noise`].
+//! * [`scatter`] places features with blue-noise spacing; no two are closer
+//!   than a minimum distance, and there are no large gaps.
+//! * [`symmetric_map`] fills a map that is the same under rotation or
+//!   reflection around its center, for fair multiplayer maps, and
+//!   [`asymmetric_cells`] checks a map for symmetry.
+//!
+//! For tiles that must fit together with their neighbors, see
+//! [`crate::wfc`].
+//!
+//! Everything random takes a [`GenRng`] or a seed, so the same seed always
+//! builds the same map.
+use bevy::{platform::collections::HashSet, prelude::*};
+
+use crate::{Axial, HexMap};
+
+/// Small deterministic random number generator (SplitMix64).  Not suitable
+/// for anything but map generation.
+#[derive(Debug, Clone)]
+pub struct GenRng(u64);
+
+impl GenRng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        mix(self.0)
+    }
+
+    /// uniform in `0.0..1.0`
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// uniform in `0..n`; `n` must not be zero
+    pub fn below(&mut self, n: u32) -> u32 {
+        (((self.next_u64() >> 32) * n as u64) >> 32) as u32
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i as u32 + 1) as usize);
+        }
+    }
+}
+
+// SplitMix64 finalizer
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// Smooth value noise in `0.0..1.0`, with features about `scale` apart.
+/// Random values on a square lattice, interpolated with smoothstep.
+pub fn value_noise(seed: u64, scale: f32) -> impl Fn(Vec2) -> f32 {
+    let lattice = move |p: IVec2| {
+        let h = mix(seed ^ mix((p.x as u32 as u64) << 32 | p.y as u32 as u64));
+        (h >> 40) as f32 / (1u64 << 24) as f32
+    };
+    move |pos| {
+        let p = pos / scale;
+        let cell = p.floor();
+        let t = p - cell;
+        let t = t * t * (3.0 - 2.0 * t);
+        let cell = cell.as_ivec2();
+        let a = lattice(cell);
+        let b = lattice(cell + IVec2::X);
+        let c = lattice(cell + IVec2::Y);
+        let d = lattice(cell + IVec2::ONE);
+        let top = a + (b - a) * t.x;
+        let bottom = c + (d - c) * t.x;
+        top + (bottom - top) * t.y
+    }
+}
+
+/// Assign a terrain to each cell.  Cells are ranked by `noise` at their
+/// center (see [`Axial::to_world`]), and the ranking is split by `weights`,
+/// so the share of cells of each terrain matches its weight exactly, and
+/// cells with close noise values get the same terrain.  Weights don't need
+/// to add up to 1.
+pub fn noise_terrain<T: Clone>(
+    cells: impl IntoIterator<Item = Axial>,
+    noise: impl Fn(Vec2) -> f32,
+    weights: &[(T, f32)],
+) -> HexMap<T> {
+    let mut ranked: Vec<(f32, Axial)> = cells
+        .into_iter()
+        .map(|cell| (noise(cell.to_world()), cell))
+        .collect();
+    ranked.sort_by(|a, b| {
+        a.0.total_cmp(&b.0)
+            .then((a.1.r, a.1.q).cmp(&(b.1.r, b.1.q)))
+    });
+
+    let total: f32 = weights.iter().map(|(_, w)| w.max(0.0)).sum();
+    let mut map = HexMap::new();
+    if total <= 0.0 {
+        return map;
+    }
+
+    let mut start = 0;
+    let mut cumulative = 0.0;
+    for (terrain, weight) in weights {
+        cumulative += weight.max(0.0);
+        let end = ((cumulative / total) * ranked.len() as f32).round() as usize;
+        let end = end.min(ranked.len());
+        for &(_, cell) in &ranked[start..end.max(start)] {
+            map.insert(cell, terrain.clone());
+        }
+        start = start.max(end);
+    }
+    // rounding may leave the last cell out
+    if let Some((terrain, _)) = weights.last() {
+        for &(_, cell) in &ranked[start..] {
+            map.insert(cell, terrain.clone());
+        }
+    }
+    map
+}
+
+/// Choose cells with blue-noise spacing: every chosen cell is at least
+/// `min_distance` steps from the others, and every candidate not chosen is
+/// closer than that to one that was.
+pub fn scatter(
+    cells: impl IntoIterator<Item = Axial>,
+    min_distance: u32,
+    rng: &mut GenRng,
+) -> Vec<Axial> {
+    let mut candidates: Vec<Axial> = cells.into_iter().collect();
+    // sort first, so the result only depends on the seed & the set of cells
+    candidates.sort_by_key(|c| (c.r, c.q));
+    candidates.dedup();
+    rng.shuffle(&mut candidates);
+
+    let mut taken = HashSet::new();
+    let mut chosen = Vec::new();
+    for cell in candidates {
+        if min_distance > 0 && cell.range(min_distance - 1).any(|c| taken.contains(&c)) {
+            continue;
+        }
+        taken.insert(cell);
+        chosen.push(cell);
+    }
+    chosen
+}
+
+/// symmetry of a map around its center
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Symmetry {
+    /// same when rotated 180 degrees; two players
+    #[default]
+    Rotate2,
+    /// same when rotated 120 degrees; three players
+    Rotate3,
+    /// same when rotated 60 degrees; six players
+    Rotate6,
+    /// mirrored across the axis where `q` is constant
+    Mirror,
+}
+
+impl Symmetry {
+    /// number of images of a cell; the number of players the symmetry is
+    /// fair for
+    pub fn order(self) -> usize {
+        match self {
+            Symmetry::Rotate2 | Symmetry::Mirror => 2,
+            Symmetry::Rotate3 => 3,
+            Symmetry::Rotate6 => 6,
+        }
+    }
+
+    /// Images of `cell` around `center`, one for each player, starting with
+    /// `cell` itself.  Unlike [`Symmetry::orbit`], these are in rotation
+    /// order & may repeat, so the `i`th image of a start position is always
+    /// the `i`th player's.
+    pub fn images(self, center: Axial, cell: Axial) -> Vec<Axial> {
+        if self == Symmetry::Mirror {
+            let mirrored = (cell - center).to_cube().reflect_q();
+            return vec![cell, Axial::from(mirrored) + center];
+        }
+        let step = 6 / self.order() as i32;
+        (0..self.order() as i32)
+            .map(|i| cell.rotate_around(center, i * step))
+            .collect()
+    }
+
+    /// cells that must match `cell` in a map with this symmetry around
+    /// `center`, including `cell`
+    pub fn orbit(self, center: Axial, cell: Axial) -> Vec<Axial> {
+        let mut orbit = self.images(center, cell);
+        orbit.sort_by_key(|c| (c.r, c.q));
+        orbit.dedup();
+        orbit
+    }
+}
+
+/// Fill the cells within `radius` of `center` so the map has `symmetry`.
+/// `f` is called once for each set of matching cells, with one of them, and
+/// its value is copied to the rest.
+pub fn symmetric_map<T: Clone>(
+    center: Axial,
+    radius: u32,
+    symmetry: Symmetry,
+    mut f: impl FnMut(Axial) -> T,
+) -> HexMap<T> {
+    let mut map = HexMap::new();
+    for cell in center.range(radius) {
+        if map.contains(cell) {
+            continue;
+        }
+        let value = f(cell);
+        for other in symmetry.orbit(center, cell) {
+            map.insert(other, value.clone());
+        }
+    }
+    map
+}
+
+/// Cells of `map` that don't match every one of their images under
+/// `symmetry` around `center`, either because the values differ or an image
+/// is missing from the map.  Empty if the map is symmetric.
+pub fn asymmetric_cells<T: PartialEq>(
+    map: &HexMap<T>,
+    center: Axial,
+    symmetry: Symmetry,
+) -> Vec<Axial> {
+    map.iter()
+        .filter(|&(cell, value)| {
+            symmetry
+                .images(center, cell)
+                .into_iter()
+                .any(|image| map.get(image) != Some(value))
+        })
+        .map(|(cell, _)| cell)
+        .collect()
+}
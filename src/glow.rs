@@ -0,0 +1,86 @@
+//! glowing outlines around groups of cells
+//!
+//! Add a [`HexGridGlow`] to a grid entity to draw a soft glow around the
+//! outside of groups of cells, such as a selection or the reach of a unit:
+//!
+//! ```ignore
+//! let mut glow = HexGridGlow::new(16.0).with_intensity(4.0);
+//! glow.fill(selected, Color::srgb(0.3, 0.8, 1.0));
+//! commands.entity(grid).insert(glow);
+//! ```
+//!
+//! The distance from each pixel to the nearest glowing cell is found on the
+//! gpu with a jump flood over a texture at half the view's resolution, so
+//! the cost follows the size of the view & the width of the glow, not the
+//! number of cells.  Cells next to each other glow as one outline; where
+//! groups of different colors are close, each pixel takes the color of the
+//! nearest.
+//!
+//! The glow is added over the view after the grids are drawn, over the
+//! scene as well.  Colors times the intensity may be brighter than 1: with
+//! [`crate::HexGridPlugin::before_tonemapping`] on an hdr camera with
+//! bloom, the glow blooms like any emissive surface, without changing the
+//! bloom settings.
+use bevy::prelude::*;
+
+use crate::{Axial, HexMap};
+
+/// Cells of a grid entity to outline with a glow; see the
+/// [module docs](self).
+#[derive(Component, Debug, Clone)]
+pub struct HexGridGlow {
+    /// color of the glow around each cell
+    pub cells: HexMap<Color>,
+    /// how far the glow reaches outside the cells, in pixels; at most
+    /// [`HexGridGlow::MAX_WIDTH`]
+    pub width: f32,
+    /// multiplies the glow colors
+    pub intensity: f32,
+}
+
+impl Default for HexGridGlow {
+    fn default() -> Self {
+        Self::new(12.0)
+    }
+}
+
+impl HexGridGlow {
+    /// widest glow, in pixels
+    pub const MAX_WIDTH: f32 = 128.0;
+
+    pub fn new(width: f32) -> Self {
+        Self {
+            cells: HexMap::new(),
+            width,
+            intensity: 1.0,
+        }
+    }
+
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    /// glow around every cell in `cells` with `color`
+    pub fn fill(&mut self, cells: impl IntoIterator<Item = Axial>, color: Color) {
+        self.cells
+            .extend(cells.into_iter().map(|cell| (cell, color)));
+    }
+
+    /// width clamped to what can be drawn
+    pub(crate) fn clamped_width(&self) -> f32 {
+        self.width.clamp(0.0, Self::MAX_WIDTH)
+    }
+
+    /// Strength of the glow `distance` pixels outside the nearest glowing
+    /// cell, from 1 at the cell to 0 at [`HexGridGlow::width`].  Mirrors
+    /// `composite()` in `hex_grid_glow.wgsl`.
+    pub fn falloff(&self, distance: f32) -> f32 {
+        let width = self.clamped_width();
+        if width <= 0.0 {
+            return 0.0;
+        }
+        let t = 1.0 - (distance / width).clamp(0.0, 1.0);
+        t * t
+    }
+}
@@ -0,0 +1,106 @@
+//! per-cell hashing shared with shaders
+//!
+//! [`hash_cell`] gives every cell a well mixed pseudo-random number, the
+//! same in Rust & in `assets/hex_grid_hash.wgsl`, so variation chosen per
+//! cell in game code, such as which prop stands on a tile, matches variation
+//! drawn by a shader, such as fill jitter or texture rotation.  Import it in
+//! a custom shader with:
+//!
+//! ```wgsl
+//! #import hex_grid::hash::{hash_cell, hash_cell_f32}
+//! ```
+//!
+//! The shader module is loaded by [`crate::HexGridPlugin`].  Use a different
+//! `seed` for each independent value on a cell.  Hash the canonical cell on
+//! wrapped maps, so every copy of a cell gets the same values.
+//!
+//! For several decisions on one cell, such as loot rolls, a [`CellRng`]
+//! draws as many values as needed from a cell & seed.  Its first value is
+//! [`hash_cell`], and `cell_rng(cell, seed, n)` in the shader gives its
+//! `n`th.
+use crate::Axial;
+
+// added to a CellRng's key for each value drawn
+const STEP: u32 = 0x6a09_e667;
+
+/// Hash of a cell & seed; mirrors `hash_cell()` in the shader.
+pub fn hash_cell(cell: Axial, seed: u32) -> u32 {
+    mix(key(cell, seed))
+}
+
+// hash_cell() before the last mix
+fn key(cell: Axial, seed: u32) -> u32 {
+    // mix in one coordinate at a time, so that mirrored cells such as
+    // (1, -9) & (-1, 9) don't collide
+    let h = mix(seed.wrapping_add(0x9e37_79b9));
+    let h = mix((cell.r as u32).wrapping_add(h));
+    (cell.q as u32).wrapping_add(h)
+}
+
+// lowbias32 finalizer
+fn mix(mut h: u32) -> u32 {
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7feb_352d);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x846c_a68b);
+    h ^ (h >> 16)
+}
+
+/// [`hash_cell`] as a value from 0 up to, but not including, 1; mirrors
+/// `hash_cell_f32()` in the shader.
+pub fn hash_cell_f32(cell: Axial, seed: u32) -> f32 {
+    unit(hash_cell(cell, seed))
+}
+
+fn unit(h: u32) -> f32 {
+    (h >> 8) as f32 / 16_777_216.0
+}
+
+/// Small deterministic random number generator for one cell, giving the
+/// same values on every platform.  Values are drawn the same way as
+/// [`hash_cell`], so shaders can reproduce them with `cell_rng()`.  Like
+/// [`crate::gen::GenRng`], not suitable for anything security related.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CellRng(u32);
+
+impl CellRng {
+    pub fn new(seed: u32, cell: Axial) -> Self {
+        Self(key(cell, seed))
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let h = mix(self.0);
+        self.0 = self.0.wrapping_add(STEP);
+        h
+    }
+
+    /// uniform in `0.0..1.0`; mirrors `cell_rng_f32()` in the shader
+    pub fn next_f32(&mut self) -> f32 {
+        unit(self.next_u32())
+    }
+
+    /// uniform in `0..n`; `n` must not be zero
+    pub fn below(&mut self, n: u32) -> u32 {
+        ((self.next_u32() as u64 * n as u64) >> 32) as u32
+    }
+
+    /// true with probability `p`
+    pub fn chance(&mut self, p: f32) -> bool {
+        self.next_f32() < p
+    }
+
+    /// uniformly chosen item; `None` if `items` is empty
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            return None;
+        }
+        Some(&items[self.below(items.len() as u32) as usize])
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i as u32 + 1) as usize);
+        }
+    }
+}
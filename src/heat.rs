@@ -0,0 +1,260 @@
+//! render world side of [`crate::heatmap`]
+//!
+//! The values of a heatmap are stored in an `Rg32Float` texture covering the
+//! bounds of the values plus some padding, as (value, 1); (0, 0) is a cell
+//! without a value.  Chunks of the map changed since the texture was last
+//! written are copied over as whole rectangles.  The texture is rebuilt when
+//! the values are replaced, or a value is set outside it.
+use bevy::{
+    platform::collections::HashMap,
+    prelude::*,
+    render::{
+        render_resource::{
+            Extent3d, Origin3d, TexelCopyBufferLayout, TexelCopyTextureInfo, Texture,
+            TextureAspect, TextureDataOrder, TextureDescriptor, TextureDimension, TextureFormat,
+            TextureUsages, TextureView, TextureViewDescriptor,
+        },
+        renderer::{RenderDevice, RenderQueue},
+        sync_world::RenderEntity,
+        Extract,
+    },
+};
+
+use crate::{
+    heatmap::{resolve_range, HexGridHeatmap},
+    render::ExtractedHexGrid,
+    Axial, HexBounds, HexGridConfig, HexMap, HexMapVersion,
+};
+
+const TEXTURE_FORMAT: TextureFormat = TextureFormat::Rg32Float;
+
+/// cells of padding around the values when the texture is built, so the
+/// heatmap can grow a little without rebuilding it
+const TEXTURE_PADDING: i32 = 16;
+
+/// heatmap changes for a grid's texture, on its render world entity.
+/// Removed while the grid is hidden or has no values.
+#[derive(Component)]
+pub(crate) struct ExtractedHeatmap {
+    version: HexMapVersion,
+    /// range of the values, for [`crate::HeatNormalization::Auto`]
+    values_range: Option<(f32, f32)>,
+    /// values mapped to the ends of the gradient
+    range: (f32, f32),
+    update: HeatUpdate,
+}
+
+enum HeatUpdate {
+    /// build the texture over `bounds`
+    Full(HexBounds, HexMap<f32>),
+    /// rectangles changed since the texture was written, with their texels
+    /// row by row; all within the existing texture
+    Changes(Vec<(HexBounds, Vec<[f32; 2]>)>),
+}
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn extract_hex_grid_heatmaps(
+    mut commands: Commands,
+    textures: Res<HexGridHeatTextures>,
+    grids: Extract<
+        Query<
+            (
+                Entity,
+                RenderEntity,
+                &InheritedVisibility,
+                Option<Ref<HexGridHeatmap>>,
+            ),
+            With<HexGridConfig>,
+        >,
+    >,
+    render_device: Res<RenderDevice>,
+) {
+    let max_texture_size = render_device.limits().max_texture_dimension_2d as i32;
+
+    for (entity, render_entity, visibility, heatmap) in &grids {
+        let Some(heatmap) = heatmap.filter(|h| visibility.get() && !h.values.is_empty()) else {
+            commands.entity(render_entity).remove::<ExtractedHeatmap>();
+            continue;
+        };
+
+        let version = heatmap.values.version();
+        let texture = textures.0.get(&render_entity);
+        let changes = texture.and_then(|t| changed_rects(&heatmap.values, t.version, t.bounds));
+        // the values are only scanned for their range when they've changed
+        let values_range = match texture {
+            Some(texture) if texture.version == version => texture.values_range,
+            _ => heatmap.value_range(),
+        };
+        let update = match changes {
+            Some(changes) => HeatUpdate::Changes(changes),
+            None => {
+                let mut bounds = heatmap.values.bounds().unwrap();
+                bounds.min -= Axial::new(TEXTURE_PADDING, TEXTURE_PADDING);
+                bounds.max += Axial::new(TEXTURE_PADDING, TEXTURE_PADDING);
+                if bounds.size().max_element() > max_texture_size {
+                    if heatmap.is_changed() {
+                        warn!(
+                            "heatmap values on grid {:?} are too spread out to draw; \
+                             max texture size is {}",
+                            entity, max_texture_size
+                        );
+                    }
+                    commands.entity(render_entity).remove::<ExtractedHeatmap>();
+                    continue;
+                }
+                HeatUpdate::Full(bounds, heatmap.values.clone())
+            }
+        };
+        commands.entity(render_entity).insert(ExtractedHeatmap {
+            version,
+            values_range,
+            range: resolve_range(heatmap.normalization, || values_range),
+            update,
+        });
+    }
+}
+
+/// Chunks of `values` changed since `since`, clipped to a texture covering
+/// `bounds`.  `None` if the texture must be rebuilt; the values are from
+/// another map, or have grown outside the texture.
+fn changed_rects(
+    values: &HexMap<f32>,
+    since: HexMapVersion,
+    bounds: HexBounds,
+) -> Option<Vec<(HexBounds, Vec<[f32; 2]>)>> {
+    let mut rects = Vec::new();
+    for chunk in values.changed_chunks(since)? {
+        if chunk
+            .cells()
+            .any(|c| !bounds.contains(c) && values.contains(c))
+        {
+            return None;
+        }
+        let rect = HexBounds::new(
+            Axial::new(chunk.min.q.max(bounds.min.q), chunk.min.r.max(bounds.min.r)),
+            Axial::new(chunk.max.q.min(bounds.max.q), chunk.max.r.min(bounds.max.r)),
+        );
+        if rect.min.q > rect.max.q || rect.min.r > rect.max.r {
+            continue;
+        }
+        rects.push((rect, rect.cells().map(|c| texel(values.get(c))).collect()));
+    }
+    Some(rects)
+}
+
+// texel value for a cell
+fn texel(value: Option<&f32>) -> [f32; 2] {
+    value.map_or([0.0; 2], |&v| [v, 1.0])
+}
+
+fn texel_bytes(texels: impl IntoIterator<Item = [f32; 2]>) -> Vec<u8> {
+    texels
+        .into_iter()
+        .flatten()
+        .flat_map(f32::to_ne_bytes)
+        .collect()
+}
+
+pub(crate) struct HeatTexture {
+    pub bounds: HexBounds,
+    /// version of the values last written
+    version: HexMapVersion,
+    values_range: Option<(f32, f32)>,
+    pub range: (f32, f32),
+    texture: Texture,
+    pub view: TextureView,
+}
+
+/// heatmap textures, keyed by render world grid entity.  Dropped the first
+/// frame the grid isn't extracted, or has no values.
+#[derive(Resource, Default)]
+pub(crate) struct HexGridHeatTextures(pub HashMap<Entity, HeatTexture>);
+
+pub(crate) fn prepare_hex_grid_heatmaps(
+    mut textures: ResMut<HexGridHeatTextures>,
+    grids: Query<(Entity, &ExtractedHeatmap), With<ExtractedHexGrid>>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    textures.0.retain(|entity, _| grids.contains(*entity));
+
+    for (entity, heatmap) in &grids {
+        match &heatmap.update {
+            HeatUpdate::Full(bounds, values) => {
+                let size = bounds.size().as_uvec2();
+                let mut data = vec![[0.0; 2]; (size.x * size.y) as usize];
+                for (cell, value) in values.iter() {
+                    let offset = IVec2::from(cell - bounds.min).as_uvec2();
+                    data[(offset.y * size.x + offset.x) as usize] = texel(Some(value));
+                }
+
+                let texture = render_device.create_texture_with_data(
+                    &render_queue,
+                    &TextureDescriptor {
+                        label: Some("hex_grid_heatmap"),
+                        size: Extent3d {
+                            width: size.x,
+                            height: size.y,
+                            depth_or_array_layers: 1,
+                        },
+                        mip_level_count: 1,
+                        sample_count: 1,
+                        dimension: TextureDimension::D2,
+                        format: TEXTURE_FORMAT,
+                        usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+                        view_formats: &[],
+                    },
+                    TextureDataOrder::LayerMajor,
+                    &texel_bytes(data),
+                );
+                let view = texture.create_view(&TextureViewDescriptor::default());
+                textures.0.insert(
+                    entity,
+                    HeatTexture {
+                        bounds: *bounds,
+                        version: heatmap.version,
+                        values_range: heatmap.values_range,
+                        range: heatmap.range,
+                        texture,
+                        view,
+                    },
+                );
+            }
+            HeatUpdate::Changes(changes) => {
+                let Some(texture) = textures.0.get_mut(&entity) else {
+                    continue;
+                };
+                texture.version = heatmap.version;
+                texture.values_range = heatmap.values_range;
+                texture.range = heatmap.range;
+                for (rect, texels) in changes {
+                    let offset = IVec2::from(rect.min - texture.bounds.min).as_uvec2();
+                    let size = rect.size().as_uvec2();
+                    render_queue.write_texture(
+                        TexelCopyTextureInfo {
+                            texture: &texture.texture,
+                            mip_level: 0,
+                            origin: Origin3d {
+                                x: offset.x,
+                                y: offset.y,
+                                z: 0,
+                            },
+                            aspect: TextureAspect::All,
+                        },
+                        &texel_bytes(texels.iter().copied()),
+                        TexelCopyBufferLayout {
+                            offset: 0,
+                            bytes_per_row: Some(size.x * 8),
+                            rows_per_image: None,
+                        },
+                        Extent3d {
+                            width: size.x,
+                            height: size.y,
+                            depth_or_array_layers: 1,
+                        },
+                    );
+                }
+            }
+        }
+    }
+}
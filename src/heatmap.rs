@@ -0,0 +1,154 @@
+//! scalar heatmaps over cells
+//!
+//! Add a [`HexGridHeatmap`] to a grid entity to shade each cell by a value,
+//! such as the threat on it, or an AI's influence map.  Values are mapped to
+//! 0..1 by the [`HeatNormalization`], then colored along the
+//! [`HexGridHeatmap::gradient`]:
+//!
+//! ```ignore
+//! let mut heatmap = HexGridHeatmap::new(threat).with_alpha(0.6);
+//! commands.entity(grid).insert(heatmap);
+//! ```
+//!
+//! The heatmap is drawn over territory, and under highlights, decals, &
+//! grid lines.  Cells without a value aren't shaded.
+//!
+//! Values are kept in a texture on the gpu.  Only the chunks of
+//! [`HexGridHeatmap::values`] changed since the last frame are written to it;
+//! the whole map is uploaded when the heatmap is added, its values are
+//! replaced, or a value is set outside the texture.  With
+//! [`HeatNormalization::Auto`], every value is scanned for the range on any
+//! change.
+use bevy::prelude::*;
+
+use crate::{Axial, HexMap};
+
+/// how values are mapped onto the gradient
+#[derive(Debug, Default, Clone, Copy, PartialEq, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HeatNormalization {
+    /// the smallest value is the start of the gradient, and the largest the
+    /// end
+    #[default]
+    Auto,
+    /// `min` is the start of the gradient, and `max` the end; values outside
+    /// are clamped
+    Range { min: f32, max: f32 },
+}
+
+/// Values to draw as a heatmap on a grid entity; see the
+/// [module docs](self).
+#[derive(Component, Debug, Clone)]
+pub struct HexGridHeatmap {
+    pub values: HexMap<f32>,
+    /// colors spaced evenly from the start of the gradient to the end; only
+    /// the first [`HexGridHeatmap::MAX_COLORS`] are used
+    pub gradient: Vec<Color>,
+    /// opacity of the whole heatmap, multiplied by the gradient alpha
+    pub alpha: f32,
+    pub normalization: HeatNormalization,
+}
+
+impl Default for HexGridHeatmap {
+    fn default() -> Self {
+        Self {
+            values: HexMap::new(),
+            gradient: vec![
+                Color::srgb(0.1, 0.2, 0.9),
+                Color::srgb(0.1, 0.8, 0.8),
+                Color::srgb(0.2, 0.85, 0.2),
+                Color::srgb(0.95, 0.9, 0.1),
+                Color::srgb(0.95, 0.15, 0.1),
+            ],
+            alpha: 0.5,
+            normalization: HeatNormalization::Auto,
+        }
+    }
+}
+
+impl HexGridHeatmap {
+    pub const MAX_COLORS: usize = 8;
+
+    pub fn new(values: HexMap<f32>) -> Self {
+        Self {
+            values,
+            ..default()
+        }
+    }
+
+    pub fn with_gradient(mut self, gradient: impl IntoIterator<Item = Color>) -> Self {
+        self.gradient = gradient.into_iter().collect();
+        self
+    }
+
+    pub fn with_alpha(mut self, alpha: f32) -> Self {
+        self.alpha = alpha;
+        self
+    }
+
+    pub fn with_normalization(mut self, normalization: HeatNormalization) -> Self {
+        self.normalization = normalization;
+        self
+    }
+
+    /// smallest & largest value; `None` without values
+    pub fn value_range(&self) -> Option<(f32, f32)> {
+        self.values
+            .iter()
+            .map(|(_, &v)| v)
+            .filter(|v| !v.is_nan())
+            .fold(None, |range, v| match range {
+                None => Some((v, v)),
+                Some((min, max)) => Some((min.min(v), max.max(v))),
+            })
+    }
+
+    /// values mapped to the start & end of the gradient
+    pub fn range(&self) -> (f32, f32) {
+        resolve_range(self.normalization, || self.value_range())
+    }
+
+    /// Color of `cell`, before [`HexGridHeatmap::alpha`]; `None` without a
+    /// value.
+    pub fn color(&self, cell: Axial) -> Option<Color> {
+        let value = *self.values.get(cell)?;
+        Some(self.gradient_color(normalize(value, self.range())))
+    }
+
+    /// Color at `t`, from 0 to 1, along the gradient; mirrors
+    /// `gradient_color()` in the shader.
+    pub fn gradient_color(&self, t: f32) -> Color {
+        let colors = &self.gradient[..self.gradient.len().min(Self::MAX_COLORS)];
+        match colors {
+            [] => Color::NONE,
+            [color] => *color,
+            _ => {
+                let x = t.clamp(0.0, 1.0) * (colors.len() - 1) as f32;
+                let i = (x as usize).min(colors.len() - 2);
+                let (a, b) = (colors[i].to_linear(), colors[i + 1].to_linear());
+                Color::from(a.mix(&b, x - i as f32))
+            }
+        }
+    }
+}
+
+/// range for `normalization`, with `values` giving the range of the values
+/// for [`HeatNormalization::Auto`]
+pub(crate) fn resolve_range(
+    normalization: HeatNormalization,
+    values: impl FnOnce() -> Option<(f32, f32)>,
+) -> (f32, f32) {
+    match normalization {
+        HeatNormalization::Auto => values().unwrap_or((0.0, 1.0)),
+        HeatNormalization::Range { min, max } => (min, max),
+    }
+}
+
+/// `value` mapped to 0..1 over `range`; with an empty range, values at or
+/// past the end are 1 & the rest 0.  Mirrors `heat_color()` in the shader.
+fn normalize(value: f32, (min, max): (f32, f32)) -> f32 {
+    if max <= min {
+        return if value >= max { 1.0 } else { 0.0 };
+    }
+    ((value - min) / (max - min)).clamp(0.0, 1.0)
+}
@@ -0,0 +1,230 @@
+//! colored cells
+//!
+//! Add a [`HexGridHighlights`] component to a grid entity to fill cells with
+//! a color; movement ranges, selections, areas of effect.  Highlights are
+//! drawn over the parity tint, and under decals & grid lines.
+//!
+//! Only highlighted cells near each camera's view frustum are uploaded to the
+//! gpu each frame, so large maps can be highlighted without the cost growing
+//! with the size of the map.  Highlights that rarely change can instead be
+//! baked into a texture with [`HexGridBakeHighlights`].
+//!
+//! The edges & corners of hex cells can be highlighted with
+//! [`HexGridPartHighlights`], for roads, walls, and settlements.  Groups of
+//! edges can be stroked with dashes, dots, or double lines by an
+//! [`EdgeStroke`], and dashes can march along the edges for selections:
+//!
+//! ```ignore
+//! let ants = parts.add_stroke(EdgeStroke::new(StrokePattern::Dash, 0.06).with_speed(2.0));
+//! parts.stroke_edges(selection_outline, Color::WHITE, ants);
+//! ```
+use bevy::{
+    camera::primitives::{Frustum, Sphere},
+    platform::collections::HashMap,
+    prelude::*,
+};
+
+use crate::{Axial, HexEdge, HexGridConfig, HexGridPlane, HexMap, HexVertex};
+
+/// Bake the [`HexGridHighlights`] of this grid entity into a texture with a
+/// compute pass whenever they change, instead of uploading them every frame.
+/// The grid shader then reads a single texel per pixel, so drawing costs the
+/// same no matter how many cells are highlighted.
+///
+/// The texture covers the [`HexMap::bounds`] of the highlights; highlights
+/// too spread out to fit in a texture are drawn without baking.
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct HexGridBakeHighlights;
+
+/// Highlight colors for a grid entity, keyed by cell.  Colors are alpha
+/// blended over the cell.
+#[derive(Component, Debug, Default, Clone)]
+pub struct HexGridHighlights(pub HexMap<Color>);
+
+impl HexGridHighlights {
+    /// highlight every cell in `cells` with `color`, such as a shape from
+    /// [`crate::shape`]
+    pub fn fill(&mut self, cells: impl IntoIterator<Item = Axial>, color: Color) {
+        self.0.extend(cells.into_iter().map(|cell| (cell, color)));
+    }
+
+    /// Highlighted cells that may be visible within a frustum.  A cell is
+    /// kept when a sphere around its center, one cell size in radius,
+    /// intersects the frustum; this covers the whole cell for every
+    /// [`crate::GridKind`].  Nothing is culled on wrapped maps, as any copy
+    /// of a cell may be in view.
+    pub fn visible<'a>(
+        &'a self,
+        config: &'a HexGridConfig,
+        plane: &'a HexGridPlane,
+        frustum: &'a Frustum,
+    ) -> impl Iterator<Item = (Axial, Color)> + 'a {
+        let (scale, _, _) = plane.transform.to_scale_rotation_translation();
+        let radius = config.size * scale.max_element();
+        self.0.iter().filter_map(move |(cell, color)| {
+            if config.wrap.is_some() {
+                return Some((cell, *color));
+            }
+            let sphere = Sphere {
+                center: plane.cell_to_world(config, cell).into(),
+                radius,
+            };
+            frustum
+                .intersects_sphere(&sphere, false)
+                .then_some((cell, *color))
+        })
+    }
+}
+
+/// Highlight colors for the edges & corners of the cells on a grid entity.
+/// Edges are drawn as bands centered on the edge, and corners as dots over
+/// them, both on top of the grid lines.  Only drawn on [`crate::GridKind::Hex`]
+/// grids.
+///
+/// Unlike [`HexGridHighlights`], these aren't culled to the view; they're
+/// meant for the handful of roads & settlements on a board.
+#[derive(Component, Debug, Clone)]
+pub struct HexGridPartHighlights {
+    pub edges: HashMap<HexEdge, Color>,
+    pub vertices: HashMap<HexVertex, Color>,
+    /// width of highlighted edges without a stroke group, as a fraction of
+    /// the cell size
+    pub edge_width: f32,
+    /// radius of highlighted corners, as a fraction of the cell size
+    pub vertex_radius: f32,
+    /// stroke group of highlighted edges, as an index into `strokes`; edges
+    /// without one are drawn as a solid band `edge_width` wide
+    pub edge_groups: HashMap<HexEdge, usize>,
+    /// stroke of each group; at most [`HexGridPartHighlights::MAX_STROKES`]
+    /// are drawn, and edges of later groups are drawn without one
+    pub strokes: Vec<EdgeStroke>,
+}
+
+impl Default for HexGridPartHighlights {
+    fn default() -> Self {
+        Self {
+            edges: HashMap::new(),
+            vertices: HashMap::new(),
+            edge_width: 0.12,
+            vertex_radius: 0.12,
+            edge_groups: HashMap::new(),
+            strokes: Vec::new(),
+        }
+    }
+}
+
+impl HexGridPartHighlights {
+    /// most stroke groups drawn on a grid
+    pub const MAX_STROKES: usize = 4;
+
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty() && self.vertices.is_empty()
+    }
+
+    /// add a stroke group, returning its index for
+    /// [`HexGridPartHighlights::stroke_edges`]
+    pub fn add_stroke(&mut self, stroke: EdgeStroke) -> usize {
+        self.strokes.push(stroke);
+        self.strokes.len() - 1
+    }
+
+    /// highlight every edge in `edges` with `color`, drawn with the stroke
+    /// of `group`
+    pub fn stroke_edges(
+        &mut self,
+        edges: impl IntoIterator<Item = HexEdge>,
+        color: Color,
+        group: usize,
+    ) {
+        for edge in edges {
+            self.edges.insert(edge, color);
+            self.edge_groups.insert(edge, group);
+        }
+    }
+
+    /// the stroke a highlighted edge is drawn with
+    pub fn stroke(&self, edge: HexEdge) -> EdgeStroke {
+        self.edge_groups
+            .get(&edge)
+            .filter(|&&group| group < Self::MAX_STROKES)
+            .and_then(|&group| self.strokes.get(group))
+            .copied()
+            .unwrap_or(EdgeStroke::new(StrokePattern::Solid, self.edge_width))
+    }
+}
+
+/// pattern of an [`EdgeStroke`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StrokePattern {
+    #[default]
+    Solid,
+    /// dashes half of each period long
+    Dash,
+    /// round dots as wide as the stroke, one each period
+    Dot,
+    /// two lines a third of the width each, with a gap between
+    Double,
+}
+
+/// How the edges of a stroke group in [`HexGridPartHighlights`] are drawn.
+/// Dashes & dots are laid along each edge from its middle, so they line up
+/// across the edges of a straight outline.
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EdgeStroke {
+    pub pattern: StrokePattern,
+    /// width, as a fraction of the cell size
+    pub width: f32,
+    /// length of a dash & the gap after it, or the distance between dots,
+    /// as a fraction of the cell size
+    pub period: f32,
+    /// periods the pattern moves along the edges each second of
+    /// [`crate::GridTime`]; marching ants for selections
+    pub speed: f32,
+}
+
+impl Default for EdgeStroke {
+    fn default() -> Self {
+        Self::new(StrokePattern::Solid, 0.12)
+    }
+}
+
+impl EdgeStroke {
+    pub fn new(pattern: StrokePattern, width: f32) -> Self {
+        Self {
+            pattern,
+            width,
+            period: 0.2,
+            speed: 0.0,
+        }
+    }
+
+    pub fn with_period(mut self, period: f32) -> Self {
+        self.period = period;
+        self
+    }
+
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// True if the stroke covers a point `across` from its edge & `along`
+    /// it from the middle of the edge, at `time` seconds.  Mirrors
+    /// `stroke_coverage()` in the grid shader, without anti-aliasing.
+    pub fn covers(&self, across: f32, along: f32, time: f32) -> bool {
+        let half = self.width * 0.5;
+        let across = across.abs();
+        let phase = along / self.period.max(0.001) - time * self.speed;
+        match self.pattern {
+            StrokePattern::Solid => across < half,
+            StrokePattern::Dash => across < half && phase.rem_euclid(1.0) < 0.5,
+            StrokePattern::Dot => {
+                let offset = ((phase + 0.5).rem_euclid(1.0) - 0.5) * self.period;
+                offset.hypot(across) < half
+            }
+            StrokePattern::Double => across < half && across > half / 3.0,
+        }
+    }
+}
@@ -0,0 +1,233 @@
+//! undo & redo for maps
+//!
+//! A [`HexMapHistory`] wraps a [`HexMap`] and records the cells each change
+//! touches, so a level editor can undo & redo them.  Group the changes of
+//! one edit, such as a brush stroke, into a transaction:
+//!
+//! ```ignore
+//! history.begin_txn();
+//! for cell in stroke {
+//!     history.insert(cell, brush.terrain);
+//! }
+//! history.commit();
+//!
+//! // ctrl-z
+//! history.undo();
+//! ```
+//!
+//! Changes made with no transaction open are each a transaction of their
+//! own.  Only the value before & after of each cell a transaction changed
+//! is kept, not the whole map.  Undoing & redoing changes the map like any
+//! other edit, so mirrors of it, such as gpu textures, pick up the changes
+//! through [`HexMap::changed_chunks`].
+use bevy::platform::collections::HashMap;
+
+use crate::{Axial, HexMap};
+
+// value of a cell before & after a transaction
+#[derive(Debug, Clone)]
+struct CellChange<T> {
+    cell: Axial,
+    before: Option<T>,
+    after: Option<T>,
+}
+
+/// A [`HexMap`] with undo & redo.
+#[derive(Debug, Clone)]
+pub struct HexMapHistory<T> {
+    map: HexMap<T>,
+    /// value of each cell the open transaction touched, before it did
+    open: Option<HashMap<Axial, Option<T>>>,
+    undo: Vec<Vec<CellChange<T>>>,
+    redo: Vec<Vec<CellChange<T>>>,
+    /// most transactions kept to undo; the oldest are forgotten first
+    pub limit: usize,
+}
+
+impl<T: Clone + PartialEq> Default for HexMapHistory<T> {
+    fn default() -> Self {
+        Self::new(HexMap::new())
+    }
+}
+
+impl<T: Clone + PartialEq> HexMapHistory<T> {
+    pub fn new(map: HexMap<T>) -> Self {
+        Self {
+            map,
+            open: None,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            limit: 100,
+        }
+    }
+
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub fn as_map(&self) -> &HexMap<T> {
+        &self.map
+    }
+
+    /// the map, dropping its history; an open transaction is kept
+    pub fn into_map(self) -> HexMap<T> {
+        self.map
+    }
+
+    pub fn get(&self, cell: Axial) -> Option<&T> {
+        self.map.get(cell)
+    }
+
+    /// Start grouping changes into one transaction, until
+    /// [`HexMapHistory::commit`].  Does nothing if one is already open.
+    pub fn begin_txn(&mut self) {
+        self.open.get_or_insert_with(HashMap::default);
+    }
+
+    /// true while a transaction is open
+    pub fn in_txn(&self) -> bool {
+        self.open.is_some()
+    }
+
+    /// Close the open transaction, so it can be undone.  Returns false if
+    /// it changed nothing, or none was open.
+    pub fn commit(&mut self) -> bool {
+        let Some(open) = self.open.take() else {
+            return false;
+        };
+        let mut changes: Vec<CellChange<T>> = open
+            .into_iter()
+            .filter_map(|(cell, before)| {
+                let after = self.map.get(cell).cloned();
+                (before != after).then_some(CellChange {
+                    cell,
+                    before,
+                    after,
+                })
+            })
+            .collect();
+        if changes.is_empty() {
+            return false;
+        }
+        changes.sort_by_key(|change| change.cell);
+        self.undo.push(changes);
+        if self.undo.len() > self.limit {
+            let excess = self.undo.len() - self.limit;
+            self.undo.drain(..excess);
+        }
+        self.redo.clear();
+        true
+    }
+
+    /// close the open transaction, putting back every cell it changed
+    pub fn rollback(&mut self) {
+        for (cell, before) in self.open.take().into_iter().flatten() {
+            set(&mut self.map, cell, before);
+        }
+    }
+
+    /// set the value for a cell, returning the previous value
+    pub fn insert(&mut self, cell: Axial, value: T) -> Option<T> {
+        self.edit(cell, |map| map.insert(cell, value))
+    }
+
+    pub fn remove(&mut self, cell: Axial) -> Option<T> {
+        self.edit(cell, |map| map.remove(cell))
+    }
+
+    /// change the value of a cell in place; false if it has none
+    pub fn modify(&mut self, cell: Axial, f: impl FnOnce(&mut T)) -> bool {
+        self.edit(cell, |map| map.get_mut(cell).map(f).is_some())
+    }
+
+    /// set every cell in `cells` to `value`, as one change
+    pub fn fill(&mut self, cells: impl IntoIterator<Item = Axial>, value: T) {
+        self.batch(|history| {
+            for cell in cells {
+                history.insert(cell, value.clone());
+            }
+        });
+    }
+
+    /// Undo the last transaction, committing an open one first.  Returns
+    /// false if there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        self.commit();
+        let Some(changes) = self.undo.pop() else {
+            return false;
+        };
+        for change in &changes {
+            set(&mut self.map, change.cell, change.before.clone());
+        }
+        self.redo.push(changes);
+        true
+    }
+
+    /// Redo the last undone transaction.  Returns false if there is nothing
+    /// to redo, or a transaction is open.
+    pub fn redo(&mut self) -> bool {
+        if self.in_txn() {
+            return false;
+        }
+        let Some(changes) = self.redo.pop() else {
+            return false;
+        };
+        for change in &changes {
+            set(&mut self.map, change.cell, change.after.clone());
+        }
+        self.undo.push(changes);
+        true
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty() || self.open.as_ref().is_some_and(|open| !open.is_empty())
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.in_txn() && !self.redo.is_empty()
+    }
+
+    /// forget every transaction, keeping the map as it is
+    pub fn clear_history(&mut self) {
+        self.open = None;
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    // apply an edit to one cell, remembering its value if this is the first
+    // time the open transaction touches it
+    fn edit<R>(&mut self, cell: Axial, f: impl FnOnce(&mut HexMap<T>) -> R) -> R {
+        self.batch(|history| {
+            let open = history.open.as_mut().unwrap();
+            if !open.contains_key(&cell) {
+                open.insert(cell, history.map.get(cell).cloned());
+            }
+            f(&mut history.map)
+        })
+    }
+
+    // run `f` in the open transaction, or in one of its own
+    fn batch<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        if self.in_txn() {
+            return f(self);
+        }
+        self.begin_txn();
+        let result = f(self);
+        self.commit();
+        result
+    }
+}
+
+impl<T: Clone + PartialEq> From<HexMap<T>> for HexMapHistory<T> {
+    fn from(map: HexMap<T>) -> Self {
+        Self::new(map)
+    }
+}
+
+fn set<T>(map: &mut HexMap<T>, cell: Axial, value: Option<T>) {
+    match value {
+        Some(value) => map.insert(cell, value),
+        None => map.remove(cell),
+    };
+}
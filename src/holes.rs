@@ -0,0 +1,153 @@
+//! cells cut out of a grid
+//!
+//! Add a [`HexGridHoles`] to a grid entity to leave cells out entirely, for
+//! chasms, or boards that aren't a rectangle: neither the lines nor anything
+//! filling the cell (tint, terrain, highlights, decals) are drawn there, and
+//! nothing behind the grid is hidden by it.  Edges between a hole & a cell
+//! that isn't keep the cell's half of the line.
+//!
+//! ```ignore
+//! let holes: HexGridHoles = Axial::ZERO.ring(2).collect();
+//! commands.entity(grid).insert(holes);
+//! ```
+//!
+//! Holes are stored as one bit per cell over the bounds of the holes, and
+//! sent to the gpu as-is.  [`crate::HexGridGpuPicking`] never picks a hole,
+//! but the cpu cursor still finds the cell under it.
+use bevy::prelude::*;
+
+use crate::{Axial, HexBounds};
+
+/// cells of padding added around the bounds when a hole is added outside
+/// them, so a map cut out cell by cell doesn't repack every time
+const PADDING: i32 = 4;
+
+/// Set of cells not drawn on a grid; see the [module docs](self).
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct HexGridHoles {
+    bounds: HexBounds,
+    /// one bit per cell in `bounds`, row by row
+    bits: Vec<u32>,
+    len: usize,
+}
+
+impl Default for HexGridHoles {
+    fn default() -> Self {
+        Self {
+            bounds: HexBounds::from_cell(Axial::ZERO),
+            bits: vec![0],
+            len: 0,
+        }
+    }
+}
+
+impl HexGridHoles {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn contains(&self, cell: Axial) -> bool {
+        self.index(cell)
+            .is_some_and(|i| self.bits[i / 32] & (1 << (i % 32)) != 0)
+    }
+
+    /// cut `cell` out; false if it already was
+    pub fn insert(&mut self, cell: Axial) -> bool {
+        if !self.bounds.contains(cell) {
+            self.grow(cell);
+        }
+        let i = self.index(cell).unwrap();
+        let (word, bit) = (&mut self.bits[i / 32], 1 << (i % 32));
+        let added = *word & bit == 0;
+        *word |= bit;
+        self.len += usize::from(added);
+        added
+    }
+
+    /// draw `cell` again; false if it wasn't a hole
+    pub fn remove(&mut self, cell: Axial) -> bool {
+        let Some(i) = self.index(cell) else {
+            return false;
+        };
+        let (word, bit) = (&mut self.bits[i / 32], 1 << (i % 32));
+        let removed = *word & bit != 0;
+        *word &= !bit;
+        self.len -= usize::from(removed);
+        removed
+    }
+
+    /// holes, row by row
+    pub fn iter(&self) -> impl Iterator<Item = Axial> + '_ {
+        self.bounds.cells().filter(|&cell| self.contains(cell))
+    }
+
+    /// Bounds covered by the bitset; contains every hole, but may be larger.
+    pub fn bounds(&self) -> HexBounds {
+        self.bounds
+    }
+
+    /// one bit per cell of [`HexGridHoles::bounds`], row by row, as sent to
+    /// the shader
+    pub(crate) fn bits(&self) -> &[u32] {
+        &self.bits
+    }
+
+    fn index(&self, cell: Axial) -> Option<usize> {
+        if !self.bounds.contains(cell) {
+            return None;
+        }
+        let width = self.bounds.size().x;
+        let (q, r) = (cell.q - self.bounds.min.q, cell.r - self.bounds.min.r);
+        Some((r * width + q) as usize)
+    }
+
+    // repack over bounds that include `cell`
+    fn grow(&mut self, cell: Axial) {
+        let pad = Axial::new(PADDING, PADDING);
+        let bounds = if self.is_empty() {
+            HexBounds::new(cell - pad, cell + pad)
+        } else {
+            self.bounds.union(HexBounds::new(cell - pad, cell + pad))
+        };
+        let size = bounds.size();
+        let old = std::mem::replace(
+            self,
+            Self {
+                bounds,
+                bits: vec![0; (size.x * size.y) as usize / 32 + 1],
+                len: 0,
+            },
+        );
+        for hole in old.iter() {
+            self.insert(hole);
+        }
+    }
+}
+
+impl FromIterator<Axial> for HexGridHoles {
+    fn from_iter<I: IntoIterator<Item = Axial>>(iter: I) -> Self {
+        let mut holes = Self::default();
+        holes.extend(iter);
+        holes
+    }
+}
+
+impl Extend<Axial> for HexGridHoles {
+    fn extend<I: IntoIterator<Item = Axial>>(&mut self, iter: I) {
+        for cell in iter {
+            self.insert(cell);
+        }
+    }
+}
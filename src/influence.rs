@@ -0,0 +1,316 @@
+//! influence maps
+//!
+//! [`propagate`] spreads the strength of each source out to the cells
+//! within `max_range` steps, falling off with distance by a [`Decay`], and
+//! sums the influence of every source into one field:
+//!
+//! ```ignore
+//! let threat = influence::propagate(
+//!     enemies.iter().map(|e| (e.cell, e.attack)),
+//!     Decay::Linear,
+//!     4,
+//! );
+//! commands.entity(grid).insert(HexGridHeatmap::new(threat));
+//! ```
+//!
+//! Use [`propagate_blended`] to keep the strongest influence on each cell
+//! instead of the sum, and [`propagate_with_cost`] to measure distance
+//! along paths, so influence doesn't leak through walls.  An
+//! [`InfluenceMap`] keeps several channels, such as one per team, that can
+//! be compared cell by cell.
+//!
+//! Fields are [`HexMap`]s of `f32`, so they can be drawn as a
+//! [`crate::HexGridHeatmap`] as-is.  Cells no source reaches have no value.
+//! With the `parallel` feature, [`propagate`] & [`propagate_blended`] fill
+//! each chunk of the field on its own thread, which pays off with thousands
+//! of sources.
+use bevy::prelude::*;
+
+#[cfg(feature = "parallel")]
+use crate::{map::chunk_index, HexBounds};
+use crate::{path::movement_range, Axial, HexMap};
+
+/// how influence falls off with distance from its source
+#[derive(Debug, Default, Clone, Copy, PartialEq, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Decay {
+    /// full strength out to the max range
+    None,
+    /// falls by the same amount each step, to zero one step past the max
+    /// range
+    #[default]
+    Linear,
+    /// multiplied by the factor for each step
+    Exponential(f32),
+}
+
+impl Decay {
+    /// fraction of the strength left `distance` steps from the source
+    pub fn falloff(self, distance: u32, max_range: u32) -> f32 {
+        if distance > max_range {
+            return 0.0;
+        }
+        match self {
+            Decay::None => 1.0,
+            Decay::Linear => 1.0 - distance as f32 / (max_range + 1) as f32,
+            Decay::Exponential(factor) => factor.powi(distance as i32),
+        }
+    }
+}
+
+/// how the influence of several sources on one cell is combined
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Blend {
+    /// sum of every source
+    #[default]
+    Add,
+    /// largest of every source
+    Max,
+}
+
+impl Blend {
+    fn apply(self, a: f32, b: f32) -> f32 {
+        match self {
+            Blend::Add => a + b,
+            Blend::Max => a.max(b),
+        }
+    }
+}
+
+/// source of influence in an [`InfluenceMap`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InfluenceSource {
+    pub cell: Axial,
+    /// influence on its own cell; negative to push values down
+    pub strength: f32,
+    /// channel of the map it's added to
+    pub channel: usize,
+}
+
+impl InfluenceSource {
+    pub fn new(cell: Axial, strength: f32) -> Self {
+        Self {
+            cell,
+            strength,
+            channel: 0,
+        }
+    }
+
+    pub fn with_channel(mut self, channel: usize) -> Self {
+        self.channel = channel;
+        self
+    }
+}
+
+/// Sum of the influence of each `(cell, strength)` source, over the cells
+/// within `max_range` steps of them.
+pub fn propagate(
+    sources: impl IntoIterator<Item = (Axial, f32)>,
+    decay: Decay,
+    max_range: u32,
+) -> HexMap<f32> {
+    propagate_blended(sources, decay, max_range, Blend::Add)
+}
+
+/// [`propagate`], combining sources with `blend`
+pub fn propagate_blended(
+    sources: impl IntoIterator<Item = (Axial, f32)>,
+    decay: Decay,
+    max_range: u32,
+    blend: Blend,
+) -> HexMap<f32> {
+    #[cfg(feature = "parallel")]
+    return propagate_chunks(
+        &sources.into_iter().collect::<Vec<_>>(),
+        decay,
+        max_range,
+        blend,
+    );
+    #[cfg(not(feature = "parallel"))]
+    {
+        let mut field = HexMap::new();
+        for (source, strength) in sources {
+            let steps = source
+                .range(max_range)
+                .map(|cell| (cell, source.distance(cell)));
+            stamp(&mut field, steps, strength, decay, max_range, blend);
+        }
+        field
+    }
+}
+
+// propagate_blended with each chunk of the field filled on its own thread;
+// every cell still blends its sources in the order given, so the field is
+// the same as filled one source at a time
+#[cfg(feature = "parallel")]
+fn propagate_chunks(
+    sources: &[(Axial, f32)],
+    decay: Decay,
+    max_range: u32,
+    blend: Blend,
+) -> HexMap<f32> {
+    use bevy::platform::collections::HashMap;
+    use rayon::prelude::*;
+
+    let reach = Axial::new(max_range as i32, max_range as i32);
+    let square = |source: Axial| HexBounds::new(source - reach, source + reach);
+
+    // sources that may reach each chunk
+    let mut chunks: HashMap<Axial, Vec<usize>> = HashMap::default();
+    for (i, &(source, _)) in sources.iter().enumerate() {
+        let bounds = square(source);
+        let (min, _) = chunk_index(bounds.min);
+        let (max, _) = chunk_index(bounds.max);
+        for chunk in HexBounds::new(min, max).cells() {
+            chunks.entry(chunk).or_default().push(i);
+        }
+    }
+
+    let chunks: Vec<_> = chunks.into_iter().collect();
+    let parts: Vec<Vec<(Axial, f32)>> = chunks
+        .par_iter()
+        .map(|(chunk, indices)| {
+            let chunk = HexBounds::chunk(*chunk);
+            let mut field = HexMap::new();
+            for &i in indices {
+                let (source, strength) = sources[i];
+                let bounds = square(source);
+                let overlap = HexBounds::new(
+                    Axial::new(bounds.min.q.max(chunk.min.q), bounds.min.r.max(chunk.min.r)),
+                    Axial::new(bounds.max.q.min(chunk.max.q), bounds.max.r.min(chunk.max.r)),
+                );
+                let steps = overlap
+                    .cells()
+                    .map(|cell| (cell, source.distance(cell)))
+                    .filter(|&(_, distance)| distance <= max_range);
+                stamp(&mut field, steps, strength, decay, max_range, blend);
+            }
+            field.iter().map(|(cell, &v)| (cell, v)).collect()
+        })
+        .collect();
+    parts.into_iter().flatten().collect()
+}
+
+/// [`propagate_blended`], with distance measured as the cost of the
+/// cheapest path from each source; see [`movement_range`].  `cost(cell)` is
+/// the cost of stepping into a cell, or `None` if influence can't pass it.
+pub fn propagate_with_cost(
+    sources: impl IntoIterator<Item = (Axial, f32)>,
+    decay: Decay,
+    max_range: u32,
+    blend: Blend,
+    cost: impl Fn(Axial) -> Option<u32>,
+) -> HexMap<f32> {
+    let mut field = HexMap::new();
+    for (source, strength) in sources {
+        let costs = movement_range(source, max_range, &cost);
+        let steps = costs.iter().map(|(cell, &distance)| (cell, distance));
+        stamp(&mut field, steps, strength, decay, max_range, blend);
+    }
+    field
+}
+
+// blend one source into `field`, over (cell, distance) pairs
+fn stamp(
+    field: &mut HexMap<f32>,
+    steps: impl Iterator<Item = (Axial, u32)>,
+    strength: f32,
+    decay: Decay,
+    max_range: u32,
+    blend: Blend,
+) {
+    for (cell, distance) in steps {
+        let value = strength * decay.falloff(distance, max_range);
+        match field.get_mut(cell) {
+            Some(v) => *v = blend.apply(*v, value),
+            None => {
+                field.insert(cell, value);
+            }
+        }
+    }
+}
+
+/// Influence fields in several channels, such as one per team, all
+/// propagated the same way.
+#[derive(Debug, Clone)]
+pub struct InfluenceMap {
+    channels: Vec<HexMap<f32>>,
+    pub decay: Decay,
+    pub max_range: u32,
+    pub blend: Blend,
+}
+
+impl InfluenceMap {
+    pub fn new(channels: usize, decay: Decay, max_range: u32) -> Self {
+        Self {
+            channels: (0..channels).map(|_| HexMap::new()).collect(),
+            decay,
+            max_range,
+            blend: Blend::Add,
+        }
+    }
+
+    pub fn with_blend(mut self, blend: Blend) -> Self {
+        self.blend = blend;
+        self
+    }
+
+    /// Spread a source into its channel.  Panics if the channel is past
+    /// the channels the map was created with.
+    pub fn add(&mut self, source: InfluenceSource) {
+        let (cell, max_range) = (source.cell, self.max_range);
+        let steps = cell.range(max_range).map(|c| (c, cell.distance(c)));
+        let field = &mut self.channels[source.channel];
+        stamp(
+            field,
+            steps,
+            source.strength,
+            self.decay,
+            max_range,
+            self.blend,
+        );
+    }
+
+    /// clear every channel
+    pub fn clear(&mut self) {
+        self.channels.iter_mut().for_each(HexMap::clear);
+    }
+
+    pub fn channel_count(&self) -> usize {
+        self.channels.len()
+    }
+
+    pub fn channel(&self, channel: usize) -> &HexMap<f32> {
+        &self.channels[channel]
+    }
+
+    /// influence of a channel on `cell`; 0 where no source reaches
+    pub fn get(&self, channel: usize, cell: Axial) -> f32 {
+        self.channels[channel].get(cell).copied().unwrap_or(0.0)
+    }
+
+    /// Influence of channel `a` less channel `b`, over the cells either
+    /// reaches; positive where `a` is stronger.  The front line between two
+    /// teams is where it crosses zero.
+    pub fn difference(&self, a: usize, b: usize) -> HexMap<f32> {
+        let mut field: HexMap<f32> = self.channels[a].iter().map(|(c, &v)| (c, v)).collect();
+        for (cell, &v) in self.channels[b].iter() {
+            *field.get_or_insert_with(cell, || 0.0) -= v;
+        }
+        field
+    }
+
+    pub fn into_channels(self) -> Vec<HexMap<f32>> {
+        self.channels
+    }
+}
+
+impl Extend<InfluenceSource> for InfluenceMap {
+    fn extend<I: IntoIterator<Item = InfluenceSource>>(&mut self, iter: I) {
+        for source in iter {
+            self.add(source);
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//! ready-made grid controls built on leafwing-input-manager
+//!
+//! Add [`HexGridInputPlugin`] to the app, and an [`InputMap`] such as
+//! [`HexGridAction::default_input_map`] to any camera with a [`HexGridCursor`].  Raw input is translated into
+//! [`HexGridInputEvent`]s so game code doesn't need to know about bindings.
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+use crate::{Axial, HexGridCursor};
+
+pub struct HexGridInputPlugin;
+
+impl Plugin for HexGridInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(InputManagerPlugin::<HexGridAction>::default())
+            .add_message::<HexGridInputEvent>()
+            .add_systems(Update, emit_hex_grid_input_events);
+    }
+}
+
+#[derive(Actionlike, PartialEq, Eq, Clone, Copy, Hash, Debug, Reflect)]
+pub enum HexGridAction {
+    /// select the cell under the cursor
+    Select,
+    /// pan the camera across the grid plane
+    #[actionlike(DualAxis)]
+    Pan,
+    /// rotate the camera; only applied while `RotateEnable` is held
+    #[actionlike(DualAxis)]
+    Rotate,
+    RotateEnable,
+    /// zoom in & out
+    #[actionlike(Axis)]
+    Zoom,
+}
+
+impl HexGridAction {
+    #[rustfmt::skip]
+    pub fn default_input_map() -> InputMap<HexGridAction> {
+        InputMap::default()
+            .with(HexGridAction::Select, MouseButton::Left)
+            .with_dual_axis(HexGridAction::Pan, VirtualDPad::wasd())
+            .with_dual_axis(HexGridAction::Pan, VirtualDPad::arrow_keys())
+            .with_dual_axis(HexGridAction::Rotate, MouseMove::default())
+            .with(HexGridAction::RotateEnable, MouseButton::Right)
+            .with_axis(HexGridAction::Zoom, MouseScrollAxis::Y)
+    }
+}
+
+/// grid input for a specific camera
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub enum HexGridInputEvent {
+    Select {
+        camera: Entity,
+        grid: Entity,
+        cell: Axial,
+    },
+    Pan {
+        camera: Entity,
+        delta: Vec2,
+    },
+    Rotate {
+        camera: Entity,
+        delta: Vec2,
+    },
+    Zoom {
+        camera: Entity,
+        delta: f32,
+    },
+}
+
+fn emit_hex_grid_input_events(
+    cameras: Query<(Entity, &ActionState<HexGridAction>, &HexGridCursor)>,
+    mut events: MessageWriter<HexGridInputEvent>,
+) {
+    for (camera, actions, cursor) in &cameras {
+        // selecting only makes sense with a grid under the cursor
+        if let Some(grid) = cursor
+            .grid
+            .filter(|_| actions.just_pressed(&HexGridAction::Select))
+        {
+            events.write(HexGridInputEvent::Select {
+                camera,
+                grid,
+                cell: cursor.cell,
+            });
+        }
+
+        let pan = actions.axis_pair(&HexGridAction::Pan);
+        if pan != Vec2::ZERO {
+            events.write(HexGridInputEvent::Pan { camera, delta: pan });
+        }
+
+        if actions.pressed(&HexGridAction::RotateEnable) {
+            let rotate = actions.axis_pair(&HexGridAction::Rotate);
+            if rotate != Vec2::ZERO {
+                events.write(HexGridInputEvent::Rotate {
+                    camera,
+                    delta: rotate,
+                });
+            }
+        }
+
+        let zoom = actions.value(&HexGridAction::Zoom);
+        if zoom != 0.0 {
+            events.write(HexGridInputEvent::Zoom {
+                camera,
+                delta: zoom,
+            });
+        }
+    }
+}
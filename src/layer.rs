@@ -0,0 +1,226 @@
+//! stacked layers of grids
+//!
+//! Boards with several floors, such as the levels of a dungeon or the air
+//! above a battlefield, are a grid entity for each layer, marked with its
+//! [`HexGridLayer`], under one board entity with [`HexGridLayers`].  Each
+//! layer's grid is raised to the height of its layer, and shown or hidden
+//! with it:
+//!
+//! ```ignore
+//! let board = commands.spawn(HexGridLayers::new(3.0)).id();
+//! for layer in 0..3 {
+//!     commands.spawn((HexGridConfig::default(), HexGridLayer(layer), ChildOf(board)));
+//! }
+//! ```
+//!
+//! Units stand on the grid of their layer with a [`crate::HexPosition`] as
+//! usual.  To search for paths between layers, cells are a [`LayerCell`],
+//! and a [`LayerLinks`] holds the ways from one layer to another, such as
+//! stairs & ladders, for [`find_layered_path`].  For flying units, every
+//! cell can be joined to those straight above & below it instead.
+use bevy::{
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
+
+use crate::{path::astar, Axial};
+
+/// Layer of the grid on this entity, within the [`HexGridLayers`] of its
+/// parent.
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Component)]
+pub struct HexGridLayer(pub i32);
+
+/// Height & visibility of each layer of a board; on the parent of the
+/// layer grids.
+#[derive(Component, Debug, Clone, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct HexGridLayers {
+    /// height between layers, for those without their own height
+    pub spacing: f32,
+    /// heights of layers that aren't `spacing` apart, in the local frame of
+    /// the board
+    pub heights: HashMap<i32, f32>,
+    pub hidden: HashSet<i32>,
+}
+
+impl Default for HexGridLayers {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+impl HexGridLayers {
+    pub fn new(spacing: f32) -> Self {
+        Self {
+            spacing,
+            heights: HashMap::new(),
+            hidden: HashSet::new(),
+        }
+    }
+
+    pub fn with_height(mut self, layer: i32, height: f32) -> Self {
+        self.heights.insert(layer, height);
+        self
+    }
+
+    /// height of a layer's grid above the board
+    pub fn height(&self, layer: i32) -> f32 {
+        self.heights
+            .get(&layer)
+            .copied()
+            .unwrap_or(layer as f32 * self.spacing)
+    }
+
+    pub fn is_visible(&self, layer: i32) -> bool {
+        !self.hidden.contains(&layer)
+    }
+
+    pub fn set_visible(&mut self, layer: i32, visible: bool) {
+        if visible {
+            self.hidden.remove(&layer);
+        } else {
+            self.hidden.insert(layer);
+        }
+    }
+}
+
+pub(crate) fn update_hex_grid_layers(
+    boards: Query<&HexGridLayers>,
+    mut grids: Query<(&HexGridLayer, &ChildOf, &mut Transform, &mut Visibility)>,
+) {
+    for (&HexGridLayer(layer), child_of, mut transform, mut visibility) in &mut grids {
+        let Ok(layers) = boards.get(child_of.parent()) else {
+            continue;
+        };
+        let height = layers.height(layer);
+        if transform.translation.y != height {
+            transform.translation.y = height;
+        }
+        visibility.set_if_neq(match layers.is_visible(layer) {
+            true => Visibility::Inherited,
+            false => Visibility::Hidden,
+        });
+    }
+}
+
+/// cell on one layer of a board
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LayerCell {
+    pub cell: Axial,
+    pub layer: i32,
+}
+
+impl LayerCell {
+    pub const fn new(cell: Axial, layer: i32) -> Self {
+        Self { cell, layer }
+    }
+
+    /// the six neighbors on the same layer
+    pub fn neighbors(self) -> impl Iterator<Item = LayerCell> {
+        self.cell
+            .neighbors()
+            .map(move |cell| Self::new(cell, self.layer))
+    }
+
+    /// same cell on the layer above
+    pub const fn above(self) -> Self {
+        Self::new(self.cell, self.layer + 1)
+    }
+
+    /// same cell on the layer below
+    pub const fn below(self) -> Self {
+        Self::new(self.cell, self.layer - 1)
+    }
+
+    /// steps between two cells, where each step moves to a neighbor, up,
+    /// or down, or both at once
+    pub fn distance(self, other: LayerCell) -> u32 {
+        self.cell
+            .distance(other.cell)
+            .max(self.layer.abs_diff(other.layer))
+    }
+}
+
+/// Ways between the layers of a board, besides the neighbors on each layer.
+/// Links join a cell to the same cell or a neighbor on the layer above or
+/// below; [`find_layered_path`] may not find the cheapest path across
+/// longer links.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LayerLinks {
+    links: HashMap<LayerCell, Vec<LayerCell>>,
+    vertical: bool,
+}
+
+impl LayerLinks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// join every cell to the same cell above & below, for flying units
+    pub fn with_vertical(mut self, vertical: bool) -> Self {
+        self.vertical = vertical;
+        self
+    }
+
+    /// join two cells both ways, such as the ends of a stair
+    pub fn link(&mut self, a: LayerCell, b: LayerCell) {
+        for (from, to) in [(a, b), (b, a)] {
+            let links = self.links.entry(from).or_default();
+            if !links.contains(&to) {
+                links.push(to);
+            }
+        }
+    }
+
+    pub fn unlink(&mut self, a: LayerCell, b: LayerCell) {
+        for (from, to) in [(a, b), (b, a)] {
+            if let Some(links) = self.links.get_mut(&from) {
+                links.retain(|&c| c != to);
+                if links.is_empty() {
+                    self.links.remove(&from);
+                }
+            }
+        }
+    }
+
+    /// cells linked to `cell` on other layers
+    pub fn linked(&self, cell: LayerCell) -> impl Iterator<Item = LayerCell> + '_ {
+        let vertical = self.vertical.then(|| [cell.above(), cell.below()]);
+        self.links
+            .get(&cell)
+            .into_iter()
+            .flatten()
+            .copied()
+            .chain(vertical.into_iter().flatten())
+    }
+
+    /// the neighbors of `cell` on its layer, then the cells linked to it
+    pub fn neighbors(&self, cell: LayerCell) -> impl Iterator<Item = LayerCell> + '_ {
+        cell.neighbors().chain(self.linked(cell))
+    }
+}
+
+/// Cheapest path from `start` to `goal` across the layers joined by
+/// `links`, including both, and its total cost.  `cost(from, to)` is the
+/// cost of each step, as for [`crate::path::find_path`].
+pub fn find_layered_path(
+    start: LayerCell,
+    goal: LayerCell,
+    links: &LayerLinks,
+    mut cost: impl FnMut(LayerCell, LayerCell) -> Option<u32>,
+) -> Option<(Vec<LayerCell>, u32)> {
+    astar(
+        start,
+        |cell| cell == goal,
+        |cell| cell.distance(goal),
+        |cell, successors| {
+            successors.extend(
+                links
+                    .neighbors(cell)
+                    .filter_map(|next| Some((next, cost(cell, next)?))),
+            )
+        },
+    )
+}
@@ -0,0 +1,545 @@
+//! infinite hex grid rendered as a post-processing pass in bevy 0.18.
+//!
+//! Add [`HexGridPlugin`] to your app, spawn a [`HexGridConfig`] for each grid,
+//! and add a [`HexGridCursor`] to any 3d camera that should track the cursor
+//! position on the grids.  Each grid is drawn on the local Y = 0 plane of its
+//! entity for every 3d camera, with either an orthographic or perspective
+//! projection.  Grid layout & appearance is controlled by the
+//! [`HexGridConfig`] component on the grid entity.  Grids can be spawned,
+//! hidden, and despawned at any time.
+//!
+//! Cells can be filled with a color using [`HexGridHighlights`], and small
+//! textures can be drawn on them with [`HexGridDecals`]; see the
+//! [`highlight`] & [`decal`] modules.  Cells can be filled with tiled
+//! terrain textures, blended across their edges, with [`HexGridTerrain`];
+//! see [`terrain`].  Shores & other transitions between terrains can be
+//! picked from each cell's neighbors by [`HexGridAutoTile`] rules; see
+//! [`autotile`].  Height differences between cells are drawn as cliffs
+//! with [`HexGridElevation`]; see [`elevation`].  Single cells can be cut
+//! out of a grid with [`HexGridHoles`]; see [`holes`].  Animated clouds,
+//! scanlines, or static can be drawn over regions of cells with
+//! [`HexGridWeather`]; see [`weather`].  Cells can be hatched, striped, or
+//! dotted with [`HexGridFills`], for players who can't tell the colors
+//! apart; see [`fill`].  Clicking a cell can show the
+//! distance to its neighbors in rings with [`HexGridDistanceRings`]; see
+//! [`rings`].  Values such as threat can be drawn as a heatmap with
+//! [`HexGridHeatmap`]; see [`heatmap`].  Per-cell random variation that
+//! matches between game code & shaders comes from [`hash`].  Grids can be
+//! clipped to arbitrary shapes with the stencil masks in [`mask`], and
+//! shaded by owning team with [`HexGridOwners`]; see [`territory`].  Teams
+//! can be drawn in colorblind-safe colors & patterns with a
+//! [`ColorblindPreset`]; see [`colorblind`].  Lines
+//! can be drawn between cells of different terrain, weather, or owners with
+//! [`HexGridBorders`]; see [`border`].  Selections can be outlined with a
+//! soft [`HexGridGlow`]; see [`glow`].  Paths can be previewed with a
+//! [`HexGridPathPreview`] ribbon; see [`preview`].  Rivers & roads are
+//! drawn with [`HexGridNetworks`]; see [`network`].  World maps can wrap
+//! around east-west or in both directions; see [`wrap`].  Whole planets can
+//! be tiled with hexagons in the experimental [`sphere`] module.
+//!
+//! For shadow maps, reflections, or VR, where a screen-space pass
+//! misbehaves, the grid can be built as an ordinary mesh instead; see
+//! [`mesh`].  A [`HexGridMinimap`] draws a grid's terrain into an image for
+//! the UI.  A camera's view, grid included, can be drawn into an image with
+//! a [`HexGridSnapshot`], as the thumbnail of a saved game; see
+//! [`snapshot`].  Whole boards can be exported to glTF with [`export`].
+//!
+//! UI & billboards can follow cells as the camera moves with a
+//! [`HexAnchor`]; see [`anchor`].  The look of a grid can be kept in a
+//! hot-reloadable [`HexGridStyle`] asset; see [`style`].  Named styles can
+//! be switched between & crossfaded as themes; see [`theme`].
+//!
+//! Boards with several floors stack a grid for each with
+//! [`HexGridLayers`]; see [`layer`].
+//!
+//! Cell state can be kept in components on entities spawned only for the
+//! cells that need them; see [`cells`].  Gameplay flags can be put on cells
+//! by name with [`HexTags`]; see [`tags`].  The entities standing on each
+//! cell are tracked by [`HexOccupancy`]; see [`occupancy`].  Entities are moved
+//! between cells with a [`HexPosition`]; see [`position`].  Built-in
+//! animations run on [`GridTime`], which can be paused & scaled; see
+//! [`time`].  Threat & influence fields for AI can be spread from sources
+//! with [`influence`], and the cells each unit can see found with [`fov`].
+//! Tactics rules for moving past enemy units are in [`zoc`].
+//! Networked games can replicate changes to the board as [`HexCommand`]s;
+//! see [`command`].  Those changes can be recorded &
+//! played back at any speed with [`replay`].  Level editors can undo &
+//! redo changes to a [`HexMap`] with a [`HexMapHistory`]; see [`history`].
+//!
+//! [`camera::HexGridCameraPlugin`] provides an optional orbit/pan camera
+//! controller for board-game style views, and [`drag::HexGridDragPlugin`]
+//! moves units between cells by drag-and-drop.  Long paths can be searched
+//! for on background threads with [`pathfind::HexGridPathPlugin`], and
+//! groups of units can keep together as they move with [`formation`].
+//! [`editor::HexEditorPlugin`] paints terrain, elevation, & owners in game,
+//! for level editing.  [`HexGridDiagnosticsPlugin`] measures the gpu time
+//! of the grid pass; see [`diagnostics`].  [`changes::HexMapChangesPlugin`]
+//! sends a message for each changed cell of a grid's maps.
+//!
+//! Optional features:
+//! - `editor`: egui panel for [`editor::HexEditorPlugin`], with saving &
+//!   loading; enables `serde`
+//! - `leafwing`: [`input`] module with a ready-made action set for grid
+//!   controls built on leafwing-input-manager
+//! - `parallel`: runs field of view for many units, influence
+//!   propagation, and batches of flow fields across threads with rayon
+//! - `picking`: [`pointer`] module, a bevy_picking backend that sends
+//!   pointer events for grid cells
+//! - `serde`: `Serialize`/`Deserialize` for coordinates, [`HexMap`], and
+//!   config types, plus a compact binary encoding for [`HexMap`], and
+//!   loading [`HexGridStyle`]s from RON
+//! - `tiled`: [`tiled`] module for loading hexagonal Tiled (`.tmx`) maps
+use bevy::{
+    camera::visibility::RenderLayers,
+    core_pipeline::core_3d::graph::{Core3d, Node3d},
+    prelude::*,
+    render::{
+        extract_component::{ExtractComponent, ExtractComponentPlugin},
+        graph::CameraDriverLabel,
+        render_graph::{
+            InternedRenderLabel, RenderGraph, RenderGraphExt, RenderLabel, ViewNodeRunner,
+        },
+        render_resource::{SpecializedMeshPipelines, SpecializedRenderPipelines},
+        Render, RenderApp, RenderSystems,
+    },
+    ui::UiSystems,
+};
+
+pub mod anchor;
+pub mod animation;
+pub mod autotile;
+mod bake;
+pub mod border;
+pub mod camera;
+pub mod cells;
+pub mod changes;
+pub mod colorblind;
+pub mod command;
+pub mod config;
+pub mod coords;
+pub mod decal;
+pub mod diagnostics;
+pub mod drag;
+pub mod editor;
+pub mod elevation;
+pub mod export;
+pub mod fill;
+mod flood;
+pub mod formation;
+pub mod fov;
+pub mod frame;
+pub mod gen;
+pub mod glow;
+pub mod hash;
+mod heat;
+pub mod heatmap;
+pub mod highlight;
+pub mod history;
+pub mod holes;
+pub mod influence;
+#[cfg(feature = "leafwing")]
+pub mod input;
+pub mod layer;
+pub mod map;
+pub mod mask;
+pub mod mesh;
+pub mod minimap;
+pub mod network;
+pub mod occupancy;
+mod owners;
+pub mod path;
+pub mod pathfind;
+pub mod picking;
+#[cfg(feature = "picking")]
+pub mod pointer;
+pub mod position;
+pub mod preview;
+pub mod raycast;
+mod render;
+pub mod replay;
+pub mod rings;
+pub mod select;
+pub mod shape;
+pub mod snapshot;
+pub mod sphere;
+mod stencil;
+pub mod style;
+pub mod tags;
+pub mod terrain;
+pub mod territory;
+pub mod theme;
+#[cfg(feature = "tiled")]
+pub mod tiled;
+pub mod time;
+pub mod weather;
+pub mod wfc;
+pub mod wrap;
+pub mod zoc;
+
+pub use anchor::HexAnchor;
+pub use animation::{CellAnimation, CellAnimationCommands, HexGridCellAnimations};
+pub use autotile::{AutoTileRule, HexGridAutoTile};
+use bake::{HexGridBakeLabel, HexGridBakeNode, HexGridBakePipeline, HexGridBakes};
+pub use border::{BorderLayer, BorderStyle, HexGridBorders};
+pub use cells::{HexCell, HexCellIndex, HexCells};
+pub use colorblind::{ColorblindPreset, OKABE_ITO};
+pub use command::{
+    HexBoard, HexCommand, HexCommandApplied, HexGridCommand, HexGridCommandPlugin, HexUnit, UnitId,
+};
+pub use config::{
+    GridKind, HexGridConfig, LineAntialiasing, LineWidthMode, Orientation, SuperHexBorders,
+};
+pub use coords::{Axial, Cube, HexDirection, HexEdge, HexVertex};
+pub use decal::{Decal, DecalId, HexGridDecalAtlas, HexGridDecals};
+pub use diagnostics::{HexGridDiagnostics, HexGridDiagnosticsPlugin};
+pub use elevation::{CliffStyle, HexGridElevation};
+pub use fill::{CellFill, FillId, FillPattern, HexGridFills};
+use flood::HexGridGlowPipeline;
+pub use frame::HexGridPlane;
+pub use glow::HexGridGlow;
+use heat::HexGridHeatTextures;
+pub use heatmap::{HeatNormalization, HexGridHeatmap};
+pub use highlight::{
+    EdgeStroke, HexGridBakeHighlights, HexGridHighlights, HexGridPartHighlights, StrokePattern,
+};
+pub use history::HexMapHistory;
+pub use holes::HexGridHoles;
+pub use influence::{InfluenceMap, InfluenceSource};
+pub use layer::{HexGridLayer, HexGridLayers, LayerCell};
+pub use map::{HexBounds, HexMap, HexMapVersion, HexStamp};
+pub use mask::{HexGridMask, HexGridStencil, StencilCompare};
+pub use mesh::HexGridMesh;
+pub use minimap::HexGridMinimap;
+pub use network::{HexEdgeSet, HexGridNetworks, StrokeStyle};
+pub use occupancy::{HexOccupancy, Occupancy};
+use owners::HexGridOwnerTextures;
+pub use path::{PathNode, PortalGraph};
+pub use picking::HexGridGpuPicking;
+pub use position::{EnteredCell, FollowPath, HexPosition, HexPositionInterpolation, PathComplete};
+pub use preview::{HexGridPathPreview, PathPreviewStyle};
+use render::{
+    ExtractedDecalAtlas, HexGridBindGroupCache, HexGridBuffers, HexGridLabel, HexGridPipeline,
+    HexGridRenderNode, SharedHexGridStatus,
+};
+pub use render::{HexGridStatus, HexGridStatusChanged};
+pub use replay::{HexGridPlayback, HexGridRecorder, HexReplay};
+pub use rings::HexGridDistanceRings;
+pub use select::{HexGridBoxSelect, HexGridSelectStarted, HexGridSelected};
+pub use snapshot::{HexGridSnapshot, HexGridSnapshotTaken};
+use stencil::{HexGridMaskPipeline, HexGridMasks};
+pub use style::{HexGridStyle, HexGridStyleHandle};
+pub use tags::HexTags;
+pub use terrain::{HexGridTerrain, Terrain, TerrainId, TileVariant};
+pub use territory::{CellOwner, HexGridOwners, HexGridPalette};
+pub use theme::{HexGridTheme, HexGridThemeCommands, HexGridThemeFade, HexGridThemes};
+pub use time::GridTime;
+pub use weather::{HexGridWeather, Weather, WeatherId, WeatherPattern};
+pub use wrap::HexWrap;
+
+/// Renders grids for every 3d camera.  By default the grid pass runs after
+/// tonemapping; use [`HexGridPlugin::before_tonemapping`] to draw grids into
+/// the hdr scene instead, and [`HexGridPlugin::insert_after`] &
+/// [`HexGridPlugin::insert_before`] to move it relative to other nodes in the
+/// core 3d render graph.
+#[derive(Debug, Clone)]
+pub struct HexGridPlugin {
+    after: InternedRenderLabel,
+    before: InternedRenderLabel,
+    tonemapping: HexGridTonemapping,
+}
+
+impl Default for HexGridPlugin {
+    fn default() -> Self {
+        Self {
+            after: Node3d::Tonemapping.intern(),
+            before: Node3d::EndMainPassPostProcessing.intern(),
+            tonemapping: HexGridTonemapping::After,
+        }
+    }
+}
+
+/// Where the grid pass runs relative to tonemapping, so grid colors look the
+/// same either way.  Colors are converted from sRGB to linear on the cpu &
+/// blended in linear space; the view target encodes them back to sRGB.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HexGridTonemapping {
+    /// drawn over the tonemapped image, with the configured colors as-is
+    #[default]
+    After,
+    /// Drawn into the hdr scene before post-processing, so grids get bloom &
+    /// the like.  On hdr cameras colors are run through the inverse of the
+    /// camera's tonemapping & exposure, so they come out of tonemapping as
+    /// configured.  The inverse is exact for `Reinhard` &
+    /// `ReinhardLuminance`; the other tonemappers are approximated by the
+    /// inverse of `Reinhard`, which is close for the neutral ones such as
+    /// `TonyMcMapface`.
+    /// Cameras without hdr are tonemapped in their material shaders, and get
+    /// the configured colors.
+    Before,
+}
+
+impl HexGridPlugin {
+    /// run the grid pass after the core 3d graph node `label`, for example
+    /// `Node3d::Bloom`, or a node added by another plugin
+    pub fn insert_after(mut self, label: impl RenderLabel) -> Self {
+        self.after = label.intern();
+        self
+    }
+
+    /// run the grid pass before the core 3d graph node `label`; defaults to
+    /// the end of post-processing
+    pub fn insert_before(mut self, label: impl RenderLabel) -> Self {
+        self.before = label.intern();
+        self
+    }
+
+    /// run the grid pass between the main pass & post-processing, with
+    /// colors compensated for tonemapping; see [`HexGridTonemapping::Before`].
+    /// Use [`HexGridPlugin::insert_after`] & [`HexGridPlugin::insert_before`]
+    /// afterwards to move it among the hdr post-processing nodes.
+    pub fn before_tonemapping(mut self) -> Self {
+        self.after = Node3d::EndMainPass.intern();
+        self.before = Node3d::StartMainPassPostProcessing.intern();
+        self.tonemapping = HexGridTonemapping::Before;
+        self
+    }
+}
+
+impl Plugin for HexGridPlugin {
+    fn build(&self, app: &mut App) {
+        let status = SharedHexGridStatus::default();
+        app.register_type::<HexGridConfig>()
+            .register_type::<HexGridCursor>()
+            .register_type::<HexGridBoxSelect>()
+            .register_type::<HexGridDistanceRings>()
+            .register_type::<HexGridGpuPicking>()
+            .register_type::<HexGridMask>()
+            .register_type::<HexGridPalette>()
+            .register_type::<HexGridBorders>()
+            .register_type::<HexGridPathPreview>()
+            .register_type::<Axial>()
+            .register_type::<HexCell>()
+            .register_type::<HexPosition>()
+            .register_type::<HexPositionInterpolation>()
+            .register_type::<FollowPath>()
+            .register_type::<HexAnchor>()
+            .register_type::<HexGridLayer>()
+            .register_type::<HexGridLayers>()
+            .init_asset::<HexGridStyle>()
+            .init_resource::<HexGridStatus>()
+            .init_resource::<HexGridThemes>()
+            .init_resource::<HexGridDecalAtlas>()
+            .init_resource::<HexCellIndex>()
+            .init_resource::<HexOccupancy>()
+            .insert_resource(status.clone())
+            .add_message::<HexGridStatusChanged>()
+            .add_message::<HexGridSelectStarted>()
+            .add_message::<HexGridSelected>()
+            .add_message::<EnteredCell>()
+            .add_message::<PathComplete>()
+            .add_message::<HexGridSnapshotTaken>()
+            .add_plugins((
+                HexGridCommandPlugin,
+                ExtractComponentPlugin::<HexGridCursor>::default(),
+                ExtractComponentPlugin::<HexGridBoxSelect>::default(),
+                ExtractComponentPlugin::<HexGridGpuPicking>::default(),
+            ))
+            .add_systems(
+                Update,
+                (
+                    (
+                        update_hex_grid_cursor,
+                        select::update_box_select,
+                        rings::select_distance_rings,
+                    )
+                        .chain(),
+                    picking::update_gpu_picking,
+                    snapshot::start_snapshots,
+                    render::sync_hex_grid_status,
+                    decal::build_decal_atlas,
+                    preview::animate_path_previews,
+                    theme::update_theme_fades,
+                ),
+            )
+            .add_systems(First, territory::clear_owner_changes)
+            .add_systems(
+                PostUpdate,
+                (
+                    animation::update_cell_animations,
+                    preview::update_path_previews,
+                    (position::follow_paths, position::sync_hex_positions)
+                        .chain()
+                        .before(TransformSystems::Propagate),
+                    layer::update_hex_grid_layers.before(TransformSystems::Propagate),
+                    minimap::update_minimaps,
+                    autotile::update_auto_tiles,
+                    anchor::anchor_ui_nodes.before(UiSystems::Layout),
+                    anchor::anchor_billboards.after(TransformSystems::Propagate),
+                ),
+            );
+        #[cfg(feature = "serde")]
+        app.init_asset_loader::<style::HexGridStyleLoader>();
+
+        let render_app = app
+            .get_sub_app_mut(RenderApp)
+            .expect("RenderApp should already exist in App");
+
+        render_app
+            .init_resource::<HexGridBuffers>()
+            .init_resource::<HexGridBindGroupCache>()
+            .init_resource::<ExtractedDecalAtlas>()
+            .init_resource::<HexGridBakes>()
+            .init_resource::<HexGridMasks>()
+            .init_resource::<HexGridOwnerTextures>()
+            .init_resource::<HexGridHeatTextures>()
+            .init_resource::<SpecializedRenderPipelines<HexGridPipeline>>()
+            .init_resource::<SpecializedMeshPipelines<HexGridMaskPipeline>>()
+            .insert_resource(status)
+            .add_systems(
+                ExtractSchedule,
+                (
+                    render::extract_hex_grids,
+                    render::extract_hex_grid_view_scales,
+                    stencil::extract_hex_grid_masks,
+                    owners::extract_hex_grid_owners,
+                    heat::extract_hex_grid_heatmaps,
+                ),
+            )
+            .add_systems(
+                Render,
+                (
+                    (
+                        bake::prepare_hex_grid_bakes,
+                        owners::prepare_hex_grid_owners,
+                        heat::prepare_hex_grid_heatmaps,
+                        render::prepare_hex_grids,
+                        stencil::prepare_hex_grid_stencils,
+                        flood::prepare_hex_grid_glows,
+                    )
+                        .chain()
+                        .in_set(RenderSystems::PrepareResources),
+                    (
+                        bake::queue_hex_grid_bakes,
+                        render::queue_hex_grid_bind_groups,
+                        stencil::queue_hex_grid_stencil_bind_groups,
+                        flood::queue_hex_grid_glow_bind_groups,
+                    )
+                        .in_set(RenderSystems::PrepareBindGroups),
+                    render::update_hex_grid_status
+                        .in_set(RenderSystems::Prepare)
+                        .after(render::queue_hex_grid_bind_groups),
+                    render::evict_hex_grid_bind_groups.in_set(RenderSystems::Cleanup),
+                ),
+            );
+
+        // add our post-processing render node to the render graph; it's
+        // wired up in finish() once other plugins have added their nodes
+        render_app.add_render_graph_node::<ViewNodeRunner<HexGridRenderNode>>(Core3d, HexGridLabel);
+    }
+
+    fn finish(&self, app: &mut App) {
+        let render_app = app
+            .get_sub_app_mut(RenderApp)
+            .expect("RenderApp should already exist in App");
+        // baking runs once per frame, before any camera is drawn
+        let mut render_graph = render_app.world_mut().resource_mut::<RenderGraph>();
+        render_graph.add_node(HexGridBakeLabel, HexGridBakeNode);
+        render_graph.add_node_edge(HexGridBakeLabel, CameraDriverLabel);
+
+        render_app
+            .init_resource::<HexGridPipeline>()
+            .init_resource::<HexGridBakePipeline>()
+            .init_resource::<HexGridMaskPipeline>()
+            .init_resource::<HexGridGlowPipeline>()
+            .insert_resource(self.tonemapping)
+            .add_render_graph_edges(Core3d, (self.after, HexGridLabel, self.before));
+    }
+}
+
+/// Cursor position on the nearest grid under the cursor for a camera.  This
+/// is the component that will get passed to the shader.  Found on the cpu,
+/// unless the camera has [`HexGridGpuPicking`].
+#[derive(Component, Default, Debug, Clone, Copy, ExtractComponent, Reflect)]
+#[reflect(Component)]
+pub struct HexGridCursor {
+    /// grid entity under the cursor, if any
+    pub grid: Option<Entity>,
+    /// cursor position on the grid plane (local X, local Z)
+    pub pos: Vec2,
+    /// cell under the cursor; canonical on wrapped maps
+    pub cell: Axial,
+}
+
+#[allow(clippy::type_complexity)]
+fn update_hex_grid_cursor(
+    windows: Query<&Window>,
+    mut cameras: Query<
+        (
+            &Camera,
+            &GlobalTransform,
+            Option<&RenderLayers>,
+            &mut HexGridCursor,
+        ),
+        (
+            Without<HexGridGpuPicking>,
+            Without<snapshot::SnapshotCamera>,
+        ),
+    >,
+    grids: Query<(
+        Entity,
+        &HexGridConfig,
+        &GlobalTransform,
+        &InheritedVisibility,
+        Option<&RenderLayers>,
+    )>,
+) {
+    for window in &windows {
+        let Some(cursor_pos) = window.cursor_position() else {
+            continue;
+        };
+        for (camera, global_transform, camera_layers, mut hex_grid_cursor) in &mut cameras {
+            let Some(ray) = cursor_ray(camera, global_transform, cursor_pos) else {
+                continue;
+            };
+            let camera_layers = camera_layers.cloned().unwrap_or_default();
+
+            // pick the closest grid along the ray that this camera draws
+            let hit = grids
+                .iter()
+                .filter(|(.., visibility, layers)| {
+                    visibility.get()
+                        && camera_layers.intersects(&layers.cloned().unwrap_or_default())
+                })
+                .filter_map(|(entity, config, transform, ..)| {
+                    let plane = HexGridPlane::from(*transform);
+                    Some((
+                        plane.ray_distance(ray)?,
+                        entity,
+                        config,
+                        plane.intersect(ray)?,
+                    ))
+                })
+                .min_by(|a, b| a.0.total_cmp(&b.0));
+
+            match hit {
+                Some((_, entity, config, pos)) => {
+                    hex_grid_cursor.grid = Some(entity);
+                    hex_grid_cursor.pos = pos;
+                    hex_grid_cursor.cell = config.wrap_cell(config.world_to_cell(pos));
+                }
+                None => hex_grid_cursor.grid = None,
+            }
+        }
+    }
+}
+
+/// world-space ray through a window cursor position
+pub(crate) fn cursor_ray(
+    camera: &Camera,
+    transform: &GlobalTransform,
+    cursor: Vec2,
+) -> Option<Ray3d> {
+    // viewport_to_world() takes window positions, and offsets them by the
+    // viewport itself
+    camera.viewport_to_world(transform, cursor).ok()
+}
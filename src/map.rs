@@ -0,0 +1,609 @@
+//! sparse per-cell storage
+//!
+//! [`HexMap`] stores values for an unbounded set of cells.  Cells are grouped
+//! into square chunks of [`CHUNK_SIZE`] x [`CHUNK_SIZE`] axial coordinates so
+//! that dense regions are cheap to store and iterate, and empty regions cost
+//! nothing.
+//!
+//! Maps track which chunks have changed, so a consumer that mirrors a map
+//! (such as a texture on the gpu) can update just those chunks; see
+//! [`HexMap::version`] & [`HexMap::changed_chunks`].  A map can also log
+//! each changed cell, for [`HexMap::drain_changes`] to hand out once, as the
+//! [`crate::changes`] plugin does.  Wrap a map in a [`crate::HexMapHistory`]
+//! to undo & redo changes to it.
+//!
+//! Regions of a map can be copied into a [`HexStamp`] with
+//! [`HexMap::extract_region`], and pasted back anywhere, at any of the six
+//! rotations, with [`HexMap::apply_stamp`], to build maps from prefabs.
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use bevy::{
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
+
+use crate::{Axial, Cube};
+
+/// width & height of a chunk in axial coordinates
+pub const CHUNK_SIZE: i32 = 16;
+const CHUNK_CELLS: usize = (CHUNK_SIZE * CHUNK_SIZE) as usize;
+
+/// inclusive axial-space rectangle of cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HexBounds {
+    pub min: Axial,
+    pub max: Axial,
+}
+
+impl HexBounds {
+    pub fn new(min: Axial, max: Axial) -> Self {
+        Self { min, max }
+    }
+
+    /// bounds containing a single cell
+    pub fn from_cell(cell: Axial) -> Self {
+        Self::new(cell, cell)
+    }
+
+    pub fn contains(&self, cell: Axial) -> bool {
+        (self.min.q..=self.max.q).contains(&cell.q) && (self.min.r..=self.max.r).contains(&cell.r)
+    }
+
+    /// grow the bounds to include `cell`
+    pub fn include(&mut self, cell: Axial) {
+        self.min = Axial::new(self.min.q.min(cell.q), self.min.r.min(cell.r));
+        self.max = Axial::new(self.max.q.max(cell.q), self.max.r.max(cell.r));
+    }
+
+    pub fn union(mut self, other: HexBounds) -> Self {
+        self.include(other.min);
+        self.include(other.max);
+        self
+    }
+
+    /// width & height in cells
+    pub fn size(&self) -> IVec2 {
+        IVec2::new(self.max.q - self.min.q + 1, self.max.r - self.min.r + 1)
+    }
+
+    /// all cells within the bounds, row by row
+    pub fn cells(&self) -> impl Iterator<Item = Axial> {
+        let (min, max) = (self.min, self.max);
+        (min.r..=max.r).flat_map(move |r| (min.q..=max.q).map(move |q| Axial::new(q, r)))
+    }
+
+    /// bounds of a chunk, from [`chunk_index`]
+    pub(crate) fn chunk(chunk: Axial) -> Self {
+        let min = Axial::new(chunk.q * CHUNK_SIZE, chunk.r * CHUNK_SIZE);
+        Self::new(min, min + Axial::new(CHUNK_SIZE - 1, CHUNK_SIZE - 1))
+    }
+}
+
+/// Point in the history of a [`HexMap`]; see [`HexMap::changed_chunks`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HexMapVersion {
+    /// unique to each map; clones get their own
+    map: u64,
+    generation: u64,
+}
+
+// source of HexMapVersion::map
+static NEXT_MAP_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone)]
+struct Chunk<T> {
+    cells: Box<[Option<T>]>,
+    len: usize,
+    /// map generation of the last change
+    changed: u64,
+}
+
+impl<T> Chunk<T> {
+    fn new() -> Self {
+        Self {
+            cells: std::iter::repeat_with(|| None).take(CHUNK_CELLS).collect(),
+            len: 0,
+            changed: 0,
+        }
+    }
+}
+
+/// chunk containing a cell, and the cell index within that chunk
+pub(crate) fn chunk_index(cell: Axial) -> (Axial, usize) {
+    let chunk = Axial::new(cell.q.div_euclid(CHUNK_SIZE), cell.r.div_euclid(CHUNK_SIZE));
+    let q = cell.q.rem_euclid(CHUNK_SIZE);
+    let r = cell.r.rem_euclid(CHUNK_SIZE);
+    (chunk, (r * CHUNK_SIZE + q) as usize)
+}
+
+/// inverse of [`chunk_index`]
+fn chunk_cell(chunk: Axial, index: usize) -> Axial {
+    let index = index as i32;
+    Axial::new(
+        chunk.q * CHUNK_SIZE + index % CHUNK_SIZE,
+        chunk.r * CHUNK_SIZE + index / CHUNK_SIZE,
+    )
+}
+
+// every cell with a value
+fn occupied<T>(chunks: &HashMap<Axial, Chunk<T>>) -> impl Iterator<Item = Axial> + '_ {
+    chunks.iter().flat_map(|(&chunk, c)| {
+        c.cells
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| v.is_some())
+            .map(move |(i, _)| chunk_cell(chunk, i))
+    })
+}
+
+/// sparse map of cell coordinates to values
+#[derive(Debug, Resource)]
+pub struct HexMap<T> {
+    chunks: HashMap<Axial, Chunk<T>>,
+    len: usize,
+    id: u64,
+    /// bumped by every change
+    generation: u64,
+    /// generation each emptied chunk was removed at, until it's refilled
+    removed: HashMap<Axial, u64>,
+    /// cells changed since the last drain, when logging changes
+    changes: Option<HashSet<Axial>>,
+}
+
+impl<T> Default for HexMap<T> {
+    fn default() -> Self {
+        Self {
+            chunks: HashMap::default(),
+            len: 0,
+            id: NEXT_MAP_ID.fetch_add(1, Ordering::Relaxed),
+            generation: 0,
+            removed: HashMap::default(),
+            changes: None,
+        }
+    }
+}
+
+impl<T: Clone> Clone for HexMap<T> {
+    fn clone(&self) -> Self {
+        Self {
+            chunks: self.chunks.clone(),
+            len: self.len,
+            removed: self.removed.clone(),
+            generation: self.generation,
+            changes: self.changes.as_ref().map(|_| HashSet::default()),
+            ..Self::default()
+        }
+    }
+}
+
+/// maps are equal when they hold the same values; their histories don't
+/// matter
+impl<T: PartialEq> PartialEq for HexMap<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().all(|(cell, v)| other.get(cell) == Some(v))
+    }
+}
+
+impl<T> HexMap<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// number of cells with a value
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        if let Some(changes) = &mut self.changes {
+            changes.extend(occupied(&self.chunks));
+        }
+        self.generation += 1;
+        let generation = self.generation;
+        self.removed
+            .extend(self.chunks.drain().map(|(key, _)| (key, generation)));
+        self.len = 0;
+    }
+
+    pub fn get(&self, cell: Axial) -> Option<&T> {
+        let (chunk, index) = chunk_index(cell);
+        self.chunks.get(&chunk)?.cells[index].as_ref()
+    }
+
+    /// the cell's chunk is marked changed, whether or not the value is
+    pub fn get_mut(&mut self, cell: Axial) -> Option<&mut T> {
+        let (key, index) = chunk_index(cell);
+        let chunk = self.chunks.get_mut(&key)?;
+        let value = chunk.cells[index].as_mut()?;
+        self.generation += 1;
+        chunk.changed = self.generation;
+        if let Some(changes) = &mut self.changes {
+            changes.insert(cell);
+        }
+        Some(value)
+    }
+
+    pub fn contains(&self, cell: Axial) -> bool {
+        self.get(cell).is_some()
+    }
+
+    /// set the value for a cell, returning the previous value
+    pub fn insert(&mut self, cell: Axial, value: T) -> Option<T> {
+        let chunk = self.chunk_mut(cell);
+        let prev = chunk.cells[chunk_index(cell).1].replace(value);
+        if prev.is_none() {
+            chunk.len += 1;
+            self.len += 1;
+        }
+        prev
+    }
+
+    pub fn remove(&mut self, cell: Axial) -> Option<T> {
+        let (key, index) = chunk_index(cell);
+        let chunk = self.chunks.get_mut(&key)?;
+        let prev = chunk.cells[index].take()?;
+        if let Some(changes) = &mut self.changes {
+            changes.insert(cell);
+        }
+        chunk.len -= 1;
+        self.len -= 1;
+        self.generation += 1;
+        chunk.changed = self.generation;
+        if chunk.len == 0 {
+            self.chunks.remove(&key);
+            self.removed.insert(key, self.generation);
+        }
+        Some(prev)
+    }
+
+    /// get the value for a cell, inserting the result of `f` if missing
+    pub fn get_or_insert_with(&mut self, cell: Axial, f: impl FnOnce() -> T) -> &mut T {
+        let missing = !self.contains(cell);
+        if missing {
+            self.len += 1;
+        }
+        let chunk = self.chunk_mut(cell);
+        if missing {
+            chunk.len += 1;
+        }
+        chunk.cells[chunk_index(cell).1].get_or_insert_with(f)
+    }
+
+    // chunk holding a cell, created if missing, & marked changed along with
+    // the cell
+    fn chunk_mut(&mut self, cell: Axial) -> &mut Chunk<T> {
+        let key = chunk_index(cell).0;
+        if let Some(changes) = &mut self.changes {
+            changes.insert(cell);
+        }
+        self.generation += 1;
+        let chunk = self.chunks.entry(key).or_insert_with(|| {
+            self.removed.remove(&key);
+            Chunk::new()
+        });
+        chunk.changed = self.generation;
+        chunk
+    }
+
+    /// iterate over all cells with values; order is unspecified
+    pub fn iter(&self) -> impl Iterator<Item = (Axial, &T)> {
+        self.chunks.iter().flat_map(|(&chunk, c)| {
+            c.cells
+                .iter()
+                .enumerate()
+                .filter_map(move |(i, v)| Some((chunk_cell(chunk, i), v.as_ref()?)))
+        })
+    }
+
+    /// every chunk is marked changed, & every cell when logging changes
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Axial, &mut T)> {
+        if let Some(changes) = &mut self.changes {
+            changes.extend(occupied(&self.chunks));
+        }
+        self.generation += 1;
+        let generation = self.generation;
+        self.chunks.iter_mut().flat_map(move |(&chunk, c)| {
+            c.changed = generation;
+            c.cells
+                .iter_mut()
+                .enumerate()
+                .filter_map(move |(i, v)| Some((chunk_cell(chunk, i), v.as_mut()?)))
+        })
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = Axial> + '_ {
+        self.iter().map(|(cell, _)| cell)
+    }
+
+    /// chunks with at least one value, as from [`chunk_index`]
+    pub(crate) fn chunks(&self) -> impl Iterator<Item = Axial> + '_ {
+        self.chunks.keys().copied()
+    }
+
+    /// current version of the map, to pass to [`HexMap::changed_chunks`]
+    /// later
+    pub fn version(&self) -> HexMapVersion {
+        HexMapVersion {
+            map: self.id,
+            generation: self.generation,
+        }
+    }
+
+    /// Bounds of each chunk that has changed since `since`, including
+    /// chunks that have since been emptied.  Chunks are marked changed by
+    /// any mutable access to their cells.  `None` if `since` is from another
+    /// map, in which case every cell should be treated as changed.
+    pub fn changed_chunks(
+        &self,
+        since: HexMapVersion,
+    ) -> Option<impl Iterator<Item = HexBounds> + '_> {
+        if since.map != self.id {
+            return None;
+        }
+        let changed = self
+            .chunks
+            .iter()
+            .filter(move |(_, chunk)| chunk.changed > since.generation)
+            .map(|(&key, _)| key);
+        let removed = self
+            .removed
+            .iter()
+            .filter(move |(_, &generation)| generation > since.generation)
+            .map(|(&key, _)| key);
+        Some(changed.chain(removed).map(HexBounds::chunk))
+    }
+
+    /// Start logging each changed cell for [`HexMap::drain_changes`].  Every
+    /// cell with a value is logged to begin with, as a consumer starting
+    /// now hasn't seen any of them.  Clones log changes too, from an empty
+    /// log.
+    pub fn track_changes(&mut self) {
+        if self.changes.is_none() {
+            self.changes = Some(occupied(&self.chunks).collect());
+        }
+    }
+
+    pub fn with_change_log(mut self) -> Self {
+        self.track_changes();
+        self
+    }
+
+    pub fn tracks_changes(&self) -> bool {
+        self.changes.is_some()
+    }
+
+    /// Cells changed since the last drain, once each, in no order, & empty
+    /// the log.  Cells are logged by the same accesses that mark chunks
+    /// changed in [`HexMap::changed_chunks`], including cells since
+    /// removed.  Nothing is logged until [`HexMap::track_changes`].
+    pub fn drain_changes(&mut self) -> impl Iterator<Item = Axial> + '_ {
+        self.changes.iter_mut().flat_map(|changes| changes.drain())
+    }
+
+    /// smallest bounds containing every cell with a value
+    pub fn bounds(&self) -> Option<HexBounds> {
+        let mut cells = self.cells();
+        let first = cells.next()?;
+        Some(cells.fold(HexBounds::from_cell(first), |mut bounds, cell| {
+            bounds.include(cell);
+            bounds
+        }))
+    }
+}
+
+impl<T: Clone> HexMap<T> {
+    /// Copy `cells` into a stamp, relative to the middle of the region.
+    /// Cells without a value are copied too, & cleared when it's applied.
+    pub fn extract_region(&self, cells: impl IntoIterator<Item = Axial>) -> HexStamp<T> {
+        let mut cells: Vec<Axial> = cells.into_iter().collect();
+        cells.sort();
+        cells.dedup();
+        let sum = cells.iter().fold(Vec3::ZERO, |sum, &cell| {
+            let cube = cell.to_cube();
+            sum + Vec3::new(cube.q as f32, cube.r as f32, cube.s as f32)
+        });
+        let middle: Axial = Cube::round(sum / cells.len().max(1) as f32).into();
+        HexStamp {
+            cells: cells
+                .into_iter()
+                .map(|cell| (cell - middle, self.get(cell).cloned()))
+                .collect(),
+        }
+    }
+
+    /// Paste a stamp with its middle on `anchor`, turned `rotation` * 60
+    /// degrees as in [`Axial::rotate_around`].
+    pub fn apply_stamp(&mut self, stamp: &HexStamp<T>, anchor: Axial, rotation: i32) {
+        self.apply_stamp_with(stamp, anchor, rotation, T::clone);
+    }
+
+    /// Paste a stamp like [`HexMap::apply_stamp`], passing each value
+    /// through `f`, to turn values that face a direction along with it.
+    pub fn apply_stamp_with(
+        &mut self,
+        stamp: &HexStamp<T>,
+        anchor: Axial,
+        rotation: i32,
+        mut f: impl FnMut(&T) -> T,
+    ) {
+        for (cell, value) in stamp.placed(anchor, rotation) {
+            match value {
+                Some(value) => self.insert(cell, f(value)),
+                None => self.remove(cell),
+            };
+        }
+    }
+}
+
+/// Region of a [`HexMap`] copied with [`HexMap::extract_region`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HexStamp<T> {
+    /// each cell of the region, relative to its middle, & its value
+    pub cells: Vec<(Axial, Option<T>)>,
+}
+
+impl<T> HexStamp<T> {
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// drop the cells without a value, so applying the stamp leaves those
+    /// cells of the map as they are
+    pub fn without_empty(mut self) -> Self {
+        self.cells.retain(|(_, value)| value.is_some());
+        self
+    }
+
+    /// each cell of the stamp & its value when pasted with its middle on
+    /// `anchor`, turned `rotation` * 60 degrees
+    pub fn placed(
+        &self,
+        anchor: Axial,
+        rotation: i32,
+    ) -> impl Iterator<Item = (Axial, Option<&T>)> + '_ {
+        self.cells.iter().map(move |(offset, value)| {
+            (
+                offset.rotate_around(Axial::ZERO, rotation) + anchor,
+                value.as_ref(),
+            )
+        })
+    }
+}
+
+impl<T> FromIterator<(Axial, T)> for HexMap<T> {
+    fn from_iter<I: IntoIterator<Item = (Axial, T)>>(iter: I) -> Self {
+        let mut map = HexMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<T> Extend<(Axial, T)> for HexMap<T> {
+    fn extend<I: IntoIterator<Item = (Axial, T)>>(&mut self, iter: I) {
+        for (cell, value) in iter {
+            self.insert(cell, value);
+        }
+    }
+}
+
+/// serde support.  Maps are written as a list of chunks, each holding a
+/// bitmask of the occupied cells followed by only the occupied values in
+/// index order.  Chunks with no cells are skipped when reading.
+#[cfg(feature = "serde")]
+mod serialize {
+    use super::*;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    const MASK_WORDS: usize = CHUNK_CELLS / 64;
+
+    #[derive(Serialize)]
+    struct ChunkRef<'a, T> {
+        chunk: Axial,
+        mask: [u64; MASK_WORDS],
+        values: Vec<&'a T>,
+    }
+
+    #[derive(Deserialize)]
+    struct ChunkOwned<T> {
+        chunk: Axial,
+        mask: [u64; MASK_WORDS],
+        values: Vec<T>,
+    }
+
+    impl<T: Serialize> Serialize for HexMap<T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            // sort the chunks so the same map always produces the same output
+            let mut keys: Vec<_> = self.chunks.keys().copied().collect();
+            keys.sort();
+
+            let chunks: Vec<ChunkRef<T>> = keys
+                .into_iter()
+                .map(|key| {
+                    let chunk = &self.chunks[&key];
+                    let mut mask = [0; MASK_WORDS];
+                    let mut values = Vec::with_capacity(chunk.len);
+                    for (i, value) in chunk.cells.iter().enumerate() {
+                        if let Some(value) = value {
+                            mask[i / 64] |= 1 << (i % 64);
+                            values.push(value);
+                        }
+                    }
+                    ChunkRef {
+                        chunk: key,
+                        mask,
+                        values,
+                    }
+                })
+                .collect();
+            chunks.serialize(serializer)
+        }
+    }
+
+    impl<'de, T: Deserialize<'de>> Deserialize<'de> for HexMap<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            use serde::de::Error;
+
+            let chunks: Vec<ChunkOwned<T>> = Vec::deserialize(deserializer)?;
+            let mut map = HexMap::new();
+            for ChunkOwned {
+                chunk: key,
+                mask,
+                values,
+            } in chunks
+            {
+                let occupied = mask.iter().map(|w| w.count_ones() as usize).sum::<usize>();
+                if occupied != values.len() {
+                    return Err(D::Error::custom(format!(
+                        "chunk {:?} mask has {} cells, but {} values",
+                        key,
+                        occupied,
+                        values.len()
+                    )));
+                }
+
+                // keep chunks non-empty, as `remove` does
+                if occupied == 0 {
+                    continue;
+                }
+                if map.chunks.contains_key(&key) {
+                    return Err(D::Error::custom(format!("duplicate chunk {:?}", key)));
+                }
+
+                let mut values = values.into_iter();
+                let chunk = map.chunks.entry(key).or_insert_with(Chunk::new);
+                for i in 0..CHUNK_CELLS {
+                    if mask[i / 64] & (1 << (i % 64)) != 0 {
+                        chunk.cells[i] = values.next();
+                        chunk.len += 1;
+                    }
+                }
+                map.len += chunk.len;
+            }
+            Ok(map)
+        }
+    }
+
+    impl<T: Serialize> HexMap<T> {
+        /// encode the map into a compact binary format
+        pub fn to_bytes(&self) -> bincode::Result<Vec<u8>> {
+            bincode::serialize(self)
+        }
+    }
+
+    impl<T: for<'de> Deserialize<'de>> HexMap<T> {
+        /// decode a map written by [`HexMap::to_bytes`]
+        pub fn from_bytes(bytes: &[u8]) -> bincode::Result<Self> {
+            bincode::deserialize(bytes)
+        }
+    }
+}
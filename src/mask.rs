@@ -0,0 +1,87 @@
+//! masking grids to arbitrary shapes
+//!
+//! Add a [`HexGridMask`] to an entity with a [`Mesh3d`] to write its shape
+//! into the stencil buffer of every camera that can see it, then set
+//! [`crate::HexGridConfig::stencil`] on a grid to only draw it where the
+//! stencil test passes; inside a building footprint, or everywhere but a lake.
+//! Mask meshes are only drawn into the stencil buffer, so they don't need a
+//! material.
+//!
+//! The core 3d depth texture has no stencil aspect, so each camera that draws
+//! a masked grid gets its own 8-bit stencil texture.  It's cleared to 0 and
+//! the masks are drawn into it right before the grid pass, then the grid
+//! shader tests every pixel against it.  Masks aren't depth tested; a mask
+//! behind other geometry still masks the grid.
+use bevy::prelude::*;
+
+/// Writes the shape of this entity's [`Mesh3d`] into the grid stencil buffer
+/// with this value.  Where masks overlap, the value of either may be written.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct HexGridMask(pub u8);
+
+impl Default for HexGridMask {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+/// Comparison made by a [`HexGridStencil`] test, between the reference value
+/// and the value in the stencil buffer.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StencilCompare {
+    #[default]
+    Equal,
+    NotEqual,
+    /// reference < stencil
+    Less,
+    /// reference <= stencil
+    LessEqual,
+    /// reference > stencil
+    Greater,
+    /// reference >= stencil
+    GreaterEqual,
+}
+
+/// Stencil test for a grid; the grid is only drawn on pixels where
+/// `reference` compares true against the stencil value.  Pixels not covered
+/// by any [`HexGridMask`] have a stencil value of 0.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HexGridStencil {
+    pub reference: u8,
+    pub compare: StencilCompare,
+}
+
+impl HexGridStencil {
+    /// only draw the grid inside masks with `value`
+    pub fn inside(value: u8) -> Self {
+        Self {
+            reference: value,
+            compare: StencilCompare::Equal,
+        }
+    }
+
+    /// only draw the grid outside masks with `value`
+    pub fn outside(value: u8) -> Self {
+        Self {
+            reference: value,
+            compare: StencilCompare::NotEqual,
+        }
+    }
+
+    /// result of the test against a stencil value; mirrors `stencil_test()`
+    /// in the shader
+    pub fn test(&self, stencil: u8) -> bool {
+        let reference = self.reference;
+        match self.compare {
+            StencilCompare::Equal => reference == stencil,
+            StencilCompare::NotEqual => reference != stencil,
+            StencilCompare::Less => reference < stencil,
+            StencilCompare::LessEqual => reference <= stencil,
+            StencilCompare::Greater => reference > stencil,
+            StencilCompare::GreaterEqual => reference >= stencil,
+        }
+    }
+}
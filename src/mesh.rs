@@ -0,0 +1,95 @@
+//! grid meshes
+//!
+//! The grid is normally drawn per-pixel in a post-processing pass, which
+//! never reaches shadow maps, reflections, or anything else rendered from a
+//! different view.  [`HexGridMesh`] builds ordinary meshes of the cells
+//! within some bounds instead, in the local frame of the grid, to draw with
+//! any material:
+//!
+//! ```ignore
+//! let bounds = HexBounds::new(Axial::new(-8, -8), Axial::new(8, 8));
+//! commands.entity(grid).with_child((
+//!     Mesh3d(meshes.add(HexGridMesh::lines(bounds, &config))),
+//!     MeshMaterial3d(materials.add(StandardMaterial::from_color(WHITE))),
+//! ));
+//! ```
+use bevy::{
+    asset::RenderAssetUsages,
+    mesh::{Indices, PrimitiveTopology},
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
+
+use crate::{HexBounds, HexGridConfig};
+
+/// Builds meshes of grid cells; see the [module docs](self).
+pub struct HexGridMesh;
+
+impl HexGridMesh {
+    /// Line list of the edges of every cell within `bounds`, on the grid
+    /// plane.  Edges shared by two cells are only included once.
+    pub fn lines(bounds: HexBounds, config: &HexGridConfig) -> Mesh {
+        // corners are shared between cells, but computed separately for
+        // each, so they're matched on a rounded position
+        let key = |p: Vec2| (p / config.size * 1024.0).round().as_ivec2();
+        let mut vertices = HashMap::new();
+        let mut positions = Vec::new();
+        let mut edges = HashSet::new();
+        let mut indices = Vec::new();
+        for cell in bounds.cells() {
+            let corners: Vec<u32> = config
+                .cell_corners(cell)
+                .into_iter()
+                .map(|corner| {
+                    *vertices.entry(key(corner)).or_insert_with(|| {
+                        positions.push([corner.x, 0.0, corner.y]);
+                        positions.len() as u32 - 1
+                    })
+                })
+                .collect();
+            for (i, &a) in corners.iter().enumerate() {
+                let b = corners[(i + 1) % corners.len()];
+                if edges.insert((a.min(b), a.max(b))) {
+                    indices.extend([a, b]);
+                }
+            }
+        }
+
+        Mesh::new(PrimitiveTopology::LineList, RenderAssetUsages::default())
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+            .with_inserted_indices(Indices::U32(indices))
+    }
+
+    /// Triangle list filling every cell within `bounds`, facing up from the
+    /// grid plane.  Each cell has its own vertices, in row order of
+    /// [`HexBounds::cells`], so they can be recolored per cell.  UVs are the
+    /// position on the grid plane in cells.
+    pub fn fills(bounds: HexBounds, config: &HexGridConfig) -> Mesh {
+        let mut positions = Vec::new();
+        let mut uvs = Vec::new();
+        let mut indices = Vec::new();
+        for cell in bounds.cells() {
+            let first = positions.len() as u32;
+            let corners = config.cell_corners(cell);
+            for corner in &corners {
+                positions.push([corner.x, 0.0, corner.y]);
+                uvs.push((*corner / config.size).to_array());
+            }
+            // cells are convex; fan out from the first corner, wound to face
+            // +Y
+            for i in 1..corners.len() as u32 - 1 {
+                indices.extend([first, first + i + 1, first + i]);
+            }
+        }
+
+        let normals = vec![[0.0, 1.0, 0.0]; positions.len()];
+        Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+        .with_inserted_indices(Indices::U32(indices))
+    }
+}
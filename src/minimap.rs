@@ -0,0 +1,208 @@
+//! minimap images
+//!
+//! Add a [`HexGridMinimap`] to a grid entity to rasterize its terrain, fog,
+//! & unit markers into an [`Image`], ready to show in a UI node.  The image
+//! is drawn again whenever the minimap or the grid's [`HexGridConfig`]
+//! changes:
+//!
+//! ```ignore
+//! let image = images.reserve_handle();
+//! commands.entity(grid).insert(HexGridMinimap::new(image.clone()).with_terrain(terrain));
+//! commands.spawn(ImageNode::new(image));
+//! ```
+//!
+//! Rows of the image run along the grid's local X, from its most negative Z
+//! at the top, as seen from above with -Z up the screen.
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+
+use crate::{Axial, HexBounds, HexGridConfig, HexMap};
+
+/// Minimap of a grid, drawn into [`HexGridMinimap::image`].
+#[derive(Component, Debug, Clone)]
+pub struct HexGridMinimap {
+    /// color of each cell
+    pub terrain: HexMap<Color>,
+    /// opacity of [`HexGridMinimap::fog_color`] over each cell, 0 to 1;
+    /// cells without fog are clear
+    pub fog: HexMap<f32>,
+    pub fog_color: Color,
+    /// dots drawn over the cells, such as units
+    pub markers: Vec<(Axial, Color)>,
+    /// radius of the marker dots, as a fraction of the cell size
+    pub marker_size: f32,
+    /// color outside any terrain
+    pub background: Color,
+    /// image size in pixels
+    pub resolution: UVec2,
+    /// cells fit into the image, keeping their aspect ratio; defaults to the
+    /// bounds of the terrain
+    pub bounds: Option<HexBounds>,
+    /// image the minimap is drawn into; replaced with a new image if it's
+    /// the default handle
+    pub image: Handle<Image>,
+}
+
+impl Default for HexGridMinimap {
+    fn default() -> Self {
+        Self {
+            terrain: HexMap::new(),
+            fog: HexMap::new(),
+            fog_color: Color::BLACK,
+            markers: Vec::new(),
+            marker_size: 0.35,
+            background: Color::NONE,
+            resolution: UVec2::splat(256),
+            bounds: None,
+            image: Handle::default(),
+        }
+    }
+}
+
+impl HexGridMinimap {
+    pub fn new(image: Handle<Image>) -> Self {
+        Self { image, ..default() }
+    }
+
+    pub fn with_terrain(mut self, terrain: HexMap<Color>) -> Self {
+        self.terrain = terrain;
+        self
+    }
+
+    pub fn with_resolution(mut self, resolution: UVec2) -> Self {
+        self.resolution = resolution;
+        self
+    }
+
+    pub fn with_bounds(mut self, bounds: HexBounds) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    pub fn with_background(mut self, background: Color) -> Self {
+        self.background = background;
+        self
+    }
+
+    pub fn with_fog_color(mut self, fog_color: Color) -> Self {
+        self.fog_color = fog_color;
+        self
+    }
+
+    /// Draw the minimap for a grid laid out by `config`.  Empty if there's
+    /// no terrain & no bounds.
+    pub fn rasterize(&self, config: &HexGridConfig) -> Image {
+        let size = self.resolution.max(UVec2::ONE);
+        let mut pixels = vec![self.background.to_linear(); (size.x * size.y) as usize];
+
+        if let Some(bounds) = self.bounds.or_else(|| self.terrain.bounds()) {
+            let frame = Frame::new(config, bounds, size);
+            for (i, pixel) in pixels.iter_mut().enumerate() {
+                let x = i as u32 % size.x;
+                let y = i as u32 / size.x;
+                let cell = config.world_to_cell(frame.to_world(UVec2::new(x, y)));
+                if let Some(color) = self.terrain.get(cell) {
+                    *pixel = color.to_linear();
+                }
+                if let Some(&fog) = self.fog.get(cell) {
+                    *pixel = pixel.mix(&self.fog_color.to_linear(), fog.clamp(0.0, 1.0));
+                }
+            }
+
+            let radius = self.marker_size * config.size * frame.scale;
+            for &(cell, color) in &self.markers {
+                let center = frame.to_pixel(config.cell_to_world(cell));
+                let min = (center - radius).floor().max(Vec2::ZERO).as_uvec2();
+                let max = (center + radius).ceil().min(size.as_vec2()).as_uvec2();
+                for y in min.y..max.y {
+                    for x in min.x..max.x {
+                        let pixel = Vec2::new(x as f32, y as f32) + 0.5;
+                        if pixel.distance_squared(center) <= radius * radius {
+                            pixels[(y * size.x + x) as usize] = color.to_linear();
+                        }
+                    }
+                }
+            }
+        }
+
+        let data = pixels
+            .iter()
+            .flat_map(|p| Srgba::from(*p).to_u8_array())
+            .collect();
+        Image::new(
+            Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        )
+    }
+}
+
+/// mapping between image pixels & the grid plane
+struct Frame {
+    /// grid plane position of the image's top left corner
+    origin: Vec2,
+    /// pixels per world unit
+    scale: f32,
+}
+
+impl Frame {
+    fn new(config: &HexGridConfig, bounds: HexBounds, size: UVec2) -> Self {
+        // the bounds are a parallelogram on the grid plane, so the corner
+        // cells hold its extremes
+        let (min, max) = [
+            bounds.min,
+            Axial::new(bounds.max.q, bounds.min.r),
+            Axial::new(bounds.min.q, bounds.max.r),
+            bounds.max,
+        ]
+        .into_iter()
+        .flat_map(|cell| config.cell_corners(cell))
+        .fold((Vec2::MAX, Vec2::MIN), |(min, max), p| {
+            (min.min(p), max.max(p))
+        });
+
+        let extent = (max - min).max(Vec2::splat(f32::EPSILON));
+        let scale = (size.as_vec2() / extent).min_element();
+        // centered along the axis with room to spare
+        let origin = (min + max) * 0.5 - size.as_vec2() * 0.5 / scale;
+        Self { origin, scale }
+    }
+
+    /// grid plane position at the center of a pixel
+    fn to_world(&self, pixel: UVec2) -> Vec2 {
+        self.origin + (pixel.as_vec2() + 0.5) / self.scale
+    }
+
+    fn to_pixel(&self, pos: Vec2) -> Vec2 {
+        (pos - self.origin) * self.scale
+    }
+}
+
+/// draw minimaps that changed, or whose grid did
+#[allow(clippy::type_complexity)]
+pub(crate) fn update_minimaps(
+    mut minimaps: Query<
+        (&mut HexGridMinimap, &HexGridConfig),
+        Or<(Changed<HexGridMinimap>, Changed<HexGridConfig>)>,
+    >,
+    mut images: ResMut<Assets<Image>>,
+) {
+    for (mut minimap, config) in &mut minimaps {
+        let image = minimap.rasterize(config);
+        if minimap.image == Handle::default() {
+            // not a change anyone needs to see; it's already drawn
+            minimap.bypass_change_detection().image = images.add(image);
+        } else if images.insert(&minimap.image, image).is_err() {
+            warn!("minimap image {:?} was removed", minimap.image);
+        }
+    }
+}
@@ -0,0 +1,267 @@
+//! rivers & roads
+//!
+//! Rivers run along the edges between cells, and roads from the center of
+//! one cell to the center of a neighbor.  Both are stored as a
+//! [`HexEdgeSet`]; a road is stored as the edge it crosses.  Add
+//! [`HexGridNetworks`] to a hex grid entity to have the grid shader draw
+//! them as strokes with an optional border, joined smoothly where they meet.
+//!
+//! [`river`] traces a river downhill from a source, and [`road_network`]
+//! connects a set of towns with the cheapest roads.
+use std::cmp::Ordering;
+
+use bevy::prelude::*;
+
+use crate::{path::find_path, Axial, HexDirection, HexEdge, HexMap, HexVertex};
+
+/// Set of edges between cells, stored as a bitmask of the three canonical
+/// edges on each cell.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HexEdgeSet(HexMap<u8>);
+
+impl HexEdgeSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// add an edge, returning false if it was already in the set
+    pub fn insert(&mut self, edge: HexEdge) -> bool {
+        let bit = 1 << edge.dir().index();
+        match self.0.get_mut(edge.cell()) {
+            Some(mask) if *mask & bit != 0 => false,
+            Some(mask) => {
+                *mask |= bit;
+                true
+            }
+            None => {
+                self.0.insert(edge.cell(), bit);
+                true
+            }
+        }
+    }
+
+    /// remove an edge, returning false if it wasn't in the set
+    pub fn remove(&mut self, edge: HexEdge) -> bool {
+        let bit = 1 << edge.dir().index();
+        let Some(mask) = self.0.get_mut(edge.cell()) else {
+            return false;
+        };
+        let found = *mask & bit != 0;
+        *mask &= !bit;
+        if *mask == 0 {
+            self.0.remove(edge.cell());
+        }
+        found
+    }
+
+    pub fn contains(&self, edge: HexEdge) -> bool {
+        self.0
+            .get(edge.cell())
+            .is_some_and(|mask| mask & (1 << edge.dir().index()) != 0)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = HexEdge> + '_ {
+        self.0.iter().flat_map(|(cell, &mask)| {
+            (0..3)
+                .filter(move |dir| mask & (1 << dir) != 0)
+                .map(move |dir| HexEdge::new(cell, HexDirection::new(dir)))
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.0
+            .iter()
+            .map(|(_, mask)| mask.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// edges of a cell in the set, by direction from the cell
+    pub fn around(&self, cell: Axial) -> impl Iterator<Item = HexEdge> + '_ {
+        HexDirection::ALL
+            .into_iter()
+            .map(move |dir| HexEdge::new(cell, dir))
+            .filter(|&edge| self.contains(edge))
+    }
+
+    /// edges in the set meeting at a corner
+    pub fn at_vertex(&self, vertex: HexVertex) -> impl Iterator<Item = HexEdge> + '_ {
+        vertex
+            .edges()
+            .into_iter()
+            .filter(|&edge| self.contains(edge))
+    }
+
+    /// bitmask of the canonical edges stored on each cell
+    pub(crate) fn masks(&self) -> impl Iterator<Item = (Axial, u8)> + '_ {
+        self.0.iter().map(|(cell, &mask)| (cell, mask))
+    }
+}
+
+impl FromIterator<HexEdge> for HexEdgeSet {
+    fn from_iter<I: IntoIterator<Item = HexEdge>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<HexEdge> for HexEdgeSet {
+    fn extend<I: IntoIterator<Item = HexEdge>>(&mut self, iter: I) {
+        for edge in iter {
+            self.insert(edge);
+        }
+    }
+}
+
+/// how a river or road is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StrokeStyle {
+    pub color: Color,
+    /// width, as a fraction of the cell size
+    pub width: f32,
+    pub border_color: Color,
+    /// width of the border on each side, as a fraction of the cell size;
+    /// zero for no border
+    pub border_width: f32,
+}
+
+/// Rivers & roads on a grid entity, drawn by the grid shader over cell
+/// highlights & decals, and under the grid lines.  Roads are drawn over
+/// rivers.  Only drawn on [`crate::GridKind::Hex`] grids.
+#[derive(Component, Debug, Clone)]
+pub struct HexGridNetworks {
+    /// edges rivers run along
+    pub rivers: HexEdgeSet,
+    /// edges roads cross, between the centers of the cells sharing them
+    pub roads: HexEdgeSet,
+    pub river_style: StrokeStyle,
+    pub road_style: StrokeStyle,
+}
+
+impl Default for HexGridNetworks {
+    fn default() -> Self {
+        Self {
+            rivers: HexEdgeSet::new(),
+            roads: HexEdgeSet::new(),
+            river_style: StrokeStyle {
+                color: Color::srgb(0.2, 0.45, 0.8),
+                width: 0.12,
+                border_color: Color::srgb(0.1, 0.25, 0.5),
+                border_width: 0.02,
+            },
+            road_style: StrokeStyle {
+                color: Color::srgb(0.6, 0.5, 0.35),
+                width: 0.1,
+                border_color: Color::srgb(0.3, 0.25, 0.15),
+                border_width: 0.015,
+            },
+        }
+    }
+}
+
+impl HexGridNetworks {
+    pub fn is_empty(&self) -> bool {
+        self.rivers.is_empty() && self.roads.is_empty()
+    }
+}
+
+/// Trace a river from `source` along the edges, always flowing to the lowest
+/// neighboring corner, until no neighbor is lower or `max_len` edges have
+/// been laid.  `height` is the height of a corner, or `None` for the sea or
+/// the edge of the map; a river flowing into one ends there.
+pub fn river(
+    source: HexVertex,
+    height: impl Fn(HexVertex) -> Option<f32>,
+    max_len: usize,
+) -> Vec<HexEdge> {
+    let mut edges = Vec::new();
+    let Some(mut level) = height(source) else {
+        return edges;
+    };
+    let mut vertex = source;
+    while edges.len() < max_len {
+        // the sea is lower than any land
+        let lowest = vertex
+            .neighbors()
+            .map(|v| (v, height(v)))
+            .min_by(|a, b| match (a.1, b.1) {
+                (None, None) => Ordering::Equal,
+                (None, _) => Ordering::Less,
+                (_, None) => Ordering::Greater,
+                (Some(a), Some(b)) => a.total_cmp(&b),
+            });
+        let Some((next, next_height)) = lowest else {
+            break;
+        };
+        if next_height.is_some_and(|h| h >= level) {
+            break;
+        }
+        let edge = vertex
+            .edges()
+            .into_iter()
+            .find(|e| e.vertices().contains(&next))
+            .expect("neighboring corners share an edge");
+        edges.push(edge);
+        match next_height {
+            Some(h) => (vertex, level) = (next, h),
+            None => break,
+        }
+    }
+    edges
+}
+
+/// Roads connecting every one of `towns`, as the edges between the cells
+/// they pass through.  Towns are joined one at a time, nearest first, to the
+/// closest town already connected, along a cheap path; roads already laid
+/// cost nothing to reuse.  `cost(cell)` is the cost of building through
+/// a cell, or `None` if it's impassable; towns that can't be reached are left
+/// unconnected.
+pub fn road_network(towns: &[Axial], cost: impl Fn(Axial) -> Option<u32>) -> HexEdgeSet {
+    let mut roads = HexEdgeSet::new();
+    let Some((&first, rest)) = towns.split_first() else {
+        return roads;
+    };
+    let mut connected = vec![first];
+    let mut pending = rest.to_vec();
+
+    while !pending.is_empty() {
+        // next town to join is the one nearest the network
+        let (index, to) = pending
+            .iter()
+            .enumerate()
+            .map(|(i, &town)| {
+                let nearest = connected
+                    .iter()
+                    .copied()
+                    .min_by_key(|c| c.distance(town))
+                    .expect("at least one town is connected");
+                (i, town, nearest)
+            })
+            .min_by_key(|&(_, town, nearest)| town.distance(nearest))
+            .map(|(i, _, nearest)| (i, nearest))
+            .expect("pending is not empty");
+        let town = pending.swap_remove(index);
+
+        let path = find_path(town, to, |from, next| {
+            if roads.contains(road_edge(from, next)) {
+                return Some(0);
+            }
+            cost(next)
+        });
+        let Some((path, _)) = path else { continue };
+        roads.extend(path.windows(2).map(|pair| road_edge(pair[0], pair[1])));
+        connected.push(town);
+    }
+    roads
+}
+
+// edge crossed stepping between neighboring cells
+fn road_edge(from: Axial, to: Axial) -> HexEdge {
+    let dir = HexDirection::between(from, to).expect("roads step between neighbors");
+    HexEdge::new(from, dir)
+}
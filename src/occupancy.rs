@@ -0,0 +1,163 @@
+//! which entities stand on each cell
+//!
+//! Give units, or anything else that takes up space on a grid, a
+//! [`HexPosition`].  Any number of entities may share a cell.  The
+//! [`HexOccupancy`] resource tracks the entities on every cell of every grid,
+//! kept in sync by [`HexPosition`]'s hooks, so pathfinding can skip occupied
+//! cells:
+//!
+//! ```ignore
+//! fn plan(occupancy: Res<HexOccupancy>, unit: Single<(Entity, &HexPosition)>, goal: Res<Goal>) {
+//!     let (entity, pos) = *unit;
+//!     let occupancy = occupancy.grid(pos.grid);
+//!     let path = find_path(pos.cell, goal.0, |_, to| {
+//!         occupancy.is_none_or(|o| o.is_free_for(to, entity)).then_some(1)
+//!     });
+//! }
+//! ```
+//!
+//! Cells can be reserved with [`Occupancy::try_reserve`], so two units
+//! moving in the same turn don't pick the same destination.  A reservation
+//! lasts until it's released, or the entity holding it moves onto the cell.
+use bevy::{
+    ecs::{lifecycle::HookContext, world::DeferredWorld},
+    platform::collections::HashMap,
+    prelude::*,
+};
+
+use crate::{Axial, HexMap, HexPosition};
+
+/// Entities on, & reservations of, the cells of one grid.
+#[derive(Debug, Default, Clone)]
+pub struct Occupancy {
+    occupants: HexMap<Vec<Entity>>,
+    reserved: HexMap<Entity>,
+}
+
+impl Occupancy {
+    /// entities on a cell
+    pub fn occupants(&self, cell: Axial) -> &[Entity] {
+        self.occupants.get(cell).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn is_occupied(&self, cell: Axial) -> bool {
+        self.occupants.contains(cell)
+    }
+
+    /// entity holding the reservation on a cell
+    pub fn reserved_by(&self, cell: Axial) -> Option<Entity> {
+        self.reserved.get(cell).copied()
+    }
+
+    /// true if nothing but `entity` is on the cell or has reserved it
+    pub fn is_free_for(&self, cell: Axial, entity: Entity) -> bool {
+        self.occupants(cell).iter().all(|&e| e == entity)
+            && self.reserved_by(cell).is_none_or(|e| e == entity)
+    }
+
+    /// Reserve a cell for `entity`.  Fails if anything else is on the cell or
+    /// has reserved it; reserving a cell again for the same entity succeeds.
+    pub fn try_reserve(&mut self, cell: Axial, entity: Entity) -> bool {
+        if !self.is_free_for(cell, entity) {
+            return false;
+        }
+        self.reserved.insert(cell, entity);
+        true
+    }
+
+    /// release the reservation on a cell, returning the entity that held it
+    pub fn release(&mut self, cell: Axial) -> Option<Entity> {
+        self.reserved.remove(cell)
+    }
+
+    /// cells with at least one entity on them
+    pub fn occupied(&self) -> impl Iterator<Item = (Axial, &[Entity])> {
+        self.occupants.iter().map(|(cell, e)| (cell, e.as_slice()))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.occupants.is_empty() && self.reserved.is_empty()
+    }
+}
+
+/// [`Occupancy`] of every grid with an entity on it.  Maintained by
+/// [`HexPosition`]'s hooks.
+#[derive(Resource, Debug, Default)]
+pub struct HexOccupancy {
+    grids: HashMap<Entity, Occupancy>,
+}
+
+impl HexOccupancy {
+    pub fn grid(&self, grid: Entity) -> Option<&Occupancy> {
+        self.grids.get(&grid)
+    }
+
+    /// entities on a cell
+    pub fn occupants(&self, grid: Entity, cell: Axial) -> &[Entity] {
+        self.grid(grid).map_or(&[], |o| o.occupants(cell))
+    }
+
+    /// reserve a cell for `entity`; see [`Occupancy::try_reserve`]
+    pub fn try_reserve(&mut self, grid: Entity, cell: Axial, entity: Entity) -> bool {
+        self.grids
+            .entry(grid)
+            .or_default()
+            .try_reserve(cell, entity)
+    }
+
+    /// release the reservation on a cell, returning the entity that held it
+    pub fn release(&mut self, grid: Entity, cell: Axial) -> Option<Entity> {
+        let occupancy = self.grids.get_mut(&grid)?;
+        let entity = occupancy.release(cell);
+        if occupancy.is_empty() {
+            self.grids.remove(&grid);
+        }
+        entity
+    }
+
+    fn insert(&mut self, entity: Entity, pos: HexPosition) {
+        let occupancy = self.grids.entry(pos.grid).or_default();
+        if occupancy.reserved_by(pos.cell) == Some(entity) {
+            occupancy.release(pos.cell);
+        }
+        match occupancy.occupants.get_mut(pos.cell) {
+            Some(occupants) => occupants.push(entity),
+            None => {
+                occupancy.occupants.insert(pos.cell, vec![entity]);
+            }
+        }
+    }
+
+    fn remove(&mut self, entity: Entity, pos: HexPosition) {
+        let Some(occupancy) = self.grids.get_mut(&pos.grid) else {
+            return;
+        };
+        if let Some(occupants) = occupancy.occupants.get_mut(pos.cell) {
+            occupants.retain(|&e| e != entity);
+            if occupants.is_empty() {
+                occupancy.occupants.remove(pos.cell);
+            }
+        }
+        if occupancy.is_empty() {
+            self.grids.remove(&pos.grid);
+        }
+    }
+}
+
+pub(crate) fn occupy_cell(mut world: DeferredWorld, context: HookContext) {
+    let Some(&pos) = world.get::<HexPosition>(context.entity) else {
+        return;
+    };
+    if let Some(mut occupancy) = world.get_resource_mut::<HexOccupancy>() {
+        occupancy.insert(context.entity, pos);
+    }
+}
+
+pub(crate) fn vacate_cell(mut world: DeferredWorld, context: HookContext) {
+    let Some(&pos) = world.get::<HexPosition>(context.entity) else {
+        return;
+    };
+    if let Some(mut occupancy) = world.get_resource_mut::<HexOccupancy>() {
+        occupancy.remove(context.entity, pos);
+    }
+}
@@ -0,0 +1,201 @@
+//! render world side of [`crate::territory`]
+//!
+//! The owner of each cell is stored in an `R8Uint` texture covering the
+//! bounds of the owned cells plus some padding, as the team index + 1; 0 is
+//! unowned.  When only a few cells change, just those texels are written.
+//! The texture is rebuilt when the owners are replaced, or a cell outside it
+//! is claimed.
+use bevy::{
+    platform::collections::HashMap,
+    prelude::*,
+    render::{
+        render_resource::{
+            Extent3d, Origin3d, TexelCopyBufferLayout, TexelCopyTextureInfo, Texture,
+            TextureAspect, TextureDataOrder, TextureDescriptor, TextureDimension, TextureFormat,
+            TextureUsages, TextureView, TextureViewDescriptor,
+        },
+        renderer::{RenderDevice, RenderQueue},
+        sync_world::RenderEntity,
+        Extract,
+    },
+};
+
+use crate::{
+    render::ExtractedHexGrid,
+    territory::{CellOwner, HexGridOwners},
+    Axial, HexBounds, HexGridConfig, HexMap,
+};
+
+const TEXTURE_FORMAT: TextureFormat = TextureFormat::R8Uint;
+
+/// cells of padding around the owned cells when the texture is built, so
+/// territory can grow a little without rebuilding it
+const TEXTURE_PADDING: i32 = 16;
+
+/// owner changes for a grid's texture, on its render world entity.  Removed
+/// while the grid is hidden or has no owners.
+#[derive(Component)]
+pub(crate) enum ExtractedOwners {
+    /// build the texture over `bounds`
+    Full(HexBounds, HexMap<CellOwner>),
+    /// cells changed since last frame; all within the existing texture
+    Changes(Vec<(Axial, Option<CellOwner>)>),
+}
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn extract_hex_grid_owners(
+    mut commands: Commands,
+    textures: Res<HexGridOwnerTextures>,
+    grids: Extract<
+        Query<
+            (
+                Entity,
+                RenderEntity,
+                &InheritedVisibility,
+                Option<Ref<HexGridOwners>>,
+            ),
+            With<HexGridConfig>,
+        >,
+    >,
+    render_device: Res<RenderDevice>,
+) {
+    let max_texture_size = render_device.limits().max_texture_dimension_2d as i32;
+
+    for (entity, render_entity, visibility, owners) in &grids {
+        let Some(owners) = owners.filter(|_| visibility.get()) else {
+            commands.entity(render_entity).remove::<ExtractedOwners>();
+            continue;
+        };
+
+        let texture = textures.0.get(&render_entity);
+        let extracted = match texture {
+            Some(texture)
+                if !owners.is_reset()
+                    && owners.changed().iter().all(|c| texture.bounds.contains(*c)) =>
+            {
+                ExtractedOwners::Changes(
+                    owners
+                        .changed()
+                        .iter()
+                        .map(|&cell| (cell, owners.get(cell)))
+                        .collect(),
+                )
+            }
+            _ => {
+                let mut bounds = owners
+                    .as_map()
+                    .bounds()
+                    .unwrap_or(HexBounds::from_cell(Axial::ZERO));
+                bounds.min -= Axial::new(TEXTURE_PADDING, TEXTURE_PADDING);
+                bounds.max += Axial::new(TEXTURE_PADDING, TEXTURE_PADDING);
+                if bounds.size().max_element() > max_texture_size {
+                    if owners.is_changed() {
+                        warn!(
+                            "owned cells on grid {:?} are too spread out to draw; \
+                             max texture size is {}",
+                            entity, max_texture_size
+                        );
+                    }
+                    commands.entity(render_entity).remove::<ExtractedOwners>();
+                    continue;
+                }
+                ExtractedOwners::Full(bounds, owners.as_map().clone())
+            }
+        };
+        commands.entity(render_entity).insert(extracted);
+    }
+}
+
+// texel value for a cell
+fn texel(owner: Option<CellOwner>) -> u8 {
+    owner.map_or(0, |o| o.0.saturating_add(1))
+}
+
+pub(crate) struct OwnerTexture {
+    pub bounds: HexBounds,
+    texture: Texture,
+    pub view: TextureView,
+}
+
+/// owner textures, keyed by render world grid entity.  Dropped the first
+/// frame the grid isn't extracted, or has no owners.
+#[derive(Resource, Default)]
+pub(crate) struct HexGridOwnerTextures(pub HashMap<Entity, OwnerTexture>);
+
+pub(crate) fn prepare_hex_grid_owners(
+    mut textures: ResMut<HexGridOwnerTextures>,
+    grids: Query<(Entity, &ExtractedOwners), With<ExtractedHexGrid>>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    textures.0.retain(|entity, _| grids.contains(*entity));
+
+    for (entity, owners) in &grids {
+        match owners {
+            ExtractedOwners::Full(bounds, owners) => {
+                let size = bounds.size().as_uvec2();
+                let mut data = vec![0; (size.x * size.y) as usize];
+                for (cell, owner) in owners.iter() {
+                    let offset = IVec2::from(cell - bounds.min).as_uvec2();
+                    data[(offset.y * size.x + offset.x) as usize] = texel(Some(*owner));
+                }
+
+                let texture = render_device.create_texture_with_data(
+                    &render_queue,
+                    &TextureDescriptor {
+                        label: Some("hex_grid_owners"),
+                        size: Extent3d {
+                            width: size.x,
+                            height: size.y,
+                            depth_or_array_layers: 1,
+                        },
+                        mip_level_count: 1,
+                        sample_count: 1,
+                        dimension: TextureDimension::D2,
+                        format: TEXTURE_FORMAT,
+                        usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+                        view_formats: &[],
+                    },
+                    TextureDataOrder::LayerMajor,
+                    &data,
+                );
+                let view = texture.create_view(&TextureViewDescriptor::default());
+                textures.0.insert(
+                    entity,
+                    OwnerTexture {
+                        bounds: *bounds,
+                        texture,
+                        view,
+                    },
+                );
+            }
+            ExtractedOwners::Changes(changes) => {
+                let Some(texture) = textures.0.get(&entity) else {
+                    continue;
+                };
+                for &(cell, owner) in changes {
+                    let offset = IVec2::from(cell - texture.bounds.min).as_uvec2();
+                    render_queue.write_texture(
+                        TexelCopyTextureInfo {
+                            texture: &texture.texture,
+                            mip_level: 0,
+                            origin: Origin3d {
+                                x: offset.x,
+                                y: offset.y,
+                                z: 0,
+                            },
+                            aspect: TextureAspect::All,
+                        },
+                        &[texel(owner)],
+                        TexelCopyBufferLayout {
+                            offset: 0,
+                            bytes_per_row: None,
+                            rows_per_image: None,
+                        },
+                        Extent3d::default(),
+                    );
+                }
+            }
+        }
+    }
+}
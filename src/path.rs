@@ -0,0 +1,851 @@
+//! pathfinding over hex cells
+//!
+//! [`find_path`] is A* between two cells, with the cost of each step given by
+//! a closure.  [`find_path_facing`] also tracks the direction the mover is
+//! facing, so the cost of a step can depend on how far it has to turn; use
+//! it for vehicles & units that pay extra to change direction.
+//!
+//! For maps too large for A* over every cell, [`find_path_hierarchical`]
+//! searches a [`PortalGraph`] of the entrances between the chunks of a
+//! [`HexMap`] instead, then fills in the path within each chunk.  Its paths
+//! are usually close to the cheapest, but not always.
+//!
+//! To move many units toward the same goals, build one [`FlowField`]
+//! instead; it gives the direction of the cheapest step from every cell.
+//! [`FlowField::many`] builds one for each of several sets of goals, in
+//! parallel with the `parallel` feature.  Units already following a path can keep
+//! it up to date as cells open & close with an [`IncrementalPath`].
+//!
+//! On boards with height, wrap the cost closure with
+//! [`crate::HexGridElevation::step_cost`] so paths don't climb cliffs, only
+//! small steps & ramps.
+//!
+//! Grids are unbounded, so the search only ends early when the goal can't be
+//! reached if the cost closure returns `None` outside the playable area.
+//! Every step must cost at least 1, or the path found may not be the
+//! cheapest.
+use std::{cmp::Reverse, collections::BinaryHeap, hash::Hash, mem};
+
+use bevy::{
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
+
+use crate::{
+    map::{chunk_index, CHUNK_SIZE},
+    Axial, HexDirection, HexMap,
+};
+
+/// cell & facing along a path from [`find_path_facing`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PathNode {
+    pub coord: Axial,
+    /// direction the mover faces
+    pub facing: HexDirection,
+}
+
+impl PathNode {
+    pub fn new(coord: Axial, facing: HexDirection) -> Self {
+        Self { coord, facing }
+    }
+
+    /// number of 60 degree turns to face `dir`, 0 to 3
+    pub fn turns_to(self, dir: HexDirection) -> u32 {
+        let diff = (dir.index() + 6 - self.facing.index()) % 6;
+        diff.min(6 - diff) as u32
+    }
+}
+
+/// Cheapest path from `start` to `goal`, including both, and its total cost.
+/// `cost(from, to)` is the cost of stepping between two neighboring cells,
+/// or `None` if the step is blocked.
+pub fn find_path(
+    start: Axial,
+    goal: Axial,
+    mut cost: impl FnMut(Axial, Axial) -> Option<u32>,
+) -> Option<(Vec<Axial>, u32)> {
+    astar(
+        start,
+        |cell| cell == goal,
+        |cell| cell.distance(goal),
+        |cell, successors| {
+            successors.extend(
+                cell.neighbors()
+                    .filter_map(|next| Some((next, cost(cell, next)?))),
+            )
+        },
+    )
+}
+
+/// Cheapest path from `start` to any facing on `goal`, including both, and
+/// its total cost.  Each step moves to a neighboring cell & turns to face the
+/// direction moved in; `cost(from, to)` is the cost of that step, or `None`
+/// if it's blocked.  Use [`PathNode::turns_to`] to charge for turning.
+pub fn find_path_facing(
+    start: PathNode,
+    goal: Axial,
+    mut cost: impl FnMut(PathNode, PathNode) -> Option<u32>,
+) -> Option<(Vec<PathNode>, u32)> {
+    astar(
+        start,
+        |node| node.coord == goal,
+        |node| node.coord.distance(goal),
+        |node, successors| {
+            successors.extend(HexDirection::ALL.into_iter().filter_map(|dir| {
+                let next = PathNode::new(node.coord + dir, dir);
+                Some((next, cost(node, next)?))
+            }))
+        },
+    )
+}
+
+/// A* over any node type; `heuristic` must never overestimate the remaining
+/// cost
+pub(crate) fn astar<N: Copy + Eq + Hash>(
+    start: N,
+    is_goal: impl Fn(N) -> bool,
+    heuristic: impl Fn(N) -> u32,
+    mut successors: impl FnMut(N, &mut Vec<(N, u32)>),
+) -> Option<(Vec<N>, u32)> {
+    // every node pushed, with the index of the node it was reached from
+    let mut nodes = vec![(start, usize::MAX)];
+    let mut best = HashMap::new();
+    best.insert(start, 0);
+    // (estimated total, cost so far, node index)
+    let mut open = BinaryHeap::new();
+    open.push(Reverse((heuristic(start), 0, 0)));
+    let mut next = Vec::new();
+
+    while let Some(Reverse((_, cost, index))) = open.pop() {
+        let node = nodes[index].0;
+        if best.get(&node).is_some_and(|&b| b < cost) {
+            continue;
+        }
+        if is_goal(node) {
+            let mut path = Vec::new();
+            let mut i = index;
+            while i != usize::MAX {
+                path.push(nodes[i].0);
+                i = nodes[i].1;
+            }
+            path.reverse();
+            return Some((path, cost));
+        }
+
+        next.clear();
+        successors(node, &mut next);
+        for &(succ, step) in &next {
+            let succ_cost = cost.saturating_add(step);
+            if best.get(&succ).is_some_and(|&b| b <= succ_cost) {
+                continue;
+            }
+            best.insert(succ, succ_cost);
+            nodes.push((succ, index));
+            open.push(Reverse((
+                succ_cost.saturating_add(heuristic(succ)),
+                succ_cost,
+                nodes.len() - 1,
+            )));
+        }
+    }
+    None
+}
+
+/// entrances at least this many cells long get a portal at each end instead
+/// of one in the middle
+const LONG_ENTRANCE: usize = 6;
+
+/// Portals between the chunks of a [`HexMap`] for [`find_path_hierarchical`].
+///
+/// Where two neighboring chunks have passable cells touching across their
+/// border, each run of touching cells is an entrance, with a portal cell on
+/// either side of it.  The cost between every pair of portals in a chunk is
+/// cached, so a search only has to cross the portals.  Call
+/// [`PortalGraph::mark_changed`] when the cost of a cell changes; only the
+/// chunks around it are rebuilt.
+#[derive(Debug, Clone, Default)]
+pub struct PortalGraph {
+    /// portal pairs on the border between two chunks, keyed by the chunks in
+    /// order
+    borders: HashMap<(Axial, Axial), Vec<(Axial, Axial)>>,
+    /// portal cells in each chunk
+    portals: HashMap<Axial, Vec<Axial>>,
+    /// edges from each portal to the others in its chunk, and across its
+    /// border
+    edges: HashMap<Axial, Vec<(Axial, u32)>>,
+    /// chunks to rebuild
+    dirty: HashSet<Axial>,
+}
+
+impl PortalGraph {
+    /// graph for every chunk of `map`; it's built by the first
+    /// [`PortalGraph::update`] or search
+    pub fn new<T>(map: &HexMap<T>) -> Self {
+        Self {
+            dirty: map.chunks().collect(),
+            ..default()
+        }
+    }
+
+    /// rebuild the chunk around a cell whose cost changed, or that was
+    /// added to or removed from the map
+    pub fn mark_changed(&mut self, cell: Axial) {
+        self.dirty.insert(chunk_index(cell).0);
+    }
+
+    /// number of portal cells in the graph
+    pub fn portal_count(&self) -> usize {
+        self.portals.values().map(Vec::len).sum()
+    }
+
+    /// rebuild the chunks marked as changed.  `cost` is the cost of entering
+    /// a cell, or `None` if it's blocked; cells not in `map` are blocked.
+    pub fn update<T>(&mut self, map: &HexMap<T>, cost: impl Fn(&T) -> Option<u32>) {
+        if self.dirty.is_empty() {
+            return;
+        }
+        let enter = |cell: Axial| map.get(cell).and_then(&cost);
+        let dirty = mem::take(&mut self.dirty);
+
+        let mut rebuild = HashSet::new();
+        let mut borders = HashSet::new();
+        for &chunk in &dirty {
+            rebuild.insert(chunk);
+            for other in chunk.neighbors() {
+                rebuild.insert(other);
+                borders.insert(border_key(chunk, other));
+            }
+        }
+        for key in borders {
+            self.borders.remove(&key);
+            let portals = find_portals(key, enter);
+            if !portals.is_empty() {
+                self.borders.insert(key, portals);
+            }
+        }
+
+        for chunk in rebuild {
+            for portal in self.portals.remove(&chunk).unwrap_or_default() {
+                self.edges.remove(&portal);
+            }
+
+            // (portal in this chunk, cell across the border)
+            let links: Vec<(Axial, Axial)> = chunk
+                .neighbors()
+                .filter_map(|other| self.borders.get(&border_key(chunk, other)))
+                .flatten()
+                .map(|&(a, b)| {
+                    if chunk_index(a).0 == chunk {
+                        (a, b)
+                    } else {
+                        (b, a)
+                    }
+                })
+                .collect();
+            let mut portals: Vec<Axial> = links.iter().map(|&(a, _)| a).collect();
+            portals.sort_by_key(|c| (c.r, c.q));
+            portals.dedup();
+
+            for &portal in &portals {
+                let costs = chunk_costs(chunk, portal, false, enter);
+                let mut edges: Vec<(Axial, u32)> = portals
+                    .iter()
+                    .filter(|&&other| other != portal)
+                    .filter_map(|&other| Some((other, *costs.get(&other)?)))
+                    .collect();
+                edges.extend(
+                    links
+                        .iter()
+                        .filter(|&&(a, _)| a == portal)
+                        .filter_map(|&(_, b)| Some((b, enter(b)?))),
+                );
+                self.edges.insert(portal, edges);
+            }
+            if !portals.is_empty() {
+                self.portals.insert(chunk, portals);
+            }
+        }
+    }
+}
+
+fn border_key(a: Axial, b: Axial) -> (Axial, Axial) {
+    if (a.q, a.r) < (b.q, b.r) {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/// portal pairs for the entrances across the border between two chunks
+fn find_portals(
+    (a, b): (Axial, Axial),
+    enter: impl Fn(Axial) -> Option<u32>,
+) -> Vec<(Axial, Axial)> {
+    let origin = Axial::new(a.q * CHUNK_SIZE, a.r * CHUNK_SIZE);
+    let mut pairs: Vec<(Axial, Axial)> = (0..CHUNK_SIZE)
+        .flat_map(|r| (0..CHUNK_SIZE).map(move |q| origin + Axial::new(q, r)))
+        .flat_map(|cell| cell.neighbors().map(move |n| (cell, n)))
+        .filter(|&(cell, n)| chunk_index(n).0 == b && enter(cell).is_some() && enter(n).is_some())
+        .collect();
+    pairs.sort_by_key(|(c, n)| (c.r, c.q, n.r, n.q));
+
+    // group the pairs into runs of touching cells
+    let mut portals = Vec::new();
+    let mut grouped = vec![false; pairs.len()];
+    for first in 0..pairs.len() {
+        if grouped[first] {
+            continue;
+        }
+        grouped[first] = true;
+        let mut run = vec![first];
+        let mut i = 0;
+        while i < run.len() {
+            let (c, n) = pairs[run[i]];
+            for j in 0..pairs.len() {
+                let (oc, on) = pairs[j];
+                if !grouped[j] && c.distance(oc) <= 1 && n.distance(on) <= 1 {
+                    grouped[j] = true;
+                    run.push(j);
+                }
+            }
+            i += 1;
+        }
+        run.sort_unstable();
+
+        if run.len() >= LONG_ENTRANCE {
+            portals.push(pairs[run[0]]);
+            portals.push(pairs[run[run.len() - 1]]);
+        } else {
+            portals.push(pairs[run[run.len() / 2]]);
+        }
+    }
+    portals
+}
+
+/// Cost from `origin` to every cell reachable from it within a chunk.  With
+/// `reverse`, the cost to `origin` from every cell instead.
+fn chunk_costs(
+    chunk: Axial,
+    origin: Axial,
+    reverse: bool,
+    enter: impl Fn(Axial) -> Option<u32>,
+) -> HashMap<Axial, u32> {
+    let mut costs = HashMap::new();
+    costs.insert(origin, 0);
+    let mut open = BinaryHeap::new();
+    open.push(Reverse((0u32, origin.q, origin.r)));
+
+    while let Some(Reverse((cost, q, r))) = open.pop() {
+        let cell = Axial::new(q, r);
+        if costs.get(&cell).is_some_and(|&c| c < cost) {
+            continue;
+        }
+        for next in cell.neighbors() {
+            if chunk_index(next).0 != chunk {
+                continue;
+            }
+            // stepping from `next` into `cell` when searching backwards
+            let step = if reverse {
+                enter(next).and(enter(cell))
+            } else {
+                enter(next)
+            };
+            let Some(step) = step else { continue };
+            let next_cost = cost.saturating_add(step);
+            if costs.get(&next).is_some_and(|&c| c <= next_cost) {
+                continue;
+            }
+            costs.insert(next, next_cost);
+            open.push(Reverse((next_cost, next.q, next.r)));
+        }
+    }
+    costs
+}
+
+/// path between two cells without leaving their chunk
+fn chunk_path(
+    start: Axial,
+    goal: Axial,
+    enter: impl Fn(Axial) -> Option<u32>,
+) -> Option<(Vec<Axial>, u32)> {
+    let chunk = chunk_index(start).0;
+    find_path(start, goal, |_, to| {
+        if chunk_index(to).0 == chunk {
+            enter(to)
+        } else {
+            None
+        }
+    })
+}
+
+/// Path from `start` to `goal` over the portals of `graph`, including both,
+/// and its total cost.  `cost` is the cost of entering a cell, or `None` if
+/// it's blocked; cells not in `map` are blocked.  Chunks marked as changed
+/// are rebuilt first.
+///
+/// Paths only cross chunk borders at portals, so they may cost a little more
+/// than the path from [`find_path`], but a path is found whenever one exists.
+pub fn find_path_hierarchical<T>(
+    graph: &mut PortalGraph,
+    map: &HexMap<T>,
+    start: Axial,
+    goal: Axial,
+    cost: impl Fn(&T) -> Option<u32>,
+) -> Option<(Vec<Axial>, u32)> {
+    graph.update(map, &cost);
+    let enter = |cell: Axial| map.get(cell).and_then(&cost);
+    enter(goal)?;
+
+    let (start_chunk, goal_chunk) = (chunk_index(start).0, chunk_index(goal).0);
+    if start_chunk == goal_chunk {
+        if let Some(path) = chunk_path(start, goal, enter) {
+            return Some(path);
+        }
+    }
+
+    // connect the start & goal to the portals of their chunks
+    let no_portals = Vec::new();
+    let from_start = chunk_costs(start_chunk, start, false, enter);
+    let start_edges: Vec<(Axial, u32)> = graph
+        .portals
+        .get(&start_chunk)
+        .unwrap_or(&no_portals)
+        .iter()
+        .filter(|&&p| p != start)
+        .filter_map(|&p| Some((p, *from_start.get(&p)?)))
+        .collect();
+    let to_goal = chunk_costs(goal_chunk, goal, true, enter);
+
+    let (abstract_path, total) = astar(
+        start,
+        |cell| cell == goal,
+        |cell| cell.distance(goal),
+        |cell, successors| {
+            if cell == start {
+                successors.extend_from_slice(&start_edges);
+            }
+            if let Some(edges) = graph.edges.get(&cell) {
+                successors.extend_from_slice(edges);
+            }
+            if chunk_index(cell).0 == goal_chunk {
+                if let Some(&cost) = to_goal.get(&cell) {
+                    successors.push((goal, cost));
+                }
+            }
+        },
+    )?;
+
+    // fill in the steps within each chunk; steps across a border are
+    // already single cells
+    let mut path = vec![start];
+    for pair in abstract_path.windows(2) {
+        let (from, to) = (pair[0], pair[1]);
+        if chunk_index(from).0 == chunk_index(to).0 {
+            let (steps, _) = chunk_path(from, to, enter)?;
+            path.extend_from_slice(&steps[1..]);
+        } else {
+            path.push(to);
+        }
+    }
+    Some((path, total))
+}
+
+/// Direction of the cheapest step toward the nearest goal, from every cell
+/// that can reach one.  The cost to the goal is kept for each cell, so
+/// [`FlowField::update`] only recomputes the cells affected by a change.
+#[derive(Debug, Clone, Default)]
+pub struct FlowField {
+    goals: Vec<Axial>,
+    /// cost from each cell to the nearest goal
+    costs: HexMap<u32>,
+    directions: HexMap<HexDirection>,
+}
+
+impl FlowField {
+    /// Flow field toward `goals`.  `cost` is the cost of entering a cell, or
+    /// `None` if it's blocked; it must be `None` outside the playable area,
+    /// or this won't return.
+    pub fn new(
+        goals: impl IntoIterator<Item = Axial>,
+        cost: impl Fn(Axial) -> Option<u32>,
+    ) -> Self {
+        let mut field = Self {
+            goals: goals.into_iter().collect(),
+            ..default()
+        };
+        let mut open = BinaryHeap::new();
+        for &goal in &field.goals {
+            field.costs.insert(goal, 0);
+            open.push(Reverse((0, goal)));
+        }
+        field.flood(open, cost);
+        field
+    }
+
+    /// A flow field toward each set of goals, such as one per squad
+    /// objective, in order.  With the `parallel` feature they're built on
+    /// rayon's threads.
+    pub fn many<G: IntoIterator<Item = Axial>>(
+        goal_sets: impl IntoIterator<Item = G>,
+        cost: impl Fn(Axial) -> Option<u32> + Sync,
+    ) -> Vec<Self> {
+        let goal_sets: Vec<Vec<Axial>> = goal_sets
+            .into_iter()
+            .map(|goals| goals.into_iter().collect())
+            .collect();
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+            goal_sets
+                .into_par_iter()
+                .map(|goals| Self::new(goals, &cost))
+                .collect()
+        }
+        #[cfg(not(feature = "parallel"))]
+        goal_sets
+            .into_iter()
+            .map(|goals| Self::new(goals, &cost))
+            .collect()
+    }
+
+    pub fn goals(&self) -> &[Axial] {
+        &self.goals
+    }
+
+    /// direction to step from `cell`; `None` on goals, and cells that can't
+    /// reach a goal
+    pub fn direction(&self, cell: Axial) -> Option<HexDirection> {
+        self.directions.get(cell).copied()
+    }
+
+    /// cost from `cell` to the nearest goal
+    pub fn cost(&self, cell: Axial) -> Option<u32> {
+        self.costs.get(cell).copied()
+    }
+
+    pub fn directions(&self) -> &HexMap<HexDirection> {
+        &self.directions
+    }
+
+    pub fn into_directions(self) -> HexMap<HexDirection> {
+        self.directions
+    }
+
+    /// Recompute after the cost of the `changed` cells changed.  Only cells
+    /// whose route passed through a changed cell, or that can now reach a
+    /// goal more cheaply through one, are touched.
+    pub fn update(
+        &mut self,
+        changed: impl IntoIterator<Item = Axial>,
+        cost: impl Fn(Axial) -> Option<u32>,
+    ) {
+        // every cell whose route passes through a changed cell
+        let mut affected: Vec<Axial> = changed.into_iter().collect();
+        let mut seen: HashSet<Axial> = affected.iter().copied().collect();
+        let mut i = 0;
+        while i < affected.len() {
+            let cell = affected[i];
+            for next in cell.neighbors() {
+                let upstream = self.direction(next).is_some_and(|dir| next + dir == cell);
+                if upstream && seen.insert(next) {
+                    affected.push(next);
+                }
+            }
+            i += 1;
+        }
+
+        for &cell in &affected {
+            if !self.goals.contains(&cell) {
+                self.costs.remove(cell);
+                self.directions.remove(cell);
+            }
+        }
+
+        // restart from the cheapest unaffected neighbor of each affected
+        // cell; changed cells that got cheaper spread outward from there
+        let mut open = BinaryHeap::new();
+        for &cell in &affected {
+            if self.goals.contains(&cell) {
+                open.push(Reverse((0, cell)));
+                continue;
+            }
+            if cost(cell).is_none() {
+                continue;
+            }
+            let best = cell
+                .neighbors()
+                .filter_map(|next| {
+                    let total = self.cost(next)?.saturating_add(cost(next)?);
+                    Some((total, HexDirection::between(cell, next)?))
+                })
+                .min();
+            if let Some((total, dir)) = best {
+                self.costs.insert(cell, total);
+                self.directions.insert(cell, dir);
+                open.push(Reverse((total, cell)));
+            }
+        }
+        self.flood(open, cost);
+    }
+
+    // Dijkstra outward from the cells in `open`, lowering the cost of every
+    // cell that can reach a goal more cheaply through them
+    fn flood(
+        &mut self,
+        mut open: BinaryHeap<Reverse<(u32, Axial)>>,
+        cost: impl Fn(Axial) -> Option<u32>,
+    ) {
+        while let Some(Reverse((total, cell))) = open.pop() {
+            if self.cost(cell).is_some_and(|c| c < total) {
+                continue;
+            }
+            // stepping from a neighbor into this cell
+            let Some(step) = cost(cell) else { continue };
+            let next_total = total.saturating_add(step);
+            for (dir, &offset) in Axial::NEIGHBORS.iter().enumerate() {
+                let next = cell + offset;
+                if self.cost(next).is_some_and(|c| c <= next_total) || cost(next).is_none() {
+                    continue;
+                }
+                self.costs.insert(next, next_total);
+                self.directions
+                    .insert(next, HexDirection::new(dir).opposite());
+                open.push(Reverse((next_total, next)));
+            }
+        }
+    }
+}
+
+/// Direction of the cheapest step toward the nearest of `goals` from every
+/// cell that can reach one; see [`FlowField`] to update it incrementally.
+pub fn flow_field(
+    goals: impl IntoIterator<Item = Axial>,
+    cost: impl Fn(Axial) -> Option<u32>,
+) -> HexMap<HexDirection> {
+    FlowField::new(goals, cost).into_directions()
+}
+
+/// Path from a unit's cell to a fixed goal that's kept up to date as the
+/// unit moves & cell costs change, with D* Lite.  When a door closes or
+/// another unit blocks a cell, only the costs that depend on the changed
+/// cells are searched again, which is far cheaper than a fresh
+/// [`find_path`] on long paths.
+///
+/// `cost(cell)` is the cost of stepping into a cell, or `None` if it's
+/// blocked, as for [`FlowField`]; pass the same closure to every method.
+#[derive(Debug, Clone)]
+pub struct IncrementalPath {
+    start: Axial,
+    goal: Axial,
+    /// grows as the start moves, so keys already queued stay lower bounds
+    km: u32,
+    /// cost from each searched cell to the goal
+    g: HashMap<Axial, u32>,
+    /// cost from each cell to the goal through its best neighbor
+    rhs: HashMap<Axial, u32>,
+    /// current key of each queued cell; entries of `open` that don't match
+    /// it are stale
+    queued: HashMap<Axial, (u32, u32)>,
+    open: BinaryHeap<Reverse<((u32, u32), Axial)>>,
+}
+
+impl IncrementalPath {
+    pub fn new(start: Axial, goal: Axial, cost: impl Fn(Axial) -> Option<u32>) -> Self {
+        let mut path = Self {
+            start,
+            goal,
+            km: 0,
+            g: HashMap::new(),
+            rhs: HashMap::new(),
+            queued: HashMap::new(),
+            open: BinaryHeap::new(),
+        };
+        path.rhs.insert(goal, 0);
+        path.push(goal);
+        path.search(&cost);
+        path
+    }
+
+    pub fn start(&self) -> Axial {
+        self.start
+    }
+
+    pub fn goal(&self) -> Axial {
+        self.goal
+    }
+
+    /// cost from the start to the goal; `None` if it can't be reached
+    pub fn cost(&self) -> Option<u32> {
+        Some(self.g(self.start)).filter(|&g| g != u32::MAX)
+    }
+
+    /// cell to step into from the start; `None` at the goal, or if it
+    /// can't be reached
+    pub fn next_step(&self, cost: impl Fn(Axial) -> Option<u32>) -> Option<Axial> {
+        self.best_neighbor(self.start, &cost).map(|(_, cell)| cell)
+    }
+
+    /// cheapest path from the start to the goal, including both
+    pub fn path(&self, cost: impl Fn(Axial) -> Option<u32>) -> Option<Vec<Axial>> {
+        let total = self.cost()?;
+        let mut path = vec![self.start];
+        let mut cell = self.start;
+        // every step costs at least 1, so a consistent search reaches the
+        // goal within `total` steps
+        while cell != self.goal && path.len() <= total as usize {
+            (_, cell) = self.best_neighbor(cell, &cost)?;
+            path.push(cell);
+        }
+        (cell == self.goal).then_some(path)
+    }
+
+    /// Replan after the unit moved to `start`, and the cost of the
+    /// `changed` cells changed.
+    pub fn update(
+        &mut self,
+        start: Axial,
+        changed: impl IntoIterator<Item = Axial>,
+        cost: impl Fn(Axial) -> Option<u32>,
+    ) {
+        self.km = self.km.saturating_add(self.start.distance(start));
+        self.start = start;
+        // only the cost of steps into a changed cell changed
+        for cell in changed {
+            for next in cell.neighbors() {
+                self.update_cell(next, &cost);
+            }
+        }
+        self.search(&cost);
+    }
+
+    fn g(&self, cell: Axial) -> u32 {
+        self.g.get(&cell).copied().unwrap_or(u32::MAX)
+    }
+
+    fn rhs(&self, cell: Axial) -> u32 {
+        self.rhs.get(&cell).copied().unwrap_or(u32::MAX)
+    }
+
+    fn key(&self, cell: Axial) -> (u32, u32) {
+        let best = self.g(cell).min(self.rhs(cell));
+        let estimate = best
+            .saturating_add(self.start.distance(cell))
+            .saturating_add(self.km);
+        (estimate, best)
+    }
+
+    fn push(&mut self, cell: Axial) {
+        let key = self.key(cell);
+        self.queued.insert(cell, key);
+        self.open.push(Reverse((key, cell)));
+    }
+
+    // lowest queued key, dropping stale entries
+    fn top(&mut self) -> Option<((u32, u32), Axial)> {
+        while let Some(&Reverse((key, cell))) = self.open.peek() {
+            if self.queued.get(&cell) == Some(&key) {
+                return Some((key, cell));
+            }
+            self.open.pop();
+        }
+        None
+    }
+
+    // neighbor of `cell` with the cheapest route to the goal, and its cost
+    fn best_neighbor(
+        &self,
+        cell: Axial,
+        cost: &impl Fn(Axial) -> Option<u32>,
+    ) -> Option<(u32, Axial)> {
+        cell.neighbors()
+            .filter_map(|next| {
+                let g = self.g(next);
+                (g != u32::MAX).then_some((cost(next)?.saturating_add(g), next))
+            })
+            .min_by_key(|&(total, _)| total)
+    }
+
+    // recompute the cost of `cell` through its neighbors, & queue it if
+    // that differs from its searched cost
+    fn update_cell(&mut self, cell: Axial, cost: &impl Fn(Axial) -> Option<u32>) {
+        if cell != self.goal {
+            match self.best_neighbor(cell, cost) {
+                Some((total, _)) => self.rhs.insert(cell, total),
+                None => self.rhs.remove(&cell),
+            };
+        }
+        self.queued.remove(&cell);
+        if self.g(cell) != self.rhs(cell) {
+            self.push(cell);
+        }
+    }
+
+    fn search(&mut self, cost: &impl Fn(Axial) -> Option<u32>) {
+        while let Some((old, cell)) = self.top() {
+            if old >= self.key(self.start) && self.rhs(self.start) == self.g(self.start) {
+                break;
+            }
+            let key = self.key(cell);
+            if old < key {
+                self.push(cell);
+                continue;
+            }
+            self.queued.remove(&cell);
+            self.open.pop();
+            let (g, rhs) = (self.g(cell), self.rhs(cell));
+            if g > rhs {
+                self.g.insert(cell, rhs);
+            } else {
+                self.g.remove(&cell);
+                self.update_cell(cell, cost);
+            }
+            // stepping from a neighbor into this cell
+            if cost(cell).is_some() {
+                for next in cell.neighbors() {
+                    self.update_cell(next, cost);
+                }
+            }
+        }
+    }
+}
+
+/// Cost to reach every cell within `budget` of `origin`, including `origin`
+/// at zero.  `cost(cell)` is the cost of stepping into a cell, or `None` if
+/// it's blocked.
+pub fn movement_range(
+    origin: Axial,
+    budget: u32,
+    cost: impl Fn(Axial) -> Option<u32>,
+) -> HexMap<u32> {
+    movement_range_steps(origin, budget, |_, to| cost(to))
+}
+
+/// [`movement_range`] with the cost of each step given by `cost(from, to)`,
+/// as for [`find_path`], so it can depend on where the step is from.
+pub fn movement_range_steps(
+    origin: Axial,
+    budget: u32,
+    mut cost: impl FnMut(Axial, Axial) -> Option<u32>,
+) -> HexMap<u32> {
+    let mut costs = HexMap::new();
+    costs.insert(origin, 0);
+    let mut open = BinaryHeap::from([Reverse((0u32, origin))]);
+    while let Some(Reverse((total, cell))) = open.pop() {
+        if costs.get(cell).is_some_and(|&c| c < total) {
+            continue;
+        }
+        for next in cell.neighbors() {
+            let Some(step) = cost(cell, next) else {
+                continue;
+            };
+            let next_total = total.saturating_add(step);
+            if next_total > budget || costs.get(next).is_some_and(|&c| c <= next_total) {
+                continue;
+            }
+            costs.insert(next, next_total);
+            open.push(Reverse((next_total, next)));
+        }
+    }
+    costs
+}
@@ -0,0 +1,153 @@
+//! pathfinding off the main thread
+//!
+//! Add [`HexGridPathPlugin`] to the app, and insert a [`PathRequest`] on any
+//! entity to search for a path on the [`AsyncComputeTaskPool`].  When the
+//! search ends the request is removed and a [`PathReady`] is sent, so long
+//! paths across huge maps never hold up a frame:
+//!
+//! ```ignore
+//! commands.entity(unit).insert(PathRequest::new(grid, from, to));
+//!
+//! fn follow_ready_paths(mut commands: Commands, mut ready: MessageReader<PathReady>) {
+//!     for ready in ready.read() {
+//!         if let Some((path, _)) = &ready.path {
+//!             commands.entity(ready.entity).insert(FollowPath::new(path.clone(), 4.0));
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Cells cost 1 to enter, or the cost in the grid's [`HexGridMoveCosts`].
+//! Each search works on the costs as they were when it started.  Changing or
+//! inserting a new request restarts the search, and removing the request or
+//! despawning the entity cancels it without a message.
+use std::sync::Arc;
+
+use bevy::{
+    platform::collections::HashMap,
+    prelude::*,
+    tasks::{futures::check_ready, AsyncComputeTaskPool, Task},
+};
+
+use crate::{drag::HexGridMoveCosts, path::find_path, Axial, HexMap, HexMapVersion};
+
+pub struct HexGridPathPlugin;
+
+impl Plugin for HexGridPathPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<PathRequest>()
+            .add_message::<PathReady>()
+            .add_systems(
+                Update,
+                (
+                    cancel_path_requests,
+                    start_path_requests,
+                    finish_path_requests,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Path to search for in the background; removed when a [`PathReady`] is
+/// sent for it.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct PathRequest {
+    /// grid entity whose [`HexGridMoveCosts`] are used
+    pub grid: Entity,
+    pub from: Axial,
+    pub to: Axial,
+}
+
+impl PathRequest {
+    pub fn new(grid: Entity, from: Axial, to: Axial) -> Self {
+        Self { grid, from, to }
+    }
+}
+
+/// sent when the search for a [`PathRequest`] ends
+#[derive(Message, Debug, Clone, PartialEq, Eq)]
+pub struct PathReady {
+    /// entity the request was on
+    pub entity: Entity,
+    pub grid: Entity,
+    pub from: Axial,
+    pub to: Axial,
+    /// cheapest path, including both ends, and its cost; `None` if `to`
+    /// can't be reached
+    pub path: Option<(Vec<Axial>, u32)>,
+}
+
+/// search in progress for the [`PathRequest`] on the same entity; dropping
+/// it cancels the search
+#[derive(Component)]
+struct PathTask(Task<Option<(Vec<Axial>, u32)>>);
+
+// searches whose request was removed
+fn cancel_path_requests(
+    mut commands: Commands,
+    tasks: Query<Entity, (With<PathTask>, Without<PathRequest>)>,
+) {
+    for entity in &tasks {
+        commands.entity(entity).remove::<PathTask>();
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn start_path_requests(
+    mut commands: Commands,
+    requests: Query<(Entity, &PathRequest), Changed<PathRequest>>,
+    costs: Query<&HexGridMoveCosts>,
+    // costs of each grid shared by its searches, copied again when they
+    // change
+    mut shared: Local<HashMap<Entity, (HexMapVersion, Arc<HexMap<u32>>)>>,
+) {
+    if requests.is_empty() {
+        return;
+    }
+    shared.retain(|&grid, _| costs.contains(grid));
+
+    let pool = AsyncComputeTaskPool::get();
+    for (entity, &PathRequest { grid, from, to }) in &requests {
+        let costs = costs.get(grid).ok().map(|costs| {
+            let version = costs.0.version();
+            let (synced, map) = shared
+                .entry(grid)
+                .or_insert_with(|| (version, Arc::new(costs.0.clone())));
+            if *synced != version {
+                *synced = version;
+                *map = Arc::new(costs.0.clone());
+            }
+            map.clone()
+        });
+        let task = pool.spawn(async move {
+            find_path(from, to, |_, cell| match &costs {
+                Some(costs) => costs.get(cell).copied(),
+                None => Some(1),
+            })
+        });
+        // replacing a search in progress cancels it
+        commands.entity(entity).insert(PathTask(task));
+    }
+}
+
+fn finish_path_requests(
+    mut commands: Commands,
+    mut tasks: Query<(Entity, &PathRequest, &mut PathTask)>,
+    mut ready: MessageWriter<PathReady>,
+) {
+    for (entity, request, mut task) in &mut tasks {
+        let Some(path) = check_ready(&mut task.0) else {
+            continue;
+        };
+        commands.entity(entity).remove::<(PathRequest, PathTask)>();
+        ready.write(PathReady {
+            entity,
+            grid: request.grid,
+            from: request.from,
+            to: request.to,
+            path,
+        });
+    }
+}
@@ -0,0 +1,126 @@
+//! gpu cursor picking
+//!
+//! [`HexGridCursor`] is normally found by intersecting the cursor ray with
+//! each grid plane on the cpu.  Add [`HexGridGpuPicking`] to a camera to have
+//! the grid pass find it instead: every grid the camera draws writes its id &
+//! the cell at the cursor pixel into a 1x1 target, with the nearest grid
+//! winning the depth test, and the target is read back asynchronously.
+//!
+//! The result is exactly what was drawn under the cursor; a grid clipped by a
+//! [`crate::HexGridMask`] can't be picked where it's masked out.  Reads
+//! arrive a frame or two after the cursor moves.
+use bevy::{
+    image::Image,
+    prelude::*,
+    render::{
+        extract_component::ExtractComponent,
+        gpu_readback::{Readback, ReadbackComplete},
+        render_resource::{TextureFormat, TextureUsages},
+    },
+};
+
+use crate::{cursor_ray, Axial, HexGridConfig, HexGridCursor, HexGridPlane};
+
+/// format of the pick target; grid id, q, r, & 1 when a grid was hit
+pub(crate) const PICK_FORMAT: TextureFormat = TextureFormat::Rgba32Uint;
+
+/// Pick the grid & cell under the cursor on the gpu for this camera,
+/// instead of on the cpu.  Updates the [`HexGridCursor`] on the same entity.
+#[derive(Component, Debug, Default, Clone, ExtractComponent, Reflect)]
+#[reflect(Component)]
+#[require(HexGridCursor)]
+pub struct HexGridGpuPicking {
+    /// cursor position in physical pixels of the window, if it's over it
+    #[reflect(ignore)]
+    pub(crate) pixel: Option<Vec2>,
+    /// 1x1 pick target, read back every frame
+    #[reflect(ignore)]
+    pub(crate) target: Option<Handle<Image>>,
+}
+
+/// create the pick target of new cameras, and track the cursor pixel
+pub(crate) fn update_gpu_picking(
+    mut commands: Commands,
+    windows: Query<&Window>,
+    mut cameras: Query<(Entity, &mut HexGridGpuPicking)>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let pixel = windows.iter().find_map(|w| w.physical_cursor_position());
+    for (entity, mut picking) in &mut cameras {
+        if picking.pixel != pixel {
+            picking.pixel = pixel;
+        }
+        if picking.target.is_some() {
+            continue;
+        }
+
+        let mut image = Image::new_target_texture(1, 1, PICK_FORMAT, None);
+        image.texture_descriptor.usage |= TextureUsages::COPY_SRC;
+        let target = images.add(image);
+        picking.target = Some(target.clone());
+        // the readback goes away with the camera
+        commands
+            .spawn((
+                Name::new("HexGridGpuPicking readback"),
+                Readback::texture(target),
+                ChildOf(entity),
+            ))
+            .observe(read_pick);
+    }
+}
+
+// apply a pick read back from the gpu to the camera's cursor
+fn read_pick(
+    event: On<ReadbackComplete>,
+    parents: Query<&ChildOf>,
+    windows: Query<&Window>,
+    mut cameras: Query<(&Camera, &GlobalTransform, &mut HexGridCursor), With<HexGridGpuPicking>>,
+    grids: Query<(Entity, &HexGridConfig, &GlobalTransform)>,
+) {
+    let Ok(child_of) = parents.get(event.entity) else {
+        return;
+    };
+    let Ok((camera, transform, mut cursor)) = cameras.get_mut(child_of.parent()) else {
+        return;
+    };
+    let Some(pick) = decode_pick(&event.data) else {
+        return;
+    };
+
+    let Some((id, cell)) = pick else {
+        if cursor.grid.is_some() {
+            cursor.grid = None;
+        }
+        return;
+    };
+    // grids are identified by entity index in the shader
+    let Some((grid, config, grid_transform)) = grids.iter().find(|(e, ..)| e.index_u32() == id)
+    else {
+        cursor.grid = None;
+        return;
+    };
+
+    // the position on the plane is still found on the cpu; fall back to the
+    // center of the picked cell if the ray misses
+    let plane = HexGridPlane::from(*grid_transform);
+    let pos = windows
+        .iter()
+        .find_map(|w| w.cursor_position())
+        .and_then(|p| cursor_ray(camera, transform, p))
+        .and_then(|ray| plane.intersect(ray))
+        .unwrap_or_else(|| config.cell_to_world(cell));
+    cursor.grid = Some(grid);
+    cursor.cell = config.wrap_cell(cell);
+    cursor.pos = pos;
+}
+
+// `None` if the data is too short, otherwise the grid id & cell hit, if any
+#[allow(clippy::option_option)]
+fn decode_pick(data: &[u8]) -> Option<Option<(u32, Axial)>> {
+    let word = |i: usize| {
+        let bytes = data.get(i * 4..i * 4 + 4)?;
+        Some(u32::from_le_bytes(bytes.try_into().ok()?))
+    };
+    let (id, q, r, hit) = (word(0)?, word(1)?, word(2)?, word(3)?);
+    Some((hit != 0).then(|| (id, Axial::new(q as i32, r as i32))))
+}
@@ -0,0 +1,161 @@
+//! bevy_picking backend for grid cells
+//!
+//! Add [`HexGridPickingPlugin`] to send bevy_picking pointer events (`Over`,
+//! `Out`, `Click`, `Drag`, ...) for the cells of every grid, as if each cell
+//! were an entity.  The cell under a pointer gets a short-lived entity with a
+//! [`HexGridPickCell`], spawned as a child of its grid, so events bubble up
+//! to the grid entity too:
+//!
+//! ```ignore
+//! app.add_observer(|click: On<Pointer<Click>>, cells: Query<&HexGridPickCell>| {
+//!     if let Ok(cell) = cells.get(click.entity) {
+//!         info!("clicked {:?} on grid {:?}", cell.cell, cell.grid);
+//!     }
+//! });
+//! ```
+//!
+//! Cell entities are despawned once no pointer has been over them for
+//! [`HexGridPickingSettings::cell_lifetime`], and never while a pointer
+//! button is held, so drags always end on a live entity.  Grids are hit on
+//! their plane, like [`crate::HexGridCursor`]; other backends' meshes in
+//! front of the grid sort ahead of it by depth.
+use bevy::{
+    camera::visibility::RenderLayers,
+    picking::{
+        backend::{ray::RayMap, HitData, PointerHits},
+        pointer::PointerPress,
+        PickingSystems,
+    },
+    platform::collections::HashMap,
+    prelude::*,
+};
+
+use crate::{Axial, HexGridConfig, HexGridPlane};
+
+pub struct HexGridPickingPlugin;
+
+impl Plugin for HexGridPickingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HexGridPickingSettings>()
+            .init_resource::<HexGridPickCells>()
+            .add_systems(
+                PreUpdate,
+                (hex_grid_picking, despawn_stale_cells)
+                    .chain()
+                    .in_set(PickingSystems::Backend),
+            );
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct HexGridPickingSettings {
+    /// seconds a cell entity is kept after the last pointer leaves it
+    pub cell_lifetime: f32,
+}
+
+impl Default for HexGridPickingSettings {
+    fn default() -> Self {
+        Self { cell_lifetime: 1.0 }
+    }
+}
+
+/// grid cell standing in for an entity in pointer events
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HexGridPickCell {
+    pub grid: Entity,
+    pub cell: Axial,
+}
+
+/// live cell entities by (grid, cell), with the time each was last hit
+#[derive(Resource, Debug, Default)]
+struct HexGridPickCells(HashMap<(Entity, Axial), (Entity, f32)>);
+
+#[allow(clippy::type_complexity)]
+fn hex_grid_picking(
+    mut commands: Commands,
+    time: Res<Time>,
+    ray_map: Res<RayMap>,
+    cameras: Query<(&Camera, Option<&RenderLayers>)>,
+    grids: Query<(
+        Entity,
+        &HexGridConfig,
+        &GlobalTransform,
+        &InheritedVisibility,
+        Option<&RenderLayers>,
+    )>,
+    mut cells: ResMut<HexGridPickCells>,
+    mut output: MessageWriter<PointerHits>,
+) {
+    let now = time.elapsed_secs();
+    for (&ray_id, &ray) in ray_map.iter() {
+        let Ok((camera, camera_layers)) = cameras.get(ray_id.camera) else {
+            continue;
+        };
+        if !camera.is_active {
+            continue;
+        }
+        let camera_layers = camera_layers.cloned().unwrap_or_default();
+
+        let mut picks = Vec::new();
+        for (grid, config, transform, visibility, layers) in &grids {
+            if !visibility.get() || !camera_layers.intersects(&layers.cloned().unwrap_or_default())
+            {
+                continue;
+            }
+            let plane = HexGridPlane::from(*transform);
+            let Some(depth) = plane.ray_distance(ray) else {
+                continue;
+            };
+            let Some(pos) = plane.intersect(ray) else {
+                continue;
+            };
+            let cell = config.world_to_cell(pos);
+
+            let (entity, last_hit) = cells.0.entry((grid, cell)).or_insert_with(|| {
+                let entity = commands
+                    .spawn((
+                        Name::new(format!("HexGridPickCell {:?}", cell)),
+                        HexGridPickCell { grid, cell },
+                        ChildOf(grid),
+                    ))
+                    .id();
+                (entity, now)
+            });
+            *last_hit = now;
+            let hit = HitData::new(
+                ray_id.camera,
+                depth,
+                Some(ray.get_point(depth)),
+                Some(*transform.up()),
+            );
+            picks.push((*entity, hit));
+        }
+        output.write(PointerHits::new(ray_id.pointer, picks, camera.order as f32));
+    }
+}
+
+// despawn cell entities no pointer has been over for a while; none are
+// despawned while a button is held, as a drag may have started on one
+fn despawn_stale_cells(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<HexGridPickingSettings>,
+    pointers: Query<&PointerPress>,
+    live: Query<(), With<HexGridPickCell>>,
+    mut cells: ResMut<HexGridPickCells>,
+) {
+    // cells of despawned grids went with them
+    cells.0.retain(|_, (entity, _)| live.contains(*entity));
+    if pointers.iter().any(|press| press.is_any_pressed()) {
+        return;
+    }
+
+    let now = time.elapsed_secs();
+    cells.0.retain(|_, &mut (entity, last_hit)| {
+        let keep = now - last_hit < settings.cell_lifetime;
+        if !keep {
+            commands.entity(entity).despawn();
+        }
+        keep
+    });
+}
@@ -0,0 +1,265 @@
+//! entities placed on grid cells
+//!
+//! A [`HexPosition`] puts an entity on a cell of a grid.  Its [`Transform`]
+//! follows the cell, so moving a unit is one line:
+//!
+//! ```ignore
+//! commands.entity(unit).insert(HexPosition { grid, cell: to });
+//! ```
+//!
+//! Add a [`HexPositionInterpolation`] to glide between cells instead of
+//! jumping, or a [`FollowPath`] to walk a path one cell at a time, sending
+//! [`EnteredCell`] for each step.  Entities on each cell are tracked in the
+//! [`crate::HexOccupancy`].
+use bevy::prelude::*;
+
+use crate::{
+    occupancy::{occupy_cell, vacate_cell},
+    Axial, GridTime, HexGridConfig, HexGridPlane,
+};
+
+/// Cell an entity stands on.  Unlike [`crate::HexCell`], any number of
+/// entities can share a cell.  Immutable; insert a new one to move the
+/// entity.
+///
+/// The entity's transform is moved to the center of the cell.  Children of
+/// the grid keep their height above it; other entities are placed on the
+/// grid plane.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Component)]
+#[component(immutable, on_insert = occupy_cell, on_replace = vacate_cell)]
+#[require(Transform)]
+pub struct HexPosition {
+    pub grid: Entity,
+    pub cell: Axial,
+}
+
+/// Move an entity to its new [`HexPosition`] over time, instead of at once.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct HexPositionInterpolation {
+    /// seconds to move between cells; zero to jump
+    pub duration: f32,
+    pub ease: EaseFunction,
+    /// translation the move started from, & seconds since it started
+    #[reflect(ignore)]
+    from: Option<(Vec3, f32)>,
+}
+
+impl Default for HexPositionInterpolation {
+    fn default() -> Self {
+        Self::new(0.25)
+    }
+}
+
+impl HexPositionInterpolation {
+    pub fn new(duration: f32) -> Self {
+        Self {
+            duration,
+            ease: EaseFunction::CubicInOut,
+            from: None,
+        }
+    }
+
+    pub fn with_ease(mut self, ease: EaseFunction) -> Self {
+        self.ease = ease;
+        self
+    }
+
+    /// true while moving between cells
+    pub fn is_moving(&self) -> bool {
+        self.from.is_some()
+    }
+}
+
+/// Walk an entity with a [`HexPosition`] along a path, such as one from
+/// [`crate::path::find_path`], passing through the center of each cell.
+/// The [`HexPosition`] is updated, & [`EnteredCell`] sent, as the entity
+/// crosses into each cell; [`PathComplete`] is sent, & this component
+/// removed, when it reaches the center of the last one.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct FollowPath {
+    /// cells to pass through, starting with the one the entity is on
+    pub cells: Vec<Axial>,
+    /// cells per second
+    pub speed: f32,
+    /// easing of each step between cells
+    pub easing: EaseFunction,
+    /// steps taken so far, including the fraction of the current one
+    #[reflect(ignore)]
+    progress: f32,
+}
+
+impl FollowPath {
+    pub fn new(cells: impl IntoIterator<Item = Axial>, speed: f32) -> Self {
+        Self {
+            cells: cells.into_iter().collect(),
+            speed,
+            easing: EaseFunction::Linear,
+            progress: 0.0,
+        }
+    }
+
+    pub fn with_easing(mut self, easing: EaseFunction) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// steps taken so far, including the fraction of the current one
+    pub fn progress(&self) -> f32 {
+        self.progress
+    }
+}
+
+/// sent when an entity following a path crosses into a cell
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnteredCell {
+    pub entity: Entity,
+    pub grid: Entity,
+    pub cell: Axial,
+}
+
+/// sent when an entity reaches the end of its [`FollowPath`]
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathComplete {
+    pub entity: Entity,
+    pub grid: Entity,
+    pub cell: Axial,
+}
+
+// translation of an entity standing on `cell`; children of the grid keep
+// their height above it
+fn cell_translation(
+    grid: Entity,
+    cell: Axial,
+    config: &HexGridConfig,
+    grid_transform: &GlobalTransform,
+    child_of: Option<&ChildOf>,
+    current: Vec3,
+) -> Vec3 {
+    if child_of.is_some_and(|c| c.parent() == grid) {
+        let center = config.cell_to_world(cell);
+        Vec3::new(center.x, current.y, center.y)
+    } else {
+        HexGridPlane::from(*grid_transform).cell_to_world(config, cell)
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn follow_paths(
+    mut commands: Commands,
+    time: Res<GridTime>,
+    mut entities: Query<(
+        Entity,
+        &HexPosition,
+        &mut FollowPath,
+        &mut Transform,
+        Option<&ChildOf>,
+    )>,
+    grids: Query<(&HexGridConfig, &GlobalTransform)>,
+    mut entered: MessageWriter<EnteredCell>,
+    mut complete: MessageWriter<PathComplete>,
+) {
+    for (entity, pos, mut path, mut transform, child_of) in &mut entities {
+        let Ok((config, grid_transform)) = grids.get(pos.grid) else {
+            continue;
+        };
+        let steps = path.cells.len().saturating_sub(1) as f32;
+        let before = path.progress;
+        path.progress = (before + path.speed * time.delta_secs()).min(steps);
+
+        // cells are entered halfway through the step into them, where the
+        // edge between the cells is crossed
+        let first = (before + 0.5).floor() as usize;
+        let last = (path.progress + 0.5).floor() as usize;
+        for &cell in path.cells.iter().take(last + 1).skip(first + 1) {
+            entered.write(EnteredCell {
+                entity,
+                grid: pos.grid,
+                cell,
+            });
+        }
+        if let Some(&cell) = path.cells.get(last).filter(|&&c| c != pos.cell) {
+            commands.entity(entity).insert(HexPosition {
+                grid: pos.grid,
+                cell,
+            });
+        }
+
+        let step = (path.progress.floor() as usize).min(path.cells.len().saturating_sub(2));
+        let translation = match path.cells.get(step..step + 2) {
+            Some(&[from, to]) => {
+                let at = |cell| {
+                    let current = transform.translation;
+                    cell_translation(pos.grid, cell, config, grid_transform, child_of, current)
+                };
+                let t = path.easing.sample_clamped(path.progress - step as f32);
+                at(from).lerp(at(to), t)
+            }
+            _ => transform.translation,
+        };
+        if transform.translation != translation {
+            transform.translation = translation;
+        }
+
+        if path.progress >= steps {
+            commands.entity(entity).remove::<FollowPath>();
+            complete.write(PathComplete {
+                entity,
+                grid: pos.grid,
+                cell: path.cells.last().copied().unwrap_or(pos.cell),
+            });
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn sync_hex_positions(
+    time: Res<GridTime>,
+    mut entities: Query<
+        (
+            Ref<HexPosition>,
+            &mut Transform,
+            Option<&ChildOf>,
+            Option<&mut HexPositionInterpolation>,
+        ),
+        Without<FollowPath>,
+    >,
+    grids: Query<(&HexGridConfig, &GlobalTransform)>,
+) {
+    for (pos, mut transform, child_of, interpolation) in &mut entities {
+        let Ok((config, grid_transform)) = grids.get(pos.grid) else {
+            continue;
+        };
+        let target = cell_translation(
+            pos.grid,
+            pos.cell,
+            config,
+            grid_transform,
+            child_of,
+            transform.translation,
+        );
+
+        let translation = match interpolation {
+            Some(mut interpolation) if interpolation.duration > 0.0 => {
+                if pos.is_changed() && !pos.is_added() {
+                    interpolation.from = Some((transform.translation, 0.0));
+                }
+                match interpolation.from {
+                    Some((from, elapsed)) => {
+                        let elapsed = elapsed + time.delta_secs();
+                        let t = elapsed / interpolation.duration;
+                        interpolation.from = (t < 1.0).then_some((from, elapsed));
+                        from.lerp(target, interpolation.ease.sample_clamped(t))
+                    }
+                    None => target,
+                }
+            }
+            _ => target,
+        };
+        if transform.translation != translation {
+            transform.translation = translation;
+        }
+    }
+}
@@ -0,0 +1,298 @@
+//! path previews
+//!
+//! Spawn a [`HexGridPathPreview`] as a child of a grid entity to draw a
+//! ribbon through the centers of the cells along a path, such as one found
+//! with [`crate::path::find_path`].  The ribbon is patterned with dashes or
+//! chained arrows that flow from the start of the path to the end.
+//!
+//! ```ignore
+//! commands.entity(grid).with_child(HexGridPathPreview::new(path));
+//! ```
+//!
+//! Each preview is an ordinary unlit, alpha blended mesh, rebuilt whenever
+//! the preview or the grid's [`HexGridConfig`] changes.  It's drawn in the
+//! main pass, so grid lines & highlights are drawn over it.
+use bevy::{
+    asset::RenderAssetUsages,
+    image::{ImageAddressMode, ImageSampler, ImageSamplerDescriptor},
+    math::Affine2,
+    mesh::{Indices, PrimitiveTopology},
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+
+use crate::{Axial, GridTime, HexGridConfig};
+
+/// pattern texture size; one repeat along the ribbon by its width
+const PATTERN_SIZE: UVec2 = UVec2::new(64, 32);
+
+/// height of the ribbon above the grid plane, as a fraction of the cell size
+const LIFT: f32 = 0.01;
+
+/// pattern drawn along a [`HexGridPathPreview`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PathPreviewStyle {
+    /// chevrons pointing along the path
+    #[default]
+    Arrows,
+    /// dashed line
+    Dashes,
+}
+
+/// Ribbon drawn through the cells of a path.  Must be a child of a grid
+/// entity; despawn it to remove the preview.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+#[require(Transform, Visibility)]
+pub struct HexGridPathPreview {
+    /// cells the ribbon passes through, in order
+    pub path: Vec<Axial>,
+    pub color: Color,
+    pub style: PathPreviewStyle,
+    /// width of the ribbon, as a fraction of the cell size
+    pub width: f32,
+    /// cells per second the pattern moves toward the end of the path;
+    /// zero for a still pattern
+    pub speed: f32,
+}
+
+impl Default for HexGridPathPreview {
+    fn default() -> Self {
+        Self {
+            path: Vec::new(),
+            color: Color::srgba(1.0, 1.0, 1.0, 0.8),
+            style: PathPreviewStyle::Arrows,
+            width: 0.3,
+            speed: 1.0,
+        }
+    }
+}
+
+impl HexGridPathPreview {
+    pub fn new(path: impl IntoIterator<Item = Axial>) -> Self {
+        Self {
+            path: path.into_iter().collect(),
+            ..default()
+        }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_style(mut self, style: PathPreviewStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn with_width(mut self, width: f32) -> Self {
+        self.width = width;
+        self
+    }
+
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+}
+
+/// Ribbon mesh through the centers of the cells in `path`, `width` (a
+/// fraction of the cell size) wide, in the local frame of the grid.  The U
+/// texture coordinate runs along the ribbon, repeating every two widths, and
+/// V across it.  `None` if the path doesn't leave its first cell.
+pub fn path_ribbon_mesh(config: &HexGridConfig, path: &[Axial], width: f32) -> Option<Mesh> {
+    let mut points: Vec<Vec2> = path.iter().map(|&c| config.cell_to_world(c)).collect();
+    points.dedup();
+    if points.len() < 2 {
+        return None;
+    }
+
+    let half = width * config.size * 0.5;
+    let period = width * config.size * 2.0;
+    let lift = LIFT * config.size;
+    let mut positions = Vec::with_capacity(points.len() * 2);
+    let mut uvs = Vec::with_capacity(points.len() * 2);
+    let mut distance = 0.0;
+    for (i, &point) in points.iter().enumerate() {
+        let incoming = (point - points[i.saturating_sub(1)]).normalize_or_zero();
+        let outgoing = (points.get(i + 1).unwrap_or(&point) - point).normalize_or_zero();
+        // mitered join; the side offset grows where the path turns so the
+        // ribbon keeps its width, up to double at the sharpest turns
+        let side = |dir: Vec2| dir.perp();
+        let mut offset = side(incoming + outgoing).normalize_or(side(incoming));
+        let along = if incoming == Vec2::ZERO {
+            outgoing
+        } else {
+            incoming
+        };
+        offset /= offset.dot(side(along)).max(0.5);
+
+        if i > 0 {
+            distance += point.distance(points[i - 1]);
+        }
+        let u = distance / period;
+        for (v, edge) in [(0.0, point + offset * half), (1.0, point - offset * half)] {
+            positions.push([edge.x, lift, edge.y]);
+            uvs.push([u, v]);
+        }
+    }
+
+    let normals = vec![[0.0, 1.0, 0.0]; positions.len()];
+    let indices = (0..points.len() as u32 - 1)
+        .flat_map(|i| {
+            let (a, b) = (i * 2, i * 2 + 2);
+            [a, a + 1, b, b, a + 1, b + 1]
+        })
+        .collect();
+    Some(
+        Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+        .with_inserted_indices(Indices::U32(indices)),
+    )
+}
+
+/// pattern textures, built on first use
+#[derive(Default)]
+pub(crate) struct PathPatterns {
+    arrows: Option<Handle<Image>>,
+    dashes: Option<Handle<Image>>,
+}
+
+impl PathPatterns {
+    fn get(&mut self, style: PathPreviewStyle, images: &mut Assets<Image>) -> Handle<Image> {
+        let slot = match style {
+            PathPreviewStyle::Arrows => &mut self.arrows,
+            PathPreviewStyle::Dashes => &mut self.dashes,
+        };
+        slot.get_or_insert_with(|| images.add(pattern_image(style)))
+            .clone()
+    }
+}
+
+// One repeat of a pattern; white, with the pattern in the alpha channel.
+// The image is two ribbon widths long, so both axes have the same scale.
+fn pattern_image(style: PathPreviewStyle) -> Image {
+    let scale = PATTERN_SIZE.y as f32;
+    let mut data = Vec::with_capacity((PATTERN_SIZE.x * PATTERN_SIZE.y * 4) as usize);
+    for y in 0..PATTERN_SIZE.y {
+        for x in 0..PATTERN_SIZE.x {
+            // in ribbon widths; u along the ribbon, v from its center
+            let u = (x as f32 + 0.5) / scale;
+            let v = (y as f32 + 0.5) / scale - 0.5;
+            // signed distance to the edge of the shape, negative inside
+            let d = match style {
+                PathPreviewStyle::Arrows => {
+                    // chevron with its tip 1.4 along, arms back to 0.6
+                    let slope = 1.6;
+                    (u - (1.4 - slope * v.abs())).abs() / (1.0 + slope * slope).sqrt() - 0.15
+                }
+                PathPreviewStyle::Dashes => (u - 1.0).abs() - 0.5,
+            };
+            let alpha = (0.5 - d * scale).clamp(0.0, 1.0);
+            data.extend([255, 255, 255, (alpha * 255.0).round() as u8]);
+        }
+    }
+
+    let mut image = Image::new(
+        Extent3d {
+            width: PATTERN_SIZE.x,
+            height: PATTERN_SIZE.y,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+        address_mode_u: ImageAddressMode::Repeat,
+        ..ImageSamplerDescriptor::linear()
+    });
+    image
+}
+
+/// rebuild the mesh & material of previews that changed, or whose grid did
+#[allow(clippy::type_complexity)]
+pub(crate) fn update_path_previews(
+    mut commands: Commands,
+    previews: Query<(
+        Entity,
+        Ref<HexGridPathPreview>,
+        &ChildOf,
+        Option<&Mesh3d>,
+        Option<&MeshMaterial3d<StandardMaterial>>,
+    )>,
+    grids: Query<Ref<HexGridConfig>>,
+    mut patterns: Local<PathPatterns>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    for (entity, preview, child_of, mesh, material) in &previews {
+        let Ok(config) = grids.get(child_of.parent()) else {
+            continue;
+        };
+        if !preview.is_changed() && !config.is_changed() {
+            continue;
+        }
+
+        let Some(ribbon) = path_ribbon_mesh(&config, &preview.path, preview.width) else {
+            commands.entity(entity).remove::<Mesh3d>();
+            continue;
+        };
+        match mesh.and_then(|m| meshes.get_mut(&m.0)) {
+            Some(mesh) => *mesh = ribbon,
+            None => {
+                commands.entity(entity).insert(Mesh3d(meshes.add(ribbon)));
+            }
+        }
+
+        let texture = patterns.get(preview.style, &mut images);
+        match material.and_then(|m| materials.get_mut(&m.0)) {
+            Some(material) => {
+                material.base_color = preview.color;
+                material.base_color_texture = Some(texture);
+            }
+            None => {
+                commands
+                    .entity(entity)
+                    .insert(MeshMaterial3d(materials.add(StandardMaterial {
+                        base_color: preview.color,
+                        base_color_texture: Some(texture),
+                        alpha_mode: AlphaMode::Blend,
+                        unlit: true,
+                        cull_mode: None,
+                        ..default()
+                    })));
+            }
+        }
+    }
+}
+
+/// scroll the pattern of each preview along its path
+pub(crate) fn animate_path_previews(
+    time: Res<GridTime>,
+    previews: Query<(&HexGridPathPreview, &MeshMaterial3d<StandardMaterial>)>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (preview, material) in &previews {
+        if preview.speed == 0.0 || preview.width <= 0.0 {
+            continue;
+        }
+        let Some(material) = materials.get_mut(&material.0) else {
+            continue;
+        };
+        // the pattern repeats every two widths; sampling behind the vertex
+        // moves the pattern forward
+        let step = time.delta_secs() * preview.speed / (preview.width * 2.0);
+        let offset = (material.uv_transform.translation.x - step).rem_euclid(1.0);
+        material.uv_transform = Affine2::from_translation(Vec2::new(offset, 0.0));
+    }
+}
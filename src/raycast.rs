@@ -0,0 +1,197 @@
+//! ray casts across grid cells
+//!
+//! [`raycast`] walks the cells a ray crosses on the grid plane, in order, &
+//! stops at the first one that blocks it.  Use it for projectiles and line of
+//! sight that must agree with the cells drawn by the grid shader.  Cells are
+//! stepped through edge by edge rather than sampled, so a ray clipping the
+//! corner of a cell still visits it.
+use bevy::prelude::*;
+
+use crate::{config::GridKind, Axial, HexDirection, HexGridConfig};
+
+/// cell crossed by a ray
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    pub cell: Axial,
+    /// where the ray enters the cell, on the grid plane (local X, local Z);
+    /// the origin for the first cell
+    pub point: Vec2,
+    /// distance along the ray to `point`
+    pub distance: f32,
+}
+
+/// First cell along a ray on the grid plane (local X, local Z) for which
+/// `blocks` returns true, up to `max_dist` away.  The cell containing
+/// `origin` is tested too.
+pub fn raycast(
+    config: &HexGridConfig,
+    origin: Vec2,
+    dir: Vec2,
+    max_dist: f32,
+    mut blocks: impl FnMut(Axial) -> bool,
+) -> Option<RayHit> {
+    ray_cells(config, origin, dir, max_dist).find(|hit| blocks(hit.cell))
+}
+
+/// every cell a ray crosses within `max_dist` of `origin`, in order
+pub fn ray_cells(config: &HexGridConfig, origin: Vec2, dir: Vec2, max_dist: f32) -> RayCells {
+    let dir = dir.normalize_or_zero();
+    // world_to_grid() is linear, so distances along the ray carry over
+    let grid_origin = config.world_to_grid(origin);
+    let grid_dir = config.world_to_grid(dir);
+    let cell = config.world_to_cell(origin);
+
+    let lines = match config.kind {
+        GridKind::Hex => Vec::new(),
+        // square kinds are bounded by lines halfway between the centers
+        GridKind::Square | GridKind::Isometric => vec![(Vec2::X, 0.5), (Vec2::Y, 0.5)],
+        // the triangle lattice & the diagonal splitting each parallelogram;
+        // see HexGridConfig::world_to_cell()
+        GridKind::Triangle => vec![
+            (Vec2::new(1.0, -1.0 / 3f32.sqrt()), 0.0),
+            (Vec2::new(0.0, 2.0 / 3f32.sqrt()), 0.0),
+            (Vec2::new(1.0, 1.0 / 3f32.sqrt()), 0.0),
+        ],
+    };
+    let lines = lines
+        .into_iter()
+        .map(|(normal, offset)| {
+            let at = normal.dot(grid_origin) - offset;
+            Line {
+                normal,
+                offset,
+                index: at.floor() as i32,
+            }
+        })
+        .collect();
+
+    RayCells {
+        kind: config.kind,
+        origin,
+        dir,
+        grid_origin,
+        grid_dir,
+        max_dist: if dir == Vec2::ZERO { 0.0 } else { max_dist },
+        distance: 0.0,
+        cell: Some(cell),
+        lines,
+    }
+}
+
+// family of parallel cell boundaries at `dot(normal, p) = n + offset` in grid
+// space
+#[derive(Debug, Clone)]
+struct Line {
+    normal: Vec2,
+    offset: f32,
+    /// boundary the ray is past; it's between `index` & `index + 1`
+    index: i32,
+}
+
+impl Line {
+    // distance along a ray in grid space to the next boundary it crosses
+    fn crossing(&self, origin: Vec2, dir: Vec2) -> f32 {
+        let speed = self.normal.dot(dir);
+        let at = self.normal.dot(origin) - self.offset;
+        if speed > 0.0 {
+            ((self.index + 1) as f32 - at) / speed
+        } else if speed < 0.0 {
+            (self.index as f32 - at) / speed
+        } else {
+            f32::INFINITY
+        }
+    }
+}
+
+/// iterator from [`ray_cells`]
+#[derive(Debug, Clone)]
+pub struct RayCells {
+    kind: GridKind,
+    origin: Vec2,
+    dir: Vec2,
+    grid_origin: Vec2,
+    grid_dir: Vec2,
+    max_dist: f32,
+    /// distance to where the ray entered `cell`
+    distance: f32,
+    /// `None` once past `max_dist`
+    cell: Option<Axial>,
+    lines: Vec<Line>,
+}
+
+impl RayCells {
+    // distance to where the ray leaves the current cell, and the next cell
+    fn exit(&mut self, cell: Axial) -> (f32, Axial) {
+        if self.kind == GridKind::Hex {
+            // a hex is bounded by three pairs of edges half a step from its
+            // center, facing the neighbors
+            let center = cell.to_world();
+            let mut exit = (f32::INFINITY, cell);
+            for dir in &HexDirection::ALL[..3] {
+                let normal = dir.offset().to_world();
+                let at = normal.dot(self.grid_origin - center);
+                let speed = normal.dot(self.grid_dir);
+                let (edge, dir) = if speed > 0.0 {
+                    (0.5, *dir)
+                } else if speed < 0.0 {
+                    (-0.5, dir.opposite())
+                } else {
+                    continue;
+                };
+                let distance = (edge - at) / speed;
+                if distance < exit.0 {
+                    exit = (distance, cell + dir);
+                }
+            }
+            return exit;
+        }
+
+        // next boundary crossed in any family; every family crossed at the
+        // same point, through a corner, is stepped over
+        let (origin, dir) = (self.grid_origin, self.grid_dir);
+        let distance = self
+            .lines
+            .iter()
+            .map(|line| line.crossing(origin, dir))
+            .fold(f32::INFINITY, f32::min);
+        for line in &mut self.lines {
+            if line.crossing(origin, dir) <= distance {
+                line.index += line.normal.dot(dir).signum() as i32;
+            }
+        }
+
+        let index = |i: usize| self.lines[i].index;
+        let next = match self.kind {
+            GridKind::Triangle => {
+                let upper = index(2) - index(0) - index(1);
+                Axial::new(index(0) * 2 + upper, index(1))
+            }
+            _ => Axial::new(index(0) + 1, index(1) + 1),
+        };
+        (distance, next)
+    }
+}
+
+impl Iterator for RayCells {
+    type Item = RayHit;
+
+    fn next(&mut self) -> Option<RayHit> {
+        let cell = self.cell?;
+        let hit = RayHit {
+            cell,
+            point: self.origin + self.dir * self.distance,
+            distance: self.distance,
+        };
+
+        let (exit, next) = self.exit(cell);
+        // float error can put the exit a hair behind the entry
+        let exit = exit.max(self.distance);
+        if exit > self.max_dist || !exit.is_finite() {
+            self.cell = None;
+        } else {
+            self.cell = Some(next);
+            self.distance = exit;
+        }
+        Some(hit)
+    }
+}
@@ -0,0 +1,2048 @@
+//! render world side of the hex grid
+//!
+//! The grid is drawn by a single post-processing node that draws a
+//! fullscreen triangle per grid and reconstructs the grid plane intersection
+//! per-pixel in `hex_grid.wgsl`.
+//!
+//! Grids are extracted from the main world every frame onto their render
+//! world entity.  Their buffers live in [`HexGridBuffers`] across frames, and
+//! are dropped as soon as the grid (or camera) stops being extracted, so
+//! grids can be spawned and despawned at any time.  Buffers written every
+//! frame are double buffered, and written in prepare; see [`FrameBuffers`].
+//! Highlights are culled
+//! against each view's frustum before they're written, so there's one
+//! highlight buffer per view & grid.  Each highlight buffer is a hash table
+//! keyed by cell, so the shader can look up a pixel's cell without scanning
+//! every highlight.  Bind groups are cached in [`HexGridBindGroupCache`],
+//! and only created again when something bound in them is recreated.
+//!
+//! Where the adapter supports push constants large enough for
+//! [`ViewUniform`], the view is pushed with each draw instead of written to a
+//! uniform buffer; see [`ViewUniform::push_constants`].  WebGL, and most
+//! adapters with only 128 bytes of push constants, use the uniform buffer.
+use std::sync::{Arc, Mutex};
+
+use bevy::{
+    camera::{primitives::Frustum, visibility::RenderLayers},
+    core_pipeline::tonemapping::Tonemapping,
+    ecs::query::QueryItem,
+    image::BevyDefault,
+    platform::collections::HashMap,
+    prelude::*,
+    render::{
+        camera::ExtractedCamera,
+        diagnostic::RecordDiagnostics,
+        render_asset::RenderAssets,
+        render_graph::{NodeRunError, RenderGraphContext, RenderLabel, ViewNode},
+        render_resource::{
+            binding_types::{sampler, storage_buffer_read_only, texture_2d, uniform_buffer},
+            encase, BindGroup, BindGroupEntries, BindGroupLayout, BindGroupLayoutDescriptor,
+            BindGroupLayoutEntries, BindGroupLayoutId, BlendState, Buffer, BufferId,
+            CachedPipelineState, CachedRenderPipelineId, ColorTargetState, ColorWrites,
+            CompareFunction, DepthBiasState, DepthStencilState, Extent3d, FragmentState, LoadOp,
+            MultisampleState, Operations, PipelineCache, PrimitiveState, PushConstantRange,
+            RenderPassColorAttachment, RenderPassDepthStencilAttachment, RenderPassDescriptor,
+            RenderPipelineDescriptor, Sampler, SamplerBindingType, SamplerId, ShaderStages,
+            ShaderType, SpecializedRenderPipeline, SpecializedRenderPipelines, StencilFaceState,
+            StencilState, StorageBuffer, StoreOp, TextureDescriptor, TextureDimension,
+            TextureFormat, TextureSampleType, TextureUsages, TextureView, TextureViewDescriptor,
+            TextureViewId, UniformBuffer, VertexState,
+        },
+        renderer::{RenderContext, RenderDevice, RenderQueue},
+        settings::WgpuFeatures,
+        sync_world::RenderEntity,
+        texture::{FallbackImage, GpuImage},
+        view::{ExtractedView, ViewDepthTexture, ViewTarget},
+        Extract,
+    },
+    shader::{PipelineCacheError, ShaderDefVal},
+};
+
+use crate::{
+    bake::{changed_cells, ExtractedBake, HexGridBakes},
+    border::{BorderLayer, HexGridBorders},
+    colorblind::{ColorblindPreset, OKABE_ITO},
+    decal::{HexGridDecalAtlas, HexGridDecals},
+    elevation::HexGridElevation,
+    fill::{CellFill, HexGridFills},
+    flood::{draw_hex_grid_glow, ViewHexGridGlow, COLOR_FORMAT, SEED_FORMAT},
+    glow::HexGridGlow,
+    hash::hash_cell,
+    heat::HexGridHeatTextures,
+    heatmap::HexGridHeatmap,
+    highlight::{HexGridBakeHighlights, HexGridHighlights, HexGridPartHighlights},
+    holes::HexGridHoles,
+    network::HexGridNetworks,
+    owners::HexGridOwnerTextures,
+    picking::{HexGridGpuPicking, PICK_FORMAT},
+    rings::HexGridDistanceRings,
+    select::HexGridBoxSelect,
+    stencil::{draw_hex_grid_masks, ViewHexGridStencil},
+    style::{HexGridStyle, HexGridStyleHandle},
+    terrain::HexGridTerrain,
+    territory::HexGridPalette,
+    theme::{themed_config, HexGridThemeFade, HexGridThemes},
+    weather::HexGridWeather,
+    GridKind, GridTime, HexBounds, HexGridConfig, HexGridCursor, HexGridPlane, HexGridTonemapping,
+    HexMap, HexMapVersion, LineAntialiasing, LineWidthMode, Orientation,
+};
+
+// no grid is under the cursor
+const NO_GRID: u32 = u32::MAX;
+
+// key of the river & road masks of a cell in the part highlights table
+const NETWORK_KEY: u32 = 6;
+
+// key of the terrain of a cell in the part highlights table
+const TERRAIN_KEY: u32 = 7;
+
+// key of the height of a cell in the part highlights table
+const ELEVATION_KEY: u32 = 8;
+
+// key of the weather of a cell in the part highlights table
+const WEATHER_KEY: u32 = 9;
+
+// key of the glow of a cell in the part highlights table
+const GLOW_KEY: u32 = 10;
+
+// key of the fill of a cell in the part highlights table
+const FILL_KEY: u32 = 11;
+
+// bits of a part highlights key above the part; the stroke group + 1 of an
+// edge, 0 without one
+const STROKE_SHIFT: u32 = 8;
+
+#[derive(Debug, ShaderType, Default, Clone, Copy)]
+pub(crate) struct ViewUniform {
+    viewport: UVec4,
+    projection: Mat4,
+    inverse_projection: Mat4,
+    view: Mat4,
+    inverse_view: Mat4,
+    cursor_pos: Vec2,
+    // GridUniform::id of the grid under the cursor
+    cursor_grid: u32,
+    // GridUniform::id of the grid being box selected
+    select_grid: u32,
+    // box selection corners on the grid plane; arrays of Vec2 can't be in a
+    // uniform, so two corners are packed in each
+    select_quad: [Vec4; 2],
+    select_color: Vec4,
+    // cursor position in render target pixels, for HexGridGpuPicking
+    pick_pixel: Vec2,
+    // undoes the color grading exposure; only used with UNTONEMAP
+    color_scale: f32,
+    // physical pixels per LineWidthMode::Pixels pixel; see ViewHexGridScale
+    scale_factor: f32,
+}
+
+impl ViewUniform {
+    fn new(
+        view: &ExtractedView,
+        cursor: &HexGridCursor,
+        select: Option<&HexGridBoxSelect>,
+        picking: Option<&HexGridGpuPicking>,
+        scale_factor: f32,
+    ) -> Self {
+        let view_matrix = view.world_from_view.to_matrix();
+        let drag = select.and_then(|s| s.drag().map(|(grid, corners)| (s, grid, corners)));
+        let (select_grid, select_quad, select_color) = match drag {
+            Some((select, grid, [a, b, c, d])) => (
+                grid.index_u32(),
+                [a.extend(b.x).extend(b.y), c.extend(d.x).extend(d.y)],
+                select.color.to_linear().to_vec4(),
+            ),
+            None => (NO_GRID, [Vec4::ZERO; 2], Vec4::ZERO),
+        };
+        Self {
+            viewport: view.viewport,
+            projection: view.clip_from_view,
+            inverse_projection: view.clip_from_view.inverse(),
+            view: view_matrix,
+            inverse_view: view_matrix.inverse(),
+            cursor_pos: cursor.pos,
+            cursor_grid: cursor.grid.map(|e| e.index_u32()).unwrap_or(NO_GRID),
+            select_grid,
+            select_quad,
+            select_color,
+            pick_pixel: picking.and_then(|p| p.pixel).unwrap_or_default(),
+            color_scale: (-view.color_grading.global.exposure).exp2(),
+            scale_factor,
+        }
+    }
+
+    /// true if the view can be passed in push constants on this device,
+    /// instead of a uniform buffer; the shaders are built with
+    /// `VIEW_PUSH_CONSTANTS`
+    pub(crate) fn push_constants(render_device: &RenderDevice) -> bool {
+        render_device
+            .features()
+            .contains(WgpuFeatures::PUSH_CONSTANTS)
+            && render_device.limits().max_push_constant_size as u64 >= Self::min_size().get()
+    }
+
+    /// push constant range holding the view, visible to `stages`
+    pub(crate) fn push_constant_range(stages: ShaderStages) -> PushConstantRange {
+        PushConstantRange {
+            stages,
+            range: 0..Self::min_size().get() as u32,
+        }
+    }
+
+    /// the view as push constant bytes; laid out as in a uniform buffer
+    fn to_push_constants(self) -> Vec<u8> {
+        let mut buffer = encase::UniformBuffer::new(Vec::new());
+        buffer
+            .write(&self)
+            .expect("ViewUniform should fit in a growable buffer");
+        buffer.into_inner()
+    }
+}
+
+// HexGridConfig & grid transform as passed to the shader
+#[derive(Debug, ShaderType, Default, Clone, Copy)]
+struct GridUniform {
+    // grid plane local to world transform
+    transform: Mat4,
+    inverse_transform: Mat4,
+    line_color: Vec4,
+    cursor_color: Vec4,
+    cursor_edge_color: Vec4,
+    // transparent when parity tinting is disabled
+    parity_colors: [Vec4; 3],
+    size: f32,
+    // 0 = pointy, 1 = flat
+    orientation: u32,
+    // fraction of the cell size; unused when line_width_pixels is set
+    line_width: f32,
+    cursor_line_width: f32,
+    cursor_edge_distance: f32,
+    // main world grid entity index, compared against ViewUniform::cursor_grid
+    id: u32,
+    // cell stored in texel (0, 0) of the baked highlights
+    baked_origin: IVec2,
+    // cell stored in texel (0, 0) of the owner texture
+    owners_origin: IVec2,
+    // HexGridStencil; only used with STENCIL_TEST
+    stencil_reference: u32,
+    stencil_compare: u32,
+    // HexGridPartHighlights
+    edge_width: f32,
+    vertex_radius: f32,
+    // LineWidthMode::Pixels in logical pixels, 0 otherwise
+    line_width_pixels: f32,
+    // HexWrap width & height, 0 when not wrapping
+    wrap: UVec2,
+    // SuperHexBorders; radius 0 when not drawn
+    superhex_color: Vec4,
+    superhex_radius: u32,
+    superhex_width: f32,
+    // HexGridNetworks strokes; only used with PART_HIGHLIGHTS
+    river_color: Vec4,
+    river_border_color: Vec4,
+    road_color: Vec4,
+    road_border_color: Vec4,
+    river_width: f32,
+    river_border_width: f32,
+    road_width: f32,
+    road_border_width: f32,
+    // HexGridTerrain; only used with TERRAIN
+    terrain_blend_width: f32,
+    // HexGridElevation; only used with ELEVATION
+    cliff_color: Vec4,
+    cliff_shadow_color: Vec4,
+    cliff_width: f32,
+    cliff_shadow_width: f32,
+    cliff_threshold: f32,
+    ramp_color: Vec4,
+    // HexGridDistanceRings; count 0 when not drawn
+    rings_center: IVec2,
+    rings_count: u32,
+    rings_near_color: Vec4,
+    rings_far_color: Vec4,
+    // HexGridHeatmap; only used with HEATMAP
+    heat_colors: [Vec4; HexGridHeatmap::MAX_COLORS],
+    heat_count: u32,
+    heat_alpha: f32,
+    // values at the ends of the gradient
+    heat_min: f32,
+    heat_max: f32,
+    // cell stored in texel (0, 0) of the heatmap texture
+    heat_origin: IVec2,
+    // GridTime::elapsed_secs
+    time: f32,
+    // HexGridBorders of each BorderLayer, width 0 when not drawn; only used
+    // with BORDERS
+    border_colors: [Vec4; 3],
+    border_widths: Vec4,
+    // EdgeStroke of each HexGridPartHighlights group, as (pattern, width,
+    // period, speed); only used with PART_HIGHLIGHTS
+    edge_strokes: [Vec4; HexGridPartHighlights::MAX_STROKES],
+    // half the widest edge stroke
+    edge_reach: f32,
+    // HexGridFills kinds, as their color & (pattern, spacing, width, angle);
+    // only used with FILLS
+    fill_colors: [Vec4; HexGridFills::MAX_KINDS],
+    fill_params: [Vec4; HexGridFills::MAX_KINDS],
+    // ColorblindPreset::team_fill of the first teams, packed as the fills
+    // above; only used with TEAM_FILLS
+    team_fill_colors: [Vec4; OKABE_ITO.len()],
+    team_fill_params: [Vec4; OKABE_ITO.len()],
+}
+
+impl GridUniform {
+    fn new(entity: Entity, config: &HexGridConfig, transform: &GlobalTransform) -> Self {
+        let (scale, _, _) = transform.to_scale_rotation_translation();
+        let (line_width, line_width_pixels) = match config.line_width_mode {
+            LineWidthMode::CellFraction => (config.line_width, 0.0),
+            LineWidthMode::World(width) => (width / (config.size * scale.max_element()), 0.0),
+            LineWidthMode::Pixels(width) => (0.0, width),
+        };
+        let transform = transform.to_matrix();
+        let superhex = config
+            .superhex_borders
+            .filter(|_| config.kind == GridKind::Hex);
+        Self {
+            transform,
+            inverse_transform: transform.inverse(),
+            line_color: config.line_color.to_linear().to_vec4(),
+            cursor_color: config.cursor_color.to_linear().to_vec4(),
+            cursor_edge_color: config.cursor_edge_color.to_linear().to_vec4(),
+            parity_colors: config
+                .parity_tint
+                .map(|colors| colors.map(|c| c.to_linear().to_vec4()))
+                .unwrap_or_default(),
+            size: config.size,
+            orientation: match config.orientation {
+                Orientation::Pointy => 0,
+                Orientation::Flat => 1,
+            },
+            line_width,
+            cursor_line_width: config.cursor_line_width,
+            cursor_edge_distance: config.cursor_edge_distance,
+            id: entity.index_u32(),
+            baked_origin: IVec2::ZERO,
+            owners_origin: IVec2::ZERO,
+            stencil_reference: config.stencil.map_or(0, |s| s.reference as u32),
+            stencil_compare: config.stencil.map_or(0, |s| s.compare as u32),
+            edge_width: 0.0,
+            vertex_radius: 0.0,
+            line_width_pixels,
+            wrap: config
+                .wrap
+                .filter(|_| config.kind == GridKind::Hex)
+                .map_or(UVec2::ZERO, |w| UVec2::new(w.width(), w.height())),
+            superhex_color: superhex.map_or(Vec4::ZERO, |s| s.color.to_linear().to_vec4()),
+            superhex_radius: superhex.map_or(0, |s| s.radius),
+            superhex_width: superhex.map_or(0.0, |s| s.width),
+            river_color: Vec4::ZERO,
+            river_border_color: Vec4::ZERO,
+            road_color: Vec4::ZERO,
+            road_border_color: Vec4::ZERO,
+            river_width: 0.0,
+            river_border_width: 0.0,
+            road_width: 0.0,
+            road_border_width: 0.0,
+            terrain_blend_width: 0.0,
+            cliff_color: Vec4::ZERO,
+            cliff_shadow_color: Vec4::ZERO,
+            cliff_width: 0.0,
+            cliff_shadow_width: 0.0,
+            cliff_threshold: 0.0,
+            ramp_color: Vec4::ZERO,
+            rings_center: IVec2::ZERO,
+            rings_count: 0,
+            rings_near_color: Vec4::ZERO,
+            rings_far_color: Vec4::ZERO,
+            heat_colors: [Vec4::ZERO; HexGridHeatmap::MAX_COLORS],
+            heat_count: 0,
+            heat_alpha: 0.0,
+            heat_min: 0.0,
+            heat_max: 0.0,
+            heat_origin: IVec2::ZERO,
+            time: 0.0,
+            border_colors: [Vec4::ZERO; 3],
+            border_widths: Vec4::ZERO,
+            edge_strokes: [Vec4::ZERO; HexGridPartHighlights::MAX_STROKES],
+            edge_reach: 0.0,
+            fill_colors: [Vec4::ZERO; HexGridFills::MAX_KINDS],
+            fill_params: [Vec4::ZERO; HexGridFills::MAX_KINDS],
+            team_fill_colors: [Vec4::ZERO; OKABE_ITO.len()],
+            team_fill_params: [Vec4::ZERO; OKABE_ITO.len()],
+        }
+    }
+
+    fn with_fills(mut self, fills: &HexGridFills) -> Self {
+        for (i, fill) in fills.kinds.iter().take(HexGridFills::MAX_KINDS).enumerate() {
+            self.fill_colors[i] = fill.color.to_linear().to_vec4();
+            self.fill_params[i] = fill_params(fill);
+        }
+        self
+    }
+
+    fn with_team_fills(mut self, preset: ColorblindPreset) -> Self {
+        for team in 0..OKABE_ITO.len() {
+            let Some(fill) = preset.team_fill(team) else {
+                continue;
+            };
+            self.team_fill_colors[team] = fill.color.to_linear().to_vec4();
+            self.team_fill_params[team] = fill_params(&fill);
+        }
+        self
+    }
+
+    fn with_part_highlights(mut self, parts: &HexGridPartHighlights) -> Self {
+        self.edge_width = parts.edge_width;
+        self.vertex_radius = parts.vertex_radius;
+        let mut widest = parts.edge_width;
+        for (gpu, stroke) in self.edge_strokes.iter_mut().zip(&parts.strokes) {
+            *gpu = Vec4::new(
+                stroke.pattern as u32 as f32,
+                stroke.width,
+                stroke.period,
+                stroke.speed,
+            );
+            widest = widest.max(stroke.width);
+        }
+        self.edge_reach = widest * 0.5;
+        self
+    }
+
+    fn with_networks(mut self, networks: &HexGridNetworks) -> Self {
+        let color = |c: Color| c.to_linear().to_vec4();
+        let (river, road) = (networks.river_style, networks.road_style);
+        self.river_color = color(river.color);
+        self.river_border_color = color(river.border_color);
+        self.road_color = color(road.color);
+        self.road_border_color = color(road.border_color);
+        self.river_width = river.width;
+        self.river_border_width = river.border_width;
+        self.road_width = road.width;
+        self.road_border_width = road.border_width;
+        self
+    }
+
+    fn with_elevation(mut self, elevation: &HexGridElevation) -> Self {
+        let cliff = elevation.cliff;
+        self.cliff_color = cliff.color.to_linear().to_vec4();
+        self.cliff_shadow_color = cliff.shadow_color.to_linear().to_vec4();
+        self.cliff_width = cliff.width;
+        self.cliff_shadow_width = cliff.shadow_width;
+        self.cliff_threshold = elevation.threshold;
+        self.ramp_color = cliff.ramp_color.to_linear().to_vec4();
+        self
+    }
+
+    fn with_heatmap(mut self, heatmap: &HexGridHeatmap) -> Self {
+        for (dst, color) in self.heat_colors.iter_mut().zip(&heatmap.gradient) {
+            *dst = color.to_linear().to_vec4();
+            self.heat_count += 1;
+        }
+        self.heat_alpha = heatmap.alpha;
+        self
+    }
+
+    fn with_borders(mut self, borders: &HexGridBorders) -> Self {
+        for (i, layer) in BorderLayer::ALL.into_iter().enumerate() {
+            let Some(style) = borders.style(layer) else {
+                continue;
+            };
+            self.border_colors[i] = style.color.to_linear().to_vec4();
+            self.border_widths[i] = style.width;
+        }
+        self
+    }
+
+    fn with_rings(mut self, rings: &HexGridDistanceRings) -> Self {
+        let Some(center) = rings.center else {
+            return self;
+        };
+        self.rings_center = center.into();
+        self.rings_count = rings.count;
+        self.rings_near_color = rings.near_color.to_linear().to_vec4();
+        self.rings_far_color = rings.far_color.to_linear().to_vec4();
+        self
+    }
+}
+
+// CellFill pattern as passed to the shader
+fn fill_params(fill: &CellFill) -> Vec4 {
+    Vec4::new(
+        fill.pattern as u32 as f32,
+        fill.spacing,
+        fill.width,
+        fill.angle,
+    )
+}
+
+// HexGridPalette as passed to the shader
+#[derive(Debug, ShaderType, Clone, Copy)]
+struct GpuPalette {
+    border_width: f32,
+    count: u32,
+    colors: [Vec4; HexGridPalette::MAX_TEAMS],
+}
+
+impl Default for GpuPalette {
+    fn default() -> Self {
+        Self {
+            border_width: 0.0,
+            count: 0,
+            colors: [Vec4::ZERO; HexGridPalette::MAX_TEAMS],
+        }
+    }
+}
+
+impl From<&HexGridPalette> for GpuPalette {
+    fn from(palette: &HexGridPalette) -> Self {
+        let mut gpu = Self {
+            border_width: palette.border_width,
+            ..default()
+        };
+        for (dst, color) in gpu.colors.iter_mut().zip(&palette.colors) {
+            *dst = color.to_linear().to_vec4();
+            gpu.count += 1;
+        }
+        gpu
+    }
+}
+
+// Weather as passed to the shader
+#[derive(Debug, ShaderType, Default, Clone, Copy)]
+struct GpuWeather {
+    color: Vec4,
+    pattern: u32,
+    intensity: f32,
+    scale: f32,
+    speed: f32,
+}
+
+// HexGridWeather kinds as passed to the shader
+#[derive(Debug, ShaderType, Default, Clone, Copy)]
+struct GpuWeathers {
+    kinds: [GpuWeather; HexGridWeather::MAX_KINDS],
+}
+
+impl From<&HexGridWeather> for GpuWeathers {
+    fn from(weather: &HexGridWeather) -> Self {
+        let mut gpu = Self::default();
+        for (dst, kind) in gpu.kinds.iter_mut().zip(&weather.kinds) {
+            *dst = GpuWeather {
+                color: kind.color.to_linear().to_vec4(),
+                pattern: kind.pattern as u32,
+                intensity: kind.intensity,
+                scale: kind.scale,
+                speed: kind.speed,
+            };
+        }
+        gpu
+    }
+}
+
+// Decal as passed to the shader
+#[derive(Debug, ShaderType, Clone, Copy)]
+struct GpuDecal {
+    cell: IVec2,
+    uv_min: Vec2,
+    uv_max: Vec2,
+    color: Vec4,
+    rotation: f32,
+    scale: f32,
+}
+
+// decals for a grid, sorted by (r, q) so the shader can binary search them,
+// followed by the textures of its HexGridTerrain kinds
+#[derive(Debug, ShaderType, Default)]
+struct GpuDecals {
+    count: u32,
+    terrains: u32,
+    // never empty; storage buffers can't be zero sized
+    #[shader(size(runtime))]
+    decals: Vec<GpuDecal>,
+}
+
+// HexGridHoles as passed to the shader
+#[derive(Debug, ShaderType, Default)]
+struct GpuHoles {
+    min: IVec2,
+    // width & height of the bitset; zero without holes
+    size: IVec2,
+    // never empty
+    #[shader(size(runtime))]
+    bits: Vec<u32>,
+}
+
+#[derive(Debug, ShaderType, Default, Clone, Copy)]
+struct GpuHighlight {
+    cell: IVec2,
+    // 0 for empty slots
+    occupied: u32,
+    color: Vec4,
+}
+
+// hash of a cell in the highlight table; must match `cell_hash()` in the
+// shader
+fn cell_hash(cell: IVec2) -> u32 {
+    hash_cell(cell.into(), 0)
+}
+
+// Highlights for a grid, culled to a single view.  Stored as an open
+// addressing hash table with linear probing; the table is at most half full,
+// so probes stay short and always end at an empty slot.
+#[derive(Debug, ShaderType, Default)]
+struct GpuHighlights {
+    // slot count - 1; the slot count is a power of two
+    mask: u32,
+    #[shader(size(runtime))]
+    slots: Vec<GpuHighlight>,
+}
+
+impl GpuHighlights {
+    fn build(&mut self, highlights: &[(IVec2, Vec4)]) {
+        self.build_keyed(highlights.iter().map(|&(cell, color)| (cell, 1, color)));
+    }
+
+    // build a table where a cell may have several entries, told apart by a
+    // non-zero key stored in `occupied`
+    fn build_keyed(&mut self, entries: impl ExactSizeIterator<Item = (IVec2, u32, Vec4)>) {
+        let len = (entries.len() * 2).next_power_of_two();
+        self.mask = len as u32 - 1;
+        self.slots.clear();
+        self.slots.resize(len, GpuHighlight::default());
+
+        for (cell, key, color) in entries {
+            let mut slot = cell_hash(cell) & self.mask;
+            while self.slots[slot as usize].occupied != 0 {
+                slot = (slot + 1) & self.mask;
+            }
+            self.slots[slot as usize] = GpuHighlight {
+                cell,
+                occupied: key,
+                color,
+            };
+        }
+    }
+}
+
+/// grid extracted from the main world, on its render world entity.  Removed
+/// while the grid is hidden.
+#[derive(Component)]
+pub(crate) struct ExtractedHexGrid {
+    uniform: GridUniform,
+    key: HexGridPipelineKey,
+    pub(crate) layers: RenderLayers,
+    decals: Vec<GpuDecal>,
+    // HexGridTerrain kinds, as decals without a cell
+    terrains: Vec<GpuDecal>,
+    palette: GpuPalette,
+    weathers: GpuWeathers,
+    // HexGridPartHighlights, HexGridNetworks, HexGridTerrain, HexGridElevation,
+    // HexGridWeather, HexGridGlow, & HexGridFills as (canonical cell, key,
+    // color); see `part_highlights` in the shader
+    parts: Vec<(IVec2, u32, Vec4)>,
+    holes: Option<HexGridHoles>,
+    // culled per view in prepare
+    pub(crate) config: HexGridConfig,
+    plane: HexGridPlane,
+    highlights: Option<HexGridHighlights>,
+    pub(crate) bake: ExtractedBake,
+    /// width of the grid's glow in pixels, if it has one
+    pub(crate) glow_width: Option<f32>,
+}
+
+/// packed decal atlas image, if it has been built
+#[derive(Resource, Default)]
+pub(crate) struct ExtractedDecalAtlas(Option<Handle<Image>>);
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub(crate) fn extract_hex_grids(
+    mut commands: Commands,
+    // grids with baked highlights, with the version of the highlights last
+    // sent & the bounds of the texture
+    mut baked: Local<HashMap<Entity, (HexMapVersion, HexBounds)>>,
+    grids: Extract<
+        Query<(
+            Entity,
+            RenderEntity,
+            &HexGridConfig,
+            &GlobalTransform,
+            &InheritedVisibility,
+            Option<&RenderLayers>,
+            Option<&HexGridDecals>,
+            Option<Ref<HexGridHighlights>>,
+            Option<Ref<HexGridBakeHighlights>>,
+            Option<&HexGridPalette>,
+            Option<&HexGridPartHighlights>,
+            Option<&HexGridNetworks>,
+            (
+                Option<&HexGridTerrain>,
+                Option<&HexGridHoles>,
+                Option<&HexGridElevation>,
+                Option<&HexGridWeather>,
+                Option<&HexGridDistanceRings>,
+                Option<&HexGridHeatmap>,
+            ),
+            (
+                Option<&HexGridStyleHandle>,
+                Option<&HexGridThemeFade>,
+                Option<&HexGridBorders>,
+                Option<&HexGridGlow>,
+                Option<&HexGridFills>,
+            ),
+        )>,
+    >,
+    atlas: Extract<Res<HexGridDecalAtlas>>,
+    time: Extract<Res<GridTime>>,
+    styles: Extract<Res<Assets<HexGridStyle>>>,
+    themes: Extract<Res<HexGridThemes>>,
+    render_device: Res<RenderDevice>,
+) {
+    let max_texture_size = render_device.limits().max_texture_dimension_2d as i32;
+    let prev_baked = std::mem::take(&mut *baked);
+
+    // grids are not frustum culled; the plane is infinite
+    for (
+        entity,
+        render_entity,
+        config,
+        transform,
+        visibility,
+        layers,
+        decals,
+        highlights,
+        bake,
+        palette,
+        part_highlights,
+        networks,
+        (terrain, holes, elevation, weather, rings, heatmap),
+        (style, fade, borders, glow, fills),
+    ) in &grids
+    {
+        if !visibility.get() {
+            commands.entity(render_entity).remove::<ExtractedHexGrid>();
+            continue;
+        }
+
+        // the theme & style are looked up every frame, so a reloaded style
+        // is drawn as soon as it's changed
+        let style = style.and_then(|s| styles.get(&s.0));
+        let config = themed_config(config, &themes, fade, style);
+        let config = &*config;
+
+        let mut gpu_decals: Vec<GpuDecal> = decals
+            .iter()
+            .flat_map(|decals| decals.0.iter())
+            .filter_map(|(cell, decal)| {
+                let rect = atlas.rect(decal.id)?;
+                Some(GpuDecal {
+                    cell: cell.into(),
+                    uv_min: rect.min,
+                    uv_max: rect.max,
+                    color: decal.color.to_linear().to_vec4(),
+                    rotation: decal.rotation,
+                    scale: decal.scale,
+                })
+            })
+            .collect();
+        gpu_decals.sort_unstable_by_key(|d| (d.cell.y, d.cell.x));
+
+        // baked highlights are only sent when they change, and then only the
+        // changed chunks if they still fit in the texture; highlights that
+        // don't fit in a texture fall back to the per-view hash table
+        let highlights = highlights.filter(|h| !h.0.is_empty());
+        let (bake, highlights) = match (bake, highlights) {
+            (Some(bake), Some(highlights)) => {
+                let changed = bake.is_changed() || highlights.is_changed();
+                let prev = prev_baked.get(&entity).copied();
+                let partial = || {
+                    let (version, texture) = prev.filter(|_| !bake.is_changed())?;
+                    Some((changed_cells(&highlights.0, version, texture)?, texture))
+                };
+                let bounds = || {
+                    highlights
+                        .0
+                        .bounds()
+                        .filter(|b| b.size().max_element() <= max_texture_size)
+                };
+                if let Some(prev) = prev.filter(|_| !changed) {
+                    baked.insert(entity, prev);
+                    (ExtractedBake::Unchanged, None)
+                } else if let Some((cells, texture)) = partial() {
+                    baked.insert(entity, (highlights.0.version(), texture));
+                    if cells.is_empty() {
+                        (ExtractedBake::Unchanged, None)
+                    } else {
+                        (ExtractedBake::Partial(cells), None)
+                    }
+                } else if let Some(texture) = bounds() {
+                    baked.insert(entity, (highlights.0.version(), texture));
+                    (ExtractedBake::Changed((*highlights).clone()), None)
+                } else {
+                    if changed {
+                        warn!(
+                            "highlights on grid {:?} are too spread out to bake; \
+                             max texture size is {}",
+                            entity, max_texture_size
+                        );
+                    }
+                    (ExtractedBake::None, Some((*highlights).clone()))
+                }
+            }
+            (_, highlights) => (ExtractedBake::None, highlights.map(|h| (*h).clone())),
+        };
+
+        // edges & corners are only drawn on hex grids
+        let part_highlights = part_highlights.filter(|_| config.kind == GridKind::Hex);
+        let mut parts: Vec<_> = part_highlights
+            .iter()
+            .flat_map(|p| {
+                let edges = p.edges.iter().map(|(edge, color)| {
+                    let group = p
+                        .edge_groups
+                        .get(edge)
+                        .filter(|&&g| g < p.strokes.len().min(HexGridPartHighlights::MAX_STROKES))
+                        .map_or(0, |&g| g as u32 + 1);
+                    let key = (1 + edge.dir() as u32) | (group << STROKE_SHIFT);
+                    (edge.cell(), key, color)
+                });
+                let vertices = p
+                    .vertices
+                    .iter()
+                    .map(|(vertex, color)| (vertex.cell(), 4 + vertex.corner() as u32, color));
+                edges.chain(vertices)
+            })
+            .map(|(cell, key, color)| (cell.into(), key, color.to_linear().to_vec4()))
+            .collect();
+
+        // rivers & roads share the table, as the masks of the canonical
+        // edges on each cell
+        let networks = networks.filter(|n| config.kind == GridKind::Hex && !n.is_empty());
+        if let Some(networks) = networks {
+            let mut masks: HexMap<[u8; 2]> = HexMap::new();
+            for (i, set) in [&networks.rivers, &networks.roads].into_iter().enumerate() {
+                for (cell, mask) in set.masks() {
+                    match masks.get_mut(cell) {
+                        Some(m) => m[i] = mask,
+                        None => {
+                            let mut m = [0; 2];
+                            m[i] = mask;
+                            masks.insert(cell, m);
+                        }
+                    }
+                }
+            }
+            parts.extend(masks.iter().map(|(cell, &[rivers, roads])| {
+                let value = Vec4::new(rivers as f32, roads as f32, 0.0, 0.0);
+                (IVec2::from(cell), NETWORK_KEY, value)
+            }));
+        }
+
+        // terrain of each cell as (kind + 1, priority, variant kind + 1,
+        // variant rotation), with a variant kind of 0 for none; cells of
+        // unknown kinds are left out
+        let mut terrains = Vec::new();
+        if let Some(terrain) = terrain {
+            terrains.extend(terrain.kinds.iter().map(|kind| {
+                let rect = atlas.rect(kind.texture).unwrap_or_default();
+                GpuDecal {
+                    cell: IVec2::ZERO,
+                    uv_min: rect.min,
+                    uv_max: rect.max,
+                    color: kind.color.to_linear().to_vec4(),
+                    rotation: 0.0,
+                    scale: kind.scale,
+                }
+            }));
+            parts.extend(terrain.cells.iter().filter_map(|(cell, id)| {
+                let kind = terrain.kinds.get(id.0 as usize)?;
+                let (variant, rotation) = terrain
+                    .variants
+                    .get(cell)
+                    .filter(|v| (v.kind.0 as usize) < terrain.kinds.len())
+                    .map_or((0.0, 0.0), |v| {
+                        (v.kind.0 as f32 + 1.0, (v.rotation % 6) as f32)
+                    });
+                let value = Vec4::new(id.0 as f32 + 1.0, kind.priority as f32, variant, rotation);
+                Some((IVec2::from(cell), TERRAIN_KEY, value))
+            }));
+        }
+        // height of each cell as (height, 1, ramp direction + 1); the 1
+        // tells a height of 0 from a cell without one, and the ramp is 0
+        // without one
+        let elevation = elevation.filter(|e| !e.is_empty());
+        if let Some(elevation) = elevation {
+            let mut cells: HexMap<Vec4> = elevation
+                .cells
+                .iter()
+                .map(|(cell, &height)| (cell, Vec4::new(height, 1.0, 0.0, 0.0)))
+                .collect();
+            for (cell, dir) in elevation.ramps.iter() {
+                cells.get_or_insert_with(cell, || Vec4::ZERO).z = dir.index() as f32 + 1.0;
+            }
+            parts.extend(
+                cells
+                    .iter()
+                    .map(|(cell, &value)| (IVec2::from(cell), ELEVATION_KEY, value)),
+            );
+        }
+        // weather of each cell as (kind + 1); cells of unknown kinds are
+        // left out
+        let weather = weather.filter(|w| !w.cells.is_empty() && !w.kinds.is_empty());
+        if let Some(weather) = weather {
+            let kinds = weather.kinds.len().min(HexGridWeather::MAX_KINDS);
+            parts.extend(
+                weather
+                    .cells
+                    .iter()
+                    .filter(|(_, id)| (id.0 as usize) < kinds)
+                    .map(|(cell, id)| {
+                        let value = Vec4::new(id.0 as f32 + 1.0, 0.0, 0.0, 0.0);
+                        (IVec2::from(cell), WEATHER_KEY, value)
+                    }),
+            );
+        }
+        // fill of each cell as (kind + 1); cells of unknown kinds are left
+        // out
+        let fills = fills.filter(|f| !f.cells.is_empty() && !f.kinds.is_empty());
+        if let Some(fills) = fills {
+            let kinds = fills.kinds.len().min(HexGridFills::MAX_KINDS);
+            parts.extend(
+                fills
+                    .cells
+                    .iter()
+                    .filter(|(_, id)| (id.0 as usize) < kinds)
+                    .map(|(cell, id)| {
+                        let value = Vec4::new(id.0 as f32 + 1.0, 0.0, 0.0, 0.0);
+                        (IVec2::from(cell), FILL_KEY, value)
+                    }),
+            );
+        }
+        // glow of each cell as (color * intensity, width); read by the
+        // glow seed pass
+        let glow = glow.filter(|g| !g.cells.is_empty() && g.clamped_width() > 0.0);
+        if let Some(glow) = glow {
+            let width = glow.clamped_width();
+            parts.extend(glow.cells.iter().map(|(cell, color)| {
+                let color = color.to_linear();
+                let rgb = color.to_vec3() * color.alpha * glow.intensity;
+                (IVec2::from(cell), GLOW_KEY, rgb.extend(width))
+            }));
+        }
+        let mut uniform = GridUniform {
+            time: time.elapsed_secs(),
+            ..GridUniform::new(entity, config, transform)
+        };
+        if let Some(part_highlights) = part_highlights {
+            uniform = uniform.with_part_highlights(part_highlights);
+        }
+        if let Some(networks) = networks {
+            uniform = uniform.with_networks(networks);
+        }
+        if let Some(terrain) = terrain {
+            uniform.terrain_blend_width = terrain.blend_width;
+        }
+        if let Some(elevation) = elevation {
+            uniform = uniform.with_elevation(elevation);
+        }
+        if let Some(heatmap) = heatmap {
+            uniform = uniform.with_heatmap(heatmap);
+        }
+        if let Some(fills) = fills {
+            uniform = uniform.with_fills(fills);
+        }
+        // team patterns are only drawn over owned cells, so need a palette
+        let team_fills = palette
+            .and(config.colorblind)
+            .filter(|p| p.team_fill(0).is_some());
+        if let Some(preset) = team_fills {
+            uniform = uniform.with_team_fills(preset);
+        }
+        let borders = borders.filter(|b| b.is_drawn());
+        if let Some(borders) = borders {
+            uniform = uniform.with_borders(borders);
+        }
+        // rings are only drawn on hex grids
+        if let Some(rings) = rings.filter(|_| config.kind == GridKind::Hex) {
+            uniform = uniform.with_rings(rings);
+        }
+        let holes = holes.filter(|h| !h.is_empty()).cloned();
+
+        commands.entity(render_entity).insert(ExtractedHexGrid {
+            uniform,
+            key: HexGridPipelineKey {
+                kind: config.kind,
+                antialiasing: config.antialiasing,
+                // set in queue, once the bake exists
+                baked_highlights: false,
+                write_depth: config.write_depth,
+                stencil: config.stencil.is_some(),
+                // set in queue, once the owner & heatmap textures exist
+                territory: false,
+                heatmap: false,
+                terrain: !terrains.is_empty(),
+                elevation: elevation.is_some(),
+                weather: weather.is_some(),
+                fills: fills.is_some(),
+                team_fills: team_fills.is_some(),
+                part_highlights: !parts.is_empty(),
+                holes: holes.is_some(),
+                borders: borders.is_some(),
+                picking: false,
+                glow_seed: false,
+                // set in queue, for each view
+                hdr: false,
+                untonemap: None,
+            },
+            layers: layers.cloned().unwrap_or_default(),
+            decals: gpu_decals,
+            terrains,
+            palette: palette
+                .map(|palette| match config.colorblind {
+                    Some(preset) => GpuPalette::from(&preset.palette(palette)),
+                    None => GpuPalette::from(palette),
+                })
+                .unwrap_or_default(),
+            weathers: weather.map(GpuWeathers::from).unwrap_or_default(),
+            parts,
+            holes,
+            config: config.clone(),
+            plane: HexGridPlane::from(*transform),
+            highlights,
+            bake,
+            glow_width: glow.map(|g| g.clamped_width()),
+        });
+    }
+    commands.insert_resource(ExtractedDecalAtlas(atlas.image().cloned()));
+}
+
+/// Physical pixels per [`LineWidthMode::Pixels`] pixel of a view; the scale
+/// factor of its render target, times the [`UiScale`], so pixel widths match
+/// the ui on hi-dpi displays
+#[derive(Component, Debug, Clone, Copy)]
+pub(crate) struct ViewHexGridScale(f32);
+
+pub(crate) fn extract_hex_grid_view_scales(
+    mut commands: Commands,
+    cameras: Extract<Query<(RenderEntity, &Camera), With<HexGridCursor>>>,
+    ui_scale: Extract<Option<Res<UiScale>>>,
+) {
+    let ui_scale = ui_scale.as_ref().map_or(1.0, |s| s.0);
+    for (render_entity, camera) in &cameras {
+        let scale = camera.target_scaling_factor().unwrap_or(1.0) * ui_scale;
+        commands
+            .entity(render_entity)
+            .insert(ViewHexGridScale(scale));
+    }
+}
+
+#[derive(Default)]
+struct GridBuffers {
+    uniform: UniformBuffer<GridUniform>,
+    decals: StorageBuffer<GpuDecals>,
+    palette: UniformBuffer<GpuPalette>,
+    parts: StorageBuffer<GpuHighlights>,
+    holes: StorageBuffer<GpuHoles>,
+    weathers: UniformBuffer<GpuWeathers>,
+}
+
+/// Two copies of buffers written every frame, used on alternate frames.
+/// Writing this frame's copy never has to wait on the gpu reading last
+/// frame's, and the commands submitted last frame never see a half written
+/// buffer.
+#[derive(Default)]
+struct FrameBuffers<T>([T; 2]);
+
+impl<T> FrameBuffers<T> {
+    fn get(&self, frame: usize) -> &T {
+        &self.0[frame % 2]
+    }
+
+    fn get_mut(&mut self, frame: usize) -> &mut T {
+        &mut self.0[frame % 2]
+    }
+}
+
+/// buffers for extracted grids & views, keyed by render world entity.
+/// Buffers are reused while the entity keeps being extracted, and dropped the
+/// first frame it isn't.
+#[derive(Resource, Default)]
+pub(crate) struct HexGridBuffers {
+    /// selects the copy of each [`FrameBuffers`] in use this frame
+    frame: usize,
+    grids: HashMap<Entity, FrameBuffers<GridBuffers>>,
+    views: HashMap<Entity, FrameBuffers<UniformBuffer<ViewUniform>>>,
+    /// pushed instead of `views` when [`ViewUniform::push_constants`]
+    view_constants: HashMap<Entity, Vec<u8>>,
+    // keyed by (view, grid)
+    highlights: HashMap<(Entity, Entity), FrameBuffers<StorageBuffer<GpuHighlights>>>,
+}
+
+impl HexGridBuffers {
+    /// this frame's view uniform
+    pub(crate) fn view(&self, entity: Entity) -> Option<&UniformBuffer<ViewUniform>> {
+        Some(self.views.get(&entity)?.get(self.frame))
+    }
+
+    /// view as push constants, on devices that support them
+    pub(crate) fn view_constants(&self, entity: Entity) -> Option<&[u8]> {
+        self.view_constants.get(&entity).map(Vec::as_slice)
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub(crate) fn prepare_hex_grids(
+    mut buffers: ResMut<HexGridBuffers>,
+    pipeline: Res<HexGridPipeline>,
+    mut visible: Local<Vec<(IVec2, Vec4)>>,
+    (bakes, owners, heats): (
+        Res<HexGridBakes>,
+        Res<HexGridOwnerTextures>,
+        Res<HexGridHeatTextures>,
+    ),
+    grids: Query<(Entity, &ExtractedHexGrid)>,
+    views: Query<(
+        Entity,
+        &ExtractedView,
+        &HexGridCursor,
+        Option<&HexGridBoxSelect>,
+        Option<&HexGridGpuPicking>,
+        Option<&ViewHexGridScale>,
+    )>,
+    atlas: Res<ExtractedDecalAtlas>,
+    images: Res<RenderAssets<GpuImage>>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    // decals can't be drawn until the atlas is on the gpu
+    let atlas_ready = atlas.0.as_ref().is_some_and(|h| images.get(h).is_some());
+
+    let buffers = &mut *buffers;
+    buffers.frame = buffers.frame.wrapping_add(1);
+    let frame = buffers.frame;
+    buffers.grids.retain(|entity, _| grids.contains(*entity));
+    for (entity, grid) in &grids {
+        let buffer = buffers.grids.entry(entity).or_default().get_mut(frame);
+        let heat = heats.0.get(&entity);
+        let (heat_min, heat_max) = heat.map_or((0.0, 0.0), |h| h.range);
+        buffer.uniform.set(GridUniform {
+            baked_origin: bakes.0.get(&entity).map_or(IVec2::ZERO, |b| b.origin),
+            owners_origin: owners
+                .0
+                .get(&entity)
+                .map_or(IVec2::ZERO, |o| o.bounds.min.into()),
+            heat_origin: heat.map_or(IVec2::ZERO, |h| h.bounds.min.into()),
+            heat_min,
+            heat_max,
+            ..grid.uniform
+        });
+        buffer.uniform.write_buffer(&render_device, &render_queue);
+        buffer.palette.set(grid.palette);
+        buffer.palette.write_buffer(&render_device, &render_queue);
+        buffer.weathers.set(grid.weathers);
+        buffer.weathers.write_buffer(&render_device, &render_queue);
+        buffer
+            .parts
+            .get_mut()
+            .build_keyed(grid.parts.iter().copied());
+        buffer.parts.write_buffer(&render_device, &render_queue);
+
+        let decals = buffer.decals.get_mut();
+        decals.decals.clear();
+        if atlas_ready {
+            decals.decals.extend_from_slice(&grid.decals);
+        }
+        decals.count = decals.decals.len() as u32;
+        if atlas_ready {
+            decals.decals.extend_from_slice(&grid.terrains);
+        }
+        decals.terrains = decals.decals.len() as u32 - decals.count;
+        if decals.decals.is_empty() {
+            decals.decals.push(GpuDecal {
+                cell: IVec2::ZERO,
+                uv_min: Vec2::ZERO,
+                uv_max: Vec2::ZERO,
+                color: Vec4::ZERO,
+                rotation: 0.0,
+                scale: 0.0,
+            });
+        }
+        buffer.decals.write_buffer(&render_device, &render_queue);
+
+        let holes = buffer.holes.get_mut();
+        holes.bits.clear();
+        match &grid.holes {
+            Some(grid_holes) => {
+                let bounds = grid_holes.bounds();
+                holes.min = bounds.min.into();
+                holes.size = bounds.size();
+                holes.bits.extend_from_slice(grid_holes.bits());
+            }
+            None => {
+                holes.size = IVec2::ZERO;
+                holes.bits.push(0);
+            }
+        }
+        buffer.holes.write_buffer(&render_device, &render_queue);
+    }
+
+    buffers.views.retain(|entity, _| views.contains(*entity));
+    buffers.view_constants.clear();
+    for (entity, view, cursor, select, picking, scale) in &views {
+        let scale_factor = scale.map_or(1.0, |s| s.0);
+        let uniform = ViewUniform::new(view, cursor, select, picking, scale_factor);
+        if pipeline.push_constants {
+            buffers
+                .view_constants
+                .insert(entity, uniform.to_push_constants());
+            continue;
+        }
+        let buffer = buffers.views.entry(entity).or_default().get_mut(frame);
+        buffer.set(uniform);
+        buffer.write_buffer(&render_device, &render_queue);
+    }
+
+    buffers
+        .highlights
+        .retain(|(view, grid), _| views.contains(*view) && grids.contains(*grid));
+    for (view_entity, view, ..) in &views {
+        let clip_from_world = view.clip_from_view * view.world_from_view.to_matrix().inverse();
+        let frustum = Frustum::from_clip_from_world(&clip_from_world);
+
+        for (grid_entity, grid) in &grids {
+            let buffer = buffers
+                .highlights
+                .entry((view_entity, grid_entity))
+                .or_default()
+                .get_mut(frame);
+            visible.clear();
+            if let Some(highlights) = &grid.highlights {
+                visible.extend(
+                    highlights
+                        .visible(&grid.config, &grid.plane, &frustum)
+                        .map(|(cell, color)| (cell.into(), color.to_linear().to_vec4())),
+                );
+            }
+            buffer.get_mut().build(&visible);
+            buffer.write_buffer(&render_device, &render_queue);
+        }
+    }
+}
+
+/// pipeline & bind group for each grid drawn by a view, with the picking
+/// pipeline for views with [`HexGridGpuPicking`], and the glow seed pipeline
+/// for grids with a [`HexGridGlow`]; collected every frame from
+/// [`HexGridBindGroupCache`], and removed from views that draw no grids
+#[derive(Component)]
+pub(crate) struct HexGridBindGroups(
+    Vec<(
+        CachedRenderPipelineId,
+        Option<CachedRenderPipelineId>,
+        Option<CachedRenderPipelineId>,
+        BindGroup,
+    )>,
+);
+
+/// a resource bound in a cached bind group
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum BoundId {
+    Buffer(BufferId),
+    Texture(TextureViewId),
+    Sampler(SamplerId),
+}
+
+impl From<&Buffer> for BoundId {
+    fn from(buffer: &Buffer) -> Self {
+        Self::Buffer(buffer.id())
+    }
+}
+
+impl From<&TextureView> for BoundId {
+    fn from(view: &TextureView) -> Self {
+        Self::Texture(view.id())
+    }
+}
+
+impl From<&Sampler> for BoundId {
+    fn from(sampler: &Sampler) -> Self {
+        Self::Sampler(sampler.id())
+    }
+}
+
+/// Bind groups keyed by their layout & the ids of everything bound in them.
+/// Buffers keep their id until they have to grow, so a bind group is only
+/// created when a buffer or texture it binds is recreated.  Bind groups
+/// unused for two frames are dropped; double buffered buffers alternate
+/// between two bind groups.
+#[derive(Resource, Default)]
+pub(crate) struct HexGridBindGroupCache {
+    frame: usize,
+    bind_groups: HashMap<(BindGroupLayoutId, Vec<BoundId>), (BindGroup, usize)>,
+}
+
+impl HexGridBindGroupCache {
+    /// the bind group for `layout` & `ids`, created by `create` if it isn't
+    /// cached
+    pub(crate) fn get_or_create(
+        &mut self,
+        layout: &BindGroupLayout,
+        ids: &[BoundId],
+        create: impl FnOnce() -> BindGroup,
+    ) -> BindGroup {
+        let (bind_group, used) = self
+            .bind_groups
+            .entry((layout.id(), ids.to_vec()))
+            .or_insert_with(|| (create(), 0));
+        *used = self.frame;
+        bind_group.clone()
+    }
+}
+
+/// drop bind groups that weren't used this frame or the last
+pub(crate) fn evict_hex_grid_bind_groups(mut cache: ResMut<HexGridBindGroupCache>) {
+    let frame = cache.frame;
+    cache
+        .bind_groups
+        .retain(|_, (_, used)| frame.wrapping_sub(*used) < 2);
+    cache.frame = frame.wrapping_add(1);
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub(crate) fn queue_hex_grid_bind_groups(
+    mut commands: Commands,
+    pipeline: Res<HexGridPipeline>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<HexGridPipeline>>,
+    pipeline_cache: Res<PipelineCache>,
+    buffers: Res<HexGridBuffers>,
+    mut cache: ResMut<HexGridBindGroupCache>,
+    render_device: Res<RenderDevice>,
+    grids: Query<(Entity, &ExtractedHexGrid)>,
+    views: Query<(
+        Entity,
+        Option<&RenderLayers>,
+        Option<&ViewHexGridStencil>,
+        Has<HexGridGpuPicking>,
+        Has<ViewHexGridGlow>,
+        &ExtractedView,
+        Option<&Tonemapping>,
+    )>,
+    (atlas, images, fallback_image, bakes, owners, heats): (
+        Res<ExtractedDecalAtlas>,
+        Res<RenderAssets<GpuImage>>,
+        Res<FallbackImage>,
+        Res<HexGridBakes>,
+        Res<HexGridOwnerTextures>,
+        Res<HexGridHeatTextures>,
+    ),
+    tonemapping: Res<HexGridTonemapping>,
+) {
+    // grids without decals still need a texture bound
+    let atlas = atlas
+        .0
+        .as_ref()
+        .and_then(|handle| images.get(handle))
+        .unwrap_or(&fallback_image.d2);
+
+    let layout = pipeline_cache.get_bind_group_layout(&pipeline.layout);
+    for (view, view_layers, stencil, picking, glow, extracted_view, view_tonemapping) in &views {
+        let view_buffer = buffers.view(view).and_then(|b| b.buffer());
+        if view_buffer.is_none() && buffers.view_constants(view).is_none() {
+            commands.entity(view).remove::<HexGridBindGroups>();
+            continue;
+        }
+        let view_layers = view_layers.cloned().unwrap_or_default();
+        // only read with STENCIL_TEST, and every view drawing such a grid
+        // has a stencil texture
+        let stencil = stencil.map_or(&pipeline.uint_fallback, |s| &s.texture.default_view);
+        // hdr views are tonemapped after the main pass; the rest in their
+        // material shaders, so the grid is drawn over tonemapped colors
+        let hdr = extracted_view.hdr;
+        let untonemap = (hdr && *tonemapping == HexGridTonemapping::Before)
+            .then(|| view_tonemapping.copied().unwrap_or(Tonemapping::None));
+
+        let bind_groups: Vec<_> = grids
+            .iter()
+            .filter(|(_, grid)| view_layers.intersects(&grid.layers))
+            .filter_map(|(entity, grid)| {
+                let grid_buffers = buffers.grids.get(&entity)?.get(buffers.frame);
+                let highlights = buffers.highlights.get(&(view, entity))?.get(buffers.frame);
+                // the fallback texture is bound when there's no bake; it's
+                // only read with BAKED_HIGHLIGHTS
+                let baked = bakes.0.get(&entity);
+                let owners = owners.0.get(&entity);
+                let heat = heats.0.get(&entity);
+                let key = HexGridPipelineKey {
+                    baked_highlights: baked.is_some(),
+                    territory: owners.is_some(),
+                    heatmap: heat.is_some(),
+                    hdr,
+                    untonemap,
+                    ..grid.key
+                };
+                let pipeline_id = pipelines.specialize(&pipeline_cache, &pipeline, key);
+                // picking & glow seeds only need the cell shape & the
+                // stencil
+                let shape_key = HexGridPipelineKey {
+                    kind: key.kind,
+                    antialiasing: LineAntialiasing::default(),
+                    baked_highlights: false,
+                    write_depth: true,
+                    stencil: key.stencil,
+                    territory: false,
+                    heatmap: false,
+                    terrain: false,
+                    elevation: false,
+                    weather: false,
+                    fills: false,
+                    team_fills: false,
+                    part_highlights: false,
+                    holes: key.holes,
+                    borders: false,
+                    picking: false,
+                    glow_seed: false,
+                    hdr: false,
+                    untonemap: None,
+                };
+                let pick_id = picking.then(|| {
+                    let key = HexGridPipelineKey {
+                        picking: true,
+                        ..shape_key
+                    };
+                    pipelines.specialize(&pipeline_cache, &pipeline, key)
+                });
+                let glow_id = (glow && grid.glow_width.is_some()).then(|| {
+                    let key = HexGridPipelineKey {
+                        glow_seed: true,
+                        ..shape_key
+                    };
+                    pipelines.specialize(&pipeline_cache, &pipeline, key)
+                });
+                let uniform = grid_buffers.uniform.buffer()?;
+                let decals = grid_buffers.decals.buffer()?;
+                let highlights = highlights.buffer()?;
+                let baked = baked.map_or(&fallback_image.d2.texture_view, |b| &b.view);
+                let owners = owners.map_or(&pipeline.uint_fallback, |o| &o.view);
+                let palette = grid_buffers.palette.buffer()?;
+                let parts = grid_buffers.parts.buffer()?;
+                let holes = grid_buffers.holes.buffer()?;
+                let weathers = grid_buffers.weathers.buffer()?;
+                // only read with HEATMAP
+                let heat = heat.map_or(&fallback_image.d2.texture_view, |h| &h.view);
+                // binding 0 is left out of the layout when the view is
+                // pushed; the grid uniform stands in for it, and is skipped
+                let view_buffer = view_buffer.unwrap_or(uniform);
+                let skip = usize::from(pipeline.push_constants);
+                let ids = [
+                    view_buffer.into(),
+                    uniform.into(),
+                    decals.into(),
+                    (&atlas.texture_view).into(),
+                    (&atlas.sampler).into(),
+                    highlights.into(),
+                    baked.into(),
+                    stencil.into(),
+                    owners.into(),
+                    palette.into(),
+                    parts.into(),
+                    holes.into(),
+                    weathers.into(),
+                    heat.into(),
+                ];
+                let bind_group = cache.get_or_create(&layout, &ids[skip..], || {
+                    let entries = BindGroupEntries::sequential((
+                        view_buffer.as_entire_binding(),
+                        uniform.as_entire_binding(),
+                        decals.as_entire_binding(),
+                        &atlas.texture_view,
+                        &atlas.sampler,
+                        highlights.as_entire_binding(),
+                        baked,
+                        stencil,
+                        owners,
+                        palette.as_entire_binding(),
+                        parts.as_entire_binding(),
+                        holes.as_entire_binding(),
+                        weathers.as_entire_binding(),
+                        heat,
+                    ));
+                    render_device.create_bind_group(
+                        "hex_grid_bind_group",
+                        &layout,
+                        &entries[skip..],
+                    )
+                });
+                Some((pipeline_id, pick_id, glow_id, bind_group))
+            })
+            .collect();
+        if bind_groups.is_empty() {
+            commands.entity(view).remove::<HexGridBindGroups>();
+        } else {
+            commands.entity(view).insert(HexGridBindGroups(bind_groups));
+        }
+    }
+}
+
+/// State of the grid render pipeline.  The grid isn't drawn until the shader
+/// has compiled; if compilation fails (for example after hot-reloading a
+/// broken shader) the error is reported here until the shader is fixed.
+#[derive(Resource, Debug, Default, Clone, PartialEq, Eq)]
+pub enum HexGridStatus {
+    /// shader is loading or compiling
+    #[default]
+    Loading,
+    Ready,
+    /// shader failed to compile
+    Error(String),
+}
+
+/// sent whenever [`HexGridStatus`] changes
+#[derive(Message, Debug, Clone, PartialEq, Eq)]
+pub struct HexGridStatusChanged(pub HexGridStatus);
+
+/// pipeline status shared between the render & main worlds
+#[derive(Resource, Debug, Default, Clone)]
+pub(crate) struct SharedHexGridStatus(Arc<Mutex<HexGridStatus>>);
+
+fn pipeline_status(state: &CachedPipelineState) -> HexGridStatus {
+    match state {
+        CachedPipelineState::Queued | CachedPipelineState::Creating(_) => HexGridStatus::Loading,
+        CachedPipelineState::Ok(_) => HexGridStatus::Ready,
+        // the pipeline cache retries these until the shader has loaded
+        CachedPipelineState::Err(
+            PipelineCacheError::ShaderNotLoaded(_)
+            | PipelineCacheError::ShaderImportNotYetAvailable,
+        ) => HexGridStatus::Loading,
+        CachedPipelineState::Err(err) => HexGridStatus::Error(err.to_string()),
+    }
+}
+
+/// combine the state of every pipeline variant in use this frame; the
+/// status is left alone when no grids are drawn
+pub(crate) fn update_hex_grid_status(
+    views: Query<&HexGridBindGroups>,
+    pipeline_cache: Res<PipelineCache>,
+    shared: Res<SharedHexGridStatus>,
+) {
+    let mut status = None;
+    for (id, ..) in views.iter().flat_map(|bind_groups| &bind_groups.0) {
+        let current = pipeline_status(pipeline_cache.get_render_pipeline_state(*id));
+        status = match (status, current) {
+            (Some(HexGridStatus::Error(err)), _) | (_, HexGridStatus::Error(err)) => {
+                Some(HexGridStatus::Error(err))
+            }
+            (Some(HexGridStatus::Loading), _) | (_, HexGridStatus::Loading) => {
+                Some(HexGridStatus::Loading)
+            }
+            _ => Some(HexGridStatus::Ready),
+        };
+    }
+    if let Some(status) = status {
+        *shared.0.lock().unwrap() = status;
+    }
+}
+
+pub(crate) fn sync_hex_grid_status(
+    shared: Res<SharedHexGridStatus>,
+    mut status: ResMut<HexGridStatus>,
+    mut events: MessageWriter<HexGridStatusChanged>,
+) {
+    let current = shared.0.lock().unwrap().clone();
+    if *status == current {
+        return;
+    }
+    match &current {
+        HexGridStatus::Error(err) => error!("hex grid shader failed to compile: {}", err),
+        HexGridStatus::Ready => info!("hex grid shader ready"),
+        HexGridStatus::Loading => (),
+    }
+    *status = current.clone();
+    events.write(HexGridStatusChanged(current));
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+pub(crate) struct HexGridLabel;
+
+#[derive(Debug, Default)]
+pub(crate) struct HexGridRenderNode;
+
+impl ViewNode for HexGridRenderNode {
+    type ViewQuery = (
+        &'static ExtractedCamera,
+        &'static ViewTarget,
+        &'static ViewDepthTexture,
+        &'static HexGridBindGroups,
+        Option<&'static ViewHexGridStencil>,
+        Option<&'static HexGridGpuPicking>,
+        Option<&'static ViewHexGridGlow>,
+    );
+
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (camera, view_target, depth, bind_groups, stencil, picking, glow): QueryItem<
+            Self::ViewQuery,
+        >,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let view_constants = world
+            .resource::<HexGridBuffers>()
+            .view_constants(graph.view_entity());
+
+        if let Some(stencil) = stencil {
+            draw_hex_grid_masks(render_context, camera, stencil, view_constants, world);
+        }
+
+        // timed with HexGridDiagnosticsPlugin
+        let diagnostics = render_context.diagnostic_recorder();
+
+        // create a render pass.  Note that we don't want to inherit the
+        // color_attachments because then the pipeline Multisample must match
+        // whatever msaa was set to.
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("hex_grid_pass"),
+            color_attachments: &[Some(view_target.get_color_attachment())],
+            depth_stencil_attachment: Some(depth.get_attachment(StoreOp::Store)),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        let pass_span = diagnostics.pass_span(&mut render_pass, "hex_grid");
+
+        // only draw within the camera viewport; the shader uses the viewport
+        // in ViewUniform to compute coordinates relative to it
+        if let Some(viewport) = camera.viewport.as_ref() {
+            render_pass.set_camera_viewport(viewport);
+        }
+        for (pipeline_id, _, _, bind_group) in &bind_groups.0 {
+            // still compiling, or failed; see HexGridStatus
+            let Some(pipeline) = pipeline_cache.get_render_pipeline(*pipeline_id) else {
+                continue;
+            };
+            render_pass.set_render_pipeline(pipeline);
+            if let Some(constants) = view_constants {
+                render_pass.set_push_constants(ShaderStages::VERTEX_FRAGMENT, 0, constants);
+            }
+            render_pass.set_bind_group(0, bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+        pass_span.end(&mut render_pass);
+        drop(render_pass);
+
+        if let Some(glow) = glow {
+            let seed_draws = bind_groups
+                .0
+                .iter()
+                .filter_map(|(_, _, id, bind_group)| Some(((*id)?, bind_group)));
+            draw_hex_grid_glow(
+                render_context,
+                camera,
+                view_target,
+                glow,
+                seed_draws,
+                view_constants,
+                world,
+            );
+        }
+
+        // the pick target is cleared even when the cursor isn't over the
+        // window, so a stale pick isn't read back
+        let Some(picking) = picking else {
+            return Ok(());
+        };
+        let images = world.resource::<RenderAssets<GpuImage>>();
+        let Some(target) = picking.target.as_ref().and_then(|t| images.get(t)) else {
+            return Ok(());
+        };
+        let pick_depth = &world.resource::<HexGridPipeline>().pick_depth;
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("hex_grid_pick_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: &target.texture_view,
+                depth_slice: None,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(default()),
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                view: pick_depth,
+                depth_ops: Some(Operations {
+                    load: LoadOp::Clear(0.0),
+                    store: StoreOp::Discard,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        if picking.pixel.is_none() {
+            return Ok(());
+        }
+        for (_, pipeline_id, _, bind_group) in &bind_groups.0 {
+            let Some(pipeline) = pipeline_id.and_then(|id| pipeline_cache.get_render_pipeline(id))
+            else {
+                continue;
+            };
+            render_pass.set_render_pipeline(pipeline);
+            if let Some(constants) = view_constants {
+                render_pass.set_push_constants(ShaderStages::VERTEX_FRAGMENT, 0, constants);
+            }
+            render_pass.set_bind_group(0, bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Resource)]
+pub(crate) struct HexGridPipeline {
+    shader: Handle<Shader>,
+    /// `hex_grid::hash`, imported by the grid shader & open to custom ones;
+    /// held so it stays loaded
+    _hash_shader: Handle<Shader>,
+    layout: BindGroupLayoutDescriptor,
+    /// 1x1 `R8Uint` texture bound in place of a view stencil or owner
+    /// texture; each is only read with its shader def
+    pub(crate) uint_fallback: TextureView,
+    /// 1x1 depth target for the pick pass; see [`HexGridGpuPicking`]
+    pick_depth: TextureView,
+    /// view is passed in push constants; see [`ViewUniform::push_constants`]
+    pub(crate) push_constants: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct HexGridPipelineKey {
+    kind: GridKind,
+    antialiasing: LineAntialiasing,
+    baked_highlights: bool,
+    write_depth: bool,
+    stencil: bool,
+    territory: bool,
+    heatmap: bool,
+    terrain: bool,
+    elevation: bool,
+    weather: bool,
+    fills: bool,
+    team_fills: bool,
+    part_highlights: bool,
+    holes: bool,
+    borders: bool,
+    /// write the cell under the cursor for [`HexGridGpuPicking`] instead of
+    /// drawing the grid
+    picking: bool,
+    /// draw the seeds of the [`HexGridGlow`] flood instead of the grid
+    glow_seed: bool,
+    /// the view target is the hdr main texture
+    hdr: bool,
+    /// view tonemapping to undo; see [`HexGridTonemapping::Before`]
+    untonemap: Option<Tonemapping>,
+}
+
+impl FromWorld for HexGridPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let shader = world.resource::<AssetServer>().load("hex_grid.wgsl");
+        let hash_shader = world.resource::<AssetServer>().load("hex_grid_hash.wgsl");
+
+        let push_constants = ViewUniform::push_constants(world.resource::<RenderDevice>());
+        let entries = BindGroupLayoutEntries::sequential(
+            ShaderStages::FRAGMENT,
+            (
+                uniform_buffer::<ViewUniform>(false).visibility(ShaderStages::VERTEX_FRAGMENT),
+                uniform_buffer::<GridUniform>(false).visibility(ShaderStages::VERTEX_FRAGMENT),
+                // decals
+                storage_buffer_read_only::<GpuDecals>(false),
+                texture_2d(TextureSampleType::Float { filterable: true }),
+                sampler(SamplerBindingType::Filtering),
+                // highlights
+                storage_buffer_read_only::<GpuHighlights>(false),
+                // baked highlights
+                texture_2d(TextureSampleType::Float { filterable: false }),
+                // view stencil
+                texture_2d(TextureSampleType::Uint),
+                // territory
+                texture_2d(TextureSampleType::Uint),
+                uniform_buffer::<GpuPalette>(false),
+                // edge & corner highlights
+                storage_buffer_read_only::<GpuHighlights>(false),
+                storage_buffer_read_only::<GpuHoles>(false),
+                uniform_buffer::<GpuWeathers>(false),
+                // heatmap values
+                texture_2d(TextureSampleType::Float { filterable: false }),
+            ),
+        );
+        // the view is left out when it's pushed
+        let layout = BindGroupLayoutDescriptor::new(
+            "hex_grid_bind_group_layout",
+            &entries[usize::from(push_constants)..],
+        );
+
+        let render_device = world.resource::<RenderDevice>();
+        let uint_fallback = render_device
+            .create_texture(&TextureDescriptor {
+                label: Some("hex_grid_uint_fallback"),
+                size: Extent3d::default(),
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::R8Uint,
+                usage: TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            })
+            .create_view(&TextureViewDescriptor::default());
+        let pick_depth = render_device
+            .create_texture(&TextureDescriptor {
+                label: Some("hex_grid_pick_depth"),
+                size: Extent3d::default(),
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::Depth32Float,
+                usage: TextureUsages::RENDER_ATTACHMENT,
+                view_formats: &[],
+            })
+            .create_view(&TextureViewDescriptor::default());
+
+        Self {
+            shader,
+            _hash_shader: hash_shader,
+            layout,
+            uint_fallback,
+            pick_depth,
+            push_constants,
+        }
+    }
+}
+
+impl SpecializedRenderPipeline for HexGridPipeline {
+    type Key = HexGridPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        // square & isometric grids share the same cells
+        let mut shader_defs: Vec<ShaderDefVal> = match key.kind {
+            GridKind::Hex => vec![],
+            GridKind::Square => vec!["GRID_SQUARE".into()],
+            GridKind::Triangle => vec!["GRID_TRIANGLE".into()],
+            GridKind::Isometric => vec!["GRID_SQUARE".into(), "GRID_ISOMETRIC".into()],
+        };
+        match key.antialiasing {
+            LineAntialiasing::Off => shader_defs.push("AA_OFF".into()),
+            LineAntialiasing::Smoothstep => (),
+            LineAntialiasing::Fwidth => shader_defs.push("AA_FWIDTH".into()),
+            LineAntialiasing::Supersample => {
+                shader_defs.extend(["AA_FWIDTH".into(), "AA_SUPERSAMPLE".into()])
+            }
+        }
+        if key.baked_highlights {
+            shader_defs.push("BAKED_HIGHLIGHTS".into());
+        }
+        if key.stencil {
+            shader_defs.push("STENCIL_TEST".into());
+        }
+        if key.territory {
+            shader_defs.push("TERRITORY".into());
+        }
+        if key.heatmap {
+            shader_defs.push("HEATMAP".into());
+        }
+        if key.terrain {
+            shader_defs.push("TERRAIN".into());
+        }
+        if key.elevation {
+            shader_defs.push("ELEVATION".into());
+        }
+        if key.weather {
+            shader_defs.push("WEATHER".into());
+        }
+        if key.fills {
+            shader_defs.push("FILLS".into());
+        }
+        if key.team_fills {
+            shader_defs.push("TEAM_FILLS".into());
+        }
+        if key.part_highlights {
+            shader_defs.push("PART_HIGHLIGHTS".into());
+        }
+        if key.holes {
+            shader_defs.push("HOLES".into());
+        }
+        if key.borders {
+            shader_defs.push("BORDERS".into());
+        }
+        match key.untonemap {
+            None => (),
+            Some(Tonemapping::None) => shader_defs.push("UNTONEMAP".into()),
+            Some(Tonemapping::ReinhardLuminance) => {
+                shader_defs.extend(["UNTONEMAP".into(), "UNTONEMAP_LUMINANCE".into()])
+            }
+            Some(_) => shader_defs.extend(["UNTONEMAP".into(), "UNTONEMAP_REINHARD".into()]),
+        }
+        let mut push_constant_ranges = Vec::new();
+        if self.push_constants {
+            shader_defs.push("VIEW_PUSH_CONSTANTS".into());
+            push_constant_ranges.push(ViewUniform::push_constant_range(
+                ShaderStages::VERTEX_FRAGMENT,
+            ));
+        }
+
+        // the pick pass draws a single pixel into an integer target, and the
+        // glow seeds into the half resolution glow textures, without msaa
+        let (entry_point, sample_count, targets) = if key.picking {
+            let target = ColorTargetState {
+                format: PICK_FORMAT,
+                blend: None,
+                write_mask: ColorWrites::ALL,
+            };
+            ("pick", 1, vec![Some(target)])
+        } else if key.glow_seed {
+            let targets = [SEED_FORMAT, COLOR_FORMAT].map(|format| {
+                Some(ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })
+            });
+            ("glow_seed", 1, targets.into())
+        } else {
+            let format = if key.hdr {
+                ViewTarget::TEXTURE_FORMAT_HDR
+            } else {
+                TextureFormat::bevy_default()
+            };
+            let target = ColorTargetState {
+                format,
+                blend: Some(BlendState::ALPHA_BLENDING),
+                write_mask: ColorWrites::ALL,
+            };
+            ("fragment", 4, vec![Some(target)])
+        };
+        // the glow seed pass has no depth target
+        let depth_stencil = (!key.glow_seed).then_some(DepthStencilState {
+            format: TextureFormat::Depth32Float,
+            depth_write_enabled: key.write_depth,
+            depth_compare: CompareFunction::Greater,
+            stencil: StencilState {
+                front: StencilFaceState::IGNORE,
+                back: StencilFaceState::IGNORE,
+                read_mask: 0,
+                write_mask: 0,
+            },
+            bias: DepthBiasState {
+                constant: 0,
+                slope_scale: 0.0,
+                clamp: 0.0,
+            },
+        });
+
+        RenderPipelineDescriptor {
+            label: Some("hex_grid_pipeline".into()),
+            layout: vec![self.layout.clone()],
+            push_constant_ranges,
+            vertex: VertexState {
+                shader: self.shader.clone(),
+                shader_defs: shader_defs.clone(),
+                entry_point: Some("vertex".into()),
+                buffers: vec![],
+            },
+            // a single fullscreen triangle, without a vertex buffer
+            primitive: PrimitiveState::default(),
+            depth_stencil,
+            multisample: MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            fragment: Some(FragmentState {
+                shader: self.shader.clone(),
+                shader_defs,
+                entry_point: Some(entry_point.into()),
+                targets,
+            }),
+            zero_initialize_workgroup_memory: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::{render::RenderApp, window::ExitCondition, winit::WinitPlugin};
+
+    use crate::{HexGridConfig, HexGridPlugin};
+
+    // spawning & despawning a grid every frame must not accumulate buffers in
+    // the render world
+    #[test]
+    #[ignore = "requires a GPU adapter"]
+    fn toggle_grid_every_frame() {
+        let mut app = App::new();
+        app.add_plugins((
+            DefaultPlugins
+                .set(WindowPlugin {
+                    primary_window: None,
+                    exit_condition: ExitCondition::DontExit,
+                    close_when_requested: false,
+                    ..default()
+                })
+                .disable::<WinitPlugin>(),
+            HexGridPlugin::default(),
+        ));
+
+        let mut grid = None;
+        for frame in 0..120 {
+            match grid.take() {
+                Some(entity) => {
+                    app.world_mut().despawn(entity);
+                }
+                None => grid = Some(app.world_mut().spawn(HexGridConfig::default()).id()),
+            }
+            app.update();
+
+            let buffers = app.sub_app(RenderApp).world().resource::<HexGridBuffers>();
+            assert_eq!(
+                buffers.grids.len(),
+                grid.iter().len(),
+                "grid buffers on frame {}",
+                frame
+            );
+        }
+    }
+
+    // mirrors `highlight_color()` in the shader
+    fn lookup(table: &GpuHighlights, cell: IVec2) -> Option<Vec4> {
+        let mut slot = cell_hash(cell) & table.mask;
+        loop {
+            let entry = table.slots[slot as usize];
+            if entry.occupied == 0 {
+                return None;
+            }
+            if entry.cell == cell {
+                return Some(entry.color);
+            }
+            slot = (slot + 1) & table.mask;
+        }
+    }
+
+    #[test]
+    fn highlight_table_lookup() {
+        let highlights: Vec<(IVec2, Vec4)> = crate::Axial::ZERO
+            .spiral(20)
+            .enumerate()
+            .map(|(i, cell)| (cell.into(), Vec4::splat(i as f32)))
+            .collect();
+        let mut table = GpuHighlights::default();
+        table.build(&highlights);
+
+        assert!(table.slots.len() >= highlights.len() * 2);
+        for &(cell, color) in &highlights {
+            assert_eq!(lookup(&table, cell), Some(color), "cell {}", cell);
+        }
+        for cell in crate::Axial::ZERO.ring(21) {
+            assert_eq!(lookup(&table, cell.into()), None, "cell {:?}", cell);
+        }
+
+        table.build(&[]);
+        assert_eq!(table.slots.len(), 1);
+        assert_eq!(lookup(&table, IVec2::ZERO), None);
+    }
+}
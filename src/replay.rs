@@ -0,0 +1,302 @@
+//! replay recording & playback
+//!
+//! Add a [`HexGridRecorder`] to a grid entity with a [`HexBoard`] to record
+//! every [`HexCommand`] applied to it, such as moves & ownership changes,
+//! and every box selection on it, stamped with the [`GridTime`] since
+//! recording started.  With the `serde` feature, the [`HexReplay`] can be
+//! saved at any time:
+//!
+//! ```ignore
+//! let replay = recorders.get(grid)?.replay().unwrap();
+//! replay.save("match.replay")?;
+//! ```
+//!
+//! Add a [`HexGridPlayback`] to play one back; the board, the grid's
+//! [`HexGridOwners`], [`HexGridHighlights`] & [`HexGridMinimap`] fog, and
+//! the [`HexPosition`] of each [`HexUnit`] on it, are reset to the ones the
+//! replay started from, and commands are applied through [`HexGridCommand`]
+//! again as their time comes, so the grid follows along.  Every event
+//! played is also sent as a [`ReplayEventPlayed`].
+//! [`HexGridPlayback::speed`] plays replays slower or faster, for stepping
+//! through a desync or cutting a trailer.
+//!
+//! [`HexReplay::board_at`] rebuilds the board at any point of a replay
+//! without playing it, to compare against what a peer had.
+use bevy::prelude::*;
+
+use crate::{
+    Axial, GridTime, HexBoard, HexCommand, HexCommandApplied, HexGridCommand, HexGridHighlights,
+    HexGridMinimap, HexGridOwners, HexGridSelected, HexMap, HexPosition, HexUnit,
+};
+
+/// something that happened on a grid
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ReplayEvent {
+    /// a command applied to the board
+    Command(HexCommand),
+    /// a box selection, with its cells in sorted order
+    Selected(Vec<Axial>),
+}
+
+/// event in a [`HexReplay`], with the seconds since recording started
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReplayFrame {
+    pub time: f32,
+    pub event: ReplayEvent,
+}
+
+/// Recorded events of one grid, in time order, & the board they started
+/// from.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HexReplay {
+    pub start: HexBoard,
+    pub frames: Vec<ReplayFrame>,
+}
+
+impl HexReplay {
+    pub fn new(start: HexBoard) -> Self {
+        Self {
+            start,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Add an event.  Events must be pushed in time order; an event earlier
+    /// than the last is moved up to its time.
+    pub fn push(&mut self, time: f32, event: ReplayEvent) {
+        let time = self.frames.last().map_or(time, |last| time.max(last.time));
+        self.frames.push(ReplayFrame { time, event });
+    }
+
+    /// seconds from the start to the last event
+    pub fn duration(&self) -> f32 {
+        self.frames.last().map_or(0.0, |frame| frame.time)
+    }
+
+    /// the board after every command up to & including `time`
+    pub fn board_at(&self, time: f32) -> HexBoard {
+        let mut board = self.start.clone();
+        for frame in self.frames.iter().take_while(|frame| frame.time <= time) {
+            if let ReplayEvent::Command(command) = &frame.event {
+                board.apply(command);
+            }
+        }
+        board
+    }
+
+    /// the board after every command
+    pub fn final_board(&self) -> HexBoard {
+        self.board_at(f32::INFINITY)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl HexReplay {
+    /// encode the replay into a compact binary format
+    pub fn to_bytes(&self) -> bincode::Result<Vec<u8>> {
+        bincode::serialize(self)
+    }
+
+    /// decode a replay written by [`HexReplay::to_bytes`]
+    pub fn from_bytes(bytes: &[u8]) -> bincode::Result<Self> {
+        bincode::deserialize(bytes)
+    }
+
+    /// write the replay to a file
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> anyhow::Result<()> {
+        std::fs::write(path, self.to_bytes()?)?;
+        Ok(())
+    }
+
+    /// read a replay written by [`HexReplay::save`]
+    pub fn load(path: impl AsRef<std::path::Path>) -> anyhow::Result<Self> {
+        Ok(Self::from_bytes(&std::fs::read(path)?)?)
+    }
+}
+
+/// Record the events of this grid entity into a [`HexReplay`].  The replay
+/// starts from the grid's [`HexBoard`] as it is the first frame the
+/// recorder runs.
+#[derive(Component, Debug, Default, Clone)]
+pub struct HexGridRecorder {
+    replay: Option<HexReplay>,
+    /// grid time recording started at
+    started: f32,
+}
+
+impl HexGridRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// events recorded so far; `None` before the recorder has run
+    pub fn replay(&self) -> Option<&HexReplay> {
+        self.replay.as_ref()
+    }
+
+    /// the finished replay; empty if the recorder never ran
+    pub fn into_replay(self) -> HexReplay {
+        self.replay.unwrap_or_default()
+    }
+}
+
+/// Play a [`HexReplay`] on this grid entity.  The grid's [`HexBoard`] is
+/// replaced with the replay's starting board when playback begins.  Units
+/// on the starting board need entities with a [`HexUnit`] for them to be
+/// moved.  Fog is reset to the fog the grid had when playback first began,
+/// less the cells revealed on the starting board.
+#[derive(Component, Debug, Clone)]
+pub struct HexGridPlayback {
+    pub replay: HexReplay,
+    /// replay seconds per second of [`GridTime`]
+    pub speed: f32,
+    pub paused: bool,
+    /// replay seconds played so far
+    time: f32,
+    /// index of the next frame to play
+    next: usize,
+    started: bool,
+    /// minimap fog before playback began
+    fog: Option<HexMap<f32>>,
+}
+
+impl HexGridPlayback {
+    pub fn new(replay: HexReplay) -> Self {
+        Self {
+            replay,
+            speed: 1.0,
+            paused: false,
+            time: 0.0,
+            next: 0,
+            started: false,
+            fog: None,
+        }
+    }
+
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// replay seconds played so far
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// true once every event has been played
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.replay.frames.len()
+    }
+
+    /// start over from the beginning, resetting the board again
+    pub fn restart(&mut self) {
+        self.time = 0.0;
+        self.next = 0;
+        self.started = false;
+    }
+}
+
+/// sent for each event of a [`HexGridPlayback`] as it's played
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct ReplayEventPlayed {
+    pub grid: Entity,
+    pub event: ReplayEvent,
+}
+
+pub(crate) fn record_grid_events(
+    time: Res<GridTime>,
+    mut applied: MessageReader<HexCommandApplied>,
+    mut selected: MessageReader<HexGridSelected>,
+    mut recorders: Query<(Entity, &mut HexGridRecorder, &HexBoard)>,
+) {
+    let now = time.elapsed_secs();
+    // the board of a new recorder already has this frame's commands
+    let mut started = Vec::new();
+    for (grid, mut recorder, board) in &mut recorders {
+        if recorder.replay.is_none() {
+            recorder.replay = Some(HexReplay::new(board.clone()));
+            recorder.started = now;
+            started.push(grid);
+        }
+    }
+
+    let mut record = |grid: Entity, event: ReplayEvent| {
+        if started.contains(&grid) {
+            return;
+        }
+        if let Ok((_, mut recorder, _)) = recorders.get_mut(grid) {
+            let time = now - recorder.started;
+            recorder.replay.as_mut().unwrap().push(time, event);
+        }
+    };
+    for applied in applied.read() {
+        record(applied.grid, ReplayEvent::Command(applied.command));
+    }
+    for selected in selected.read() {
+        let mut cells: Vec<Axial> = selected.cells.iter().copied().collect();
+        cells.sort();
+        record(selected.grid, ReplayEvent::Selected(cells));
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn play_replays(
+    mut commands: Commands,
+    time: Res<GridTime>,
+    mut grid_commands: MessageWriter<HexGridCommand>,
+    mut played: MessageWriter<ReplayEventPlayed>,
+    mut playbacks: Query<(
+        Entity,
+        &mut HexGridPlayback,
+        &mut HexBoard,
+        Option<&mut HexGridOwners>,
+        Option<&mut HexGridHighlights>,
+        Option<&mut HexGridMinimap>,
+    )>,
+    units: Query<(Entity, &HexUnit)>,
+) {
+    for (grid, mut playback, mut board, owners, highlights, minimap) in &mut playbacks {
+        if !playback.started {
+            playback.started = true;
+            *board = playback.replay.start.clone();
+            if let Some(mut owners) = owners {
+                *owners = HexGridOwners::new(board.owners.clone());
+            }
+            if let Some(mut highlights) = highlights {
+                highlights.0 = board.highlights.clone();
+            }
+            if let Some(mut minimap) = minimap {
+                let fog = playback.fog.get_or_insert_with(|| minimap.fog.clone());
+                minimap.fog = fog.clone();
+                for cell in board.revealed.cells() {
+                    minimap.fog.remove(cell);
+                }
+            }
+            for (entity, unit) in &units {
+                if let Some(cell) = board.unit(unit.0) {
+                    commands.entity(entity).insert(HexPosition { grid, cell });
+                }
+            }
+        } else if !playback.paused {
+            playback.time += time.delta_secs() * playback.speed;
+        }
+
+        let playback = &mut *playback;
+        while let Some(frame) = playback.replay.frames.get(playback.next) {
+            if frame.time > playback.time {
+                break;
+            }
+            if let ReplayEvent::Command(command) = frame.event {
+                grid_commands.write(HexGridCommand { grid, command });
+            }
+            played.write(ReplayEventPlayed {
+                grid,
+                event: frame.event.clone(),
+            });
+            playback.next += 1;
+        }
+    }
+}
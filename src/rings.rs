@@ -0,0 +1,108 @@
+//! distance rings around a selected cell
+//!
+//! Add [`HexGridDistanceRings`] to a hex grid entity, and clicking a cell
+//! fills the cells around it, ring by ring, out to
+//! [`HexGridDistanceRings::count`] steps away; clicking it again clears the
+//! rings.  Each ring is colored between [`HexGridDistanceRings::near_color`]
+//! & [`HexGridDistanceRings::far_color`] by its distance:
+//!
+//! ```ignore
+//! commands.entity(grid).insert(HexGridDistanceRings::new(4));
+//! ```
+//!
+//! Set [`HexGridDistanceRings::center`] directly to show rings from game
+//! code, such as the move range of a unit.  The distance to each cell is
+//! worked out in the shader, so the rings cost the same however far they
+//! reach.  On wrapped maps, distances are the short way around, as
+//! [`crate::HexWrap::distance`].  Rings are only drawn on
+//! [`crate::GridKind::Hex`] grids.
+use bevy::prelude::*;
+
+use crate::{Axial, HexGridCursor, HexWrap};
+
+/// Distance rings for a grid entity; see the [module docs](self).
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct HexGridDistanceRings {
+    /// cell the rings are drawn around; none draws nothing
+    pub center: Option<Axial>,
+    /// rings drawn, counting out from the center; the center itself isn't
+    /// filled
+    pub count: u32,
+    /// color of the innermost ring
+    pub near_color: Color,
+    /// color of the outermost ring
+    pub far_color: Color,
+    /// button that moves the center to the cell under a [`HexGridCursor`];
+    /// none to only set it from code
+    pub select_button: Option<MouseButton>,
+}
+
+impl Default for HexGridDistanceRings {
+    fn default() -> Self {
+        Self {
+            center: None,
+            count: 3,
+            near_color: Color::srgba(1.0, 0.85, 0.3, 0.45),
+            far_color: Color::srgba(1.0, 0.85, 0.3, 0.08),
+            select_button: Some(MouseButton::Left),
+        }
+    }
+}
+
+impl HexGridDistanceRings {
+    pub fn new(count: u32) -> Self {
+        Self { count, ..default() }
+    }
+
+    pub fn with_center(mut self, center: Axial) -> Self {
+        self.center = Some(center);
+        self
+    }
+
+    pub fn with_colors(mut self, near: Color, far: Color) -> Self {
+        self.near_color = near;
+        self.far_color = far;
+        self
+    }
+
+    pub fn with_select_button(mut self, button: Option<MouseButton>) -> Self {
+        self.select_button = button;
+        self
+    }
+
+    /// Ring `cell` is drawn in, from 1 to [`HexGridDistanceRings::count`];
+    /// `None` outside the rings, or on the center.
+    pub fn ring(&self, cell: Axial, wrap: Option<HexWrap>) -> Option<u32> {
+        let center = self.center?;
+        let distance = match wrap {
+            Some(wrap) => wrap.distance(center, cell),
+            None => center.distance(cell),
+        };
+        (1..=self.count).contains(&distance).then_some(distance)
+    }
+
+    /// Color of ring `ring`; mirrors `ring_color()` in the shader.
+    pub fn ring_color(&self, ring: u32) -> Color {
+        let t = (ring.max(1) - 1) as f32 / self.count.saturating_sub(1).max(1) as f32;
+        let near = self.near_color.to_linear();
+        Color::from(near.mix(&self.far_color.to_linear(), t.min(1.0)))
+    }
+}
+
+pub(crate) fn select_distance_rings(
+    buttons: Res<ButtonInput<MouseButton>>,
+    cursors: Query<&HexGridCursor>,
+    mut grids: Query<&mut HexGridDistanceRings>,
+) {
+    for cursor in &cursors {
+        let Some(mut rings) = cursor.grid.and_then(|g| grids.get_mut(g).ok()) else {
+            continue;
+        };
+        if !rings.select_button.is_some_and(|b| buttons.just_pressed(b)) {
+            continue;
+        }
+        // clicking the center again clears it
+        rings.center = (rings.center != Some(cursor.cell)).then_some(cursor.cell);
+    }
+}
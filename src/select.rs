@@ -0,0 +1,181 @@
+//! box selection of cells
+//!
+//! Add [`HexGridBoxSelect`] to a camera to select cells by dragging a
+//! rectangle with the mouse.  A drag starts on the grid under the cursor;
+//! the corners of the rectangle are projected onto that grid's plane, and
+//! every cell whose center lies inside the projected shape is selected.
+//! [`HexGridSelectStarted`] is sent when a drag begins, and
+//! [`HexGridSelected`] with the covered cells when the button is released.
+//!
+//! While dragging, the grid shader can fill the rectangle; see
+//! [`HexGridBoxSelect::color`].
+use bevy::{
+    platform::collections::HashSet, prelude::*, render::extract_component::ExtractComponent,
+};
+
+use crate::{cursor_ray, Axial, HexGridConfig, HexGridCursor, HexGridPlane};
+
+/// Box selection for a camera.  Requires a [`HexGridCursor`] on the same
+/// entity to find the grid a drag starts on.
+#[derive(Component, Debug, Clone, ExtractComponent, Reflect)]
+#[reflect(Component)]
+#[require(HexGridCursor)]
+pub struct HexGridBoxSelect {
+    pub button: MouseButton,
+    /// fill color of the rectangle while dragging; transparent to not draw
+    /// it
+    pub color: Color,
+    #[reflect(ignore)]
+    drag: Option<Drag>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Drag {
+    grid: Entity,
+    /// window cursor position the drag started at
+    start: Vec2,
+    /// corners of the rectangle on the grid plane (local X, local Z)
+    corners: [Vec2; 4],
+}
+
+impl Default for HexGridBoxSelect {
+    fn default() -> Self {
+        Self {
+            button: MouseButton::Left,
+            color: Color::srgba(0.3, 0.6, 1.0, 0.25),
+            drag: None,
+        }
+    }
+}
+
+impl HexGridBoxSelect {
+    /// grid being dragged over, and the corners of the rectangle on its plane
+    /// (local X, local Z)
+    pub fn drag(&self) -> Option<(Entity, [Vec2; 4])> {
+        self.drag.map(|d| (d.grid, d.corners))
+    }
+}
+
+/// sent when a box selection drag begins
+#[derive(Message, Debug, Clone)]
+pub struct HexGridSelectStarted {
+    pub camera: Entity,
+    pub grid: Entity,
+    /// cell the drag started on
+    pub cell: Axial,
+}
+
+/// sent when a box selection drag ends, with the cells inside the rectangle
+#[derive(Message, Debug, Clone)]
+pub struct HexGridSelected {
+    pub camera: Entity,
+    pub grid: Entity,
+    pub cells: HashSet<Axial>,
+}
+
+/// Cells with their center inside a convex polygon on the grid plane (local
+/// X, local Z).  The polygon corners may wind either way.
+pub fn cells_in_polygon(config: &HexGridConfig, corners: &[Vec2]) -> HashSet<Axial> {
+    let Some(first) = corners.first() else {
+        return HashSet::new();
+    };
+
+    // every kind maps cells to axial coordinates linearly, so the cells of
+    // the corners bound the cells inside; pad for cells straddling the edge
+    let (mut min, mut max) = (config.world_to_cell(*first), config.world_to_cell(*first));
+    for corner in corners {
+        let cell = config.world_to_cell(*corner);
+        min = Axial::new(min.q.min(cell.q), min.r.min(cell.r));
+        max = Axial::new(max.q.max(cell.q), max.r.max(cell.r));
+    }
+
+    (min.r - 2..=max.r + 2)
+        .flat_map(|r| (min.q - 2..=max.q + 2).map(move |q| Axial::new(q, r)))
+        .filter(|cell| in_convex_polygon(corners, config.cell_to_world(*cell)))
+        .collect()
+}
+
+fn in_convex_polygon(corners: &[Vec2], point: Vec2) -> bool {
+    let mut sign = 0.0;
+    for (i, a) in corners.iter().enumerate() {
+        let b = corners[(i + 1) % corners.len()];
+        let cross = (b - *a).perp_dot(point - *a);
+        if cross * sign < 0.0 {
+            return false;
+        }
+        if cross != 0.0 {
+            sign = cross;
+        }
+    }
+    true
+}
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn update_box_select(
+    windows: Query<&Window>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    mut cameras: Query<(
+        Entity,
+        &Camera,
+        &GlobalTransform,
+        &HexGridCursor,
+        &mut HexGridBoxSelect,
+    )>,
+    grids: Query<(&HexGridConfig, &GlobalTransform)>,
+    mut started: MessageWriter<HexGridSelectStarted>,
+    mut selected: MessageWriter<HexGridSelected>,
+) {
+    let cursor_pos = windows.iter().find_map(|w| w.cursor_position());
+
+    for (camera_entity, camera, camera_transform, cursor, mut select) in &mut cameras {
+        if buttons.just_pressed(select.button) {
+            if let (Some(grid), Some(start)) = (cursor.grid, cursor_pos) {
+                select.drag = Some(Drag {
+                    grid,
+                    start,
+                    corners: [cursor.pos; 4],
+                });
+                started.write(HexGridSelectStarted {
+                    camera: camera_entity,
+                    grid,
+                    cell: cursor.cell,
+                });
+            }
+        }
+
+        let Some(mut drag) = select.drag else {
+            continue;
+        };
+        let Ok((config, grid_transform)) = grids.get(drag.grid) else {
+            select.drag = None;
+            continue;
+        };
+
+        // keep the last rectangle if a corner is off the plane, past the
+        // horizon
+        if let Some(end) = cursor_pos {
+            let plane = HexGridPlane::from(*grid_transform);
+            let corners = [
+                drag.start,
+                Vec2::new(end.x, drag.start.y),
+                end,
+                Vec2::new(drag.start.x, end.y),
+            ]
+            .map(|p| plane.intersect(cursor_ray(camera, camera_transform, p)?));
+            if let [Some(a), Some(b), Some(c), Some(d)] = corners {
+                drag.corners = [a, b, c, d];
+            }
+        }
+
+        if buttons.just_released(select.button) {
+            select.drag = None;
+            selected.write(HexGridSelected {
+                camera: camera_entity,
+                grid: drag.grid,
+                cells: cells_in_polygon(config, &drag.corners),
+            });
+        } else {
+            select.drag = Some(drag);
+        }
+    }
+}
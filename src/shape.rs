@@ -0,0 +1,170 @@
+//! area of effect shapes
+//!
+//! Cell sets for ability previews & targeting on hex grids.  Every shape is
+//! a set of [`Axial`] cells, ready for [`HexGridHighlights::fill`]:
+//!
+//! ```ignore
+//! highlights.fill(shape::cone(caster, HexDirection::new(2), 4), Color::srgba(1.0, 0.3, 0.0, 0.4));
+//! ```
+//!
+//! A [`Brush`] picks between shapes at runtime, so map editing tools & area
+//! effects can share one.
+//!
+//! [`HexGridHighlights::fill`]: crate::HexGridHighlights::fill
+use bevy::{platform::collections::HashSet, prelude::*};
+
+use crate::{
+    coords::{Offset, OffsetKind},
+    hash::hash_cell_f32,
+    Axial, Cube, HexDirection,
+};
+
+/// every cell within `radius` steps of `center`, including it
+pub fn blast(center: Axial, radius: u32) -> HashSet<Axial> {
+    center.range(radius).collect()
+}
+
+/// cells exactly `radius` steps from `center`
+pub fn ring(center: Axial, radius: u32) -> HashSet<Axial> {
+    center.ring(radius).collect()
+}
+
+/// cells from `inner` to `outer` steps from `center`, inclusive
+pub fn donut(center: Axial, inner: u32, outer: u32) -> HashSet<Axial> {
+    (inner..=outer).flat_map(|r| center.ring(r)).collect()
+}
+
+/// 60 degree cone from `origin` toward `dir`, out to `length` steps.  The
+/// cone widens by one cell on each side every two steps; `origin` isn't
+/// included.
+pub fn cone(origin: Axial, dir: HexDirection, length: u32) -> HashSet<Axial> {
+    origin
+        .range(length)
+        .filter(|&cell| {
+            // rotate into direction 0, then keep the wedge between the
+            // corner directions either side of it
+            let v = (cell - origin).rotate_around(Axial::ZERO, -(dir.index() as i32));
+            v != Axial::ZERO && v.q >= v.r && v.q + 2 * v.r >= 0
+        })
+        .collect()
+}
+
+/// Cells on the straight line from `from` to `to`, in order, including
+/// both.  Where the line runs exactly along the edge between two cells, the
+/// same side is always chosen.
+pub fn line(from: Axial, to: Axial) -> Vec<Axial> {
+    // nudge off cell edges so rounding doesn't flip between sides
+    let nudge = Vec3::new(1e-4, 1e-4, -2e-4);
+    let cube = |c: Axial| Vec3::new(c.q as f32, c.r as f32, c.s() as f32) + nudge;
+    let (a, b) = (cube(from), cube(to));
+    let n = from.distance(to).max(1);
+    (0..=from.distance(to))
+        .map(|i| Cube::round(a.lerp(b, i as f32 / n as f32)).into())
+        .collect()
+}
+
+/// line from `from` to `to`, `width` cells wide on either side
+pub fn beam(from: Axial, to: Axial, width: u32) -> HashSet<Axial> {
+    line(from, to)
+        .into_iter()
+        .flat_map(|cell| cell.range(width))
+        .collect()
+}
+
+/// Cells in the rectangle of rows with corners `a` & `b`, inclusive, as
+/// drawn on screen.  Rows alternate as in [`OffsetKind::OddR`].
+pub fn rectangle(a: Axial, b: Axial) -> HashSet<Axial> {
+    let (a, b) = (
+        Offset::from_axial(a, OffsetKind::OddR),
+        Offset::from_axial(b, OffsetKind::OddR),
+    );
+    let (cols, rows) = (
+        a.col.min(b.col)..=a.col.max(b.col),
+        a.row.min(b.row)..=a.row.max(b.row),
+    );
+    rows.flat_map(|row| {
+        cols.clone()
+            .map(move |col| Offset::new(col, row).to_axial(OffsetKind::OddR))
+    })
+    .collect()
+}
+
+/// Cells reachable from `start` by stepping between neighbors for which
+/// `connected` is true, including `start`.  Stops after `limit` cells, so
+/// an unbounded region doesn't run forever.
+pub fn flood(
+    start: Axial,
+    limit: usize,
+    mut connected: impl FnMut(Axial) -> bool,
+) -> HashSet<Axial> {
+    let mut cells = HashSet::from_iter([start]);
+    let mut open = vec![start];
+    while let Some(cell) = open.pop() {
+        for next in cell.neighbors() {
+            if cells.len() >= limit {
+                return cells;
+            }
+            if !cells.contains(&next) && connected(next) {
+                cells.insert(next);
+                open.push(next);
+            }
+        }
+    }
+    cells
+}
+
+/// Shape stamped by an editing tool or an area effect, such as a terrain
+/// brush or a spell.  Every shape but [`Brush::Line`] is centered on the
+/// target cell.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Brush {
+    /// just the target cell
+    #[default]
+    Single,
+    /// every cell within `radius` steps of the target
+    Blob { radius: u32 },
+    /// line from the origin to the target, `width` cells wide on either side
+    Line { width: u32 },
+    /// cells exactly `radius` steps from the target
+    Ring { radius: u32 },
+    /// Cells within `radius` steps of the target, each kept with a chance
+    /// of `density`.  Cells are picked with [`hash_cell_f32`], so the same
+    /// ones are picked wherever the brush is stamped, and a shader can pick
+    /// them too.
+    Scatter {
+        radius: u32,
+        density: f32,
+        seed: u32,
+    },
+}
+
+impl Brush {
+    /// cells covered when aimed from `origin`, such as a caster, at `target`
+    pub fn cells(&self, origin: Axial, target: Axial) -> HashSet<Axial> {
+        match *self {
+            Brush::Single => HashSet::from_iter([target]),
+            Brush::Blob { radius } => blast(target, radius),
+            Brush::Line { width } => beam(origin, target, width),
+            Brush::Ring { radius } => ring(target, radius),
+            Brush::Scatter {
+                radius,
+                density,
+                seed,
+            } => target
+                .range(radius)
+                .filter(|&cell| hash_cell_f32(cell, seed) < density)
+                .collect(),
+        }
+    }
+
+    /// Cells covered when stamped on every cell of the line from `from` to
+    /// `to`, as when dragged across a map.  A [`Brush::Line`] is stamped
+    /// as a blob `width` cells across.
+    pub fn stroke(&self, from: Axial, to: Axial) -> HashSet<Axial> {
+        line(from, to)
+            .into_iter()
+            .flat_map(|cell| self.cells(cell, cell))
+            .collect()
+    }
+}
@@ -0,0 +1,282 @@
+//! snapshots of a camera's view
+//!
+//! Add a [`HexGridSnapshot`] to a camera to draw its view, grids included,
+//! into an [`Image`] once, at any resolution; to share a board state, or as
+//! the thumbnail of a saved game:
+//!
+//! ```ignore
+//! let image = images.reserve_handle();
+//! commands
+//!     .entity(camera)
+//!     .insert(HexGridSnapshot::new(UVec2::new(320, 180)).with_image(image.clone()));
+//!
+//! fn save_thumbnails(mut taken: MessageReader<HexGridSnapshotTaken>, images: Res<Assets<Image>>) {
+//!     for taken in taken.read() {
+//!         let image = images.get(&taken.image).unwrap();
+//!         image.clone().try_into_dynamic()?.save("thumbnail.png")?;
+//!     }
+//! }
+//! ```
+//!
+//! The view is drawn by a second camera, with the same transform &
+//! projection, into a target [`HexGridSnapshot::supersampling`] times the
+//! size of the image, then read back & averaged down to the image.  Pixel
+//! line widths are scaled to match, so the grid looks as it does on screen.
+//! The cursor isn't drawn.  The [`HexGridSnapshot`] is removed from the
+//! camera once the image is ready, and a [`HexGridSnapshotTaken`] is sent.
+use bevy::{
+    asset::RenderAssetUsages,
+    camera::{visibility::RenderLayers, ImageRenderTarget, RenderTarget},
+    core_pipeline::tonemapping::Tonemapping,
+    prelude::*,
+    render::{
+        gpu_readback::{Readback, ReadbackComplete},
+        render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages},
+    },
+};
+
+use crate::HexGridCursor;
+
+/// Draw the view of the camera entity into [`HexGridSnapshot::image`]; see
+/// the [module docs](self).
+#[derive(Component, Debug, Clone)]
+pub struct HexGridSnapshot {
+    /// image size in pixels
+    pub resolution: UVec2,
+    /// pixels drawn along each side of an image pixel, which are averaged;
+    /// 1 to draw the image directly
+    pub supersampling: u32,
+    /// image the view is drawn into; replaced with a new image if it's the
+    /// default handle
+    pub image: Handle<Image>,
+    // camera drawing the view, once it's spawned
+    capture: Option<Entity>,
+}
+
+impl HexGridSnapshot {
+    pub fn new(resolution: UVec2) -> Self {
+        Self {
+            resolution,
+            supersampling: 2,
+            image: Handle::default(),
+            capture: None,
+        }
+    }
+
+    pub fn with_supersampling(mut self, supersampling: u32) -> Self {
+        self.supersampling = supersampling;
+        self
+    }
+
+    pub fn with_image(mut self, image: Handle<Image>) -> Self {
+        self.image = image;
+        self
+    }
+
+    /// size of the target the view is drawn into
+    pub fn target_size(&self) -> UVec2 {
+        self.resolution.max(UVec2::ONE) * self.supersampling.max(1)
+    }
+}
+
+/// sent when the view of a camera has been drawn into its
+/// [`HexGridSnapshot::image`]
+#[derive(Message, Debug, Clone)]
+pub struct HexGridSnapshotTaken {
+    pub camera: Entity,
+    pub image: Handle<Image>,
+}
+
+/// camera drawing a snapshot, as a child of the snapshot camera; its cursor
+/// is left off the grid
+#[derive(Component, Debug)]
+pub(crate) struct SnapshotCamera;
+
+/// spawn a camera drawing the view of each new snapshot
+#[allow(clippy::type_complexity)]
+pub(crate) fn start_snapshots(
+    mut commands: Commands,
+    mut cameras: Query<(
+        Entity,
+        &Camera,
+        &mut HexGridSnapshot,
+        Option<&Camera3d>,
+        Option<&Projection>,
+        Option<&RenderLayers>,
+        Option<&Tonemapping>,
+        Option<&Msaa>,
+    )>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    for (entity, camera, mut snapshot, camera_3d, projection, layers, tonemapping, msaa) in
+        &mut cameras
+    {
+        if snapshot.capture.is_some() {
+            continue;
+        }
+
+        let size = snapshot.target_size();
+        let mut image =
+            Image::new_target_texture(size.x, size.y, TextureFormat::Rgba8UnormSrgb, None);
+        image.texture_descriptor.usage |= TextureUsages::COPY_SRC;
+        let target = images.add(image);
+
+        // pixel line widths are as wide, relative to the view, as on screen
+        let scale_factor =
+            camera.target_scaling_factor().unwrap_or(1.0) * snapshot.supersampling.max(1) as f32;
+        let mut capture = commands.spawn((
+            Name::new("HexGridSnapshot camera"),
+            SnapshotCamera,
+            camera_3d.cloned().unwrap_or_default(),
+            Camera {
+                order: camera.order,
+                clear_color: camera.clear_color,
+                ..default()
+            },
+            RenderTarget::Image(ImageRenderTarget {
+                handle: target.clone(),
+                scale_factor,
+            }),
+            projection.cloned().unwrap_or_default(),
+            tonemapping.copied().unwrap_or_default(),
+            msaa.copied().unwrap_or_default(),
+            HexGridCursor::default(),
+            ChildOf(entity),
+        ));
+        if let Some(layers) = layers {
+            capture.insert(layers.clone());
+        }
+        // the readback goes away with the snapshot camera
+        capture.with_children(|parent| {
+            parent
+                .spawn((
+                    Name::new("HexGridSnapshot readback"),
+                    Readback::texture(target),
+                ))
+                .observe(read_snapshot);
+        });
+        snapshot.capture = Some(capture.id());
+    }
+}
+
+// average the view read back from the gpu into the snapshot image
+fn read_snapshot(
+    event: On<ReadbackComplete>,
+    mut commands: Commands,
+    parents: Query<&ChildOf>,
+    readbacks: Query<&Readback>,
+    snapshots: Query<&HexGridSnapshot>,
+    mut images: ResMut<Assets<Image>>,
+    mut taken: MessageWriter<HexGridSnapshotTaken>,
+) {
+    let Ok(capture) = parents.get(event.entity).map(ChildOf::parent) else {
+        return;
+    };
+    let Ok(camera) = parents.get(capture).map(ChildOf::parent) else {
+        return;
+    };
+    let Ok(snapshot) = snapshots.get(camera) else {
+        return;
+    };
+    let Ok(Readback::Texture(target)) = readbacks.get(event.entity) else {
+        return;
+    };
+
+    // rows are read back padded to the copy alignment
+    let size = snapshot.target_size();
+    let row = size.x as usize * 4;
+    let stride = event.data.len() / size.y as usize;
+    if stride < row {
+        return;
+    }
+    let data = event
+        .data
+        .chunks(stride)
+        .flat_map(|r| &r[..row])
+        .copied()
+        .collect();
+    let drawn = Image::new(
+        Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    let Some(image) = downsample(&drawn, snapshot.supersampling) else {
+        return;
+    };
+
+    let handle = if snapshot.image == Handle::default() {
+        images.add(image)
+    } else {
+        if images.insert(&snapshot.image, image).is_err() {
+            warn!("snapshot image {:?} was removed", snapshot.image);
+        }
+        snapshot.image.clone()
+    };
+    images.remove(target);
+    commands.entity(capture).despawn();
+    commands.entity(camera).remove::<HexGridSnapshot>();
+    taken.write(HexGridSnapshotTaken {
+        camera,
+        image: handle,
+    });
+}
+
+/// Average each `factor` by `factor` block of pixels of an
+/// [`TextureFormat::Rgba8UnormSrgb`] image into one pixel, in linear color.
+/// Pixels past the last whole block are dropped.  `None` for other formats,
+/// an image without data, or one smaller than a block.
+pub fn downsample(image: &Image, factor: u32) -> Option<Image> {
+    if image.texture_descriptor.format != TextureFormat::Rgba8UnormSrgb {
+        return None;
+    }
+    let data = image.data.as_ref()?;
+    let factor = factor.max(1);
+    let (width, height) = (image.width(), image.height());
+    if width < factor || height < factor {
+        return None;
+    }
+    let size = UVec2::new(width / factor, height / factor);
+
+    let linear: Vec<f32> = (0..=255u8)
+        .map(|c| Srgba::gamma_function(c as f32 / 255.0))
+        .collect();
+    let mut out = Vec::with_capacity((size.x * size.y * 4) as usize);
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let mut sum = [0.0; 4];
+            for sy in y * factor..(y + 1) * factor {
+                for sx in x * factor..(x + 1) * factor {
+                    let i = ((sy * width + sx) * 4) as usize;
+                    for (c, sum) in sum.iter_mut().enumerate() {
+                        // alpha is stored linear
+                        *sum += match c {
+                            3 => data[i + c] as f32 / 255.0,
+                            _ => linear[data[i + c] as usize],
+                        };
+                    }
+                }
+            }
+            let n = (factor * factor) as f32;
+            let [r, g, b, a] = sum.map(|s| s / n);
+            out.extend(Srgba::from(LinearRgba::new(r, g, b, a)).to_u8_array());
+        }
+    }
+
+    Some(Image::new(
+        Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        out,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    ))
+}
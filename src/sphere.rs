@@ -0,0 +1,368 @@
+//! hex tiling of a sphere (experimental)
+//!
+//! [`HexSphere`] covers a sphere with hexagons & twelve pentagons, the
+//! cells of a Goldberg polyhedron, for planet-scale strategy prototypes.
+//! The sphere is an icosahedron with each edge split into `frequency`
+//! steps, pushed out onto the sphere; its corners become the pentagons.
+//!
+//! The icosahedron unfolds into ten diamonds, five around each pole, each a
+//! `frequency` by `frequency` parallelogram of cells.  A [`SphereCell`] is
+//! one of these cells, or one of the two poles.  [`HexSphere::to_axial`]
+//! lays the diamonds side by side on a flat grid, so the data for each cell
+//! can be kept in a [`HexMap`] like any other board:
+//!
+//! ```ignore
+//! let sphere = HexSphere::new(16);
+//! let mut owners = HexMap::new();
+//! owners.insert(sphere.to_axial(capital), 1);
+//!
+//! commands.spawn((
+//!     Mesh3d(meshes.add(sphere.mesh(100.0))),
+//!     MeshMaterial3d(materials.add(StandardMaterial::from_color(GREEN))),
+//! ));
+//! let cell = sphere.ray_cell(ray, &planet_transform, 100.0);
+//! ```
+//!
+//! Neighbors, distances, & rings on the flat grid don't match those on the
+//! sphere across the edges of the diamonds; use [`HexSphere::neighbors`].
+use std::f32::consts::PI;
+
+use bevy::{
+    asset::RenderAssetUsages,
+    mesh::{Indices, PrimitiveTopology},
+    platform::collections::HashMap,
+    prelude::*,
+};
+
+use crate::{Axial, HexMap};
+
+/// cell of a [`HexSphere`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SphereCell {
+    /// diamond the cell is on; 0 to 4 around the north pole, 5 to 9 around
+    /// the south, or [`SphereCell::NORTH`] & [`SphereCell::SOUTH`]
+    pub face: u8,
+    /// position on the diamond, each coordinate from 0 to `frequency` - 1;
+    /// zero for the poles
+    pub pos: Axial,
+}
+
+impl SphereCell {
+    pub const NORTH: SphereCell = SphereCell::new(10, Axial::ZERO);
+    pub const SOUTH: SphereCell = SphereCell::new(11, Axial::ZERO);
+
+    pub const fn new(face: u8, pos: Axial) -> Self {
+        Self { face, pos }
+    }
+}
+
+// position of a lattice point on the icosahedron, as the weight of each
+// icosahedron corner summing to the frequency; the same point on two faces
+// has the same key
+type Key = [(u8, u32); 3];
+
+/// Hexagons & pentagons covering a sphere; see the [module docs](self).
+/// Positions are on the unit sphere, with the poles on the Y axis.
+#[derive(Debug, Clone)]
+pub struct HexSphere {
+    frequency: u32,
+    cells: Vec<SphereCell>,
+    index: HashMap<SphereCell, u32>,
+    centers: Vec<Vec3>,
+    // counter-clockwise seen from outside
+    neighbors: Vec<Vec<u32>>,
+}
+
+impl HexSphere {
+    /// Sphere with each icosahedron edge split into `frequency` steps, at
+    /// least 1, for `10 * frequency^2 + 2` cells.
+    pub fn new(frequency: u32) -> Self {
+        let n = frequency.max(1);
+        let corners = icosahedron();
+
+        let mut cells = vec![SphereCell::NORTH];
+        let mut keys = vec![key([(0, n), (0, 0), (0, 0)])];
+        for face in 0..10u8 {
+            for r in 0..n {
+                for q in 0..n {
+                    cells.push(SphereCell::new(face, Axial::new(q as i32, r as i32)));
+                    keys.push(lattice(face, n, q + 1, r));
+                }
+            }
+        }
+        cells.push(SphereCell::SOUTH);
+        keys.push(key([(11, n), (0, 0), (0, 0)]));
+
+        let by_key: HashMap<Key, u32> = keys
+            .iter()
+            .enumerate()
+            .map(|(i, &k)| (k, i as u32))
+            .collect();
+        let centers: Vec<Vec3> = keys
+            .iter()
+            .map(|key| {
+                key.iter()
+                    .filter(|&&(_, weight)| weight > 0)
+                    .map(|&(corner, weight)| corners[corner as usize] * weight as f32)
+                    .sum::<Vec3>()
+                    .normalize()
+            })
+            .collect();
+
+        // every edge between lattice points lies on some diamond
+        let mut neighbors = vec![Vec::new(); cells.len()];
+        for face in 0..10u8 {
+            for i in 0..=n {
+                for j in 0..=n {
+                    let a = by_key[&lattice(face, n, i, j)];
+                    let steps = [(i + 1, j), (i, j + 1), (i + 1, j.wrapping_sub(1))];
+                    for (i, j) in steps {
+                        if i > n || j > n {
+                            continue;
+                        }
+                        let b = by_key[&lattice(face, n, i, j)];
+                        if !neighbors[a as usize].contains(&b) {
+                            neighbors[a as usize].push(b);
+                            neighbors[b as usize].push(a);
+                        }
+                    }
+                }
+            }
+        }
+        for (cell, list) in neighbors.iter_mut().enumerate() {
+            let normal = centers[cell];
+            let x = normal.any_orthonormal_vector();
+            let y = normal.cross(x);
+            let angle = |&n: &u32| {
+                let d = centers[n as usize];
+                d.dot(y).atan2(d.dot(x))
+            };
+            list.sort_by(|a, b| angle(a).total_cmp(&angle(b)));
+        }
+
+        let index = cells
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| (c, i as u32))
+            .collect();
+        Self {
+            frequency: n,
+            cells,
+            index,
+            centers,
+            neighbors,
+        }
+    }
+
+    pub fn frequency(&self) -> u32 {
+        self.frequency
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// every cell; the north pole, each diamond in row order, then the
+    /// south pole
+    pub fn cells(&self) -> impl Iterator<Item = SphereCell> + '_ {
+        self.cells.iter().copied()
+    }
+
+    pub fn contains(&self, cell: SphereCell) -> bool {
+        self.index.contains_key(&cell)
+    }
+
+    /// center of a cell on the unit sphere
+    pub fn center(&self, cell: SphereCell) -> Option<Vec3> {
+        Some(self.centers[*self.index.get(&cell)? as usize])
+    }
+
+    /// The five or six neighbors of a cell, counter-clockwise seen from
+    /// outside the sphere; none for cells not on it.
+    pub fn neighbors(&self, cell: SphereCell) -> impl Iterator<Item = SphereCell> + '_ {
+        let list = self
+            .index
+            .get(&cell)
+            .map_or(&[][..], |&i| &self.neighbors[i as usize]);
+        list.iter().map(|&n| self.cells[n as usize])
+    }
+
+    /// true for the twelve cells with five sides
+    pub fn is_pentagon(&self, cell: SphereCell) -> bool {
+        self.neighbors(cell).count() == 5
+    }
+
+    /// Corners of a cell on the unit sphere, counter-clockwise seen from
+    /// outside; corner `i` is between neighbors `i` & `i + 1`.
+    pub fn corners(&self, cell: SphereCell) -> Vec<Vec3> {
+        let Some(&i) = self.index.get(&cell) else {
+            return Vec::new();
+        };
+        let (center, list) = (self.centers[i as usize], &self.neighbors[i as usize]);
+        (0..list.len())
+            .map(|k| {
+                let a = self.centers[list[k] as usize];
+                let b = self.centers[list[(k + 1) % list.len()] as usize];
+                (center + a + b).normalize()
+            })
+            .collect()
+    }
+
+    /// angle between the centers of two cells, in radians; times the radius,
+    /// the great-circle distance between them
+    pub fn angle(&self, a: SphereCell, b: SphereCell) -> Option<f32> {
+        Some(self.center(a)?.angle_between(self.center(b)?))
+    }
+
+    /// cell whose center is nearest the direction `dir` from the center of
+    /// the sphere
+    pub fn cell_at(&self, dir: Vec3) -> SphereCell {
+        // walk toward the direction until no neighbor is nearer; the
+        // nearest center is always reachable this way
+        let dir = dir.normalize_or(Vec3::Y);
+        let mut cell = 0;
+        loop {
+            let here = self.centers[cell].dot(dir);
+            let next = self.neighbors[cell]
+                .iter()
+                .map(|&n| (self.centers[n as usize].dot(dir), n as usize))
+                .max_by(|a, b| a.0.total_cmp(&b.0));
+            match next {
+                Some((nearer, n)) if nearer > here => cell = n,
+                _ => return self.cells[cell],
+            }
+        }
+    }
+
+    /// Cell hit by a world-space ray on a sphere of `radius` at the origin
+    /// of `transform`'s local frame, such as the planet's entity.
+    pub fn ray_cell(
+        &self,
+        ray: Ray3d,
+        transform: &GlobalTransform,
+        radius: f32,
+    ) -> Option<SphereCell> {
+        let to_local = transform.affine().inverse();
+        let origin = to_local.transform_point3(ray.origin);
+        let dir = to_local.transform_vector3(*ray.direction);
+        // |origin + t * dir| = radius; the near side, or the far side from
+        // inside the sphere
+        let (a, b, c) = (
+            dir.dot(dir),
+            2.0 * origin.dot(dir),
+            origin.dot(origin) - radius * radius,
+        );
+        let root = (b * b - 4.0 * a * c).sqrt();
+        let t = [(-b - root) / (2.0 * a), (-b + root) / (2.0 * a)]
+            .into_iter()
+            .find(|t| *t >= 0.0)?;
+        Some(self.cell_at(origin + dir * t))
+    }
+
+    /// Flat grid cell holding `cell`, for keeping its data in a [`HexMap`].
+    /// Diamonds are side by side along q, followed by the poles.
+    pub fn to_axial(&self, cell: SphereCell) -> Axial {
+        let n = self.frequency as i32;
+        Axial::new(cell.face as i32 * n + cell.pos.q, cell.pos.r)
+    }
+
+    /// inverse of [`HexSphere::to_axial`]; `None` for cells not holding one
+    pub fn from_axial(&self, axial: Axial) -> Option<SphereCell> {
+        let n = self.frequency as i32;
+        let face = u8::try_from(axial.q.div_euclid(n)).ok()?;
+        let cell = SphereCell::new(face, Axial::new(axial.q.rem_euclid(n), axial.r));
+        self.contains(cell).then_some(cell)
+    }
+
+    /// values of a [`HexMap`] laid out by [`HexSphere::to_axial`], for every
+    /// cell that has one
+    pub fn values<'a, T>(
+        &'a self,
+        map: &'a HexMap<T>,
+    ) -> impl Iterator<Item = (SphereCell, &'a T)> + 'a {
+        self.cells()
+            .filter_map(|cell| Some((cell, map.get(self.to_axial(cell))?)))
+    }
+
+    /// Triangle list of every cell on a sphere of `radius`, facing out.
+    /// Each cell has its own vertices, in the order of
+    /// [`HexSphere::cells`], so they can be recolored per cell.
+    pub fn mesh(&self, radius: f32) -> Mesh {
+        let mut positions = Vec::new();
+        let mut normals = Vec::new();
+        let mut indices = Vec::new();
+        for cell in self.cells() {
+            let first = positions.len() as u32;
+            let corners = self.corners(cell);
+            for corner in &corners {
+                positions.push((*corner * radius).to_array());
+                normals.push(corner.to_array());
+            }
+            // cells are convex; fan out from the first corner
+            for i in 1..corners.len() as u32 - 1 {
+                indices.extend([first, first + i, first + i + 1]);
+            }
+        }
+
+        Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+        .with_inserted_indices(Indices::U32(indices))
+    }
+}
+
+// corners of the icosahedron: the north pole, five around it, five around
+// the south pole offset half a step, then the south pole
+fn icosahedron() -> [Vec3; 12] {
+    let lat = 0.5f32.atan();
+    let ring = |k: u32, offset: f32, lat: f32| {
+        let lon = (k as f32 + offset) * 2.0 * PI / 5.0;
+        Vec3::new(lat.cos() * lon.cos(), lat.sin(), lat.cos() * lon.sin())
+    };
+    let mut corners = [Vec3::Y; 12];
+    for k in 0..5 {
+        corners[1 + k as usize] = ring(k, 0.0, lat);
+        corners[6 + k as usize] = ring(k, 0.5, -lat);
+    }
+    corners[11] = Vec3::NEG_Y;
+    corners
+}
+
+// canonical form of a key, without the zero weights
+fn key(mut weights: Key) -> Key {
+    for w in &mut weights {
+        if w.1 == 0 {
+            *w = (u8::MAX, 0);
+        }
+    }
+    weights.sort();
+    weights
+}
+
+// Lattice point (i, j) on diamond `face`, each from 0 to `n`.  A diamond is
+// two triangles of the icosahedron sharing the edge from its corner I to J;
+// the cells it holds are those with i > 0 & j < n, so its edges from O to J
+// & J to F, and its far corner F at the pole, belong to its neighbors.
+fn lattice(face: u8, n: u32, i: u32, j: u32) -> Key {
+    let k = face % 5;
+    let (next, lower) = ((k + 1) % 5, face >= 5);
+    // [O, I, J, F]
+    let [o, a, b, f] = if lower {
+        [1 + next, 6 + k, 6 + next, 11]
+    } else {
+        [6 + k, 1 + k, 1 + next, 0]
+    };
+    if i + j <= n {
+        key([(o, n - i - j), (a, i), (b, j)])
+    } else {
+        key([(f, i + j - n), (a, n - j), (b, n - i)])
+    }
+}
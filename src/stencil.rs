@@ -0,0 +1,375 @@
+//! render world side of [`crate::mask`]
+//!
+//! Masks are extracted into a single storage buffer of transforms every
+//! frame.  Each view that draws a grid with a stencil test gets an `R8Uint`
+//! stencil texture from the texture cache, and the masks on the view's render
+//! layers are drawn into it by the grid node, before any grid is drawn.
+use bevy::{
+    camera::visibility::RenderLayers,
+    mesh::MeshVertexBufferLayoutRef,
+    prelude::*,
+    render::{
+        camera::ExtractedCamera,
+        mesh::{allocator::MeshAllocator, RenderMesh, RenderMeshBufferInfo},
+        render_asset::RenderAssets,
+        render_resource::{
+            binding_types::{storage_buffer_read_only, uniform_buffer},
+            BindGroup, BindGroupEntries, BindGroupLayoutDescriptor, BindGroupLayoutEntries,
+            CachedRenderPipelineId, ColorTargetState, ColorWrites, Extent3d, FragmentState, LoadOp,
+            MultisampleState, Operations, PipelineCache, PrimitiveState, PrimitiveTopology,
+            RenderPassColorAttachment, RenderPassDescriptor, RenderPipelineDescriptor,
+            ShaderStages, ShaderType, SpecializedMeshPipeline, SpecializedMeshPipelineError,
+            SpecializedMeshPipelines, StorageBuffer, StoreOp, TextureDescriptor, TextureDimension,
+            TextureFormat, TextureUsages, VertexState,
+        },
+        renderer::{RenderContext, RenderDevice, RenderQueue},
+        texture::{CachedTexture, TextureCache},
+        view::ExtractedView,
+        Extract,
+    },
+};
+
+use crate::{
+    mask::HexGridMask,
+    render::{ExtractedHexGrid, HexGridBindGroupCache, HexGridBuffers, ViewUniform},
+};
+
+const STENCIL_FORMAT: TextureFormat = TextureFormat::R8Uint;
+
+// HexGridMask as passed to the shader
+#[derive(Debug, ShaderType, Clone, Copy)]
+struct GpuMask {
+    transform: Mat4,
+    value: u32,
+}
+
+struct ExtractedMask {
+    mesh: AssetId<Mesh>,
+    layers: RenderLayers,
+}
+
+/// masks extracted this frame; `buffer` holds the transform & value of each
+/// mask, in the same order
+#[derive(Resource, Default)]
+pub(crate) struct HexGridMasks {
+    masks: Vec<ExtractedMask>,
+    // never empty; storage buffers can't be zero sized
+    buffer: StorageBuffer<Vec<GpuMask>>,
+}
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn extract_hex_grid_masks(
+    mut masks: ResMut<HexGridMasks>,
+    query: Extract<
+        Query<(
+            &Mesh3d,
+            &GlobalTransform,
+            &HexGridMask,
+            &InheritedVisibility,
+            Option<&RenderLayers>,
+        )>,
+    >,
+) {
+    let masks = &mut *masks;
+    masks.masks.clear();
+    let buffer = masks.buffer.get_mut();
+    buffer.clear();
+    for (mesh, transform, mask, visibility, layers) in &query {
+        if !visibility.get() {
+            continue;
+        }
+        masks.masks.push(ExtractedMask {
+            mesh: mesh.id(),
+            layers: layers.cloned().unwrap_or_default(),
+        });
+        buffer.push(GpuMask {
+            transform: transform.to_matrix(),
+            value: mask.0 as u32,
+        });
+    }
+    if buffer.is_empty() {
+        buffer.push(GpuMask {
+            transform: Mat4::ZERO,
+            value: 0,
+        });
+    }
+}
+
+/// stencil texture & masks drawn by a view; only present on views that draw
+/// a grid with a stencil test
+#[derive(Component)]
+pub(crate) struct ViewHexGridStencil {
+    pub texture: CachedTexture,
+    // pipeline & mask index of each mask drawn by the view
+    draws: Vec<(CachedRenderPipelineId, u32)>,
+    bind_group: Option<BindGroup>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn prepare_hex_grid_stencils(
+    mut commands: Commands,
+    mut masks: ResMut<HexGridMasks>,
+    mut texture_cache: ResMut<TextureCache>,
+    pipeline: Res<HexGridMaskPipeline>,
+    mut pipelines: ResMut<SpecializedMeshPipelines<HexGridMaskPipeline>>,
+    pipeline_cache: Res<PipelineCache>,
+    meshes: Res<RenderAssets<RenderMesh>>,
+    grids: Query<&ExtractedHexGrid>,
+    views: Query<(Entity, &ExtractedCamera, Option<&RenderLayers>), With<ExtractedView>>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    masks.buffer.write_buffer(&render_device, &render_queue);
+
+    for (view, camera, view_layers) in &views {
+        let view_layers = view_layers.cloned().unwrap_or_default();
+        let stenciled = grids
+            .iter()
+            .any(|grid| grid.config.stencil.is_some() && view_layers.intersects(&grid.layers));
+        let (true, Some(size)) = (stenciled, camera.physical_target_size) else {
+            commands.entity(view).remove::<ViewHexGridStencil>();
+            continue;
+        };
+
+        let texture = texture_cache.get(
+            &render_device,
+            TextureDescriptor {
+                label: Some("hex_grid_stencil_texture"),
+                size: Extent3d {
+                    width: size.x,
+                    height: size.y,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: STENCIL_FORMAT,
+                usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+        );
+
+        // masks are not frustum culled; there are usually only a few
+        let draws = masks
+            .masks
+            .iter()
+            .enumerate()
+            .filter(|(_, mask)| view_layers.intersects(&mask.layers))
+            .filter_map(|(index, mask)| {
+                let mesh = meshes.get(mask.mesh)?;
+                match pipelines.specialize(
+                    &pipeline_cache,
+                    &pipeline,
+                    mesh.primitive_topology(),
+                    &mesh.layout,
+                ) {
+                    Ok(id) => Some((id, index as u32)),
+                    Err(err) => {
+                        warn_once!("can't draw hex grid mask mesh {:?}: {}", mask.mesh, err);
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        commands.entity(view).insert(ViewHexGridStencil {
+            texture,
+            draws,
+            bind_group: None,
+        });
+    }
+}
+
+pub(crate) fn queue_hex_grid_stencil_bind_groups(
+    mut views: Query<(Entity, &mut ViewHexGridStencil)>,
+    masks: Res<HexGridMasks>,
+    buffers: Res<HexGridBuffers>,
+    mut cache: ResMut<HexGridBindGroupCache>,
+    pipeline: Res<HexGridMaskPipeline>,
+    pipeline_cache: Res<PipelineCache>,
+    render_device: Res<RenderDevice>,
+) {
+    let layout = pipeline_cache.get_bind_group_layout(&pipeline.layout);
+    for (view, mut stencil) in &mut views {
+        let Some(masks_buffer) = masks.buffer.buffer() else {
+            continue;
+        };
+        // binding 0 is left out of the layout when the view is pushed; the
+        // masks stand in for it, and are skipped
+        let view_buffer = match buffers.view(view).and_then(|b| b.buffer()) {
+            Some(buffer) => buffer,
+            None if pipeline.push_constants => masks_buffer,
+            None => continue,
+        };
+        let skip = usize::from(pipeline.push_constants);
+        let ids = [view_buffer.into(), masks_buffer.into()];
+        stencil.bind_group = Some(cache.get_or_create(&layout, &ids[skip..], || {
+            let entries = BindGroupEntries::sequential((
+                view_buffer.as_entire_binding(),
+                masks_buffer.as_entire_binding(),
+            ));
+            render_device.create_bind_group("hex_grid_mask_bind_group", &layout, &entries[skip..])
+        }));
+    }
+}
+
+/// clear the view's stencil texture & draw its masks into it
+pub(crate) fn draw_hex_grid_masks(
+    render_context: &mut RenderContext,
+    camera: &ExtractedCamera,
+    stencil: &ViewHexGridStencil,
+    view_constants: Option<&[u8]>,
+    world: &World,
+) {
+    let pipeline_cache = world.resource::<PipelineCache>();
+    let masks = world.resource::<HexGridMasks>();
+    let meshes = world.resource::<RenderAssets<RenderMesh>>();
+    let allocator = world.resource::<MeshAllocator>();
+
+    let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+        label: Some("hex_grid_mask_pass"),
+        color_attachments: &[Some(RenderPassColorAttachment {
+            view: &stencil.texture.default_view,
+            depth_slice: None,
+            resolve_target: None,
+            ops: Operations {
+                load: LoadOp::Clear(default()),
+                store: StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+    let Some(bind_group) = &stencil.bind_group else {
+        return;
+    };
+    if let Some(viewport) = camera.viewport.as_ref() {
+        render_pass.set_camera_viewport(viewport);
+    }
+    render_pass.set_bind_group(0, bind_group, &[]);
+
+    for &(pipeline_id, index) in &stencil.draws {
+        let Some(pipeline) = pipeline_cache.get_render_pipeline(pipeline_id) else {
+            continue;
+        };
+        let mesh_id = &masks.masks[index as usize].mesh;
+        let Some(mesh) = meshes.get(*mesh_id) else {
+            continue;
+        };
+        let Some(vertices) = allocator.mesh_vertex_slice(mesh_id) else {
+            continue;
+        };
+
+        render_pass.set_render_pipeline(pipeline);
+        if let Some(constants) = view_constants {
+            render_pass.set_push_constants(ShaderStages::VERTEX, 0, constants);
+        }
+        render_pass.set_vertex_buffer(0, vertices.buffer.slice(..));
+        // the instance index selects the mask transform in the shader
+        match &mesh.buffer_info {
+            RenderMeshBufferInfo::Indexed {
+                count,
+                index_format,
+            } => {
+                let Some(indices) = allocator.mesh_index_slice(mesh_id) else {
+                    continue;
+                };
+                render_pass.set_index_buffer(indices.buffer.slice(..), *index_format);
+                render_pass.draw_indexed(
+                    indices.range.start..indices.range.start + count,
+                    vertices.range.start as i32,
+                    index..index + 1,
+                );
+            }
+            RenderMeshBufferInfo::NonIndexed => {
+                render_pass.draw(vertices.range, index..index + 1);
+            }
+        }
+    }
+}
+
+#[derive(Resource)]
+pub(crate) struct HexGridMaskPipeline {
+    shader: Handle<Shader>,
+    layout: BindGroupLayoutDescriptor,
+    /// view is passed in push constants; see [`ViewUniform::push_constants`]
+    push_constants: bool,
+}
+
+impl FromWorld for HexGridMaskPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let shader = world.resource::<AssetServer>().load("hex_grid_mask.wgsl");
+
+        let push_constants = ViewUniform::push_constants(world.resource::<RenderDevice>());
+        let entries = BindGroupLayoutEntries::sequential(
+            ShaderStages::VERTEX,
+            (
+                uniform_buffer::<ViewUniform>(false),
+                storage_buffer_read_only::<Vec<GpuMask>>(false),
+            ),
+        );
+        // the view is left out when it's pushed
+        let layout = BindGroupLayoutDescriptor::new(
+            "hex_grid_mask_bind_group_layout",
+            &entries[usize::from(push_constants)..],
+        );
+
+        Self {
+            shader,
+            layout,
+            push_constants,
+        }
+    }
+}
+
+impl SpecializedMeshPipeline for HexGridMaskPipeline {
+    type Key = PrimitiveTopology;
+
+    fn specialize(
+        &self,
+        key: Self::Key,
+        layout: &MeshVertexBufferLayoutRef,
+    ) -> Result<RenderPipelineDescriptor, SpecializedMeshPipelineError> {
+        let vertex_layout = layout
+            .0
+            .get_layout(&[Mesh::ATTRIBUTE_POSITION.at_shader_location(0)])?;
+        let (shader_defs, push_constant_ranges) = if self.push_constants {
+            let range = ViewUniform::push_constant_range(ShaderStages::VERTEX);
+            (vec!["VIEW_PUSH_CONSTANTS".into()], vec![range])
+        } else {
+            (vec![], vec![])
+        };
+
+        Ok(RenderPipelineDescriptor {
+            label: Some("hex_grid_mask_pipeline".into()),
+            layout: vec![self.layout.clone()],
+            push_constant_ranges,
+            vertex: VertexState {
+                shader: self.shader.clone(),
+                shader_defs: shader_defs.clone(),
+                entry_point: Some("vertex".into()),
+                buffers: vec![vertex_layout],
+            },
+            // masks are drawn from both sides
+            primitive: PrimitiveState {
+                topology: key,
+                cull_mode: None,
+                ..default()
+            },
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            fragment: Some(FragmentState {
+                shader: self.shader.clone(),
+                shader_defs,
+                entry_point: Some("fragment".into()),
+                targets: vec![Some(ColorTargetState {
+                    format: STENCIL_FORMAT,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            zero_initialize_workgroup_memory: false,
+        })
+    }
+}
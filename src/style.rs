@@ -0,0 +1,115 @@
+//! grid style assets
+//!
+//! A [`HexGridStyle`] holds the look of a grid (line & cursor colors and
+//! widths, antialiasing, parity tint, superhex borders) apart from its
+//! layout.  Add a [`HexGridStyleHandle`] to a grid entity to draw it with a
+//! style; fields set in the style override the entity's
+//! [`HexGridConfig`], and the rest are left alone.  The style is looked up
+//! every frame when the grid is extracted, so edits to a loaded style show
+//! up as soon as the asset changes.
+//!
+//! With the `serde` feature, styles are loaded from `.hexgrid.ron` files,
+//! and hot-reloaded when the asset server watches for changes:
+//!
+//! ```ron
+//! (
+//!     line_color: Srgba((red: 0.2, green: 0.2, blue: 0.25, alpha: 0.8)),
+//!     line_width_mode: Pixels(1.5),
+//!     antialiasing: Fwidth,
+//! )
+//! ```
+//!
+//! Optional fields may be written without `Some(..)`.
+use bevy::prelude::*;
+
+use crate::{HexGridConfig, LineAntialiasing, LineWidthMode, SuperHexBorders};
+
+/// Appearance of a grid.  Fields left `None` keep the value from the grid's
+/// [`HexGridConfig`].
+#[derive(Asset, TypePath, Debug, Default, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct HexGridStyle {
+    pub line_color: Option<Color>,
+    pub line_width: Option<f32>,
+    pub line_width_mode: Option<LineWidthMode>,
+    pub antialiasing: Option<LineAntialiasing>,
+    pub cursor_color: Option<Color>,
+    pub cursor_line_width: Option<f32>,
+    pub cursor_edge_color: Option<Color>,
+    pub cursor_edge_distance: Option<f32>,
+    /// turns parity tinting on with these colors
+    pub parity_tint: Option<[Color; 3]>,
+    /// turns superhex borders on
+    pub superhex_borders: Option<SuperHexBorders>,
+}
+
+impl HexGridStyle {
+    /// override the fields of `config` set in this style
+    pub fn apply(&self, config: &mut HexGridConfig) {
+        fn set<T: Clone>(dst: &mut T, src: &Option<T>) {
+            if let Some(src) = src {
+                *dst = src.clone();
+            }
+        }
+        set(&mut config.line_color, &self.line_color);
+        set(&mut config.line_width, &self.line_width);
+        set(&mut config.line_width_mode, &self.line_width_mode);
+        set(&mut config.antialiasing, &self.antialiasing);
+        set(&mut config.cursor_color, &self.cursor_color);
+        set(&mut config.cursor_line_width, &self.cursor_line_width);
+        set(&mut config.cursor_edge_color, &self.cursor_edge_color);
+        set(&mut config.cursor_edge_distance, &self.cursor_edge_distance);
+        if self.parity_tint.is_some() {
+            config.parity_tint = self.parity_tint;
+        }
+        if self.superhex_borders.is_some() {
+            config.superhex_borders = self.superhex_borders;
+        }
+    }
+}
+
+/// [`HexGridStyle`] a grid entity is drawn with
+#[derive(Component, Debug, Default, Clone, PartialEq, Deref)]
+pub struct HexGridStyleHandle(pub Handle<HexGridStyle>);
+
+/// parse a style from RON
+#[cfg(feature = "serde")]
+pub fn parse_style(text: &str) -> anyhow::Result<HexGridStyle> {
+    use ron::extensions::Extensions;
+
+    let options = ron::Options::default().with_default_extension(Extensions::IMPLICIT_SOME);
+    Ok(options.from_str(text)?)
+}
+
+#[cfg(feature = "serde")]
+#[derive(Default, TypePath)]
+pub struct HexGridStyleLoader;
+
+#[cfg(feature = "serde")]
+impl bevy::asset::AssetLoader for HexGridStyleLoader {
+    type Asset = HexGridStyle;
+    type Settings = ();
+    type Error = anyhow::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn bevy::asset::io::Reader,
+        _settings: &(),
+        load_context: &mut bevy::asset::LoadContext<'_>,
+    ) -> Result<HexGridStyle, anyhow::Error> {
+        use anyhow::Context;
+
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let text = std::str::from_utf8(&bytes).context("style file is not utf-8")?;
+        parse_style(text).with_context(|| format!("failed to load {}", load_context.path()))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["hexgrid.ron"]
+    }
+}
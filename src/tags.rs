@@ -0,0 +1,230 @@
+//! named tags on cells
+//!
+//! [`HexTags`] attaches flags such as `"water"` or `"spawn"` to cells, so
+//! designers can mark cells for gameplay rules without adding a typed layer
+//! for each.  Each cell's tags are kept as a bitmask, so a cell costs one
+//! word however many tags it has, and queries test every tag at once:
+//!
+//! ```ignore
+//! let mut tags = HexTags::new();
+//! tags.tag(cell, "water");
+//! tags.tag(cell, "deep");
+//!
+//! let boats: Vec<Axial> = tags.cells_with_all(["water", "deep"]).collect();
+//! let landings = TagQuery::new().all(["shore"]).none(["cliff", "reef"]);
+//! for cell in tags.cells_matching(&landings) { ... }
+//! ```
+//!
+//! Anything that names a tag takes a `&str`; an enum of tags can implement
+//! `AsRef<str>` to be used in their place.
+use bevy::prelude::*;
+
+use crate::{Axial, HexMap};
+
+/// Named tags on cells; see the [module docs](self).  Add to a grid entity
+/// to keep its tags with it.
+#[derive(Component, Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HexTags {
+    /// name of each tag, indexed by its bit
+    names: Vec<String>,
+    cells: HexMap<u64>,
+}
+
+impl HexTags {
+    /// distinct tag names a map can hold
+    pub const MAX_TAGS: usize = 64;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a tag to a cell, returning false if it already had it.  Panics
+    /// when adding a new name past [`HexTags::MAX_TAGS`].
+    pub fn tag(&mut self, cell: Axial, tag: impl AsRef<str>) -> bool {
+        let bit = 1 << self.bit_or_insert(tag.as_ref());
+        let mask = self.cells.get_or_insert_with(cell, || 0);
+        let added = *mask & bit == 0;
+        *mask |= bit;
+        added
+    }
+
+    /// remove a tag from a cell, returning false if it didn't have it
+    pub fn untag(&mut self, cell: Axial, tag: impl AsRef<str>) -> bool {
+        let Some(bit) = self.mask(&[tag]) else {
+            return false;
+        };
+        let Some(mask) = self.cells.get_mut(cell) else {
+            return false;
+        };
+        let found = *mask & bit != 0;
+        *mask &= !bit;
+        if *mask == 0 {
+            self.cells.remove(cell);
+        }
+        found
+    }
+
+    /// remove every tag from a cell
+    pub fn clear_cell(&mut self, cell: Axial) {
+        self.cells.remove(cell);
+    }
+
+    pub fn has(&self, cell: Axial, tag: impl AsRef<str>) -> bool {
+        self.mask(&[tag])
+            .is_some_and(|bit| self.cell_mask(cell) & bit != 0)
+    }
+
+    /// names of the tags on a cell
+    pub fn tags(&self, cell: Axial) -> impl Iterator<Item = &str> + '_ {
+        let mask = self.cell_mask(cell);
+        self.names
+            .iter()
+            .enumerate()
+            .filter(move |(bit, _)| mask & (1 << bit) != 0)
+            .map(|(_, name)| name.as_str())
+    }
+
+    /// every tag name used so far, in the order first used
+    pub fn names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.names.iter().map(String::as_str)
+    }
+
+    /// every cell with at least one tag
+    pub fn cells(&self) -> impl Iterator<Item = Axial> + '_ {
+        self.cells.cells()
+    }
+
+    /// cells with `tag`
+    pub fn cells_with(&self, tag: impl AsRef<str>) -> impl Iterator<Item = Axial> + '_ {
+        self.cells_with_any([tag])
+    }
+
+    /// cells with every one of `tags`
+    pub fn cells_with_all<S: AsRef<str>>(
+        &self,
+        tags: impl IntoIterator<Item = S>,
+    ) -> impl Iterator<Item = Axial> + '_ {
+        self.cells_matching(&TagQuery::new().all(tags))
+    }
+
+    /// cells with any of `tags`
+    pub fn cells_with_any<S: AsRef<str>>(
+        &self,
+        tags: impl IntoIterator<Item = S>,
+    ) -> impl Iterator<Item = Axial> + '_ {
+        self.cells_matching(&TagQuery::new().any(tags))
+    }
+
+    /// true if the tags on `cell` match `query`
+    pub fn matches(&self, cell: Axial, query: &TagQuery) -> bool {
+        self.compile(query)
+            .is_some_and(|q| q.matches(self.cell_mask(cell)))
+    }
+
+    /// every tagged cell matching `query`
+    pub fn cells_matching(&self, query: &TagQuery) -> impl Iterator<Item = Axial> + '_ {
+        let compiled = self.compile(query);
+        self.cells
+            .iter()
+            .filter(move |(_, &mask)| compiled.is_some_and(|q| q.matches(mask)))
+            .map(|(cell, _)| cell)
+    }
+
+    fn cell_mask(&self, cell: Axial) -> u64 {
+        self.cells.get(cell).copied().unwrap_or(0)
+    }
+
+    fn bit(&self, name: &str) -> Option<u8> {
+        self.names.iter().position(|n| n == name).map(|i| i as u8)
+    }
+
+    fn bit_or_insert(&mut self, name: &str) -> u8 {
+        if let Some(bit) = self.bit(name) {
+            return bit;
+        }
+        assert!(
+            self.names.len() < Self::MAX_TAGS,
+            "more than {} tag names",
+            Self::MAX_TAGS
+        );
+        self.names.push(name.to_owned());
+        self.names.len() as u8 - 1
+    }
+
+    // mask of every one of `tags`; `None` if any has never been used
+    fn mask<S: AsRef<str>>(&self, tags: &[S]) -> Option<u64> {
+        tags.iter()
+            .try_fold(0, |mask, tag| Some(mask | 1 << self.bit(tag.as_ref())?))
+    }
+
+    // `None` when the query can't match any cell
+    fn compile(&self, query: &TagQuery) -> Option<Compiled> {
+        let all = self.mask(&query.all)?;
+        let known = |tags: &[String]| {
+            tags.iter()
+                .filter_map(|tag| self.bit(tag))
+                .fold(0, |mask, bit| mask | 1 << bit)
+        };
+        let any = known(&query.any);
+        if !query.any.is_empty() && any == 0 {
+            return None;
+        }
+        Some(Compiled {
+            all,
+            any: (!query.any.is_empty()).then_some(any),
+            none: known(&query.none),
+        })
+    }
+}
+
+/// Cells to find by their tags: those with every tag of [`TagQuery::all`],
+/// at least one of [`TagQuery::any`], & none of [`TagQuery::none`].  Calls
+/// add to the tags already given.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TagQuery {
+    pub all: Vec<String>,
+    pub any: Vec<String>,
+    pub none: Vec<String>,
+}
+
+impl TagQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn all<S: AsRef<str>>(mut self, tags: impl IntoIterator<Item = S>) -> Self {
+        self.all
+            .extend(tags.into_iter().map(|t| t.as_ref().to_owned()));
+        self
+    }
+
+    pub fn any<S: AsRef<str>>(mut self, tags: impl IntoIterator<Item = S>) -> Self {
+        self.any
+            .extend(tags.into_iter().map(|t| t.as_ref().to_owned()));
+        self
+    }
+
+    pub fn none<S: AsRef<str>>(mut self, tags: impl IntoIterator<Item = S>) -> Self {
+        self.none
+            .extend(tags.into_iter().map(|t| t.as_ref().to_owned()));
+        self
+    }
+}
+
+// query as bitmasks for one map
+#[derive(Clone, Copy)]
+struct Compiled {
+    all: u64,
+    any: Option<u64>,
+    none: u64,
+}
+
+impl Compiled {
+    fn matches(self, mask: u64) -> bool {
+        mask & self.all == self.all
+            && self.any.is_none_or(|any| mask & any != 0)
+            && mask & self.none == 0
+    }
+}
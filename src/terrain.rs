@@ -0,0 +1,151 @@
+//! textured terrain fills
+//!
+//! Register a tiling texture for each kind of terrain (grass, sand, water)
+//! with the [`crate::HexGridDecalAtlas`], then add a [`HexGridTerrain`] to a
+//! grid entity to fill each cell with the texture of its terrain.  Textures
+//! are tiled across the grid plane, so neighboring cells of the same terrain
+//! join seamlessly.
+//!
+//! Where two terrains meet, the one with the higher [`Terrain::priority`]
+//! spills over the edge into its neighbor, fading out over
+//! [`HexGridTerrain::blend_width`]; terrains of equal priority are blended
+//! evenly on either side of the edge.  Terrain is drawn under territory,
+//! highlights, decals, & grid lines.
+//!
+//! A cell can show a [`TileVariant`] instead of its terrain's texture, such
+//! as a stretch of shore, placed by hand or picked from its neighbors by a
+//! [`crate::HexGridAutoTile`].
+use bevy::prelude::*;
+
+use crate::{decal::DecalId, Axial, HexMap};
+
+/// kind of terrain on a cell; index into [`HexGridTerrain::kinds`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TerrainId(pub u8);
+
+/// a kind of terrain, drawn as a texture tiled across its cells
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Terrain {
+    pub texture: DecalId,
+    /// multiplied with the texture
+    pub color: Color,
+    /// width of one repeat of the texture, as a multiple of the cell size
+    pub scale: f32,
+    /// terrain with a higher priority is drawn over the edge into neighbors
+    /// with a lower one
+    pub priority: i32,
+}
+
+impl Terrain {
+    pub fn new(texture: DecalId) -> Self {
+        Self {
+            texture,
+            color: Color::WHITE,
+            scale: 1.0,
+            priority: 0,
+        }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+/// Texture drawn over a single cell in place of its terrain's tiled one.
+/// The texture of the kind is stretched over the cell from corner to
+/// corner, with its right edge facing the neighbor in direction 0 of
+/// [`Axial::NEIGHBORS`], then turned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileVariant {
+    /// kind whose texture & color are drawn; its priority & scale are
+    /// ignored
+    pub kind: TerrainId,
+    /// sixths of a turn, toward increasing [`Axial::NEIGHBORS`] index
+    pub rotation: u8,
+}
+
+/// Terrain of each cell on a grid entity.  Cells without terrain, or with a
+/// [`TerrainId`] past the end of `kinds`, keep the parity tint.
+#[derive(Component, Debug, Clone)]
+pub struct HexGridTerrain {
+    pub kinds: Vec<Terrain>,
+    pub cells: HexMap<TerrainId>,
+    /// variant drawn on a cell with terrain; its terrain still decides how
+    /// it blends with its neighbors
+    pub variants: HexMap<TileVariant>,
+    /// distance from an edge a higher priority terrain spills into its
+    /// neighbor, as a fraction of the cell size; 0 for hard edges
+    pub blend_width: f32,
+}
+
+impl Default for HexGridTerrain {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl HexGridTerrain {
+    pub fn new(kinds: impl IntoIterator<Item = Terrain>) -> Self {
+        Self {
+            kinds: kinds.into_iter().collect(),
+            cells: HexMap::new(),
+            variants: HexMap::new(),
+            blend_width: 0.15,
+        }
+    }
+
+    pub fn with_blend_width(mut self, blend_width: f32) -> Self {
+        self.blend_width = blend_width;
+        self
+    }
+
+    /// terrain of a cell
+    pub fn get(&self, cell: Axial) -> Option<&Terrain> {
+        let id = self.cells.get(cell)?;
+        self.kinds.get(id.0 as usize)
+    }
+
+    /// Weight of the terrain of `other` at a point in `cell`, `edge_dist`
+    /// from the edge between them; the texture of `cell` gets the rest.
+    /// Mirrors `terrain_color()` in the shader.
+    pub fn blend_weight(&self, cell: Axial, other: Axial, edge_dist: f32) -> f32 {
+        let (Some(own), Some(theirs)) = (self.get(cell), self.get(other)) else {
+            return 0.0;
+        };
+        terrain_weight(own.priority, theirs.priority, edge_dist, self.blend_width)
+    }
+}
+
+// weight of a neighbor's terrain `edge_dist` from the edge; higher priority
+// terrain covers the whole blend width, equal priority meets halfway
+fn terrain_weight(own: i32, other: i32, edge_dist: f32, blend_width: f32) -> f32 {
+    if other < own || edge_dist >= blend_width {
+        return 0.0;
+    }
+    let fade = 1.0 - smoothstep(edge_dist / blend_width);
+    if other == own {
+        fade * 0.5
+    } else {
+        fade
+    }
+}
+
+// smoothstep(0, 1, x), as in wgsl
+fn smoothstep(x: f32) -> f32 {
+    let x = x.clamp(0.0, 1.0);
+    x * x * (3.0 - 2.0 * x)
+}
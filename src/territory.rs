@@ -0,0 +1,134 @@
+//! cell ownership & team coloring
+//!
+//! Add [`HexGridOwners`] & a [`HexGridPalette`] to a grid entity to shade
+//! each cell by the team that owns it.  Where two territories meet, their
+//! colors are blended over [`HexGridPalette::border_width`] on either side of
+//! the edge.  Territory is drawn over the parity tint, and under highlights,
+//! decals, & grid lines.
+//!
+//! Owners are kept in a texture on the gpu.  Only the cells that changed
+//! since the last frame are written to it; the whole map is uploaded when the
+//! owners are first added, or when a cell outside the texture is claimed.
+use bevy::prelude::*;
+
+use crate::{Axial, HexMap};
+
+/// team owning a cell; index into [`HexGridPalette::colors`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CellOwner(pub u8);
+
+/// Owner of each cell on a grid entity.  Changes are tracked per cell, so
+/// claiming a few cells a frame doesn't re-upload the map.
+#[derive(Component, Debug, Clone)]
+pub struct HexGridOwners {
+    owners: HexMap<CellOwner>,
+    /// cells changed since the last frame
+    changed: Vec<Axial>,
+    /// the whole map must be uploaded
+    reset: bool,
+}
+
+impl Default for HexGridOwners {
+    fn default() -> Self {
+        Self::new(HexMap::new())
+    }
+}
+
+impl HexGridOwners {
+    pub fn new(owners: HexMap<CellOwner>) -> Self {
+        Self {
+            owners,
+            changed: Vec::new(),
+            reset: true,
+        }
+    }
+
+    pub fn get(&self, cell: Axial) -> Option<CellOwner> {
+        self.owners.get(cell).copied()
+    }
+
+    /// set the owner of a cell, returning the previous owner
+    pub fn set(&mut self, cell: Axial, owner: CellOwner) -> Option<CellOwner> {
+        let prev = self.owners.insert(cell, owner);
+        if prev != Some(owner) {
+            self.changed.push(cell);
+        }
+        prev
+    }
+
+    /// make a cell unowned, returning the previous owner
+    pub fn remove(&mut self, cell: Axial) -> Option<CellOwner> {
+        let prev = self.owners.remove(cell);
+        if prev.is_some() {
+            self.changed.push(cell);
+        }
+        prev
+    }
+
+    pub fn clear(&mut self) {
+        self.changed.extend(self.owners.cells());
+        self.owners.clear();
+    }
+
+    pub fn as_map(&self) -> &HexMap<CellOwner> {
+        &self.owners
+    }
+
+    /// cells whose owner changed since the last frame
+    pub fn changed(&self) -> &[Axial] {
+        &self.changed
+    }
+
+    /// true until the first frame after the owners were created
+    pub(crate) fn is_reset(&self) -> bool {
+        self.reset
+    }
+}
+
+/// Team colors for a grid entity with [`HexGridOwners`].  Cells owned by a
+/// team without a color, or past [`HexGridPalette::MAX_TEAMS`], are drawn as
+/// unowned.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HexGridPalette {
+    /// color of each team, indexed by [`CellOwner`]; alpha blended over the
+    /// cell
+    pub colors: Vec<Color>,
+    /// distance from an edge over which neighboring territories are
+    /// blended, as a fraction of the cell size; 0 for hard borders
+    pub border_width: f32,
+}
+
+impl HexGridPalette {
+    pub const MAX_TEAMS: usize = 64;
+
+    pub fn new(colors: impl IntoIterator<Item = Color>) -> Self {
+        Self {
+            colors: colors.into_iter().collect(),
+            ..default()
+        }
+    }
+}
+
+impl Default for HexGridPalette {
+    fn default() -> Self {
+        Self {
+            colors: Vec::new(),
+            border_width: 0.1,
+        }
+    }
+}
+
+/// forget the changes extracted last frame; runs in `First`, after the
+/// render world has extracted them
+pub(crate) fn clear_owner_changes(mut owners: Query<&mut HexGridOwners>) {
+    for mut owners in &mut owners {
+        if owners.reset || !owners.changed.is_empty() {
+            let owners = owners.bypass_change_detection();
+            owners.changed.clear();
+            owners.reset = false;
+        }
+    }
+}
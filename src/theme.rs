@@ -0,0 +1,290 @@
+//! named grid themes
+//!
+//! A theme is a [`HexGridStyle`] registered under a name in the
+//! [`HexGridThemes`] resource.  Set [`HexGridConfig::theme`] to draw a grid
+//! with one; the built-in themes are [`HexGridTheme::BLUEPRINT`],
+//! [`HexGridTheme::TACTICAL`], [`HexGridTheme::MINIMAL`], &
+//! [`HexGridTheme::NEON`].  Register custom themes at startup, or load them
+//! as style assets & register them once loaded:
+//!
+//! ```ignore
+//! themes.register(HexGridTheme::new("parchment"), style);
+//! ```
+//!
+//! Changing the theme in the config switches at once; crossfade to a new
+//! theme with [`HexGridThemeCommands::crossfade_theme`]:
+//!
+//! ```ignore
+//! commands
+//!     .entity(grid)
+//!     .crossfade_theme(Some(HexGridTheme::NEON), 0.5);
+//! ```
+//!
+//! A grid's [`crate::HexGridStyleHandle`] is applied over its theme.
+use std::borrow::Cow;
+
+use bevy::{platform::collections::HashMap, prelude::*};
+
+use crate::{HexGridConfig, HexGridStyle, LineAntialiasing, LineWidthMode, SuperHexBorders};
+
+/// name of a theme in [`HexGridThemes`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HexGridTheme(pub Cow<'static, str>);
+
+impl HexGridTheme {
+    /// white lines on blue paper
+    pub const BLUEPRINT: Self = Self(Cow::Borrowed("blueprint"));
+    /// muted lines with superhex borders, for planning moves
+    pub const TACTICAL: Self = Self(Cow::Borrowed("tactical"));
+    /// thin, faint lines that stay out of the way
+    pub const MINIMAL: Self = Self(Cow::Borrowed("minimal"));
+    /// bright lines on a dark tint
+    pub const NEON: Self = Self(Cow::Borrowed("neon"));
+
+    pub fn new(name: impl Into<Cow<'static, str>>) -> Self {
+        Self(name.into())
+    }
+}
+
+/// Themes by name, starting with the built-in ones.  Grids with a theme that
+/// isn't registered are drawn without one.
+#[derive(Resource, Debug, Clone)]
+pub struct HexGridThemes {
+    themes: HashMap<HexGridTheme, HexGridStyle>,
+}
+
+impl Default for HexGridThemes {
+    fn default() -> Self {
+        let srgba = Color::srgba;
+        let themes = [
+            (
+                HexGridTheme::BLUEPRINT,
+                HexGridStyle {
+                    line_color: Some(srgba(0.85, 0.92, 1.0, 0.85)),
+                    line_width: Some(0.03),
+                    antialiasing: Some(LineAntialiasing::Fwidth),
+                    cursor_color: Some(Color::WHITE),
+                    cursor_edge_color: Some(srgba(0.55, 0.85, 1.0, 1.0)),
+                    parity_tint: Some([
+                        srgba(0.08, 0.25, 0.55, 0.7),
+                        srgba(0.1, 0.28, 0.58, 0.7),
+                        srgba(0.06, 0.22, 0.5, 0.7),
+                    ]),
+                    ..default()
+                },
+            ),
+            (
+                HexGridTheme::TACTICAL,
+                HexGridStyle {
+                    line_color: Some(srgba(0.55, 0.6, 0.45, 0.7)),
+                    line_width: Some(0.05),
+                    cursor_color: Some(srgba(1.0, 0.7, 0.1, 1.0)),
+                    cursor_edge_color: Some(srgba(1.0, 0.35, 0.1, 1.0)),
+                    superhex_borders: Some(SuperHexBorders {
+                        radius: 2,
+                        color: srgba(0.75, 0.8, 0.6, 0.6),
+                        width: 0.08,
+                    }),
+                    ..default()
+                },
+            ),
+            (
+                HexGridTheme::MINIMAL,
+                HexGridStyle {
+                    line_color: Some(srgba(0.5, 0.5, 0.5, 0.25)),
+                    line_width_mode: Some(LineWidthMode::Pixels(1.0)),
+                    antialiasing: Some(LineAntialiasing::Fwidth),
+                    cursor_color: Some(srgba(1.0, 1.0, 1.0, 0.6)),
+                    cursor_line_width: Some(0.1),
+                    cursor_edge_color: Some(srgba(1.0, 1.0, 1.0, 0.8)),
+                    ..default()
+                },
+            ),
+            (
+                HexGridTheme::NEON,
+                HexGridStyle {
+                    line_color: Some(srgba(1.0, 0.1, 0.8, 1.0)),
+                    line_width: Some(0.04),
+                    antialiasing: Some(LineAntialiasing::Fwidth),
+                    cursor_color: Some(srgba(0.1, 1.0, 1.0, 1.0)),
+                    cursor_edge_color: Some(srgba(1.0, 1.0, 0.2, 1.0)),
+                    parity_tint: Some([
+                        srgba(0.06, 0.0, 0.12, 0.6),
+                        srgba(0.08, 0.0, 0.15, 0.6),
+                        srgba(0.04, 0.0, 0.1, 0.6),
+                    ]),
+                    ..default()
+                },
+            ),
+        ];
+        Self {
+            themes: themes.into_iter().collect(),
+        }
+    }
+}
+
+impl HexGridThemes {
+    /// add or replace a theme, returning the style it had
+    pub fn register(&mut self, theme: HexGridTheme, style: HexGridStyle) -> Option<HexGridStyle> {
+        self.themes.insert(theme, style)
+    }
+
+    pub fn get(&self, theme: &HexGridTheme) -> Option<&HexGridStyle> {
+        self.themes.get(theme)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&HexGridTheme, &HexGridStyle)> {
+        self.themes.iter()
+    }
+
+    /// `config` with `theme` applied
+    pub fn apply(&self, config: &HexGridConfig, theme: Option<&HexGridTheme>) -> HexGridConfig {
+        let mut config = config.clone();
+        if let Some(style) = theme.and_then(|t| self.get(t)) {
+            style.apply(&mut config);
+        }
+        config
+    }
+}
+
+/// Crossfade of a grid entity from its previous theme to
+/// [`HexGridConfig::theme`]; removed when it's done.  Added by
+/// [`HexGridThemeCommands::crossfade_theme`].
+#[derive(Component, Debug, Clone)]
+pub struct HexGridThemeFade {
+    from: Option<HexGridTheme>,
+    duration: f32,
+    elapsed: f32,
+}
+
+impl HexGridThemeFade {
+    /// theme being faded out
+    pub fn from(&self) -> Option<&HexGridTheme> {
+        self.from.as_ref()
+    }
+
+    /// how far along the fade is, from 0 to 1
+    pub fn progress(&self) -> f32 {
+        if self.duration <= 0.0 {
+            return 1.0;
+        }
+        (self.elapsed / self.duration).clamp(0.0, 1.0)
+    }
+}
+
+/// switch the theme of a grid entity from [`Commands`]
+pub trait HexGridThemeCommands {
+    /// set [`HexGridConfig::theme`], fading from the current look over
+    /// `duration` seconds
+    fn crossfade_theme(&mut self, theme: Option<HexGridTheme>, duration: f32) -> &mut Self;
+}
+
+impl HexGridThemeCommands for EntityCommands<'_> {
+    fn crossfade_theme(&mut self, theme: Option<HexGridTheme>, duration: f32) -> &mut Self {
+        self.queue(move |mut entity: EntityWorldMut| {
+            let Some(mut config) = entity.get_mut::<HexGridConfig>() else {
+                return;
+            };
+            let from = std::mem::replace(&mut config.theme, theme);
+            entity.insert(HexGridThemeFade {
+                from,
+                duration,
+                elapsed: 0.0,
+            });
+        })
+    }
+}
+
+/// Blend between the look of two configs; `t` of 0 is `from`, 1 is `to`.
+/// Colors & widths are interpolated, and everything else is taken from `to`,
+/// except the antialiasing & superhex radius, which switch halfway.
+pub fn crossfade(from: &HexGridConfig, to: &HexGridConfig, t: f32) -> HexGridConfig {
+    let mix = |a: Color, b: Color| Color::from(a.to_linear().mix(&b.to_linear(), t));
+    let lerp = |a: f32, b: f32| a + (b - a) * t;
+    let clear = |c: Color| c.with_alpha(0.0);
+
+    let mut out = to.clone();
+    out.line_color = mix(from.line_color, to.line_color);
+    out.line_width = lerp(from.line_width, to.line_width);
+    out.line_width_mode = match (from.line_width_mode, to.line_width_mode) {
+        (LineWidthMode::World(a), LineWidthMode::World(b)) => LineWidthMode::World(lerp(a, b)),
+        (LineWidthMode::Pixels(a), LineWidthMode::Pixels(b)) => LineWidthMode::Pixels(lerp(a, b)),
+        (_, mode) => mode,
+    };
+    if t < 0.5 {
+        out.antialiasing = from.antialiasing;
+    }
+    out.cursor_color = mix(from.cursor_color, to.cursor_color);
+    out.cursor_line_width = lerp(from.cursor_line_width, to.cursor_line_width);
+    out.cursor_edge_color = mix(from.cursor_edge_color, to.cursor_edge_color);
+    out.cursor_edge_distance = lerp(from.cursor_edge_distance, to.cursor_edge_distance);
+
+    // a tint or border that's only on one side fades in or out
+    out.parity_tint = match (from.parity_tint, to.parity_tint) {
+        (None, None) => None,
+        (a, b) => {
+            let a = a.unwrap_or_else(|| b.unwrap().map(clear));
+            let b = b.unwrap_or_else(|| a.map(clear));
+            Some([mix(a[0], b[0]), mix(a[1], b[1]), mix(a[2], b[2])])
+        }
+    };
+    out.superhex_borders = match (from.superhex_borders, to.superhex_borders) {
+        (None, None) => None,
+        (a, b) => {
+            let a = a.unwrap_or_else(|| SuperHexBorders {
+                color: clear(b.unwrap().color),
+                ..b.unwrap()
+            });
+            let b = b.unwrap_or(SuperHexBorders {
+                color: clear(a.color),
+                ..a
+            });
+            Some(SuperHexBorders {
+                radius: if t < 0.5 { a.radius } else { b.radius },
+                color: mix(a.color, b.color),
+                width: lerp(a.width, b.width),
+            })
+        }
+    };
+    out
+}
+
+/// config a grid is drawn with: its theme, then its style, applied over it;
+/// mid crossfade, the previous theme is blended in
+pub(crate) fn themed_config<'a>(
+    config: &'a HexGridConfig,
+    themes: &HexGridThemes,
+    fade: Option<&HexGridThemeFade>,
+    style: Option<&HexGridStyle>,
+) -> Cow<'a, HexGridConfig> {
+    if config.theme.is_none() && fade.is_none() && style.is_none() {
+        return Cow::Borrowed(config);
+    }
+    let look = |theme: Option<&HexGridTheme>| {
+        let mut config = themes.apply(config, theme);
+        if let Some(style) = style {
+            style.apply(&mut config);
+        }
+        config
+    };
+    let to = look(config.theme.as_ref());
+    match fade {
+        Some(fade) => Cow::Owned(crossfade(&look(fade.from()), &to, fade.progress())),
+        None => Cow::Owned(to),
+    }
+}
+
+pub(crate) fn update_theme_fades(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut fades: Query<(Entity, &mut HexGridThemeFade)>,
+) {
+    for (entity, mut fade) in &mut fades {
+        if fade.progress() >= 1.0 {
+            commands.entity(entity).remove::<HexGridThemeFade>();
+            continue;
+        }
+        fade.elapsed += time.delta_secs();
+    }
+}
@@ -0,0 +1,322 @@
+//! loader for hexagonal [Tiled](https://www.mapeditor.org/) maps
+//!
+//! Add [`TiledMapPlugin`] to load `.tmx` files as [`TiledMap`] assets, or
+//! call [`parse_tmx`] directly.  Only maps with `orientation="hexagonal"` are
+//! supported.  Tiled uses staggered offset coordinates; these are converted
+//! to axial coordinates using the map's stagger axis & index, with the Tiled
+//! row mapping to `r`.
+//!
+//! Tile layers using `csv` or uncompressed `base64` encoding are supported,
+//! for both fixed-size and infinite (chunked) maps.
+use anyhow::{anyhow, bail, Context};
+use base64::Engine;
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    platform::collections::HashMap,
+    prelude::*,
+};
+
+use crate::{
+    coords::{Offset, OffsetKind},
+    HexMap,
+};
+
+pub struct TiledMapPlugin;
+
+impl Plugin for TiledMapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<TiledMap>()
+            .init_asset_loader::<TiledMapLoader>();
+    }
+}
+
+/// tile global id (gid) from a Tiled layer, including the flip flags stored in
+/// the high bits
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileId(pub u32);
+
+impl TileId {
+    const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+    const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+    const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+    const ROTATED_HEXAGONAL_120: u32 = 0x1000_0000;
+    const FLAGS: u32 = 0xf000_0000;
+
+    /// global tile id without the flip flags
+    pub fn gid(self) -> u32 {
+        self.0 & !Self::FLAGS
+    }
+
+    pub fn flipped_horizontally(self) -> bool {
+        self.0 & Self::FLIPPED_HORIZONTALLY != 0
+    }
+
+    pub fn flipped_vertically(self) -> bool {
+        self.0 & Self::FLIPPED_VERTICALLY != 0
+    }
+
+    pub fn flipped_diagonally(self) -> bool {
+        self.0 & Self::FLIPPED_DIAGONALLY != 0
+    }
+
+    /// hexagonal maps use this bit for 120 degree rotation
+    pub fn rotated_120(self) -> bool {
+        self.0 & Self::ROTATED_HEXAGONAL_120 != 0
+    }
+}
+
+#[derive(Asset, Debug, Clone, TypePath)]
+pub struct TiledMap {
+    /// map size in tiles; zero for infinite maps
+    pub width: u32,
+    pub height: u32,
+    /// tile size in pixels
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub hex_side_length: u32,
+    /// offset layout used to convert Tiled coordinates to axial
+    pub offset_kind: OffsetKind,
+    pub tilesets: Vec<TiledTileset>,
+    pub layers: Vec<TiledLayer>,
+    pub properties: HashMap<String, String>,
+}
+
+impl TiledMap {
+    pub fn layer(&self, name: &str) -> Option<&TiledLayer> {
+        self.layers.iter().find(|l| l.name == name)
+    }
+
+    /// tileset containing a tile
+    pub fn tileset_for(&self, tile: TileId) -> Option<&TiledTileset> {
+        let gid = tile.gid();
+        self.tilesets
+            .iter()
+            .filter(|t| t.first_gid <= gid)
+            .max_by_key(|t| t.first_gid)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TiledTileset {
+    pub first_gid: u32,
+    pub name: Option<String>,
+    /// path of an external tileset (`.tsx`), relative to the map
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TiledLayer {
+    pub id: u32,
+    pub name: String,
+    pub visible: bool,
+    pub opacity: f32,
+    pub properties: HashMap<String, String>,
+    pub tiles: HexMap<TileId>,
+}
+
+#[derive(Default, TypePath)]
+pub struct TiledMapLoader;
+
+impl AssetLoader for TiledMapLoader {
+    type Asset = TiledMap;
+    type Settings = ();
+    type Error = anyhow::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<TiledMap, anyhow::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let text = std::str::from_utf8(&bytes).context("tmx file is not utf-8")?;
+        parse_tmx(text).with_context(|| format!("failed to load {}", load_context.path()))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tmx"]
+    }
+}
+
+fn attr<T: std::str::FromStr>(node: roxmltree::Node, name: &str) -> anyhow::Result<Option<T>>
+where
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    node.attribute(name)
+        .map(|v| {
+            v.parse().with_context(|| {
+                format!("invalid {}=\"{}\" on <{}>", name, v, node.tag_name().name())
+            })
+        })
+        .transpose()
+}
+
+fn properties(node: roxmltree::Node) -> HashMap<String, String> {
+    node.children()
+        .filter(|n| n.has_tag_name("properties"))
+        .flat_map(|n| n.children().filter(|n| n.has_tag_name("property")))
+        .filter_map(|p| {
+            // multi-line string properties store their value as text
+            let value = p.attribute("value").or_else(|| p.text())?;
+            Some((p.attribute("name")?.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// parse the contents of a hexagonal `.tmx` file
+pub fn parse_tmx(text: &str) -> anyhow::Result<TiledMap> {
+    let doc = roxmltree::Document::parse(text)?;
+    let root = doc.root_element();
+    if !root.has_tag_name("map") {
+        bail!(
+            "expected <map> root element, found <{}>",
+            root.tag_name().name()
+        );
+    }
+
+    let orientation = root.attribute("orientation").unwrap_or("orthogonal");
+    if orientation != "hexagonal" {
+        bail!("unsupported map orientation \"{}\"", orientation);
+    }
+
+    let odd = match root.attribute("staggerindex").unwrap_or("odd") {
+        "odd" => true,
+        "even" => false,
+        other => bail!("invalid staggerindex \"{}\"", other),
+    };
+    let offset_kind = match (root.attribute("staggeraxis").unwrap_or("y"), odd) {
+        ("y", true) => OffsetKind::OddR,
+        ("y", false) => OffsetKind::EvenR,
+        ("x", true) => OffsetKind::OddQ,
+        ("x", false) => OffsetKind::EvenQ,
+        (other, _) => bail!("invalid staggeraxis \"{}\"", other),
+    };
+
+    let tilesets = root
+        .children()
+        .filter(|n| n.has_tag_name("tileset"))
+        .map(|n| {
+            Ok(TiledTileset {
+                first_gid: attr(n, "firstgid")?
+                    .ok_or_else(|| anyhow!("tileset missing firstgid"))?,
+                name: n.attribute("name").map(str::to_string),
+                source: n.attribute("source").map(str::to_string),
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    // layers may be nested inside groups; flatten them
+    let layers = root
+        .descendants()
+        .filter(|n| n.has_tag_name("layer"))
+        .map(|n| parse_layer(n, offset_kind))
+        .collect::<anyhow::Result<_>>()?;
+
+    Ok(TiledMap {
+        width: attr(root, "width")?.unwrap_or(0),
+        height: attr(root, "height")?.unwrap_or(0),
+        tile_width: attr(root, "tilewidth")?.unwrap_or(0),
+        tile_height: attr(root, "tileheight")?.unwrap_or(0),
+        hex_side_length: attr(root, "hexsidelength")?.unwrap_or(0),
+        offset_kind,
+        tilesets,
+        layers,
+        properties: properties(root),
+    })
+}
+
+fn parse_layer(node: roxmltree::Node, offset_kind: OffsetKind) -> anyhow::Result<TiledLayer> {
+    let name = node.attribute("name").unwrap_or_default().to_string();
+    let mut tiles = HexMap::new();
+
+    let data = node
+        .children()
+        .find(|n| n.has_tag_name("data"))
+        .ok_or_else(|| anyhow!("layer \"{}\" has no <data>", name))?;
+    let encoding = data.attribute("encoding");
+    if let Some(compression) = data.attribute("compression") {
+        bail!(
+            "layer \"{}\": unsupported compression \"{}\"",
+            name,
+            compression
+        );
+    }
+
+    // infinite maps store tiles in chunks, each with its own origin & width
+    let chunks: Vec<_> = data
+        .children()
+        .filter(|n| n.has_tag_name("chunk"))
+        .collect();
+    let regions = if chunks.is_empty() {
+        vec![(0, 0, attr(node, "width")?.unwrap_or(0), data)]
+    } else {
+        chunks
+            .into_iter()
+            .map(|c| {
+                Ok((
+                    attr(c, "x")?.unwrap_or(0),
+                    attr(c, "y")?.unwrap_or(0),
+                    attr(c, "width")?.unwrap_or(0),
+                    c,
+                ))
+            })
+            .collect::<anyhow::Result<_>>()?
+    };
+
+    for (x, y, width, region) in regions {
+        if width <= 0 {
+            bail!("layer \"{}\": invalid width {}", name, width);
+        }
+        let gids = decode_gids(region, encoding).with_context(|| format!("layer \"{}\"", name))?;
+        for (i, gid) in gids.into_iter().enumerate() {
+            if gid == 0 {
+                continue;
+            }
+            let i = i as i32;
+            let offset = Offset::new(x + i % width, y + i / width);
+            tiles.insert(offset.to_axial(offset_kind), TileId(gid));
+        }
+    }
+
+    Ok(TiledLayer {
+        id: attr(node, "id")?.unwrap_or(0),
+        name,
+        visible: attr::<u8>(node, "visible")?.unwrap_or(1) != 0,
+        opacity: attr(node, "opacity")?.unwrap_or(1.0),
+        properties: properties(node),
+        tiles,
+    })
+}
+
+fn decode_gids(node: roxmltree::Node, encoding: Option<&str>) -> anyhow::Result<Vec<u32>> {
+    match encoding {
+        Some("csv") => node
+            .text()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse().with_context(|| format!("invalid gid \"{}\"", s)))
+            .collect(),
+        Some("base64") => {
+            let text: String = node.text().unwrap_or_default().split_whitespace().collect();
+            let bytes = base64::engine::general_purpose::STANDARD.decode(text)?;
+            if bytes.len() % 4 != 0 {
+                bail!("base64 data is not a multiple of 4 bytes");
+            }
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect())
+        }
+        // legacy xml encoding, one <tile gid=".."/> per cell
+        None => node
+            .children()
+            .filter(|n| n.has_tag_name("tile"))
+            .map(|n| Ok(attr(n, "gid")?.unwrap_or(0)))
+            .collect(),
+        Some(other) => bail!("unsupported encoding \"{}\"", other),
+    }
+}
@@ -0,0 +1,93 @@
+//! clock for grid animations
+//!
+//! The built-in animations (cell animations, path previews, & units moving
+//! between cells) advance with the [`GridTime`] resource rather than
+//! [`Time`] directly, so they can be paused or slowed down along with the
+//! game without stopping the rest of the app:
+//!
+//! ```ignore
+//! fn toggle_pause(keys: Res<ButtonInput<KeyCode>>, mut time: ResMut<GridTime>) {
+//!     if keys.just_pressed(KeyCode::KeyP) {
+//!         time.toggle();
+//!     }
+//! }
+//! ```
+//!
+//! The grid shader gets the elapsed seconds as `grid.time`, so animated
+//! effects freeze with everything else.
+use bevy::prelude::*;
+
+/// Time for grid animations, advanced every frame by the [`Time`] delta
+/// times [`GridTime::scale`], except while paused.
+#[derive(Resource, Debug, Clone, PartialEq, Reflect)]
+#[reflect(Resource)]
+pub struct GridTime {
+    elapsed: f32,
+    delta: f32,
+    scale: f32,
+    paused: bool,
+}
+
+impl Default for GridTime {
+    fn default() -> Self {
+        Self {
+            elapsed: 0.0,
+            delta: 0.0,
+            scale: 1.0,
+            paused: false,
+        }
+    }
+}
+
+impl GridTime {
+    /// seconds since the last frame; zero while paused
+    pub fn delta_secs(&self) -> f32 {
+        self.delta
+    }
+
+    /// seconds of grid time since startup
+    pub fn elapsed_secs(&self) -> f32 {
+        self.elapsed
+    }
+
+    /// speed relative to [`Time`]
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Set the speed relative to [`Time`].  Panics if `scale` is negative or
+    /// not finite.
+    pub fn set_scale(&mut self, scale: f32) {
+        assert!(
+            scale.is_finite() && scale >= 0.0,
+            "grid time scale must be finite & non-negative: {scale}"
+        );
+        self.scale = scale;
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn unpause(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn toggle(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// advance by `delta` real seconds, scaled & skipped while paused
+    pub fn advance(&mut self, delta: f32) {
+        self.delta = if self.paused { 0.0 } else { delta * self.scale };
+        self.elapsed += self.delta;
+    }
+}
+
+pub(crate) fn update_grid_time(time: Res<Time>, mut grid_time: ResMut<GridTime>) {
+    grid_time.advance(time.delta_secs());
+}
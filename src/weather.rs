@@ -0,0 +1,124 @@
+//! animated weather overlays
+//!
+//! Add a [`HexGridWeather`] to a grid entity to draw an animated pattern over
+//! some of its cells: drifting clouds over a storm, scanlines over a scanned
+//! sector, or static over cells the player can't see.  Each kind of weather
+//! is a [`Weather`], and each cell is given the index of its kind:
+//!
+//! ```ignore
+//! let mut weather = HexGridWeather::new([
+//!     Weather::new(WeatherPattern::Clouds).with_color(Color::srgba(0.2, 0.2, 0.3, 0.7)),
+//! ]);
+//! weather.set_region(Axial::new(4, 2).range(2), WeatherId(0));
+//! commands.entity(grid).insert(weather);
+//! ```
+//!
+//! Patterns are animated with [`crate::GridTime`], so they stop while grid
+//! time is paused.  Weather is drawn over everything on the cell, including
+//! the grid lines.
+use bevy::prelude::*;
+
+use crate::{Axial, HexMap};
+
+/// kind of weather on a cell; index into [`HexGridWeather::kinds`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WeatherId(pub u8);
+
+/// animated pattern drawn by a [`Weather`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WeatherPattern {
+    /// soft noise drifting across the grid
+    #[default]
+    Clouds,
+    /// bands rolling across the grid
+    Scanlines,
+    /// flickering specks
+    Static,
+}
+
+/// a kind of weather drawn over its cells
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Weather {
+    pub pattern: WeatherPattern,
+    /// color of the pattern where it's strongest
+    pub color: Color,
+    /// how much of the pattern shows, from 0 to 1
+    pub intensity: f32,
+    /// size of the pattern features, as a multiple of the cell size
+    pub scale: f32,
+    /// how fast the pattern moves, as a multiple of its usual speed
+    pub speed: f32,
+}
+
+impl Weather {
+    pub fn new(pattern: WeatherPattern) -> Self {
+        Self {
+            pattern,
+            color: Color::srgba(0.8, 0.8, 0.85, 0.6),
+            intensity: 1.0,
+            scale: 1.0,
+            speed: 1.0,
+        }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+}
+
+/// Weather over the cells of a grid entity; see the [module docs](self).
+/// Cells of a kind past [`HexGridWeather::MAX_KINDS`], or one that isn't in
+/// `kinds`, are drawn without weather.
+#[derive(Component, Debug, Default, Clone)]
+pub struct HexGridWeather {
+    pub kinds: Vec<Weather>,
+    pub cells: HexMap<WeatherId>,
+}
+
+impl HexGridWeather {
+    pub const MAX_KINDS: usize = 8;
+
+    pub fn new(kinds: impl IntoIterator<Item = Weather>) -> Self {
+        Self {
+            kinds: kinds.into_iter().collect(),
+            cells: HexMap::new(),
+        }
+    }
+
+    /// set the weather of every cell in `region`
+    pub fn set_region(&mut self, region: impl IntoIterator<Item = Axial>, id: WeatherId) {
+        self.cells.extend(region.into_iter().map(|cell| (cell, id)));
+    }
+
+    /// clear the weather from every cell in `region`
+    pub fn clear_region(&mut self, region: impl IntoIterator<Item = Axial>) {
+        for cell in region {
+            self.cells.remove(cell);
+        }
+    }
+
+    /// weather drawn on `cell`, if any
+    pub fn get(&self, cell: Axial) -> Option<&Weather> {
+        let id = self.cells.get(cell)?.0 as usize;
+        self.kinds.get(id).filter(|_| id < Self::MAX_KINDS)
+    }
+}
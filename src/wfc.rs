@@ -0,0 +1,224 @@
+//! wave function collapse over hex cells
+//!
+//! [`WfcRules`] lists which tiles may sit next to each other in each of the
+//! six [`HexDirection`]s, and how common each tile is.
+//! [`WfcRules::generate`] then fills a set of cells with tiles so that every
+//! pair of neighbors is allowed.  The same seed always produces the same map.
+//!
+//! Cells are collapsed lowest entropy first.  When a choice leaves a cell
+//! with no possible tile, generation starts over, up to
+//! [`WfcRules::attempts`] times.
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use bevy::prelude::*;
+
+use crate::{gen::GenRng, Axial, HexDirection, HexMap};
+
+/// index of a tile in a [`WfcRules`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WfcTile(pub u8);
+
+/// tiles, their weights, & which may be neighbors
+#[derive(Debug, Clone)]
+pub struct WfcRules {
+    weights: Vec<f32>,
+    /// tiles allowed next to each tile, in each direction
+    allowed: Vec<[u64; 6]>,
+    /// times to start over after a contradiction before giving up
+    pub attempts: u32,
+}
+
+impl WfcRules {
+    pub const MAX_TILES: usize = 64;
+
+    /// Rules for one tile per weight; weights are the relative frequency of
+    /// each tile.  No tiles may be neighbors until allowed.
+    ///
+    /// # Panics
+    /// if there are more than [`WfcRules::MAX_TILES`] weights
+    pub fn new(weights: impl IntoIterator<Item = f32>) -> Self {
+        let weights: Vec<f32> = weights.into_iter().map(|w| w.max(0.0)).collect();
+        assert!(
+            weights.len() <= Self::MAX_TILES,
+            "at most {} tiles, got {}",
+            Self::MAX_TILES,
+            weights.len()
+        );
+        Self {
+            allowed: vec![[0; 6]; weights.len()],
+            weights,
+            attempts: 10,
+        }
+    }
+
+    pub fn tile_count(&self) -> usize {
+        self.weights.len()
+    }
+
+    /// allow `b` to be the neighbor of `a` in direction `dir`, and so `a` the
+    /// neighbor of `b` in the opposite direction
+    pub fn allow(&mut self, a: WfcTile, dir: HexDirection, b: WfcTile) -> &mut Self {
+        self.allowed[a.0 as usize][dir.index()] |= 1 << b.0;
+        self.allowed[b.0 as usize][dir.opposite().index()] |= 1 << a.0;
+        self
+    }
+
+    /// allow `a` & `b` to be neighbors in every direction
+    pub fn allow_all(&mut self, a: WfcTile, b: WfcTile) -> &mut Self {
+        for dir in HexDirection::ALL {
+            self.allow(a, dir, b);
+        }
+        self
+    }
+
+    pub fn is_allowed(&self, a: WfcTile, dir: HexDirection, b: WfcTile) -> bool {
+        self.allowed[a.0 as usize][dir.index()] & (1 << b.0) != 0
+    }
+
+    /// Fill `cells` with tiles so every pair of neighboring cells is allowed;
+    /// cells outside `cells` don't constrain their neighbors.  `None` if every
+    /// attempt hit a contradiction.
+    pub fn generate(
+        &self,
+        cells: impl IntoIterator<Item = Axial>,
+        seed: u64,
+    ) -> Option<HexMap<WfcTile>> {
+        let all = self
+            .weights
+            .iter()
+            .enumerate()
+            .filter(|(_, &w)| w > 0.0)
+            .fold(0u64, |mask, (i, _)| mask | 1 << i);
+        let mut cells: Vec<Axial> = cells.into_iter().collect();
+        cells.sort_by_key(|c| (c.r, c.q));
+        cells.dedup();
+        if all == 0 && !cells.is_empty() {
+            return None;
+        }
+
+        let mut rng = GenRng::new(seed);
+        for _ in 0..self.attempts.max(1) {
+            let mut domains: HexMap<u64> = cells.iter().map(|&c| (c, all)).collect();
+            if self.solve(&mut domains, &cells, &mut rng) {
+                return Some(
+                    domains
+                        .iter()
+                        .map(|(cell, mask)| (cell, WfcTile(mask.trailing_zeros() as u8)))
+                        .collect(),
+                );
+            }
+        }
+        None
+    }
+
+    // true if every cell collapsed without a contradiction
+    fn solve(&self, domains: &mut HexMap<u64>, cells: &[Axial], rng: &mut GenRng) -> bool {
+        let mut changed = Vec::new();
+        if !self.propagate(domains, cells.to_vec(), &mut changed) {
+            return false;
+        }
+
+        // (entropy bits, tie breaker, q, r); stale entries are skipped when
+        // the entropy no longer matches
+        let mut open = BinaryHeap::new();
+        for &cell in cells {
+            self.push(&mut open, domains, cell, rng);
+        }
+
+        while let Some(Reverse((entropy, _, q, r))) = open.pop() {
+            let cell = Axial::new(q, r);
+            let mask = domains.get(cell).copied().unwrap_or(0);
+            if mask.count_ones() <= 1 || self.entropy(mask).to_bits() != entropy {
+                continue;
+            }
+
+            let tile = self.choose(mask, rng);
+            domains.insert(cell, 1 << tile);
+            changed.clear();
+            if !self.propagate(domains, vec![cell], &mut changed) {
+                return false;
+            }
+            // cells whose entropy dropped get a fresh entry
+            for &cell in &changed {
+                self.push(&mut open, domains, cell, rng);
+            }
+        }
+        true
+    }
+
+    fn push(
+        &self,
+        open: &mut BinaryHeap<Reverse<(u32, u32, i32, i32)>>,
+        domains: &HexMap<u64>,
+        cell: Axial,
+        rng: &mut GenRng,
+    ) {
+        if let Some(&mask) = domains.get(cell) {
+            if mask.count_ones() > 1 {
+                let entropy = self.entropy(mask).to_bits();
+                open.push(Reverse((entropy, rng.below(u32::MAX), cell.q, cell.r)));
+            }
+        }
+    }
+
+    // remove tiles that no longer have an allowed neighbor, starting from
+    // `stack`, adding the cells changed to `changed`; false on a
+    // contradiction
+    fn propagate(
+        &self,
+        domains: &mut HexMap<u64>,
+        mut stack: Vec<Axial>,
+        changed: &mut Vec<Axial>,
+    ) -> bool {
+        while let Some(cell) = stack.pop() {
+            let mask = domains.get(cell).copied().unwrap_or(0);
+            for dir in HexDirection::ALL {
+                let neighbor = cell + dir;
+                let Some(&current) = domains.get(neighbor) else {
+                    continue;
+                };
+                let allowed = (0..self.weights.len())
+                    .filter(|t| mask & (1 << t) != 0)
+                    .fold(0, |m, t| m | self.allowed[t][dir.index()]);
+                let next = current & allowed;
+                if next == current {
+                    continue;
+                }
+                if next == 0 {
+                    return false;
+                }
+                domains.insert(neighbor, next);
+                stack.push(neighbor);
+                changed.push(neighbor);
+            }
+        }
+        true
+    }
+
+    // Shannon entropy of the tile weights in a mask; never negative, so the
+    // float bits order the same as the values
+    fn entropy(&self, mask: u64) -> f32 {
+        let (sum, sum_log) = (0..self.weights.len())
+            .filter(|t| mask & (1 << t) != 0)
+            .map(|t| self.weights[t])
+            .fold((0.0, 0.0), |(s, l), w| (s + w, l + w * w.ln()));
+        (sum.ln() - sum_log / sum).max(0.0)
+    }
+
+    // pick a tile from a mask by weight
+    fn choose(&self, mask: u64, rng: &mut GenRng) -> usize {
+        let tiles = (0..self.weights.len()).filter(|t| mask & (1 << t) != 0);
+        let total: f32 = tiles.clone().map(|t| self.weights[t]).sum();
+        let mut pick = rng.next_f32() * total;
+        let mut last = 0;
+        for t in tiles {
+            last = t;
+            pick -= self.weights[t];
+            if pick < 0.0 {
+                return t;
+            }
+        }
+        last
+    }
+}
@@ -0,0 +1,102 @@
+//! maps that wrap around
+//!
+//! Set [`HexGridConfig::wrap`](crate::HexGridConfig::wrap) to a [`HexWrap`]
+//! for a world map that wraps east-west, like a cylinder, or in both
+//! directions, like a torus.  Every cell then has a canonical coordinate,
+//! [`HexWrap::wrap`], with its column in `0..width` and row in `0..height`.
+//! The grid is still drawn on the whole plane; highlights, decals, & owners
+//! of a canonical cell are drawn on every copy of it, and the
+//! [`crate::HexGridCursor`] reports canonical cells.  Keep per-cell data,
+//! such as a [`crate::HexMap`], keyed by canonical cells.
+//!
+//! Rows are the cells with the same `r`, running along the X axis for
+//! pointy-topped hexes.  Only hex grids wrap.
+use bevy::prelude::*;
+
+use crate::Axial;
+
+/// Size of a map that wraps around; zero for a direction that doesn't wrap.
+/// Built with [`HexWrap::east_west`] or [`HexWrap::toroidal`], which keep
+/// the height even.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HexWrap {
+    width: u32,
+    height: u32,
+}
+
+impl HexWrap {
+    /// wrap east-west every `width` cells
+    pub fn east_west(width: u32) -> Self {
+        Self { width, height: 0 }
+    }
+
+    /// Wrap in both directions.  Panics if `height` is odd.
+    pub fn toroidal(width: u32, height: u32) -> Self {
+        assert!(
+            height.is_multiple_of(2),
+            "wrapped map height must be even: {height}"
+        );
+        Self { width, height }
+    }
+
+    /// cells per row
+    pub fn width(self) -> u32 {
+        self.width
+    }
+
+    /// rows; always even, so the map lines up with itself
+    pub fn height(self) -> u32 {
+        self.height
+    }
+
+    // offset between copies of the map along each direction; zero if it
+    // doesn't wrap
+    fn periods(self) -> [Axial; 2] {
+        let (w, h) = (self.width as i32, self.height as i32);
+        // rows are staggered by half a cell, so straight down the map is
+        // half a step back in q for every row
+        [Axial::new(w, 0), Axial::new(-h / 2, h)]
+    }
+
+    /// Canonical coordinate of a cell, with its column in `0..width` & row in
+    /// `0..height`.  Mirrors `wrap_cell()` in the shader.
+    pub fn wrap(self, cell: Axial) -> Axial {
+        let mut cell = cell;
+        if self.height > 0 {
+            let h = self.height as i32;
+            let copies = cell.r.div_euclid(h);
+            cell = Axial::new(cell.q + copies * h / 2, cell.r - copies * h);
+        }
+        if self.width > 0 {
+            // column in an offset layout, where rows don't lean
+            let w = self.width as i32;
+            let col = cell.q + cell.r.div_euclid(2);
+            cell.q -= col.div_euclid(w) * w;
+        }
+        cell
+    }
+
+    /// true if two coordinates are copies of the same cell
+    pub fn same_cell(self, a: Axial, b: Axial) -> bool {
+        self.wrap(a) == self.wrap(b)
+    }
+
+    /// distance between two cells, the short way around the map
+    pub fn distance(self, a: Axial, b: Axial) -> u32 {
+        let (a, b) = (self.wrap(a), self.wrap(b));
+        let [x, y] = self.periods();
+        let mut best = a.distance(b);
+        for i in -1..=1 {
+            for j in -1..=1 {
+                best = best.min(a.distance(b + x * i + y * j));
+            }
+        }
+        best
+    }
+
+    /// canonical coordinates of the six neighbors of a cell
+    pub fn neighbors(self, cell: Axial) -> impl Iterator<Item = Axial> {
+        cell.neighbors().map(move |n| self.wrap(n))
+    }
+}
@@ -0,0 +1,114 @@
+//! zones of control
+//!
+//! In many tactics games a unit controls the cells around it: enemies that
+//! step into them must stop, or pay extra to enter, and those that step
+//! away from the unit give it an attack of opportunity.  A
+//! [`ZoneOfControl`] holds the enemy units & which of these rules apply,
+//! and wraps the step cost given to [`find_path`] or
+//! [`movement_range_steps`] to follow them:
+//!
+//! ```ignore
+//! let zoc = ZoneOfControl::new(enemies.iter().map(|pos| pos.cell)).with_leave_cost(2);
+//! let range = movement_range_steps(unit, 6, zoc.step_cost(unit, |_, to| costs.get(to).copied()));
+//! for (step, attacker) in zoc.opportunities(&path) {
+//!     // resolve the attack before the unit takes `step`
+//! }
+//! ```
+//!
+//! The cells of the units themselves aren't blocked; leave that to the cost
+//! being wrapped, as for any other occupied cell.
+//!
+//! [`find_path`]: crate::path::find_path
+//! [`movement_range_steps`]: crate::path::movement_range_steps
+use bevy::platform::collections::HashSet;
+
+use crate::Axial;
+
+/// Cells controlled by units, & the rules for moving through them.  By
+/// default, entering a controlled cell ends a move, and nothing costs extra.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZoneOfControl {
+    units: HashSet<Axial>,
+    stop: bool,
+    enter_cost: u32,
+    leave_cost: u32,
+}
+
+impl ZoneOfControl {
+    /// zones around the units on `units`
+    pub fn new(units: impl IntoIterator<Item = Axial>) -> Self {
+        Self {
+            units: units.into_iter().collect(),
+            stop: true,
+            enter_cost: 0,
+            leave_cost: 0,
+        }
+    }
+
+    /// whether entering a controlled cell ends the move; a move may always
+    /// start in one
+    pub fn with_stop(mut self, stop: bool) -> Self {
+        self.stop = stop;
+        self
+    }
+
+    /// extra cost of each step into a controlled cell
+    pub fn with_enter_cost(mut self, cost: u32) -> Self {
+        self.enter_cost = cost;
+        self
+    }
+
+    /// extra cost of each attack of opportunity a step gives; see
+    /// [`ZoneOfControl::attackers`]
+    pub fn with_leave_cost(mut self, cost: u32) -> Self {
+        self.leave_cost = cost;
+        self
+    }
+
+    pub fn units(&self) -> impl Iterator<Item = Axial> + '_ {
+        self.units.iter().copied()
+    }
+
+    /// true if `cell` is next to any of the units
+    pub fn controls(&self, cell: Axial) -> bool {
+        cell.neighbors().any(|n| self.units.contains(&n))
+    }
+
+    /// Units that get an attack of opportunity on a unit stepping from
+    /// `from` to `to`; those next to `from` but not to `to`.
+    pub fn attackers(&self, from: Axial, to: Axial) -> impl Iterator<Item = Axial> + '_ {
+        from.neighbors()
+            .filter(move |n| self.units.contains(n) && n.distance(to) > 1)
+    }
+
+    /// Index of each step along `path` that gives an attack of
+    /// opportunity, with the unit making it; step `i` is from `path[i]` to
+    /// `path[i + 1]`.
+    pub fn opportunities(&self, path: &[Axial]) -> Vec<(usize, Axial)> {
+        path.windows(2)
+            .enumerate()
+            .flat_map(|(i, step)| self.attackers(step[0], step[1]).map(move |unit| (i, unit)))
+            .collect()
+    }
+
+    /// Step cost for [`crate::path::find_path`] & the like, for a move
+    /// starting at `start`, that follows these rules, and otherwise costs
+    /// what `cost` does.
+    pub fn step_cost<'a>(
+        &'a self,
+        start: Axial,
+        mut cost: impl FnMut(Axial, Axial) -> Option<u32> + 'a,
+    ) -> impl FnMut(Axial, Axial) -> Option<u32> + 'a {
+        move |from, to| {
+            if self.stop && from != start && self.controls(from) {
+                return None;
+            }
+            let mut total = cost(from, to)?;
+            if self.controls(to) {
+                total = total.saturating_add(self.enter_cost);
+            }
+            let attacks = self.attackers(from, to).count() as u32;
+            Some(total.saturating_add(self.leave_cost.saturating_mul(attacks)))
+        }
+    }
+}
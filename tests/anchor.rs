@@ -0,0 +1,81 @@
+//! cell anchor tests
+use bevy::{
+    camera::{ComputedCameraValues, RenderTargetInfo, Viewport},
+    prelude::*,
+};
+use hex_grid::{Axial, GridKind, HexAnchor, HexGridConfig, HexGridPlane, Orientation};
+use proptest::prelude::*;
+
+proptest! {
+    #[test]
+    fn anchored_over_the_cell(
+        kind in proptest::sample::select(GridKind::ALL.to_vec()),
+        flat in any::<bool>(),
+        q in -20i32..20,
+        r in -20i32..20,
+        offset in -2.0f32..2.0,
+        scale in 0.5f32..2.0,
+        tilt in -1.0f32..1.0,
+    ) {
+        let config = HexGridConfig {
+            kind,
+            orientation: if flat { Orientation::Flat } else { Orientation::Pointy },
+            ..default()
+        };
+        let plane = HexGridPlane::from(GlobalTransform::from(
+            Transform::from_xyz(3.0, 1.0, -2.0)
+                .with_rotation(Quat::from_rotation_z(tilt))
+                .with_scale(Vec3::splat(scale)),
+        ));
+        let cell = Axial::new(q, r);
+        let anchor = HexAnchor::new(Entity::PLACEHOLDER, cell).with_offset(offset);
+
+        let pos = anchor.world_position(&config, &plane);
+        prop_assert_eq!(plane.world_to_cell(&config, pos), cell);
+        // offset is in the grid's units, along its up axis
+        let local = plane.world_to_local(pos);
+        prop_assert!((local.y - offset).abs() < 1e-4);
+        let above = pos - plane.cell_to_world(&config, cell);
+        prop_assert!((above.length() - offset.abs() * scale).abs() < 1e-3);
+    }
+}
+
+#[test]
+fn anchored_inside_an_offset_viewport() {
+    // 1280x720 window at 2x scale, with a logical viewport from (200, 100)
+    // to (1000, 600)
+    let mut camera = Camera {
+        viewport: Some(Viewport {
+            physical_position: UVec2::new(400, 200),
+            physical_size: UVec2::new(1600, 1000),
+            ..default()
+        }),
+        ..default()
+    };
+    camera.computed = ComputedCameraValues {
+        clip_from_view: Mat4::perspective_infinite_reverse_rh(0.8, 1.6, 0.1),
+        target_info: Some(RenderTargetInfo {
+            physical_size: UVec2::new(2560, 1440),
+            scale_factor: 2.0,
+        }),
+        ..default()
+    };
+    let camera_transform =
+        GlobalTransform::from(Transform::from_xyz(2.0, 20.0, 12.0).looking_at(Vec3::ZERO, Vec3::Y));
+    let anchor =
+        HexAnchor::new(Entity::PLACEHOLDER, Axial::ZERO).with_screen_offset(Vec2::new(10.0, -5.0));
+
+    let screen = anchor
+        .screen_position(
+            &HexGridConfig::default(),
+            &HexGridPlane::default(),
+            &camera,
+            &camera_transform,
+        )
+        .unwrap();
+    assert!(
+        screen.distance(Vec2::new(610.0, 345.0)) < 0.01,
+        "{:?}",
+        screen
+    );
+}
@@ -0,0 +1,51 @@
+//! cell animation timing tests
+use bevy::prelude::*;
+use hex_grid::{Axial, CellAnimation};
+
+fn alpha_at(animation: &CellAnimation, elapsed: f32) -> Vec<(Axial, f32)> {
+    animation
+        .sample(elapsed)
+        .map(|(cell, color)| (cell, color.alpha()))
+        .collect()
+}
+
+#[test]
+fn flash_peaks_halfway() {
+    let cell = Axial::new(1, 2);
+    let flash = CellAnimation::flash([cell], Color::WHITE, 1.0);
+    assert_eq!(flash.length(), 1.0);
+    assert_eq!(alpha_at(&flash, 0.0), [(cell, 0.0)]);
+    assert_eq!(alpha_at(&flash, 0.5), [(cell, 1.0)]);
+    assert_eq!(alpha_at(&flash, 0.75), [(cell, 0.5)]);
+    assert_eq!(alpha_at(&flash, 2.0), [(cell, 0.0)]);
+}
+
+#[test]
+fn fade_in_holds() {
+    let fade =
+        CellAnimation::fade_in([Axial::ZERO], Color::WHITE.with_alpha(0.5), 2.0).delayed(1.0);
+    assert_eq!(fade.length(), 3.0);
+    assert!(alpha_at(&fade, 0.5).is_empty());
+    assert_eq!(alpha_at(&fade, 2.0), [(Axial::ZERO, 0.25)]);
+    assert_eq!(alpha_at(&fade, 10.0), [(Axial::ZERO, 0.5)]);
+}
+
+#[test]
+fn lerp_colors() {
+    let lerp = CellAnimation::lerp([Axial::ZERO], Color::BLACK, Color::WHITE, 1.0);
+    let (_, color) = lerp.sample(0.5).next().unwrap();
+    assert!((color.to_linear().red - 0.5).abs() < 1e-5);
+}
+
+#[test]
+fn wave_staggers() {
+    let path: Vec<Axial> = (0..4).map(|q| Axial::new(q, 0)).collect();
+    let wave = CellAnimation::wave(path.clone(), Color::WHITE, 0.25, 0.5);
+    assert_eq!(wave.length(), 0.75 + 0.5);
+
+    // at 0.5s the first cell is done, the third has just started, and the
+    // fourth hasn't
+    let cells: Vec<Axial> = wave.sample(0.5).map(|(cell, _)| cell).collect();
+    assert_eq!(cells, &path[..3]);
+    assert_eq!(alpha_at(&wave, 0.5)[1], (path[1], 1.0));
+}
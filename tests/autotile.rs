@@ -0,0 +1,122 @@
+//! terrain auto-tiling tests
+use hex_grid::{
+    AutoTileRule, Axial, HexDirection, HexGridAutoTile, HexGridConfig, HexGridTerrain, HexMap,
+    HexWrap, TerrainId, TileVariant,
+};
+use proptest::prelude::*;
+
+const WATER: TerrainId = TerrainId(0);
+const LAND: TerrainId = TerrainId(1);
+const SHORE_ONE: TerrainId = TerrainId(2);
+const SHORE_TWO: TerrainId = TerrainId(3);
+const SHORE_SPLIT: TerrainId = TerrainId(4);
+
+fn auto_tile() -> HexGridAutoTile {
+    HexGridAutoTile::new([AutoTileRule::new(WATER)
+        .with_border([LAND])
+        .with_variant(0b000001, SHORE_ONE)
+        .with_variant(0b000011, SHORE_TWO)
+        .with_variant(0b001001, SHORE_SPLIT)])
+}
+
+fn variant(cells: &HexMap<TerrainId>, cell: Axial) -> Option<TileVariant> {
+    auto_tile().variant(|c| cells.get(c).copied(), cell)
+}
+
+#[test]
+fn turned_to_fit() {
+    for dir in 0..6 {
+        let mut cells = HexMap::new();
+        cells.insert(Axial::ZERO, WATER);
+        cells.insert(Axial::ZERO + HexDirection::new(dir).offset(), LAND);
+        assert_eq!(
+            variant(&cells, Axial::ZERO),
+            Some(TileVariant {
+                kind: SHORE_ONE,
+                rotation: dir as u8
+            })
+        );
+
+        // land on two neighboring sides
+        cells.insert(Axial::ZERO + HexDirection::new(dir + 1).offset(), LAND);
+        assert_eq!(
+            variant(&cells, Axial::ZERO),
+            Some(TileVariant {
+                kind: SHORE_TWO,
+                rotation: dir as u8
+            })
+        );
+    }
+
+    // opposite sides match a pattern that is the same turned halfway
+    let mut cells = HexMap::new();
+    cells.insert(Axial::ZERO, WATER);
+    cells.insert(Axial::new(-1, 0), LAND);
+    cells.insert(Axial::new(1, 0), LAND);
+    assert_eq!(variant(&cells, Axial::ZERO).unwrap().kind, SHORE_SPLIT);
+}
+
+#[test]
+fn no_match() {
+    let mut cells = HexMap::new();
+    cells.insert(Axial::ZERO, WATER);
+    // open water, & land on three sides, have no variant
+    assert_eq!(variant(&cells, Axial::ZERO), None);
+    for dir in 0..3 {
+        cells.insert(HexDirection::new(dir).offset(), LAND);
+    }
+    assert_eq!(variant(&cells, Axial::ZERO), None);
+    // land has no rule
+    assert_eq!(variant(&cells, Axial::new(1, 0)), None);
+}
+
+#[test]
+fn wrapped_neighbors() {
+    let config = HexGridConfig {
+        wrap: Some(HexWrap::east_west(8)),
+        ..Default::default()
+    };
+    let mut terrain = HexGridTerrain::default();
+    terrain.cells.insert(Axial::ZERO, WATER);
+    let across = config.wrap_cell(Axial::new(-1, 0));
+    assert_ne!(across, Axial::new(-1, 0));
+    terrain.cells.insert(across, LAND);
+
+    let mut auto = auto_tile();
+    auto.update(&mut terrain, &config);
+    assert_eq!(
+        terrain.variants.get(Axial::ZERO),
+        Some(&TileVariant {
+            kind: SHORE_ONE,
+            rotation: 3
+        })
+    );
+}
+
+fn edit() -> impl Strategy<Value = (Axial, Option<TerrainId>)> {
+    let cell = (-20i32..20, -20i32..20).prop_map(|(q, r)| Axial::new(q, r));
+    let terrain = prop::option::of((0u8..2).prop_map(TerrainId));
+    (cell, terrain)
+}
+
+proptest! {
+    #[test]
+    fn updates_match_full_pass(
+        start in prop::collection::vec(edit(), 0..200),
+        batches in prop::collection::vec(prop::collection::vec(edit(), 0..20), 1..6),
+    ) {
+        let config = HexGridConfig::default();
+        let mut terrain = HexGridTerrain::default();
+        let mut auto = auto_tile();
+        for batch in std::iter::once(&start).chain(&batches) {
+            for &(cell, t) in batch {
+                match t {
+                    Some(t) => terrain.cells.insert(cell, t),
+                    None => terrain.cells.remove(cell),
+                };
+            }
+            auto.update(&mut terrain, &config);
+            prop_assert_eq!(&terrain.variants, &auto.variants(&terrain.cells));
+        }
+    }
+}
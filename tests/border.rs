@@ -0,0 +1,78 @@
+//! border tests
+use bevy::prelude::*;
+use hex_grid::{
+    shape, Axial, BorderLayer, BorderStyle, CellOwner, HexDirection, HexEdge, HexGridBorders,
+    HexMap,
+};
+use proptest::prelude::*;
+
+// edge between two neighbors
+fn edge(a: Axial, b: Axial) -> HexEdge {
+    HexEdge::new(a, HexDirection::between(a, b).unwrap())
+}
+
+#[test]
+fn layers_are_styled_apart() {
+    let style = BorderStyle::new(Color::WHITE, 0.1);
+    let borders = HexGridBorders::new().with_border(BorderLayer::Owners, style);
+    assert_eq!(borders.style(BorderLayer::Owners), Some(style));
+    assert_eq!(borders.style(BorderLayer::Terrain), None);
+    assert!(borders.is_drawn());
+
+    let mut hidden = borders.clone();
+    hidden
+        .style_mut(BorderLayer::Owners)
+        .as_mut()
+        .unwrap()
+        .width = 0.0;
+    assert!(!hidden.is_drawn());
+    assert!(!HexGridBorders::new().is_drawn());
+}
+
+#[test]
+fn territory_edges() {
+    // two teams splitting a disk down the middle
+    let owners: HexMap<CellOwner> = Axial::ZERO
+        .range(3)
+        .map(|cell| (cell, CellOwner((cell.q >= 0) as u8)))
+        .collect();
+    let edges = HexGridBorders::edges(&owners);
+
+    // every edge of the disk's rim is a border
+    let rim = shape::ring(Axial::ZERO, 3)
+        .into_iter()
+        .flat_map(|cell| HexDirection::ALL.map(|dir| HexEdge::new(cell, dir)))
+        .filter(|edge| edge.cells().iter().any(|c| !owners.contains(*c)));
+    for edge in rim {
+        assert!(edges.contains(&edge), "{edge:?}");
+    }
+    // & the split between the teams
+    assert!(edges.contains(&edge(Axial::new(-1, 0), Axial::ZERO)));
+    assert!(edges.contains(&edge(Axial::new(-1, 2), Axial::new(0, 1))));
+    assert!(!edges.contains(&edge(Axial::ZERO, Axial::new(1, 0))));
+    assert!(!edges.contains(&edge(Axial::new(-1, 0), Axial::new(-2, 0))));
+}
+
+proptest! {
+    #[test]
+    fn edges_join_differing_cells(
+        values in prop::collection::vec(((-4i32..4, -4i32..4), 0u8..3), 0..40),
+    ) {
+        let map: HexMap<u8> = values
+            .into_iter()
+            .map(|((q, r), v)| (Axial::new(q, r), v))
+            .collect();
+        let edges = HexGridBorders::edges(&map);
+        for (cell, _) in map.iter() {
+            for dir in HexDirection::ALL {
+                let edge = HexEdge::new(cell, dir);
+                let [a, b] = edge.cells();
+                prop_assert_eq!(edges.contains(&edge), map.get(a) != map.get(b));
+            }
+        }
+        for edge in &edges {
+            let [a, b] = edge.cells();
+            prop_assert!(map.get(a) != map.get(b));
+        }
+    }
+}
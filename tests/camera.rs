@@ -0,0 +1,41 @@
+//! camera controller tests
+use hex_grid::camera::HexGridCamera;
+use proptest::prelude::*;
+
+#[test]
+fn fast_scroll_zooms_within_limits() {
+    let mut camera = HexGridCamera {
+        zoom_limits: (0.001, 1000.0),
+        ..Default::default()
+    };
+    // a trackpad flick of 160 pixels is 10 lines
+    camera.zoom_by(10.0);
+    assert!(
+        camera.zoom > 0.3 && camera.zoom < 1.0,
+        "zoom {}",
+        camera.zoom
+    );
+    camera.zoom_by(-10.0);
+    assert!((camera.zoom - 1.0).abs() < 1e-5);
+
+    let mut camera = HexGridCamera::default();
+    camera.zoom_by(1000.0);
+    assert_eq!(camera.zoom, camera.zoom_limits.0);
+    camera.zoom_by(-1000.0);
+    assert_eq!(camera.zoom, camera.zoom_limits.1);
+}
+
+proptest! {
+    // scrolling in steps zooms as far as scrolling all at once
+    #[test]
+    fn zoom_steps_compose(a in -20.0f32..20.0, b in -20.0f32..20.0) {
+        let limits = (1e-6, 1e6);
+        let mut stepped = HexGridCamera { zoom_limits: limits, ..Default::default() };
+        stepped.zoom_by(a);
+        stepped.zoom_by(b);
+        let mut once = HexGridCamera { zoom_limits: limits, ..Default::default() };
+        once.zoom_by(a + b);
+        prop_assert!(stepped.zoom > 0.0);
+        prop_assert!((stepped.zoom / once.zoom - 1.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,87 @@
+//! on demand cell entity tests
+use bevy::{ecs::system::RunSystemOnce, prelude::*};
+use hex_grid::{Axial, HexCell, HexCellIndex, HexCells};
+
+#[derive(Component)]
+struct Poisoned;
+
+fn world() -> (World, Entity) {
+    let mut world = World::new();
+    world.init_resource::<HexCellIndex>();
+    let grid = world.spawn_empty().id();
+    (world, grid)
+}
+
+#[test]
+fn spawned_once_per_cell() {
+    let (mut world, grid) = world();
+    let cell = Axial::new(3, -1);
+    let (a, b) = world
+        .run_system_once(move |mut cells: HexCells| {
+            let a = cells.entity(grid, cell).insert(Poisoned).id();
+            let b = cells.entity(grid, cell).id();
+            (a, b)
+        })
+        .unwrap();
+    assert_eq!(a, b);
+
+    let index = world.resource::<HexCellIndex>();
+    assert_eq!(index.len(), 1);
+    assert_eq!(index.get(grid, cell), Some(a));
+    assert_eq!(index.cell(a), Some(HexCell { grid, cell }));
+    assert!(world.entity(a).contains::<Poisoned>());
+    assert_eq!(
+        world.entity(a).get::<ChildOf>().map(|c| c.parent()),
+        Some(grid)
+    );
+}
+
+#[test]
+fn index_follows_components() {
+    let (mut world, grid) = world();
+    let entity = world
+        .spawn(HexCell {
+            grid,
+            cell: Axial::ZERO,
+        })
+        .id();
+    assert_eq!(
+        world.resource::<HexCellIndex>().get(grid, Axial::ZERO),
+        Some(entity)
+    );
+
+    // moving the entity to another cell
+    let cell = Axial::new(1, 1);
+    world.entity_mut(entity).insert(HexCell { grid, cell });
+    let index = world.resource::<HexCellIndex>();
+    assert_eq!(index.get(grid, Axial::ZERO), None);
+    assert_eq!(index.get(grid, cell), Some(entity));
+
+    world.despawn(entity);
+    let index = world.resource::<HexCellIndex>();
+    assert!(index.is_empty());
+    assert!(index.grid(grid).is_none());
+}
+
+#[test]
+fn despawned_with_grid() {
+    let (mut world, grid) = world();
+    let other = world.spawn_empty().id();
+    world
+        .run_system_once(move |mut cells: HexCells| {
+            for cell in Axial::ZERO.range(2) {
+                cells.entity(grid, cell);
+                cells.entity(other, cell);
+            }
+            cells.despawn(other, Axial::ZERO);
+        })
+        .unwrap();
+    let index = world.resource::<HexCellIndex>();
+    assert_eq!(index.grid(grid).map(|m| m.len()), Some(19));
+    assert_eq!(index.grid(other).map(|m| m.len()), Some(18));
+
+    world.despawn(grid);
+    let index = world.resource::<HexCellIndex>();
+    assert_eq!(index.len(), 18);
+    assert!(index.get(grid, Axial::ZERO).is_none());
+}
@@ -0,0 +1,74 @@
+//! changed cell message tests
+use bevy::prelude::*;
+use hex_grid::{
+    changes::{CellChanged, HexMapChangesPlugin, HexMapLayer},
+    Axial, HexGridHighlights, HexGridTerrain, HexMap, TerrainId,
+};
+
+#[derive(Component, Default)]
+struct Fog(HexMap<f32>);
+
+impl HexMapLayer for Fog {
+    type Value = f32;
+    const NAME: &'static str = "fog";
+
+    fn map_mut(&mut self) -> &mut HexMap<f32> {
+        &mut self.0
+    }
+}
+
+fn sent(app: &mut App) -> Vec<CellChanged> {
+    let mut sent: Vec<CellChanged> = app
+        .world_mut()
+        .resource_mut::<Messages<CellChanged>>()
+        .drain()
+        .collect();
+    sent.sort_by_key(|c| (c.layer, c.cell));
+    sent
+}
+
+#[test]
+fn changed_cells_are_sent_once() {
+    let mut app = App::new();
+    app.add_plugins(HexMapChangesPlugin::default().with_layer::<Fog>());
+
+    let mut terrain = HexGridTerrain::new([]);
+    terrain.cells.insert(Axial::new(1, 0), TerrainId(0));
+    let grid = app
+        .world_mut()
+        .spawn((terrain, HexGridHighlights::default(), Fog::default()))
+        .id();
+    app.update();
+
+    // cells already there are sent when the grid is first seen
+    let changed = sent(&mut app);
+    assert_eq!(changed.len(), 1);
+    assert_eq!((changed[0].grid, changed[0].cell), (grid, Axial::new(1, 0)));
+    assert!(changed[0].is::<HexGridTerrain>());
+
+    app.update();
+    assert!(sent(&mut app).is_empty());
+
+    let mut entity = app.world_mut().entity_mut(grid);
+    entity
+        .get_mut::<Fog>()
+        .unwrap()
+        .0
+        .insert(Axial::new(0, 2), 0.5);
+    let mut terrain = entity.get_mut::<HexGridTerrain>().unwrap();
+    terrain.cells.insert(Axial::new(3, 0), TerrainId(1));
+    terrain.cells.remove(Axial::new(1, 0));
+    app.update();
+
+    let changed: Vec<(&str, Axial)> = sent(&mut app).iter().map(|c| (c.layer, c.cell)).collect();
+    assert_eq!(
+        changed,
+        [
+            ("fog", Axial::new(0, 2)),
+            ("terrain", Axial::new(1, 0)),
+            ("terrain", Axial::new(3, 0)),
+        ]
+    );
+    app.update();
+    assert!(sent(&mut app).is_empty());
+}
@@ -0,0 +1,45 @@
+//! colorblind preset tests
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use hex_grid::{ColorblindPreset, HexGridConfig, HexGridPalette, OKABE_ITO};
+
+#[test]
+fn palette_takes_okabe_ito_colors() {
+    let palette = HexGridPalette {
+        colors: (0..10)
+            .map(|i| Color::srgba(0.1 * i as f32, 0.5, 0.5, 0.25 + 0.05 * i as f32))
+            .collect(),
+        ..default()
+    };
+    let safe = ColorblindPreset::OkabeIto.palette(&palette);
+
+    assert_eq!(safe.colors.len(), palette.colors.len());
+    assert_eq!(safe.border_width, palette.border_width);
+    for (team, (color, old)) in safe.colors.iter().zip(&palette.colors).enumerate() {
+        let expected = OKABE_ITO[team % OKABE_ITO.len()];
+        assert_eq!(color.to_srgba().with_alpha(1.0), expected.to_srgba());
+        assert_eq!(color.alpha(), old.alpha(), "team {team}");
+    }
+}
+
+#[test]
+fn patterns_only_with_their_preset() {
+    assert_eq!(HexGridConfig::default().colorblind, None);
+    assert_eq!(ColorblindPreset::OkabeIto.team_fill(0), None);
+
+    let preset = ColorblindPreset::OkabeItoPatterns;
+    let fills: Vec<_> = (0..OKABE_ITO.len())
+        .map(|team| preset.team_fill(team).unwrap())
+        .collect();
+
+    // each of the first teams has a pattern of its own
+    let distinct: HashSet<_> = fills
+        .iter()
+        .map(|f| (f.pattern, f.angle.to_bits()))
+        .collect();
+    assert_eq!(distinct.len(), fills.len());
+
+    // later teams reuse them in order
+    assert_eq!(preset.team_fill(OKABE_ITO.len() + 2), Some(fills[2]));
+}
@@ -0,0 +1,113 @@
+//! board command tests
+use bevy::prelude::*;
+use hex_grid::{Axial, CellOwner, HexBoard, HexCommand, UnitId};
+use proptest::prelude::*;
+
+fn axial() -> impl Strategy<Value = Axial> {
+    (-20i32..20, -20i32..20).prop_map(|(q, r)| Axial::new(q, r))
+}
+
+fn command() -> impl Strategy<Value = HexCommand> {
+    let unit = (0u32..6).prop_map(UnitId);
+    let color =
+        prop::option::of((0.0f32..1.0, 0.0f32..1.0).prop_map(|(r, g)| Color::srgb(r, g, 0.5)));
+    prop_oneof![
+        (unit.clone(), axial()).prop_map(|(unit, cell)| HexCommand::SpawnUnit { unit, cell }),
+        (unit.clone(), axial()).prop_map(|(unit, to)| HexCommand::MoveUnit { unit, to }),
+        unit.prop_map(|unit| HexCommand::RemoveUnit { unit }),
+        (axial(), prop::option::of((0u8..4).prop_map(CellOwner)))
+            .prop_map(|(cell, owner)| HexCommand::ClaimCell { cell, owner }),
+        (axial(), color).prop_map(|(cell, color)| HexCommand::Highlight { cell, color }),
+        (axial(), 0u32..3).prop_map(|(center, radius)| HexCommand::RevealFog { center, radius }),
+    ]
+}
+
+#[test]
+fn units() {
+    let mut board = HexBoard::new();
+    let (a, b) = (UnitId(1), UnitId(2));
+    let cell = Axial::new(1, 2);
+
+    assert!(!board.apply(&HexCommand::MoveUnit { unit: a, to: cell }));
+    assert!(board.apply(&HexCommand::SpawnUnit {
+        unit: a,
+        cell: Axial::ZERO
+    }));
+    assert!(board.apply(&HexCommand::SpawnUnit { unit: b, cell }));
+    // ids can't be reused
+    assert!(!board.apply(&HexCommand::SpawnUnit { unit: a, cell }));
+    assert_eq!(board.unit(a), Some(Axial::ZERO));
+
+    assert!(board.apply(&HexCommand::MoveUnit { unit: a, to: cell }));
+    assert_eq!(board.units_on(cell).collect::<Vec<_>>(), [a, b]);
+    assert!(board.apply(&HexCommand::RemoveUnit { unit: b }));
+    assert!(!board.apply(&HexCommand::RemoveUnit { unit: b }));
+    assert_eq!(board.units_on(cell).collect::<Vec<_>>(), [a]);
+}
+
+#[test]
+fn cells() {
+    let mut board = HexBoard::new();
+    let cell = Axial::new(-3, 1);
+    let owner = CellOwner(2);
+
+    assert!(board.apply(&HexCommand::ClaimCell {
+        cell,
+        owner: Some(owner)
+    }));
+    assert_eq!(board.owners.get(cell), Some(&owner));
+    assert!(board.apply(&HexCommand::ClaimCell { cell, owner: None }));
+    assert!(board.owners.is_empty());
+
+    let color = Some(Color::WHITE);
+    assert!(board.apply(&HexCommand::Highlight { cell, color }));
+    assert_eq!(board.highlights.get(cell), color.as_ref());
+    assert!(board.apply(&HexCommand::Highlight { cell, color: None }));
+    assert!(board.highlights.is_empty());
+
+    assert!(board.apply(&HexCommand::RevealFog {
+        center: cell,
+        radius: 2
+    }));
+    assert_eq!(board.revealed.len(), 19);
+    assert!(board.is_revealed(cell + Axial::new(2, 0)));
+    assert!(!board.is_revealed(cell + Axial::new(3, 0)));
+}
+
+proptest! {
+    #[test]
+    fn replays_match(commands in prop::collection::vec(command(), 0..60)) {
+        let replay = || {
+            let mut board = HexBoard::new();
+            let applied: Vec<bool> = commands.iter().map(|c| board.apply(c)).collect();
+            (board, applied)
+        };
+        prop_assert_eq!(replay(), replay());
+    }
+
+    #[test]
+    fn rejected_commands_change_nothing(commands in prop::collection::vec(command(), 0..60)) {
+        let mut board = HexBoard::new();
+        for command in &commands {
+            let before = board.clone();
+            if !board.apply(command) {
+                prop_assert_eq!(&board, &before);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+proptest! {
+    #[test]
+    fn serde_roundtrip(commands in prop::collection::vec(command(), 0..60)) {
+        let mut board = HexBoard::new();
+        for command in &commands {
+            let bytes = command.to_bytes().unwrap();
+            prop_assert_eq!(&HexCommand::from_bytes(&bytes).unwrap(), command);
+            board.apply(command);
+        }
+        let bytes = board.to_bytes().unwrap();
+        prop_assert_eq!(HexBoard::from_bytes(&bytes).unwrap(), board);
+    }
+}
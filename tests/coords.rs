@@ -0,0 +1,354 @@
+//! property tests for the coordinate math
+//!
+//! Off-by-half errors between the shader, world space, and the various
+//! coordinate systems are easy to introduce and hard to spot visually, so
+//! everything here is checked against random coordinates.
+use bevy::prelude::*;
+use hex_grid::{
+    coords::{Offset, OffsetKind},
+    select::cells_in_polygon,
+    Axial, Cube, GridKind, HexDirection, HexEdge, HexGridConfig, HexVertex, Orientation,
+};
+use proptest::prelude::*;
+use std::{collections::HashSet, f32::consts::FRAC_PI_3};
+
+// keep coordinates small enough that world positions stay precise in f32
+const RANGE: std::ops::Range<i32> = -10_000..10_000;
+
+fn axial() -> impl Strategy<Value = Axial> {
+    (RANGE, RANGE).prop_map(|(q, r)| Axial::new(q, r))
+}
+
+fn offset_kind() -> impl Strategy<Value = OffsetKind> {
+    prop_oneof![
+        Just(OffsetKind::OddR),
+        Just(OffsetKind::EvenR),
+        Just(OffsetKind::OddQ),
+        Just(OffsetKind::EvenQ),
+    ]
+}
+
+fn config() -> impl Strategy<Value = HexGridConfig> {
+    (
+        proptest::sample::select(GridKind::ALL.to_vec()),
+        prop_oneof![Just(Orientation::Pointy), Just(Orientation::Flat)],
+        0.25f32..4.0,
+    )
+        .prop_map(|(kind, orientation, size)| HexGridConfig {
+            kind,
+            orientation,
+            size,
+            ..default()
+        })
+}
+
+proptest! {
+    #[test]
+    fn superhex_contains_cell(a in axial(), radius in 0u32..12) {
+        let superhex = a.to_superhex(radius);
+        prop_assert!(superhex.superhex_center(radius).distance(a) <= radius);
+        prop_assert_eq!(superhex.superhex_center(radius).to_superhex(radius), superhex);
+    }
+
+    #[test]
+    fn superhexes_tile(s in (-50i32..50, -50i32..50), radius in 0u32..8) {
+        let superhex = Axial::new(s.0, s.1);
+        let children: HashSet<Axial> = superhex.superhex_children(radius).collect();
+        prop_assert_eq!(children.len() as u32, 3 * radius * radius + 3 * radius + 1);
+        for child in &children {
+            prop_assert_eq!(child.to_superhex(radius), superhex);
+        }
+        // neighboring superhexes are adjacent & don't overlap
+        let center = superhex.superhex_center(radius);
+        for neighbor in superhex.neighbors() {
+            prop_assert_eq!(neighbor.superhex_center(radius).distance(center), 2 * radius + 1);
+        }
+    }
+
+    #[test]
+    fn axial_cube_round_trip(a in axial()) {
+        let cube = a.to_cube();
+        prop_assert_eq!(cube.q + cube.r + cube.s, 0);
+        prop_assert_eq!(Axial::from(cube), a);
+    }
+
+    #[test]
+    fn axial_world_round_trip(a in axial()) {
+        prop_assert_eq!(Axial::from_world(a.to_world()), a);
+    }
+
+    #[test]
+    fn config_world_round_trip(a in axial(), config in config()) {
+        prop_assert_eq!(config.world_to_cell(config.cell_to_world(a)), a);
+    }
+
+    #[test]
+    fn axial_offset_round_trip(a in axial(), kind in offset_kind()) {
+        prop_assert_eq!(Offset::from_axial(a, kind).to_axial(kind), a);
+    }
+
+    #[test]
+    fn offset_axial_round_trip(col in RANGE, row in RANGE, kind in offset_kind()) {
+        let offset = Offset::new(col, row);
+        prop_assert_eq!(Offset::from_axial(offset.to_axial(kind), kind), offset);
+    }
+
+    /// any point within the inner radius of a cell center is in that cell
+    #[test]
+    fn world_point_in_cell(a in axial(), angle in 0.0f32..std::f32::consts::TAU, dist in 0.0f32..0.49) {
+        let point = a.to_world() + Vec2::from_angle(angle) * dist;
+        prop_assert_eq!(Axial::from_world(point), a);
+    }
+
+    /// any point maps to the cell with the closest center
+    #[test]
+    fn world_point_nearest_center(x in -1000.0f32..1000.0, y in -1000.0f32..1000.0) {
+        let point = Vec2::new(x, y);
+        let cell = Axial::from_world(point);
+        let dist = cell.to_world().distance(point);
+        for n in cell.neighbors() {
+            prop_assert!(dist <= n.to_world().distance(point) + 1e-3);
+        }
+    }
+
+    #[test]
+    fn cube_round_preserves_invariant(q in -1000.0f32..1000.0, r in -1000.0f32..1000.0) {
+        let cube = Cube::round(Vec3::new(q, r, -q - r));
+        prop_assert_eq!(cube.q + cube.r + cube.s, 0);
+    }
+
+    #[test]
+    fn distance_symmetric(a in axial(), b in axial()) {
+        prop_assert_eq!(a.distance(b), b.distance(a));
+        prop_assert_eq!(a.distance(b), (a - b).length());
+    }
+
+    #[test]
+    fn distance_identity(a in axial()) {
+        prop_assert_eq!(a.distance(a), 0);
+    }
+
+    #[test]
+    fn distance_triangle_inequality(a in axial(), b in axial(), c in axial()) {
+        prop_assert!(a.distance(c) <= a.distance(b) + b.distance(c));
+    }
+
+    #[test]
+    fn neighbors_are_adjacent(a in axial()) {
+        for n in a.neighbors() {
+            prop_assert_eq!(a.distance(n), 1);
+            // neighbor centers are exactly one unit apart in world space
+            prop_assert!((a.to_world().distance(n.to_world()) - 1.0).abs() < 1e-2);
+        }
+    }
+
+    #[test]
+    fn ring_cells_at_radius(a in axial(), radius in 0u32..20) {
+        let ring: Vec<Axial> = a.ring(radius).collect();
+        prop_assert_eq!(ring.len(), if radius == 0 { 1 } else { 6 * radius as usize });
+        prop_assert!(ring.iter().all(|c| a.distance(*c) == radius));
+        // consecutive cells are adjacent, including the wrap-around
+        for (i, c) in ring.iter().enumerate() {
+            let next = ring[(i + 1) % ring.len()];
+            prop_assert!(radius == 0 || c.distance(next) == 1);
+        }
+        let unique: HashSet<Axial> = ring.iter().copied().collect();
+        prop_assert_eq!(unique.len(), ring.len());
+    }
+
+    #[test]
+    fn spiral_and_range_match(a in axial(), radius in 0u32..20) {
+        let spiral: Vec<Axial> = a.spiral(radius).collect();
+        let range: HashSet<Axial> = a.range(radius).collect();
+        let n = radius as usize;
+        prop_assert_eq!(spiral.len(), 1 + 3 * n * (n + 1));
+        prop_assert_eq!(range.len(), spiral.len());
+        prop_assert!(spiral.iter().all(|c| range.contains(c)));
+        prop_assert!(spiral.windows(2).all(|w| a.distance(w[0]) <= a.distance(w[1])));
+    }
+
+    #[test]
+    fn rotate_left_moves_to_next_neighbor(dir in 0usize..6) {
+        let cube = Axial::NEIGHBORS[dir].to_cube();
+        prop_assert_eq!(cube.rotate_left().to_axial(), Axial::NEIGHBORS[(dir + 1) % 6]);
+        prop_assert_eq!(cube.rotate_right().to_axial(), Axial::NEIGHBORS[(dir + 5) % 6]);
+    }
+
+    #[test]
+    fn rotation_invariants(a in axial(), center in axial(), steps in -12i32..12) {
+        let cube = a.to_cube();
+        prop_assert_eq!(cube.rotate_left().rotate_right(), cube);
+        prop_assert_eq!(a.rotate_around(center, 6), a);
+        prop_assert_eq!(a.rotate_around(center, steps).rotate_around(center, -steps), a);
+
+        let rotated = a.rotate_around(center, steps);
+        prop_assert_eq!(rotated.distance(center), a.distance(center));
+        let rotated = rotated.to_cube();
+        prop_assert_eq!(rotated.q + rotated.r + rotated.s, 0);
+    }
+
+    /// rotating a pair of cells preserves the distance between them
+    #[test]
+    fn rotation_is_rigid(a in axial(), b in axial(), center in axial(), steps in 0i32..6) {
+        prop_assert_eq!(
+            a.rotate_around(center, steps).distance(b.rotate_around(center, steps)),
+            a.distance(b)
+        );
+    }
+
+    #[test]
+    fn reflection_invariants(a in axial(), b in axial()) {
+        let cube = a.to_cube();
+        for reflect in [Cube::reflect_q, Cube::reflect_r, Cube::reflect_s] {
+            let mirrored = reflect(cube);
+            prop_assert_eq!(reflect(mirrored), cube);
+            prop_assert_eq!(mirrored.q + mirrored.r + mirrored.s, 0);
+            prop_assert_eq!(mirrored.to_axial().length(), a.length());
+            prop_assert_eq!(
+                mirrored.to_axial().distance(reflect(b.to_cube()).to_axial()),
+                a.distance(b)
+            );
+        }
+    }
+
+    #[test]
+    fn hex_parity_differs_from_neighbors(a in axial()) {
+        let config = HexGridConfig::default();
+        for n in a.neighbors() {
+            prop_assert_ne!(config.parity(a), config.parity(n));
+        }
+    }
+
+    #[test]
+    fn neighbor_reciprocity(a in axial(), dir in 0usize..6) {
+        let dir = HexDirection::new(dir);
+        prop_assert_eq!(a.neighbor(dir).neighbor(dir.opposite()), a);
+        prop_assert!(a.neighbor(dir).neighbors().any(|n| n == a));
+    }
+
+    #[test]
+    fn direction_arithmetic(a in axial(), dir in 0usize..6, angle in -10.0f32..10.0) {
+        let d = HexDirection::new(dir);
+        prop_assert_eq!(a + d, a + Axial::NEIGHBORS[dir]);
+        prop_assert_eq!(a + d - d, a);
+        prop_assert_eq!(a + d + -d, a);
+        prop_assert_eq!(-d, d.opposite());
+        prop_assert_eq!(d * 3, Axial::from(d) * 3);
+        prop_assert_eq!(d.rotate(1).offset(), d.offset().to_cube().rotate_left().to_axial());
+        prop_assert_eq!(d.rotate(-1).offset(), d.offset().to_cube().rotate_right().to_axial());
+        let pointy = HexGridConfig::default();
+        prop_assert!((pointy.grid_to_world(d.offset().to_world()) - d.to_vec(Orientation::Pointy)).length() < 1e-5);
+        for orientation in [Orientation::Pointy, Orientation::Flat] {
+            prop_assert_eq!(HexDirection::from_angle(orientation, d.to_angle(orientation)), d);
+            prop_assert_eq!(HexDirection::from_vec(orientation, d.to_vec(orientation)), d);
+            // the nearest direction is within 30 degrees
+            let near = HexDirection::from_angle(orientation, angle);
+            prop_assert!(Vec2::from_angle(angle).dot(near.to_vec(orientation)) >= 0.866);
+        }
+    }
+
+    #[test]
+    fn direction_rotation(dir in 0usize..6, steps in -12i32..12) {
+        let d = HexDirection::new(dir);
+        for orientation in [Orientation::Pointy, Orientation::Flat] {
+            prop_assert_eq!(d.rotate_cw(orientation).rotate_ccw(orientation), d);
+            prop_assert_eq!(d.rotate_ccw(orientation).rotate_cw(orientation), d);
+            let (mut cw, mut ccw) = (d, d);
+            for _ in 0..6 {
+                cw = cw.rotate_cw(orientation);
+                ccw = ccw.rotate_ccw(orientation);
+            }
+            prop_assert_eq!(cw, d);
+            prop_assert_eq!(ccw, d);
+        }
+        prop_assert_eq!(d.rotate(steps).rotate(-steps), d);
+    }
+
+    /// counter-clockwise is counter-clockwise where the neighbor is drawn,
+    /// with +X right & +Z up, for both orientations
+    #[test]
+    fn direction_turns_on_screen(dir in 0usize..6) {
+        let d = HexDirection::new(dir);
+        for orientation in [Orientation::Pointy, Orientation::Flat] {
+            let config = HexGridConfig { orientation, ..default() };
+            // where the neighbor is drawn on the grid plane
+            let drawn = |d: HexDirection| config.grid_to_world(d.offset().to_world());
+            prop_assert!((drawn(d) - d.to_vec(orientation)).length() < 1e-5);
+            let ccw = drawn(d).angle_to(drawn(d.rotate_ccw(orientation)));
+            prop_assert!((ccw - FRAC_PI_3).abs() < 1e-4, "{:?} {}", orientation, ccw);
+            let cw = drawn(d).angle_to(drawn(d.rotate_cw(orientation)));
+            prop_assert!((cw + FRAC_PI_3).abs() < 1e-4, "{:?} {}", orientation, cw);
+        }
+    }
+
+    #[test]
+    fn edge_canonical(a in axial(), dir in 0usize..6) {
+        let dir = HexDirection::new(dir);
+        let edge = HexEdge::new(a, dir);
+        prop_assert!(edge.dir().index() < 3);
+        prop_assert_eq!(HexEdge::new(a.neighbor(dir), dir.opposite()), edge);
+        let cells = edge.cells();
+        prop_assert!(cells.contains(&a) && cells.contains(&a.neighbor(dir)));
+        // the midpoint is halfway between the two cell centers
+        let mid = (a.to_world() + a.neighbor(dir).to_world()) / 2.0;
+        prop_assert!(edge.to_world().distance(mid) < 1e-2);
+        prop_assert_eq!(HexEdge::from_world(mid), edge);
+    }
+
+    #[test]
+    fn vertex_canonical(a in axial(), corner in 0usize..6) {
+        let vertex = HexVertex::new(a, corner);
+        prop_assert!(vertex.corner() < 2);
+        prop_assert!(vertex.cells().contains(&a));
+        let angle = (corner as f32 * 60.0 + 30.0).to_radians();
+        let pos = a.to_world() + Vec2::from_angle(angle) / 3f32.sqrt();
+        prop_assert!(vertex.to_world().distance(pos) < 1e-2);
+        prop_assert_eq!(HexVertex::from_world(pos), vertex);
+        // every cell touching the corner is at the same distance from it
+        for cell in vertex.cells() {
+            prop_assert!((cell.to_world().distance(pos) - 1.0 / 3f32.sqrt()).abs() < 1e-2);
+        }
+    }
+
+    #[test]
+    fn edge_vertex_adjacency(a in axial(), dir in 0usize..6) {
+        let edge = HexEdge::new(a, HexDirection::new(dir));
+        for vertex in edge.vertices() {
+            prop_assert!(vertex.edges().contains(&edge));
+            prop_assert!(edge.cells().iter().all(|c| vertex.cells().contains(c)));
+        }
+        let neighbors: HashSet<HexEdge> = edge.neighbors().collect();
+        prop_assert_eq!(neighbors.len(), 4);
+        prop_assert!(!neighbors.contains(&edge));
+
+        let vertex = HexVertex::new(a, dir);
+        let neighbors: HashSet<HexVertex> = vertex.neighbors().collect();
+        prop_assert_eq!(neighbors.len(), 3);
+        for n in neighbors {
+            prop_assert!((n.to_world().distance(vertex.to_world()) - 1.0 / 3f32.sqrt()).abs() < 1e-2);
+            prop_assert!(n.neighbors().any(|v| v == vertex));
+        }
+    }
+
+    #[test]
+    fn polygon_cells(config in config(), a in (-20..20, -20..20), b in (-20..20, -20..20)) {
+        let (a, b) = (Axial::new(a.0, a.1), Axial::new(b.0, b.1));
+        let (pa, pb) = (config.cell_to_world(a), config.cell_to_world(b));
+        let pad = config.size * 0.1;
+        let (min, max) = (pa.min(pb) - pad, pa.max(pb) + pad);
+        let corners = [min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y)];
+
+        let cells = cells_in_polygon(&config, &corners);
+        prop_assert!(cells.contains(&a) && cells.contains(&b));
+
+        // compare against every cell near the box; triangle q is doubled
+        let expected: HashSet<Axial> = (-100..100)
+            .flat_map(|q| (-50..50).map(move |r| Axial::new(q, r)))
+            .filter(|cell| {
+                let pos = config.cell_to_world(*cell);
+                pos.cmpgt(min).all() && pos.cmplt(max).all()
+            })
+            .collect();
+        prop_assert_eq!(cells.into_iter().collect::<HashSet<_>>(), expected);
+    }
+}
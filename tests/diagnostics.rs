@@ -0,0 +1,50 @@
+//! grid pass diagnostics tests
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticMeasurement, DiagnosticsStore},
+    platform::time::Instant,
+    prelude::*,
+};
+use hex_grid::{HexGridDiagnostics, HexGridDiagnosticsPlugin};
+use std::time::Duration;
+
+fn measure(app: &mut App, values: &[(Instant, f64, f64)]) {
+    let mut store = app.world_mut().resource_mut::<DiagnosticsStore>();
+    for path in [HexGridDiagnostics::GPU_TIME, HexGridDiagnostics::CPU_TIME] {
+        if store.get(&path).is_none() {
+            store.add(Diagnostic::new(path));
+        }
+    }
+    for &(time, gpu, cpu) in values {
+        for (path, value) in [
+            (HexGridDiagnostics::GPU_TIME, gpu),
+            (HexGridDiagnostics::CPU_TIME, cpu),
+        ] {
+            let diagnostic = store.get_mut(&path).unwrap();
+            diagnostic.add_measurement(DiagnosticMeasurement { time, value });
+        }
+    }
+}
+
+#[test]
+fn sums_passes_of_the_latest_frame() {
+    let mut app = App::new();
+    app.add_plugins(HexGridDiagnosticsPlugin);
+    app.update();
+    assert_eq!(
+        *app.world().resource::<HexGridDiagnostics>(),
+        HexGridDiagnostics::default()
+    );
+
+    // two cameras last frame, one the frame before
+    let earlier = Instant::now();
+    let latest = earlier + Duration::from_millis(16);
+    measure(
+        &mut app,
+        &[(earlier, 4.0, 1.0), (latest, 0.5, 0.25), (latest, 1.5, 0.5)],
+    );
+    app.update();
+    let diagnostics = app.world().resource::<HexGridDiagnostics>();
+    assert_eq!(diagnostics.gpu_time, Some(2.0));
+    assert_eq!(diagnostics.cpu_time, Some(0.75));
+    assert_eq!(diagnostics.passes, 2);
+}
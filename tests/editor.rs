@@ -0,0 +1,272 @@
+//! map editor tests
+use bevy::prelude::*;
+use hex_grid::{
+    editor::{EditorCell, EditorLayer, EditorTool, HexEditor, HexEditorPlugin},
+    shape::Brush,
+    Axial, CellOwner, HexGridConfig, HexGridCursor, HexGridElevation, HexGridOwners,
+    HexGridTerrain, TerrainId,
+};
+
+fn terrain(editor: &HexEditor, cell: Axial) -> Option<TerrainId> {
+    editor.document().get(cell).and_then(|c| c.terrain)
+}
+
+#[test]
+fn paint_layers() {
+    let mut editor = HexEditor::default();
+    editor.terrain = TerrainId(3);
+    editor.paint([Axial::ZERO]);
+    editor.layer = EditorLayer::Elevation;
+    editor.elevation = 2.0;
+    editor.paint([Axial::ZERO]);
+    editor.layer = EditorLayer::Ownership;
+    editor.owner = CellOwner(1);
+    editor.paint([Axial::ZERO]);
+    assert_eq!(
+        editor.document().get(Axial::ZERO),
+        Some(&EditorCell {
+            terrain: Some(TerrainId(3)),
+            elevation: Some(2.0),
+            owner: Some(CellOwner(1)),
+        })
+    );
+
+    // erasing every layer removes the cell
+    editor.erase = true;
+    for layer in [
+        EditorLayer::Terrain,
+        EditorLayer::Elevation,
+        EditorLayer::Ownership,
+    ] {
+        editor.layer = layer;
+        editor.paint([Axial::ZERO]);
+    }
+    assert!(editor.document().is_empty());
+
+    // each paint is one change
+    for _ in 0..6 {
+        assert!(editor.undo());
+    }
+    assert!(!editor.undo());
+    assert!(editor.document().is_empty());
+}
+
+#[test]
+fn fill() {
+    let mut editor = HexEditor::default();
+    editor.paint(Axial::ZERO.range(3));
+    editor.terrain = TerrainId(1);
+    editor.paint(Axial::ZERO.ring(2));
+
+    // the inside of the ring is bounded by it
+    editor.terrain = TerrainId(2);
+    editor.fill(Axial::ZERO);
+    assert!(Axial::ZERO
+        .range(1)
+        .all(|c| terrain(&editor, c) == Some(TerrainId(2))));
+    assert!(Axial::ZERO
+        .ring(2)
+        .all(|c| terrain(&editor, c) == Some(TerrainId(1))));
+    assert!(Axial::ZERO
+        .ring(3)
+        .all(|c| terrain(&editor, c) == Some(TerrainId(0))));
+
+    // empty regions are bounded by the document
+    let bounds = editor.document().bounds().unwrap();
+    editor.fill(Axial::new(3, 3));
+    assert_eq!(editor.document().bounds(), Some(bounds));
+    assert_eq!(terrain(&editor, Axial::new(3, 3)), Some(TerrainId(2)));
+
+    assert!(editor.undo());
+    assert_eq!(terrain(&editor, Axial::new(3, 3)), None);
+    assert!(editor.undo());
+    assert_eq!(terrain(&editor, Axial::ZERO), Some(TerrainId(0)));
+}
+
+#[test]
+fn tool_cells() {
+    let mut editor = HexEditor::default();
+    let (a, b) = (Axial::new(-2, 1), Axial::new(3, -2));
+    editor.tool = EditorTool::Line;
+    let mut line = editor.tool_cells(a, b);
+    line.sort();
+    let mut expected = hex_grid::shape::line(a, b);
+    expected.sort();
+    assert_eq!(line, expected);
+    editor.tool = EditorTool::Rectangle;
+    assert_eq!(editor.tool_cells(a, b).len(), 5 * 4);
+    editor.tool = EditorTool::Brush;
+    editor.brush = Brush::Blob { radius: 1 };
+    assert_eq!(editor.tool_cells(a, a).len(), 7);
+}
+
+// app with a grid under the cursor at `cell`
+fn app(cell: Axial) -> (App, Entity) {
+    let mut app = App::new();
+    app.add_plugins(HexEditorPlugin)
+        .init_resource::<ButtonInput<MouseButton>>()
+        .init_resource::<ButtonInput<KeyCode>>();
+    let mut terrain = HexGridTerrain::default();
+    terrain.cells.insert(Axial::new(5, 5), TerrainId(4));
+    let grid = app
+        .world_mut()
+        .spawn((
+            HexGridConfig::default(),
+            terrain,
+            HexGridElevation::default(),
+            HexGridOwners::default(),
+        ))
+        .id();
+    app.world_mut().spawn(HexGridCursor {
+        grid: Some(grid),
+        cell,
+        ..default()
+    });
+    (app, grid)
+}
+
+fn move_cursor(app: &mut App, cell: Axial) {
+    let mut cursors = app.world_mut().query::<&mut HexGridCursor>();
+    cursors.single_mut(app.world_mut()).unwrap().cell = cell;
+}
+
+fn buttons(app: &mut App) -> Mut<'_, ButtonInput<MouseButton>> {
+    app.world_mut().resource_mut::<ButtonInput<MouseButton>>()
+}
+
+#[test]
+fn brush_stroke() {
+    let (mut app, grid) = app(Axial::ZERO);
+    app.world_mut().resource_mut::<HexEditor>().terrain = TerrainId(1);
+
+    buttons(&mut app).press(MouseButton::Left);
+    app.update();
+    buttons(&mut app).clear();
+    let to = Axial::new(3, 0);
+    move_cursor(&mut app, to);
+    app.update();
+    buttons(&mut app).release(MouseButton::Left);
+    app.update();
+
+    let terrain = app.world().get::<HexGridTerrain>(grid).unwrap();
+    // the grid's cells were read in, & the stroke written back
+    assert_eq!(terrain.cells.get(Axial::new(5, 5)), Some(&TerrainId(4)));
+    for cell in hex_grid::shape::line(Axial::ZERO, to) {
+        assert_eq!(terrain.cells.get(cell), Some(&TerrainId(1)));
+    }
+    assert_eq!(terrain.cells.len(), 5);
+
+    // the whole stroke is undone at once
+    assert!(app.world_mut().resource_mut::<HexEditor>().undo());
+    app.update();
+    let terrain = app.world().get::<HexGridTerrain>(grid).unwrap();
+    assert_eq!(terrain.cells.len(), 1);
+    assert!(!app.world().resource::<HexEditor>().can_undo());
+}
+
+#[test]
+fn rectangle_and_ownership() {
+    let (mut app, grid) = app(Axial::new(1, 1));
+    {
+        let mut editor = app.world_mut().resource_mut::<HexEditor>();
+        editor.tool = EditorTool::Rectangle;
+        editor.layer = EditorLayer::Ownership;
+        editor.owner = CellOwner(2);
+    }
+
+    buttons(&mut app).press(MouseButton::Left);
+    app.update();
+    buttons(&mut app).clear();
+    move_cursor(&mut app, Axial::new(2, 3));
+    app.update();
+    // nothing is painted until the button is released
+    assert!(app
+        .world()
+        .get::<HexGridOwners>(grid)
+        .unwrap()
+        .as_map()
+        .is_empty());
+    buttons(&mut app).release(MouseButton::Left);
+    app.update();
+
+    let owners = app.world().get::<HexGridOwners>(grid).unwrap();
+    let rect = hex_grid::shape::rectangle(Axial::new(1, 1), Axial::new(2, 3));
+    assert_eq!(owners.as_map().len(), rect.len());
+    assert!(rect.iter().all(|&c| owners.get(c) == Some(CellOwner(2))));
+}
+
+#[test]
+fn keys_undo() {
+    let (mut app, grid) = app(Axial::ZERO);
+    buttons(&mut app).press(MouseButton::Left);
+    app.update();
+    buttons(&mut app).release(MouseButton::Left);
+    app.update();
+    buttons(&mut app).clear();
+    assert_eq!(
+        app.world().get::<HexGridTerrain>(grid).unwrap().cells.len(),
+        2
+    );
+
+    let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+    keys.press(KeyCode::ControlLeft);
+    keys.press(KeyCode::KeyZ);
+    app.update();
+    assert_eq!(
+        app.world().get::<HexGridTerrain>(grid).unwrap().cells.len(),
+        1
+    );
+
+    let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+    keys.clear();
+    keys.press(KeyCode::KeyY);
+    app.update();
+    assert_eq!(
+        app.world().get::<HexGridTerrain>(grid).unwrap().cells.len(),
+        2
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn save_and_load() {
+    let (mut app, grid) = app(Axial::ZERO);
+    let path = std::env::temp_dir().join(format!("hex_grid_editor_{}", std::process::id()));
+    {
+        let mut editor = app.world_mut().resource_mut::<HexEditor>();
+        editor.grid = Some(grid);
+    }
+    app.update();
+    let saved = {
+        let mut editor = app.world_mut().resource_mut::<HexEditor>();
+        editor.layer = EditorLayer::Elevation;
+        editor.paint(Axial::ZERO.range(2));
+        editor.save(&path).unwrap();
+        let saved = editor.document().clone();
+        editor.erase = true;
+        editor.paint(Axial::ZERO.range(2));
+        saved
+    };
+    app.update();
+    assert!(app
+        .world()
+        .get::<HexGridElevation>(grid)
+        .unwrap()
+        .cells
+        .is_empty());
+
+    app.world_mut()
+        .resource_mut::<HexEditor>()
+        .load(&path)
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    app.update();
+    let editor = app.world().resource::<HexEditor>();
+    assert_eq!(editor.document(), &saved);
+    assert!(!editor.can_undo());
+    let elevation = app.world().get::<HexGridElevation>(grid).unwrap();
+    assert_eq!(elevation.cells.len(), 19);
+    // a loaded document replaces the grid's layers entirely
+    let terrain = app.world().get::<HexGridTerrain>(grid).unwrap();
+    assert_eq!(terrain.cells.len(), 1);
+}
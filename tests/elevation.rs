@@ -0,0 +1,61 @@
+//! cliff edge tests
+use hex_grid::{path::find_path, Axial, HexDirection, HexGridElevation, HexMap};
+
+#[test]
+fn cliffs_past_the_threshold() {
+    let (low, high, mid, bare) = (
+        Axial::new(0, 0),
+        Axial::new(1, 0),
+        Axial::new(0, 1),
+        Axial::new(-1, 0),
+    );
+    let cells: HexMap<f32> = [(low, 0.0), (high, 2.0), (mid, 0.5)].into_iter().collect();
+    let elevation = HexGridElevation::new(cells).with_threshold(1.0);
+
+    assert_eq!(elevation.cliff(high, low), Some(2.0));
+    assert_eq!(elevation.cliff(low, high), Some(-2.0));
+    assert_eq!(elevation.cliff(low, mid), None);
+    // cells without a height never have cliffs
+    assert_eq!(elevation.cliff(low, bare), None);
+
+    // with no threshold, any difference is a cliff, but equal heights never
+    let elevation = elevation.with_threshold(0.0);
+    assert_eq!(elevation.cliff(mid, low), Some(0.5));
+    assert_eq!(elevation.cliff(low, low), None);
+}
+
+#[test]
+fn paths_climb_by_ramps() {
+    // a plateau of height 2 around the origin, on flat ground
+    let heights: HexMap<f32> = Axial::ZERO
+        .range(6)
+        .map(|cell| {
+            (
+                cell,
+                if cell.distance(Axial::ZERO) <= 1 {
+                    2.0
+                } else {
+                    0.0
+                },
+            )
+        })
+        .collect();
+    let elevation = HexGridElevation::new(heights);
+    let start = Axial::new(4, 0);
+    let in_bounds = |_, to: Axial| (to.distance(Axial::ZERO) <= 6).then_some(1);
+
+    assert!(!elevation.can_step(Axial::new(2, 0), Axial::new(1, 0)));
+    assert_eq!(
+        find_path(start, Axial::ZERO, elevation.step_cost(in_bounds)),
+        None
+    );
+
+    // a ramp at the foot of the plateau, rising west onto it
+    let elevation = elevation.with_ramp(Axial::new(2, 0), HexDirection::new(3));
+    assert!(elevation.ramp_between(Axial::new(1, 0), Axial::new(2, 0)));
+    assert_eq!(elevation.cliff(Axial::new(1, 0), Axial::new(2, 0)), None);
+    let (path, cost) = find_path(start, Axial::ZERO, elevation.step_cost(in_bounds)).unwrap();
+    assert_eq!(cost, 4);
+    assert!(path.windows(2).all(|w| elevation.can_step(w[0], w[1])));
+    assert!(path.contains(&Axial::new(2, 0)));
+}
@@ -0,0 +1,125 @@
+//! glTF export tests
+use bevy::prelude::*;
+use hex_grid::{
+    export::{hex_map_glb, GltfCell, GltfMeshes},
+    map::CHUNK_SIZE,
+    Axial, HexGridConfig, HexMap,
+};
+
+// json text & binary buffer of a glb, checking the container on the way
+fn split_glb(glb: &[u8]) -> (String, Vec<u8>) {
+    let word = |i: usize| u32::from_le_bytes(glb[i..i + 4].try_into().unwrap()) as usize;
+    assert_eq!(&glb[0..4], b"glTF");
+    assert_eq!(word(4), 2);
+    assert_eq!(word(8), glb.len());
+
+    let json_len = word(12);
+    assert_eq!(&glb[16..20], b"JSON");
+    assert_eq!(json_len % 4, 0);
+    let json = String::from_utf8(glb[20..20 + json_len].to_vec()).unwrap();
+
+    let rest = 20 + json_len;
+    if rest == glb.len() {
+        return (json, Vec::new());
+    }
+    let bin_len = word(rest);
+    assert_eq!(&glb[rest + 4..rest + 8], b"BIN\0");
+    assert_eq!(rest + 8 + bin_len, glb.len());
+    (json, glb[rest + 8..].to_vec())
+}
+
+fn counts(json: &str, key: &str) -> Vec<usize> {
+    json.match_indices(key)
+        .map(|(i, _)| {
+            let digits: String = json[i + key.len()..]
+                .chars()
+                .take_while(char::is_ascii_digit)
+                .collect();
+            digits.parse().unwrap()
+        })
+        .collect()
+}
+
+fn flat(_: Axial, color: &Color) -> GltfCell {
+    GltfCell {
+        color: *color,
+        height: 0.0,
+    }
+}
+
+#[test]
+fn empty_map() {
+    let glb = hex_map_glb(
+        &HexMap::<Color>::new(),
+        &HexGridConfig::default(),
+        GltfMeshes::Merged,
+        flat,
+    );
+    let (json, bin) = split_glb(&glb);
+    assert!(json.contains(r#""nodes":[]"#));
+    assert!(!json.contains("meshes"));
+    assert!(bin.is_empty());
+}
+
+#[test]
+fn flat_cells_have_only_tops() {
+    let mut map = HexMap::new();
+    map.insert(Axial::ZERO, Color::WHITE);
+    map.insert(Axial::new(1, 0), Color::BLACK);
+    let glb = hex_map_glb(&map, &HexGridConfig::default(), GltfMeshes::Merged, flat);
+    let (json, bin) = split_glb(&glb);
+
+    // position, normal, color & index accessors of one mesh: 6 corners &
+    // 4 triangles per hex
+    assert_eq!(counts(&json, r#""count":"#), [12, 12, 12, 24]);
+    assert_eq!(
+        counts(&json, r#""buffers":[{"byteLength":"#),
+        [12 * (12 + 12 + 16) + 24 * 4]
+    );
+    assert_eq!(bin.len(), 12 * (12 + 12 + 16) + 24 * 4);
+}
+
+#[test]
+fn raised_cells_have_walls() {
+    let mut map = HexMap::new();
+    map.insert(Axial::ZERO, 2.0);
+    let glb = hex_map_glb(
+        &map,
+        &HexGridConfig::default(),
+        GltfMeshes::Merged,
+        |_, h| GltfCell {
+            color: Color::WHITE,
+            height: *h,
+        },
+    );
+    let (json, _) = split_glb(&glb);
+    // top, and a quad per side
+    assert_eq!(counts(&json, r#""count":"#), [30, 30, 30, 12 + 36]);
+    assert!(json.contains(r#""max":["#));
+}
+
+#[test]
+fn per_chunk_meshes() {
+    let mut map = HexMap::new();
+    for q in 0..CHUNK_SIZE * 2 {
+        map.insert(Axial::new(q, 0), Color::WHITE);
+    }
+    map.insert(Axial::new(0, -1), Color::WHITE);
+    let config = HexGridConfig::default();
+
+    let merged = hex_map_glb(&map, &config, GltfMeshes::Merged, flat);
+    let (json, _) = split_glb(&merged);
+    assert_eq!(json.matches(r#""mode":4"#).count(), 1);
+
+    let chunked = hex_map_glb(&map, &config, GltfMeshes::PerChunk, flat);
+    let (json, _) = split_glb(&chunked);
+    assert_eq!(json.matches(r#""mode":4"#).count(), 3);
+    assert!(json.contains(r#""nodes":[0,1,2]"#));
+    assert!(json.contains(r#""name":"chunk 0 -1""#));
+
+    // the same map always exports the same bytes
+    assert_eq!(
+        chunked,
+        hex_map_glb(&map.clone(), &config, GltfMeshes::PerChunk, flat)
+    );
+}
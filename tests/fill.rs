@@ -0,0 +1,87 @@
+//! pattern fill tests
+use bevy::prelude::*;
+use hex_grid::{Axial, CellFill, FillId, FillPattern, HexGridFills};
+use proptest::prelude::*;
+
+#[test]
+fn regions_get_their_fill() {
+    let blocked = CellFill::new(FillPattern::Hatch).with_color(Color::srgba(0.9, 0.2, 0.2, 0.8));
+    let contested = CellFill::new(FillPattern::Stripes);
+    let mut fills = HexGridFills::new([blocked, contested]);
+
+    fills.set_region(Axial::ZERO.range(1), FillId(0));
+    fills.set_region(Axial::new(4, 0).range(1), FillId(1));
+    assert_eq!(fills.get(Axial::new(0, 1)), Some(&blocked));
+    assert_eq!(fills.get(Axial::new(5, -1)), Some(&contested));
+    assert_eq!(fills.get(Axial::new(9, 0)), None);
+
+    fills.clear_region([Axial::ZERO]);
+    assert_eq!(fills.get(Axial::ZERO), None);
+
+    let kinds = vec![blocked; HexGridFills::MAX_KINDS + 1];
+    let mut fills = HexGridFills::new(kinds);
+    fills.set_region([Axial::ZERO], FillId(HexGridFills::MAX_KINDS as u8));
+    assert_eq!(fills.get(Axial::ZERO), None);
+}
+
+#[test]
+fn patterns() {
+    let stripes = CellFill::new(FillPattern::Stripes)
+        .with_spacing(1.0)
+        .with_width(0.5)
+        .with_angle(0.0);
+    assert!(stripes.covers(Vec2::new(0.1, 0.3)));
+    assert!(stripes.covers(Vec2::new(-0.2, 7.0)));
+    assert!(!stripes.covers(Vec2::new(0.5, 0.0)));
+    assert!(stripes.covers(Vec2::new(3.1, 0.0)));
+
+    let cross = CellFill::new(FillPattern::Crosshatch)
+        .with_spacing(1.0)
+        .with_width(0.2)
+        .with_angle(0.0);
+    assert!(cross.covers(Vec2::new(0.05, 0.5)));
+    assert!(cross.covers(Vec2::new(0.5, 0.05)));
+    assert!(!cross.covers(Vec2::new(0.5, 0.5)));
+
+    let dots = CellFill::new(FillPattern::Dots)
+        .with_spacing(1.0)
+        .with_width(0.4)
+        .with_angle(0.0);
+    assert!(dots.covers(Vec2::new(0.1, 0.1)));
+    assert!(dots.covers(Vec2::new(2.0, -3.0)));
+    assert!(!dots.covers(Vec2::new(0.5, 0.0)));
+}
+
+proptest! {
+    // patterns repeat every `spacing` along & across their lines
+    #[test]
+    fn patterns_repeat(
+        x in -5.0f32..5.0,
+        y in -5.0f32..5.0,
+        angle in 0.0f32..std::f32::consts::TAU,
+        steps in -3i32..3,
+        pattern in prop_oneof![
+            Just(FillPattern::Hatch),
+            Just(FillPattern::Stripes),
+            Just(FillPattern::Crosshatch),
+            Just(FillPattern::Dots),
+        ],
+    ) {
+        let fill = CellFill::new(pattern).with_angle(angle);
+        let point = Vec2::new(x, y);
+        let dir = Vec2::from_angle(angle);
+        let half = fill.width * fill.spacing * 0.5;
+        // away from the edges of a line or dot, where rounding decides
+        let offset = |d: f32| {
+            let o = ((d / fill.spacing + 0.5).rem_euclid(1.0) - 0.5) * fill.spacing;
+            o.abs()
+        };
+        let (across, along) = (offset(point.dot(dir)), offset(point.dot(dir.perp())));
+        prop_assume!((across - half).abs() > 1e-3 && (along - half).abs() > 1e-3);
+        prop_assume!((across.hypot(along) - half).abs() > 1e-3);
+
+        let step = steps as f32 * fill.spacing;
+        let moved = point + dir * step + dir.perp() * step;
+        prop_assert_eq!(fill.covers(point), fill.covers(moved));
+    }
+}
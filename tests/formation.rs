@@ -0,0 +1,145 @@
+//! group movement tests
+use bevy::platform::collections::HashSet;
+use hex_grid::{
+    formation::{group_paths, Formation},
+    Axial, HexDirection,
+};
+use proptest::prelude::*;
+
+const FORMATIONS: [Formation; 4] = [
+    Formation::Cluster,
+    Formation::Line,
+    Formation::Column,
+    Formation::Wedge,
+];
+
+fn open(cell: Axial) -> Option<u32> {
+    (cell.length() <= 20).then_some(1)
+}
+
+// open ground with a wall across q = 0, open through a gap 3 cells wide
+fn pass(cell: Axial) -> Option<u32> {
+    let wall = cell.q == 0 && !(0..3).contains(&cell.r);
+    (cell.length() <= 20 && !wall).then_some(1)
+}
+
+// cell of every unit at each step, holding the last cell once a path ends
+fn steps(paths: &[Vec<Axial>]) -> Vec<Vec<Axial>> {
+    let len = paths.iter().map(Vec::len).max().unwrap_or(0);
+    (0..len)
+        .map(|i| paths.iter().map(|p| p[i.min(p.len() - 1)]).collect())
+        .collect()
+}
+
+fn check(units: &[Axial], paths: &[Vec<Axial>], cost: impl Fn(Axial) -> Option<u32>) {
+    assert_eq!(paths.len(), units.len());
+    for (path, &unit) in paths.iter().zip(units) {
+        assert_eq!(path[0], unit);
+        for pair in path.windows(2) {
+            assert!(pair[0].distance(pair[1]) <= 1);
+            assert!(pair[0] == pair[1] || cost(pair[1]).is_some());
+        }
+    }
+    let steps = steps(paths);
+    for (i, cells) in steps.iter().enumerate() {
+        let unique: HashSet<Axial> = cells.iter().copied().collect();
+        assert_eq!(unique.len(), cells.len(), "units share a cell at step {i}");
+        if let Some(next) = steps.get(i + 1) {
+            for a in 0..cells.len() {
+                for b in 0..cells.len() {
+                    let swapped = a != b && cells[a] == next[b] && cells[b] == next[a];
+                    assert!(!swapped, "units swap cells at step {i}");
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn offsets_are_distinct_and_start_with_the_leader() {
+    for formation in FORMATIONS {
+        for facing in 0..6 {
+            let offsets = formation.offsets(12, HexDirection::new(facing));
+            assert_eq!(offsets.len(), 12);
+            assert_eq!(offsets[0], Axial::ZERO);
+            let unique: HashSet<Axial> = offsets.iter().copied().collect();
+            assert_eq!(unique.len(), 12, "{formation:?}");
+        }
+    }
+    // a line is across the direction of travel
+    let line = Formation::Line.offsets(5, HexDirection::new(0));
+    assert!(line.iter().all(|o| o.distance(Axial::ZERO) <= 2));
+    assert!(line.iter().any(|o| o.r > 0) && line.iter().any(|o| o.r < 0));
+    // a column trails behind
+    let column = Formation::Column.offsets(6, HexDirection::new(0));
+    assert!(column.iter().all(|o| o.q <= 0));
+}
+
+#[test]
+fn groups_end_in_formation() {
+    let units: Vec<Axial> = Axial::new(-12, 0).spiral(1).collect();
+    let goal = Axial::new(10, -2);
+    for formation in FORMATIONS {
+        let paths = group_paths(&units, goal, formation, open);
+        check(&units, &paths, open);
+        // in the open, every unit is in its place around the goal, facing
+        // the way the group came
+        let ends: HashSet<Axial> = paths.iter().map(|p| *p.last().unwrap()).collect();
+        let placed = (0..6).any(|facing| {
+            let offsets = formation.offsets(units.len(), HexDirection::new(facing));
+            ends == offsets.iter().map(|&o| goal + o).collect()
+        });
+        assert!(placed, "{formation:?} {ends:?}");
+    }
+}
+
+#[test]
+fn groups_stay_together_in_the_open() {
+    let units: Vec<Axial> = Axial::new(-12, 0).spiral(1).collect();
+    let paths = group_paths(&units, Axial::new(12, 0), Formation::Cluster, open);
+    // halfway there the group is as close as when it set off, not strung
+    // out single file
+    let steps = steps(&paths);
+    let middle = &steps[steps.len() / 2];
+    let spread = middle
+        .iter()
+        .flat_map(|a| middle.iter().map(move |b| a.distance(*b)))
+        .max();
+    assert!(spread <= Some(3), "{middle:?}");
+}
+
+#[test]
+fn groups_squeeze_through_a_pass() {
+    let units: Vec<Axial> = Axial::new(-8, 1).spiral(1).collect();
+    let goal = Axial::new(8, 1);
+    let paths = group_paths(&units, goal, Formation::Line, pass);
+    check(&units, &paths, pass);
+    assert!(paths.iter().all(|p| p.last().unwrap().q > 0));
+}
+
+#[test]
+fn unreachable_goals_leave_units_in_place() {
+    let units = [Axial::new(-3, 0), Axial::new(-3, 1)];
+    let paths = group_paths(&units, Axial::new(40, 0), Formation::Line, open);
+    assert_eq!(paths, vec![vec![units[0]], vec![units[1]]]);
+}
+
+proptest! {
+    #[test]
+    fn paths_never_collide(
+        units in prop::collection::hash_set((-6i32..6, -6i32..6), 1..10),
+        goal in (-10i32..10, -10i32..10),
+        formation in proptest::sample::select(FORMATIONS.to_vec()),
+    ) {
+        let units: Vec<Axial> = units.into_iter().map(|(q, r)| Axial::new(q, r)).collect();
+        let goal = Axial::new(goal.0, goal.1);
+        let paths = group_paths(&units, goal, formation, pass);
+        check(&units, &paths, pass);
+        // every unit that could reach the goal gets near it
+        for (path, &unit) in paths.iter().zip(&units) {
+            if pass(unit).is_some() && pass(goal).is_some() {
+                prop_assert!(path.last().unwrap().distance(goal) <= 6, "{:?}", path);
+            }
+        }
+    }
+}
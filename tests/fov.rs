@@ -0,0 +1,60 @@
+//! field of view tests
+use hex_grid::{
+    fov::{field_of_view, fields_of_view},
+    shape::line,
+    Axial,
+};
+use proptest::prelude::*;
+
+fn axial() -> impl Strategy<Value = Axial> {
+    (-6i32..6, -6i32..6).prop_map(|(q, r)| Axial::new(q, r))
+}
+
+#[test]
+fn open_ground_sees_everything_in_range() {
+    let seen = field_of_view(Axial::ZERO, 4, |_| false);
+    assert_eq!(seen.len(), Axial::ZERO.range(4).count());
+    assert!(field_of_view(Axial::ZERO, 0, |_| true).contains(&Axial::ZERO));
+}
+
+#[test]
+fn walls_are_seen_but_hide_what_is_behind() {
+    let wall = Axial::new(2, 0);
+    let seen = field_of_view(Axial::ZERO, 5, |cell| cell == wall);
+    assert!(seen.contains(&wall));
+    assert!(!seen.contains(&Axial::new(4, 0)));
+    assert!(seen.contains(&Axial::new(0, 4)));
+    // neighbors are always seen
+    let boxed = field_of_view(Axial::ZERO, 3, |cell| cell != Axial::ZERO);
+    assert_eq!(boxed.len(), 7);
+}
+
+proptest! {
+    #[test]
+    fn seen_cells_have_a_clear_line(
+        origin in axial(),
+        walls in prop::collection::hash_set(axial(), 0..30),
+        radius in 0u32..6,
+    ) {
+        let blocks = |cell: Axial| walls.contains(&cell);
+        let seen = field_of_view(origin, radius, blocks);
+        for cell in origin.range(radius) {
+            let path = line(origin, cell);
+            let clear = path.iter().skip(1).rev().skip(1).all(|&c| !blocks(c));
+            prop_assert_eq!(seen.contains(&cell), clear, "{:?}", cell);
+        }
+    }
+
+    #[test]
+    fn many_viewers_match_one_at_a_time(
+        viewers in prop::collection::vec((axial(), 0u32..5), 0..12),
+        walls in prop::collection::hash_set(axial(), 0..30),
+    ) {
+        let blocks = |cell: Axial| walls.contains(&cell);
+        let fields = fields_of_view(&viewers, blocks);
+        prop_assert_eq!(fields.len(), viewers.len());
+        for (seen, &(origin, radius)) in fields.iter().zip(&viewers) {
+            prop_assert_eq!(seen, &field_of_view(origin, radius, blocks));
+        }
+    }
+}
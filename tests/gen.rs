@@ -0,0 +1,95 @@
+//! map generation tests
+use hex_grid::{
+    gen::{asymmetric_cells, noise_terrain, scatter, symmetric_map, value_noise, GenRng, Symmetry},
+    Axial,
+};
+use proptest::prelude::*;
+
+fn symmetry() -> impl Strategy<Value = Symmetry> {
+    prop_oneof![
+        Just(Symmetry::Rotate2),
+        Just(Symmetry::Rotate3),
+        Just(Symmetry::Rotate6),
+        Just(Symmetry::Mirror),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn terrain_matches_weights(seed in any::<u64>(), a in 0.0f32..5.0, b in 0.0f32..5.0) {
+        prop_assume!(a + b > 0.1);
+        let cells: Vec<Axial> = Axial::ZERO.range(20).collect();
+        let map = noise_terrain(cells.iter().copied(), value_noise(seed, 4.0), &[(0, a), (1, b)]);
+        prop_assert_eq!(map.len(), cells.len());
+
+        let zeros = map.iter().filter(|(_, t)| **t == 0).count() as f32;
+        let expected = a / (a + b) * cells.len() as f32;
+        prop_assert!((zeros - expected).abs() <= 1.0);
+    }
+
+    #[test]
+    fn scatter_spacing(seed in any::<u64>(), min_distance in 1u32..5) {
+        let cells: Vec<Axial> = Axial::ZERO.range(15).collect();
+        let chosen = scatter(cells.iter().copied(), min_distance, &mut GenRng::new(seed));
+        for (i, a) in chosen.iter().enumerate() {
+            for b in &chosen[i + 1..] {
+                prop_assert!(a.distance(*b) >= min_distance);
+            }
+        }
+        // no gaps; every cell is near a chosen one
+        for cell in cells {
+            prop_assert!(chosen.iter().any(|c| c.distance(cell) < min_distance));
+        }
+        // deterministic
+        let again = scatter(Axial::ZERO.range(15), min_distance, &mut GenRng::new(seed));
+        prop_assert_eq!(chosen, again);
+    }
+
+    #[test]
+    fn symmetric(symmetry in symmetry(), q in -5i32..5, r in -5i32..5, seed in any::<u64>()) {
+        let center = Axial::new(q, r);
+        let mut rng = GenRng::new(seed);
+        let map = symmetric_map(center, 8, symmetry, |_| rng.below(1000));
+        prop_assert_eq!(map.len(), center.range(8).count());
+        for (cell, value) in map.iter() {
+            for other in symmetry.orbit(center, cell) {
+                prop_assert_eq!(map.get(other), Some(value));
+            }
+        }
+        prop_assert!(asymmetric_cells(&map, center, symmetry).is_empty());
+
+        // breaking one cell breaks every image of it
+        let cell = center + Axial::new(3, -1);
+        let mut broken = map.clone();
+        broken.insert(cell, 1000);
+        let mut bad = asymmetric_cells(&broken, center, symmetry);
+        bad.sort_by_key(|c| (c.r, c.q));
+        prop_assert_eq!(bad, symmetry.orbit(center, cell));
+    }
+
+    #[test]
+    fn images_in_player_order(symmetry in symmetry(), center in (-5i32..5, -5i32..5), cell in (-9i32..9, -9i32..9)) {
+        let center = Axial::new(center.0, center.1);
+        let cell = Axial::new(cell.0, cell.1);
+        let images = symmetry.images(center, cell);
+        prop_assert_eq!(images.len(), symmetry.order());
+        prop_assert_eq!(images[0], cell);
+        for (i, image) in images.iter().enumerate() {
+            prop_assert_eq!(image.distance(center), cell.distance(center));
+            // the images of any image are the same players, shifted
+            let again = symmetry.images(center, *image);
+            for (j, other) in again.iter().enumerate() {
+                prop_assert_eq!(*other, images[(i + j) % images.len()]);
+            }
+        }
+    }
+}
+
+#[test]
+fn value_noise_range() {
+    let noise = value_noise(3, 2.5);
+    for cell in Axial::ZERO.range(30) {
+        let v = noise(cell.to_world() * 0.37);
+        assert!((0.0..1.0).contains(&v), "{v}");
+    }
+}
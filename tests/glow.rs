@@ -0,0 +1,46 @@
+//! glow tests
+use bevy::prelude::*;
+use hex_grid::{Axial, HexGridGlow};
+use proptest::prelude::*;
+
+#[test]
+fn fill_sets_each_cell() {
+    let mut glow = HexGridGlow::new(16.0).with_intensity(3.0);
+    let red = Color::srgb(1.0, 0.0, 0.0);
+    let blue = Color::srgb(0.0, 0.0, 1.0);
+    glow.fill(Axial::ZERO.range(1), red);
+    glow.fill([Axial::ZERO], blue);
+
+    assert_eq!(glow.cells.len(), 7);
+    assert_eq!(glow.cells.get(Axial::ZERO), Some(&blue));
+    for cell in Axial::ZERO.ring(1) {
+        assert_eq!(glow.cells.get(cell), Some(&red), "cell {:?}", cell);
+    }
+    assert_eq!(glow.intensity, 3.0);
+}
+
+#[test]
+fn falloff_fades_over_width() {
+    let glow = HexGridGlow::new(10.0);
+    assert_eq!(glow.falloff(0.0), 1.0);
+    assert_eq!(glow.falloff(5.0), 0.25);
+    assert_eq!(glow.falloff(10.0), 0.0);
+    assert_eq!(glow.falloff(20.0), 0.0);
+
+    assert_eq!(HexGridGlow::new(0.0).falloff(0.0), 0.0);
+    assert_eq!(HexGridGlow::new(-4.0).falloff(0.0), 0.0);
+
+    // wider glows are cut off at the widest that can be drawn
+    let wide = HexGridGlow::new(HexGridGlow::MAX_WIDTH * 4.0);
+    assert_eq!(wide.falloff(HexGridGlow::MAX_WIDTH), 0.0);
+}
+
+proptest! {
+    #[test]
+    fn falloff_never_grows(width in 0.5f32..200.0, a in 0.0f32..200.0, b in 0.0f32..200.0) {
+        let glow = HexGridGlow::new(width);
+        let (near, far) = (a.min(b), a.max(b));
+        prop_assert!(glow.falloff(near) >= glow.falloff(far));
+        prop_assert!((0.0..=1.0).contains(&glow.falloff(near)));
+    }
+}
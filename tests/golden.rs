@@ -0,0 +1,257 @@
+//! golden image test for the grid shader
+//!
+//! Renders a single frame off-screen with a fixed camera and compares it
+//! against `tests/golden/grid.png`.  The grid math is all in the shader, so
+//! this is the only thing that catches regressions in it, for example after
+//! upgrading bevy.
+//!
+//! Needs a GPU adapter, so it's ignored by default; run it with
+//!     cargo test --test golden -- --ignored
+//!
+//! To update the golden image after an intentional change, run with
+//! `HEX_GRID_BLESS=1`, and check the new image by eye before committing it.
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use bevy::{
+    app::PluginsState,
+    asset::RenderAssetUsages,
+    camera::RenderTarget,
+    core_pipeline::tonemapping::Tonemapping,
+    prelude::*,
+    render::{
+        render_asset::RenderAssets,
+        render_resource::{
+            BufferDescriptor, BufferUsages, CommandEncoderDescriptor, Extent3d, MapMode, PollType,
+            TexelCopyBufferInfo, TexelCopyBufferLayout, TextureDimension, TextureFormat,
+            TextureUsages,
+        },
+        renderer::{render_system, RenderDevice, RenderQueue},
+        settings::{WgpuLimits, WgpuSettings},
+        texture::GpuImage,
+        Render, RenderApp, RenderPlugin, RenderSystems,
+    },
+    tasks::tick_global_task_pools_on_main_thread,
+    window::ExitCondition,
+    winit::WinitPlugin,
+};
+use hex_grid::{
+    Axial, HexGridConfig, HexGridCursor, HexGridHighlights, HexGridPlugin, HexGridStatus,
+};
+
+const WIDTH: u32 = 256;
+const HEIGHT: u32 = 256;
+const GOLDEN: &str = "tests/golden/grid.png";
+
+// per-channel difference allowed between drivers, and the fraction of
+// pixels allowed to exceed it (edges of antialiased lines)
+const CHANNEL_TOLERANCE: u8 = 8;
+const PIXEL_TOLERANCE: f64 = 0.001;
+
+/// render target to read back, in the render world
+#[derive(Resource)]
+struct Target(Handle<Image>);
+
+/// pixels of the most recent frame, shared with the render world
+#[derive(Resource, Clone, Default)]
+struct Pixels(Arc<Mutex<Vec<u8>>>);
+
+/// copy the render target into `Pixels` once the frame has been rendered
+fn read_back(
+    target: Res<Target>,
+    images: Res<RenderAssets<GpuImage>>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    pixels: Res<Pixels>,
+) {
+    let Some(image) = images.get(&target.0) else {
+        return;
+    };
+
+    // WIDTH * 4 is a multiple of 256, so rows need no padding
+    let buffer = render_device.create_buffer(&BufferDescriptor {
+        label: Some("golden_read_back"),
+        size: (WIDTH * HEIGHT * 4) as u64,
+        usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    let mut encoder = render_device.create_command_encoder(&CommandEncoderDescriptor::default());
+    encoder.copy_texture_to_buffer(
+        image.texture.as_image_copy(),
+        TexelCopyBufferInfo {
+            buffer: &buffer,
+            layout: TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(WIDTH * 4),
+                rows_per_image: None,
+            },
+        },
+        Extent3d {
+            width: WIDTH,
+            height: HEIGHT,
+            depth_or_array_layers: 1,
+        },
+    );
+    render_queue.submit([encoder.finish()]);
+
+    let slice = buffer.slice(..);
+    slice.map_async(MapMode::Read, |result| result.unwrap());
+    render_device.poll(PollType::wait_indefinitely()).unwrap();
+    *pixels.0.lock().unwrap() = slice.get_mapped_range().to_vec();
+}
+
+fn render_frame() -> Vec<u8> {
+    let mut app = App::new();
+    app.add_plugins((
+        DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: None,
+                exit_condition: ExitCondition::DontExit,
+                close_when_requested: false,
+                ..default()
+            })
+            .set(RenderPlugin {
+                // ssao needs 5 storage textures, & its shader fails to
+                // compile on gl; keep it off so llvmpipe can run this
+                render_creation: WgpuSettings {
+                    constrained_limits: Some(WgpuLimits {
+                        max_storage_textures_per_shader_stage: 4,
+                        ..default()
+                    }),
+                    ..default()
+                }
+                .into(),
+                ..default()
+            })
+            .disable::<WinitPlugin>(),
+        HexGridPlugin::default(),
+    ));
+
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: WIDTH,
+            height: HEIGHT,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.texture_descriptor.usage =
+        TextureUsages::COPY_SRC | TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING;
+    let target = app.world_mut().resource_mut::<Assets<Image>>().add(image);
+
+    let pixels = Pixels::default();
+    app.sub_app_mut(RenderApp)
+        .insert_resource(Target(target.clone()))
+        .insert_resource(pixels.clone())
+        .add_systems(
+            Render,
+            read_back.after(render_system).in_set(RenderSystems::Render),
+        );
+    // the render app is moved to its own thread on cleanup
+    while app.plugins_state() == PluginsState::Adding {
+        tick_global_task_pools_on_main_thread();
+    }
+    app.finish();
+    app.cleanup();
+
+    // parity tint & highlights cover more of the shader than bare lines
+    let highlights = Axial::new(1, -1)
+        .range(1)
+        .map(|cell| (cell, Color::srgba(1.0, 0.2, 0.2, 0.6)));
+    app.world_mut().spawn((
+        HexGridConfig {
+            parity_tint: Some([
+                Color::srgba(0.2, 0.3, 0.4, 0.3),
+                Color::srgba(0.3, 0.4, 0.2, 0.3),
+                Color::srgba(0.4, 0.2, 0.3, 0.3),
+            ]),
+            ..default()
+        },
+        HexGridHighlights(highlights.collect()),
+    ));
+    app.world_mut().spawn((
+        HexGridCursor::default(),
+        Camera3d::default(),
+        Camera {
+            clear_color: ClearColorConfig::Custom(Color::BLACK),
+            ..default()
+        },
+        RenderTarget::Image(target.into()),
+        Tonemapping::None,
+        Transform::from_xyz(2.0, 6.0, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+    ));
+
+    // the shader loads & compiles asynchronously; render a few frames once
+    // it's ready so the pipeline is in use
+    let mut ready_frames = 0;
+    for _ in 0..600 {
+        app.update();
+        match app.world().resource::<HexGridStatus>() {
+            HexGridStatus::Ready => ready_frames += 1,
+            HexGridStatus::Error(err) => panic!("shader failed to compile: {}", err),
+            HexGridStatus::Loading => (),
+        }
+        if ready_frames == 3 {
+            let pixels = pixels.0.lock().unwrap().clone();
+            assert_eq!(pixels.len(), (WIDTH * HEIGHT * 4) as usize);
+            return pixels;
+        }
+    }
+    panic!("grid shader never became ready");
+}
+
+#[test]
+#[ignore = "requires a GPU adapter"]
+fn grid_matches_golden_image() {
+    let pixels = render_frame();
+    let golden = Path::new(env!("CARGO_MANIFEST_DIR")).join(GOLDEN);
+
+    if std::env::var_os("HEX_GRID_BLESS").is_some() {
+        std::fs::create_dir_all(golden.parent().unwrap()).unwrap();
+        image::RgbaImage::from_raw(WIDTH, HEIGHT, pixels)
+            .unwrap()
+            .save(&golden)
+            .unwrap();
+        return;
+    }
+    assert!(
+        golden.exists(),
+        "{} is missing; run with HEX_GRID_BLESS=1 to write it",
+        GOLDEN
+    );
+
+    let expected = image::open(&golden).unwrap().into_rgba8();
+    assert_eq!(expected.dimensions(), (WIDTH, HEIGHT), "golden image size");
+
+    let differing = pixels
+        .chunks_exact(4)
+        .zip(expected.pixels())
+        .filter(|(actual, expected)| {
+            actual
+                .iter()
+                .zip(expected.0)
+                .any(|(a, e)| a.abs_diff(e) > CHANNEL_TOLERANCE)
+        })
+        .count();
+    let allowed = ((WIDTH * HEIGHT) as f64 * PIXEL_TOLERANCE) as usize;
+    if differing > allowed {
+        let actual = golden.with_file_name("grid.actual.png");
+        image::RgbaImage::from_raw(WIDTH, HEIGHT, pixels)
+            .unwrap()
+            .save(&actual)
+            .unwrap();
+        panic!(
+            "{} of {} pixels differ from {} (allowed {}); output written to {}",
+            differing,
+            WIDTH * HEIGHT,
+            GOLDEN,
+            allowed,
+            actual.display()
+        );
+    }
+}
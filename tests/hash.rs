@@ -0,0 +1,344 @@
+//! cell hash tests
+//!
+//! `gpu_matches_cpu` runs `hex_grid_hash.wgsl` in a compute shader and
+//! compares every result, including the values a `CellRng` draws, with the
+//! Rust side.  Needs a GPU adapter, so it's ignored by default; run it with
+//!     cargo test --test hash -- --ignored
+use bevy::{
+    app::PluginsState,
+    prelude::*,
+    render::{
+        render_resource::{
+            BindGroupDescriptor, BindGroupEntry, BufferDescriptor, BufferInitDescriptor,
+            BufferUsages, CommandEncoderDescriptor, ComputePassDescriptor, MapMode, PollType,
+            RawComputePipelineDescriptor, ShaderModuleDescriptor, ShaderSource,
+        },
+        renderer::{RenderDevice, RenderQueue},
+    },
+    tasks::tick_global_task_pools_on_main_thread,
+    window::ExitCondition,
+    winit::WinitPlugin,
+};
+use hex_grid::{
+    hash::{hash_cell, hash_cell_f32, CellRng},
+    Axial,
+};
+use proptest::prelude::*;
+
+fn axial() -> impl Strategy<Value = Axial> {
+    (-10_000i32..10_000, -10_000i32..10_000).prop_map(|(q, r)| Axial::new(q, r))
+}
+
+#[test]
+fn pinned_values() {
+    // the shader has to produce these too; changing them changes every
+    // game's per-cell variation
+    assert_eq!(hash_cell(Axial::new(0, 0), 0), 0x944f_b554);
+    assert_eq!(hash_cell(Axial::new(1, 0), 0), 0x1ece_84a6);
+    assert_eq!(hash_cell(Axial::new(0, 1), 0), 0xc67c_684d);
+    assert_eq!(hash_cell(Axial::new(-3, 7), 0), 0xa7c4_1d1f);
+    assert_eq!(hash_cell(Axial::new(0, 0), 1), 0xd661_ff16);
+    assert_eq!(hash_cell(Axial::new(-3, 7), 42), 0x4c2f_a48c);
+
+    let mut rng = CellRng::new(42, Axial::new(-3, 7));
+    let values: Vec<u32> = (0..4).map(|_| rng.next_u32()).collect();
+    assert_eq!(values, [0x4c2f_a48c, 0xa20f_c72b, 0x1a39_8aca, 0x803e_1f58]);
+}
+
+#[test]
+fn evenly_distributed() {
+    let mut buckets = [0u32; 16];
+    for cell in Axial::ZERO.range(60) {
+        buckets[(hash_cell_f32(cell, 7) * 16.0) as usize] += 1;
+    }
+    let expected = Axial::ZERO.range(60).count() as f32 / 16.0;
+    for count in buckets {
+        assert!(
+            (count as f32 - expected).abs() < expected * 0.1,
+            "{:?}",
+            buckets
+        );
+    }
+}
+
+#[test]
+fn cells_differ() {
+    let mut seen = std::collections::HashSet::new();
+    for cell in Axial::ZERO.range(100) {
+        for seed in 0..4 {
+            assert!(
+                seen.insert(hash_cell(cell, seed)),
+                "{:?} seed {} repeats a hash",
+                cell,
+                seed
+            );
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn f32_in_unit_range(cell in axial(), seed in any::<u32>()) {
+        let v = hash_cell_f32(cell, seed);
+        prop_assert!((0.0..1.0).contains(&v), "{}", v);
+    }
+
+    #[test]
+    fn f32_follows_u32(cell in axial(), seed in any::<u32>()) {
+        let (h, v) = (hash_cell(cell, seed), hash_cell_f32(cell, seed));
+        prop_assert_eq!((v * 16_777_216.0) as u32, h >> 8);
+    }
+
+    #[test]
+    fn neighbors_and_seeds_differ(cell in axial(), seed in any::<u32>()) {
+        let h = hash_cell(cell, seed);
+        for neighbor in cell.neighbors() {
+            prop_assert_ne!(hash_cell(neighbor, seed), h);
+        }
+        prop_assert_ne!(hash_cell(cell, seed.wrapping_add(1)), h);
+    }
+
+    #[test]
+    fn rng_starts_with_hash(cell in axial(), seed in any::<u32>()) {
+        let mut rng = CellRng::new(seed, cell);
+        prop_assert_eq!(rng.clone().next_u32(), hash_cell(cell, seed));
+        prop_assert_eq!(rng.next_f32(), hash_cell_f32(cell, seed));
+    }
+
+    #[test]
+    fn rng_is_deterministic(cell in axial(), seed in any::<u32>()) {
+        let draw = |mut rng: CellRng| {
+            let mut items: Vec<u32> = (0..20).collect();
+            rng.shuffle(&mut items);
+            (items, rng.below(7), rng.chance(0.5), rng.choose(&[1, 2, 3]).copied())
+        };
+        prop_assert_eq!(draw(CellRng::new(seed, cell)), draw(CellRng::new(seed, cell)));
+    }
+
+    #[test]
+    fn rng_ranges(cell in axial(), seed in any::<u32>(), n in 1u32..1000) {
+        let mut rng = CellRng::new(seed, cell);
+        for _ in 0..20 {
+            prop_assert!(rng.below(n) < n);
+            prop_assert!((0.0..1.0).contains(&rng.next_f32()));
+        }
+        prop_assert!(!rng.chance(0.0));
+        prop_assert!(rng.chance(1.0));
+        prop_assert_eq!(rng.choose::<u8>(&[]), None);
+
+        let mut items: Vec<u32> = (0..n).collect();
+        rng.shuffle(&mut items);
+        items.sort();
+        prop_assert_eq!(items, (0..n).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn rng_cells_differ() {
+    // nearby cells & seeds must not give shifted copies of one sequence
+    let sequence = |seed, cell| {
+        let mut rng = CellRng::new(seed, cell);
+        (0..8).map(|_| rng.next_u32()).collect::<Vec<_>>()
+    };
+    let mut seen = std::collections::HashSet::new();
+    for cell in Axial::ZERO.range(10) {
+        for seed in 0..8 {
+            for value in sequence(seed, cell) {
+                assert!(
+                    seen.insert(value),
+                    "{:?} seed {} repeats {}",
+                    cell,
+                    seed,
+                    value
+                );
+            }
+        }
+    }
+}
+
+// entry point appended to hex_grid_hash.wgsl for the comparison
+const COMPUTE: &str = r"
+@group(0) @binding(0) var<storage, read> inputs: array<vec4<u32>>;
+@group(0) @binding(1) var<storage, read_write> outputs: array<vec4<u32>>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= arrayLength(&inputs) {
+        return;
+    }
+    let input = inputs[id.x];
+    let cell = bitcast<vec2<i32>>(input.xy);
+    outputs[id.x] = vec4(
+        hash_cell(cell, input.z),
+        bitcast<u32>(hash_cell_f32(cell, input.z)),
+        cell_rng(cell, input.z, input.w),
+        bitcast<u32>(cell_rng_f32(cell, input.z, input.w)),
+    );
+}
+";
+
+fn render_device() -> (RenderDevice, RenderQueue) {
+    let mut app = App::new();
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: None,
+                exit_condition: ExitCondition::DontExit,
+                close_when_requested: false,
+                ..default()
+            })
+            .disable::<WinitPlugin>(),
+    );
+    while app.plugins_state() == PluginsState::Adding {
+        tick_global_task_pools_on_main_thread();
+    }
+    app.finish();
+    app.cleanup();
+    let world = app.world();
+    (
+        world.resource::<RenderDevice>().clone(),
+        world.resource::<RenderQueue>().clone(),
+    )
+}
+
+// [hash_cell, hash_cell_f32 bits, cell_rng, cell_rng_f32 bits] from the
+// shader for each (cell, seed, n)
+fn gpu_hashes(inputs: &[(Axial, u32, u32)]) -> Vec<[u32; 4]> {
+    let (device, queue) = render_device();
+
+    let library = std::fs::read_to_string(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/assets/hex_grid_hash.wgsl"
+    ))
+    .unwrap();
+    // the library compiles as-is once it's no longer an import
+    let source: String = library
+        .lines()
+        .filter(|line| !line.starts_with("#define_import_path"))
+        .map(|line| format!("{}\n", line))
+        .collect::<String>()
+        + COMPUTE;
+    let module = device.create_and_validate_shader_module(ShaderModuleDescriptor {
+        label: Some("hash_test"),
+        source: ShaderSource::Wgsl(source.into()),
+    });
+    let pipeline = device.create_compute_pipeline(&RawComputePipelineDescriptor {
+        label: Some("hash_test"),
+        layout: None,
+        module: &module,
+        entry_point: Some("main"),
+        compilation_options: default(),
+        cache: None,
+    });
+
+    let words: Vec<u8> = inputs
+        .iter()
+        .flat_map(|&(cell, seed, n)| [cell.q as u32, cell.r as u32, seed, n])
+        .flat_map(u32::to_le_bytes)
+        .collect();
+    let input = device.create_buffer_with_data(&BufferInitDescriptor {
+        label: Some("hash_test_inputs"),
+        contents: &words,
+        usage: BufferUsages::STORAGE,
+    });
+    let size = (inputs.len() * 16) as u64;
+    let output = device.create_buffer(&BufferDescriptor {
+        label: Some("hash_test_outputs"),
+        size,
+        usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    });
+    let read_back = device.create_buffer(&BufferDescriptor {
+        label: Some("hash_test_read_back"),
+        size,
+        usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    let bind_group = device
+        .wgpu_device()
+        .create_bind_group(&BindGroupDescriptor {
+            label: Some("hash_test"),
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: input.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: output.as_entire_binding(),
+                },
+            ],
+        });
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor::default());
+    {
+        let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor::default());
+        pass.set_pipeline(&pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.dispatch_workgroups((inputs.len() as u32).div_ceil(64), 1, 1);
+    }
+    encoder.copy_buffer_to_buffer(&output, 0, &read_back, 0, size);
+    queue.submit([encoder.finish()]);
+
+    let slice = read_back.slice(..);
+    slice.map_async(MapMode::Read, |result| result.unwrap());
+    device.poll(PollType::wait_indefinitely()).unwrap();
+    let bytes = slice.get_mapped_range();
+    bytes
+        .chunks_exact(16)
+        .map(|c| {
+            std::array::from_fn(|i| u32::from_le_bytes(c[i * 4..i * 4 + 4].try_into().unwrap()))
+        })
+        .collect()
+}
+
+#[test]
+#[ignore = "requires a GPU adapter"]
+fn gpu_matches_cpu() {
+    let far = [
+        Axial::new(i32::MAX, i32::MIN),
+        Axial::new(-123_456, 654_321),
+    ];
+    // each cell & seed with a few draws of a CellRng
+    let inputs: Vec<(Axial, u32, u32)> = Axial::ZERO
+        .range(20)
+        .chain(far)
+        .flat_map(|cell| [0, 1, 42, u32::MAX].map(|seed| (cell, seed)))
+        .flat_map(|(cell, seed)| [1, 5].map(|n| (cell, seed, n)))
+        .collect();
+
+    let gpu = gpu_hashes(&inputs);
+    assert_eq!(gpu.len(), inputs.len());
+    for ((cell, seed, n), [hash, bits, draw, draw_bits]) in inputs.into_iter().zip(gpu) {
+        assert_eq!(
+            hash,
+            hash_cell(cell, seed),
+            "hash_cell({:?}, {})",
+            cell,
+            seed
+        );
+        assert_eq!(
+            f32::from_bits(bits),
+            hash_cell_f32(cell, seed),
+            "hash_cell_f32({:?}, {})",
+            cell,
+            seed
+        );
+
+        let mut rng = CellRng::new(seed, cell);
+        for _ in 0..n {
+            rng.next_u32();
+        }
+        let expected = rng.clone().next_u32();
+        assert_eq!(draw, expected, "cell_rng({:?}, {}, {})", cell, seed, n);
+        assert_eq!(
+            f32::from_bits(draw_bits),
+            rng.next_f32(),
+            "cell_rng_f32({:?}, {}, {})",
+            cell,
+            seed,
+            n
+        );
+    }
+}
@@ -0,0 +1,78 @@
+//! heatmap tests
+use bevy::color::{color_difference::EuclideanDistance, Color, Mix};
+use hex_grid::{Axial, HeatNormalization, HexGridHeatmap, HexMap};
+use proptest::prelude::*;
+
+fn close(a: Color, b: Color) -> bool {
+    a.to_linear().distance(&b.to_linear()) < 1e-4
+}
+
+#[test]
+fn values_map_onto_the_gradient() {
+    let (cold, hot) = (Color::srgb(0.0, 0.0, 1.0), Color::srgb(1.0, 0.0, 0.0));
+    let values: HexMap<f32> = [
+        (Axial::new(0, 0), -2.0),
+        (Axial::new(1, 0), 3.0),
+        (Axial::new(2, 0), 8.0),
+    ]
+    .into_iter()
+    .collect();
+    let heatmap = HexGridHeatmap::new(values).with_gradient([cold, hot]);
+
+    assert_eq!(heatmap.range(), (-2.0, 8.0));
+    assert!(close(heatmap.color(Axial::new(0, 0)).unwrap(), cold));
+    assert!(close(heatmap.color(Axial::new(2, 0)).unwrap(), hot));
+    let mid = Color::from(cold.to_linear().mix(&hot.to_linear(), 0.5));
+    assert!(close(heatmap.color(Axial::new(1, 0)).unwrap(), mid));
+    assert_eq!(heatmap.color(Axial::new(3, 0)), None);
+
+    // a fixed range clamps values outside it
+    let heatmap = heatmap.with_normalization(HeatNormalization::Range { min: 0.0, max: 3.0 });
+    assert!(close(heatmap.color(Axial::new(0, 0)).unwrap(), cold));
+    assert!(close(heatmap.color(Axial::new(1, 0)).unwrap(), hot));
+    assert!(close(heatmap.color(Axial::new(2, 0)).unwrap(), hot));
+}
+
+#[test]
+fn degenerate_ranges_and_gradients() {
+    let values: HexMap<f32> = [(Axial::ZERO, 4.0)].into_iter().collect();
+    let (cold, hot) = (Color::srgb(0.0, 0.0, 1.0), Color::srgb(1.0, 0.0, 0.0));
+    let heatmap = HexGridHeatmap::new(values).with_gradient([cold, hot]);
+    // with a single value, the range is empty & the value is at its end
+    assert_eq!(heatmap.range(), (4.0, 4.0));
+    assert!(close(heatmap.color(Axial::ZERO).unwrap(), hot));
+
+    let heatmap = heatmap.with_gradient([cold]);
+    assert!(close(heatmap.gradient_color(0.7), cold));
+    let heatmap = heatmap.with_gradient([]);
+    assert_eq!(heatmap.gradient_color(0.7), Color::NONE);
+    assert_eq!(HexGridHeatmap::default().value_range(), None);
+}
+
+proptest! {
+    #[test]
+    fn gradient_passes_through_each_color(
+        colors in prop::collection::vec((0.0f32..1.0, 0.0f32..1.0, 0.0f32..1.0), 2..=8),
+        i in 0usize..8,
+    ) {
+        let colors: Vec<Color> = colors.into_iter().map(|(r, g, b)| Color::srgb(r, g, b)).collect();
+        let i = i % colors.len();
+        let heatmap = HexGridHeatmap::default().with_gradient(colors.clone());
+        let t = i as f32 / (colors.len() - 1) as f32;
+        prop_assert!(close(heatmap.gradient_color(t), colors[i]));
+    }
+
+    #[test]
+    fn auto_range_covers_every_value(
+        values in prop::collection::vec((-30i32..30, -30i32..30, -100.0f32..100.0), 1..50),
+    ) {
+        let map: HexMap<f32> = values.iter().map(|&(q, r, v)| (Axial::new(q, r), v)).collect();
+        let heatmap = HexGridHeatmap::new(map);
+        let (min, max) = heatmap.range();
+        for (_, &v) in heatmap.values.iter() {
+            prop_assert!(min <= v && v <= max);
+        }
+        prop_assert!(heatmap.values.iter().any(|(_, &v)| v == min));
+        prop_assert!(heatmap.values.iter().any(|(_, &v)| v == max));
+    }
+}
@@ -0,0 +1,76 @@
+//! edge stroke tests
+use bevy::prelude::*;
+use hex_grid::{Axial, EdgeStroke, HexDirection, HexEdge, HexGridPartHighlights, StrokePattern};
+use proptest::prelude::*;
+
+#[test]
+fn edges_take_their_group_stroke() {
+    let mut parts = HexGridPartHighlights::default();
+    let dashes = EdgeStroke::new(StrokePattern::Dash, 0.05).with_speed(1.0);
+    let group = parts.add_stroke(dashes);
+    let outline = HexDirection::ALL.map(|dir| HexEdge::new(Axial::ZERO, dir));
+    parts.stroke_edges(outline.iter().copied(), Color::WHITE, group);
+    let plain = HexEdge::new(Axial::new(3, 0), HexDirection::East);
+    parts.edges.insert(plain, Color::BLACK);
+
+    for &edge in &outline {
+        assert_eq!(parts.edges.get(&edge), Some(&Color::WHITE));
+        assert_eq!(parts.stroke(edge), dashes);
+    }
+    let solid = parts.stroke(plain);
+    assert_eq!(solid.pattern, StrokePattern::Solid);
+    assert_eq!(solid.width, parts.edge_width);
+
+    // groups past the last drawn stroke are drawn solid
+    for _ in 0..HexGridPartHighlights::MAX_STROKES {
+        parts.add_stroke(dashes);
+    }
+    let late = HexEdge::new(Axial::new(-3, 0), HexDirection::East);
+    parts.stroke_edges([late], Color::WHITE, HexGridPartHighlights::MAX_STROKES);
+    assert_eq!(parts.stroke(late).pattern, StrokePattern::Solid);
+}
+
+#[test]
+fn patterns() {
+    let solid = EdgeStroke::new(StrokePattern::Solid, 0.1);
+    assert!(solid.covers(0.04, 0.3, 0.0));
+    assert!(solid.covers(-0.04, 0.3, 0.0));
+    assert!(!solid.covers(0.06, 0.0, 0.0));
+
+    let dash = EdgeStroke::new(StrokePattern::Dash, 0.1).with_period(0.2);
+    assert!(dash.covers(0.0, 0.05, 0.0));
+    assert!(!dash.covers(0.0, 0.15, 0.0));
+    assert!(dash.covers(0.0, -0.15, 0.0));
+
+    let dot = EdgeStroke::new(StrokePattern::Dot, 0.1).with_period(0.2);
+    assert!(dot.covers(0.0, 0.2, 0.0));
+    assert!(dot.covers(0.02, 0.02, 0.0));
+    assert!(!dot.covers(0.0, 0.1, 0.0));
+    assert!(!dot.covers(0.04, 0.04, 0.0));
+
+    let double = EdgeStroke::new(StrokePattern::Double, 0.3);
+    assert!(!double.covers(0.0, 0.0, 0.0));
+    assert!(double.covers(0.1, 0.0, 0.0));
+    assert!(!double.covers(0.2, 0.0, 0.0));
+}
+
+proptest! {
+    // marching dashes move one period along the edge each 1 / speed seconds
+    #[test]
+    fn dashes_march(
+        along in -1.0f32..1.0,
+        time in 0.0f32..10.0,
+        speed in 0.5f32..4.0,
+    ) {
+        let dash = EdgeStroke::new(StrokePattern::Dash, 0.1)
+            .with_period(0.25)
+            .with_speed(speed);
+        let phase = (along / dash.period - time * speed).rem_euclid(1.0);
+        // away from the ends of a dash, where rounding decides
+        prop_assume!((phase - 0.5).abs() > 0.01 && phase > 0.01 && phase < 0.99);
+        let shifted = along + dash.period * 0.5;
+        prop_assert_ne!(dash.covers(0.0, along, time), dash.covers(0.0, shifted, time));
+        let later = time + 0.5 / speed;
+        prop_assert_eq!(dash.covers(0.0, along, time), dash.covers(0.0, shifted, later));
+    }
+}
@@ -0,0 +1,130 @@
+//! map undo & redo tests
+use hex_grid::{Axial, HexMap, HexMapHistory};
+use proptest::prelude::*;
+
+#[derive(Debug, Clone)]
+enum Edit {
+    Insert(Axial, u8),
+    Remove(Axial),
+    Modify(Axial),
+}
+
+fn edit() -> impl Strategy<Value = Edit> {
+    let cell = (-4i32..4, -4i32..4).prop_map(|(q, r)| Axial::new(q, r));
+    prop_oneof![
+        (cell.clone(), any::<u8>()).prop_map(|(cell, v)| Edit::Insert(cell, v)),
+        cell.clone().prop_map(Edit::Remove),
+        cell.prop_map(Edit::Modify),
+    ]
+}
+
+fn apply(history: &mut HexMapHistory<u8>, edit: &Edit) {
+    match *edit {
+        Edit::Insert(cell, v) => {
+            history.insert(cell, v);
+        }
+        Edit::Remove(cell) => {
+            history.remove(cell);
+        }
+        Edit::Modify(cell) => {
+            history.modify(cell, |v| *v = v.wrapping_add(1));
+        }
+    }
+}
+
+fn start() -> HexMap<u8> {
+    Axial::ZERO.range(2).map(|cell| (cell, 1)).collect()
+}
+
+#[test]
+fn transactions() {
+    let mut history = HexMapHistory::new(start());
+    let cells: Vec<Axial> = Axial::new(1, 1).range(1).collect();
+
+    history.begin_txn();
+    for &cell in &cells {
+        history.insert(cell, 5);
+    }
+    history.remove(Axial::ZERO);
+    assert!(history.commit());
+    // a transaction that changes nothing isn't kept
+    history.begin_txn();
+    history.insert(cells[0], 6);
+    history.insert(cells[0], 5);
+    assert!(!history.commit());
+
+    assert!(history.undo());
+    assert_eq!(history.as_map(), &start());
+    assert!(!history.can_undo());
+    assert!(history.redo());
+    assert!(cells.iter().all(|&cell| history.get(cell) == Some(&5)));
+    assert_eq!(history.get(Axial::ZERO), None);
+    assert!(!history.can_redo());
+}
+
+#[test]
+fn rollback() {
+    let mut history = HexMapHistory::new(start());
+    history.begin_txn();
+    history.fill(Axial::ZERO.range(3), 9);
+    history.remove(Axial::new(1, 0));
+    history.rollback();
+    assert!(!history.in_txn());
+    assert_eq!(history.as_map(), &start());
+    assert!(!history.can_undo());
+}
+
+#[test]
+fn new_edits_clear_redo() {
+    let mut history = HexMapHistory::<u8>::default();
+    history.insert(Axial::ZERO, 1);
+    history.insert(Axial::ZERO, 2);
+    assert!(history.undo());
+    assert!(history.can_redo());
+    history.insert(Axial::new(0, 1), 3);
+    assert!(!history.can_redo());
+    assert!(!history.redo());
+}
+
+#[test]
+fn limit() {
+    let mut history = HexMapHistory::<u8>::default().with_limit(3);
+    for v in 0..10 {
+        history.insert(Axial::ZERO, v);
+    }
+    let mut undone = 0;
+    while history.undo() {
+        undone += 1;
+    }
+    assert_eq!(undone, 3);
+    assert_eq!(history.get(Axial::ZERO), Some(&6));
+}
+
+proptest! {
+    #[test]
+    fn undo_redo_everything(txns in prop::collection::vec(prop::collection::vec(edit(), 0..8), 0..12)) {
+        let mut history = HexMapHistory::new(start());
+        // map after each committed transaction
+        let mut states = vec![start()];
+        for txn in &txns {
+            history.begin_txn();
+            txn.iter().for_each(|edit| apply(&mut history, edit));
+            if history.commit() {
+                states.push(history.as_map().clone());
+            }
+        }
+        let end = history.as_map().clone();
+
+        for state in states.iter().rev().skip(1) {
+            prop_assert!(history.undo());
+            prop_assert_eq!(history.as_map(), state);
+        }
+        prop_assert!(!history.undo());
+        for state in states.iter().skip(1) {
+            prop_assert!(history.redo());
+            prop_assert_eq!(history.as_map(), state);
+        }
+        prop_assert!(!history.redo());
+        prop_assert_eq!(history.as_map(), &end);
+    }
+}
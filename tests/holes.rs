@@ -0,0 +1,44 @@
+//! HexGridHoles bitset tests
+use bevy::platform::collections::HashSet;
+use hex_grid::{Axial, HexGridHoles};
+use proptest::prelude::*;
+
+fn cell() -> impl Strategy<Value = Axial> {
+    (-40i32..40, -40i32..40).prop_map(|(q, r)| Axial::new(q, r))
+}
+
+proptest! {
+    #[test]
+    fn matches_a_set(ops in prop::collection::vec((any::<bool>(), cell()), 0..200)) {
+        let mut holes = HexGridHoles::new();
+        let mut set = HashSet::new();
+        for (insert, cell) in ops {
+            if insert {
+                prop_assert_eq!(holes.insert(cell), set.insert(cell));
+            } else {
+                prop_assert_eq!(holes.remove(cell), set.remove(&cell));
+            }
+            prop_assert_eq!(holes.len(), set.len());
+        }
+        for cell in &set {
+            prop_assert!(holes.contains(*cell));
+            prop_assert!(holes.bounds().contains(*cell));
+        }
+        let mut cells: Vec<_> = holes.iter().collect();
+        let mut expected: Vec<_> = set.into_iter().collect();
+        cells.sort_by_key(|c| (c.r, c.q));
+        expected.sort_by_key(|c| (c.r, c.q));
+        prop_assert_eq!(cells, expected);
+    }
+}
+
+#[test]
+fn empty() {
+    let mut holes: HexGridHoles = [Axial::new(3, -2), Axial::new(-7, 5)].into_iter().collect();
+    assert_eq!(holes.len(), 2);
+    assert!(!holes.contains(Axial::ZERO));
+    holes.clear();
+    assert!(holes.is_empty());
+    assert!(!holes.contains(Axial::new(3, -2)));
+    assert!(!holes.remove(Axial::new(100, 100)));
+}
@@ -0,0 +1,108 @@
+//! influence map tests
+use hex_grid::{
+    influence::{propagate, propagate_blended, propagate_with_cost, Blend, Decay},
+    Axial, InfluenceMap, InfluenceSource,
+};
+use proptest::prelude::*;
+
+#[test]
+fn decay_falls_off_to_the_max_range() {
+    assert_eq!(Decay::None.falloff(3, 3), 1.0);
+    assert_eq!(Decay::None.falloff(4, 3), 0.0);
+    assert_eq!(Decay::Linear.falloff(0, 3), 1.0);
+    assert_eq!(Decay::Linear.falloff(2, 3), 0.5);
+    assert_eq!(Decay::Exponential(0.5).falloff(2, 3), 0.25);
+
+    let field = propagate([(Axial::ZERO, 8.0)], Decay::Linear, 3);
+    assert_eq!(field.len(), Axial::ZERO.range(3).count());
+    assert_eq!(field.get(Axial::ZERO), Some(&8.0));
+    assert_eq!(field.get(Axial::new(2, 0)), Some(&4.0));
+    assert_eq!(field.get(Axial::new(4, 0)), None);
+}
+
+#[test]
+fn sources_add_or_take_the_strongest() {
+    let sources = [(Axial::new(-1, 0), 2.0), (Axial::new(1, 0), 3.0)];
+    let added = propagate(sources, Decay::None, 2);
+    assert_eq!(added.get(Axial::ZERO), Some(&5.0));
+    assert_eq!(added.get(Axial::new(-3, 0)), Some(&2.0));
+
+    let max = propagate_blended(sources, Decay::None, 2, Blend::Max);
+    assert_eq!(max.get(Axial::ZERO), Some(&3.0));
+    assert_eq!(max.get(Axial::new(-3, 0)), Some(&2.0));
+}
+
+#[test]
+fn walls_block_influence() {
+    // a wall along q = 1, except for a gap far to the south
+    let wall = |cell: Axial| cell.q == 1 && cell.r < 3;
+    let cost = |cell: Axial| (!wall(cell) && cell.distance(Axial::ZERO) <= 8).then_some(1);
+    let field = propagate_with_cost([(Axial::ZERO, 1.0)], Decay::None, 3, Blend::Add, cost);
+
+    assert_eq!(field.get(Axial::new(1, 0)), None);
+    // two steps in a straight line, but blocked by the wall
+    assert_eq!(field.get(Axial::new(2, -1)), None);
+    assert_eq!(field.get(Axial::new(-3, 0)), Some(&1.0));
+}
+
+#[test]
+fn channels_are_kept_apart() {
+    let mut map = InfluenceMap::new(2, Decay::Linear, 2);
+    map.extend([
+        InfluenceSource::new(Axial::new(-2, 0), 3.0),
+        InfluenceSource::new(Axial::new(2, 0), 3.0).with_channel(1),
+    ]);
+    assert_eq!(map.get(0, Axial::new(-2, 0)), 3.0);
+    assert_eq!(map.get(1, Axial::new(-2, 0)), 0.0);
+
+    let front = map.difference(0, 1);
+    assert_eq!(front.get(Axial::ZERO), Some(&0.0));
+    assert!(front.get(Axial::new(-1, 0)).unwrap() > &0.0);
+    assert!(front.get(Axial::new(1, 0)).unwrap() < &0.0);
+
+    map.clear();
+    assert!(map.channel(0).is_empty() && map.channel(1).is_empty());
+}
+
+proptest! {
+    #[test]
+    fn channel_matches_propagate(
+        sources in prop::collection::vec((-10i32..10, -10i32..10, -5.0f32..5.0), 0..6),
+        max_range in 0u32..4,
+        max in any::<bool>(),
+    ) {
+        let blend = if max { Blend::Max } else { Blend::Add };
+        let sources: Vec<_> = sources
+            .into_iter()
+            .map(|(q, r, strength)| (Axial::new(q, r), strength))
+            .collect();
+        let field = propagate_blended(sources.iter().copied(), Decay::Linear, max_range, blend);
+
+        let mut map = InfluenceMap::new(1, Decay::Linear, max_range).with_blend(blend);
+        map.extend(sources.iter().map(|&(cell, s)| InfluenceSource::new(cell, s)));
+        prop_assert_eq!(map.channel(0), &field);
+
+        // every cell within range of a source has a value, and no others
+        for (cell, _) in field.iter() {
+            prop_assert!(sources.iter().any(|(s, _)| s.distance(cell) <= max_range));
+        }
+    }
+
+    #[test]
+    fn sources_across_chunks(
+        sources in prop::collection::vec((-60i32..60, -60i32..60, -5.0f32..5.0), 0..40),
+        max_range in 0u32..12,
+    ) {
+        // filled a chunk at a time with the `parallel` feature; each cell
+        // must still add its sources in the same order
+        let sources: Vec<_> = sources
+            .into_iter()
+            .map(|(q, r, strength)| (Axial::new(q, r), strength))
+            .collect();
+        let field = propagate(sources.iter().copied(), Decay::Exponential(0.7), max_range);
+
+        let mut map = InfluenceMap::new(1, Decay::Exponential(0.7), max_range);
+        map.extend(sources.iter().map(|&(cell, s)| InfluenceSource::new(cell, s)));
+        prop_assert_eq!(map.channel(0), &field);
+    }
+}
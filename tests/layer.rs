@@ -0,0 +1,96 @@
+//! stacked layer tests
+use hex_grid::{
+    layer::{find_layered_path, LayerLinks},
+    path::find_path,
+    Axial, HexGridLayers, LayerCell,
+};
+use proptest::prelude::*;
+
+fn open(cell: LayerCell) -> Option<u32> {
+    (cell.cell.length() <= 8 && (0..3).contains(&cell.layer)).then_some(1)
+}
+
+fn layer_cell() -> impl Strategy<Value = LayerCell> {
+    (-5i32..5, -5i32..5, 0i32..3)
+        .prop_map(|(q, r, layer)| LayerCell::new(Axial::new(q, r), layer))
+        .prop_filter("open", |&cell| open(cell).is_some())
+}
+
+#[test]
+fn layer_heights_and_visibility() {
+    let mut layers = HexGridLayers::new(2.5).with_height(3, 20.0);
+    assert_eq!(layers.height(0), 0.0);
+    assert_eq!(layers.height(2), 5.0);
+    assert_eq!(layers.height(-1), -2.5);
+    assert_eq!(layers.height(3), 20.0);
+
+    assert!(layers.is_visible(1));
+    layers.set_visible(1, false);
+    assert!(!layers.is_visible(1) && layers.is_visible(0));
+    layers.set_visible(1, true);
+    assert!(layers.is_visible(1));
+}
+
+#[test]
+fn stairs_join_floors() {
+    let bottom = LayerCell::new(Axial::new(-4, 0), 0);
+    let top = LayerCell::new(Axial::new(-4, 0), 1);
+    let stair = (
+        LayerCell::new(Axial::new(4, 0), 0),
+        LayerCell::new(Axial::new(5, 0), 1),
+    );
+
+    // without links, layers are apart
+    assert_eq!(
+        find_layered_path(bottom, top, &LayerLinks::new(), |_, to| open(to)),
+        None
+    );
+
+    let mut links = LayerLinks::new();
+    links.link(stair.0, stair.1);
+    let (path, cost) = find_layered_path(bottom, top, &links, |_, to| open(to)).unwrap();
+    assert_eq!(cost, 8 + 1 + 9);
+    assert!(path.windows(2).any(|step| step == [stair.0, stair.1]));
+    assert_eq!(links.linked(stair.1).collect::<Vec<_>>(), vec![stair.0]);
+
+    links.unlink(stair.1, stair.0);
+    assert_eq!(links.linked(stair.0).count(), 0);
+    assert_eq!(
+        find_layered_path(bottom, top, &links, |_, to| open(to)),
+        None
+    );
+}
+
+#[test]
+fn flying_goes_straight_up() {
+    let links = LayerLinks::new().with_vertical(true);
+    let start = LayerCell::new(Axial::ZERO, 0);
+    let linked: Vec<LayerCell> = links.linked(start).collect();
+    assert_eq!(linked, vec![start.above(), start.below()]);
+
+    let (path, cost) =
+        find_layered_path(start, start.above().above(), &links, |_, to| open(to)).unwrap();
+    assert_eq!(cost, 2);
+    assert_eq!(path, vec![start, start.above(), start.above().above()]);
+}
+
+proptest! {
+    #[test]
+    fn one_layer_matches_flat_paths(a in layer_cell(), b in layer_cell()) {
+        let b = LayerCell::new(b.cell, a.layer);
+        let layered = find_layered_path(a, b, &LayerLinks::new(), |_, to| open(to));
+        let flat = find_path(a.cell, b.cell, |_, to| open(LayerCell::new(to, a.layer)));
+        prop_assert_eq!(layered.map(|(_, c)| c), flat.map(|(_, c)| c));
+    }
+
+    #[test]
+    fn flying_paths_are_as_short_as_the_distance(a in layer_cell(), b in layer_cell()) {
+        let links = LayerLinks::new().with_vertical(true);
+        let (path, cost) = find_layered_path(a, b, &links, |_, to| open(to)).unwrap();
+        prop_assert!(cost >= a.distance(b));
+        prop_assert_eq!(cost, a.cell.distance(b.cell) + a.layer.abs_diff(b.layer));
+        for step in path.windows(2) {
+            prop_assert!(links.neighbors(step[0]).any(|n| n == step[1]));
+        }
+    }
+}
@@ -0,0 +1,202 @@
+//! HexMap change tracking & encoding tests
+use bevy::platform::collections::HashSet;
+use hex_grid::{Axial, HexBounds, HexMap};
+use proptest::prelude::*;
+
+fn changed(map: &HexMap<u32>, since: hex_grid::HexMapVersion) -> Vec<HexBounds> {
+    map.changed_chunks(since).unwrap().collect()
+}
+
+#[test]
+fn changes_tracked_by_chunk() {
+    let mut map = HexMap::new();
+    let start = map.version();
+    map.insert(Axial::new(3, 4), 1);
+    let chunks = changed(&map, start);
+    assert_eq!(chunks.len(), 1);
+    assert!(chunks[0].contains(Axial::new(3, 4)));
+    assert_eq!(chunks[0].size().x, hex_grid::map::CHUNK_SIZE);
+
+    // reads don't count as changes
+    let version = map.version();
+    let _ = map.get(Axial::new(3, 4));
+    let _ = map.iter().count();
+    assert!(changed(&map, version).is_empty());
+
+    // an emptied chunk is still reported
+    map.remove(Axial::new(3, 4));
+    assert_eq!(changed(&map, version), chunks);
+    assert_eq!(changed(&map, start), chunks);
+}
+
+#[test]
+fn clones_have_their_own_history() {
+    let map: HexMap<u32> = Axial::ZERO.range(3).map(|c| (c, 1)).collect();
+    let copy = map.clone();
+    assert_eq!(map, copy);
+    assert!(copy.changed_chunks(map.version()).is_none());
+    assert!(changed(&copy, copy.version()).is_empty());
+
+    // equality ignores history
+    let mut other = HexMap::new();
+    other.insert(Axial::new(100, 100), 1);
+    other.remove(Axial::new(100, 100));
+    other.extend(map.iter().map(|(c, &v)| (c, v)));
+    assert_eq!(map, other);
+}
+
+#[test]
+fn change_log_holds_each_changed_cell() {
+    let mut map: HexMap<u32> = HexMap::new();
+    map.insert(Axial::new(1, 1), 1);
+    assert!(!map.tracks_changes());
+    assert_eq!(map.drain_changes().count(), 0);
+
+    // existing cells are logged when logging starts
+    map.track_changes();
+    assert_eq!(map.drain_changes().collect::<Vec<_>>(), [Axial::new(1, 1)]);
+    assert_eq!(map.drain_changes().count(), 0);
+
+    map.insert(Axial::new(2, 0), 2);
+    map.insert(Axial::new(2, 0), 3);
+    map.remove(Axial::new(1, 1));
+    map.remove(Axial::new(9, 9));
+    let _ = map.get(Axial::new(2, 0));
+    let mut cells: Vec<Axial> = map.drain_changes().collect();
+    cells.sort();
+    assert_eq!(cells, [Axial::new(1, 1), Axial::new(2, 0)]);
+
+    // clones log from empty
+    let mut copy = map.clone();
+    assert!(copy.tracks_changes());
+    assert_eq!(copy.drain_changes().count(), 0);
+
+    map.clear();
+    assert_eq!(map.drain_changes().collect::<Vec<_>>(), [Axial::new(2, 0)]);
+}
+
+#[derive(Debug, Clone)]
+enum Op {
+    Insert(Axial, u32),
+    Remove(Axial),
+    Modify(Axial),
+    Clear,
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    let cell = (-40i32..40, -40i32..40).prop_map(|(q, r)| Axial::new(q, r));
+    prop_oneof![
+        8 => (cell.clone(), any::<u32>()).prop_map(|(c, v)| Op::Insert(c, v)),
+        4 => cell.clone().prop_map(Op::Remove),
+        2 => cell.prop_map(Op::Modify),
+        1 => Just(Op::Clear),
+    ]
+}
+
+fn apply(map: &mut HexMap<u32>, op: &Op) {
+    match *op {
+        Op::Insert(cell, value) => {
+            map.insert(cell, value);
+        }
+        Op::Remove(cell) => {
+            map.remove(cell);
+        }
+        Op::Modify(cell) => {
+            if let Some(v) = map.get_mut(cell) {
+                *v = v.wrapping_add(1);
+            }
+        }
+        Op::Clear => map.clear(),
+    }
+}
+
+proptest! {
+    #[test]
+    fn changed_chunks_cover_changes(
+        before in prop::collection::vec(op(), 0..50),
+        after in prop::collection::vec(op(), 0..50),
+    ) {
+        let mut map = HexMap::new();
+        for op in &before {
+            apply(&mut map, op);
+        }
+        let snapshot = map.clone();
+        let version = map.version();
+        for op in &after {
+            apply(&mut map, op);
+        }
+
+        let chunks: Vec<HexBounds> = changed(&map, version);
+        let cells: HashSet<Axial> = snapshot.cells().chain(map.cells()).collect();
+        for cell in cells {
+            if snapshot.get(cell) != map.get(cell) {
+                prop_assert!(
+                    chunks.iter().any(|c| c.contains(cell)),
+                    "{:?} changed, but isn't in a changed chunk", cell
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn drained_changes_cover_changes(
+        before in prop::collection::vec(op(), 0..50),
+        after in prop::collection::vec(op(), 0..50),
+    ) {
+        let mut map = HexMap::new();
+        for op in &before {
+            apply(&mut map, op);
+        }
+        map.track_changes();
+        let snapshot = map.clone();
+        map.drain_changes().for_each(drop);
+        for op in &after {
+            apply(&mut map, op);
+        }
+
+        let drained: HashSet<Axial> = map.drain_changes().collect();
+        let cells: HashSet<Axial> = snapshot.cells().chain(map.cells()).collect();
+        for cell in cells {
+            if snapshot.get(cell) != map.get(cell) {
+                prop_assert!(drained.contains(&cell), "{:?} changed, but wasn't logged", cell);
+            }
+        }
+        prop_assert_eq!(map.drain_changes().count(), 0);
+    }
+}
+
+/// chunk as written by `HexMap`'s `Serialize`
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Chunk {
+    chunk: Axial,
+    mask: [u64; 4],
+    values: Vec<u32>,
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn empty_chunks_are_skipped() {
+    let map: HexMap<u32> = [(Axial::ZERO, 1), (Axial::new(-40, 7), 2)]
+        .into_iter()
+        .collect();
+    let bytes = map.to_bytes().unwrap();
+
+    let mut chunks: Vec<Chunk> = bincode::deserialize(&bytes).unwrap();
+    chunks.push(Chunk {
+        chunk: Axial::new(9, -9),
+        mask: [0; 4],
+        values: Vec::new(),
+    });
+    let mut loaded = HexMap::<u32>::from_bytes(&bincode::serialize(&chunks).unwrap()).unwrap();
+    assert_eq!(loaded.len(), 2);
+    assert_eq!(loaded.to_bytes().unwrap(), bytes);
+
+    loaded.remove(Axial::ZERO);
+    loaded.remove(Axial::new(-40, 7));
+    assert!(loaded.is_empty());
+    assert_eq!(
+        loaded.to_bytes().unwrap(),
+        HexMap::<u32>::new().to_bytes().unwrap()
+    );
+}
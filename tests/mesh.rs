@@ -0,0 +1,128 @@
+//! grid mesh export tests
+use bevy::{
+    mesh::{Indices, VertexAttributeValues},
+    prelude::*,
+};
+use hex_grid::{Axial, GridKind, HexBounds, HexGridConfig, HexGridMesh, Orientation};
+use proptest::prelude::*;
+
+fn positions(mesh: &Mesh) -> Vec<Vec3> {
+    match mesh.attribute(Mesh::ATTRIBUTE_POSITION).unwrap() {
+        VertexAttributeValues::Float32x3(v) => v.iter().map(|&p| Vec3::from(p)).collect(),
+        other => panic!("unexpected attribute format {:?}", other),
+    }
+}
+
+fn indices(mesh: &Mesh) -> Vec<u32> {
+    match mesh.indices().unwrap() {
+        Indices::U32(i) => i.clone(),
+        Indices::U16(i) => i.iter().map(|&i| i as u32).collect(),
+    }
+}
+
+fn config(kind: GridKind, orientation: Orientation, size: f32) -> HexGridConfig {
+    HexGridConfig {
+        kind,
+        orientation,
+        size,
+        ..default()
+    }
+}
+
+fn any_kind() -> impl Strategy<Value = GridKind> {
+    prop::sample::select(GridKind::ALL.to_vec())
+}
+
+fn any_orientation() -> impl Strategy<Value = Orientation> {
+    prop::sample::select(vec![Orientation::Pointy, Orientation::Flat])
+}
+
+// (vertices, edges) of a line mesh
+fn line_counts(bounds: HexBounds, config: &HexGridConfig) -> (usize, usize) {
+    let mesh = HexGridMesh::lines(bounds, config);
+    (positions(&mesh).len(), indices(&mesh).len() / 2)
+}
+
+#[test]
+fn shared_edges_are_included_once() {
+    let hex = config(GridKind::Hex, Orientation::Pointy, 1.0);
+    assert_eq!(line_counts(HexBounds::from_cell(Axial::ZERO), &hex), (6, 6));
+    let pair = HexBounds::new(Axial::ZERO, Axial::new(1, 0));
+    assert_eq!(line_counts(pair, &hex), (10, 11));
+
+    let square = config(GridKind::Square, Orientation::Pointy, 2.0);
+    let quad = HexBounds::new(Axial::ZERO, Axial::new(1, 1));
+    assert_eq!(line_counts(quad, &square), (9, 12));
+
+    // a lower & upper triangle make a parallelogram with a shared diagonal
+    let triangle = config(GridKind::Triangle, Orientation::Pointy, 1.0);
+    let diamond = HexBounds::new(Axial::ZERO, Axial::new(1, 0));
+    assert_eq!(line_counts(diamond, &triangle), (4, 5));
+}
+
+proptest! {
+    #[test]
+    fn corners_surround_the_cell(
+        kind in any_kind(),
+        orientation in any_orientation(),
+        size in 0.5f32..4.0,
+        q in -50i32..50,
+        r in -50i32..50,
+    ) {
+        let config = config(kind, orientation, size);
+        let cell = Axial::new(q, r);
+        let corners = config.cell_corners(cell);
+        let center = corners.iter().sum::<Vec2>() / corners.len() as f32;
+        prop_assert!(center.distance(config.cell_to_world(cell)) < 1e-3 * size);
+
+        // counterclockwise from X towards Z, and every corner is in the cell
+        // or on its edge
+        let area: f32 = corners
+            .iter()
+            .zip(corners.iter().cycle().skip(1))
+            .map(|(a, b)| a.perp_dot(*b))
+            .sum();
+        prop_assert!(area > 0.0);
+        for corner in &corners {
+            let inside = corner.lerp(center, 0.01);
+            prop_assert_eq!(config.world_to_cell(inside), cell);
+        }
+    }
+
+    #[test]
+    fn fills_face_up_and_cover_the_cells(
+        kind in any_kind(),
+        orientation in any_orientation(),
+        size in 0.5f32..4.0,
+        q in -20i32..20,
+        r in -20i32..20,
+        w in 1i32..4,
+        h in 1i32..4,
+    ) {
+        let config = config(kind, orientation, size);
+        let bounds = HexBounds::new(Axial::new(q, r), Axial::new(q + w - 1, r + h - 1));
+        let mesh = HexGridMesh::fills(bounds, &config);
+        let positions = positions(&mesh);
+        let indices = indices(&mesh);
+
+        let mut area = 0.0;
+        for tri in indices.chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| positions[tri[i] as usize]);
+            let normal = (b - a).cross(c - a);
+            prop_assert!(normal.y > 0.0);
+            area += normal.length() * 0.5;
+
+            // each triangle lies in a single cell
+            let centroid = (a + b + c) / 3.0;
+            prop_assert!(bounds.contains(config.world_to_cell(centroid.xz())));
+        }
+
+        let cell_area = match kind {
+            GridKind::Hex => 3f32.sqrt() / 2.0,
+            GridKind::Square | GridKind::Isometric => 1.0,
+            GridKind::Triangle => 3f32.sqrt() / 4.0,
+        } * size * size;
+        let expected = cell_area * (w * h) as f32;
+        prop_assert!((area - expected).abs() < expected * 1e-3);
+    }
+}
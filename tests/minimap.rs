@@ -0,0 +1,99 @@
+//! minimap rasterization tests
+use bevy::prelude::*;
+use hex_grid::{Axial, GridKind, HexBounds, HexGridConfig, HexGridMinimap, HexMap, Orientation};
+
+fn pixel(image: &Image, x: u32, y: u32) -> Srgba {
+    image.get_color_at(x, y).unwrap().to_srgba()
+}
+
+fn assert_close(a: Srgba, b: Srgba) {
+    let d = Vec4::from_array(a.to_f32_array()) - Vec4::from_array(b.to_f32_array());
+    assert!(d.abs().max_element() < 0.02, "{:?} != {:?}", a, b);
+}
+
+fn terrain(cells: impl IntoIterator<Item = (Axial, Color)>) -> HexMap<Color> {
+    let mut map = HexMap::new();
+    for (cell, color) in cells {
+        map.insert(cell, color);
+    }
+    map
+}
+
+#[test]
+fn cells_fill_the_image() {
+    let red = Color::srgb(1.0, 0.0, 0.0);
+    let minimap = HexGridMinimap::default()
+        .with_terrain(terrain([(Axial::ZERO, red)]))
+        .with_resolution(UVec2::splat(32));
+    let image = minimap.rasterize(&HexGridConfig::default());
+    assert_eq!(image.size(), UVec2::splat(32));
+
+    assert_close(pixel(&image, 16, 16), red.to_srgba());
+    // the corners of the image are outside the hex
+    assert_close(pixel(&image, 0, 0), Srgba::NONE);
+    assert_close(pixel(&image, 31, 31), Srgba::NONE);
+}
+
+#[test]
+fn rows_run_along_x_from_negative_z() {
+    let (a, b) = (Color::srgb(1.0, 0.0, 0.0), Color::srgb(0.0, 0.0, 1.0));
+    let config = HexGridConfig {
+        kind: GridKind::Square,
+        size: 3.0,
+        ..default()
+    };
+    // two cells in a column, in an image twice as wide as the column
+    let minimap = HexGridMinimap::default()
+        .with_terrain(terrain([(Axial::ZERO, a), (Axial::new(0, 1), b)]))
+        .with_resolution(UVec2::new(64, 32))
+        .with_background(Color::WHITE);
+    let image = minimap.rasterize(&config);
+
+    assert_close(pixel(&image, 32, 8), a.to_srgba());
+    assert_close(pixel(&image, 32, 24), b.to_srgba());
+    // aspect ratio is kept; the column is centered
+    assert_close(pixel(&image, 4, 16), Srgba::WHITE);
+    assert_close(pixel(&image, 60, 16), Srgba::WHITE);
+}
+
+#[test]
+fn fog_and_markers() {
+    let config = HexGridConfig {
+        orientation: Orientation::Flat,
+        ..default()
+    };
+    let cells = [Axial::ZERO, Axial::new(1, 0)];
+    let mut minimap = HexGridMinimap::default()
+        .with_terrain(terrain(cells.map(|c| (c, Color::WHITE))))
+        .with_bounds(HexBounds::new(cells[0], cells[1]))
+        .with_resolution(UVec2::splat(64))
+        .with_fog_color(Color::BLACK);
+    minimap.fog.insert(cells[0], 1.0);
+    minimap.markers.push((cells[1], Color::srgb(0.0, 1.0, 0.0)));
+    let image = minimap.rasterize(&config);
+
+    // count pixels inside the terrain, found by drawing it alone
+    let plain = HexGridMinimap {
+        fog: HexMap::new(),
+        markers: Vec::new(),
+        ..minimap.clone()
+    }
+    .rasterize(&config);
+    let white = |x, y| pixel(&plain, x, y) == Srgba::WHITE;
+    let (mut fogged, mut marked) = (0, 0);
+    for y in 0..64 {
+        for x in 0..64 {
+            if !white(x, y) {
+                continue;
+            }
+            match pixel(&image, x, y) {
+                p if p == Srgba::BLACK => fogged += 1,
+                p if p == Srgba::rgb(0.0, 1.0, 0.0) => marked += 1,
+                _ => (),
+            }
+        }
+    }
+    assert!(fogged > 100, "{} fogged pixels", fogged);
+    // a dot 0.35 cells across, inside the other cell
+    assert!((10..fogged).contains(&marked), "{} marker pixels", marked);
+}
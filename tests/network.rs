@@ -0,0 +1,100 @@
+//! river & road network tests
+use hex_grid::{
+    network::{river, road_network},
+    Axial, HexDirection, HexEdge, HexEdgeSet, HexVertex,
+};
+use proptest::prelude::*;
+use std::collections::{HashSet, VecDeque};
+
+fn axial() -> impl Strategy<Value = Axial> {
+    (-8i32..8, -8i32..8).prop_map(|(q, r)| Axial::new(q, r))
+}
+
+fn direction() -> impl Strategy<Value = HexDirection> {
+    proptest::sample::select(HexDirection::ALL.to_vec())
+}
+
+// cells reachable from `start` along roads
+fn reachable(roads: &HexEdgeSet, start: Axial) -> HashSet<Axial> {
+    let mut seen = HashSet::from([start]);
+    let mut open = VecDeque::from([start]);
+    while let Some(cell) = open.pop_front() {
+        for edge in roads.around(cell) {
+            for next in edge.cells() {
+                if seen.insert(next) {
+                    open.push_back(next);
+                }
+            }
+        }
+    }
+    seen
+}
+
+proptest! {
+    #[test]
+    fn edge_set(edges in proptest::collection::vec((axial(), direction()), 0..40)) {
+        let mut set = HexEdgeSet::new();
+        let mut expected = HashSet::new();
+        for &(cell, dir) in &edges {
+            let edge = HexEdge::new(cell, dir);
+            prop_assert_eq!(set.insert(edge), expected.insert(edge));
+            // the same edge from the other side
+            prop_assert!(set.contains(HexEdge::new(cell + dir, dir.opposite())));
+        }
+        prop_assert_eq!(set.len(), expected.len());
+        prop_assert_eq!(set.iter().collect::<HashSet<_>>(), expected.clone());
+
+        for edge in expected {
+            prop_assert!(set.remove(edge));
+            prop_assert!(!set.remove(edge));
+        }
+        prop_assert!(set.is_empty());
+    }
+
+    #[test]
+    fn rivers_flow_downhill(cell in axial(), corner in 0usize..6, tilt in -1.0f32..1.0) {
+        // a slope down towards +X, with the sea past x = 10
+        let height = |v: HexVertex| {
+            let pos = v.to_world();
+            (pos.x < 10.0).then_some(20.0 - pos.x + tilt * pos.y * 0.1)
+        };
+        let source = HexVertex::new(cell, corner);
+        prop_assume!(height(source).is_some());
+        let edges = river(source, height, 100);
+        prop_assert!(!edges.is_empty());
+
+        // a connected chain of edges from the source
+        let mut at = source;
+        for edge in &edges {
+            let [a, b] = edge.vertices();
+            prop_assert!(a == at || b == at);
+            let next = if a == at { b } else { a };
+            if let (Some(h), Some(next_h)) = (height(at), height(next)) {
+                prop_assert!(next_h < h);
+            }
+            at = next;
+        }
+        // ending in the sea
+        prop_assert!(height(at).is_none());
+    }
+
+    #[test]
+    fn roads_connect_towns(towns in proptest::collection::vec(axial(), 1..6), wall in -4i32..4) {
+        // a wall along q = wall, with a gap at r = 0
+        let cost = |cell: Axial| (cell.q != wall || cell.r == 0).then_some(1);
+        let towns: Vec<Axial> = towns.into_iter().filter(|&t| cost(t).is_some()).collect();
+        let roads = road_network(&towns, cost);
+
+        if let Some(&first) = towns.first() {
+            let network = reachable(&roads, first);
+            for town in &towns {
+                prop_assert!(network.contains(town));
+            }
+        }
+        for edge in roads.iter() {
+            for cell in edge.cells() {
+                prop_assert!(cost(cell).is_some());
+            }
+        }
+    }
+}
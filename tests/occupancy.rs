@@ -0,0 +1,71 @@
+//! cell occupancy tests
+use bevy::prelude::*;
+use hex_grid::{Axial, HexOccupancy, HexPosition};
+
+fn world() -> (World, Entity) {
+    let mut world = World::new();
+    world.init_resource::<HexOccupancy>();
+    let grid = world.spawn_empty().id();
+    (world, grid)
+}
+
+#[test]
+fn occupancy_follows_positions() {
+    let (mut world, grid) = world();
+    let cell = Axial::new(2, -1);
+    let a = world.spawn(HexPosition { grid, cell }).id();
+    let b = world.spawn(HexPosition { grid, cell }).id();
+    assert_eq!(
+        world.resource::<HexOccupancy>().occupants(grid, cell),
+        &[a, b]
+    );
+
+    // moving
+    let next = Axial::new(2, 0);
+    world.entity_mut(a).insert(HexPosition { grid, cell: next });
+    let occupancy = world.resource::<HexOccupancy>();
+    assert_eq!(occupancy.occupants(grid, cell), &[b]);
+    assert_eq!(occupancy.occupants(grid, next), &[a]);
+
+    // removing & despawning
+    world.entity_mut(a).remove::<HexPosition>();
+    world.despawn(b);
+    let occupancy = world.resource::<HexOccupancy>();
+    assert!(occupancy.occupants(grid, cell).is_empty());
+    assert!(occupancy.occupants(grid, next).is_empty());
+    assert!(occupancy.grid(grid).is_none());
+}
+
+#[test]
+fn reservations() {
+    let (mut world, grid) = world();
+    let cell = Axial::new(0, 3);
+    let a = world.spawn_empty().id();
+    let b = world
+        .spawn(HexPosition {
+            grid,
+            cell: Axial::ZERO,
+        })
+        .id();
+
+    let mut occupancy = world.resource_mut::<HexOccupancy>();
+    assert!(occupancy.try_reserve(grid, cell, a));
+    assert!(occupancy.try_reserve(grid, cell, a));
+    assert!(!occupancy.try_reserve(grid, cell, b));
+    // occupied cells can't be reserved by others
+    assert!(!occupancy.try_reserve(grid, Axial::ZERO, a));
+    assert!(occupancy.try_reserve(grid, Axial::ZERO, b));
+    assert_eq!(occupancy.release(grid, Axial::ZERO), Some(b));
+
+    let o = occupancy.grid(grid).unwrap();
+    assert!(o.is_free_for(cell, a));
+    assert!(!o.is_free_for(cell, b));
+    assert_eq!(o.reserved_by(cell), Some(a));
+
+    // arriving consumes the reservation
+    world.entity_mut(a).insert(HexPosition { grid, cell });
+    let o = world.resource::<HexOccupancy>().grid(grid).unwrap();
+    assert_eq!(o.reserved_by(cell), None);
+    assert!(o.is_occupied(cell));
+    assert!(!o.is_free_for(cell, b));
+}
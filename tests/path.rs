@@ -0,0 +1,271 @@
+//! pathfinding tests
+use hex_grid::{
+    path::{
+        find_path, find_path_facing, find_path_hierarchical, movement_range, FlowField,
+        IncrementalPath,
+    },
+    Axial, HexDirection, HexMap, PathNode, PortalGraph,
+};
+use proptest::prelude::*;
+
+// small enough to search exhaustively when the goal is walled off
+const RADIUS: u32 = 12;
+
+fn axial() -> impl Strategy<Value = Axial> {
+    proptest::sample::select(Axial::ZERO.range(RADIUS).collect::<Vec<_>>())
+}
+
+fn in_bounds(cell: Axial) -> bool {
+    cell.length() <= RADIUS
+}
+
+// map spanning several chunks with roughly a quarter of the cells blocked
+// (cost 0), and the rest costing 1 to 3
+fn terrain(seed: u32) -> HexMap<u32> {
+    Axial::ZERO
+        .range(40)
+        .map(|cell| {
+            let mut h = (cell.q as u32).wrapping_mul(0x9e37_79b9)
+                ^ (cell.r as u32).wrapping_mul(0x85eb_ca6b)
+                ^ seed.wrapping_mul(0xc2b2_ae35);
+            h ^= h >> 15;
+            h = h.wrapping_mul(0x2c1b_3c6d);
+            h ^= h >> 13;
+            (cell, h % 4)
+        })
+        .collect()
+}
+
+fn passable(cost: &u32) -> Option<u32> {
+    (*cost > 0).then_some(*cost)
+}
+
+proptest! {
+    #[test]
+    fn open_path_is_straight(a in axial(), b in axial()) {
+        let (path, cost) =
+            find_path(a, b, |_, to| in_bounds(to).then_some(1)).unwrap();
+        prop_assert_eq!(cost, a.distance(b));
+        prop_assert_eq!(path.len() as u32, a.distance(b) + 1);
+        prop_assert_eq!(path[0], a);
+        prop_assert_eq!(*path.last().unwrap(), b);
+        for pair in path.windows(2) {
+            prop_assert_eq!(pair[0].distance(pair[1]), 1);
+        }
+    }
+
+    #[test]
+    fn facing_path_steps_face_forward(
+        a in axial(),
+        b in axial(),
+        facing in prop::sample::select(HexDirection::ALL.to_vec()),
+    ) {
+        let (path, cost) = find_path_facing(PathNode::new(a, facing), b, |from, to| {
+            in_bounds(to.coord).then_some(1 + from.turns_to(to.facing))
+        })
+        .unwrap();
+        prop_assert_eq!(path[0], PathNode::new(a, facing));
+        prop_assert_eq!(path.last().unwrap().coord, b);
+        for pair in path.windows(2) {
+            prop_assert_eq!(pair[0].coord + pair[1].facing, pair[1].coord);
+        }
+        // never cheaper than walking straight there
+        prop_assert!(cost >= a.distance(b));
+    }
+}
+
+proptest! {
+    // building the graph dominates; a few maps are enough
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn hierarchical_matches_flat(seed in any::<u32>(), a in axial(), b in axial()) {
+        let map = terrain(seed);
+        let mut graph = PortalGraph::new(&map);
+        let (a, b) = (a * 3, b * 3);
+
+        let flat = find_path(a, b, |_, to| map.get(to).and_then(passable));
+        let hierarchical = find_path_hierarchical(&mut graph, &map, a, b, passable);
+        prop_assert_eq!(flat.is_some(), hierarchical.is_some());
+        let (Some((_, flat_cost)), Some((path, cost))) = (flat, hierarchical) else {
+            return Ok(());
+        };
+        prop_assert!(cost >= flat_cost);
+        prop_assert_eq!(path[0], a);
+        prop_assert_eq!(*path.last().unwrap(), b);
+        let mut walked = 0;
+        for pair in path.windows(2) {
+            prop_assert_eq!(pair[0].distance(pair[1]), 1);
+            walked += map.get(pair[1]).and_then(passable).unwrap();
+        }
+        prop_assert_eq!(walked, cost);
+    }
+
+    #[test]
+    fn flow_field_follows_cheapest(seed in any::<u32>(), goal in axial()) {
+        let map = terrain(seed);
+        let cost = |cell| map.get(cell).and_then(passable);
+        let field = FlowField::new([goal], cost);
+
+        for (cell, dir) in field.directions().iter() {
+            let next = cell + dir.offset();
+            prop_assert_eq!(field.cost(cell), Some(field.cost(next).unwrap() + cost(next).unwrap()));
+        }
+        // same costs as searching from each open cell
+        for cell in Axial::ZERO.range(RADIUS).step_by(7).filter(|&c| cost(c).is_some()) {
+            let path = find_path(cell, goal, |_, to| cost(to));
+            prop_assert_eq!(field.cost(cell), path.map(|(_, c)| c));
+        }
+    }
+
+    #[test]
+    fn flow_field_update(seed in any::<u32>(), goal in axial(), center in axial(), wall in any::<bool>()) {
+        let mut map = terrain(seed);
+        let mut field = FlowField::new([goal], |cell| map.get(cell).and_then(passable));
+
+        // block or clear a small region
+        let changed: Vec<Axial> = center.range(2).collect();
+        for &cell in &changed {
+            map.insert(cell, if wall { 0 } else { 1 });
+        }
+        let cost = |cell| map.get(cell).and_then(passable);
+        field.update(changed, cost);
+
+        let fresh = FlowField::new([goal], cost);
+        for cell in Axial::ZERO.range(40) {
+            prop_assert_eq!(field.cost(cell), fresh.cost(cell), "{:?}", cell);
+            prop_assert_eq!(field.direction(cell).is_some(), fresh.direction(cell).is_some());
+        }
+        for (cell, dir) in field.directions().iter() {
+            let next = cell + dir.offset();
+            prop_assert_eq!(field.cost(cell), Some(field.cost(next).unwrap() + cost(next).unwrap()));
+        }
+    }
+
+    #[test]
+    fn many_flow_fields(seed in any::<u32>(), goals in prop::collection::vec(prop::collection::vec(axial(), 1..3), 0..4)) {
+        let map = terrain(seed);
+        let cost = |cell| map.get(cell).and_then(passable);
+        let fields = FlowField::many(goals.iter().cloned(), cost);
+        prop_assert_eq!(fields.len(), goals.len());
+        for (field, goals) in fields.iter().zip(&goals) {
+            let alone = FlowField::new(goals.iter().copied(), cost);
+            prop_assert_eq!(field.goals(), alone.goals());
+            prop_assert_eq!(field.directions(), alone.directions());
+        }
+    }
+
+    #[test]
+    fn incremental_path_matches_fresh_search(
+        seed in any::<u32>(),
+        start in axial(),
+        goal in axial(),
+        edits in prop::collection::vec((axial(), any::<bool>(), 0usize..4), 1..6),
+    ) {
+        let mut map = terrain(seed);
+        let mut path = IncrementalPath::new(start, goal, |cell| map.get(cell).and_then(passable));
+
+        for (center, wall, steps) in edits {
+            // walk a few steps along the path, then block or clear a cell &
+            // its neighbors
+            let mut unit = path.start();
+            for _ in 0..steps {
+                match path.next_step(|cell| map.get(cell).and_then(passable)) {
+                    Some(next) => unit = next,
+                    None => break,
+                }
+            }
+            let changed: Vec<Axial> = center.range(1).filter(|&c| c != unit).collect();
+            for &cell in &changed {
+                map.insert(cell, if wall { 0 } else { 2 });
+            }
+            let cost = |cell| map.get(cell).and_then(passable);
+            path.update(unit, changed, cost);
+
+            let fresh = find_path(unit, goal, |_, to| cost(to));
+            prop_assert_eq!(path.cost(), fresh.as_ref().map(|&(_, c)| c));
+            if let Some(cells) = path.path(cost) {
+                prop_assert_eq!(cells[0], unit);
+                prop_assert_eq!(*cells.last().unwrap(), goal);
+                let walked: u32 = cells[1..].iter().map(|&c| cost(c).unwrap()).sum();
+                prop_assert_eq!(Some(walked), path.cost());
+            }
+        }
+    }
+
+    #[test]
+    fn movement_range_matches_paths(seed in any::<u32>(), origin in axial(), budget in 0u32..8) {
+        let map = terrain(seed);
+        let cost = |cell| map.get(cell).and_then(passable);
+        let range = movement_range(origin, budget, cost);
+
+        prop_assert_eq!(range.get(origin), Some(&0));
+        for cell in origin.range(budget) {
+            let path = find_path(origin, cell, |_, to| cost(to)).map(|(_, c)| c);
+            let expected = path.filter(|&c| c <= budget);
+            prop_assert_eq!(range.get(cell).copied(), expected, "{:?}", cell);
+        }
+        prop_assert!(range.iter().all(|(cell, _)| cell.distance(origin) <= budget));
+    }
+}
+
+#[test]
+fn blocked_goal() {
+    let goal = Axial::new(3, 0);
+    let path = find_path(Axial::ZERO, goal, |_, to| {
+        (in_bounds(to) && to.distance(goal) != 1).then_some(1)
+    });
+    assert_eq!(path, None);
+}
+
+#[test]
+fn turns_to() {
+    let node = PathNode::new(Axial::ZERO, HexDirection::NorthEast);
+    let turns = HexDirection::ALL.map(|dir| node.turns_to(dir));
+    assert_eq!(turns, [1, 0, 1, 2, 3, 2]);
+}
+
+#[test]
+fn turn_costs_prefer_straight_lines() {
+    // facing +X; the goal is three cells away in the direction 60 degrees
+    // counter-clockwise.  Turning once up front & driving straight is
+    // cheaper than zig-zagging.
+    let start = PathNode::new(Axial::ZERO, HexDirection::East);
+    let goal = Axial::new(0, 3);
+    let (path, cost) = find_path_facing(start, goal, |from, to| {
+        (to.coord.length() <= 6).then_some(1 + 10 * from.turns_to(to.facing))
+    })
+    .unwrap();
+    assert_eq!(cost, 13);
+    assert!(path[1..]
+        .iter()
+        .all(|n| n.facing == HexDirection::NorthEast));
+}
+
+#[test]
+fn hierarchical_incremental_update() {
+    let mut map = terrain(7);
+    let mut graph = PortalGraph::new(&map);
+    let (start, goal) = (Axial::new(-30, 10), Axial::new(30, -10));
+    map.insert(start, 1);
+    map.insert(goal, 1);
+    graph.mark_changed(start);
+    graph.mark_changed(goal);
+
+    let (path, _) = find_path_hierarchical(&mut graph, &map, start, goal, passable).unwrap();
+
+    // wall off the middle of the path; the graph must route around it
+    let blocked = path[path.len() / 2];
+    for cell in blocked.range(1) {
+        map.insert(cell, 0);
+        graph.mark_changed(cell);
+    }
+    let (path, cost) = find_path_hierarchical(&mut graph, &map, start, goal, passable).unwrap();
+    assert!(path.iter().all(|c| c.distance(blocked) > 1));
+
+    // same result as a graph built from scratch
+    let mut fresh = PortalGraph::new(&map);
+    let (_, fresh_cost) = find_path_hierarchical(&mut fresh, &map, start, goal, passable).unwrap();
+    assert_eq!(cost, fresh_cost);
+    assert_eq!(graph.portal_count(), fresh.portal_count());
+}
@@ -0,0 +1,105 @@
+//! background pathfinding tests
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use hex_grid::{
+    drag::HexGridMoveCosts,
+    path::find_path,
+    pathfind::{HexGridPathPlugin, PathReady, PathRequest},
+    Axial, HexMap,
+};
+
+fn app(costs: HexMap<u32>) -> (App, Entity) {
+    let mut app = App::new();
+    app.add_plugins((TaskPoolPlugin::default(), HexGridPathPlugin));
+    let grid = app.world_mut().spawn(HexGridMoveCosts(costs)).id();
+    (app, grid)
+}
+
+// disk with a wall across it, open only at the south end
+fn walled(radius: u32) -> HexMap<u32> {
+    Axial::ZERO
+        .range(radius)
+        .filter(|cell| cell.q != 0 || cell.r > radius as i32 - 2)
+        .map(|cell| (cell, 1))
+        .collect()
+}
+
+fn ready(app: &mut App) -> Vec<PathReady> {
+    app.world_mut()
+        .resource_mut::<Messages<PathReady>>()
+        .drain()
+        .collect()
+}
+
+// update until a path is ready, or give up after a while
+fn wait(app: &mut App) -> Vec<PathReady> {
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(30) {
+        app.update();
+        let ready = ready(app);
+        if !ready.is_empty() {
+            return ready;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    panic!("no path ready");
+}
+
+#[test]
+fn paths_are_delivered() {
+    let costs = walled(10);
+    let (from, to) = (Axial::new(-5, 0), Axial::new(5, 0));
+    let expected = find_path(from, to, |_, cell| costs.get(cell).copied());
+    let (mut app, grid) = app(costs);
+    let unit = app.world_mut().spawn(PathRequest::new(grid, from, to)).id();
+
+    let ready = wait(&mut app);
+    assert_eq!(ready.len(), 1);
+    assert_eq!(ready[0].entity, unit);
+    assert_eq!(ready[0].grid, grid);
+    assert_eq!(ready[0].path, expected);
+    assert!(expected.is_some());
+    assert!(!app.world().entity(unit).contains::<PathRequest>());
+}
+
+#[test]
+fn unreachable_goals() {
+    let (mut app, grid) = app(walled(6));
+    // walled in on its own
+    let goal = Axial::new(20, 0);
+    app.world_mut()
+        .spawn(PathRequest::new(grid, Axial::ZERO, goal));
+    let ready = wait(&mut app);
+    assert_eq!(ready[0].path, None);
+}
+
+#[test]
+fn despawning_cancels() {
+    // searching every cell of a large map for a goal that can't be reached
+    let (mut app, grid) = app(walled(300));
+    let far = Axial::new(1000, 0);
+    let unit = app
+        .world_mut()
+        .spawn(PathRequest::new(grid, Axial::new(-1, 0), far))
+        .id();
+    let removed = app
+        .world_mut()
+        .spawn(PathRequest::new(grid, Axial::new(-2, 0), far))
+        .id();
+    app.update();
+    app.world_mut().despawn(unit);
+    app.world_mut().entity_mut(removed).remove::<PathRequest>();
+
+    // a later request still gets its path
+    let other = app
+        .world_mut()
+        .spawn(PathRequest::new(grid, Axial::new(-1, 0), Axial::new(-3, 0)))
+        .id();
+    let ready = wait(&mut app);
+    assert!(ready.iter().all(|ready| ready.entity == other));
+    for _ in 0..10 {
+        app.update();
+        assert!(self::ready(&mut app).is_empty());
+    }
+}
@@ -0,0 +1,78 @@
+//! path preview ribbon mesh tests
+use bevy::{mesh::VertexAttributeValues, prelude::*};
+use hex_grid::{preview::path_ribbon_mesh, Axial, GridKind, HexDirection, HexGridConfig};
+use proptest::prelude::*;
+
+fn attribute(mesh: &Mesh, id: impl Into<bevy::mesh::MeshVertexAttributeId>) -> Vec<Vec3> {
+    match mesh.attribute(id).unwrap() {
+        VertexAttributeValues::Float32x3(v) => v.iter().map(|&p| Vec3::from(p)).collect(),
+        VertexAttributeValues::Float32x2(v) => {
+            v.iter().map(|&[x, y]| Vec3::new(x, y, 0.0)).collect()
+        }
+        other => panic!("unexpected attribute format {:?}", other),
+    }
+}
+
+#[test]
+fn short_paths_have_no_mesh() {
+    let config = HexGridConfig::default();
+    assert!(path_ribbon_mesh(&config, &[], 0.3).is_none());
+    assert!(path_ribbon_mesh(&config, &[Axial::ZERO], 0.3).is_none());
+    assert!(path_ribbon_mesh(&config, &[Axial::ZERO, Axial::ZERO], 0.3).is_none());
+}
+
+#[test]
+fn straight_ribbon() {
+    let config = HexGridConfig {
+        size: 2.0,
+        ..default()
+    };
+    let path: Vec<Axial> = (0..4).map(|q| Axial::new(q, 0)).collect();
+    let mesh = path_ribbon_mesh(&config, &path, 0.25).unwrap();
+    let positions = attribute(&mesh, Mesh::ATTRIBUTE_POSITION);
+    let uvs = attribute(&mesh, Mesh::ATTRIBUTE_UV_0);
+    assert_eq!(positions.len(), 8);
+
+    for (i, pair) in positions.chunks(2).enumerate() {
+        // full width across, everywhere along a straight path
+        assert!((pair[0].distance(pair[1]) - 0.5).abs() < 1e-5);
+        // one cell is four periods of a pattern two widths long
+        assert!((uvs[i * 2].x - i as f32 * 2.0).abs() < 1e-4);
+    }
+}
+
+proptest! {
+    #[test]
+    fn ribbon_follows_cell_centers(
+        kind in prop::sample::select(GridKind::ALL.to_vec()),
+        start in (-20i32..20, -20i32..20),
+        steps in prop::collection::vec(prop::sample::select(HexDirection::ALL.to_vec()), 1..20),
+    ) {
+        let config = HexGridConfig { kind, ..default() };
+        let mut path = vec![Axial::new(start.0, start.1)];
+        for dir in steps {
+            let next = path.last().unwrap().neighbor(dir);
+            path.push(next);
+        }
+        let Some(mesh) = path_ribbon_mesh(&config, &path, 0.3) else {
+            // only when every step stayed on the first cell
+            prop_assert!(path.iter().all(|&c| c == path[0]));
+            return Ok(());
+        };
+        let positions = attribute(&mesh, Mesh::ATTRIBUTE_POSITION);
+        let uvs = attribute(&mesh, Mesh::ATTRIBUTE_UV_0);
+
+        let mut centers: Vec<Vec2> = path.iter().map(|&c| config.cell_to_world(c)).collect();
+        centers.dedup();
+        prop_assert_eq!(positions.len(), centers.len() * 2);
+        for (i, center) in centers.iter().enumerate() {
+            let (a, b) = (positions[i * 2], positions[i * 2 + 1]);
+            let mid = (a.xz() + b.xz()) * 0.5;
+            prop_assert!(mid.distance(*center) < 1e-4, "{:?} {:?}", mid, center);
+            prop_assert!(a.y > 0.0);
+            if i > 0 {
+                prop_assert!(uvs[i * 2].x > uvs[i * 2 - 2].x);
+            }
+        }
+    }
+}
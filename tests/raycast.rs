@@ -0,0 +1,69 @@
+//! ray cast tests
+//!
+//! The cells visited must be exactly the cells `world_to_cell()` finds along
+//! the ray, which mirrors the shader.
+use bevy::prelude::*;
+use hex_grid::{
+    raycast::{ray_cells, raycast},
+    Axial, GridKind, HexGridConfig, Orientation,
+};
+use proptest::prelude::*;
+
+fn config() -> impl Strategy<Value = HexGridConfig> {
+    (
+        proptest::sample::select(GridKind::ALL.to_vec()),
+        prop_oneof![Just(Orientation::Pointy), Just(Orientation::Flat)],
+        0.25f32..4.0,
+    )
+        .prop_map(|(kind, orientation, size)| HexGridConfig {
+            kind,
+            orientation,
+            size,
+            ..default()
+        })
+}
+
+fn point() -> impl Strategy<Value = Vec2> {
+    (-50.0f32..50.0, -50.0f32..50.0).prop_map(|(x, y)| Vec2::new(x, y))
+}
+
+proptest! {
+    #[test]
+    fn visits_cells_under_ray(config in config(), origin in point(), angle in 0.0f32..std::f32::consts::TAU, max_dist in 0.0f32..40.0) {
+        let dir = Vec2::from_angle(angle);
+        let hits: Vec<_> = ray_cells(&config, origin, dir, max_dist).collect();
+        prop_assert_eq!(hits[0].cell, config.world_to_cell(origin));
+        prop_assert_eq!(hits[0].distance, 0.0);
+
+        let ends = hits.iter().skip(1).map(|h| h.distance).chain([max_dist]);
+        for (hit, end) in hits.iter().zip(ends) {
+            prop_assert!(hit.distance <= end);
+            prop_assert!(hit.point.distance(origin + dir * hit.distance) < 1e-3);
+            // skip slivers where the ray clips a corner
+            if end - hit.distance < 1e-3 * config.size {
+                continue;
+            }
+            let mid = origin + dir * (hit.distance + end) * 0.5;
+            prop_assert_eq!(config.world_to_cell(mid), hit.cell);
+        }
+        for pair in hits.windows(2) {
+            prop_assert_ne!(pair[0].cell, pair[1].cell);
+        }
+    }
+}
+
+#[test]
+fn first_blocked_cell() {
+    let config = HexGridConfig::default();
+    let wall = Axial::new(4, 0);
+    let hit = raycast(&config, Vec2::ZERO, Vec2::X, 10.0, |cell| cell == wall).unwrap();
+    assert_eq!(hit.cell, wall);
+    // the wall's edge is half a cell before its center
+    let center = config.cell_to_world(wall);
+    assert!((hit.point.x - (center.x - config.size * 0.5)).abs() < 1e-4);
+
+    assert_eq!(
+        raycast(&config, Vec2::ZERO, Vec2::X, 3.0, |cell| cell == wall),
+        None
+    );
+}
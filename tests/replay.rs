@@ -0,0 +1,166 @@
+//! replay tests
+use std::time::Duration;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use hex_grid::{
+    replay::ReplayEvent, Axial, CellOwner, HexBoard, HexCommand, HexGridCommandPlugin,
+    HexGridMinimap, HexGridPlayback, HexMap, HexPosition, HexReplay, HexUnit, UnitId,
+};
+use proptest::prelude::*;
+
+fn command() -> impl Strategy<Value = HexCommand> {
+    let cell = (-10i32..10, -10i32..10).prop_map(|(q, r)| Axial::new(q, r));
+    let unit = (0u32..4).prop_map(UnitId);
+    prop_oneof![
+        (unit.clone(), cell.clone()).prop_map(|(unit, cell)| HexCommand::SpawnUnit { unit, cell }),
+        (unit, cell.clone()).prop_map(|(unit, to)| HexCommand::MoveUnit { unit, to }),
+        (cell, 0u8..3).prop_map(|(cell, owner)| HexCommand::ClaimCell {
+            cell,
+            owner: Some(CellOwner(owner))
+        }),
+    ]
+}
+
+// replay of `commands` one tenth of a second apart
+fn replay(start: HexBoard, commands: &[HexCommand]) -> HexReplay {
+    let mut replay = HexReplay::new(start);
+    for (i, command) in commands.iter().enumerate() {
+        replay.push(i as f32 * 0.1, ReplayEvent::Command(*command));
+    }
+    replay
+}
+
+#[test]
+fn board_at() {
+    let unit = UnitId(7);
+    let mut replay = HexReplay::default();
+    replay.push(
+        0.5,
+        ReplayEvent::Command(HexCommand::SpawnUnit {
+            unit,
+            cell: Axial::ZERO,
+        }),
+    );
+    replay.push(1.0, ReplayEvent::Selected(vec![Axial::new(1, 0)]));
+    let to = Axial::new(2, -1);
+    replay.push(2.0, ReplayEvent::Command(HexCommand::MoveUnit { unit, to }));
+
+    assert_eq!(replay.duration(), 2.0);
+    assert_eq!(replay.board_at(0.0), HexBoard::new());
+    assert_eq!(replay.board_at(0.5).unit(unit), Some(Axial::ZERO));
+    assert_eq!(replay.board_at(1.9).unit(unit), Some(Axial::ZERO));
+    assert_eq!(replay.final_board().unit(unit), Some(to));
+}
+
+#[test]
+fn push_keeps_time_order() {
+    let mut replay = HexReplay::default();
+    replay.push(2.0, ReplayEvent::Selected(Vec::new()));
+    replay.push(1.0, ReplayEvent::Selected(Vec::new()));
+    let times: Vec<f32> = replay.frames.iter().map(|f| f.time).collect();
+    assert_eq!(times, [2.0, 2.0]);
+}
+
+#[test]
+fn restart_resets_units_and_fog() {
+    let unit = UnitId(3);
+    let (from, to) = (Axial::new(1, 0), Axial::new(-2, 2));
+    let mut start = HexBoard::new();
+    start.apply(&HexCommand::SpawnUnit { unit, cell: from });
+    start.apply(&HexCommand::RevealFog {
+        center: from,
+        radius: 0,
+    });
+    let mut replay = HexReplay::new(start);
+    replay.push(0.1, ReplayEvent::Command(HexCommand::MoveUnit { unit, to }));
+    replay.push(
+        0.1,
+        ReplayEvent::Command(HexCommand::RevealFog {
+            center: to,
+            radius: 1,
+        }),
+    );
+
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, HexGridCommandPlugin))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+            200,
+        )));
+    let mut fog = HexMap::new();
+    for cell in Axial::ZERO.range(4) {
+        fog.insert(cell, 1.0);
+    }
+    let minimap = HexGridMinimap { fog, ..default() };
+    let grid = app
+        .world_mut()
+        .spawn((HexBoard::new(), HexGridPlayback::new(replay), minimap))
+        .id();
+    let entity = app.world_mut().spawn(HexUnit(unit)).id();
+
+    let position = |app: &App| app.world().get::<HexPosition>(entity).map(|p| p.cell);
+    let fog_at = |app: &App, cell| {
+        let minimap = app.world().get::<HexGridMinimap>(grid).unwrap();
+        minimap.fog.get(cell).copied()
+    };
+
+    app.update();
+    assert_eq!(position(&app), Some(from));
+    assert_eq!(fog_at(&app, from), None);
+    assert_eq!(fog_at(&app, to), Some(1.0));
+
+    app.update();
+    assert!(app
+        .world()
+        .get::<HexGridPlayback>(grid)
+        .unwrap()
+        .is_finished());
+    assert_eq!(position(&app), Some(to));
+    assert_eq!(fog_at(&app, to), None);
+
+    app.world_mut()
+        .get_mut::<HexGridPlayback>(grid)
+        .unwrap()
+        .restart();
+    app.update();
+    assert_eq!(position(&app), Some(from));
+    assert_eq!(fog_at(&app, from), None);
+    assert_eq!(fog_at(&app, to), Some(1.0));
+}
+
+proptest! {
+    #[test]
+    fn final_board_matches_commands(
+        setup in prop::collection::vec(command(), 0..10),
+        commands in prop::collection::vec(command(), 0..40),
+    ) {
+        let mut start = HexBoard::new();
+        for command in &setup {
+            start.apply(command);
+        }
+
+        let mut board = start.clone();
+        for command in &commands {
+            board.apply(command);
+        }
+        prop_assert_eq!(replay(start, &commands).final_board(), board);
+    }
+}
+
+#[cfg(feature = "serde")]
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn save_and_load(commands in prop::collection::vec(command(), 0..40)) {
+        let mut replay = replay(HexBoard::new(), &commands);
+        replay.push(9.0, ReplayEvent::Selected(vec![Axial::ZERO, Axial::new(0, 1)]));
+        let bytes = replay.to_bytes().unwrap();
+        prop_assert_eq!(&HexReplay::from_bytes(&bytes).unwrap(), &replay);
+
+        let path = std::env::temp_dir().join(format!("hex_grid_replay_{}", std::process::id()));
+        replay.save(&path).unwrap();
+        let loaded = HexReplay::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        prop_assert_eq!(loaded, replay);
+    }
+}
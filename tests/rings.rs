@@ -0,0 +1,59 @@
+//! distance ring tests
+use bevy::color::{color_difference::EuclideanDistance, Color};
+use hex_grid::{Axial, HexGridDistanceRings, HexWrap};
+use proptest::prelude::*;
+
+#[test]
+fn rings_fade_out_from_the_center() {
+    let (near, far) = (
+        Color::srgba(1.0, 0.0, 0.0, 1.0),
+        Color::srgba(0.0, 0.0, 1.0, 0.0),
+    );
+    let rings = HexGridDistanceRings::new(3).with_colors(near, far);
+    assert_eq!(rings.ring(Axial::new(1, 0), None), None);
+
+    let rings = rings.with_center(Axial::new(1, 0));
+    assert_eq!(rings.ring(Axial::new(1, 0), None), None);
+    assert_eq!(rings.ring(Axial::new(2, 0), None), Some(1));
+    assert_eq!(rings.ring(Axial::new(-2, 0), None), Some(3));
+    assert_eq!(rings.ring(Axial::new(-3, 0), None), None);
+
+    let close = |a: Color, b: Color| a.to_linear().distance(&b.to_linear()) < 1e-4;
+    assert!(close(rings.ring_color(1), near));
+    assert!(close(rings.ring_color(3), far));
+    // a single ring is drawn in the near color
+    assert!(close(
+        HexGridDistanceRings::new(1)
+            .with_colors(near, far)
+            .ring_color(1),
+        near
+    ));
+}
+
+#[test]
+fn rings_wrap_around_the_map() {
+    let wrap = HexWrap::east_west(10);
+    let rings = HexGridDistanceRings::new(2).with_center(Axial::new(0, 0));
+    assert_eq!(rings.ring(Axial::new(9, 0), None), None);
+    assert_eq!(rings.ring(Axial::new(9, 0), Some(wrap)), Some(1));
+    assert_eq!(rings.ring(Axial::new(8, 0), Some(wrap)), Some(2));
+}
+
+proptest! {
+    #[test]
+    fn ring_is_the_distance_to_the_center(
+        q in -20i32..20,
+        r in -20i32..20,
+        count in 0u32..6,
+        offset_q in -8i32..8,
+        offset_r in -8i32..8,
+    ) {
+        let center = Axial::new(q, r);
+        let cell = center + Axial::new(offset_q, offset_r);
+        let rings = HexGridDistanceRings::new(count).with_center(center);
+        let distance = center.distance(cell);
+        let expected = (distance > 0 && distance <= count).then_some(distance);
+        prop_assert_eq!(rings.ring(cell, None), expected);
+        prop_assert!(center.ring(distance).any(|c| c == cell));
+    }
+}
@@ -0,0 +1,124 @@
+//! screen space <-> cell conversion tests
+use bevy::{
+    camera::{ComputedCameraValues, RenderTargetInfo, Viewport},
+    prelude::*,
+};
+use hex_grid::{Axial, GridKind, HexGridConfig, HexGridPlane, Orientation};
+use proptest::prelude::*;
+
+// a camera rendering to a 1280x720 window at 2x scale, as the camera
+// systems would set it up
+fn camera(viewport: Option<Viewport>) -> Camera {
+    let size = viewport
+        .as_ref()
+        .map_or(Vec2::new(2560.0, 1440.0), |v| v.physical_size.as_vec2());
+    let mut camera = Camera {
+        viewport,
+        ..default()
+    };
+    camera.computed = ComputedCameraValues {
+        clip_from_view: Mat4::perspective_infinite_reverse_rh(0.8, size.x / size.y, 0.1),
+        target_info: Some(RenderTargetInfo {
+            physical_size: UVec2::new(2560, 1440),
+            scale_factor: 2.0,
+        }),
+        ..default()
+    };
+    camera
+}
+
+fn config() -> impl Strategy<Value = HexGridConfig> {
+    (
+        proptest::sample::select(GridKind::ALL.to_vec()),
+        prop_oneof![Just(Orientation::Pointy), Just(Orientation::Flat)],
+        0.5f32..2.0,
+    )
+        .prop_map(|(kind, orientation, size)| HexGridConfig {
+            kind,
+            orientation,
+            size,
+            ..default()
+        })
+}
+
+proptest! {
+    #[test]
+    fn cells_round_trip_through_the_screen(
+        config in config(),
+        q in -3i32..3,
+        r in -3i32..3,
+        tilt in -0.3f32..0.3,
+        viewport in any::<bool>(),
+    ) {
+        let viewport = viewport.then(|| Viewport {
+            physical_position: UVec2::new(400, 200),
+            physical_size: UVec2::new(1600, 1000),
+            ..default()
+        });
+        let camera = camera(viewport.clone());
+        let camera_transform = GlobalTransform::from(
+            Transform::from_xyz(2.0, 20.0, 12.0).looking_at(Vec3::ZERO, Vec3::Y),
+        );
+        let plane = HexGridPlane::from(GlobalTransform::from(
+            Transform::from_xyz(1.0, -0.5, 0.0).with_rotation(Quat::from_rotation_x(tilt)),
+        ));
+
+        let cell = Axial::new(q, r);
+        let screen = config.cell_to_screen(cell, &camera, &camera_transform, &plane).unwrap();
+        if let Some(viewport) = viewport {
+            let rect = Rect::from_corners(
+                viewport.physical_position.as_vec2() / 2.0,
+                (viewport.physical_position + viewport.physical_size).as_vec2() / 2.0,
+            );
+            prop_assert!(rect.contains(screen), "{:?} outside {:?}", screen, rect);
+        }
+        prop_assert_eq!(
+            config.screen_to_cell(screen, &camera, &camera_transform, &plane),
+            Some(cell)
+        );
+    }
+}
+
+#[test]
+fn offset_viewport_centers_the_origin_cell() {
+    // logical viewport is (200, 100) to (1000, 600)
+    let camera = camera(Some(Viewport {
+        physical_position: UVec2::new(400, 200),
+        physical_size: UVec2::new(1600, 1000),
+        ..default()
+    }));
+    let camera_transform =
+        GlobalTransform::from(Transform::from_xyz(2.0, 20.0, 12.0).looking_at(Vec3::ZERO, Vec3::Y));
+    let config = HexGridConfig::default();
+    let plane = HexGridPlane::default();
+
+    let screen = config
+        .cell_to_screen(Axial::ZERO, &camera, &camera_transform, &plane)
+        .unwrap();
+    assert!(
+        screen.distance(Vec2::new(600.0, 350.0)) < 0.01,
+        "{:?}",
+        screen
+    );
+    assert_eq!(
+        config.screen_to_cell(Vec2::new(600.0, 350.0), &camera, &camera_transform, &plane),
+        Some(Axial::ZERO)
+    );
+}
+
+#[test]
+fn looking_away_misses_the_grid() {
+    let camera = camera(None);
+    let camera_transform =
+        GlobalTransform::from(Transform::from_xyz(0.0, 5.0, 0.0).looking_to(Vec3::Y, Vec3::Z));
+    let config = HexGridConfig::default();
+    let plane = HexGridPlane::default();
+    assert_eq!(
+        config.screen_to_cell(Vec2::new(640.0, 360.0), &camera, &camera_transform, &plane),
+        None
+    );
+    assert_eq!(
+        config.cell_to_screen(Axial::ZERO, &camera, &camera_transform, &plane),
+        None
+    );
+}
@@ -0,0 +1,140 @@
+//! area of effect shape tests
+use hex_grid::{hash::hash_cell_f32, shape, shape::Brush, Axial, HexDirection};
+use proptest::prelude::*;
+
+fn axial() -> impl Strategy<Value = Axial> {
+    (-1000..1000, -1000..1000).prop_map(|(q, r)| Axial::new(q, r))
+}
+
+proptest! {
+    #[test]
+    fn blast_ring_donut(center in axial(), inner in 0u32..6, extra in 0u32..6) {
+        let outer = inner + extra;
+        let blast = shape::blast(center, outer);
+        prop_assert_eq!(blast.len() as u32, 3 * outer * (outer + 1) + 1);
+        prop_assert_eq!(shape::ring(center, outer).len() as u32, if outer == 0 { 1 } else { 6 * outer });
+
+        let donut = shape::donut(center, inner, outer);
+        prop_assert!(donut.iter().all(|c| (inner..=outer).contains(&c.distance(center))));
+        prop_assert!(donut.is_subset(&blast));
+    }
+
+    #[test]
+    fn cone_rotates(origin in axial(), dir in 0usize..6, length in 0u32..8) {
+        let cone = shape::cone(origin, HexDirection::new(dir), length);
+        let base = shape::cone(Axial::ZERO, HexDirection::new(0), length);
+        prop_assert_eq!(cone.len(), base.len());
+        for cell in base {
+            prop_assert!(cone.contains(&(cell.rotate_around(Axial::ZERO, dir as i32) + origin)));
+        }
+        // the cell straight ahead is always in the cone
+        if length > 0 {
+            prop_assert!(cone.contains(&(origin + HexDirection::new(dir).offset() * length as i32)));
+        }
+    }
+
+    #[test]
+    fn line_is_contiguous(a in axial(), b in axial()) {
+        let line = shape::line(a, b);
+        prop_assert_eq!(line.len() as u32, a.distance(b) + 1);
+        prop_assert_eq!(line[0], a);
+        prop_assert_eq!(*line.last().unwrap(), b);
+        for pair in line.windows(2) {
+            prop_assert_eq!(pair[0].distance(pair[1]), 1);
+        }
+    }
+}
+
+#[test]
+fn cone_widths() {
+    // cells at each distance in a cone of length 5
+    let cone = shape::cone(Axial::ZERO, HexDirection::new(0), 5);
+    let widths: Vec<usize> = (1..=5)
+        .map(|d| cone.iter().filter(|c| c.length() == d).count())
+        .collect();
+    assert_eq!(widths, [1, 3, 3, 5, 5]);
+}
+
+#[test]
+fn rectangle_rows() {
+    // corners in either order give the same rectangle
+    let (a, b) = (Axial::new(-1, -2), Axial::new(3, 1));
+    let rect = shape::rectangle(a, b);
+    assert_eq!(rect, shape::rectangle(b, a));
+    assert_eq!(rect.len(), 6 * 4);
+    for r in -2..=1 {
+        assert_eq!(rect.iter().filter(|c| c.r == r).count(), 6);
+    }
+    assert!(rect.contains(&a) && rect.contains(&b));
+}
+
+#[test]
+fn flood_stops_at_walls_and_limit() {
+    let inside = shape::flood(Axial::ZERO, usize::MAX, |c| c.length() < 3);
+    assert_eq!(inside, shape::blast(Axial::ZERO, 2));
+    let limited = shape::flood(Axial::ZERO, 10, |_| true);
+    assert_eq!(limited.len(), 10);
+    assert!(limited.contains(&Axial::ZERO));
+}
+
+#[test]
+fn brushes() {
+    let (origin, target) = (Axial::new(-2, 1), Axial::new(2, 0));
+    assert_eq!(Brush::Single.cells(origin, target).len(), 1);
+    assert_eq!(
+        Brush::Blob { radius: 2 }.cells(origin, target),
+        shape::blast(target, 2)
+    );
+    assert_eq!(
+        Brush::Ring { radius: 2 }.cells(origin, target),
+        shape::ring(target, 2)
+    );
+    assert_eq!(
+        Brush::Line { width: 1 }.cells(origin, target),
+        shape::beam(origin, target, 1)
+    );
+
+    // stamped along a stroke, a line is as wide as a blob
+    assert_eq!(
+        Brush::Line { width: 1 }.stroke(origin, target),
+        Brush::Blob { radius: 1 }.stroke(origin, target)
+    );
+    assert_eq!(
+        Brush::Single.stroke(origin, target).len(),
+        shape::line(origin, target).len()
+    );
+}
+
+proptest! {
+    #[test]
+    fn scatter_picks_the_same_cells(a in axial(), b in axial(), density in 0.0f32..1.0, seed: u32) {
+        let brush = Brush::Scatter { radius: 4, density, seed };
+        let (sa, sb) = (brush.cells(a, a), brush.cells(b, b));
+        prop_assert!(sa.iter().all(|c| hash_cell_f32(*c, seed) < density));
+        // where the stamps overlap, they pick the same cells
+        for cell in a.range(4).filter(|c| c.distance(b) <= 4) {
+            prop_assert_eq!(sa.contains(&cell), sb.contains(&cell));
+        }
+    }
+}
+
+#[test]
+fn scatter_density() {
+    let full = Brush::Scatter {
+        radius: 20,
+        density: 1.0,
+        seed: 7,
+    };
+    assert_eq!(
+        full.cells(Axial::ZERO, Axial::ZERO),
+        shape::blast(Axial::ZERO, 20)
+    );
+    let half = Brush::Scatter {
+        radius: 20,
+        density: 0.5,
+        seed: 7,
+    };
+    let n = half.cells(Axial::ZERO, Axial::ZERO).len() as f32;
+    let total = shape::blast(Axial::ZERO, 20).len() as f32;
+    assert!((n / total - 0.5).abs() < 0.05, "{n} of {total}");
+}
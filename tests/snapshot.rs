@@ -0,0 +1,73 @@
+//! snapshot tests
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+use hex_grid::{snapshot::downsample, HexGridSnapshot};
+use proptest::prelude::*;
+
+fn image(size: UVec2, format: TextureFormat, pixel: impl Fn(u32, u32) -> [u8; 4]) -> Image {
+    let data = (0..size.y)
+        .flat_map(|y| (0..size.x).map(move |x| (x, y)))
+        .flat_map(|(x, y)| pixel(x, y))
+        .collect();
+    Image::new(
+        Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        format,
+        RenderAssetUsages::default(),
+    )
+}
+
+#[test]
+fn target_is_supersampled() {
+    let snapshot = HexGridSnapshot::new(UVec2::new(320, 180));
+    assert_eq!(snapshot.target_size(), UVec2::new(640, 360));
+    let snapshot = snapshot.with_supersampling(0);
+    assert_eq!(snapshot.target_size(), UVec2::new(320, 180));
+}
+
+#[test]
+fn downsample_averages_in_linear_color() {
+    // black & white checkerboard
+    let checks = image(UVec2::new(4, 6), TextureFormat::Rgba8UnormSrgb, |x, y| {
+        let c = if (x + y) % 2 == 0 { 255 } else { 0 };
+        [c, c, c, 255]
+    });
+    let small = downsample(&checks, 2).unwrap();
+    assert_eq!(small.size(), UVec2::new(2, 3));
+    let gray = Srgba::from(LinearRgba::gray(0.5)).to_u8_array();
+    for chunk in small.data.as_ref().unwrap().chunks(4) {
+        assert_eq!(chunk, gray);
+    }
+
+    // pixels past the last whole block are dropped
+    assert_eq!(downsample(&checks, 3).unwrap().size(), UVec2::new(1, 2));
+    assert!(downsample(&checks, 5).is_none());
+    let linear = image(UVec2::new(2, 2), TextureFormat::Rgba8Unorm, |_, _| [0; 4]);
+    assert!(downsample(&linear, 2).is_none());
+}
+
+proptest! {
+    // an image of a single color downsamples to that color
+    #[test]
+    fn flat_color_is_kept(
+        color in prop::array::uniform4(any::<u8>()),
+        factor in 1u32..4,
+        size in (1u32..5, 1u32..5),
+    ) {
+        let size = UVec2::new(size.0, size.1) * factor;
+        let flat = image(size, TextureFormat::Rgba8UnormSrgb, |_, _| color);
+        let small = downsample(&flat, factor).unwrap();
+        prop_assert_eq!(small.size(), size / factor);
+        for chunk in small.data.as_ref().unwrap().chunks(4) {
+            prop_assert_eq!(chunk, color);
+        }
+    }
+}
@@ -0,0 +1,146 @@
+//! hex sphere tests
+use bevy::{
+    mesh::{Indices, VertexAttributeValues},
+    prelude::*,
+};
+use hex_grid::{
+    sphere::{HexSphere, SphereCell},
+    Axial, HexMap,
+};
+use proptest::prelude::*;
+use std::collections::HashSet;
+
+fn direction() -> impl Strategy<Value = Vec3> {
+    (-1.0f32..1.0, -1.0f32..1.0, -1.0f32..1.0)
+        .prop_filter("not zero", |&(x, y, z)| Vec3::new(x, y, z).length() > 0.01)
+        .prop_map(|(x, y, z)| Vec3::new(x, y, z).normalize())
+}
+
+#[test]
+fn goldberg_cell_counts() {
+    for frequency in [1, 2, 3, 8] {
+        let sphere = HexSphere::new(frequency);
+        assert_eq!(sphere.len(), 10 * (frequency * frequency) as usize + 2);
+        let cells: HashSet<SphereCell> = sphere.cells().collect();
+        assert_eq!(cells.len(), sphere.len());
+
+        let pentagons = sphere.cells().filter(|&c| sphere.is_pentagon(c)).count();
+        assert_eq!(pentagons, 12);
+        assert!(sphere.is_pentagon(SphereCell::NORTH));
+        assert!(sphere.is_pentagon(SphereCell::SOUTH));
+        for cell in sphere.cells() {
+            let count = sphere.neighbors(cell).count();
+            assert!(count == 5 || count == 6, "{cell:?} has {count} neighbors");
+        }
+    }
+}
+
+#[test]
+fn neighbors_are_mutual_and_near() {
+    let sphere = HexSphere::new(6);
+    let step = sphere.angle(
+        SphereCell::NORTH,
+        sphere.neighbors(SphereCell::NORTH).next().unwrap(),
+    );
+    for cell in sphere.cells() {
+        let neighbors: HashSet<SphereCell> = sphere.neighbors(cell).collect();
+        assert_eq!(neighbors.len(), sphere.neighbors(cell).count());
+        for n in neighbors {
+            assert!(sphere.neighbors(n).any(|c| c == cell));
+            // cells are within a factor of two of the same size
+            let angle = sphere.angle(cell, n).unwrap();
+            assert!(angle < step.unwrap() * 2.0 && angle > step.unwrap() * 0.5);
+        }
+    }
+}
+
+#[test]
+fn corners_wind_around_the_center() {
+    let sphere = HexSphere::new(4);
+    for cell in sphere.cells() {
+        let center = sphere.center(cell).unwrap();
+        let corners = sphere.corners(cell);
+        assert_eq!(corners.len(), sphere.neighbors(cell).count());
+        for (i, &a) in corners.iter().enumerate() {
+            let b = corners[(i + 1) % corners.len()];
+            // counter-clockwise seen from outside
+            assert!((a - center).cross(b - center).dot(center) > 0.0, "{cell:?}");
+            assert!((a.length() - 1.0).abs() < 1e-5);
+        }
+    }
+}
+
+#[test]
+fn axial_layout_round_trips() {
+    let sphere = HexSphere::new(5);
+    let mut map = HexMap::new();
+    let mut seen = HashSet::new();
+    for (i, cell) in sphere.cells().enumerate() {
+        let axial = sphere.to_axial(cell);
+        assert!(seen.insert(axial));
+        assert_eq!(sphere.from_axial(axial), Some(cell));
+        map.insert(axial, i);
+    }
+    assert_eq!(sphere.from_axial(Axial::new(-1, 0)), None);
+    assert_eq!(sphere.from_axial(Axial::new(0, 5)), None);
+    assert_eq!(sphere.from_axial(Axial::new(51, 0)), None);
+    assert!(sphere.values(&map).enumerate().all(|(i, (_, &v))| v == i));
+}
+
+#[test]
+fn mesh_has_a_fan_per_cell() {
+    let sphere = HexSphere::new(3);
+    let mesh = sphere.mesh(2.0);
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        panic!("no positions");
+    };
+    let corners: usize = sphere.cells().map(|c| sphere.corners(c).len()).sum();
+    assert_eq!(positions.len(), corners);
+    assert!(positions
+        .iter()
+        .all(|p| (Vec3::from_array(*p).length() - 2.0).abs() < 1e-4));
+    let Some(Indices::U32(indices)) = mesh.indices() else {
+        panic!("no indices");
+    };
+    // two triangles fewer than corners, per cell
+    assert_eq!(indices.len(), (corners - 2 * sphere.len()) * 3);
+    for tri in indices.chunks(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| Vec3::from_array(positions[tri[i] as usize]));
+        assert!((b - a).cross(c - a).dot(a) > 0.0, "facing in");
+    }
+}
+
+#[test]
+fn rays_pick_the_cell_they_hit() {
+    let sphere = HexSphere::new(8);
+    let transform =
+        GlobalTransform::from(Transform::from_xyz(10.0, 0.0, 0.0).with_scale(Vec3::splat(2.0)));
+    for cell in sphere.cells() {
+        let center = sphere.center(cell).unwrap();
+        let world = transform.transform_point(center * 3.0);
+        let ray = Ray3d::new(world + center * 50.0, Dir3::new(-center).unwrap());
+        assert_eq!(sphere.ray_cell(ray, &transform, 3.0), Some(cell));
+    }
+    let miss = Ray3d::new(Vec3::new(0.0, 20.0, 0.0), Dir3::X);
+    assert_eq!(sphere.ray_cell(miss, &transform, 3.0), None);
+}
+
+proptest! {
+    #[test]
+    fn cell_at_is_nearest_center(dir in direction(), frequency in 1u32..12) {
+        let sphere = HexSphere::new(frequency);
+        let nearest = sphere
+            .cells()
+            .max_by(|a, b| {
+                let a = sphere.center(*a).unwrap().dot(dir);
+                let b = sphere.center(*b).unwrap().dot(dir);
+                a.total_cmp(&b)
+            })
+            .unwrap();
+        let found = sphere.cell_at(dir);
+        let (a, b) = (sphere.center(found).unwrap().dot(dir), sphere.center(nearest).unwrap().dot(dir));
+        prop_assert!((a - b).abs() < 1e-6, "{:?} {:?}", found, nearest);
+    }
+}
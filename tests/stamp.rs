@@ -0,0 +1,119 @@
+//! map region copy & stamp tests
+use hex_grid::{editor::HexEditor, Axial, HexDirection, HexMap, TerrainId};
+use proptest::prelude::*;
+
+fn axial() -> impl Strategy<Value = Axial> {
+    (-30i32..30, -30i32..30).prop_map(|(q, r)| Axial::new(q, r))
+}
+
+fn map() -> impl Strategy<Value = HexMap<u8>> {
+    prop::collection::vec((axial(), any::<u8>()), 0..60).prop_map(HexMap::from_iter)
+}
+
+#[test]
+fn rotation() {
+    // a bar of three cells, east to west through the origin
+    let mut map = HexMap::new();
+    map.insert(Axial::new(-1, 0), 1);
+    map.insert(Axial::ZERO, 2);
+    map.insert(Axial::new(1, 0), 3);
+    let stamp = map.extract_region(map.cells().collect::<Vec<_>>());
+    assert_eq!(stamp.len(), 3);
+
+    let anchor = Axial::new(10, -4);
+    let mut pasted = HexMap::new();
+    pasted.apply_stamp(&stamp, anchor, 1);
+    assert_eq!(pasted.get(anchor), Some(&2));
+    for (cell, value) in [(Axial::new(-1, 0), 1), (Axial::new(1, 0), 3)] {
+        let turned = cell.rotate_around(Axial::ZERO, 1) + anchor;
+        assert_eq!(pasted.get(turned), Some(&value));
+    }
+}
+
+#[test]
+fn empty_cells() {
+    let mut map = HexMap::new();
+    map.insert(Axial::ZERO, 1);
+    let stamp = map.extract_region(Axial::ZERO.range(1));
+
+    let mut target: HexMap<u8> = Axial::new(5, 5).range(2).map(|c| (c, 9)).collect();
+    let mut kept = target.clone();
+    target.apply_stamp(&stamp, Axial::new(5, 5), 0);
+    assert_eq!(target.len(), 19 - 6);
+    assert_eq!(target.get(Axial::new(5, 5)), Some(&1));
+
+    let stamp = stamp.without_empty();
+    assert_eq!(stamp.len(), 1);
+    kept.apply_stamp(&stamp, Axial::new(5, 5), 0);
+    assert_eq!(kept.len(), 19);
+    assert_eq!(kept.get(Axial::new(5, 5)), Some(&1));
+}
+
+#[test]
+fn editor_stamp() {
+    let mut editor = HexEditor::default();
+    editor.terrain = TerrainId(2);
+    editor.paint(Axial::ZERO.range(1));
+    let stamp = editor.document().extract_region(Axial::ZERO.range(1));
+    editor.stamp(&stamp, Axial::new(8, 0), 3);
+    assert_eq!(editor.document().len(), 14);
+    // the paste is one change
+    assert!(editor.undo());
+    assert_eq!(editor.document().len(), 7);
+}
+
+proptest! {
+    #[test]
+    fn copy_in_place(map in map(), center in axial(), radius in 0u32..8) {
+        let region: Vec<Axial> = center.range(radius).collect();
+        let stamp = map.extract_region(region.iter().copied());
+        prop_assert_eq!(stamp.len(), region.len());
+
+        // the middle of a hexagon is its center
+        let mut copy = map.clone();
+        copy.apply_stamp(&stamp, center, 0);
+        prop_assert_eq!(&copy, &map);
+        // six turns is all the way around
+        copy.apply_stamp(&stamp, center, 6);
+        prop_assert_eq!(&copy, &map);
+    }
+
+    #[test]
+    fn turns_compose(map in map(), anchor in axial(), a in -6i32..6, b in -6i32..6) {
+        let stamp = map.extract_region(map.cells().collect::<Vec<_>>());
+        let mut once = HexMap::new();
+        once.apply_stamp(&stamp, Axial::ZERO, a);
+        let mut twice = HexMap::new();
+        twice.apply_stamp(&once.extract_region(once.cells().collect::<Vec<_>>()), anchor, b);
+
+        let mut direct = HexMap::new();
+        direct.apply_stamp(&stamp, anchor, a + b);
+        // the middle of the turned region may round to another cell, so
+        // compare shapes by their offsets from the first cell
+        let offsets = |map: &HexMap<u8>| {
+            let mut cells: Vec<(Axial, u8)> = map.iter().map(|(c, &v)| (c, v)).collect();
+            cells.sort();
+            let first = cells.first().map_or(Axial::ZERO, |&(c, _)| c);
+            cells.into_iter().map(|(c, v)| (c - first, v)).collect::<Vec<_>>()
+        };
+        prop_assert_eq!(offsets(&twice), offsets(&direct));
+    }
+
+    #[test]
+    fn directions_turn_with_the_stamp(cell in axial(), dir in 0usize..6, rotation in -12i32..12) {
+        // an arrow on `cell` pointing at its neighbor
+        let dir = HexDirection::new(dir);
+        let mut map = HexMap::new();
+        map.insert(cell, dir);
+        map.insert(cell + dir.offset(), dir);
+        let stamp = map.extract_region(map.cells().collect::<Vec<_>>());
+
+        let mut pasted = HexMap::new();
+        pasted.apply_stamp_with(&stamp, Axial::ZERO, rotation, |d| d.rotate(rotation));
+        for (cell, &d) in pasted.iter() {
+            let next = cell + d.offset();
+            let prev = cell + d.opposite().offset();
+            prop_assert!(pasted.contains(next) || pasted.contains(prev));
+        }
+    }
+}
@@ -0,0 +1,49 @@
+//! grid style tests
+#![cfg(feature = "serde")]
+use bevy::prelude::*;
+use hex_grid::{
+    style::parse_style, HexGridConfig, HexGridStyle, LineAntialiasing, LineWidthMode,
+    SuperHexBorders,
+};
+
+#[test]
+fn parse_example_style() {
+    let text = std::fs::read_to_string("assets/board.hexgrid.ron").unwrap();
+    let style = parse_style(&text).unwrap();
+    assert_eq!(style.line_width_mode, Some(LineWidthMode::Pixels(1.5)));
+    assert_eq!(style.antialiasing, Some(LineAntialiasing::Fwidth));
+    assert!(style.parity_tint.is_some());
+    assert_eq!(style.line_width, None);
+}
+
+#[test]
+fn unset_fields_keep_config() {
+    let style = parse_style("(line_width: 0.1, superhex_borders: (radius: 3, color: LinearRgba((red: 1.0, green: 1.0, blue: 1.0, alpha: 1.0)), width: 0.05))").unwrap();
+    let mut config = HexGridConfig::default();
+    style.apply(&mut config);
+    let base = HexGridConfig::default();
+    assert_eq!(config.line_width, 0.1);
+    assert_eq!(config.superhex_borders.map(|s| s.radius), Some(3));
+    assert_eq!(config.line_color, base.line_color);
+    assert_eq!(config.cursor_line_width, base.cursor_line_width);
+
+    // an empty style changes nothing
+    let mut config = HexGridConfig {
+        superhex_borders: Some(SuperHexBorders::default()),
+        ..default()
+    };
+    HexGridStyle::default().apply(&mut config);
+    assert_eq!(config.superhex_borders, Some(SuperHexBorders::default()));
+    assert_eq!(parse_style("()").unwrap(), HexGridStyle::default());
+}
+
+#[test]
+fn round_trip() {
+    let style = HexGridStyle {
+        line_color: Some(Color::srgb(0.1, 0.2, 0.3)),
+        cursor_edge_distance: Some(0.2),
+        ..default()
+    };
+    let text = ron::to_string(&style).unwrap();
+    assert_eq!(parse_style(&text).unwrap(), style);
+}
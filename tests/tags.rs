@@ -0,0 +1,121 @@
+//! cell tag tests
+use hex_grid::{tags::TagQuery, Axial, HexTags};
+use proptest::prelude::*;
+use std::collections::HashSet;
+
+const NAMES: [&str; 5] = ["water", "deep", "shore", "cliff", "spawn"];
+
+#[derive(Clone, Copy)]
+enum Terrain {
+    Water,
+    Deep,
+}
+
+impl AsRef<str> for Terrain {
+    fn as_ref(&self) -> &str {
+        match self {
+            Terrain::Water => "water",
+            Terrain::Deep => "deep",
+        }
+    }
+}
+
+#[test]
+fn tag_and_untag() {
+    let mut tags = HexTags::new();
+    let cell = Axial::new(2, -1);
+    assert!(tags.tag(cell, "water"));
+    assert!(!tags.tag(cell, "water"));
+    assert!(tags.tag(cell, Terrain::Deep));
+    assert!(tags.has(cell, Terrain::Water) && tags.has(cell, "deep"));
+    assert!(!tags.has(cell, "shore") && !tags.has(Axial::ZERO, "water"));
+    assert_eq!(tags.tags(cell).collect::<Vec<_>>(), ["water", "deep"]);
+
+    assert!(tags.untag(cell, "water"));
+    assert!(!tags.untag(cell, "water"));
+    assert!(!tags.untag(cell, "never used"));
+    assert_eq!(tags.tags(cell).collect::<Vec<_>>(), ["deep"]);
+    tags.clear_cell(cell);
+    assert_eq!(tags.cells().count(), 0);
+    assert_eq!(tags.names().collect::<Vec<_>>(), ["water", "deep"]);
+}
+
+#[test]
+fn queries() {
+    let mut tags = HexTags::new();
+    let (a, b, c) = (Axial::new(0, 0), Axial::new(1, 0), Axial::new(2, 0));
+    for (cell, names) in [
+        (a, &["water", "deep"][..]),
+        (b, &["water"]),
+        (c, &["shore", "cliff"]),
+    ] {
+        for name in names {
+            tags.tag(cell, name);
+        }
+    }
+    let found = |cells: Vec<Axial>| cells.into_iter().collect::<HashSet<_>>();
+
+    assert_eq!(
+        found(tags.cells_with("water").collect()),
+        HashSet::from([a, b])
+    );
+    assert_eq!(
+        found(tags.cells_with_all(["water", "deep"]).collect()),
+        HashSet::from([a])
+    );
+    assert_eq!(
+        found(tags.cells_with_any(["deep", "shore"]).collect()),
+        HashSet::from([a, c])
+    );
+    // unknown names match nothing, unless only ruled out
+    assert_eq!(tags.cells_with_all(["water", "lava"]).count(), 0);
+    assert_eq!(tags.cells_with_any(["lava"]).count(), 0);
+    let query = TagQuery::new()
+        .any(["water", "shore"])
+        .none(["deep", "lava"]);
+    assert_eq!(
+        found(tags.cells_matching(&query).collect()),
+        HashSet::from([b, c])
+    );
+    assert!(tags.matches(b, &query) && !tags.matches(a, &query));
+}
+
+#[test]
+#[should_panic]
+fn too_many_names() {
+    let mut tags = HexTags::new();
+    for i in 0..=HexTags::MAX_TAGS {
+        tags.tag(Axial::ZERO, format!("tag {i}"));
+    }
+}
+
+proptest! {
+    #[test]
+    fn queries_match_each_cell(
+        cells in prop::collection::vec(((-8i32..8, -8i32..8), prop::collection::vec(0usize..5, 0..4)), 0..30),
+        all in prop::collection::vec(0usize..5, 0..3),
+        any in prop::collection::vec(0usize..5, 0..3),
+        none in prop::collection::vec(0usize..5, 0..3),
+    ) {
+        let mut tags = HexTags::new();
+        for ((q, r), names) in &cells {
+            for &n in names {
+                tags.tag(Axial::new(*q, *r), NAMES[n]);
+            }
+        }
+        let query = TagQuery::new()
+            .all(all.iter().map(|&n| NAMES[n]))
+            .any(any.iter().map(|&n| NAMES[n]))
+            .none(none.iter().map(|&n| NAMES[n]));
+        let found: HashSet<Axial> = tags.cells_matching(&query).collect();
+        for cell in tags.cells() {
+            let has = |n: &usize| tags.has(cell, NAMES[*n]);
+            let expected = all.iter().all(has)
+                && (any.is_empty() || any.iter().any(has))
+                && !none.iter().any(has);
+            prop_assert_eq!(found.contains(&cell), expected);
+            prop_assert_eq!(tags.matches(cell, &query), expected);
+        }
+        prop_assert!(found.iter().all(|&cell| tags.cells().any(|c| c == cell)));
+    }
+}
@@ -0,0 +1,78 @@
+//! terrain blending tests
+use bevy::prelude::*;
+use hex_grid::{Axial, HexGridDecalAtlas, HexGridTerrain, Terrain, TerrainId};
+use proptest::prelude::*;
+
+// a terrain of each priority, with cells (0, 0) & (1, 0) of the given kinds
+fn two_cells(a: u8, b: u8, priorities: &[i32], blend_width: f32) -> HexGridTerrain {
+    let mut atlas = HexGridDecalAtlas::default();
+    let mut terrain = HexGridTerrain::new(
+        priorities
+            .iter()
+            .map(|&p| Terrain::new(atlas.register(Handle::default())).with_priority(p)),
+    )
+    .with_blend_width(blend_width);
+    terrain.cells.insert(Axial::new(0, 0), TerrainId(a));
+    terrain.cells.insert(Axial::new(1, 0), TerrainId(b));
+    terrain
+}
+
+proptest! {
+    #[test]
+    fn blend_is_continuous_across_edges(
+        priorities in proptest::collection::vec(-2i32..2, 1..4),
+        a in 0u8..4,
+        b in 0u8..4,
+        blend_width in 0.01f32..0.5,
+    ) {
+        let (a, b) = (a % priorities.len() as u8, b % priorities.len() as u8);
+        let terrain = two_cells(a, b, &priorities, blend_width);
+        let (left, right) = (Axial::new(0, 0), Axial::new(1, 0));
+
+        // both sides show the same mix of the two terrains at the edge
+        let wa = terrain.blend_weight(left, right, 0.0);
+        let wb = terrain.blend_weight(right, left, 0.0);
+        prop_assert!((wa + wb - 1.0).abs() < 1e-5, "{} + {}", wa, wb);
+
+        // only the lower priority side is covered
+        let (pa, pb) = (priorities[a as usize], priorities[b as usize]);
+        if pa > pb {
+            prop_assert_eq!(wa, 0.0);
+        }
+
+        // and each cell is its own terrain past the blend width
+        prop_assert_eq!(terrain.blend_weight(left, right, blend_width), 0.0);
+        prop_assert_eq!(terrain.blend_weight(right, left, blend_width), 0.0);
+    }
+
+    #[test]
+    fn blend_fades_from_edge(edge_dist in 0.0f32..0.2, step in 0.0f32..0.2) {
+        let terrain = two_cells(0, 1, &[0, 1], 0.2);
+        let (low, high) = (Axial::new(0, 0), Axial::new(1, 0));
+        let near = terrain.blend_weight(low, high, edge_dist);
+        let far = terrain.blend_weight(low, high, edge_dist + step);
+        prop_assert!(far <= near);
+        prop_assert!((0.0..=1.0).contains(&near));
+    }
+}
+
+#[test]
+fn hard_edges() {
+    let terrain = two_cells(0, 1, &[0, 1], 0.0);
+    assert_eq!(
+        terrain.blend_weight(Axial::new(0, 0), Axial::new(1, 0), 0.0),
+        0.0
+    );
+
+    // unknown kinds & cells without terrain are never blended
+    let terrain = two_cells(0, 5, &[0, 1], 0.2);
+    assert!(terrain.get(Axial::new(1, 0)).is_none());
+    assert_eq!(
+        terrain.blend_weight(Axial::new(0, 0), Axial::new(1, 0), 0.0),
+        0.0
+    );
+    assert_eq!(
+        terrain.blend_weight(Axial::new(0, 0), Axial::new(0, 1), 0.0),
+        0.0
+    );
+}
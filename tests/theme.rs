@@ -0,0 +1,128 @@
+//! grid theme tests
+use bevy::{color::color_difference::EuclideanDistance, prelude::*};
+use hex_grid::{
+    theme::crossfade, HexGridConfig, HexGridStyle, HexGridTheme, HexGridThemeCommands,
+    HexGridThemeFade, HexGridThemes, LineWidthMode, SuperHexBorders,
+};
+
+#[test]
+fn built_in_themes() {
+    let themes = HexGridThemes::default();
+    for theme in [
+        HexGridTheme::BLUEPRINT,
+        HexGridTheme::TACTICAL,
+        HexGridTheme::MINIMAL,
+        HexGridTheme::NEON,
+    ] {
+        assert!(themes.get(&theme).is_some(), "{theme:?}");
+    }
+    assert_eq!(themes.iter().count(), 4);
+
+    let base = HexGridConfig::default();
+    let neon = themes.apply(&base, Some(&HexGridTheme::NEON));
+    assert_ne!(neon.line_color, base.line_color);
+    assert_eq!(neon.size, base.size);
+    assert_eq!(themes.apply(&base, None).line_color, base.line_color);
+}
+
+#[test]
+fn register_custom_theme() {
+    let mut themes = HexGridThemes::default();
+    let parchment = HexGridTheme::new("parchment");
+    let style = HexGridStyle {
+        line_width: Some(0.2),
+        ..default()
+    };
+    assert_eq!(themes.register(parchment.clone(), style.clone()), None);
+    assert_eq!(themes.get(&parchment), Some(&style));
+    let config = themes.apply(&HexGridConfig::default(), Some(&parchment));
+    assert_eq!(config.line_width, 0.2);
+
+    // unknown themes change nothing
+    let missing = themes.apply(&HexGridConfig::default(), Some(&HexGridTheme::new("nope")));
+    assert_eq!(missing.line_width, HexGridConfig::default().line_width);
+
+    // built-in themes can be replaced
+    assert!(themes.register(HexGridTheme::NEON, style).is_some());
+}
+
+#[test]
+fn crossfade_endpoints() {
+    let themes = HexGridThemes::default();
+    let base = HexGridConfig::default();
+    let from = themes.apply(&base, Some(&HexGridTheme::BLUEPRINT));
+    let to = themes.apply(&base, Some(&HexGridTheme::TACTICAL));
+
+    let start = crossfade(&from, &to, 0.0);
+    assert_eq!(start.line_width, from.line_width);
+    assert_eq!(start.antialiasing, from.antialiasing);
+    assert!(
+        start
+            .line_color
+            .to_linear()
+            .distance(&from.line_color.to_linear())
+            < 1e-5
+    );
+
+    let end = crossfade(&from, &to, 1.0);
+    assert_eq!(end.line_width, to.line_width);
+    assert_eq!(end.antialiasing, to.antialiasing);
+    assert!(
+        end.line_color
+            .to_linear()
+            .distance(&to.line_color.to_linear())
+            < 1e-5
+    );
+
+    let mid = crossfade(&from, &to, 0.5);
+    assert!((mid.line_width - (from.line_width + to.line_width) / 2.0).abs() < 1e-6);
+}
+
+#[test]
+fn crossfade_fades_one_sided_parts() {
+    let from = HexGridConfig {
+        parity_tint: Some([Color::WHITE; 3]),
+        line_width_mode: LineWidthMode::Pixels(1.0),
+        ..default()
+    };
+    let to = HexGridConfig {
+        superhex_borders: Some(SuperHexBorders::default()),
+        line_width_mode: LineWidthMode::Pixels(3.0),
+        ..default()
+    };
+    let mid = crossfade(&from, &to, 0.5);
+    assert_eq!(mid.line_width_mode, LineWidthMode::Pixels(2.0));
+    let tint = mid.parity_tint.unwrap();
+    assert!((tint[0].alpha() - 0.5).abs() < 1e-5);
+    let borders = mid.superhex_borders.unwrap();
+    let alpha = SuperHexBorders::default().color.alpha();
+    assert!((borders.color.alpha() - alpha / 2.0).abs() < 1e-5);
+
+    let end = crossfade(&from, &to, 1.0);
+    assert_eq!(end.parity_tint.unwrap()[0].alpha(), 0.0);
+}
+
+#[test]
+fn crossfade_theme_command() {
+    let mut world = World::new();
+    let grid = world
+        .spawn(HexGridConfig {
+            theme: Some(HexGridTheme::MINIMAL),
+            ..default()
+        })
+        .id();
+    world
+        .commands()
+        .entity(grid)
+        .crossfade_theme(Some(HexGridTheme::NEON), 0.5);
+    world.flush();
+
+    let entity = world.entity(grid);
+    assert_eq!(
+        entity.get::<HexGridConfig>().unwrap().theme,
+        Some(HexGridTheme::NEON)
+    );
+    let fade = entity.get::<HexGridThemeFade>().unwrap();
+    assert_eq!(fade.from(), Some(&HexGridTheme::MINIMAL));
+    assert_eq!(fade.progress(), 0.0);
+}
@@ -0,0 +1,46 @@
+//! grid time tests
+use hex_grid::GridTime;
+use proptest::prelude::*;
+
+proptest! {
+    #[test]
+    fn advances_scaled_unless_paused(
+        steps in proptest::collection::vec((0.0f32..0.1, any::<bool>()), 0..50),
+        scale in 0.0f32..4.0,
+    ) {
+        let mut time = GridTime::default();
+        time.set_scale(scale);
+        let mut expected = 0.0;
+        for (delta, paused) in steps {
+            if paused {
+                time.pause();
+            } else {
+                time.unpause();
+            }
+            time.advance(delta);
+            let step = if paused { 0.0 } else { delta * scale };
+            prop_assert_eq!(time.delta_secs(), step);
+            expected += step;
+        }
+        prop_assert!((time.elapsed_secs() - expected).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn toggle() {
+    let mut time = GridTime::default();
+    assert!(!time.is_paused());
+    time.toggle();
+    assert!(time.is_paused());
+    time.advance(1.0);
+    assert_eq!(time.elapsed_secs(), 0.0);
+    time.toggle();
+    time.advance(1.0);
+    assert_eq!(time.elapsed_secs(), 1.0);
+}
+
+#[test]
+#[should_panic]
+fn negative_scale() {
+    GridTime::default().set_scale(-1.0);
+}
@@ -0,0 +1,57 @@
+//! weather overlay tests
+use bevy::color::Color;
+use hex_grid::{Axial, HexGridWeather, Weather, WeatherId, WeatherPattern};
+use proptest::prelude::*;
+
+#[test]
+fn regions_get_their_weather() {
+    let storm = Weather::new(WeatherPattern::Clouds).with_color(Color::srgba(0.2, 0.2, 0.3, 0.7));
+    let scan = Weather::new(WeatherPattern::Scanlines).with_speed(2.0);
+    let mut weather = HexGridWeather::new([storm, scan]);
+
+    weather.set_region(Axial::ZERO.range(2), WeatherId(0));
+    weather.set_region(Axial::new(5, 0).range(1), WeatherId(1));
+    assert_eq!(weather.get(Axial::new(1, 1)), Some(&storm));
+    assert_eq!(weather.get(Axial::new(6, -1)), Some(&scan));
+    assert_eq!(weather.get(Axial::new(10, 0)), None);
+
+    // a later region replaces the weather where they overlap
+    weather.set_region(Axial::new(2, 0).range(1), WeatherId(1));
+    assert_eq!(weather.get(Axial::new(2, 0)), Some(&scan));
+    assert_eq!(weather.get(Axial::new(-2, 0)), Some(&storm));
+
+    weather.clear_region(Axial::ZERO.range(2));
+    assert_eq!(weather.get(Axial::ZERO), None);
+    assert_eq!(weather.get(Axial::new(3, 0)), Some(&scan));
+}
+
+#[test]
+fn unknown_kinds_have_no_weather() {
+    let kinds = vec![Weather::new(WeatherPattern::Static); HexGridWeather::MAX_KINDS + 1];
+    let mut weather = HexGridWeather::new(kinds);
+    weather.set_region([Axial::ZERO], WeatherId(HexGridWeather::MAX_KINDS as u8));
+    assert_eq!(weather.get(Axial::ZERO), None);
+
+    let mut weather = HexGridWeather::new([Weather::new(WeatherPattern::Static)]);
+    weather.set_region([Axial::ZERO], WeatherId(1));
+    assert_eq!(weather.get(Axial::ZERO), None);
+}
+
+proptest! {
+    #[test]
+    fn region_covers_exactly_its_cells(
+        q in -20i32..20,
+        r in -20i32..20,
+        radius in 0u32..4,
+        probe_q in -25i32..25,
+        probe_r in -25i32..25,
+    ) {
+        let center = Axial::new(q, r);
+        let mut weather = HexGridWeather::new([Weather::new(WeatherPattern::Clouds)]);
+        weather.set_region(center.range(radius), WeatherId(0));
+
+        let probe = Axial::new(probe_q, probe_r);
+        let inside = probe.distance(center) <= radius;
+        prop_assert_eq!(weather.get(probe).is_some(), inside);
+    }
+}
@@ -0,0 +1,71 @@
+//! wave function collapse tests
+use hex_grid::{
+    wfc::{WfcRules, WfcTile},
+    Axial, HexDirection,
+};
+use proptest::prelude::*;
+
+const WATER: WfcTile = WfcTile(0);
+const SAND: WfcTile = WfcTile(1);
+const GRASS: WfcTile = WfcTile(2);
+
+// water only touches sand, sand only touches grass
+fn coast() -> WfcRules {
+    let mut rules = WfcRules::new([1.0, 0.5, 2.0]);
+    rules
+        .allow_all(WATER, WATER)
+        .allow_all(WATER, SAND)
+        .allow_all(SAND, SAND)
+        .allow_all(SAND, GRASS)
+        .allow_all(GRASS, GRASS);
+    rules
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn neighbors_allowed(seed in any::<u64>()) {
+        let rules = coast();
+        let map = rules.generate(Axial::ZERO.range(10), seed).unwrap();
+        prop_assert_eq!(map.len(), Axial::ZERO.range(10).count());
+        for (cell, &tile) in map.iter() {
+            for dir in HexDirection::ALL {
+                if let Some(&other) = map.get(cell + dir) {
+                    prop_assert!(rules.is_allowed(tile, dir, other));
+                }
+            }
+        }
+
+        // same seed, same map
+        prop_assert_eq!(rules.generate(Axial::ZERO.range(10), seed), Some(map));
+    }
+}
+
+#[test]
+fn directional_rules() {
+    // stripes of constant q: stepping along r keeps the tile, stepping along
+    // q or s changes it
+    let mut rules = WfcRules::new([1.0, 1.0]);
+    for dir in [HexDirection::East, HexDirection::NorthWest] {
+        rules
+            .allow(WfcTile(0), dir, WfcTile(1))
+            .allow(WfcTile(1), dir, WfcTile(0));
+    }
+    rules
+        .allow(WfcTile(0), HexDirection::NorthEast, WfcTile(0))
+        .allow(WfcTile(1), HexDirection::NorthEast, WfcTile(1));
+    let map = rules.generate(Axial::ZERO.range(6), 42).unwrap();
+    let parity = map.get(Axial::ZERO).unwrap().0 as i32;
+    for (cell, &tile) in map.iter() {
+        assert_eq!(tile.0 as i32, (cell.q + parity).rem_euclid(2));
+    }
+}
+
+#[test]
+fn contradiction() {
+    // a lone tile that can't touch anything can't fill two neighbors
+    let rules = WfcRules::new([1.0]);
+    assert!(rules.generate([Axial::ZERO], 1).is_some());
+    assert_eq!(rules.generate([Axial::ZERO, Axial::new(1, 0)], 1), None);
+}
@@ -0,0 +1,65 @@
+//! wrapped map tests
+use hex_grid::{Axial, HexWrap};
+use proptest::prelude::*;
+
+fn axial() -> impl Strategy<Value = Axial> {
+    (-200i32..200, -200i32..200).prop_map(|(q, r)| Axial::new(q, r))
+}
+
+fn wrap() -> impl Strategy<Value = HexWrap> {
+    (3u32..40, 0u32..20).prop_map(|(width, half)| HexWrap::toroidal(width, half * 2))
+}
+
+// column in the offset layout used for wrapping
+fn col(cell: Axial) -> i32 {
+    cell.q + cell.r.div_euclid(2)
+}
+
+proptest! {
+    #[test]
+    fn wrapped_cells_are_canonical(wrap in wrap(), cell in axial()) {
+        let wrapped = wrap.wrap(cell);
+        prop_assert!((0..wrap.width() as i32).contains(&col(wrapped)));
+        if wrap.height() > 0 {
+            prop_assert!((0..wrap.height() as i32).contains(&wrapped.r));
+        } else {
+            prop_assert_eq!(wrapped.r, cell.r);
+        }
+        prop_assert_eq!(wrap.wrap(wrapped), wrapped);
+    }
+
+    #[test]
+    fn copies_wrap_together(wrap in wrap(), cell in axial(), i in -3i32..3, j in -3i32..3) {
+        let (w, h) = (wrap.width() as i32, wrap.height() as i32);
+        let copy = cell + Axial::new(w, 0) * i + Axial::new(-h / 2, h) * j;
+        prop_assert!(wrap.same_cell(cell, copy));
+        prop_assert_eq!(wrap.distance(cell, copy), 0);
+    }
+
+    #[test]
+    fn wrapped_distance(wrap in wrap(), a in axial(), b in axial()) {
+        let distance = wrap.distance(a, b);
+        prop_assert_eq!(distance, wrap.distance(b, a));
+        prop_assert!(distance <= wrap.wrap(a).distance(wrap.wrap(b)));
+        for n in wrap.neighbors(a) {
+            prop_assert_eq!(n, wrap.wrap(n));
+            prop_assert!(wrap.distance(a, n) <= 1);
+            prop_assert!(wrap.distance(n, b).abs_diff(distance) <= 1);
+        }
+    }
+}
+
+#[test]
+fn east_west_seam() {
+    let wrap = HexWrap::east_west(10);
+    assert_eq!(wrap.wrap(Axial::new(10, 0)), Axial::new(0, 0));
+    assert_eq!(wrap.wrap(Axial::new(-3, 4)), Axial::new(7, 4));
+    assert_eq!(wrap.distance(Axial::new(0, 0), Axial::new(9, 0)), 1);
+    assert_eq!(wrap.distance(Axial::new(0, 0), Axial::new(0, 30)), 30);
+}
+
+#[test]
+#[should_panic(expected = "must be even")]
+fn odd_height_is_rejected() {
+    HexWrap::toroidal(10, 3);
+}
@@ -0,0 +1,116 @@
+//! zone of control tests
+use hex_grid::{
+    path::{find_path, movement_range, movement_range_steps},
+    zoc::ZoneOfControl,
+    Axial,
+};
+use proptest::prelude::*;
+
+fn open(cell: Axial) -> Option<u32> {
+    (cell.length() <= 12).then_some(1)
+}
+
+fn axial() -> impl Strategy<Value = Axial> {
+    (-6i32..6, -6i32..6).prop_map(|(q, r)| Axial::new(q, r))
+}
+
+#[test]
+fn controls_cells_next_to_units() {
+    let zoc = ZoneOfControl::new([Axial::ZERO]);
+    assert!(Axial::ZERO.neighbors().all(|n| zoc.controls(n)));
+    assert!(!zoc.controls(Axial::ZERO));
+    assert!(!zoc.controls(Axial::new(2, 0)));
+}
+
+#[test]
+fn entering_ends_the_move() {
+    let enemy = Axial::new(3, 0);
+    let zoc = ZoneOfControl::new([enemy]);
+    let cost = |_, to: Axial| (to != enemy).then(|| open(to)).flatten();
+    let range = movement_range_steps(Axial::ZERO, 6, zoc.step_cost(Axial::ZERO, cost));
+
+    // the cells next to the enemy are reached, but not those past them
+    assert_eq!(range.get(Axial::new(2, 0)), Some(&2));
+    assert!(range.get(Axial::new(4, 0)).is_none_or(|&c| c > 4));
+
+    // paths go around the zone rather than through it
+    let (path, _) = find_path(
+        Axial::ZERO,
+        Axial::new(6, 0),
+        zoc.step_cost(Axial::ZERO, cost),
+    )
+    .unwrap();
+    assert!(path[1..path.len() - 1].iter().all(|&c| !zoc.controls(c)));
+}
+
+#[test]
+fn moves_may_start_in_a_zone() {
+    let zoc = ZoneOfControl::new([Axial::ZERO]);
+    let start = Axial::new(1, 0);
+    let cost = |_, to: Axial| (to != Axial::ZERO).then(|| open(to)).flatten();
+    let range = movement_range_steps(start, 3, zoc.step_cost(start, cost));
+    assert_eq!(range.get(Axial::new(3, 0)), Some(&2));
+}
+
+#[test]
+fn enter_cost_is_added() {
+    let enemy = Axial::new(2, 0);
+    let zoc = ZoneOfControl::new([enemy])
+        .with_stop(false)
+        .with_enter_cost(3);
+    let mut cost = zoc.step_cost(Axial::ZERO, |_, to| open(to));
+    assert_eq!(cost(Axial::ZERO, Axial::new(1, 0)), Some(4));
+    assert_eq!(cost(Axial::ZERO, Axial::new(-1, 0)), Some(1));
+}
+
+#[test]
+fn leaving_a_unit_gives_it_an_attack() {
+    let enemy = Axial::ZERO;
+    let zoc = ZoneOfControl::new([enemy])
+        .with_stop(false)
+        .with_leave_cost(5);
+
+    // moving around the enemy stays next to it; stepping away doesn't
+    let path = [Axial::new(1, 0), Axial::new(1, -1), Axial::new(2, -1)];
+    assert_eq!(zoc.opportunities(&path), vec![(1, enemy)]);
+
+    let mut cost = zoc.step_cost(path[0], |_, to| open(to));
+    assert_eq!(cost(path[0], path[1]), Some(1));
+    assert_eq!(cost(path[1], path[2]), Some(6));
+}
+
+proptest! {
+    #[test]
+    fn range_matches_paths(
+        enemies in prop::collection::vec(axial(), 0..6),
+        origin in axial(),
+        budget in 0u32..8,
+        stop in any::<bool>(),
+        enter in 0u32..3,
+        leave in 0u32..3,
+    ) {
+        let zoc = ZoneOfControl::new(enemies.iter().copied())
+            .with_stop(stop)
+            .with_enter_cost(enter)
+            .with_leave_cost(leave);
+        let cost = |_, to: Axial| (!enemies.contains(&to)).then(|| open(to)).flatten();
+        let range = movement_range_steps(origin, budget, zoc.step_cost(origin, cost));
+
+        for cell in origin.range(budget) {
+            let path = find_path(origin, cell, zoc.step_cost(origin, cost));
+            let expected = path.as_ref().map(|&(_, c)| c).filter(|&c| c <= budget);
+            prop_assert_eq!(range.get(cell).copied(), expected, "{:?}", cell);
+            // with stop, a path only passes through a zone at its ends
+            if let (true, Some((path, _))) = (stop, path) {
+                prop_assert!(path.iter().skip(1).rev().skip(1).all(|&c| !zoc.controls(c)));
+            }
+        }
+    }
+
+    #[test]
+    fn no_zones_change_nothing(origin in axial(), budget in 0u32..8) {
+        let zoc = ZoneOfControl::new([]);
+        let range = movement_range_steps(origin, budget, zoc.step_cost(origin, |_, to| open(to)));
+        prop_assert_eq!(range, movement_range(origin, budget, open));
+    }
+}